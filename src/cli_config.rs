@@ -0,0 +1,218 @@
+//! Defaults for command line options read from a configuration file.
+//!
+//! The configuration file maps option names (e.g. `target_directory` or `target-directory`) to
+//! values. It can be YAML or, if the file has a `.toml` extension, TOML. Options from the file are
+//! placed ahead of the options given on the command line, so the command line takes precedence.
+//! Options set with an environment variable (e.g. `MTG_TARGET_DIRECTORY`) are skipped, so the
+//! environment also takes precedence over the file.
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{bail, Context, Result};
+
+/// Command line option used to specify the configuration file.
+const CONFIG_OPTION: &str = "--config";
+/// Prefix of environment variables that set command line options.
+const ENV_PREFIX: &str = "MTG_";
+
+/// Value of an option in the configuration file.
+#[derive(Debug, Clone, PartialEq)]
+enum OptionValue {
+    /// Flag that is either set or not.
+    Flag(bool),
+    /// Option that takes a value.
+    Value(String),
+}
+
+/// Add the options from the configuration file given with `--config` to the command line.
+///
+/// # Arguments
+///
+/// * `args` - Command line arguments, including the name of the executable.
+///
+/// # Returns
+///
+/// Command line arguments with the options from the configuration file added ahead of the
+/// given options.
+pub fn args_with_config_file(args: Vec<String>) -> Result<Vec<String>> {
+    let config_file = args
+        .iter()
+        .enumerate()
+        .find_map(|(i, arg)| {
+            if arg == CONFIG_OPTION {
+                args.get(i + 1).cloned()
+            } else {
+                arg.strip_prefix(&format!("{}=", CONFIG_OPTION))
+                    .map(|path| path.to_string())
+            }
+        })
+        .or_else(|| std::env::var(env_var_name("config")).ok());
+
+    match config_file {
+        Some(config_file) => {
+            let contents = std::fs::read_to_string(&config_file)
+                .with_context(|| format!("Could not read config file '{}'", config_file))?;
+            let config_args = config_file_args(Path::new(&config_file), &contents)
+                .with_context(|| format!("Could not parse config file '{}'", config_file))?;
+            let mut all_args = args[..1].to_vec();
+            all_args.extend(config_args);
+            all_args.extend(args[1..].iter().cloned());
+            Ok(all_args)
+        }
+        None => Ok(args),
+    }
+}
+
+/// Convert the contents of a configuration file to command line options.
+///
+/// # Arguments
+///
+/// * `path` - Path of the configuration file, used to determine its format.
+/// * `contents` - Contents of the configuration file.
+///
+/// # Returns
+///
+/// Command line options set by the configuration file.
+fn config_file_args(path: &Path, contents: &str) -> Result<Vec<String>> {
+    let options = if path.extension().and_then(|ext| ext.to_str()) == Some("toml") {
+        parse_toml_options(contents)?
+    } else {
+        parse_yaml_options(contents)?
+    };
+
+    let mut args = vec![];
+    for (name, value) in options {
+        let option = format!("--{}", name.replace('_', "-"));
+        if option == CONFIG_OPTION {
+            bail!("The configuration file cannot set '{}'", name);
+        }
+        if std::env::var_os(env_var_name(&name)).is_some() {
+            continue;
+        }
+        match value {
+            OptionValue::Flag(true) => args.push(option),
+            OptionValue::Flag(false) => (),
+            OptionValue::Value(value) => {
+                args.push(option);
+                args.push(value);
+            }
+        }
+    }
+    Ok(args)
+}
+
+/// Name of the environment variable that sets the given option.
+///
+/// # Arguments
+///
+/// * `name` - Name of option, with either underscores or dashes.
+///
+/// # Returns
+///
+/// Name of environment variable for the option.
+fn env_var_name(name: &str) -> String {
+    format!("{}{}", ENV_PREFIX, name.replace('-', "_").to_uppercase())
+}
+
+/// Parse options from a YAML configuration file.
+fn parse_yaml_options(contents: &str) -> Result<BTreeMap<String, OptionValue>> {
+    let values: BTreeMap<String, serde_yaml::Value> = serde_yaml::from_str(contents)?;
+    values
+        .into_iter()
+        .map(|(name, value)| {
+            let value = match value {
+                serde_yaml::Value::Bool(b) => OptionValue::Flag(b),
+                serde_yaml::Value::String(s) => OptionValue::Value(s),
+                serde_yaml::Value::Number(n) => OptionValue::Value(n.to_string()),
+                _ => bail!(
+                    "Unsupported value for option '{}' in configuration file",
+                    name
+                ),
+            };
+            Ok((name, value))
+        })
+        .collect()
+}
+
+/// Parse options from a TOML configuration file.
+fn parse_toml_options(contents: &str) -> Result<BTreeMap<String, OptionValue>> {
+    let values: BTreeMap<String, toml::Value> = toml::from_str(contents)?;
+    values
+        .into_iter()
+        .map(|(name, value)| {
+            let value = match value {
+                toml::Value::Boolean(b) => OptionValue::Flag(b),
+                toml::Value::String(s) => OptionValue::Value(s),
+                toml::Value::Integer(i) => OptionValue::Value(i.to_string()),
+                toml::Value::Float(f) => OptionValue::Value(f.to_string()),
+                _ => bail!(
+                    "Unsupported value for option '{}' in configuration file",
+                    name
+                ),
+            };
+            Ok((name, value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // config_file_args tests.
+    #[test]
+    fn test_config_file_args_should_convert_yaml_options() {
+        let contents = r#"
+            target_directory: generated
+            resmoke-command: python3 resmoke.py
+            burn_in: true
+            strict: false
+        "#;
+
+        let args = config_file_args(Path::new("config.yml"), contents).unwrap();
+
+        assert_eq!(
+            args,
+            vec![
+                "--burn-in",
+                "--resmoke-command",
+                "python3 resmoke.py",
+                "--target-directory",
+                "generated",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config_file_args_should_convert_toml_options() {
+        let contents = r#"
+            target_directory = "generated"
+            burn_in = true
+        "#;
+
+        let args = config_file_args(Path::new("config.toml"), contents).unwrap();
+
+        assert_eq!(args, vec!["--burn-in", "--target-directory", "generated"]);
+    }
+
+    #[test]
+    fn test_config_file_args_should_not_allow_nested_config() {
+        let contents = "config: other.yml";
+
+        assert!(config_file_args(Path::new("config.yml"), contents).is_err());
+    }
+
+    // env_var_name tests.
+    #[test]
+    fn test_env_var_name_should_use_prefix_and_underscores() {
+        assert_eq!(env_var_name("target-directory"), "MTG_TARGET_DIRECTORY");
+        assert_eq!(env_var_name("burn_in"), "MTG_BURN_IN");
+    }
+
+    // args_with_config_file tests.
+    #[test]
+    fn test_args_with_config_file_should_not_change_args_without_config() {
+        let args = vec!["mongo-task-generator".to_string(), "--burn-in".to_string()];
+
+        assert_eq!(args_with_config_file(args.clone()).unwrap(), args);
+    }
+}
@@ -0,0 +1,369 @@
+//! Compare two generated configurations.
+//!
+//! Generated configurations are compared semantically rather than textually, so that changes in
+//! the order tasks are written in do not show up as differences. This is useful for validating
+//! that a new release of the generator produces the configuration expected from an old one.
+use std::{collections::BTreeMap, fmt::Write, path::Path};
+
+use anyhow::{Context, Result};
+use shrub_rs::models::project::EvgProject;
+
+/// Name of the generated configuration file in a target directory.
+const GENERATED_CONFIG_FILE: &str = "evergreen_config.json";
+
+/// A value that differs between two generated configurations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueChange<T> {
+    /// What changed, e.g. the build variant and task.
+    pub item: String,
+    /// Value in the old configuration.
+    pub old: T,
+    /// Value in the new configuration.
+    pub new: T,
+}
+
+/// Differences between two generated configurations.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigDiff {
+    /// Tasks defined only in the new configuration.
+    pub added_tasks: Vec<String>,
+    /// Tasks defined only in the old configuration.
+    pub removed_tasks: Vec<String>,
+    /// Display tasks whose number of sub-tasks changed.
+    pub sub_task_count_changes: Vec<ValueChange<usize>>,
+    /// Task references whose distros changed.
+    pub distro_changes: Vec<ValueChange<Vec<String>>>,
+    /// Tasks whose dependencies changed.
+    pub dependency_changes: Vec<ValueChange<Vec<String>>>,
+}
+
+impl ConfigDiff {
+    /// Compare two generated configurations.
+    ///
+    /// # Arguments
+    ///
+    /// * `old` - Old generated configuration.
+    /// * `new` - New generated configuration.
+    ///
+    /// # Returns
+    ///
+    /// Differences between the configurations.
+    pub fn new(old: &EvgProject, new: &EvgProject) -> Self {
+        let old_dependencies = task_dependencies(old);
+        let new_dependencies = task_dependencies(new);
+
+        Self {
+            added_tasks: new_dependencies
+                .keys()
+                .filter(|task| !old_dependencies.contains_key(*task))
+                .cloned()
+                .collect(),
+            removed_tasks: old_dependencies
+                .keys()
+                .filter(|task| !new_dependencies.contains_key(*task))
+                .cloned()
+                .collect(),
+            sub_task_count_changes: changed_values(
+                &display_task_sizes(old),
+                &display_task_sizes(new),
+            ),
+            distro_changes: changed_values(&task_ref_distros(old), &task_ref_distros(new)),
+            dependency_changes: changed_values(&old_dependencies, &new_dependencies),
+        }
+    }
+
+    /// Check if the configurations had no differences.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Create a human-readable report of the differences.
+    pub fn format_report(&self) -> String {
+        if self.is_empty() {
+            return "No differences found.\n".to_string();
+        }
+
+        let mut report = String::new();
+        write_section(&mut report, "Added tasks", &self.added_tasks);
+        write_section(&mut report, "Removed tasks", &self.removed_tasks);
+        write_section(
+            &mut report,
+            "Sub-task count changes",
+            &self
+                .sub_task_count_changes
+                .iter()
+                .map(|c| format!("{}: {} -> {}", c.item, c.old, c.new))
+                .collect::<Vec<String>>(),
+        );
+        write_section(
+            &mut report,
+            "Distro changes",
+            &self
+                .distro_changes
+                .iter()
+                .map(|c| {
+                    format!(
+                        "{}: [{}] -> [{}]",
+                        c.item,
+                        c.old.join(", "),
+                        c.new.join(", ")
+                    )
+                })
+                .collect::<Vec<String>>(),
+        );
+        write_section(
+            &mut report,
+            "Dependency changes",
+            &self
+                .dependency_changes
+                .iter()
+                .map(|c| {
+                    format!(
+                        "{}: [{}] -> [{}]",
+                        c.item,
+                        c.old.join(", "),
+                        c.new.join(", ")
+                    )
+                })
+                .collect::<Vec<String>>(),
+        );
+        report
+    }
+}
+
+/// Compare the generated configurations in two target directories.
+///
+/// # Arguments
+///
+/// * `old_dir` - Directory containing the old generated configuration.
+/// * `new_dir` - Directory containing the new generated configuration.
+///
+/// # Returns
+///
+/// Differences between the configurations.
+pub fn diff_generated_configs(old_dir: &Path, new_dir: &Path) -> Result<ConfigDiff> {
+    let old = read_generated_config(old_dir)?;
+    let new = read_generated_config(new_dir)?;
+    Ok(ConfigDiff::new(&old, &new))
+}
+
+/// Read the generated configuration in the given target directory.
+pub(crate) fn read_generated_config(target_directory: &Path) -> Result<EvgProject> {
+    let config_file = target_directory.join(GENERATED_CONFIG_FILE);
+    let contents = std::fs::read_to_string(&config_file)
+        .with_context(|| format!("Unable to read '{}'", config_file.display()))?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("Unable to parse '{}'", config_file.display()))
+}
+
+/// Get the dependencies of every task defined in the configuration.
+fn task_dependencies(project: &EvgProject) -> BTreeMap<String, Vec<String>> {
+    project
+        .tasks
+        .iter()
+        .map(|task| {
+            let mut dependencies: Vec<String> = task
+                .depends_on
+                .iter()
+                .flatten()
+                .map(|dependency| match &dependency.variant {
+                    Some(variant) => format!("{} ({})", dependency.name, variant),
+                    None => dependency.name.clone(),
+                })
+                .collect();
+            dependencies.sort();
+            (task.name.clone(), dependencies)
+        })
+        .collect()
+}
+
+/// Get the number of sub-tasks in each display task of each build variant.
+fn display_task_sizes(project: &EvgProject) -> BTreeMap<String, usize> {
+    project
+        .buildvariants
+        .iter()
+        .flat_map(|build_variant| {
+            build_variant
+                .display_tasks
+                .iter()
+                .flatten()
+                .map(move |display_task| {
+                    (
+                        format!("{} on {}", display_task.name, build_variant.name),
+                        display_task.execution_tasks.len(),
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Get the distros of each task reference of each build variant.
+fn task_ref_distros(project: &EvgProject) -> BTreeMap<String, Vec<String>> {
+    project
+        .buildvariants
+        .iter()
+        .flat_map(|build_variant| {
+            build_variant.tasks.iter().map(move |task_ref| {
+                (
+                    format!("{} on {}", task_ref.name, build_variant.name),
+                    task_ref.distros.clone().unwrap_or_default(),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Find the items present in both maps whose values differ.
+fn changed_values<T: Clone + PartialEq>(
+    old: &BTreeMap<String, T>,
+    new: &BTreeMap<String, T>,
+) -> Vec<ValueChange<T>> {
+    old.iter()
+        .filter_map(|(item, old_value)| {
+            new.get(item)
+                .filter(|new_value| *new_value != old_value)
+                .map(|new_value| ValueChange {
+                    item: item.clone(),
+                    old: old_value.clone(),
+                    new: new_value.clone(),
+                })
+        })
+        .collect()
+}
+
+/// Add a section listing the given entries to a report, if there are any.
+fn write_section(report: &mut String, title: &str, entries: &[String]) {
+    if entries.is_empty() {
+        return;
+    }
+    writeln!(report, "{} ({}):", title, entries.len()).unwrap();
+    for entry in entries {
+        writeln!(report, "  {}", entry).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shrub_rs::models::{
+        task::{EvgTask, TaskDependency},
+        variant::{BuildVariant, DisplayTask},
+    };
+
+    use super::*;
+
+    fn build_project(
+        tasks: Vec<(&str, Vec<&str>)>,
+        distros: Option<Vec<String>>,
+        display_tasks: Vec<(&str, Vec<&str>)>,
+    ) -> EvgProject {
+        EvgProject {
+            buildvariants: vec![BuildVariant {
+                name: "bv".to_string(),
+                tasks: tasks
+                    .iter()
+                    .map(|(name, _)| {
+                        EvgTask {
+                            name: name.to_string(),
+                            ..Default::default()
+                        }
+                        .get_reference(distros.clone(), Some(false))
+                    })
+                    .collect(),
+                display_tasks: Some(
+                    display_tasks
+                        .into_iter()
+                        .map(|(name, execution_tasks)| DisplayTask {
+                            name: name.to_string(),
+                            execution_tasks: execution_tasks
+                                .into_iter()
+                                .map(|t| t.to_string())
+                                .collect(),
+                        })
+                        .collect(),
+                ),
+                ..Default::default()
+            }],
+            tasks: tasks
+                .into_iter()
+                .map(|(name, depends_on)| EvgTask {
+                    name: name.to_string(),
+                    depends_on: Some(
+                        depends_on
+                            .into_iter()
+                            .map(|d| TaskDependency {
+                                name: d.to_string(),
+                                variant: None,
+                            })
+                            .collect(),
+                    ),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_identical_configs_should_have_no_differences() {
+        let project = build_project(
+            vec![("task_0", vec!["compile"])],
+            None,
+            vec![("task", vec!["task_0"])],
+        );
+
+        let diff = ConfigDiff::new(&project, &project);
+
+        assert!(diff.is_empty());
+        assert_eq!(diff.format_report(), "No differences found.\n");
+    }
+
+    #[test]
+    fn test_config_diff_should_report_every_kind_of_change() {
+        let old = build_project(
+            vec![("task_0", vec!["compile"]), ("task_1", vec!["compile"])],
+            None,
+            vec![("task", vec!["task_0", "task_1"])],
+        );
+        let new = build_project(
+            vec![("task_0", vec!["archive"]), ("task_2", vec!["compile"])],
+            Some(vec!["large".to_string()]),
+            vec![("task", vec!["task_0", "task_2", "task_3"])],
+        );
+
+        let diff = ConfigDiff::new(&old, &new);
+
+        assert_eq!(diff.added_tasks, vec!["task_2"]);
+        assert_eq!(diff.removed_tasks, vec!["task_1"]);
+        assert_eq!(
+            diff.sub_task_count_changes,
+            vec![ValueChange {
+                item: "task on bv".to_string(),
+                old: 2,
+                new: 3,
+            }]
+        );
+        assert_eq!(
+            diff.distro_changes,
+            vec![ValueChange {
+                item: "task_0 on bv".to_string(),
+                old: vec![],
+                new: vec!["large".to_string()],
+            }]
+        );
+        assert_eq!(
+            diff.dependency_changes,
+            vec![ValueChange {
+                item: "task_0".to_string(),
+                old: vec!["compile".to_string()],
+                new: vec!["archive".to_string()],
+            }]
+        );
+        assert_eq!(
+            diff.format_report(),
+            "Added tasks (1):\n  task_2\nRemoved tasks (1):\n  task_1\n\
+             Sub-task count changes (1):\n  task on bv: 2 -> 3\n\
+             Distro changes (1):\n  task_0 on bv: [] -> [large]\n\
+             Dependency changes (1):\n  task_0: [compile] -> [archive]\n"
+        );
+    }
+}
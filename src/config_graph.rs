@@ -0,0 +1,685 @@
+//! Graph of the build variants, tasks and dependencies of a generated configuration.
+//!
+//! The graph can be rendered for graphviz or mermaid to see which build variant runs each
+//! sub-task and what each sub-task waits on, e.g. to find why a sub-task never gets scheduled.
+//! It is also used to check the dependencies of a generated configuration before it is written,
+//! since evergreen rejects the whole generate.tasks call if any of them are invalid.
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt::Write,
+    path::Path,
+};
+
+use anyhow::Result;
+use shrub_rs::models::{
+    project::EvgProject,
+    task::{EvgTask, TaskDependency},
+};
+
+use crate::config_diff::read_generated_config;
+
+/// A task on a build variant, as a pair of the build variant name and the task name.
+pub type TaskNode = (String, String);
+
+/// Wildcard evergreen accepts in place of a task or build variant name in a dependency.
+const DEPENDENCY_WILDCARD: &str = "*";
+
+/// Tasks of a build variant in the graph.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VariantTasks {
+    /// Display tasks of the build variant, mapped to the tasks they contain.
+    pub display_tasks: BTreeMap<String, Vec<String>>,
+    /// Tasks of the build variant not in any display task.
+    pub tasks: Vec<String>,
+}
+
+/// Graph of a generated configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigGraph {
+    /// Tasks of each build variant, keyed by build variant name.
+    pub build_variants: BTreeMap<String, VariantTasks>,
+    /// Tasks each task on a build variant depends on.
+    pub dependencies: BTreeMap<TaskNode, BTreeSet<TaskNode>>,
+}
+
+impl ConfigGraph {
+    /// Build the graph of the given configuration.
+    ///
+    /// A task runs on a build variant if the build variant references it. Its dependencies are
+    /// those of its task definition and of the build variant. Dependencies without a build
+    /// variant refer to the build variant of the dependent task.
+    ///
+    /// # Arguments
+    ///
+    /// * `project` - Configuration to build the graph of.
+    ///
+    /// # Returns
+    ///
+    /// Graph of the configuration.
+    pub fn new(project: &EvgProject) -> Self {
+        let task_map: BTreeMap<&str, &EvgTask> = project
+            .tasks
+            .iter()
+            .map(|task| (task.name.as_str(), task))
+            .collect();
+
+        let mut graph = Self::default();
+        for build_variant in &project.buildvariants {
+            let mut variant_tasks = VariantTasks::default();
+            let mut displayed_tasks = BTreeSet::new();
+            for display_task in build_variant.display_tasks.iter().flatten() {
+                displayed_tasks.extend(display_task.execution_tasks.iter().map(|t| t.as_str()));
+                variant_tasks
+                    .display_tasks
+                    .entry(display_task.name.clone())
+                    .or_default()
+                    .extend(display_task.execution_tasks.iter().cloned());
+            }
+
+            for task_ref in &build_variant.tasks {
+                if !displayed_tasks.contains(task_ref.name.as_str()) {
+                    variant_tasks.tasks.push(task_ref.name.clone());
+                }
+
+                let node = (build_variant.name.clone(), task_ref.name.clone());
+                let to_node = |dependency: &TaskDependency| {
+                    (
+                        dependency
+                            .variant
+                            .clone()
+                            .unwrap_or_else(|| build_variant.name.clone()),
+                        dependency.name.clone(),
+                    )
+                };
+                let task_dependencies = task_map
+                    .get(task_ref.name.as_str())
+                    .and_then(|task| task.depends_on.as_ref());
+                // Dependencies of the build variant do not apply to the tasks they refer to.
+                let dependencies: BTreeSet<TaskNode> = task_dependencies
+                    .into_iter()
+                    .flatten()
+                    .map(to_node)
+                    .chain(
+                        build_variant
+                            .depends_on
+                            .iter()
+                            .flatten()
+                            .map(to_node)
+                            .filter(|dependency| *dependency != node),
+                    )
+                    .collect();
+                graph
+                    .dependencies
+                    .entry(node)
+                    .or_default()
+                    .extend(dependencies);
+            }
+
+            let existing = graph
+                .build_variants
+                .entry(build_variant.name.clone())
+                .or_default();
+            existing.tasks.extend(variant_tasks.tasks);
+            for (display_task, tasks) in variant_tasks.display_tasks {
+                existing
+                    .display_tasks
+                    .entry(display_task)
+                    .or_default()
+                    .extend(tasks);
+            }
+        }
+
+        graph
+    }
+
+    /// Dependencies on tasks that are not in the graph, e.g. compile tasks of the project.
+    pub fn external_tasks(&self) -> BTreeSet<&TaskNode> {
+        self.dependencies
+            .values()
+            .flatten()
+            .filter(|node| !self.dependencies.contains_key(*node))
+            .collect()
+    }
+
+    /// Find dependencies that refer to tasks or build variants that do not exist.
+    ///
+    /// A dependency is valid if the task it refers to runs on its build variant in the graph. A
+    /// dependency on a task of the source project is also valid if both the task and the build
+    /// variant are defined there, since the build variant may run the task as part of a task
+    /// group. Wildcard dependencies are not checked.
+    ///
+    /// Dependencies of build variants cloned from a source build variant, like those of
+    /// burn_in_tags, on tasks of their own build variant that they do not run are checked against
+    /// the build variant they were cloned from instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `source_build_variants` - Names of build variants defined in the source project.
+    /// * `source_tasks` - Names of tasks defined in the source project.
+    /// * `clone_bases` - Build variant each cloned build variant was cloned from.
+    /// * `external` - Check dependencies on tasks that are not in the graph, e.g. compile tasks of
+    ///   the project, instead of dependencies on tasks in the graph.
+    ///
+    /// # Returns
+    ///
+    /// Description of each invalid dependency.
+    pub fn missing_dependencies(
+        &self,
+        source_build_variants: &HashSet<String>,
+        source_tasks: &HashSet<String>,
+        clone_bases: &HashMap<String, String>,
+        external: bool,
+    ) -> Vec<String> {
+        let graph_tasks: BTreeSet<&str> = self
+            .dependencies
+            .keys()
+            .map(|(_, task)| task.as_str())
+            .collect();
+
+        let mut missing = vec![];
+        for (node, dependencies) in &self.dependencies {
+            let clone_base = clone_bases.get(&node.0);
+            for dependency in dependencies {
+                let resolved;
+                let dependency = match clone_base {
+                    Some(base)
+                        if dependency.0 == node.0
+                            && !self.dependencies.contains_key(dependency) =>
+                    {
+                        resolved = (base.to_string(), dependency.1.clone());
+                        &resolved
+                    }
+                    _ => dependency,
+                };
+                let (variant, task) = dependency;
+                if variant == DEPENDENCY_WILDCARD
+                    || task == DEPENDENCY_WILDCARD
+                    || self.dependencies.contains_key(dependency)
+                    || graph_tasks.contains(task.as_str()) == external
+                {
+                    continue;
+                }
+
+                let in_source_variant = source_build_variants.contains(variant);
+                let in_source_tasks = source_tasks.contains(task);
+                let reason = if !in_source_variant && !self.build_variants.contains_key(variant) {
+                    format!("build variant '{}' does not exist", variant)
+                } else if !in_source_tasks && !graph_tasks.contains(task.as_str()) {
+                    format!("task '{}' does not exist", task)
+                } else if !in_source_variant || !in_source_tasks {
+                    format!(
+                        "task '{}' does not run on build variant '{}'",
+                        task, variant
+                    )
+                } else {
+                    continue;
+                };
+                missing.push(format!(
+                    "{} depends on {}, but {}",
+                    describe_node(node),
+                    describe_node(dependency),
+                    reason
+                ));
+            }
+        }
+
+        missing
+    }
+
+    /// Find cycles in the dependencies of the graph.
+    ///
+    /// # Returns
+    ///
+    /// Tasks of each cycle in dependency order, starting and ending with the same task.
+    pub fn find_cycles(&self) -> Vec<Vec<&TaskNode>> {
+        let mut finished = BTreeSet::new();
+        let mut cycles = vec![];
+        for node in self.dependencies.keys() {
+            self.visit_dependencies(node, &mut vec![], &mut finished, &mut cycles);
+        }
+
+        cycles
+    }
+
+    /// Walk the dependencies of a task depth first, recording any cycles found.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - Task to walk the dependencies of.
+    /// * `path` - Tasks leading to the task being walked.
+    /// * `finished` - Tasks whose dependencies have already been walked.
+    /// * `cycles` - Cycles found so far.
+    fn visit_dependencies<'a>(
+        &'a self,
+        node: &'a TaskNode,
+        path: &mut Vec<&'a TaskNode>,
+        finished: &mut BTreeSet<&'a TaskNode>,
+        cycles: &mut Vec<Vec<&'a TaskNode>>,
+    ) {
+        if let Some(start) = path.iter().position(|visiting| *visiting == node) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(node);
+            cycles.push(cycle);
+            return;
+        }
+        if finished.contains(node) {
+            return;
+        }
+
+        path.push(node);
+        for dependency in self.dependencies.get(node).into_iter().flatten() {
+            self.visit_dependencies(dependency, path, finished, cycles);
+        }
+        path.pop();
+        finished.insert(node);
+    }
+
+    /// Check that the dependencies of the graph are valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `source_build_variants` - Names of build variants defined in the source project.
+    /// * `source_tasks` - Names of tasks defined in the source project.
+    /// * `clone_bases` - Build variant each cloned build variant was cloned from.
+    /// * `check_external` - Also check dependencies on tasks that are not in the graph.
+    ///
+    /// # Returns
+    ///
+    /// Description of each invalid dependency and each dependency cycle.
+    pub fn dependency_problems(
+        &self,
+        source_build_variants: &HashSet<String>,
+        source_tasks: &HashSet<String>,
+        clone_bases: &HashMap<String, String>,
+        check_external: bool,
+    ) -> Vec<String> {
+        let mut problems =
+            self.missing_dependencies(source_build_variants, source_tasks, clone_bases, false);
+        if check_external {
+            problems.extend(self.missing_dependencies(
+                source_build_variants,
+                source_tasks,
+                clone_bases,
+                true,
+            ));
+        }
+        problems.extend(self.find_cycles().into_iter().map(|cycle| {
+            let tasks: Vec<String> = cycle.into_iter().map(describe_node).collect();
+            format!("Dependency cycle: {}", tasks.join(" -> "))
+        }));
+
+        problems
+    }
+
+    /// Render the graph in the graphviz DOT language.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph generated_config {\n  rankdir=LR;\n");
+        for (i, (variant, variant_tasks)) in self.build_variants.iter().enumerate() {
+            writeln!(dot, "  subgraph cluster_{} {{", i).unwrap();
+            writeln!(dot, "    label={};", dot_quote(variant)).unwrap();
+            for (j, (display_task, tasks)) in variant_tasks.display_tasks.iter().enumerate() {
+                writeln!(dot, "    subgraph cluster_{}_{} {{", i, j).unwrap();
+                writeln!(dot, "      label={};", dot_quote(display_task)).unwrap();
+                for task in tasks {
+                    writeln!(
+                        dot,
+                        "      {} [label={}];",
+                        dot_node(variant, task),
+                        dot_quote(task)
+                    )
+                    .unwrap();
+                }
+                dot.push_str("    }\n");
+            }
+            for task in &variant_tasks.tasks {
+                writeln!(
+                    dot,
+                    "    {} [label={}];",
+                    dot_node(variant, task),
+                    dot_quote(task)
+                )
+                .unwrap();
+            }
+            dot.push_str("  }\n");
+        }
+
+        for (variant, task) in self.external_tasks() {
+            writeln!(
+                dot,
+                "  {} [label={}, style=dashed];",
+                dot_node(variant, task),
+                dot_quote(&format!("{} ({})", task, variant))
+            )
+            .unwrap();
+        }
+        for ((variant, task), dependencies) in &self.dependencies {
+            for (dep_variant, dep_task) in dependencies {
+                writeln!(
+                    dot,
+                    "  {} -> {};",
+                    dot_node(variant, task),
+                    dot_node(dep_variant, dep_task)
+                )
+                .unwrap();
+            }
+        }
+        dot.push_str("}\n");
+
+        dot
+    }
+
+    /// Render the graph as a mermaid flowchart.
+    pub fn to_mermaid(&self) -> String {
+        // Mermaid node ids cannot contain most characters of task names, so number the nodes.
+        let mut ids: BTreeMap<&TaskNode, String> = BTreeMap::new();
+        for node in self.dependencies.keys().chain(self.external_tasks()) {
+            let next_id = format!("n{}", ids.len());
+            ids.entry(node).or_insert(next_id);
+        }
+
+        let mut mermaid = String::from("flowchart LR\n");
+        for (i, (variant, variant_tasks)) in self.build_variants.iter().enumerate() {
+            writeln!(mermaid, "  subgraph v{}[{}]", i, mermaid_quote(variant)).unwrap();
+            for (j, (display_task, tasks)) in variant_tasks.display_tasks.iter().enumerate() {
+                writeln!(
+                    mermaid,
+                    "    subgraph v{}_d{}[{}]",
+                    i,
+                    j,
+                    mermaid_quote(display_task)
+                )
+                .unwrap();
+                for task in tasks {
+                    if let Some(id) = ids.get(&(variant.clone(), task.clone())) {
+                        writeln!(mermaid, "      {}[{}]", id, mermaid_quote(task)).unwrap();
+                    }
+                }
+                mermaid.push_str("    end\n");
+            }
+            for task in &variant_tasks.tasks {
+                if let Some(id) = ids.get(&(variant.clone(), task.clone())) {
+                    writeln!(mermaid, "    {}[{}]", id, mermaid_quote(task)).unwrap();
+                }
+            }
+            mermaid.push_str("  end\n");
+        }
+
+        for node in self.external_tasks() {
+            let (variant, task) = node;
+            writeln!(
+                mermaid,
+                "  {}[{}]:::external",
+                ids[node],
+                mermaid_quote(&format!("{} ({})", task, variant))
+            )
+            .unwrap();
+        }
+        for (node, dependencies) in &self.dependencies {
+            for dependency in dependencies {
+                writeln!(mermaid, "  {} --> {}", ids[node], ids[dependency]).unwrap();
+            }
+        }
+        mermaid.push_str("  classDef external stroke-dasharray: 5 5\n");
+
+        mermaid
+    }
+}
+
+/// Quote a string for use as a DOT identifier or label.
+fn dot_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Identifier of a task on a build variant in the DOT language.
+fn dot_node(variant: &str, task: &str) -> String {
+    dot_quote(&format!("{}/{}", variant, task))
+}
+
+/// Describe a task on a build variant for reports.
+fn describe_node((variant, task): &TaskNode) -> String {
+    format!("'{}' on '{}'", task, variant)
+}
+
+/// Quote a string for use as a mermaid label.
+fn mermaid_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "#quot;"))
+}
+
+/// Build the graph of the generated configuration in a target directory.
+///
+/// # Arguments
+///
+/// * `target_directory` - Directory containing the generated configuration.
+///
+/// # Returns
+///
+/// Graph of the generated configuration.
+pub fn graph_generated_config(target_directory: &Path) -> Result<ConfigGraph> {
+    Ok(ConfigGraph::new(&read_generated_config(target_directory)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use shrub_rs::models::variant::{BuildVariant, DisplayTask};
+
+    use super::*;
+
+    fn build_task(name: &str, depends_on: Vec<(&str, Option<&str>)>) -> EvgTask {
+        EvgTask {
+            name: name.to_string(),
+            depends_on: Some(
+                depends_on
+                    .into_iter()
+                    .map(|(name, variant)| TaskDependency {
+                        name: name.to_string(),
+                        variant: variant.map(|v| v.to_string()),
+                    })
+                    .collect(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    fn build_project() -> EvgProject {
+        let tasks = vec![
+            build_task("auth_0", vec![("compile", Some("bv_compile"))]),
+            build_task("auth_1", vec![("compile", Some("bv_compile"))]),
+            build_task("select_binaries", vec![]),
+        ];
+        EvgProject {
+            buildvariants: vec![BuildVariant {
+                name: "bv_0".to_string(),
+                tasks: tasks
+                    .iter()
+                    .map(|task| task.get_reference(None, Some(false)))
+                    .collect(),
+                display_tasks: Some(vec![DisplayTask {
+                    name: "auth".to_string(),
+                    execution_tasks: vec!["auth_0".to_string(), "auth_1".to_string()],
+                }]),
+                depends_on: Some(vec![TaskDependency {
+                    name: "select_binaries".to_string(),
+                    variant: None,
+                }]),
+                ..Default::default()
+            }],
+            tasks,
+            ..Default::default()
+        }
+    }
+
+    fn node(variant: &str, task: &str) -> TaskNode {
+        (variant.to_string(), task.to_string())
+    }
+
+    #[test]
+    fn test_new_should_resolve_dependencies_per_build_variant() {
+        let graph = ConfigGraph::new(&build_project());
+
+        assert_eq!(
+            graph.build_variants["bv_0"],
+            VariantTasks {
+                display_tasks: BTreeMap::from([(
+                    "auth".to_string(),
+                    vec!["auth_0".to_string(), "auth_1".to_string()]
+                )]),
+                tasks: vec!["select_binaries".to_string()],
+            }
+        );
+        assert_eq!(
+            graph.dependencies[&node("bv_0", "auth_0")],
+            BTreeSet::from([
+                node("bv_0", "select_binaries"),
+                node("bv_compile", "compile")
+            ])
+        );
+        assert_eq!(
+            graph.external_tasks(),
+            BTreeSet::from([&node("bv_compile", "compile")])
+        );
+    }
+
+    #[test]
+    fn test_to_dot_should_draw_clusters_and_edges() {
+        let dot = ConfigGraph::new(&build_project()).to_dot();
+
+        assert!(dot.starts_with("digraph generated_config {\n"));
+        assert!(dot.contains("  subgraph cluster_0 {\n    label=\"bv_0\";\n"));
+        assert!(dot.contains("      \"bv_0/auth_0\" [label=\"auth_0\"];\n"));
+        assert!(dot.contains(
+            "  \"bv_compile/compile\" [label=\"compile (bv_compile)\", style=dashed];\n"
+        ));
+        assert!(dot.contains("  \"bv_0/auth_1\" -> \"bv_compile/compile\";\n"));
+    }
+
+    #[test]
+    fn test_to_mermaid_should_draw_subgraphs_and_edges() {
+        let mermaid = ConfigGraph::new(&build_project()).to_mermaid();
+
+        assert!(mermaid.starts_with("flowchart LR\n  subgraph v0[\"bv_0\"]\n"));
+        assert!(mermaid.contains("    subgraph v0_d0[\"auth\"]\n      n0[\"auth_0\"]\n"));
+        assert!(mermaid.contains("  n3[\"compile (bv_compile)\"]:::external\n"));
+        assert!(mermaid.contains("  n0 --> n3\n"));
+    }
+
+    #[test]
+    fn test_missing_dependencies_should_report_unknown_tasks_and_build_variants() {
+        let mut project = build_project();
+        project.tasks[0].depends_on.as_mut().unwrap().extend(vec![
+            TaskDependency {
+                name: "compile".to_string(),
+                variant: Some("bv_missing".to_string()),
+            },
+            TaskDependency {
+                name: "lint".to_string(),
+                variant: None,
+            },
+            TaskDependency {
+                name: "auth_1".to_string(),
+                variant: Some("bv_compile".to_string()),
+            },
+            TaskDependency {
+                name: "*".to_string(),
+                variant: Some("bv_missing".to_string()),
+            },
+        ]);
+        let graph = ConfigGraph::new(&project);
+        let source_build_variants = HashSet::from(["bv_0".to_string(), "bv_compile".to_string()]);
+        let source_tasks = HashSet::from(["compile".to_string()]);
+
+        let missing = graph.missing_dependencies(
+            &source_build_variants,
+            &source_tasks,
+            &HashMap::new(),
+            false,
+        );
+        let missing_external = graph.missing_dependencies(
+            &source_build_variants,
+            &source_tasks,
+            &HashMap::new(),
+            true,
+        );
+
+        assert_eq!(
+            missing,
+            vec![
+                "'auth_0' on 'bv_0' depends on 'auth_1' on 'bv_compile', but task 'auth_1' does not run on build variant 'bv_compile'",
+            ]
+        );
+        assert_eq!(
+            missing_external,
+            vec![
+                "'auth_0' on 'bv_0' depends on 'lint' on 'bv_0', but task 'lint' does not exist",
+                "'auth_0' on 'bv_0' depends on 'compile' on 'bv_missing', but build variant 'bv_missing' does not exist",
+            ]
+        );
+        for external in [false, true] {
+            assert!(ConfigGraph::new(&build_project())
+                .missing_dependencies(
+                    &source_build_variants,
+                    &source_tasks,
+                    &HashMap::new(),
+                    external
+                )
+                .is_empty());
+        }
+    }
+
+    #[test]
+    fn test_missing_dependencies_should_resolve_cloned_build_variants_against_their_base() {
+        let mut project = build_project();
+        for name in ["burn_in_clone", "bv_0-not-a-clone"] {
+            let mut build_variant = project.buildvariants[0].clone();
+            build_variant.name = name.to_string();
+            build_variant.depends_on = None;
+            project.buildvariants.push(build_variant);
+        }
+        project.tasks[0].depends_on.as_mut().unwrap().extend(vec![
+            TaskDependency {
+                name: "archive_dist_test".to_string(),
+                variant: None,
+            },
+            TaskDependency {
+                name: "lint".to_string(),
+                variant: None,
+            },
+        ]);
+        let graph = ConfigGraph::new(&project);
+        let source_build_variants = HashSet::from(["bv_0".to_string(), "bv_compile".to_string()]);
+        let source_tasks = HashSet::from(["compile".to_string(), "archive_dist_test".to_string()]);
+        let clone_bases = HashMap::from([("burn_in_clone".to_string(), "bv_0".to_string())]);
+
+        let missing =
+            graph.missing_dependencies(&source_build_variants, &source_tasks, &clone_bases, true);
+
+        assert_eq!(
+            missing,
+            vec![
+                "'auth_0' on 'burn_in_clone' depends on 'lint' on 'bv_0', but task 'lint' does not exist",
+                "'auth_0' on 'bv_0' depends on 'lint' on 'bv_0', but task 'lint' does not exist",
+                "'auth_0' on 'bv_0-not-a-clone' depends on 'archive_dist_test' on 'bv_0-not-a-clone', but task 'archive_dist_test' does not run on build variant 'bv_0-not-a-clone'",
+                "'auth_0' on 'bv_0-not-a-clone' depends on 'lint' on 'bv_0-not-a-clone', but task 'lint' does not exist",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_cycles_should_report_each_cycle() {
+        let mut project = build_project();
+        project.tasks[2].depends_on = Some(vec![TaskDependency {
+            name: "auth_1".to_string(),
+            variant: None,
+        }]);
+        let graph = ConfigGraph::new(&project);
+
+        assert_eq!(
+            graph.find_cycles(),
+            vec![vec![
+                &node("bv_0", "select_binaries"),
+                &node("bv_0", "auth_1"),
+                &node("bv_0", "select_binaries")
+            ]]
+        );
+        assert!(ConfigGraph::new(&build_project()).find_cycles().is_empty());
+    }
+}
@@ -0,0 +1,448 @@
+//! JSON Schemas of the configuration files read by the generator.
+//!
+//! The configuration files are parsed leniently, so a misspelled key is silently ignored and its
+//! setting falls back to the default. The schemas describe every key the generator reads, so
+//! keys they do not describe can be reported as likely typos. They can also be written out for
+//! editors to validate the files as they are written.
+use std::path::Path;
+
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    evergreen_names_config::EvergreenNamesConfig, generate_sub_tasks_config::GenerateSubTasksConfig,
+};
+
+/// Name of file to write the schema of the sub-tasks configuration to.
+pub const GENERATE_SUB_TASKS_CONFIG_SCHEMA_FILE: &str = "generate_sub_tasks_config.schema.json";
+/// Name of file to write the schema of the evergreen names configuration to.
+pub const EVERGREEN_NAMES_CONFIG_SCHEMA_FILE: &str = "evergreen_names_config.schema.json";
+/// Name of file to write the schema of the expansions file to.
+pub const EXPANSIONS_SCHEMA_FILE: &str = "expansions.schema.json";
+/// Version of JSON Schema the schemas are written in.
+const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Schema of a list of strings.
+fn string_list() -> Value {
+    json!({"type": "array", "items": {"type": "string"}})
+}
+
+/// Schema of a map of strings to strings.
+fn string_map() -> Value {
+    json!({"type": "object", "additionalProperties": {"type": "string"}})
+}
+
+/// Schema of a list of the given items.
+fn list_of(items: Value) -> Value {
+    json!({"type": "array", "items": items})
+}
+
+/// Schema of an object that only allows the given properties.
+///
+/// # Arguments
+///
+/// * `required` - Names of properties that must be set.
+/// * `properties` - Names and schemas of all properties.
+fn strict_object(required: &[&str], properties: &[(&str, Value)]) -> Value {
+    let properties: Map<String, Value> = properties
+        .iter()
+        .map(|(name, schema)| (name.to_string(), schema.clone()))
+        .collect();
+    json!({
+        "type": "object",
+        "required": required,
+        "additionalProperties": false,
+        "properties": properties,
+    })
+}
+
+/// Schema of the sub-tasks configuration file given with `--generate-sub-tasks-config`.
+pub fn generate_sub_tasks_config_schema() -> Value {
+    let string = json!({"type": "string"});
+    let mut schema = strict_object(
+        &["build_variant_large_distro_exceptions"],
+        &[
+            ("build_variant_large_distro_exceptions", string_list()),
+            ("multiversion_old_versions", string_list()),
+            (
+                "generator_display_task_groups",
+                list_of(strict_object(
+                    &["name"],
+                    &[
+                        ("name", string.clone()),
+                        ("tags", string_list()),
+                        ("task_name_patterns", string_list()),
+                    ],
+                )),
+            ),
+            ("generator_functions", string_list()),
+            (
+                "enterprise_detection",
+                json!({"enum": ["expansions", "modules"]}),
+            ),
+            (
+                "build_variant_distro_tiers",
+                list_of(strict_object(
+                    &["build_variants", "tiers"],
+                    &[("build_variants", string_list()), ("tiers", string_map())],
+                )),
+            ),
+            ("build_variant_clones", list_of(variant_clone_schema())),
+            (
+                "feature_flag_variants",
+                list_of(strict_object(
+                    &["build_variants", "feature_flags", "tasks"],
+                    &[
+                        ("build_variants", string_list()),
+                        (
+                            "feature_flags",
+                            list_of(json!({"oneOf": [string, string_list()]})),
+                        ),
+                        ("tasks", string_list()),
+                    ],
+                )),
+            ),
+            ("empty_suite_fallback", json!({"type": "boolean"})),
+            (
+                "distro_capabilities",
+                list_of(strict_object(
+                    &["distros", "cpu_count"],
+                    &[
+                        ("distros", string_list()),
+                        ("cpu_count", json!({"type": "integer", "minimum": 0})),
+                    ],
+                )),
+            ),
+            ("required_display_name_prefixes", string_list()),
+            (
+                "command_hooks",
+                list_of(strict_object(
+                    &[],
+                    &[
+                        ("tags", string_list()),
+                        ("suites", string_list()),
+                        ("prepend", string_list()),
+                        ("append", string_list()),
+                    ],
+                )),
+            ),
+            (
+                "function_names",
+                strict_object(
+                    &[],
+                    &[
+                        ("run_generated_tests", string.clone()),
+                        ("run_bazel_tests", string.clone()),
+                        ("setup_jstestfuzz", string.clone()),
+                        ("run_fuzzer", string.clone()),
+                    ],
+                ),
+            ),
+            (
+                "module_evergreen_configs",
+                list_of(strict_object(
+                    &["module", "config_file"],
+                    &[("module", string.clone()), ("config_file", string.clone())],
+                )),
+            ),
+            ("history_variant_aliases", string_map()),
+            (
+                "task_naming",
+                strict_object(
+                    &[],
+                    &[
+                        ("sub_task_template", string.clone()),
+                        ("index_delimiter", string.clone()),
+                        ("index_width", json!({"type": "integer", "minimum": 1})),
+                    ],
+                ),
+            ),
+        ],
+    );
+    schema["$schema"] = json!(SCHEMA_DIALECT);
+    schema["title"] = json!("Sub-tasks configuration");
+    schema
+}
+
+/// Schema of an entry in `build_variant_clones`.
+fn variant_clone_schema() -> Value {
+    let string = json!({"type": "string"});
+    strict_object(
+        &["name_suffix"],
+        &[
+            ("build_variants", string_list()),
+            ("name_suffix", string.clone()),
+            ("display_name_prefix", string.clone()),
+            ("expansions", string_map()),
+            (
+                "depends_on",
+                list_of(json!({
+                    "type": "object",
+                    "required": ["name"],
+                    "properties": {"name": string, "variant": string},
+                })),
+            ),
+        ],
+    )
+}
+
+/// Schema of the evergreen names configuration file given with `--evergreen-names-config`.
+pub fn evergreen_names_config_schema() -> Value {
+    let mut schema = string_map();
+    schema["$schema"] = json!(SCHEMA_DIALECT);
+    schema["title"] = json!("Evergreen names configuration");
+    schema
+}
+
+/// Schema of the expansions file given with `--expansion-file`.
+///
+/// The expansions file holds every expansion of the generating task, so only the expansions
+/// the generator reads are described and other keys are allowed.
+pub fn expansions_schema() -> Value {
+    json!({
+        "$schema": SCHEMA_DIALECT,
+        "title": "Evergreen expansions",
+        "type": "object",
+        "required": ["project", "revision", "task_name", "version_id"],
+        "properties": {
+            "project": {"type": "string"},
+            "revision": {"type": "string"},
+            "task_name": {"type": "string"},
+            "version_id": {"type": "string"},
+            "is_patch": {"type": "string"},
+            "run_covered_tests": {"type": "string"},
+            "burn_in_base_revision": {"type": "string"},
+        },
+    })
+}
+
+/// Find the keys of a document that are not described by the given schema.
+///
+/// Only objects whose schema sets `additionalProperties` to `false` can have unknown keys.
+///
+/// # Arguments
+///
+/// * `schema` - Schema the document should follow.
+/// * `document` - Parsed YAML document to check.
+///
+/// # Returns
+///
+/// Path of each unknown key, e.g. `command_hooks[0].prepnd`.
+pub fn unknown_keys(schema: &Value, document: &serde_yaml::Value) -> Vec<String> {
+    let mut unknown = vec![];
+    collect_unknown_keys(schema, document, "", &mut unknown);
+    unknown
+}
+
+/// Add the unknown keys found under the given path of a document to a list.
+///
+/// # Arguments
+///
+/// * `schema` - Schema of the value at the path.
+/// * `document` - Value at the path.
+/// * `path` - Path of the value in the document.
+/// * `unknown` - List to add the paths of unknown keys to.
+fn collect_unknown_keys(
+    schema: &Value,
+    document: &serde_yaml::Value,
+    path: &str,
+    unknown: &mut Vec<String>,
+) {
+    match document {
+        serde_yaml::Value::Mapping(mapping) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            let additional_properties = schema.get("additionalProperties");
+            for (key, value) in mapping {
+                let key = match key.as_str() {
+                    Some(key) => key,
+                    None => continue,
+                };
+                let key_path = if path.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match (properties.and_then(|p| p.get(key)), additional_properties) {
+                    (Some(property_schema), _) => {
+                        collect_unknown_keys(property_schema, value, &key_path, unknown)
+                    }
+                    (None, Some(Value::Bool(false))) => unknown.push(key_path),
+                    (None, Some(value_schema)) => {
+                        collect_unknown_keys(value_schema, value, &key_path, unknown)
+                    }
+                    (None, None) => (),
+                }
+            }
+        }
+        serde_yaml::Value::Sequence(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    collect_unknown_keys(item_schema, item, &format!("{}[{}]", path, i), unknown);
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Find the keys of the given sub-tasks configuration file that the generator does not read.
+///
+/// # Arguments
+///
+/// * `location` - Path to the sub-tasks configuration file.
+///
+/// # Returns
+///
+/// Description of each unknown key.
+pub fn unknown_sub_tasks_config_keys<P: AsRef<Path>>(location: P) -> Result<Vec<String>> {
+    let contents = std::fs::read_to_string(location)?;
+    let document: serde_yaml::Value = serde_yaml::from_str(&contents)?;
+    Ok(unknown_keys(&generate_sub_tasks_config_schema(), &document)
+        .into_iter()
+        .map(|key| format!("unknown key '{}' in sub-tasks configuration", key))
+        .collect())
+}
+
+/// Check that the given configuration files can be read and only contain known keys.
+///
+/// # Arguments
+///
+/// * `gen_sub_tasks_config_file` - Sub-tasks configuration file to check, if any.
+/// * `evergreen_names_config_file` - Evergreen names configuration file to check, if any.
+///
+/// # Returns
+///
+/// Description of each problem found in the files, or an error if a file cannot be parsed.
+pub fn check_config_files(
+    gen_sub_tasks_config_file: Option<&Path>,
+    evergreen_names_config_file: Option<&Path>,
+) -> Result<Vec<String>> {
+    let mut problems = vec![];
+    if let Some(location) = gen_sub_tasks_config_file {
+        GenerateSubTasksConfig::from_yaml_file(location)?;
+        problems.extend(unknown_sub_tasks_config_keys(location)?);
+    }
+    if let Some(location) = evergreen_names_config_file {
+        EvergreenNamesConfig::from_yaml_file(location)?;
+    }
+    Ok(problems)
+}
+
+/// Write the schemas of all configuration files to the given directory.
+///
+/// # Arguments
+///
+/// * `directory` - Directory to write the schemas to.
+pub fn write_schemas(directory: &Path) -> Result<()> {
+    std::fs::create_dir_all(directory)?;
+    for (file_name, schema) in [
+        (
+            GENERATE_SUB_TASKS_CONFIG_SCHEMA_FILE,
+            generate_sub_tasks_config_schema(),
+        ),
+        (
+            EVERGREEN_NAMES_CONFIG_SCHEMA_FILE,
+            evergreen_names_config_schema(),
+        ),
+        (EXPANSIONS_SCHEMA_FILE, expansions_schema()),
+    ] {
+        std::fs::write(
+            directory.join(file_name),
+            serde_json::to_string_pretty(&schema)?,
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FULL_CONFIG: &str = r#"
+build_variant_large_distro_exceptions:
+  - bv_0
+multiversion_old_versions: [last_lts]
+generator_display_task_groups:
+  - name: fuzzers
+    tags: [fuzzer]
+    task_name_patterns: ["^jstestfuzz"]
+generator_functions: [run jstestfuzz]
+enterprise_detection: modules
+build_variant_distro_tiers:
+  - build_variants: ["*"]
+    tiers:
+      large: rhel80-large
+build_variant_clones:
+  - build_variants: [bv_0]
+    name_suffix: "-clone"
+    display_name_prefix: "~ "
+    expansions:
+      key: value
+    depends_on:
+      - name: compile
+        variant: bv_0
+feature_flag_variants:
+  - build_variants: [bv_0]
+    feature_flags: [featureFlagA, [featureFlagB, featureFlagC]]
+    tasks: [auth_gen]
+empty_suite_fallback: true
+distro_capabilities:
+  - distros: ["*-large"]
+    cpu_count: 16
+required_display_name_prefixes: ["!"]
+command_hooks:
+  - tags: [auth]
+    suites: [core]
+    prepend: [setup]
+    append: [teardown]
+function_names:
+  run_generated_tests: run tests
+module_evergreen_configs:
+  - module: enterprise
+    config_file: etc/evergreen.yml
+history_variant_aliases:
+  bv_1: bv_0
+task_naming:
+  sub_task_template: "{task}{platform}{enterprise}_{index}"
+  index_delimiter: "-"
+  index_width: 3
+"#;
+
+    #[test]
+    fn test_schema_should_describe_every_key_of_the_sub_tasks_config() {
+        let document: serde_yaml::Value = serde_yaml::from_str(FULL_CONFIG).unwrap();
+
+        assert!(serde_yaml::from_str::<GenerateSubTasksConfig>(FULL_CONFIG).is_ok());
+        assert!(unknown_keys(&generate_sub_tasks_config_schema(), &document).is_empty());
+    }
+
+    #[test]
+    fn test_unknown_keys_should_report_the_path_of_misspelled_keys() {
+        let config = r#"
+build_variant_large_distro_exceptions: []
+empty_suite_falback: true
+command_hooks:
+  - tags: [auth]
+    prepnd: [setup]
+history_variant_aliases:
+  any_name: bv_0
+"#;
+        let document: serde_yaml::Value = serde_yaml::from_str(config).unwrap();
+
+        assert_eq!(
+            unknown_keys(&generate_sub_tasks_config_schema(), &document),
+            vec![
+                "empty_suite_falback".to_string(),
+                "command_hooks[0].prepnd".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_keys_should_allow_any_expansion() {
+        let document: serde_yaml::Value =
+            serde_yaml::from_str("project: mongodb-mongo-master\nbuild_id: abc\n").unwrap();
+
+        assert!(unknown_keys(&expansions_schema(), &document).is_empty());
+    }
+}
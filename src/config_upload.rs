@@ -0,0 +1,188 @@
+//! Upload the generated configuration to S3.
+//!
+//! The target directory is archived with `tar` into a temporary file, which is streamed to S3 with
+//! the AWS SDK. Credentials and the region are resolved by the SDK's default provider chain, so
+//! environment variables, shared config files and instance profiles all work.
+//!
+//! Test stats are read with plain HTTPS requests to a public bucket, so there is no S3 client to
+//! reuse here. Uploading needs signed requests and the credential provider chain, which is what
+//! the AWS SDK is used for.
+use std::{
+    fmt::{Display, Formatter},
+    path::Path,
+    str::FromStr,
+};
+
+use anyhow::{anyhow, Context, Result};
+use aws_config::{meta::region::RegionProviderChain, retry::RetryConfig, BehaviorVersion};
+use aws_sdk_s3::{primitives::ByteStream, Client};
+use tempfile::NamedTempFile;
+use tracing::{event, Level};
+
+use crate::{error_report::ErrorCategory, resmoke::external_cmd::run_command};
+
+/// Prefix of S3 locations.
+const S3_PREFIX: &str = "s3://";
+/// Region to use when none is configured.
+const DEFAULT_AWS_REGION: &str = "us-east-1";
+/// Number of attempts to make at uploading before giving up.
+const UPLOAD_MAX_ATTEMPTS: u32 = 5;
+
+/// Location in S3 to upload to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadLocation {
+    /// Name of S3 bucket.
+    pub bucket: String,
+    /// Key of object in the bucket.
+    pub key: String,
+}
+
+impl Display for UploadLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}/{}", S3_PREFIX, self.bucket, self.key)
+    }
+}
+
+impl FromStr for UploadLocation {
+    type Err = anyhow::Error;
+
+    fn from_str(location: &str) -> Result<Self> {
+        let (bucket, key) = location
+            .strip_prefix(S3_PREFIX)
+            .and_then(|path| path.split_once('/'))
+            .filter(|(bucket, key)| !bucket.is_empty() && !key.is_empty())
+            .ok_or_else(|| {
+                anyhow!(
+                    "Upload location '{}' is not of the form 's3://<bucket>/<key>'",
+                    location
+                )
+            })?;
+
+        Ok(Self {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+        })
+    }
+}
+
+/// Archive the given directory and upload it to the given S3 location.
+///
+/// # Arguments
+///
+/// * `target_directory` - Directory containing the generated configuration.
+/// * `location` - S3 location to upload to.
+pub async fn upload_generated_config(
+    target_directory: &Path,
+    location: &UploadLocation,
+) -> Result<()> {
+    // The archive is removed when it is dropped at the end of the upload, even on failure.
+    let archive = archive_directory(target_directory).context(ErrorCategory::Upload)?;
+    put_object(location, archive.path())
+        .await
+        .context(ErrorCategory::Upload)?;
+
+    event!(
+        Level::INFO,
+        location = location.to_string(),
+        "Uploaded generated configuration"
+    );
+    Ok(())
+}
+
+/// Create a gzipped tar archive of the given directory in a temporary file.
+///
+/// # Arguments
+///
+/// * `directory` - Directory to archive.
+///
+/// # Returns
+///
+/// Temporary file containing the archive, deleted when dropped.
+fn archive_directory(directory: &Path) -> Result<NamedTempFile> {
+    let archive = tempfile::Builder::new()
+        .prefix("generated_config")
+        .suffix(".tgz")
+        .tempfile()?;
+    run_command(&[
+        "tar",
+        "-czf",
+        &archive.path().display().to_string(),
+        "-C",
+        &directory.display().to_string(),
+        ".",
+    ])?;
+    Ok(archive)
+}
+
+/// Stream the given file to S3.
+///
+/// # Arguments
+///
+/// * `location` - Bucket and key to upload to.
+/// * `file` - File to upload.
+async fn put_object(location: &UploadLocation, file: &Path) -> Result<()> {
+    let region = RegionProviderChain::default_provider().or_else(DEFAULT_AWS_REGION);
+    let config = aws_config::defaults(BehaviorVersion::latest())
+        .region(region)
+        .retry_config(RetryConfig::standard().with_max_attempts(UPLOAD_MAX_ATTEMPTS))
+        .load()
+        .await;
+
+    Client::new(&config)
+        .put_object()
+        .bucket(&location.bucket)
+        .key(&location.key)
+        .body(ByteStream::from_path(file).await?)
+        .send()
+        .await
+        .map_err(|err| {
+            anyhow!(
+                "Upload to '{}' failed: {}",
+                location,
+                aws_sdk_s3::error::DisplayErrorContext(err)
+            )
+        })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[rstest]
+    #[case("s3://bucket/key.tgz", "bucket", "key.tgz")]
+    #[case("s3://bucket/path/to/key.tgz", "bucket", "path/to/key.tgz")]
+    fn test_upload_location_parse(#[case] location: &str, #[case] bucket: &str, #[case] key: &str) {
+        let upload_location: UploadLocation = location.parse().unwrap();
+
+        assert_eq!(upload_location.bucket, bucket);
+        assert_eq!(upload_location.key, key);
+        assert_eq!(upload_location.to_string(), location);
+    }
+
+    #[rstest]
+    #[case("https://bucket/key.tgz")]
+    #[case("s3://bucket")]
+    #[case("s3://bucket/")]
+    #[case("s3:///key.tgz")]
+    fn test_upload_location_parse_should_reject_invalid_locations(#[case] location: &str) {
+        assert!(location.parse::<UploadLocation>().is_err());
+    }
+
+    #[test]
+    fn test_archive_directory_should_archive_into_a_temporary_file() {
+        let directory = TempDir::new("generated_config").unwrap();
+        std::fs::write(directory.path().join("evergreen_config.json"), "{}").unwrap();
+
+        let archive = archive_directory(directory.path()).unwrap();
+        let archive_path = archive.path().to_path_buf();
+
+        assert!(std::fs::metadata(&archive_path).unwrap().len() > 0);
+        assert!(!archive_path.starts_with(directory.path()));
+        drop(archive);
+        assert!(!archive_path.exists());
+    }
+}
@@ -0,0 +1,144 @@
+//! Machine-readable report of errors encountered during generation.
+//!
+//! Errors are categorized by attaching an `ErrorCategory` as context where they are created. When
+//! generation fails, the category and message of the error are written to `errors.json` in the
+//! target directory so that wrapper tasks can surface them without parsing log output.
+use std::{fmt::Display, path::Path};
+
+use anyhow::Result;
+use serde::Serialize;
+
+/// Name of file to write errors to.
+pub(crate) const ERROR_REPORT_FILE: &str = "errors.json";
+
+/// Category of an error encountered during generation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// The command line options, expansions or configuration files are invalid.
+    Config,
+    /// Task history could not be fetched.
+    HistoryFetch,
+    /// Resmoke could not be run or produced invalid output.
+    Resmoke,
+    /// The project or generated configuration failed validation.
+    Validation,
+    /// The generated configuration could not be uploaded.
+    Upload,
+    /// Generation was interrupted by a signal.
+    Interrupted,
+    /// The error was not categorized.
+    Other,
+}
+
+impl Display for ErrorCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let description = match self {
+            ErrorCategory::Config => "Invalid configuration",
+            ErrorCategory::HistoryFetch => "Failed to fetch task history",
+            ErrorCategory::Resmoke => "Failed to run resmoke",
+            ErrorCategory::Validation => "Validation failed",
+            ErrorCategory::Upload => "Failed to upload generated configuration",
+            ErrorCategory::Interrupted => "Interrupted",
+            ErrorCategory::Other => "Error",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+/// An error entry in the error report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorEntry {
+    /// Category of the error.
+    pub category: ErrorCategory,
+    /// Description of the error.
+    pub message: String,
+}
+
+impl ErrorEntry {
+    /// Create an error report entry for the given error.
+    ///
+    /// # Arguments
+    ///
+    /// * `err` - Error to report.
+    ///
+    /// # Returns
+    ///
+    /// Entry describing the error.
+    pub fn from_error(err: &anyhow::Error) -> Self {
+        let category = err
+            .downcast_ref::<ErrorCategory>()
+            .copied()
+            .unwrap_or(ErrorCategory::Other);
+        let message = err
+            .chain()
+            .map(|e| e.to_string())
+            .filter(|message| *message != category.to_string())
+            .collect::<Vec<String>>()
+            .join(": ");
+
+        Self { category, message }
+    }
+}
+
+/// Report of errors encountered during generation.
+#[derive(Debug, Clone, Serialize)]
+struct ErrorReport {
+    /// Errors encountered.
+    errors: Vec<ErrorEntry>,
+}
+
+/// Write a report of the given error to `errors.json` in the target directory.
+///
+/// # Arguments
+///
+/// * `target_directory` - Directory to write the report to.
+/// * `err` - Error to report.
+pub fn write_error_report(target_directory: &Path, err: &anyhow::Error) -> Result<()> {
+    let report = ErrorReport {
+        errors: vec![ErrorEntry::from_error(err)],
+    };
+    std::fs::create_dir_all(target_directory)?;
+    std::fs::write(
+        target_directory.join(ERROR_REPORT_FILE),
+        serde_json::to_string_pretty(&report)?,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{anyhow, Context};
+
+    use super::*;
+
+    #[test]
+    fn test_error_entry_should_use_category_from_context() {
+        let err: Result<()> = Err(anyhow!("suite not found")).context(ErrorCategory::Resmoke);
+
+        let entry = ErrorEntry::from_error(&err.unwrap_err());
+
+        assert_eq!(entry.category, ErrorCategory::Resmoke);
+        assert_eq!(entry.message, "suite not found");
+    }
+
+    #[test]
+    fn test_error_entry_should_find_category_under_other_context() {
+        let err: Result<()> = Err(anyhow!("bad yaml"))
+            .context(ErrorCategory::Config)
+            .context("reading expansions");
+
+        let entry = ErrorEntry::from_error(&err.unwrap_err());
+
+        assert_eq!(entry.category, ErrorCategory::Config);
+        assert_eq!(entry.message, "reading expansions: bad yaml");
+    }
+
+    #[test]
+    fn test_error_entry_should_default_to_other() {
+        let entry = ErrorEntry::from_error(&anyhow!("something went wrong"));
+
+        assert_eq!(entry.category, ErrorCategory::Other);
+        assert_eq!(entry.message, "something went wrong");
+    }
+}
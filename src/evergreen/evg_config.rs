@@ -1,29 +1,47 @@
-use anyhow::Result;
-use maplit::hashmap;
-use std::{collections::HashMap, path::Path, process::Command};
+use anyhow::{anyhow, Result};
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    process::Command,
+    sync::Arc,
+};
+use tracing::warn;
 
 use shrub_rs::models::{project::EvgProject, task::EvgTask, variant::BuildVariant};
 
-const REQUIRED_PREFIX: &str = "!";
+use crate::{
+    evergreen_names_config::EvergreenNamesConfig, generate_sub_tasks_config::ModuleEvgConfig,
+};
+
+/// Display name prefixes of build variants that gate merges, used if none are configured.
+pub const DEFAULT_REQUIRED_PREFIXES: &[&str] = &["!", "*"];
 
 pub trait EvgConfigService: Sync + Send {
     /// Get a map of build variant names to build variant definitions.
     fn get_build_variant_map(&self) -> HashMap<String, &BuildVariant>;
 
     /// Get a map of task name to task definitions.
-    fn get_task_def_map(&self) -> HashMap<String, EvgTask>;
+    fn get_task_def_map(&self) -> HashMap<String, Arc<EvgTask>>;
 
     /// Get a list of build variants with the required build variants at the start.
-    fn sort_build_variants_by_required(&self) -> Vec<String>;
+    ///
+    /// # Arguments
+    ///
+    /// * `required_prefixes` - Display name prefixes of required build variants.
+    fn sort_build_variants_by_required(&self, required_prefixes: &[String]) -> Vec<String>;
 
-    /// Get the directory of the given module.
-    fn get_module_dir(&self, module_name: &str) -> Option<String>;
+    /// Get a map of module names to the directories the modules are checked out to.
+    fn get_module_dirs(&self) -> HashMap<String, String>;
 }
 
 /// Items needed to implement an evergreen configuration service.
 pub struct EvgProjectConfig {
     /// Shrub representation of the evg project.
     evg_project: EvgProject,
+    /// Map of task names to task definitions, shared with everything working with them.
+    task_def_map: HashMap<String, Arc<EvgTask>>,
+    /// Overrides of the names in evergreen_names used by the project.
+    evergreen_names: EvergreenNamesConfig,
 }
 
 impl EvgProjectConfig {
@@ -32,9 +50,44 @@ impl EvgProjectConfig {
     /// # Parameters
     ///
     /// * `evg_project_location` - Path to evergreen project configuration to load.
-    pub fn new(evg_project_location: &Path) -> Result<Self> {
-        let evg_project = get_project_config(evg_project_location)?;
-        Ok(Self { evg_project })
+    /// * `evergreen_names` - Overrides of the names in evergreen_names used by the project.
+    /// * `module_configs` - Evergreen configurations of modules to add tasks from.
+    pub fn new(
+        evg_project_location: &Path,
+        evergreen_names: &EvergreenNamesConfig,
+        module_configs: &[ModuleEvgConfig],
+    ) -> Result<Self> {
+        let mut evg_project = get_project_config(evg_project_location)?;
+        evergreen_names.translate_to_default(&mut evg_project);
+        for module_config in module_configs {
+            let module = evg_project
+                .modules
+                .iter()
+                .flatten()
+                .find(|m| evergreen_names.to_default(&m.name) == module_config.module)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Module '{}' is not defined in the project",
+                        module_config.module
+                    )
+                })?;
+            let module_location = Path::new(&module.prefix)
+                .join(&module.name)
+                .join(&module_config.config_file);
+            let mut module_project = get_project_config(&module_location)?;
+            evergreen_names.translate_to_default(&mut module_project);
+            merge_module_project(&mut evg_project, module_project, &module_config.module);
+        }
+        let task_def_map = evg_project
+            .task_def_map()
+            .into_iter()
+            .map(|(name, task_def)| (name, Arc::new(task_def.clone())))
+            .collect();
+        Ok(Self {
+            evg_project,
+            task_def_map,
+            evergreen_names: evergreen_names.clone(),
+        })
     }
 }
 
@@ -45,22 +98,27 @@ impl EvgConfigService for EvgProjectConfig {
     }
 
     /// Get a map of task name to task definitions.
-    fn get_task_def_map(&self) -> HashMap<String, EvgTask> {
-        let mut task_map = hashmap! {};
-        for (k, v) in self.evg_project.task_def_map() {
-            task_map.insert(k, v.clone());
-        }
-        task_map
+    fn get_task_def_map(&self) -> HashMap<String, Arc<EvgTask>> {
+        self.task_def_map.clone()
     }
 
     /// Get a list of build variants with the required build variants at the start.
-    fn sort_build_variants_by_required(&self) -> Vec<String> {
+    ///
+    /// # Arguments
+    ///
+    /// * `required_prefixes` - Display name prefixes of required build variants.
+    fn sort_build_variants_by_required(&self, required_prefixes: &[String]) -> Vec<String> {
         let build_variant_map = self.get_build_variant_map();
+        let is_required = |build_variant: &BuildVariant| {
+            let display_name = build_variant.display_name.as_ref().unwrap();
+            required_prefixes
+                .iter()
+                .any(|prefix| display_name.starts_with(prefix.as_str()))
+        };
         let mut build_variants: Vec<String> = build_variant_map
             .iter()
             .filter_map(|(name, build_variant)| {
-                let display_name = build_variant.display_name.as_ref().unwrap();
-                if display_name.starts_with(REQUIRED_PREFIX) {
+                if is_required(build_variant) {
                     Some(name.to_string())
                 } else {
                     None
@@ -72,8 +130,7 @@ impl EvgConfigService for EvgProjectConfig {
             build_variant_map
                 .iter()
                 .filter_map(|(name, build_variant)| {
-                    let display_name = build_variant.display_name.as_ref().unwrap();
-                    if !display_name.starts_with(REQUIRED_PREFIX) {
+                    if !is_required(build_variant) {
                         Some(name.to_string())
                     } else {
                         None
@@ -85,16 +142,63 @@ impl EvgConfigService for EvgProjectConfig {
         build_variants
     }
 
-    /// Get the directory of the given module.
-    fn get_module_dir(&self, module_name: &str) -> Option<String> {
-        if let Some(modules) = &self.evg_project.modules {
-            for module in modules {
-                if module.name == module_name {
-                    return Some(format!("{}/{}", &module.prefix, module_name));
-                }
-            }
+    /// Get a map of module names to the directories the modules are checked out to.
+    fn get_module_dirs(&self) -> HashMap<String, String> {
+        self.evg_project
+            .modules
+            .iter()
+            .flatten()
+            .map(|module| {
+                (
+                    self.evergreen_names.to_default(&module.name),
+                    format!("{}/{}", &module.prefix, &module.name),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Add the tasks of a module's evergreen configuration to the project.
+///
+/// Tasks the project already defines are kept. Tasks listed on build variants of the module
+/// configuration are added to the project build variants with the same name.
+///
+/// # Arguments
+///
+/// * `evg_project` - Project to add tasks to.
+/// * `module_project` - Evergreen configuration of the module.
+/// * `module_name` - Name of the module.
+fn merge_module_project(
+    evg_project: &mut EvgProject,
+    module_project: EvgProject,
+    module_name: &str,
+) {
+    let task_names: HashSet<String> = evg_project.tasks.iter().map(|t| t.name.clone()).collect();
+    for task in module_project.tasks {
+        if task_names.contains(&task.name) {
+            warn!(
+                module = module_name,
+                task = task.name.as_str(),
+                "Module task is already defined in the project, using the project definition"
+            );
+        } else {
+            evg_project.tasks.push(task);
+        }
+    }
+
+    for module_variant in module_project.buildvariants {
+        match evg_project
+            .buildvariants
+            .iter_mut()
+            .find(|bv| bv.name == module_variant.name)
+        {
+            Some(build_variant) => build_variant.tasks.extend(module_variant.tasks),
+            None => warn!(
+                module = module_name,
+                build_variant = module_variant.name.as_str(),
+                "Module build variant is not defined in the project, its tasks will not be generated"
+            ),
         }
-        None
     }
 }
 
@@ -113,3 +217,57 @@ fn get_project_config(location: &Path) -> Result<EvgProject> {
         .output()?;
     Ok(EvgProject::from_yaml_str(std::str::from_utf8(&evg_config_yaml.stdout)?).unwrap())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_task(name: &str, exec_timeout_secs: Option<u64>) -> EvgTask {
+        EvgTask {
+            name: name.to_string(),
+            exec_timeout_secs,
+            ..Default::default()
+        }
+    }
+
+    fn build_project(build_variant: &str, tasks: Vec<EvgTask>) -> EvgProject {
+        EvgProject {
+            buildvariants: vec![BuildVariant {
+                name: build_variant.to_string(),
+                tasks: tasks
+                    .iter()
+                    .map(|task| task.get_reference(None, None))
+                    .collect(),
+                ..Default::default()
+            }],
+            tasks,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_merge_module_project_should_add_module_tasks_to_project_build_variants() {
+        let mut evg_project = build_project("bv", vec![build_task("task_1", Some(3600))]);
+        let mut module_project = build_project(
+            "bv",
+            vec![build_task("task_1", None), build_task("module_task", None)],
+        );
+        module_project.buildvariants.push(
+            build_project("other_bv", vec![build_task("other_task", None)]).buildvariants[0]
+                .clone(),
+        );
+
+        merge_module_project(&mut evg_project, module_project, "enterprise");
+
+        let task_names: Vec<&str> = evg_project.tasks.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(task_names, vec!["task_1", "module_task"]);
+        assert_eq!(evg_project.tasks[0].exec_timeout_secs, Some(3600));
+        assert_eq!(evg_project.buildvariants.len(), 1);
+        let bv_task_names: Vec<&str> = evg_project.buildvariants[0]
+            .tasks
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect();
+        assert_eq!(bv_task_names, vec!["task_1", "task_1", "module_task"]);
+    }
+}
@@ -9,10 +9,12 @@ use shrub_rs::models::params::ParamValue;
 use shrub_rs::models::{commands::FunctionCall, task::EvgTask, variant::BuildVariant};
 
 use crate::evergreen_names::{
-    BURN_IN_TAG_EXCLUDE_BUILD_VARIANTS, BURN_IN_TAG_INCLUDE_ALL_REQUIRED_AND_SUGGESTED,
-    BURN_IN_TAG_INCLUDE_BUILD_VARIANTS, GENERATE_RESMOKE_TASKS, INITIALIZE_MULTIVERSION_TASKS,
-    IS_FUZZER, LINUX, MACOS, RUN_RESMOKE_TESTS, WINDOWS,
+    ARM64, BURN_IN_TAG_EXCLUDE_BUILD_VARIANTS, BURN_IN_TAG_INCLUDE_ALL_REQUIRED_AND_SUGGESTED,
+    BURN_IN_TAG_INCLUDE_BUILD_VARIANTS, ENTERPRISE_MODULE, FUZZER_TASK_VARS,
+    GENERATED_TASK_PLATFORM_EXPANSION, GENERATE_RESMOKE_TASKS, INITIALIZE_MULTIVERSION_TASKS,
+    LINUX, MACOS, PPC, RUN_RESMOKE_TESTS, S390X, WINDOWS,
 };
+use crate::generate_sub_tasks_config::EnterpriseDetection;
 use crate::utils::task_name::remove_gen_suffix;
 
 lazy_static! {
@@ -22,6 +24,16 @@ lazy_static! {
         Regex::new(r"\$\{(?P<id>[a-zA-Z0-9_]+)(\|(?P<default>.*))?}").unwrap();
 }
 
+/// Substrings of distro names that identify the architecture of the distro.
+const DISTRO_ARCHITECTURES: &[(&str, &str)] = &[
+    ("arm64", ARM64),
+    ("aarch64", ARM64),
+    ("ppc", PPC),
+    ("power", PPC),
+    ("s390x", S390X),
+    ("zseries", S390X),
+];
+
 /// Multiversion task that will be generated.
 #[derive(Default, Debug, Clone, PartialEq)]
 pub struct MultiversionGenerateTaskConfig {
@@ -152,6 +164,17 @@ pub trait EvgConfigUtils: Sync + Send {
         build_variant: &BuildVariant,
     ) -> Option<String>;
 
+    /// Lookup the specified project-level expansion.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of expansion to query.
+    ///
+    /// # Returns
+    ///
+    /// Value of expansion if it exists.
+    fn lookup_project_expansion(&self, name: &str) -> Option<String>;
+
     /// Lookup and split by whitespace the specified expansion in the given build variant.
     ///
     /// # Arguments
@@ -287,12 +310,60 @@ pub trait EvgConfigUtils: Sync + Send {
 }
 
 /// Service for utilities to help interpret evergreen configuration.
-pub struct EvgConfigUtilsImpl {}
+pub struct EvgConfigUtilsImpl {
+    /// Names of functions that mark a task as generated.
+    generator_functions: Vec<String>,
+    /// Project-level expansions to use when a build variant does not define an expansion.
+    project_expansions: HashMap<String, String>,
+    /// Method used to detect enterprise build variants.
+    enterprise_detection: EnterpriseDetection,
+}
 
 impl EvgConfigUtilsImpl {
     /// Create a new instance of the EvgConfigUtilsImpl.
     pub fn new() -> Self {
-        Self {}
+        Self::with_config(
+            vec![GENERATE_RESMOKE_TASKS.to_string()],
+            HashMap::new(),
+            EnterpriseDetection::default(),
+        )
+    }
+
+    /// Create a new instance of the EvgConfigUtilsImpl with project specific configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `generator_functions` - Names of functions that mark a task as generated.
+    /// * `project_expansions` - Project-level expansions to fallback to when resolving run vars.
+    /// * `enterprise_detection` - Method used to detect enterprise build variants.
+    pub fn with_config(
+        generator_functions: Vec<String>,
+        project_expansions: HashMap<String, String>,
+        enterprise_detection: EnterpriseDetection,
+    ) -> Self {
+        Self {
+            generator_functions,
+            project_expansions,
+            enterprise_detection,
+        }
+    }
+
+    /// Get the vars of the generator function called by the given task.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - Shrub task to query.
+    ///
+    /// # Returns
+    ///
+    /// HashMap of vars in the first generator function the task calls.
+    fn get_generator_func_vars<'a>(
+        &self,
+        task: &'a EvgTask,
+    ) -> Option<&'a HashMap<String, ParamValue>> {
+        self.generator_functions
+            .iter()
+            .find_map(|func_name| get_func_vars_by_name(task, func_name))
     }
 }
 
@@ -310,7 +381,7 @@ impl EvgConfigUtils for EvgConfigUtilsImpl {
         if let Some(commands) = &task.commands {
             commands.iter().any(|c| {
                 if let Function(func) = c {
-                    if func.func == GENERATE_RESMOKE_TASKS {
+                    if self.generator_functions.contains(&func.func) {
                         return true;
                     }
                 }
@@ -323,6 +394,9 @@ impl EvgConfigUtils for EvgConfigUtilsImpl {
 
     /// Determine if the given evergreen task a fuzzer task.
     ///
+    /// This matches the task vars the fuzzer kind is registered against in the generated task
+    /// kind registry.
+    ///
     /// # Arguments
     ///
     /// * `task` - Evergreen task to check.
@@ -331,12 +405,9 @@ impl EvgConfigUtils for EvgConfigUtilsImpl {
     ///
     /// `true` if the given task is a fuzzer task.
     fn is_task_fuzzer(&self, task: &EvgTask) -> bool {
-        let is_jstestfuzz = self.get_gen_task_var(task, IS_FUZZER);
-        if let Some(is_jstestfuzz) = is_jstestfuzz {
-            is_jstestfuzz == "true"
-        } else {
-            false
-        }
+        FUZZER_TASK_VARS
+            .iter()
+            .any(|var| self.get_gen_task_var(task, var) == Some("true"))
     }
 
     /// Find the name of the resmoke suite the given task executes.
@@ -349,7 +420,9 @@ impl EvgConfigUtils for EvgConfigUtilsImpl {
     ///
     /// Name of task the given resmoke suite executes.
     fn find_suite_name<'a>(&self, task: &'a EvgTask) -> &'a str {
-        let optional_vars = get_resmoke_vars(task);
+        let optional_vars = self
+            .get_generator_func_vars(task)
+            .or_else(|| get_func_vars_by_name(task, RUN_RESMOKE_TESTS));
 
         let generated_task_name = remove_gen_suffix(&task.name);
 
@@ -441,7 +514,7 @@ impl EvgConfigUtils for EvgConfigUtilsImpl {
     ///
     /// Value of given variable in the 'generate resmoke task' vars.
     fn get_gen_task_var<'a>(&self, task: &'a EvgTask, var: &str) -> Option<&'a str> {
-        if let Some(vars) = get_func_vars_by_name(task, GENERATE_RESMOKE_TASKS) {
+        if let Some(vars) = self.get_generator_func_vars(task) {
             if let Some(ParamValue::String(value)) = vars.get(var) {
                 return Some(value);
             }
@@ -459,7 +532,7 @@ impl EvgConfigUtils for EvgConfigUtilsImpl {
     ///
     /// HashMap of vars in the 'generate resmoke task'.
     fn get_gen_task_vars(&self, task: &EvgTask) -> Option<HashMap<String, ParamValue>> {
-        if let Some(vars) = get_func_vars_by_name(task, GENERATE_RESMOKE_TASKS) {
+        if let Some(vars) = self.get_generator_func_vars(task) {
             return Some(vars.clone());
         }
         None
@@ -467,6 +540,9 @@ impl EvgConfigUtils for EvgConfigUtilsImpl {
 
     /// Lookup the given 'run_var' in the build variant 'vars' and provide the value.
     ///
+    /// If the build variant does not define the expansion, the project-level expansions are
+    /// checked before falling back to the default value.
+    ///
     /// # Arguments
     ///
     /// * `run_var` - Name of var to lookup.
@@ -481,6 +557,8 @@ impl EvgConfigUtils for EvgConfigUtilsImpl {
             let id = captures.name("id").unwrap();
             if let Some(value) = self.lookup_build_variant_expansion(id.as_str(), build_variant) {
                 Some(value)
+            } else if let Some(value) = self.project_expansions.get(id.as_str()) {
+                Some(value.clone())
             } else {
                 captures.name("default").map(|d| d.as_str().to_string())
             }
@@ -512,6 +590,19 @@ impl EvgConfigUtils for EvgConfigUtilsImpl {
             .map(|v| v.to_string())
     }
 
+    /// Lookup the specified project-level expansion.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of expansion to query.
+    ///
+    /// # Returns
+    ///
+    /// Value of expansion if it exists.
+    fn lookup_project_expansion(&self, name: &str) -> Option<String> {
+        self.project_expansions.get(name).cloned()
+    }
+
     /// Lookup and split by whitespace the specified expansion in the given build variant.
     ///
     /// # Arguments
@@ -717,6 +808,12 @@ impl EvgConfigUtils for EvgConfigUtilsImpl {
     ///
     /// true if given build variant includes the enterprise module.
     fn is_enterprise_build_variant(&self, build_variant: &BuildVariant) -> bool {
+        if self.enterprise_detection == EnterpriseDetection::Modules {
+            if let Some(modules) = &build_variant.modules {
+                return modules.iter().any(|m| m == ENTERPRISE_MODULE);
+            }
+        }
+
         let pattern = Regex::new(r"--enableEnterpriseTests\s*=?\s*off").unwrap();
         if let Some(expansions_map) = &build_variant.expansions {
             for (_key, value) in expansions_map.iter() {
@@ -730,14 +827,23 @@ impl EvgConfigUtils for EvgConfigUtilsImpl {
 
     /// Infer platform that build variant will run on.
     ///
+    /// The `generated_task_platform` expansion can be used to override the inferred platform.
+    ///
     /// # Arguments
     ///
     /// * `build_variant` - Build variant to query.
     ///
     /// # Returns
     ///
-    /// linux, or mac, or windows platform that build variant will run on.
+    /// linux, or mac, or windows platform that build variant will run on, suffixed with the
+    /// architecture for non-x86 distros (e.g. linux-arm64).
     fn infer_build_variant_platform(&self, build_variant: &BuildVariant) -> String {
+        if let Some(platform) =
+            self.lookup_build_variant_expansion(GENERATED_TASK_PLATFORM_EXPANSION, build_variant)
+        {
+            return platform;
+        }
+
         let distro = build_variant
             .run_on
             .as_ref()
@@ -746,12 +852,22 @@ impl EvgConfigUtils for EvgConfigUtilsImpl {
             .unwrap_or(&"".to_string())
             .to_lowercase();
 
-        if distro.contains(MACOS) {
-            MACOS.to_string()
+        let os = if distro.contains(MACOS) {
+            MACOS
         } else if distro.contains(WINDOWS) {
-            WINDOWS.to_string()
+            WINDOWS
         } else {
-            LINUX.to_string()
+            LINUX
+        };
+
+        let arch = DISTRO_ARCHITECTURES
+            .iter()
+            .find(|(pattern, _)| distro.contains(pattern))
+            .map(|(_, arch)| arch);
+
+        match arch {
+            Some(arch) => format!("{}-{}", os, arch),
+            None => os.to_string(),
         }
     }
 }
@@ -807,22 +923,6 @@ fn get_func_vars_by_name<'a>(
     None
 }
 
-/// Get the vars passed to "generate resmoke task" or "run tests".
-///
-/// # Arguments
-///
-/// * `task` - Shrub task to query.
-///
-/// # Returns
-///
-/// vars forwarded to resmoke.py.
-fn get_resmoke_vars(task: &EvgTask) -> Option<&HashMap<String, ParamValue>> {
-    if let Some(generate_resmoke_tasks_vars) = get_func_vars_by_name(task, GENERATE_RESMOKE_TASKS) {
-        return Some(generate_resmoke_tasks_vars);
-    }
-    return get_func_vars_by_name(task, RUN_RESMOKE_TESTS);
-}
-
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeMap;
@@ -1003,6 +1103,33 @@ mod tests {
         assert_eq!(evg_config_utils.is_task_generated(&evg_task), true);
     }
 
+    #[rstest]
+    #[case("generate resmoke tasks", false)]
+    #[case("generate custom tasks", true)]
+    #[case("generate other tasks", true)]
+    fn test_is_task_generated_should_use_custom_generator_functions(
+        #[case] func_name: &str,
+        #[case] expected_generated: bool,
+    ) {
+        let evg_task = EvgTask {
+            commands: Some(vec![fn_call(func_name)]),
+            ..Default::default()
+        };
+        let evg_config_utils = EvgConfigUtilsImpl::with_config(
+            vec![
+                "generate custom tasks".to_string(),
+                "generate other tasks".to_string(),
+            ],
+            HashMap::new(),
+            EnterpriseDetection::default(),
+        );
+
+        assert_eq!(
+            evg_config_utils.is_task_generated(&evg_task),
+            expected_generated
+        );
+    }
+
     // is_task_fuzzer tests.
     #[test]
     fn test_is_task_fuzzer_should_return_false_if_var_is_missing() {
@@ -1045,6 +1172,43 @@ mod tests {
         assert_eq!(evg_config_utils.is_task_fuzzer(&evg_task), true);
     }
 
+    #[rstest]
+    #[case("true", true)]
+    #[case("false", false)]
+    fn test_is_task_fuzzer_should_detect_differential_fuzzers(
+        #[case] is_differential_fuzzer: &str,
+        #[case] expected: bool,
+    ) {
+        let evg_task = EvgTask {
+            commands: Some(vec![fn_call_with_params(
+                "generate resmoke tasks",
+                hashmap! {
+                    "is_differential_fuzzer".to_string() => ParamValue::from(is_differential_fuzzer),
+                },
+            )]),
+            ..Default::default()
+        };
+        let evg_config_utils = EvgConfigUtilsImpl::new();
+
+        assert_eq!(evg_config_utils.is_task_fuzzer(&evg_task), expected);
+    }
+
+    // lookup_project_expansion tests.
+    #[rstest]
+    #[case("burn_in_tag_gen_task_dependency", Some("burn_in_gen".to_string()))]
+    #[case("missing_expansion", None)]
+    fn test_lookup_project_expansion(#[case] name: &str, #[case] expected: Option<String>) {
+        let evg_config_utils = EvgConfigUtilsImpl::with_config(
+            vec![],
+            hashmap! {
+                "burn_in_tag_gen_task_dependency".to_string() => "burn_in_gen".to_string(),
+            },
+            EnterpriseDetection::default(),
+        );
+
+        assert_eq!(evg_config_utils.lookup_project_expansion(name), expected);
+    }
+
     // find_suite_name tests.
     #[test]
     fn test_find_suite_name_should_use_suite_var_for_generated_task_if_it_exists() {
@@ -1293,6 +1457,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_gen_task_var_should_use_custom_generator_functions() {
+        let evg_task = EvgTask {
+            commands: Some(vec![fn_call_with_params(
+                "generate custom tasks",
+                hashmap! {
+                    "my var".to_string() => ParamValue::from("my value"),
+                },
+            )]),
+            ..Default::default()
+        };
+        let evg_config_utils = EvgConfigUtilsImpl::with_config(
+            vec!["generate custom tasks".to_string()],
+            HashMap::new(),
+            EnterpriseDetection::default(),
+        );
+
+        assert_eq!(
+            evg_config_utils.get_gen_task_var(&evg_task, "my var"),
+            Some("my value")
+        );
+    }
+
     // get_gen_task_vars tests.
     #[test]
     fn test_get_gen_task_vars_should_return_none_if_no_func() {
@@ -1526,6 +1713,53 @@ mod tests {
         assert_eq!(lookup, Some("build variant value".to_string()));
     }
 
+    #[rstest]
+    #[case(r"${expansion|default}", Some("project value"))]
+    #[case(r"${expansion}", Some("project value"))]
+    #[case(r"${other_expansion|default}", Some("default"))]
+    #[case(r"${other_expansion}", None)]
+    fn test_project_value_should_be_returned_if_expansion_not_in_bv(
+        #[case] run_var: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        let build_variant = BuildVariant {
+            ..Default::default()
+        };
+        let evg_config_utils = EvgConfigUtilsImpl::with_config(
+            vec![GENERATE_RESMOKE_TASKS.to_string()],
+            hashmap! {
+                "expansion".to_string() => "project value".to_string(),
+            },
+            EnterpriseDetection::default(),
+        );
+
+        let lookup = evg_config_utils.translate_run_var(run_var, &build_variant);
+
+        assert_eq!(lookup, expected.map(|v| v.to_string()));
+    }
+
+    #[test]
+    fn test_bv_value_should_take_precedence_over_project_value() {
+        let build_variant = BuildVariant {
+            expansions: Some(btreemap! {
+                "expansion".to_string() => "build variant value".to_string(),
+            }),
+            ..Default::default()
+        };
+        let run_var = r"${expansion|default}";
+        let evg_config_utils = EvgConfigUtilsImpl::with_config(
+            vec![GENERATE_RESMOKE_TASKS.to_string()],
+            hashmap! {
+                "expansion".to_string() => "project value".to_string(),
+            },
+            EnterpriseDetection::default(),
+        );
+
+        let lookup = evg_config_utils.translate_run_var(run_var, &build_variant);
+
+        assert_eq!(lookup, Some("build variant value".to_string()));
+    }
+
     // lookup_build_variant_expansion tests
     #[test]
     fn test_lookup_in_a_build_variant_with_no_expansions_should_return_none() {
@@ -1749,6 +1983,37 @@ mod tests {
         assert!(!evg_config_utils.is_enterprise_build_variant(&build_variant));
     }
 
+    #[rstest]
+    #[case(Some(vec!["enterprise".to_string()]), None, true)]
+    #[case(Some(vec!["enterprise".to_string()]), Some("--enableEnterpriseTests=off"), true)]
+    #[case(Some(vec![]), None, false)]
+    #[case(Some(vec!["Not Enterprise".to_string()]), None, false)]
+    #[case(None, None, true)]
+    #[case(None, Some("--enableEnterpriseTests=off"), false)]
+    fn test_enterprise_detection_by_modules_should_fallback_to_expansions(
+        #[case] modules: Option<Vec<String>>,
+        #[case] test_flag: Option<&str>,
+        #[case] expected: bool,
+    ) {
+        let build_variant = BuildVariant {
+            modules,
+            expansions: test_flag.map(|flag| {
+                BTreeMap::from([("enterprise_test_flag".to_string(), flag.to_string())])
+            }),
+            ..Default::default()
+        };
+        let evg_config_utils = EvgConfigUtilsImpl::with_config(
+            vec![GENERATE_RESMOKE_TASKS.to_string()],
+            HashMap::new(),
+            EnterpriseDetection::Modules,
+        );
+
+        assert_eq!(
+            evg_config_utils.is_enterprise_build_variant(&build_variant),
+            expected
+        );
+    }
+
     // tests for infer_build_variant_platform
     #[rstest]
     #[case(Some(vec!["rhel80-small".to_string()]), "linux".to_string())]
@@ -1756,6 +2021,10 @@ mod tests {
     #[case(Some(vec!["macos-1100".to_string()]), "macos".to_string())]
     #[case(Some(vec!["rhel80-small".to_string(), "macos-1100".to_string()]), "linux".to_string())]
     #[case(Some(vec![]), "linux".to_string())]
+    #[case(Some(vec!["amazon2-arm64-large".to_string()]), "linux-arm64".to_string())]
+    #[case(Some(vec!["macos-1100-arm64".to_string()]), "macos-arm64".to_string())]
+    #[case(Some(vec!["rhel81-power8-small".to_string()]), "linux-ppc".to_string())]
+    #[case(Some(vec!["rhel83-zseries-small".to_string()]), "linux-s390x".to_string())]
     fn test_infer_build_variant_platform(
         #[case] distros: Option<Vec<String>>,
         #[case] platform: String,
@@ -1771,4 +2040,21 @@ mod tests {
             platform
         );
     }
+
+    #[test]
+    fn test_infer_build_variant_platform_should_use_override_expansion() {
+        let build_variant = BuildVariant {
+            run_on: Some(vec!["rhel80-small".to_string()]),
+            expansions: Some(btreemap! {
+                "generated_task_platform".to_string() => "linux-fips".to_string(),
+            }),
+            ..Default::default()
+        };
+        let evg_config_utils = EvgConfigUtilsImpl::new();
+
+        assert_eq!(
+            evg_config_utils.infer_build_variant_platform(&build_variant),
+            "linux-fips".to_string()
+        );
+    }
 }
@@ -0,0 +1,308 @@
+//! Locate the evergreen project configuration to generate tasks for.
+//!
+//! The project configuration can be read from a file, from stdin or from an HTTPS URL. Since
+//! evergreen and burn_in_tests need a file to read the configuration from, configuration read
+//! from stdin or a URL is written to a temporary file.
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use reqwest::Url;
+use serde::Deserialize;
+use tempfile::{Builder, NamedTempFile};
+
+use super::evg_task_history::build_retryable_client;
+
+/// Location that indicates the project configuration should be read from stdin.
+const STDIN_LOCATION: &str = "-";
+/// Prefix of locations that should be fetched over HTTPS.
+const HTTPS_PREFIX: &str = "https://";
+/// Prefix of the temporary file project configuration read from stdin or a URL is written to.
+const PROJECT_CONFIG_FILE_PREFIX: &str = "evergreen_project";
+/// Suffix of the temporary file project configuration read from stdin or a URL is written to.
+const PROJECT_CONFIG_FILE_SUFFIX: &str = ".yml";
+
+/// Where to read evergreen project configuration from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProjectSource {
+    /// Read the configuration from a file.
+    File(PathBuf),
+    /// Read the configuration from stdin.
+    Stdin,
+    /// Fetch the configuration from a URL.
+    Url(String),
+}
+
+impl ProjectSource {
+    /// Determine where to read project configuration from.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` - `-` for stdin, an `https://` URL or a path to a file.
+    ///
+    /// # Returns
+    ///
+    /// Source to read project configuration from.
+    pub fn parse(location: &Path) -> Self {
+        match location.to_str() {
+            Some(STDIN_LOCATION) => ProjectSource::Stdin,
+            Some(url) if url.starts_with(HTTPS_PREFIX) => ProjectSource::Url(url.to_string()),
+            _ => ProjectSource::File(location.to_path_buf()),
+        }
+    }
+}
+
+/// Credentials to authenticate against the evergreen API.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EvgAuth {
+    /// Evergreen user name.
+    pub user: String,
+    /// Evergreen API key.
+    pub api_key: String,
+    /// URL of the evergreen API the credentials belong to.
+    pub api_server_host: Option<String>,
+}
+
+impl EvgAuth {
+    /// Read evergreen credentials from the given auth file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to evergreen auth file.
+    ///
+    /// # Returns
+    ///
+    /// Credentials if the file exists, or an error if it cannot be read or parsed.
+    pub fn from_yaml_file(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read evergreen auth file '{}'", path.display()))?;
+        let auth = serde_yaml::from_str(&contents)
+            .with_context(|| format!("Could not parse evergreen auth file '{}'", path.display()))?;
+        Ok(Some(auth))
+    }
+
+    /// Determine if the credentials should be sent with a request to the given URL.
+    ///
+    /// Credentials are only sent to the host of the configured evergreen API, so fetching a
+    /// project configuration from anywhere else does not leak them.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - URL the request is sent to.
+    ///
+    /// # Returns
+    ///
+    /// true if the URL is served by the configured evergreen API host.
+    pub fn is_for_url(&self, url: &str) -> bool {
+        let api_url = match self.api_server_host.as_deref().map(Url::parse) {
+            Some(Ok(api_url)) => api_url,
+            _ => return false,
+        };
+        match Url::parse(url) {
+            Ok(url) => url.origin() == api_url.origin(),
+            Err(_) => false,
+        }
+    }
+}
+
+/// File containing the evergreen project configuration.
+///
+/// Configuration read from stdin or a URL is kept in a temporary file that is removed when this
+/// is dropped.
+#[derive(Debug)]
+pub struct EvgProjectFile {
+    /// Path to the file.
+    path: PathBuf,
+    /// Temporary file holding configuration read from stdin or a URL.
+    _temp_file: Option<NamedTempFile>,
+}
+
+impl EvgProjectFile {
+    /// Path to the file containing the evergreen project configuration.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Get a file containing the evergreen project configuration at the given location.
+///
+/// # Arguments
+///
+/// * `location` - `-` for stdin, an `https://` URL or a path to a file.
+/// * `evg_auth_file` - File with credentials to use when fetching from a URL.
+///
+/// # Returns
+///
+/// File containing the evergreen project configuration.
+pub async fn resolve_evg_project_file(
+    location: &Path,
+    evg_auth_file: &Path,
+) -> Result<EvgProjectFile> {
+    let contents = match ProjectSource::parse(location) {
+        ProjectSource::File(path) => {
+            return Ok(EvgProjectFile {
+                path,
+                _temp_file: None,
+            })
+        }
+        ProjectSource::Stdin => {
+            let mut contents = String::new();
+            std::io::stdin().read_to_string(&mut contents)?;
+            contents
+        }
+        ProjectSource::Url(url) => fetch_project_config(&url, evg_auth_file).await?,
+    };
+
+    let mut temp_file = Builder::new()
+        .prefix(PROJECT_CONFIG_FILE_PREFIX)
+        .suffix(PROJECT_CONFIG_FILE_SUFFIX)
+        .tempfile()?;
+    temp_file.write_all(contents.as_bytes())?;
+    temp_file.flush()?;
+    Ok(EvgProjectFile {
+        path: temp_file.path().to_path_buf(),
+        _temp_file: Some(temp_file),
+    })
+}
+
+/// Fetch evergreen project configuration from the given URL.
+///
+/// # Arguments
+///
+/// * `url` - URL to fetch configuration from.
+/// * `evg_auth_file` - File with credentials to authenticate with, if it exists and the URL is
+///   served by its evergreen API host.
+///
+/// # Returns
+///
+/// Contents of the project configuration.
+async fn fetch_project_config(url: &str, evg_auth_file: &Path) -> Result<String> {
+    let client = build_retryable_client();
+    let mut request = client.get(url);
+    if let Some(auth) = EvgAuth::from_yaml_file(evg_auth_file)?.filter(|a| a.is_for_url(url)) {
+        request = request
+            .header("Api-User", auth.user)
+            .header("Api-Key", auth.api_key);
+    }
+
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        bail!(
+            "Failed to fetch evergreen project configuration from '{}': {}",
+            url,
+            response.status()
+        );
+    }
+    Ok(response.text().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use tempdir::TempDir;
+
+    use super::*;
+
+    // ProjectSource::parse tests.
+    #[rstest]
+    #[case("-", ProjectSource::Stdin)]
+    #[case(
+        "https://example.com/project.yml",
+        ProjectSource::Url("https://example.com/project.yml".to_string())
+    )]
+    #[case(
+        "etc/evergreen.yml",
+        ProjectSource::File(PathBuf::from("etc/evergreen.yml"))
+    )]
+    #[case(
+        "http://example.com/project.yml",
+        ProjectSource::File(PathBuf::from("http://example.com/project.yml"))
+    )]
+    fn test_project_source_parse(#[case] location: &str, #[case] expected: ProjectSource) {
+        assert_eq!(ProjectSource::parse(Path::new(location)), expected);
+    }
+
+    // EvgAuth tests.
+    #[test]
+    fn test_evg_auth_should_be_none_for_missing_file() {
+        assert!(EvgAuth::from_yaml_file(Path::new("does/not/exist.yml"))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_evg_auth_should_fail_on_invalid_file() {
+        let tmp_dir = TempDir::new("evg_auth").unwrap();
+        let auth_file = tmp_dir.path().join("evergreen.yml");
+        std::fs::write(&auth_file, "user: [not, a, string]").unwrap();
+
+        assert!(EvgAuth::from_yaml_file(&auth_file).is_err());
+    }
+
+    // resolve_evg_project_file tests.
+    #[tokio::test]
+    async fn test_resolve_evg_project_file_should_use_file_locations_directly() {
+        let project_file = resolve_evg_project_file(
+            Path::new("tests/data/evergreen.yml"),
+            Path::new("does/not/exist.yml"),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(project_file.path(), Path::new("tests/data/evergreen.yml"));
+    }
+
+    #[rstest]
+    #[case(
+        Some("https://evergreen.mongodb.com/api"),
+        "https://evergreen.mongodb.com/rest/v2/projects/mongodb-mongo-master/parameters",
+        true
+    )]
+    #[case(
+        Some("https://evergreen.mongodb.com/api"),
+        "https://example.com/project.yml",
+        false
+    )]
+    #[case(
+        Some("https://evergreen.mongodb.com/api"),
+        "https://evergreen.mongodb.com.example.com/project.yml",
+        false
+    )]
+    #[case(
+        Some("https://evergreen.mongodb.com/api"),
+        "https://evergreen.mongodb.com:8443/project.yml",
+        false
+    )]
+    #[case(None, "https://evergreen.mongodb.com/project.yml", false)]
+    #[case(Some("not a url"), "https://evergreen.mongodb.com/project.yml", false)]
+    fn test_evg_auth_is_for_url(
+        #[case] api_server_host: Option<&str>,
+        #[case] url: &str,
+        #[case] expected: bool,
+    ) {
+        let auth = EvgAuth {
+            user: "my.user.name".to_string(),
+            api_key: "myApiKey".to_string(),
+            api_server_host: api_server_host.map(|h| h.to_string()),
+        };
+
+        assert_eq!(auth.is_for_url(url), expected);
+    }
+
+    #[test]
+    fn test_evg_auth_should_read_api_server_host() {
+        let auth = EvgAuth::from_yaml_file(Path::new("tests/data/sample_evergreen_auth.yml"))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            auth.api_server_host.as_deref(),
+            Some("https://evergreen.mongodb.com/api")
+        );
+    }
+}
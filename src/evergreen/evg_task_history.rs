@@ -1,17 +1,114 @@
 //! Lookup the history of evergreen tasks.
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use async_trait::async_trait;
-use reqwest::{Client, Error};
+use rand::Rng;
+use reqwest::{Client, Error, Response, StatusCode};
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use reqwest_retry::policies::ExponentialBackoff;
 use reqwest_retry::RetryTransientMiddleware;
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{OnceCell, Semaphore};
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use crate::error_report::{ErrorCategory, ErrorEntry};
 
 const REQWEST_CLIENT_MAX_RETRY_COUNT: u32 = 3;
 const HOOK_DELIMITER: char = ':';
+const HISTORY_MAX_RETRY_COUNT: u32 = 5;
+const HISTORY_RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+const HISTORY_RETRY_MAX_DELAY: Duration = Duration::from_secs(10);
+const HISTORY_MAX_REQUESTS_PER_SECOND: u32 = 50;
+const HISTORY_MAX_CONCURRENT_FETCHES: usize = 16;
+/// Key under a task's stats that the runtimes of its bazel test targets are stored at.
+const BAZEL_TARGET_STATS_KEY: &str = "bazel_targets";
+/// Default layout of the keys the stats of a task are stored under.
+pub const DEFAULT_HISTORY_KEY_TEMPLATE: &str = "{project}/{variant}/{task}";
+/// Placeholders that can be used in a history key template.
+const HISTORY_KEY_PLACEHOLDERS: &[&str] = &["project", "variant", "task"];
+
+/// Layout of the keys the stats of tasks are stored under.
+///
+/// The template is a path relative to the test stats endpoint in which `{project}`, `{variant}`
+/// and `{task}` are replaced with the evergreen project, build variant and task being queried.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryKeyTemplate(String);
+
+impl HistoryKeyTemplate {
+    /// Build the key of the stats of the given task.
+    ///
+    /// # Arguments
+    ///
+    /// * `project` - Evergreen project to query.
+    /// * `variant` - Name of build variant to query.
+    /// * `task` - Name of task to query.
+    ///
+    /// # Returns
+    ///
+    /// Key the stats of the task are stored under.
+    pub fn expand(&self, project: &str, variant: &str, task: &str) -> String {
+        self.0
+            .replace("{project}", project)
+            .replace("{variant}", variant)
+            .replace("{task}", task)
+    }
+}
+
+impl Display for HistoryKeyTemplate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Default for HistoryKeyTemplate {
+    fn default() -> Self {
+        Self(DEFAULT_HISTORY_KEY_TEMPLATE.to_string())
+    }
+}
+
+impl FromStr for HistoryKeyTemplate {
+    type Err = anyhow::Error;
+
+    fn from_str(template: &str) -> Result<Self> {
+        let mut rest = template;
+        let mut has_task = false;
+        while let Some(start) = rest.find('{') {
+            let end = match rest[start..].find('}') {
+                Some(end) => start + end,
+                None => bail!(
+                    "Unclosed placeholder in history key template '{}'",
+                    template
+                ),
+            };
+            let placeholder = &rest[start + 1..end];
+            if !HISTORY_KEY_PLACEHOLDERS.contains(&placeholder) {
+                bail!(
+                    "Unknown placeholder '{{{}}}' in history key template '{}', expected one of {{{}}}",
+                    placeholder,
+                    template,
+                    HISTORY_KEY_PLACEHOLDERS.join("}, {")
+                );
+            }
+            has_task |= placeholder == "task";
+            rest = &rest[end + 1..];
+        }
+        if !has_task {
+            bail!(
+                "History key template '{}' must contain the {{task}} placeholder",
+                template
+            );
+        }
+
+        Ok(Self(template.trim_matches('/').to_string()))
+    }
+}
 
 /// Test stats stored on S3 bucket.
 #[derive(Debug, Deserialize, Clone)]
@@ -77,6 +174,108 @@ pub struct TaskRuntimeHistory {
     pub test_map: HashMap<String, TestRuntimeHistory>,
 }
 
+/// Counts of the requests made to fetch task history.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryFetchStats {
+    /// Number of requests sent, including retries.
+    pub requests: u64,
+    /// Number of requests that were retried.
+    pub retries: u64,
+    /// Number of task histories that could not be fetched.
+    pub failures: u64,
+}
+
+/// How requests for task history should be retried and rate limited.
+#[derive(Debug, Clone)]
+pub struct HistoryRetryConfig {
+    /// Max number of task histories to fetch at the same time.
+    pub max_concurrent_fetches: usize,
+    /// Max number of times to retry a request.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each following retry.
+    pub base_delay: Duration,
+    /// Max delay between retries.
+    pub max_delay: Duration,
+    /// Max number of requests to send per second across all tasks.
+    pub max_requests_per_second: u32,
+}
+
+impl Default for HistoryRetryConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_fetches: HISTORY_MAX_CONCURRENT_FETCHES,
+            max_retries: HISTORY_MAX_RETRY_COUNT,
+            base_delay: HISTORY_RETRY_BASE_DELAY,
+            max_delay: HISTORY_RETRY_MAX_DELAY,
+            max_requests_per_second: HISTORY_MAX_REQUESTS_PER_SECOND,
+        }
+    }
+}
+
+impl HistoryRetryConfig {
+    /// Get the delay to wait before the given retry.
+    ///
+    /// The delay doubles with each retry up to the max delay. A random jitter of up to half the
+    /// delay is applied so that requests throttled together do not retry together.
+    ///
+    /// # Arguments
+    ///
+    /// * `retry` - Number of the retry, starting at 0.
+    ///
+    /// # Returns
+    ///
+    /// Time to wait before retrying.
+    fn retry_delay(&self, retry: u32) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(2_u32.saturating_pow(retry))
+            .min(self.max_delay);
+        let half = backoff / 2;
+        half + half.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+    }
+}
+
+/// Limit the rate requests are sent at across all callers.
+#[derive(Debug)]
+struct RateLimiter {
+    /// Minimum time between requests.
+    interval: Duration,
+    /// Earliest time the next request can be sent.
+    next_slot: tokio::sync::Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Create a rate limiter allowing the given number of requests per second.
+    fn new(max_requests_per_second: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / max_requests_per_second.max(1),
+            next_slot: tokio::sync::Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Wait until a request can be sent.
+    async fn acquire(&self) {
+        let slot = {
+            let mut next_slot = self.next_slot.lock().await;
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+/// Result of fetching a task history, shared between everyone looking it up.
+type CachedHistory = std::result::Result<TaskRuntimeHistory, String>;
+
+/// Project, task and build variant a task history is fetched for.
+type HistoryKey = (String, String, String);
+
+/// Check if a request that received the given status should be retried.
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
 /// A service for querying task history from evergreen.
 #[async_trait]
 pub trait TaskHistoryService: Send + Sync {
@@ -103,6 +302,32 @@ pub trait TaskHistoryService: Send + Sync {
     ///
     /// The runtime history of tests belonging to the given suite on the given build variant.
     async fn get_task_history(&self, task: &str, variant: &str) -> Result<TaskRuntimeHistory>;
+
+    /// Get the runtime history of the bazel test targets run by the given task.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - Name of task to query.
+    /// * `variant` - Name of build variant to query.
+    ///
+    /// # Returns
+    ///
+    /// Map of bazel test targets to their average runtime.
+    async fn get_bazel_target_history(
+        &self,
+        task: &str,
+        variant: &str,
+    ) -> Result<HashMap<String, f64>>;
+
+    /// Fetch the runtime history of the given tasks so later lookups can be served from a cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_variants` - Names of tasks and the build variants to query them on.
+    async fn prefetch_task_histories(&self, task_variants: Vec<(String, String)>);
+
+    /// Get counts of the requests made to fetch task history.
+    fn fetch_stats(&self) -> HistoryFetchStats;
 }
 
 /// An implementation of the task history service.
@@ -113,6 +338,26 @@ pub struct TaskHistoryServiceImpl {
     s3_test_stats_endpoint: String,
     /// Evergreen project to query.
     evg_project: String,
+    /// Layout of the keys the stats of tasks are stored under.
+    key_template: HistoryKeyTemplate,
+    /// Build variants to borrow the history of, by the build variant borrowing it.
+    variant_aliases: HashMap<String, String>,
+    /// Evergreen project to query when the history of a task is missing in `evg_project`.
+    fallback_project: Option<String>,
+    /// How requests should be retried.
+    retry_config: HistoryRetryConfig,
+    /// Limiter for the rate of requests.
+    rate_limiter: RateLimiter,
+    /// Limiter for the number of task histories fetched at the same time.
+    fetch_permits: Semaphore,
+    /// Task histories fetched or being fetched, by project, task and build variant.
+    history_cache: std::sync::Mutex<HashMap<HistoryKey, Arc<OnceCell<CachedHistory>>>>,
+    /// Number of requests sent.
+    requests: AtomicU64,
+    /// Number of requests retried.
+    retries: AtomicU64,
+    /// Number of task histories that could not be fetched.
+    failures: AtomicU64,
 }
 
 impl TaskHistoryServiceImpl {
@@ -123,6 +368,11 @@ impl TaskHistoryServiceImpl {
     /// * `client` - Reqwest client.
     /// * `s3_test_stats_endpoint` - S3 endpoint to get test stats from.
     /// * `evg_project` - Evergreen project to query.
+    /// * `key_template` - Layout of the keys the stats of tasks are stored under.
+    /// * `variant_aliases` - Build variants to borrow the history of, by the build variant
+    ///   borrowing it.
+    /// * `fallback_project` - Evergreen project to query when the history of a task is missing.
+    /// * `retry_config` - How requests should be retried and rate limited.
     ///
     /// # Returns
     ///
@@ -131,12 +381,220 @@ impl TaskHistoryServiceImpl {
         client: ClientWithMiddleware,
         s3_test_stats_endpoint: String,
         evg_project: String,
+        key_template: HistoryKeyTemplate,
+        variant_aliases: HashMap<String, String>,
+        fallback_project: Option<String>,
+        retry_config: HistoryRetryConfig,
     ) -> Self {
+        let rate_limiter = RateLimiter::new(retry_config.max_requests_per_second);
+        let fetch_permits = Semaphore::new(retry_config.max_concurrent_fetches.max(1));
         Self {
             client,
             s3_test_stats_endpoint,
             evg_project,
+            key_template,
+            variant_aliases,
+            fallback_project,
+            retry_config,
+            rate_limiter,
+            fetch_permits,
+            history_cache: std::sync::Mutex::new(HashMap::new()),
+            requests: AtomicU64::new(0),
+            retries: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+        }
+    }
+
+    /// Fetch and parse the test runtime history of the given task.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - Name of task to query.
+    /// * `url` - URL to fetch history from.
+    ///
+    /// # Returns
+    ///
+    /// The runtime history of tests belonging to the given task.
+    async fn fetch_task_history(&self, task: &str, url: &str) -> Result<TaskRuntimeHistory> {
+        let response = self.send_with_retry(url).await?;
+        let stats: Result<Vec<S3TestStats>, Error> = Ok(response
+            .json::<Vec<S3TestStats>>()
+            .await
+            .context(ErrorCategory::HistoryFetch)?);
+
+        if let Ok(stats) = stats {
+            Ok(task_history_from_stats(task, &stats))
+        } else {
+            Err(anyhow!("Error from S3: {:?}", stats)).context(ErrorCategory::HistoryFetch)
+        }
+    }
+
+    /// Send a request for the given URL, retrying on throttling and transient errors.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - URL to send request to.
+    ///
+    /// # Returns
+    ///
+    /// Response to the request.
+    async fn send_with_retry(&self, url: &str) -> Result<Response> {
+        let mut retry = 0;
+        loop {
+            self.rate_limiter.acquire().await;
+            self.requests.fetch_add(1, Ordering::Relaxed);
+            let retry_reason = match self.client.get(url).send().await {
+                Ok(response) if !is_retryable_status(response.status()) => return Ok(response),
+                Ok(response) => response.status().to_string(),
+                Err(err) => err.to_string(),
+            };
+            if retry >= self.retry_config.max_retries {
+                return Err(anyhow!(
+                    "Failed to fetch '{}' after {} retries: {}",
+                    url,
+                    retry,
+                    retry_reason
+                ))
+                .context(ErrorCategory::HistoryFetch);
+            }
+
+            let delay = self.retry_config.retry_delay(retry);
+            warn!(
+                url = url,
+                reason = retry_reason.as_str(),
+                delay_ms = delay.as_millis() as u64,
+                "Retrying task history request",
+            );
+            self.retries.fetch_add(1, Ordering::Relaxed);
+            retry += 1;
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Get the projects and build variants to look up the history of tasks on a build variant in.
+    ///
+    /// The build variant is looked up before its alias, and the project being generated before
+    /// the fallback project.
+    ///
+    /// # Arguments
+    ///
+    /// * `variant` - Name of build variant to query.
+    ///
+    /// # Returns
+    ///
+    /// Pairs of project and build variant to query, in order.
+    fn history_sources<'a>(&'a self, variant: &'a str) -> Vec<(&'a str, &'a str)> {
+        let variants: Vec<&str> = std::iter::once(variant)
+            .chain(
+                self.variant_aliases
+                    .get(variant)
+                    .map(|alias| alias.as_str()),
+            )
+            .collect();
+        std::iter::once(self.evg_project.as_str())
+            .chain(self.fallback_project.as_deref())
+            .flat_map(|project| variants.iter().map(move |variant| (project, *variant)))
+            .collect()
+    }
+
+    /// Build URL to fetch the history of a task in the given project from.
+    ///
+    /// # Arguments
+    ///
+    /// * `project` - Evergreen project to query.
+    /// * `task` - Name of task to query.
+    /// * `variant` - Name of build variant to query.
+    ///
+    /// # Returns
+    ///
+    /// URL to send request to.
+    fn build_project_url(&self, project: &str, task: &str, variant: &str) -> String {
+        format!(
+            "{}/{}",
+            self.s3_test_stats_endpoint,
+            self.key_template.expand(project, variant, task)
+        )
+    }
+
+    /// Get the test runtime history of the given task, fetching it only once.
+    ///
+    /// # Arguments
+    ///
+    /// * `project` - Evergreen project to query.
+    /// * `task` - Name of task to query.
+    /// * `variant` - Name of build variant to query.
+    ///
+    /// # Returns
+    ///
+    /// The runtime history of tests belonging to the given suite on the given build variant.
+    async fn get_cached_task_history(
+        &self,
+        project: &str,
+        task: &str,
+        variant: &str,
+    ) -> Result<TaskRuntimeHistory> {
+        let cell = {
+            let mut history_cache = self.history_cache.lock().unwrap();
+            history_cache
+                .entry((project.to_string(), task.to_string(), variant.to_string()))
+                .or_default()
+                .clone()
+        };
+
+        let cached_history = cell
+            .get_or_init(|| async {
+                let _permit = self.fetch_permits.acquire().await.unwrap();
+                let url = self.build_project_url(project, task, variant);
+                let result = self.fetch_task_history(task, &url).await;
+                if result.is_err() {
+                    self.failures.fetch_add(1, Ordering::Relaxed);
+                }
+                result.map_err(|err| ErrorEntry::from_error(&err).message)
+            })
+            .await;
+
+        match cached_history {
+            Ok(task_history) => Ok(task_history.clone()),
+            Err(err) => Err(anyhow!("{}", err)).context(ErrorCategory::HistoryFetch),
+        }
+    }
+
+    /// Fetch the runtime history of the bazel test targets run by the given task.
+    ///
+    /// # Arguments
+    ///
+    /// * `project` - Evergreen project to query.
+    /// * `task` - Name of task to query.
+    /// * `variant` - Name of build variant to query.
+    ///
+    /// # Returns
+    ///
+    /// Map of bazel test targets to their average runtime.
+    async fn fetch_bazel_target_history(
+        &self,
+        project: &str,
+        task: &str,
+        variant: &str,
+    ) -> Result<HashMap<String, f64>> {
+        let _permit = self.fetch_permits.acquire().await.unwrap();
+        let url = format!(
+            "{}/{}",
+            self.build_project_url(project, task, variant),
+            BAZEL_TARGET_STATS_KEY
+        );
+        let result = async {
+            let response = self.send_with_retry(&url).await?;
+            let stats = response
+                .json::<Vec<S3TestStats>>()
+                .await
+                .context(ErrorCategory::HistoryFetch)?;
+            Ok(bazel_target_runtimes(&stats))
+        }
+        .await;
+        if result.is_err() {
+            self.failures.fetch_add(1, Ordering::Relaxed);
         }
+        result
     }
 }
 
@@ -153,14 +611,14 @@ impl TaskHistoryService for TaskHistoryServiceImpl {
     ///
     /// URL to send request to.
     fn build_url(&self, task: &str, variant: &str) -> String {
-        format!(
-            "{}/{}/{}/{}",
-            self.s3_test_stats_endpoint, self.evg_project, variant, task
-        )
+        self.build_project_url(&self.evg_project, task, variant)
     }
 
     /// Get the test runtime history of the given task.
     ///
+    /// If the build variant has no history, the history of the task on its aliased build variant
+    /// and then in the fallback project is used instead.
+    ///
     /// # Arguments
     ///
     /// * `task` - Name of task to query.
@@ -170,27 +628,106 @@ impl TaskHistoryService for TaskHistoryServiceImpl {
     ///
     /// The runtime history of tests belonging to the given suite on the given build variant.
     async fn get_task_history(&self, task: &str, variant: &str) -> Result<TaskRuntimeHistory> {
-        let url = self.build_url(task, variant);
-        let response = self.client.get(url).send().await?;
-        let stats: Result<Vec<S3TestStats>, Error> =
-            Ok(response.json::<Vec<S3TestStats>>().await?);
+        let sources = self.history_sources(variant);
+        let (project, history_variant) = sources[0];
+        let result = self
+            .get_cached_task_history(project, task, history_variant)
+            .await;
+        for (project, history_variant) in sources.into_iter().skip(1) {
+            if matches!(&result, Ok(task_history) if !task_history.test_map.is_empty()) {
+                break;
+            }
+            if let Ok(task_history) = self
+                .get_cached_task_history(project, task, history_variant)
+                .await
+            {
+                if !task_history.test_map.is_empty() {
+                    info!(
+                        task_name = task,
+                        build_variant = variant,
+                        history_project = project,
+                        history_build_variant = history_variant,
+                        "Borrowing task history",
+                    );
+                    return Ok(task_history);
+                }
+            }
+        }
+        result
+    }
 
-        if let Ok(stats) = stats {
-            // Split the returned stats into stats for hooks and tests. Also attach the hook stats
-            // to the test that they ran with.
-            let hook_map = gather_hook_stats(&stats);
-            let test_map = gather_test_stats(&stats, &hook_map);
-
-            Ok(TaskRuntimeHistory {
-                task_name: task.to_string(),
-                test_map,
-            })
-        } else {
-            bail!("Error from S3: {:?}", stats)
+    /// Get the runtime history of the bazel test targets run by the given task.
+    ///
+    /// If the build variant has no history, the history of the task on its aliased build variant
+    /// and then in the fallback project is used instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - Name of task to query.
+    /// * `variant` - Name of build variant to query.
+    ///
+    /// # Returns
+    ///
+    /// Map of bazel test targets to their average runtime.
+    async fn get_bazel_target_history(
+        &self,
+        task: &str,
+        variant: &str,
+    ) -> Result<HashMap<String, f64>> {
+        let sources = self.history_sources(variant);
+        let (project, history_variant) = sources[0];
+        let result = self
+            .fetch_bazel_target_history(project, task, history_variant)
+            .await;
+        for (project, history_variant) in sources.into_iter().skip(1) {
+            if matches!(&result, Ok(target_runtimes) if !target_runtimes.is_empty()) {
+                break;
+            }
+            if let Ok(target_runtimes) = self
+                .fetch_bazel_target_history(project, task, history_variant)
+                .await
+            {
+                if !target_runtimes.is_empty() {
+                    return Ok(target_runtimes);
+                }
+            }
+        }
+        result
+    }
+
+    /// Fetch the runtime history of the given tasks so later lookups can be served from a cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_variants` - Names of tasks and the build variants to query them on.
+    async fn prefetch_task_histories(&self, task_variants: Vec<(String, String)>) {
+        futures::future::join_all(
+            task_variants
+                .iter()
+                .map(|(task, variant)| self.get_task_history(task, variant)),
+        )
+        .await;
+    }
+
+    /// Get counts of the requests made to fetch task history.
+    fn fetch_stats(&self) -> HistoryFetchStats {
+        HistoryFetchStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
         }
     }
 }
 
+/// Build reqwest client without retries, for callers that handle retries themselves.
+///
+/// # Returns
+///
+/// Reqwest client.
+pub fn build_client() -> ClientWithMiddleware {
+    ClientBuilder::new(Client::new()).build()
+}
+
 /// Build retryable reqwest client.
 ///
 /// # Returns
@@ -204,6 +741,47 @@ pub fn build_retryable_client() -> ClientWithMiddleware {
         .build()
 }
 
+/// Build the runtime history of a task from the test stats stored for it.
+///
+/// # Arguments
+///
+/// * `task` - Name of task the stats belong to.
+/// * `stat_list` - List of stats for tests and hooks that ran in the task.
+///
+/// # Returns
+///
+/// Runtime history of the task.
+pub(crate) fn task_history_from_stats(task: &str, stat_list: &[S3TestStats]) -> TaskRuntimeHistory {
+    // Split the stats into stats for hooks and tests. Also attach the hook stats to the test
+    // that they ran with.
+    let hook_map = gather_hook_stats(stat_list);
+    let test_map = gather_test_stats(stat_list, &hook_map);
+
+    TaskRuntimeHistory {
+        task_name: task.to_string(),
+        test_map,
+    }
+}
+
+/// Build the runtimes of bazel test targets from the stats stored for them.
+///
+/// Target names are used as is, since bazel labels contain the delimiter used for hooks.
+///
+/// # Arguments
+///
+/// * `stat_list` - List of stats for bazel test targets.
+///
+/// # Returns
+///
+/// Map of bazel test targets to their average runtime.
+pub(crate) fn bazel_target_runtimes(stat_list: &[S3TestStats]) -> HashMap<String, f64> {
+    let mut target_runtimes: HashMap<String, f64> = HashMap::new();
+    for stat in stat_list {
+        *target_runtimes.entry(stat.test_name.clone()).or_default() += stat.avg_duration_pass;
+    }
+    target_runtimes
+}
+
 /// Convert the list of stats into a map of test names to test stats.
 ///
 /// Also include hook information for all tests with their stats.
@@ -360,6 +938,36 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_bazel_target_runtimes_should_keep_target_labels() {
+        let stats = vec![
+            S3TestStats {
+                test_name: "//src/mongo/db:db_test".to_string(),
+                num_pass: 1,
+                num_fail: 0,
+                avg_duration_pass: 10.0,
+            },
+            S3TestStats {
+                test_name: "//src/mongo/db:db_test".to_string(),
+                num_pass: 1,
+                num_fail: 0,
+                avg_duration_pass: 5.0,
+            },
+            S3TestStats {
+                test_name: "//src/mongo/s:s_test".to_string(),
+                num_pass: 1,
+                num_fail: 0,
+                avg_duration_pass: 3.0,
+            },
+        ];
+
+        let target_runtimes = bazel_target_runtimes(&stats);
+
+        assert_eq!(target_runtimes.len(), 2);
+        assert_eq!(target_runtimes["//src/mongo/db:db_test"], 15.0);
+        assert_eq!(target_runtimes["//src/mongo/s:s_test"], 3.0);
+    }
+
     #[rstest]
     #[case("some/random/test", false)]
     #[case("some/random/test:hook1", true)]
@@ -367,6 +975,206 @@ mod tests {
         assert_eq!(is_hook(hook_name), expected_is_hook);
     }
 
+    // HistoryRetryConfig tests.
+    #[rstest]
+    #[case(0, 100, 200)]
+    #[case(1, 200, 400)]
+    #[case(2, 400, 800)]
+    #[case(5, 500, 1000)]
+    fn test_retry_delay_should_back_off_with_jitter(
+        #[case] retry: u32,
+        #[case] min_ms: u64,
+        #[case] max_ms: u64,
+    ) {
+        let retry_config = HistoryRetryConfig {
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_millis(1000),
+            ..Default::default()
+        };
+
+        let delay = retry_config.retry_delay(retry);
+
+        assert!(delay >= Duration::from_millis(min_ms));
+        assert!(delay <= Duration::from_millis(max_ms));
+    }
+
+    // get_task_history tests.
+    #[tokio::test]
+    async fn test_get_task_history_should_fetch_each_task_once() {
+        let task_history_service = TaskHistoryServiceImpl::new(
+            build_client(),
+            "http://127.0.0.1:1".to_string(),
+            "my_project".to_string(),
+            HistoryKeyTemplate::default(),
+            HashMap::new(),
+            None,
+            HistoryRetryConfig {
+                max_retries: 0,
+                ..Default::default()
+            },
+        );
+
+        task_history_service
+            .prefetch_task_histories(vec![
+                ("task_0".to_string(), "bv_0".to_string()),
+                ("task_0".to_string(), "bv_0".to_string()),
+                ("task_0".to_string(), "bv_1".to_string()),
+            ])
+            .await;
+        let result = task_history_service
+            .get_task_history("task_0", "bv_0")
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(
+            task_history_service.fetch_stats(),
+            HistoryFetchStats {
+                requests: 2,
+                retries: 0,
+                failures: 2,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_task_history_should_borrow_history_of_aliased_variant() {
+        let task_history_service = TaskHistoryServiceImpl::new(
+            build_client(),
+            "http://127.0.0.1:1".to_string(),
+            "my_project".to_string(),
+            HistoryKeyTemplate::default(),
+            HashMap::from([("bv_new".to_string(), "bv_0".to_string())]),
+            None,
+            HistoryRetryConfig {
+                max_retries: 0,
+                ..Default::default()
+            },
+        );
+
+        let result = task_history_service
+            .get_task_history("task_0", "bv_new")
+            .await;
+        task_history_service
+            .get_task_history("task_0", "bv_0")
+            .await
+            .unwrap_err();
+
+        assert!(result.is_err());
+        assert_eq!(
+            task_history_service.fetch_stats(),
+            HistoryFetchStats {
+                requests: 2,
+                retries: 0,
+                failures: 2,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_task_history_should_query_fallback_project() {
+        let task_history_service = TaskHistoryServiceImpl::new(
+            build_client(),
+            "http://127.0.0.1:1".to_string(),
+            "my_project-v8.0".to_string(),
+            HistoryKeyTemplate::default(),
+            HashMap::new(),
+            Some("my_project".to_string()),
+            HistoryRetryConfig {
+                max_retries: 0,
+                ..Default::default()
+            },
+        );
+
+        let result = task_history_service
+            .get_task_history("task_0", "bv_0")
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(task_history_service.fetch_stats().requests, 2);
+    }
+
+    // history_sources tests.
+    #[rstest]
+    #[case("bv_0", None, vec![("my_project-v8.0", "bv_0")])]
+    #[case("bv_new", None, vec![("my_project-v8.0", "bv_new"), ("my_project-v8.0", "bv_0")])]
+    #[case("bv_0", Some("my_project"), vec![("my_project-v8.0", "bv_0"), ("my_project", "bv_0")])]
+    #[case("bv_new", Some("my_project"), vec![
+        ("my_project-v8.0", "bv_new"),
+        ("my_project-v8.0", "bv_0"),
+        ("my_project", "bv_new"),
+        ("my_project", "bv_0"),
+    ])]
+    fn test_history_sources_should_query_aliases_before_fallback_project(
+        #[case] variant: &str,
+        #[case] fallback_project: Option<&str>,
+        #[case] expected_sources: Vec<(&str, &str)>,
+    ) {
+        let task_history_service = TaskHistoryServiceImpl::new(
+            build_client(),
+            "http://127.0.0.1:1".to_string(),
+            "my_project-v8.0".to_string(),
+            HistoryKeyTemplate::default(),
+            HashMap::from([("bv_new".to_string(), "bv_0".to_string())]),
+            fallback_project.map(|project| project.to_string()),
+            HistoryRetryConfig::default(),
+        );
+
+        assert_eq!(
+            task_history_service.history_sources(variant),
+            expected_sources
+        );
+    }
+
+    // HistoryKeyTemplate tests.
+    #[rstest]
+    #[case(DEFAULT_HISTORY_KEY_TEMPLATE, "my_project/bv_0/task_0")]
+    #[case("stats/{task}/{variant}.json", "stats/task_0/bv_0.json")]
+    #[case("/prefix/{project}-{variant}/{task}/", "prefix/my_project-bv_0/task_0")]
+    fn test_history_key_template_should_expand_placeholders(
+        #[case] template: &str,
+        #[case] expected_key: &str,
+    ) {
+        let key_template = HistoryKeyTemplate::from_str(template).unwrap();
+
+        assert_eq!(
+            key_template.expand("my_project", "bv_0", "task_0"),
+            expected_key
+        );
+    }
+
+    #[rstest]
+    #[case("{project}/{variant}")]
+    #[case("{project}/{build_variant}/{task}")]
+    #[case("{project}/{task")]
+    fn test_history_key_template_should_reject_invalid_templates(#[case] template: &str) {
+        assert!(HistoryKeyTemplate::from_str(template).is_err());
+    }
+
+    // is_retryable_status tests.
+    #[rstest]
+    #[case(StatusCode::TOO_MANY_REQUESTS, true)]
+    #[case(StatusCode::SERVICE_UNAVAILABLE, true)]
+    #[case(StatusCode::INTERNAL_SERVER_ERROR, true)]
+    #[case(StatusCode::FORBIDDEN, false)]
+    #[case(StatusCode::NOT_FOUND, false)]
+    #[case(StatusCode::OK, false)]
+    fn test_is_retryable_status(#[case] status: StatusCode, #[case] expected: bool) {
+        assert_eq!(is_retryable_status(status), expected);
+    }
+
+    // RateLimiter tests.
+    #[tokio::test]
+    async fn test_rate_limiter_should_space_out_requests() {
+        let rate_limiter = RateLimiter::new(100);
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            rate_limiter.acquire().await;
+        }
+
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
     #[test]
     fn test_hook_test_name() {
         assert_eq!(hook_test_name("my_test:my_hook"), "my_test");
@@ -0,0 +1,244 @@
+//! Test runtimes provided locally that take precedence over the history stored on S3.
+//!
+//! When the runtime history of a test is obviously wrong, for example because the test was
+//! recently optimized, the runtime can be corrected in a JSON file mapping task names to the
+//! runtimes of their tests:
+//!
+//! ```json
+//! {
+//!   "auth": {
+//!     "jstests/auth/repl.js": 120.0
+//!   }
+//! }
+//! ```
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use tracing::error;
+
+use super::evg_task_history::{
+    get_test_name, HistoryFetchStats, TaskHistoryService, TaskRuntimeHistory, TestRuntimeHistory,
+};
+
+/// Runtimes of tests to use in place of their runtime history, by task name.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ExtraTestStats(HashMap<String, HashMap<String, f64>>);
+
+impl ExtraTestStats {
+    /// Read the extra test stats from the given JSON file.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` - Path to JSON file containing the test runtimes.
+    ///
+    /// # Returns
+    ///
+    /// Extra test stats read from the file.
+    pub fn from_json_file<P: AsRef<Path>>(location: P) -> Result<Self> {
+        let contents = std::fs::read_to_string(&location)?;
+
+        let extra_test_stats: Result<Self, serde_json::Error> = serde_json::from_str(&contents);
+        if extra_test_stats.is_err() {
+            error!(
+                file = location.as_ref().display().to_string(),
+                contents = &contents,
+                "Failed to parse json for ExtraTestStats from file",
+            );
+        }
+        Ok(extra_test_stats?)
+    }
+
+    /// Merge the extra runtimes of the given task over its runtime history.
+    ///
+    /// Tests with an extra runtime keep the hooks of their runtime history, but use the extra
+    /// runtime. Tests not in the runtime history are added.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - Name of task the history belongs to.
+    /// * `task_history` - Runtime history of the task, if any could be found.
+    ///
+    /// # Returns
+    ///
+    /// Runtime history of the task with the extra runtimes applied, or None if there is neither.
+    pub fn merge_into(
+        &self,
+        task: &str,
+        task_history: Option<TaskRuntimeHistory>,
+    ) -> Option<TaskRuntimeHistory> {
+        let extra_runtimes = match self.0.get(task) {
+            Some(extra_runtimes) => extra_runtimes,
+            None => return task_history,
+        };
+        let mut task_history = task_history.unwrap_or_else(|| TaskRuntimeHistory {
+            task_name: task.to_string(),
+            test_map: HashMap::new(),
+        });
+        for (test_file, runtime) in extra_runtimes {
+            task_history
+                .test_map
+                .entry(get_test_name(test_file))
+                .and_modify(|test_history| test_history.average_runtime = *runtime)
+                .or_insert_with(|| TestRuntimeHistory {
+                    test_name: test_file.clone(),
+                    average_runtime: *runtime,
+                    hooks: vec![],
+                });
+        }
+
+        Some(task_history)
+    }
+}
+
+/// A task history service that applies extra test stats over the history of another service.
+pub struct ExtraTestStatsHistoryService {
+    /// Service to look up the runtime history of tasks.
+    task_history_service: Arc<dyn TaskHistoryService>,
+    /// Runtimes of tests that take precedence over their runtime history.
+    extra_test_stats: ExtraTestStats,
+}
+
+impl ExtraTestStatsHistoryService {
+    /// Create a new instance of the service.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_history_service` - Service to look up the runtime history of tasks.
+    /// * `extra_test_stats` - Runtimes of tests that take precedence over their runtime history.
+    ///
+    /// # Returns
+    ///
+    /// New instance of the service.
+    pub fn new(
+        task_history_service: Arc<dyn TaskHistoryService>,
+        extra_test_stats: ExtraTestStats,
+    ) -> Self {
+        Self {
+            task_history_service,
+            extra_test_stats,
+        }
+    }
+}
+
+#[async_trait]
+impl TaskHistoryService for ExtraTestStatsHistoryService {
+    /// Build URL to send request to.
+    fn build_url(&self, task: &str, variant: &str) -> String {
+        self.task_history_service.build_url(task, variant)
+    }
+
+    /// Get the test runtime history of the given task with the extra test stats applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - Name of task to query.
+    /// * `variant` - Name of build variant to query.
+    ///
+    /// # Returns
+    ///
+    /// The runtime history of tests belonging to the given suite on the given build variant.
+    async fn get_task_history(&self, task: &str, variant: &str) -> Result<TaskRuntimeHistory> {
+        let result = self
+            .task_history_service
+            .get_task_history(task, variant)
+            .await;
+        match self
+            .extra_test_stats
+            .merge_into(task, result.as_ref().ok().cloned())
+        {
+            Some(task_history) => Ok(task_history),
+            None => result,
+        }
+    }
+
+    /// Get the runtime history of the bazel test targets run by the given task.
+    async fn get_bazel_target_history(
+        &self,
+        task: &str,
+        variant: &str,
+    ) -> Result<HashMap<String, f64>> {
+        self.task_history_service
+            .get_bazel_target_history(task, variant)
+            .await
+    }
+
+    /// Fetch the runtime history of the given tasks so later lookups can be served from a cache.
+    async fn prefetch_task_histories(&self, task_variants: Vec<(String, String)>) {
+        self.task_history_service
+            .prefetch_task_histories(task_variants)
+            .await
+    }
+
+    /// Get counts of the requests made to fetch task history.
+    fn fetch_stats(&self) -> HistoryFetchStats {
+        self.task_history_service.fetch_stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::hashmap;
+
+    use crate::evergreen::evg_task_history::HookRuntimeHistory;
+
+    use super::*;
+
+    fn build_extra_test_stats() -> ExtraTestStats {
+        ExtraTestStats(hashmap! {
+            "auth".to_string() => hashmap! {
+                "jstests/auth/test_0.js".to_string() => 5.0,
+                "jstests/auth/test_new.js".to_string() => 20.0,
+            },
+        })
+    }
+
+    #[test]
+    fn test_merge_into_should_override_runtimes_and_keep_hooks() {
+        let task_history = TaskRuntimeHistory {
+            task_name: "auth".to_string(),
+            test_map: hashmap! {
+                "test_0".to_string() => TestRuntimeHistory {
+                    test_name: "jstests/auth/test_0.js".to_string(),
+                    average_runtime: 100.0,
+                    hooks: vec![HookRuntimeHistory {
+                        test_name: "test_0".to_string(),
+                        hook_name: "CheckReplDBHash".to_string(),
+                        average_runtime: 3.0,
+                    }],
+                },
+                "test_1".to_string() => TestRuntimeHistory {
+                    test_name: "jstests/auth/test_1.js".to_string(),
+                    average_runtime: 10.0,
+                    hooks: vec![],
+                },
+            },
+        };
+
+        let merged = build_extra_test_stats()
+            .merge_into("auth", Some(task_history))
+            .unwrap();
+
+        assert_eq!(merged.test_map.len(), 3);
+        assert_eq!(merged.test_map["test_0"].average_runtime, 5.0);
+        assert_eq!(merged.test_map["test_0"].hooks.len(), 1);
+        assert_eq!(merged.test_map["test_1"].average_runtime, 10.0);
+        assert_eq!(merged.test_map["test_new"].average_runtime, 20.0);
+    }
+
+    #[test]
+    fn test_merge_into_should_only_change_tasks_with_extra_stats() {
+        let extra_test_stats = build_extra_test_stats();
+
+        assert!(extra_test_stats.merge_into("core", None).is_none());
+        assert_eq!(
+            extra_test_stats
+                .merge_into("auth", None)
+                .unwrap()
+                .test_map
+                .len(),
+            2
+        );
+    }
+}
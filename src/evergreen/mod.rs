@@ -1,3 +1,5 @@
 pub mod evg_config;
 pub mod evg_config_utils;
+pub mod evg_project_source;
 pub mod evg_task_history;
+pub mod extra_test_stats;
@@ -17,6 +17,14 @@ pub const SETUP_JSTESTFUZZ: &str = "setup jstestfuzz";
 pub const RUN_FUZZER: &str = "run jstestfuzz";
 /// Function to run generated tasks.
 pub const RUN_GENERATED_TESTS: &str = "run generated tests";
+/// Function to run generated tasks as bazel test targets.
+pub const RUN_BAZEL_TESTS: &str = "run bazel tests";
+
+// Functions for antithesis tasks.
+/// Function to build the docker-compose configuration for an antithesis suite.
+pub const ANTITHESIS_BUILD_DOCKER_COMPOSE: &str = "antithesis build docker compose";
+/// Function to push antithesis images.
+pub const ANTITHESIS_IMAGE_PUSH: &str = "antithesis image push";
 
 // Function for multi-version tests.
 /// Function to do setup for multi-version testing.
@@ -48,16 +56,48 @@ pub const BURN_IN_TASKS: &str = "burn_in_tasks_gen";
 // Vars
 /// Variable that indicates a task is a fuzzer.
 pub const IS_FUZZER: &str = "is_jstestfuzz";
+/// Variable that indicates a task is a differential fuzzer.
+pub const IS_DIFFERENTIAL_FUZZER: &str = "is_differential_fuzzer";
+/// Variables that indicate a task is a fuzzer of any kind.
+pub const FUZZER_TASK_VARS: [&str; 2] = [IS_FUZZER, IS_DIFFERENTIAL_FUZZER];
+/// Variable that indicates a task generates antithesis images.
+pub const IS_ANTITHESIS: &str = "is_antithesis";
+/// Variable that indicates a task runs shards of bazel test targets.
+pub const IS_BAZEL_TEST_SHARDS: &str = "is_bazel_test_shards";
+/// Bazel query selecting the test targets to shard.
+pub const BAZEL_TARGETS_QUERY: &str = "bazel_targets_query";
+/// Number of shards to split bazel test targets into.
+pub const NUM_BAZEL_SHARDS: &str = "num_shards";
+/// List of suites delimited by spaces to generate antithesis images for.
+pub const ANTITHESIS_SUITES: &str = "antithesis_suites";
 /// If true, generate sub-tasks to run on large distros.
 pub const USE_LARGE_DISTRO: &str = "use_large_distro";
 /// If true, generate sub-tasks to run on large distros.
 pub const USE_XLARGE_DISTRO: &str = "use_xlarge_distro";
+/// If true, activate generated sub-tasks instead of requiring them to be scheduled manually.
+pub const ACTIVATE_GENERATED_TASKS: &str = "activate_generated_tasks";
+/// Priority to give generated sub-tasks, overriding the priority of the task definition.
+pub const TASK_PRIORITY: &str = "priority_override";
+/// Build variant to attach generated sub-tasks to instead of the declaring build variant.
+pub const RUN_ON_VARIANT: &str = "run_on_variant";
 /// Number of files that each fuzzer sub-task should generate.
 pub const NUM_FUZZER_FILES: &str = "num_files";
 /// Number of sub-tasks that should be generated for a fuzzer.
 pub const NUM_FUZZER_TASKS: &str = "num_tasks";
 /// Tag to exclude multiversion version.
 pub const MULTIVERSION_EXCLUDE_TAG: &str = "multiversion_exclude_tags_version";
+/// If true, assign tests to sub-suites by consistent hashing of their paths.
+pub const CONSISTENT_SPLIT: &str = "consistent_split";
+/// If true, split tests evenly between sub-suites without using their historic runtimes.
+pub const USE_FALLBACK_SPLIT: &str = "use_fallback_split";
+/// If true, run tests covered by more complex suites even in patch builds.
+pub const RUN_COVERED_TESTS: &str = "run_covered_tests";
+/// If true, generated sub-tasks opt out of evergreen stepback.
+pub const DISABLE_STEPBACK: &str = "disable_stepback";
+/// If true, divide the exec timeout of the task between its generated sub-tasks.
+pub const SCALE_EXEC_TIMEOUT: &str = "scale_exec_timeout";
+/// Test runner the generated sub-tasks should run their tests with.
+pub const TEST_RUNNER: &str = "test_runner";
 
 // Parameters
 // Shared parameters between fuzzers and resmoke.
@@ -71,11 +111,23 @@ pub const SUITE_NAME: &str = "suite";
 pub const GEN_TASK_CONFIG_LOCATION: &str = "gen_task_config_location";
 /// Maximum amount of resmoke jobs to execute in parallel.
 pub const RESMOKE_JOBS_MAX: &str = "resmoke_jobs_max";
+/// Index of the generated sub-task being run.
+pub const SUB_TASK_INDEX: &str = "sub_task_index";
+/// Number of sub-tasks the task was split into.
+pub const TOTAL_SUB_TASKS: &str = "total_sub_tasks";
+/// Choose the maximum amount of resmoke jobs from the CPUs of the distro sub-tasks run on.
+pub const DYNAMIC_RESMOKE_JOBS: &str = "dynamic_resmoke_jobs";
 /// Number of times to repeat a given resmoke suite.
 pub const REPEAT_SUITES: &str = "resmoke_repeat_suites";
 /// Variant used for compile.
 pub const COMPILE_VARIANT: &str = "compile_variant";
 
+// Bazel test parameters.
+/// Bazel test targets to run.
+pub const BAZEL_TARGETS: &str = "targets";
+/// Arguments to pass to the bazel test command.
+pub const BAZEL_ARGS: &str = "bazel_args";
+
 // Fuzzer parameters.
 /// Name of npm command to run.
 pub const NPM_COMMAND: &str = "npm_command";
@@ -89,8 +141,12 @@ pub const SHOULD_SHUFFLE_TESTS: &str = "should_shuffle";
 pub const TASK_NAME: &str = "task";
 /// Idle timeout to set for execution.
 pub const IDLE_TIMEOUT: &str = "timeout_secs";
+/// Execution timeout to set for execution.
+pub const EXEC_TIMEOUT: &str = "exec_timeout_secs";
 /// Multiversion version combination being run against.
 pub const MULTIVERSION_EXCLUDE_TAGS: &str = "multiversion_exclude_tags_version";
+/// Previous version a differential fuzzer compares results against.
+pub const DIFFERENTIAL_FUZZER_OLD_VERSION: &str = "differential_fuzzer_old_version";
 
 // Build Variant expansions.
 /// Name of large distro for build variant.
@@ -106,14 +162,40 @@ pub const BURN_IN_TAG_INCLUDE_ALL_REQUIRED_AND_SUGGESTED: &str =
 pub const BURN_IN_TAG_EXCLUDE_BUILD_VARIANTS: &str = "burn_in_tag_exclude_build_variants";
 /// Compile task name generated build variant should depend on.
 pub const BURN_IN_TAG_COMPILE_TASK_DEPENDENCY: &str = "burn_in_tag_compile_task_dependency";
+/// Additional `task@variant` dependencies generated build variant should depend on.
+pub const BURN_IN_TAG_ADDITIONAL_DEPENDENCIES: &str = "burn_in_tag_additional_dependencies";
+/// Add burn_in_tags tasks to the base build variants instead of cloned build variants.
+pub const BURN_IN_TAG_IN_BASE_VARIANT: &str = "burn_in_tag_in_base_variant";
+
+// Project expansions.
+/// Name of the version burn-in generation task burn_in_tags build variants should depend on.
+pub const BURN_IN_TAG_GEN_TASK_DEPENDENCY: &str = "burn_in_tag_gen_task_dependency";
+/// Name of the variant running the version burn-in generation task.
+pub const BURN_IN_TAG_GEN_VARIANT_DEPENDENCY: &str = "burn_in_tag_gen_variant_dependency";
 /// Name of build variant to determine the timeouts for.
 pub const BURN_IN_BYPASS: &str = "burn_in_bypass";
 /// List of tasks to burn in.
 pub const BURN_IN_TASK_NAME: &str = "burn_in_task_name";
 /// Variant specific override of last_versions in the multiversion-config
 pub const LAST_VERSIONS_EXPANSION: &str = "last_versions";
+/// Old versions that multiversion tasks should not be generated against on a build variant.
+pub const EXCLUDED_OLD_VERSIONS_EXPANSION: &str = "excluded_old_versions";
 /// Unique identifier for generated tasks to use that override last_versions
 pub const UNIQUE_GEN_SUFFIX_EXPANSION: &str = "unique_gen_suffix";
+/// Factor to scale the timeouts of generated tasks by on slower build variants.
+pub const TIMEOUT_FACTOR_EXPANSION: &str = "timeout_factor";
+/// Flags passed to resmoke by the tasks of a build variant.
+pub const TEST_FLAGS_EXPANSION: &str = "test_flags";
+/// Platform to name generated tasks with, overriding the platform inferred from distros.
+pub const GENERATED_TASK_PLATFORM_EXPANSION: &str = "generated_task_platform";
+/// Maximum runtime in seconds generated sub-tasks should finish in on a build variant.
+pub const MAX_TASK_RUNTIME_EXPANSION: &str = "max_task_runtime_secs";
+/// Maximum number of sub-tasks to generate on a build variant across all of its generated tasks.
+pub const MAX_TOTAL_GENERATED_SUBTASKS_EXPANSION: &str = "max_total_generated_subtasks";
+/// Number of sub-tasks to split generated tasks into on a build variant.
+pub const DEFAULT_SUBTASKS_PER_TASK_EXPANSION: &str = "default_subtasks_per_task";
+/// Maximum number of sub-tasks to split each generated task into on a build variant.
+pub const MAX_SUBTASKS_PER_TASK_EXPANSION: &str = "max_subtasks_per_task";
 
 // Task Tags
 /// Tag to include multiversion setup is required.
@@ -126,8 +208,8 @@ pub const NO_MULTIVERSION_GENERATE_TASKS: &str = "no_multiversion_generate_tasks
 pub const BACKPORT_REQUIRED_TAG: &str = "backport_required_multiversion";
 /// Tag to mark task multiversion incompatible.
 pub const MULTIVERSION_INCOMPATIBLE: &str = "multiversion_incompatible";
-/// Filename of multiversion exclude tags file.
-pub const MULTIVERSION_EXCLUDE_TAGS_FILE: &str = "multiversion_exclude_tags.yml";
+/// Filename prefix of multiversion exclude tags files.
+pub const MULTIVERSION_EXCLUDE_TAGS_FILE: &str = "multiversion_exclude_tags";
 /// Name of last lts configuration.
 pub const MULTIVERSION_LAST_LTS: &str = "last_lts";
 /// Name of last continuous configuration.
@@ -141,6 +223,20 @@ pub const MACOS: &str = "macos";
 /// Linux distro group name.
 pub const LINUX: &str = "linux";
 
+// Distro tiers
+/// Name of the large distro tier.
+pub const LARGE_DISTRO_TIER: &str = "large";
+/// Name of the xlarge distro tier.
+pub const XLARGE_DISTRO_TIER: &str = "xlarge";
+
+// Architecture names
+/// ARM 64-bit architecture name.
+pub const ARM64: &str = "arm64";
+/// PowerPC architecture name.
+pub const PPC: &str = "ppc";
+/// IBM Z architecture name.
+pub const S390X: &str = "s390x";
+
 // Constants in evergreen.yml.
 /// Name of the variant that calls generate.task on the version.
 pub const VERSION_GEN_VARIANT: &str = "generate-tasks-for-version";
@@ -0,0 +1,273 @@
+//! Overrides of the names in evergreen_names for projects with different naming conventions.
+//!
+//! Names in the project configuration are translated to the default names when the project is
+//! read, and the default names are translated back to the project's names when the generated
+//! configuration is written. The rest of the generator only deals with the default names.
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use anyhow::{bail, Result};
+use shrub_rs::models::{
+    commands::EvgCommand::Function, project::EvgProject, task::EvgTask, variant::BuildVariant,
+};
+
+/// Map of names in evergreen_names to the names a project uses instead.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EvergreenNamesConfig {
+    /// Map of default names to the names used by the project.
+    project_names: HashMap<String, String>,
+    /// Map of names used by the project to the default names.
+    default_names: HashMap<String, String>,
+}
+
+impl EvergreenNamesConfig {
+    /// Create a names configuration from a map of default names to project names.
+    ///
+    /// # Arguments
+    ///
+    /// * `project_names` - Map of default names to the names used by the project.
+    ///
+    /// # Returns
+    ///
+    /// Names configuration, or an error if two default names map to the same project name.
+    pub fn new(project_names: HashMap<String, String>) -> Result<Self> {
+        let mut default_names = HashMap::new();
+        for (default_name, project_name) in &project_names {
+            if let Some(other) = default_names.insert(project_name.clone(), default_name.clone()) {
+                bail!(
+                    "Evergreen names '{}' and '{}' are both overridden with '{}'",
+                    other,
+                    default_name,
+                    project_name
+                );
+            }
+        }
+
+        Ok(Self {
+            project_names,
+            default_names,
+        })
+    }
+
+    /// Read the names configuration from the given YAML file.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` - Path to YAML file mapping default names to project names.
+    ///
+    /// # Returns
+    ///
+    /// Names configuration read from the file.
+    pub fn from_yaml_file<P: AsRef<Path>>(location: P) -> Result<Self> {
+        let contents = std::fs::read_to_string(location)?;
+        Self::new(serde_yaml::from_str(&contents)?)
+    }
+
+    /// Get the default name for the given project name.
+    pub fn to_default(&self, name: &str) -> String {
+        self.default_names
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    /// Get the project name for the given default name.
+    pub fn to_project(&self, name: &str) -> String {
+        self.project_names
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    /// Translate the names in a project configuration to the default names.
+    ///
+    /// Function calls, function vars, task tags, build variant expansions and the modules of build
+    /// variants are translated. The modules of the project keep their names since they name the
+    /// directories modules are checked out to.
+    ///
+    /// # Arguments
+    ///
+    /// * `evg_project` - Project configuration to translate.
+    pub fn translate_to_default(&self, evg_project: &mut EvgProject) {
+        if self.project_names.is_empty() {
+            return;
+        }
+        let rename = |name: &str| self.to_default(name);
+        evg_project
+            .tasks
+            .iter_mut()
+            .for_each(|task| rename_task(task, &rename));
+        evg_project
+            .buildvariants
+            .iter_mut()
+            .for_each(|build_variant| rename_build_variant(build_variant, &rename));
+    }
+
+    /// Translate the default names in a generated configuration to the names of the project.
+    ///
+    /// # Arguments
+    ///
+    /// * `evg_project` - Generated configuration to translate.
+    pub fn translate_to_project(&self, evg_project: &mut EvgProject) {
+        if self.project_names.is_empty() {
+            return;
+        }
+        let rename = |name: &str| self.to_project(name);
+        evg_project
+            .tasks
+            .iter_mut()
+            .for_each(|task| rename_task(task, &rename));
+        evg_project
+            .buildvariants
+            .iter_mut()
+            .for_each(|build_variant| rename_build_variant(build_variant, &rename));
+    }
+
+    /// Translate the names of project-level expansions to the default names.
+    ///
+    /// # Arguments
+    ///
+    /// * `expansions` - Project-level expansions to translate.
+    ///
+    /// # Returns
+    ///
+    /// Expansions using the default names.
+    pub fn expansions_to_default(
+        &self,
+        expansions: &HashMap<String, String>,
+    ) -> HashMap<String, String> {
+        expansions
+            .iter()
+            .map(|(name, value)| (self.to_default(name), value.clone()))
+            .collect()
+    }
+}
+
+/// Rename the function calls, function vars and tags of the given task.
+///
+/// # Arguments
+///
+/// * `task` - Task to rename.
+/// * `rename` - Function giving the new name for a name.
+fn rename_task<F: Fn(&str) -> String>(task: &mut EvgTask, rename: &F) {
+    for command in task.commands.iter_mut().flatten() {
+        if let Function(func) = command {
+            func.func = rename(&func.func);
+            if let Some(vars) = func.vars.take() {
+                func.vars = Some(
+                    vars.into_iter()
+                        .map(|(name, value)| (rename(&name), value))
+                        .collect(),
+                );
+            }
+        }
+    }
+    for tag in task.tags.iter_mut().flatten() {
+        *tag = rename(tag);
+    }
+}
+
+/// Rename the expansions and modules of the given build variant.
+///
+/// # Arguments
+///
+/// * `build_variant` - Build variant to rename.
+/// * `rename` - Function giving the new name for a name.
+fn rename_build_variant<F: Fn(&str) -> String>(build_variant: &mut BuildVariant, rename: &F) {
+    if let Some(expansions) = build_variant.expansions.take() {
+        build_variant.expansions = Some(
+            expansions
+                .into_iter()
+                .map(|(name, value)| (rename(&name), value))
+                .collect::<BTreeMap<String, String>>(),
+        );
+    }
+    for module in build_variant.modules.iter_mut().flatten() {
+        *module = rename(module);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::{btreemap, hashmap};
+    use shrub_rs::models::{commands::fn_call_with_params, params::ParamValue};
+
+    use crate::evergreen_names::{ENTERPRISE_MODULE, GENERATE_RESMOKE_TASKS, SUITE_NAME};
+
+    use super::*;
+
+    fn build_names_config() -> EvergreenNamesConfig {
+        EvergreenNamesConfig::new(hashmap! {
+            GENERATE_RESMOKE_TASKS.to_string() => "generate tasks".to_string(),
+            SUITE_NAME.to_string() => "test_suite".to_string(),
+            ENTERPRISE_MODULE.to_string() => "closed-source".to_string(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_new_should_fail_if_project_names_are_not_unique() {
+        let result = EvergreenNamesConfig::new(hashmap! {
+            "run tests".to_string() => "run".to_string(),
+            "run generated tests".to_string() => "run".to_string(),
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_translate_to_default_should_rename_project_names() {
+        let names_config = build_names_config();
+        let mut evg_project = EvgProject {
+            tasks: vec![EvgTask {
+                name: "auth_gen".to_string(),
+                commands: Some(vec![fn_call_with_params(
+                    "generate tasks",
+                    hashmap! {
+                        "test_suite".to_string() => ParamValue::from("auth"),
+                    },
+                )]),
+                ..Default::default()
+            }],
+            buildvariants: vec![BuildVariant {
+                name: "bv".to_string(),
+                expansions: Some(btreemap! {
+                    "test_suite".to_string() => "value".to_string(),
+                }),
+                modules: Some(vec!["closed-source".to_string(), "other".to_string()]),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        names_config.translate_to_default(&mut evg_project);
+
+        if let Some(Function(func)) = &evg_project.tasks[0].commands.as_ref().unwrap().first() {
+            assert_eq!(func.func, GENERATE_RESMOKE_TASKS);
+            assert!(func.vars.as_ref().unwrap().contains_key(SUITE_NAME));
+        } else {
+            panic!("Expected a function call");
+        }
+        let build_variant = &evg_project.buildvariants[0];
+        assert!(build_variant
+            .expansions
+            .as_ref()
+            .unwrap()
+            .contains_key(SUITE_NAME));
+        assert_eq!(
+            build_variant.modules,
+            Some(vec![ENTERPRISE_MODULE.to_string(), "other".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_to_project_should_keep_names_that_are_not_overridden() {
+        let names_config = build_names_config();
+
+        assert_eq!(names_config.to_project(SUITE_NAME), "test_suite");
+        assert_eq!(names_config.to_project("do setup"), "do setup");
+        assert_eq!(names_config.to_default("test_suite"), SUITE_NAME);
+    }
+}
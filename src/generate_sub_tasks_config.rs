@@ -1,12 +1,314 @@
-use std::{collections::HashSet, path::Path};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+};
 use tracing::error;
 
 use anyhow::Result;
+use regex::Regex;
 use serde::Deserialize;
+use shrub_rs::models::variant::BuildVariant;
+
+use crate::{
+    evergreen::evg_config::DEFAULT_REQUIRED_PREFIXES,
+    evergreen_names::{
+        RUN_BAZEL_TESTS, RUN_FUZZER, RUN_GENERATED_TESTS, SETUP_JSTESTFUZZ, TEST_FLAGS_EXPANSION,
+    },
+    services::variant_cloning::VariantCloneConfig,
+    utils::task_name::{remove_gen_suffix, TaskNamingPolicy},
+};
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct GenerateSubTasksConfig {
     pub build_variant_large_distro_exceptions: HashSet<String>,
+    #[serde(default)]
+    pub multiversion_old_versions: Option<Vec<String>>,
+    #[serde(default)]
+    pub generator_display_task_groups: Vec<DisplayTaskGroup>,
+    #[serde(default)]
+    pub generator_functions: Vec<String>,
+    #[serde(default)]
+    pub enterprise_detection: EnterpriseDetection,
+    #[serde(default)]
+    pub build_variant_distro_tiers: Vec<DistroTierConfig>,
+    #[serde(default)]
+    pub build_variant_clones: Vec<VariantCloneConfig>,
+    #[serde(default)]
+    pub feature_flag_variants: Vec<FeatureFlagVariantsConfig>,
+    #[serde(default)]
+    pub empty_suite_fallback: bool,
+    #[serde(default)]
+    pub distro_capabilities: Vec<DistroCapabilityConfig>,
+    #[serde(default = "default_required_display_name_prefixes")]
+    pub required_display_name_prefixes: Vec<String>,
+    #[serde(default)]
+    pub command_hooks: Vec<CommandHookConfig>,
+    #[serde(default)]
+    pub function_names: FunctionNames,
+    #[serde(default)]
+    pub module_evergreen_configs: Vec<ModuleEvgConfig>,
+    #[serde(default)]
+    pub history_variant_aliases: HashMap<String, String>,
+    #[serde(default)]
+    pub task_naming: TaskNamingPolicy,
+}
+
+/// Display name prefixes of build variants treated as required, `!` and `*` by default.
+pub fn default_required_display_name_prefixes() -> Vec<String> {
+    DEFAULT_REQUIRED_PREFIXES
+        .iter()
+        .map(|prefix| prefix.to_string())
+        .collect()
+}
+
+/// A feature flag or set of feature flags to enable together.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum FeatureFlagSet {
+    /// A single feature flag.
+    Flag(String),
+    /// A set of feature flags.
+    Flags(Vec<String>),
+}
+
+impl FeatureFlagSet {
+    /// Get the feature flags in this set.
+    pub fn flags(&self) -> Vec<String> {
+        match self {
+            FeatureFlagSet::Flag(flag) => vec![flag.clone()],
+            FeatureFlagSet::Flags(flags) => flags.clone(),
+        }
+    }
+}
+
+/// Build variants to create for running generated tasks with feature flags enabled.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct FeatureFlagVariantsConfig {
+    /// Build variant names or glob patterns to create feature flag build variants for.
+    pub build_variants: Vec<String>,
+    /// Feature flags or sets of feature flags to create build variants for.
+    pub feature_flags: Vec<FeatureFlagSet>,
+    /// Names or glob patterns of generated tasks to run on feature flag build variants.
+    pub tasks: Vec<String>,
+}
+
+impl FeatureFlagVariantsConfig {
+    /// Check if feature flag build variants should be created for the given build variant.
+    ///
+    /// # Arguments
+    ///
+    /// * `build_variant_name` - Name of build variant to check.
+    ///
+    /// # Returns
+    ///
+    /// true if feature flag build variants should be created.
+    pub fn matches(&self, build_variant_name: &str) -> bool {
+        self.build_variants
+            .iter()
+            .any(|pattern| glob_matches(pattern, build_variant_name))
+    }
+
+    /// Check if the given generated task should run on feature flag build variants.
+    ///
+    /// # Arguments
+    ///
+    /// * `display_task_name` - Display name of generated task.
+    ///
+    /// # Returns
+    ///
+    /// true if the generated task should run on feature flag build variants.
+    pub fn includes_task(&self, display_task_name: &str) -> bool {
+        self.tasks
+            .iter()
+            .any(|pattern| glob_matches(remove_gen_suffix(pattern), display_task_name))
+    }
+
+    /// Build the configuration to derive a build variant for each feature flag set.
+    ///
+    /// The feature flags are enabled by adding them to the `test_flags` expansion of the base
+    /// build variant.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_build_variant` - Build variant feature flag build variants are derived from.
+    ///
+    /// # Returns
+    ///
+    /// Configuration for deriving each feature flag build variant.
+    pub fn clone_configs(&self, base_build_variant: &BuildVariant) -> Vec<VariantCloneConfig> {
+        let base_test_flags = base_build_variant
+            .expansions
+            .as_ref()
+            .and_then(|expansions| expansions.get(TEST_FLAGS_EXPANSION));
+
+        self.feature_flags
+            .iter()
+            .map(|flag_set| {
+                let flags = flag_set.flags();
+                let flag_args = flags
+                    .iter()
+                    .map(|flag| format!("--additionalFeatureFlags={}", flag))
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                let test_flags = match base_test_flags {
+                    Some(base_flags) => format!("{} {}", base_flags, flag_args),
+                    None => flag_args,
+                };
+
+                VariantCloneConfig {
+                    build_variants: self.build_variants.clone(),
+                    name_suffix: flags.join("-"),
+                    display_name_prefix: Some(format!("[{}]", flags.join(", "))),
+                    expansions: BTreeMap::from([(TEST_FLAGS_EXPANSION.to_string(), test_flags)]),
+                    depends_on: vec![],
+                }
+            })
+            .collect()
+    }
+}
+
+/// Distros to use for named distro tiers on matching build variants.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct DistroTierConfig {
+    /// Build variant names or glob patterns the distro tiers apply to.
+    pub build_variants: Vec<String>,
+    /// Map of distro tier names (e.g. large, xlarge, memory_intensive) to distro names.
+    pub tiers: HashMap<String, String>,
+}
+
+/// Capabilities of the hosts provided by matching distros.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct DistroCapabilityConfig {
+    /// Distro names or glob patterns the capabilities apply to.
+    pub distros: Vec<String>,
+    /// Number of CPUs available on hosts of the distros.
+    pub cpu_count: u64,
+}
+
+/// Functions to add to the commands of the sub-tasks generated for matching tasks.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct CommandHookConfig {
+    /// Tags of tasks the hook applies to.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Suite names or glob patterns the hook applies to.
+    #[serde(default)]
+    pub suites: Vec<String>,
+    /// Functions to call before the other commands of sub-tasks.
+    #[serde(default)]
+    pub prepend: Vec<String>,
+    /// Functions to call after the other commands of sub-tasks.
+    #[serde(default)]
+    pub append: Vec<String>,
+}
+
+impl CommandHookConfig {
+    /// Check if the hook applies to a task with the given suite and tags.
+    ///
+    /// # Arguments
+    ///
+    /// * `suite_name` - Name of suite the task runs.
+    /// * `task_tags` - Tags of the task.
+    ///
+    /// # Returns
+    ///
+    /// true if the hook applies to the task.
+    pub fn matches(&self, suite_name: &str, task_tags: &HashSet<String>) -> bool {
+        self.tags.iter().any(|tag| task_tags.contains(tag))
+            || self
+                .suites
+                .iter()
+                .any(|pattern| glob_matches(pattern, suite_name))
+    }
+}
+
+/// Functions to call around the commands of generated sub-tasks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandHooks {
+    /// Functions to call before the other commands of sub-tasks.
+    pub prepend: Vec<String>,
+    /// Functions to call after the other commands of sub-tasks.
+    pub append: Vec<String>,
+}
+
+/// Evergreen configuration in a module to generate tasks from.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ModuleEvgConfig {
+    /// Name of the module.
+    pub module: String,
+    /// Path to the evergreen configuration, relative to the directory of the module.
+    pub config_file: String,
+}
+
+/// Names of the evergreen functions generated sub-tasks call to run their tests.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct FunctionNames {
+    /// Function to run the tests of resmoke and fuzzer sub-tasks.
+    pub run_generated_tests: String,
+    /// Function to run the tests of sub-tasks as bazel test targets.
+    pub run_bazel_tests: String,
+    /// Function to setup the fuzzer.
+    pub setup_jstestfuzz: String,
+    /// Function to generate fuzzer tests.
+    pub run_fuzzer: String,
+}
+
+impl Default for FunctionNames {
+    fn default() -> Self {
+        Self {
+            run_generated_tests: RUN_GENERATED_TESTS.to_string(),
+            run_bazel_tests: RUN_BAZEL_TESTS.to_string(),
+            setup_jstestfuzz: SETUP_JSTESTFUZZ.to_string(),
+            run_fuzzer: RUN_FUZZER.to_string(),
+        }
+    }
+}
+
+/// Method used to detect if a build variant runs enterprise tests.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EnterpriseDetection {
+    /// Enterprise unless an expansion disables enterprise tests with `--enableEnterpriseTests=off`.
+    #[default]
+    Expansions,
+    /// Enterprise if the build variant `modules` list contains the enterprise module. Build
+    /// variants that do not list any modules fall back to checking expansions.
+    Modules,
+}
+
+/// Rule for grouping "_gen" tasks into a display task.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct DisplayTaskGroup {
+    /// Name of display task to create.
+    pub name: String,
+    /// Tasks with any of these tags belong to the group.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Tasks with names matching any of these regular expressions belong to the group.
+    #[serde(default)]
+    pub task_name_patterns: Vec<String>,
+}
+
+impl DisplayTaskGroup {
+    /// Check if the given task belongs to this group.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_name` - Name of task to check.
+    /// * `task_tags` - Tags of task to check.
+    ///
+    /// # Returns
+    ///
+    /// true if the task belongs to this group.
+    pub fn matches(&self, task_name: &str, task_tags: &HashSet<String>) -> bool {
+        self.tags.iter().any(|tag| task_tags.contains(tag))
+            || self.task_name_patterns.iter().any(|pattern| {
+                Regex::new(pattern)
+                    .map(|re| re.is_match(task_name))
+                    .unwrap_or(false)
+            })
+    }
 }
 
 impl GenerateSubTasksConfig {
@@ -22,11 +324,601 @@ impl GenerateSubTasksConfig {
             );
         }
 
-        Ok(subtasks?)
+        let subtasks = subtasks?;
+        for group in &subtasks.generator_display_task_groups {
+            for pattern in &group.task_name_patterns {
+                Regex::new(pattern)?;
+            }
+        }
+        subtasks.task_naming.validate()?;
+
+        Ok(subtasks)
     }
 
+    /// Check if the given build variant can generate large distro tasks without a large distro.
+    ///
+    /// Entries in `build_variant_large_distro_exceptions` can be exact build variant names or
+    /// glob patterns (e.g. `*-debug-*`). Patterns prefixed with `!` exclude matching build
+    /// variants. An exact name always applies, followed by exclusions and then patterns.
+    ///
+    /// # Arguments
+    ///
+    /// * `build_variant_name` - Name of build variant to check.
+    ///
+    /// # Returns
+    ///
+    /// true if the build variant is an exception to the large distro requirement.
     pub fn ignore_missing_large_distro(&self, build_variant_name: &str) -> bool {
-        self.build_variant_large_distro_exceptions
+        if self
+            .build_variant_large_distro_exceptions
             .contains(build_variant_name)
+        {
+            return true;
+        }
+
+        let (exclusions, patterns): (Vec<&String>, Vec<&String>) = self
+            .build_variant_large_distro_exceptions
+            .iter()
+            .partition(|entry| entry.starts_with('!'));
+
+        if exclusions
+            .iter()
+            .any(|entry| glob_matches(&entry[1..], build_variant_name))
+        {
+            return false;
+        }
+
+        patterns
+            .iter()
+            .any(|entry| glob_matches(entry, build_variant_name))
+    }
+
+    /// Find the distro to use for the given distro tier on the given build variant.
+    ///
+    /// Entries in `build_variant_distro_tiers` are checked in order and the first entry matching
+    /// the build variant that defines the distro tier is used.
+    ///
+    /// # Arguments
+    ///
+    /// * `build_variant_name` - Name of build variant to query.
+    /// * `tier` - Name of distro tier to query.
+    ///
+    /// # Returns
+    ///
+    /// Name of distro configured for the distro tier, if any.
+    pub fn distro_for_tier(&self, build_variant_name: &str, tier: &str) -> Option<&str> {
+        self.build_variant_distro_tiers
+            .iter()
+            .filter(|config| {
+                config
+                    .build_variants
+                    .iter()
+                    .any(|pattern| glob_matches(pattern, build_variant_name))
+            })
+            .find_map(|config| config.tiers.get(tier))
+            .map(|distro| distro.as_str())
+    }
+
+    /// Find the number of CPUs available on hosts of the given distro.
+    ///
+    /// Entries in `distro_capabilities` are checked in order and the first entry matching the
+    /// distro is used.
+    ///
+    /// # Arguments
+    ///
+    /// * `distro` - Name of distro to query.
+    ///
+    /// # Returns
+    ///
+    /// Number of CPUs configured for the distro, if any.
+    pub fn cpu_count_for_distro(&self, distro: &str) -> Option<u64> {
+        self.distro_capabilities
+            .iter()
+            .find(|config| {
+                config
+                    .distros
+                    .iter()
+                    .any(|pattern| glob_matches(pattern, distro))
+            })
+            .map(|config| config.cpu_count)
+    }
+
+    /// Find the functions to call around the commands of sub-tasks generated for a task.
+    ///
+    /// The functions of all matching entries in `command_hooks` are used, in order.
+    ///
+    /// # Arguments
+    ///
+    /// * `suite_name` - Name of suite the task runs.
+    /// * `task_tags` - Tags of the task.
+    ///
+    /// # Returns
+    ///
+    /// Functions to call before and after the commands of generated sub-tasks.
+    pub fn command_hooks_for(&self, suite_name: &str, task_tags: &HashSet<String>) -> CommandHooks {
+        self.command_hooks
+            .iter()
+            .filter(|hook| hook.matches(suite_name, task_tags))
+            .fold(CommandHooks::default(), |mut hooks, hook| {
+                hooks.prepend.extend(hook.prepend.iter().cloned());
+                hooks.append.extend(hook.append.iter().cloned());
+                hooks
+            })
+    }
+
+    /// Find entries in the configuration that do not match anything in the project.
+    ///
+    /// # Arguments
+    ///
+    /// * `build_variant_names` - Names of build variants in the project.
+    ///
+    /// # Returns
+    ///
+    /// Description of each entry that does not match anything in the project.
+    pub fn validate(&self, build_variant_names: &HashSet<String>) -> Vec<String> {
+        let mut sorted_entries: Vec<&String> =
+            self.build_variant_large_distro_exceptions.iter().collect();
+        sorted_entries.sort();
+
+        let exception_problems = sorted_entries
+            .into_iter()
+            .filter(|entry| {
+                let pattern = entry.strip_prefix('!').unwrap_or(entry);
+                !build_variant_names
+                    .iter()
+                    .any(|bv_name| glob_matches(pattern, bv_name))
+            })
+            .map(|entry| {
+                format!(
+                    "build_variant_large_distro_exceptions entry '{}' does not match any build variant",
+                    entry
+                )
+            });
+
+        let tier_problems = unmatched_patterns(
+            "build_variant_distro_tiers",
+            self.build_variant_distro_tiers
+                .iter()
+                .flat_map(|config| config.build_variants.iter()),
+            build_variant_names,
+        );
+        let clone_problems = unmatched_patterns(
+            "build_variant_clones",
+            self.build_variant_clones
+                .iter()
+                .flat_map(|config| config.build_variants.iter()),
+            build_variant_names,
+        );
+        let feature_flag_problems = unmatched_patterns(
+            "feature_flag_variants",
+            self.feature_flag_variants
+                .iter()
+                .flat_map(|config| config.build_variants.iter()),
+            build_variant_names,
+        );
+        let mut aliased_variants: Vec<&String> = self.history_variant_aliases.keys().collect();
+        aliased_variants.sort();
+        let alias_problems = unmatched_patterns(
+            "history_variant_aliases",
+            aliased_variants.into_iter(),
+            build_variant_names,
+        );
+
+        exception_problems
+            .chain(tier_problems)
+            .chain(clone_problems)
+            .chain(feature_flag_problems)
+            .chain(alias_problems)
+            .collect()
+    }
+
+    /// Find the display task a "_gen" task should be grouped under.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_name` - Name of "_gen" task.
+    /// * `task_tags` - Tags of "_gen" task.
+    ///
+    /// # Returns
+    ///
+    /// Name of the first matching display task group, if any.
+    pub fn generator_display_task_group(
+        &self,
+        task_name: &str,
+        task_tags: &HashSet<String>,
+    ) -> Option<&str> {
+        self.generator_display_task_groups
+            .iter()
+            .find(|group| group.matches(task_name, task_tags))
+            .map(|group| group.name.as_str())
+    }
+}
+
+/// Describe the patterns in a section of the configuration that match no build variants.
+///
+/// # Arguments
+///
+/// * `section` - Name of configuration section patterns are from.
+/// * `patterns` - Build variant names or glob patterns to check.
+/// * `build_variant_names` - Names of build variants in the project.
+///
+/// # Returns
+///
+/// Description of each pattern that does not match any build variant.
+fn unmatched_patterns<'a>(
+    section: &'a str,
+    patterns: impl Iterator<Item = &'a String> + 'a,
+    build_variant_names: &'a HashSet<String>,
+) -> impl Iterator<Item = String> + 'a {
+    patterns
+        .filter(move |pattern| {
+            !build_variant_names
+                .iter()
+                .any(|bv_name| glob_matches(pattern, bv_name))
+        })
+        .map(move |pattern| {
+            format!(
+                "{} entry '{}' does not match any build variant",
+                section, pattern
+            )
+        })
+}
+
+/// Check if the given name matches a glob pattern.
+///
+/// `*` matches any sequence of characters and `?` matches a single character.
+///
+/// # Arguments
+///
+/// * `pattern` - Glob pattern to match against.
+/// * `name` - Name to check.
+///
+/// # Returns
+///
+/// true if the name matches the pattern.
+pub fn glob_matches(pattern: &str, name: &str) -> bool {
+    let regex_pattern = regex::escape(pattern)
+        .replace(r"\*", ".*")
+        .replace(r"\?", ".");
+    Regex::new(&format!("^{}$", regex_pattern))
+        .map(|re| re.is_match(name))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::{hashmap, hashset};
+    use rstest::rstest;
+
+    use super::*;
+
+    fn build_config() -> GenerateSubTasksConfig {
+        GenerateSubTasksConfig {
+            build_variant_large_distro_exceptions: hashset! {},
+            multiversion_old_versions: None,
+            generator_display_task_groups: vec![
+                DisplayTaskGroup {
+                    name: "multiversion_generator_tasks".to_string(),
+                    tags: vec!["multiversion".to_string()],
+                    ..Default::default()
+                },
+                DisplayTaskGroup {
+                    name: "fuzzer_generator_tasks".to_string(),
+                    task_name_patterns: vec![r"^.*fuzz.*_gen$".to_string()],
+                    ..Default::default()
+                },
+            ],
+            generator_functions: vec![],
+            enterprise_detection: EnterpriseDetection::default(),
+            build_variant_distro_tiers: vec![
+                DistroTierConfig {
+                    build_variants: vec!["*-debug-*".to_string()],
+                    tiers: hashmap! {
+                        "memory_intensive".to_string() => "debug-memory".to_string(),
+                    },
+                },
+                DistroTierConfig {
+                    build_variants: vec!["*".to_string()],
+                    tiers: hashmap! {
+                        "large".to_string() => "default-large".to_string(),
+                        "memory_intensive".to_string() => "default-memory".to_string(),
+                    },
+                },
+            ],
+            build_variant_clones: vec![],
+            feature_flag_variants: vec![],
+            empty_suite_fallback: false,
+            distro_capabilities: vec![],
+            required_display_name_prefixes: default_required_display_name_prefixes(),
+            command_hooks: vec![],
+            function_names: FunctionNames::default(),
+            module_evergreen_configs: vec![],
+            history_variant_aliases: hashmap! {},
+            task_naming: TaskNamingPolicy::default(),
+        }
+    }
+
+    // generator_display_task_group tests.
+    #[rstest]
+    #[case("auth_gen", vec![], None)]
+    #[case("auth_gen", vec!["multiversion"], Some("multiversion_generator_tasks"))]
+    #[case("jstestfuzz_gen", vec![], Some("fuzzer_generator_tasks"))]
+    #[case("jstestfuzz_gen", vec!["multiversion"], Some("multiversion_generator_tasks"))]
+    fn test_generator_display_task_group_should_use_first_matching_group(
+        #[case] task_name: &str,
+        #[case] tags: Vec<&str>,
+        #[case] expected_group: Option<&str>,
+    ) {
+        let config = build_config();
+        let task_tags = tags.into_iter().map(|t| t.to_string()).collect();
+
+        assert_eq!(
+            config.generator_display_task_group(task_name, &task_tags),
+            expected_group
+        );
+    }
+
+    // ignore_missing_large_distro tests.
+    #[rstest]
+    #[case("build_variant_0", true)]
+    #[case("ubuntu1804-debug-asan", true)]
+    #[case("rhel80-debug-suggested", true)]
+    #[case("rhel80-debug-required", false)]
+    #[case("windows-debug-required", true)]
+    #[case("macos-debug", false)]
+    #[case("rhel-80", false)]
+    fn test_ignore_missing_large_distro_should_support_patterns(
+        #[case] build_variant_name: &str,
+        #[case] expected: bool,
+    ) {
+        let mut config = build_config();
+        config.build_variant_large_distro_exceptions = hashset! {
+            "build_variant_0".to_string(),
+            "*-debug-*".to_string(),
+            "!*-required".to_string(),
+            "windows-debug-required".to_string(),
+        };
+
+        assert_eq!(
+            config.ignore_missing_large_distro(build_variant_name),
+            expected
+        );
+    }
+
+    // FeatureFlagVariantsConfig tests.
+    #[rstest]
+    #[case("jsCore", true)]
+    #[case("aggregation_multiversion", true)]
+    #[case("noPassthrough", false)]
+    fn test_feature_flag_variants_includes_task(
+        #[case] display_task_name: &str,
+        #[case] expected: bool,
+    ) {
+        let config = FeatureFlagVariantsConfig {
+            tasks: vec!["jsCore_gen".to_string(), "aggregation*".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(config.includes_task(display_task_name), expected);
+    }
+
+    #[test]
+    fn test_feature_flag_variants_clone_configs_should_add_flags_to_test_flags() {
+        let config: FeatureFlagVariantsConfig = serde_yaml::from_str(
+            r#"
+            build_variants:
+              - enterprise-rhel-80-64-bit
+            feature_flags:
+              - featureFlagA
+              - - featureFlagB
+                - featureFlagC
+            tasks:
+              - jsCore_gen
+            "#,
+        )
+        .unwrap();
+        let base_build_variant = BuildVariant {
+            name: "enterprise-rhel-80-64-bit".to_string(),
+            expansions: Some(BTreeMap::from([(
+                TEST_FLAGS_EXPANSION.to_string(),
+                "--base".to_string(),
+            )])),
+            ..Default::default()
+        };
+
+        let clone_configs = config.clone_configs(&base_build_variant);
+
+        assert_eq!(clone_configs.len(), 2);
+        assert_eq!(
+            clone_configs[0].variant_name(&base_build_variant.name),
+            "enterprise-rhel-80-64-bit-featureFlagA"
+        );
+        assert_eq!(
+            clone_configs[0].expansions.get(TEST_FLAGS_EXPANSION),
+            Some(&"--base --additionalFeatureFlags=featureFlagA".to_string())
+        );
+        assert_eq!(
+            clone_configs[1].variant_name(&base_build_variant.name),
+            "enterprise-rhel-80-64-bit-featureFlagB-featureFlagC"
+        );
+        assert_eq!(
+            clone_configs[1].expansions.get(TEST_FLAGS_EXPANSION),
+            Some(
+                &"--base --additionalFeatureFlags=featureFlagB --additionalFeatureFlags=featureFlagC"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            clone_configs[1].display_name_prefix,
+            Some("[featureFlagB, featureFlagC]".to_string())
+        );
+    }
+
+    // distro_for_tier tests.
+    #[rstest]
+    #[case("rhel80-debug-required", "memory_intensive", Some("debug-memory"))]
+    #[case("rhel80-debug-required", "large", Some("default-large"))]
+    #[case("rhel80", "memory_intensive", Some("default-memory"))]
+    #[case("rhel80", "xlarge", None)]
+    fn test_distro_for_tier_should_use_first_matching_entry(
+        #[case] build_variant_name: &str,
+        #[case] tier: &str,
+        #[case] expected_distro: Option<&str>,
+    ) {
+        let config = build_config();
+
+        assert_eq!(
+            config.distro_for_tier(build_variant_name, tier),
+            expected_distro
+        );
+    }
+
+    // required_display_name_prefixes tests.
+    #[rstest]
+    #[case("build_variant_large_distro_exceptions: []", vec!["!", "*"])]
+    #[case(
+        "build_variant_large_distro_exceptions: []\nrequired_display_name_prefixes: ['!']",
+        vec!["!"]
+    )]
+    fn test_required_display_name_prefixes_should_default_to_required_and_suggested(
+        #[case] contents: &str,
+        #[case] expected_prefixes: Vec<&str>,
+    ) {
+        let config: GenerateSubTasksConfig = serde_yaml::from_str(contents).unwrap();
+
+        assert_eq!(config.required_display_name_prefixes, expected_prefixes);
+    }
+
+    // function_names tests.
+    #[test]
+    fn test_function_names_should_default_names_that_are_not_overridden() {
+        let contents = "build_variant_large_distro_exceptions: []\nfunction_names:\n  run_generated_tests: execute tests";
+
+        let config: GenerateSubTasksConfig = serde_yaml::from_str(contents).unwrap();
+
+        assert_eq!(
+            config.function_names,
+            FunctionNames {
+                run_generated_tests: "execute tests".to_string(),
+                ..Default::default()
+            }
+        );
+    }
+
+    // command_hooks_for tests.
+    #[test]
+    fn test_command_hooks_for_should_combine_matching_hooks() {
+        let mut config = build_config();
+        config.command_hooks = vec![
+            CommandHookConfig {
+                tags: vec!["kerberos".to_string()],
+                prepend: vec!["set up kerberos".to_string()],
+                ..Default::default()
+            },
+            CommandHookConfig {
+                suites: vec!["*".to_string()],
+                append: vec!["collect core dumps".to_string()],
+                ..Default::default()
+            },
+            CommandHookConfig {
+                suites: vec!["sharding*".to_string()],
+                prepend: vec!["set up sharding".to_string()],
+                ..Default::default()
+            },
+        ];
+
+        let hooks = config.command_hooks_for("auth", &hashset! {"kerberos".to_string()});
+
+        assert_eq!(
+            hooks,
+            CommandHooks {
+                prepend: vec!["set up kerberos".to_string()],
+                append: vec!["collect core dumps".to_string()],
+            }
+        );
+        assert_eq!(
+            config.command_hooks_for("core", &hashset! {}).prepend,
+            Vec::<String>::new()
+        );
+    }
+
+    // cpu_count_for_distro tests.
+    #[rstest]
+    #[case("rhel80-xlarge", Some(16))]
+    #[case("rhel80-large", Some(8))]
+    #[case("rhel80-small", Some(2))]
+    #[case("windows-vsCurrent-large", None)]
+    fn test_cpu_count_for_distro_should_use_first_matching_entry(
+        #[case] distro: &str,
+        #[case] expected_cpu_count: Option<u64>,
+    ) {
+        let mut config = build_config();
+        config.distro_capabilities = vec![
+            DistroCapabilityConfig {
+                distros: vec!["rhel80-xlarge".to_string()],
+                cpu_count: 16,
+            },
+            DistroCapabilityConfig {
+                distros: vec!["rhel80-*large".to_string()],
+                cpu_count: 8,
+            },
+            DistroCapabilityConfig {
+                distros: vec!["rhel80-*".to_string()],
+                cpu_count: 2,
+            },
+        ];
+
+        assert_eq!(config.cpu_count_for_distro(distro), expected_cpu_count);
+    }
+
+    // validate tests.
+    #[test]
+    fn test_validate_should_report_entries_that_match_no_build_variants() {
+        let mut config = build_config();
+        config.build_variant_distro_tiers[0].build_variants = vec!["*-debug-*".to_string()];
+        config.build_variant_distro_tiers[1].build_variants = vec!["windows-*".to_string()];
+        config.build_variant_clones = vec![VariantCloneConfig {
+            build_variants: vec!["rhel80-*".to_string(), "rhel90-*".to_string()],
+            name_suffix: "clone".to_string(),
+            ..Default::default()
+        }];
+        config.build_variant_large_distro_exceptions = hashset! {
+            "build_variant_0".to_string(),
+            "build_variant_typo".to_string(),
+            "*-debug-*".to_string(),
+            "!*-required".to_string(),
+            "!*-suggested".to_string(),
+        };
+        config.history_variant_aliases = hashmap! {
+            "rhel80-debug-required".to_string() => "build_variant_0".to_string(),
+            "rhel90-debug-required".to_string() => "build_variant_0".to_string(),
+        };
+        let build_variant_names = hashset! {
+            "build_variant_0".to_string(),
+            "rhel80-debug-required".to_string(),
+        };
+
+        let problems = config.validate(&build_variant_names);
+
+        assert_eq!(
+            problems,
+            vec![
+                "build_variant_large_distro_exceptions entry '!*-suggested' does not match any build variant".to_string(),
+                "build_variant_large_distro_exceptions entry 'build_variant_typo' does not match any build variant".to_string(),
+                "build_variant_distro_tiers entry 'windows-*' does not match any build variant".to_string(),
+                "build_variant_clones entry 'rhel90-*' does not match any build variant".to_string(),
+                "history_variant_aliases entry 'rhel90-debug-required' does not match any build variant".to_string(),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("*-debug-*", "ubuntu1804-debug-asan", true)]
+    #[case("*-debug-*", "ubuntu1804-debug", false)]
+    #[case("rhel8?", "rhel80", true)]
+    #[case("rhel8?", "rhel800", false)]
+    #[case("enterprise.rhel", "enterprise-rhel", false)]
+    fn test_glob_matches(#[case] pattern: &str, #[case] name: &str, #[case] expected: bool) {
+        assert_eq!(glob_matches(pattern, name), expected);
     }
 }
@@ -0,0 +1,211 @@
+//! Record of the tasks generated by a run of the generator.
+//!
+//! The generated tasks are written next to `evergreen_config.json` so a later run can rebuild the
+//! build variants of the configuration against the same tasks without generating them again.
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error_report::ERROR_REPORT_FILE,
+    generation_checkpoint::CHECKPOINT_DIR,
+    manifest::{list_files, MANIFEST_FILE},
+    task_types::{
+        generated_suite::{GeneratedSubTask, GeneratedSuite},
+        resmoke_tasks::GeneratedResmokeSuite,
+    },
+    GenTaskCollection,
+};
+
+/// Name of file to write the generated tasks to.
+pub const GENERATED_TASKS_FILE: &str = "generated_tasks.json";
+
+/// Record of a single generated task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedTaskRecord {
+    /// Name of the display task grouping the sub-tasks.
+    pub display_name: String,
+    /// Sub-tasks the task was split into.
+    pub sub_tasks: Vec<GeneratedSubTask>,
+}
+
+impl GeneratedTaskRecord {
+    /// Build a record of the given generated task.
+    ///
+    /// # Arguments
+    ///
+    /// * `generated_task` - Generated task to record.
+    ///
+    /// # Returns
+    ///
+    /// Record of the generated task.
+    pub fn from_suite(generated_task: &dyn GeneratedSuite) -> Self {
+        Self {
+            display_name: generated_task.display_name(),
+            sub_tasks: generated_task.sub_tasks(),
+        }
+    }
+
+    /// Rebuild the generated task this record was built from.
+    pub fn into_suite(self) -> Box<dyn GeneratedSuite> {
+        Box::new(GeneratedResmokeSuite {
+            task_name: self.display_name,
+            sub_suites: self.sub_tasks,
+        })
+    }
+}
+
+/// Build a record of the given generated tasks.
+///
+/// # Arguments
+///
+/// * `generated_tasks` - Map of generated task names to their generated configuration.
+///
+/// # Returns
+///
+/// Map of generated task names to their records, sorted by name.
+pub fn record_generated_tasks(
+    generated_tasks: &GenTaskCollection,
+) -> BTreeMap<String, GeneratedTaskRecord> {
+    generated_tasks
+        .iter()
+        .map(|entry| {
+            (
+                entry.key().clone(),
+                GeneratedTaskRecord::from_suite(entry.value().as_ref()),
+            )
+        })
+        .collect()
+}
+
+/// Read the generated tasks recorded in a generated configuration directory.
+///
+/// # Arguments
+///
+/// * `directory` - Directory containing a previously generated configuration.
+///
+/// # Returns
+///
+/// Map of generated task names to their generated configuration.
+pub fn read_generated_tasks(directory: &Path) -> Result<GenTaskCollection> {
+    let contents = std::fs::read_to_string(directory.join(GENERATED_TASKS_FILE))?;
+    let records: BTreeMap<String, GeneratedTaskRecord> = serde_json::from_str(&contents)?;
+
+    let generated_tasks = GenTaskCollection::new();
+    for (task_name, record) in records {
+        generated_tasks.insert(task_name, record.into_suite());
+    }
+    Ok(generated_tasks)
+}
+
+/// Copy the files of a previous generation into the directory its build variants are regenerated
+/// into.
+///
+/// Regenerating the build variants only rewrites `evergreen_config.json` and the reports about it,
+/// so the resmoke suite files and other files it refers to are copied over to make the target
+/// directory usable on its own. Error reports, checkpoints and the manifest of the previous
+/// generation are not copied. Nothing is copied if both directories are the same.
+///
+/// # Arguments
+///
+/// * `source_directory` - Directory containing a previously generated configuration.
+/// * `target_directory` - Directory the build variants are regenerated into.
+pub fn copy_generated_files(source_directory: &Path, target_directory: &Path) -> Result<()> {
+    std::fs::create_dir_all(target_directory)?;
+    if source_directory.canonicalize()? == target_directory.canonicalize()? {
+        return Ok(());
+    }
+
+    let skipped = |file: &PathBuf| {
+        file == Path::new(ERROR_REPORT_FILE)
+            || file == Path::new(MANIFEST_FILE)
+            || file.starts_with(CHECKPOINT_DIR)
+    };
+    for file in list_files(source_directory, Path::new(""))? {
+        if skipped(&file) {
+            continue;
+        }
+        let target_file = target_directory.join(&file);
+        if let Some(parent) = target_file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(source_directory.join(&file), target_file)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use shrub_rs::models::task::EvgTask;
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_generated_tasks_should_round_trip() {
+        let tmp_dir = TempDir::new("generated_tasks").unwrap();
+        let directory = tmp_dir.path();
+        let generated_tasks = GenTaskCollection::new();
+        generated_tasks.insert(
+            "auth-linux".to_string(),
+            Box::new(GeneratedResmokeSuite {
+                task_name: "auth".to_string(),
+                sub_suites: vec![GeneratedSubTask {
+                    evg_task: EvgTask {
+                        name: "auth_0-linux".to_string(),
+                        ..Default::default()
+                    },
+                    use_large_distro: true,
+                    ..Default::default()
+                }],
+            }),
+        );
+
+        std::fs::write(
+            directory.join(GENERATED_TASKS_FILE),
+            serde_json::to_string(&record_generated_tasks(&generated_tasks)).unwrap(),
+        )
+        .unwrap();
+        let read_tasks = read_generated_tasks(directory).unwrap();
+
+        let read_task = read_tasks.get("auth-linux").unwrap();
+        assert_eq!(read_task.display_name(), "auth");
+        let sub_tasks = read_task.sub_tasks();
+        assert_eq!(sub_tasks.len(), 1);
+        assert_eq!(sub_tasks[0].evg_task.name, "auth_0-linux");
+        assert!(sub_tasks[0].use_large_distro);
+    }
+
+    #[test]
+    fn test_copy_generated_files_should_copy_suite_files_but_not_reports() {
+        let tmp_dir = TempDir::new("copy_generated").unwrap();
+        let source_directory = tmp_dir.path().join("source");
+        let target_directory = tmp_dir.path().join("target");
+        std::fs::create_dir_all(source_directory.join("nested")).unwrap();
+        std::fs::create_dir_all(source_directory.join(CHECKPOINT_DIR)).unwrap();
+        std::fs::write(source_directory.join(GENERATED_TASKS_FILE), "{}").unwrap();
+        std::fs::write(source_directory.join("nested").join("auth_0.yml"), "").unwrap();
+        std::fs::write(source_directory.join(ERROR_REPORT_FILE), "").unwrap();
+        std::fs::write(source_directory.join(CHECKPOINT_DIR).join("auth.json"), "").unwrap();
+
+        copy_generated_files(&source_directory, &target_directory).unwrap();
+        // Copying into the same directory should leave it as is.
+        copy_generated_files(&source_directory, &source_directory).unwrap();
+        let mut copied = list_files(&target_directory, Path::new("")).unwrap();
+        copied.sort();
+        let source_files = list_files(&source_directory, Path::new("")).unwrap();
+
+        assert_eq!(
+            copied,
+            vec![
+                PathBuf::from(GENERATED_TASKS_FILE),
+                Path::new("nested").join("auth_0.yml"),
+            ]
+        );
+        assert_eq!(source_files.len(), 4);
+    }
+}
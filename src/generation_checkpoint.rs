@@ -0,0 +1,334 @@
+//! Checkpoints of the tasks generated so far by a run of the generator.
+//!
+//! Each generated task is written to its own file in a checkpoint directory as soon as it has
+//! been generated. If generation is interrupted, a later run started with `--resume` reuses the
+//! checkpointed tasks instead of generating them again. The checkpoints are removed once the
+//! configuration has been generated successfully.
+//!
+//! A fingerprint of the inputs of the run is stored with the checkpoints. Checkpoints of a run
+//! with different inputs are discarded instead of being resumed, so tasks generated from an old
+//! project configuration or with other options never end up in the output.
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{event, Level};
+
+use crate::{
+    generated_tasks_file::GeneratedTaskRecord, manifest::file_sha256,
+    task_types::generated_suite::GeneratedSuite, GenTaskCollection,
+};
+
+/// Name of the directory in the target directory to write checkpoints to.
+pub const CHECKPOINT_DIR: &str = "generation_checkpoints";
+/// Name of the file in the checkpoint directory holding the fingerprint of the run's inputs.
+const INPUT_FINGERPRINT_FILE: &str = "inputs.sha256";
+
+/// Compute a fingerprint of the inputs of a run.
+///
+/// # Arguments
+///
+/// * `input_files` - Files the run reads its configuration from.
+/// * `options` - Description of the options the run was started with.
+///
+/// # Returns
+///
+/// Hex encoded SHA-256 of the contents of the files and the options.
+pub fn input_fingerprint(input_files: &[&Path], options: &str) -> Result<String> {
+    let mut hasher = Sha256::new();
+    for input_file in input_files {
+        hasher.update(file_sha256(input_file)?.as_bytes());
+    }
+    hasher.update(options.as_bytes());
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Contents of the checkpoint file of a single generated task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TaskCheckpoint {
+    /// Name the generated task is stored under.
+    task_name: String,
+    /// Record of the generated task.
+    #[serde(flatten)]
+    record: GeneratedTaskRecord,
+}
+
+/// Checkpoints of the tasks generated into a target directory.
+#[derive(Debug)]
+pub struct GenerationCheckpoint {
+    /// Directory checkpoints are written to.
+    directory: PathBuf,
+    /// Reuse the checkpoints of an earlier, interrupted run.
+    resume: bool,
+    /// Fingerprint of the inputs of the run.
+    input_fingerprint: String,
+    /// Whether generated tasks are being checkpointed.
+    enabled: AtomicBool,
+}
+
+impl GenerationCheckpoint {
+    /// Create the checkpoints for generating into the given directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `target_directory` - Directory generated configuration is written to.
+    /// * `resume` - Reuse the checkpoints of an earlier, interrupted run.
+    /// * `input_fingerprint` - Fingerprint of the inputs of the run.
+    ///
+    /// # Returns
+    ///
+    /// Checkpoints of the tasks generated into the directory.
+    pub fn new(target_directory: &Path, resume: bool, input_fingerprint: &str) -> Self {
+        Self {
+            directory: target_directory.join(CHECKPOINT_DIR),
+            resume,
+            input_fingerprint: input_fingerprint.to_string(),
+            enabled: AtomicBool::new(false),
+        }
+    }
+
+    /// Prepare the checkpoint directory for a run.
+    ///
+    /// Checkpoints left by an earlier run are removed unless the run is being resumed with the
+    /// same inputs.
+    pub fn start(&self) -> Result<()> {
+        if self.directory.exists() && !(self.resume && self.matches_inputs()) {
+            if self.resume {
+                event!(
+                    Level::WARN,
+                    "Discarding checkpoints of a run with different inputs"
+                );
+            }
+            std::fs::remove_dir_all(&self.directory)?;
+        }
+        std::fs::create_dir_all(&self.directory)?;
+        std::fs::write(
+            self.directory.join(INPUT_FINGERPRINT_FILE),
+            &self.input_fingerprint,
+        )?;
+        self.enabled.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Whether generated tasks are being checkpointed, i.e. the checkpoints have been started and
+    /// not finished yet.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::SeqCst)
+    }
+
+    /// Whether the checkpoints were written by a run with the same inputs as this run.
+    fn matches_inputs(&self) -> bool {
+        std::fs::read_to_string(self.directory.join(INPUT_FINGERPRINT_FILE))
+            .map(|fingerprint| fingerprint == self.input_fingerprint)
+            .unwrap_or(false)
+    }
+
+    /// Read the tasks completed by an earlier run.
+    ///
+    /// Checkpoint files that cannot be read, for example because the run was interrupted while
+    /// writing them, are ignored and their tasks are generated again.
+    ///
+    /// # Returns
+    ///
+    /// Map of the names of completed tasks to their generated configuration. This is empty
+    /// unless the run is being resumed.
+    pub fn completed_tasks(&self) -> Result<GenTaskCollection> {
+        let completed_tasks = GenTaskCollection::new();
+        if !self.resume || !self.directory.exists() {
+            return Ok(completed_tasks);
+        }
+
+        for entry in std::fs::read_dir(&self.directory)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let checkpoint = std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|contents| Ok(serde_json::from_str::<TaskCheckpoint>(&contents)?));
+            match checkpoint {
+                Ok(checkpoint) => {
+                    completed_tasks.insert(checkpoint.task_name, checkpoint.record.into_suite());
+                }
+                Err(err) => event!(
+                    Level::WARN,
+                    path = path.display().to_string(),
+                    error = err.to_string(),
+                    "Ignoring unreadable checkpoint"
+                ),
+            }
+        }
+        event!(
+            Level::INFO,
+            completed_tasks = completed_tasks.len(),
+            "Resuming generation from checkpoints"
+        );
+        Ok(completed_tasks)
+    }
+
+    /// Record that the given task has been generated.
+    ///
+    /// This should only be called once the suite files of the task have been written, since a
+    /// resumed run does not write them again.
+    ///
+    /// The checkpoint is written to a temporary file first and then moved into place, so an
+    /// interrupted write never leaves a partial checkpoint behind.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_name` - Name the generated task is stored under.
+    /// * `generated_task` - Generated task to record.
+    pub fn record(&self, task_name: &str, generated_task: &dyn GeneratedSuite) -> Result<()> {
+        let checkpoint = TaskCheckpoint {
+            task_name: task_name.to_string(),
+            record: GeneratedTaskRecord::from_suite(generated_task),
+        };
+        let file_name = task_name.replace(['/', '\\'], "_");
+        let path = self.directory.join(format!("{}.json", file_name));
+        let tmp_path = self.directory.join(format!("{}.json.tmp", file_name));
+        std::fs::write(&tmp_path, serde_json::to_string(&checkpoint)?)?;
+        std::fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    /// Remove the checkpoints once generation has completed successfully.
+    pub fn finish(&self) -> Result<()> {
+        self.enabled.store(false, Ordering::SeqCst);
+        if self.directory.exists() {
+            std::fs::remove_dir_all(&self.directory)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shrub_rs::models::task::EvgTask;
+    use tempdir::TempDir;
+
+    use crate::task_types::{
+        generated_suite::GeneratedSubTask, resmoke_tasks::GeneratedResmokeSuite,
+    };
+
+    use super::*;
+
+    fn build_generated_task(display_name: &str) -> GeneratedResmokeSuite {
+        GeneratedResmokeSuite {
+            task_name: display_name.to_string(),
+            sub_suites: vec![GeneratedSubTask {
+                evg_task: EvgTask {
+                    name: format!("{}_0-linux", display_name),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }],
+        }
+    }
+
+    #[test]
+    fn test_completed_tasks_should_return_recorded_tasks_when_resuming() {
+        let tmp_dir = TempDir::new("checkpoint").unwrap();
+        let checkpoint = GenerationCheckpoint::new(tmp_dir.path(), false, "inputs_0");
+        checkpoint.start().unwrap();
+        checkpoint
+            .record("auth-linux", &build_generated_task("auth"))
+            .unwrap();
+
+        let resumed = GenerationCheckpoint::new(tmp_dir.path(), true, "inputs_0");
+        resumed.start().unwrap();
+        let completed_tasks = resumed.completed_tasks().unwrap();
+
+        assert_eq!(completed_tasks.len(), 1);
+        let completed_task = completed_tasks.get("auth-linux").unwrap();
+        assert_eq!(completed_task.display_name(), "auth");
+        assert_eq!(completed_task.sub_tasks()[0].evg_task.name, "auth_0-linux");
+    }
+
+    #[test]
+    fn test_start_should_remove_checkpoints_when_not_resuming() {
+        let tmp_dir = TempDir::new("checkpoint").unwrap();
+        let checkpoint = GenerationCheckpoint::new(tmp_dir.path(), true, "inputs_0");
+        checkpoint.start().unwrap();
+        checkpoint
+            .record("auth-linux", &build_generated_task("auth"))
+            .unwrap();
+
+        let restarted = GenerationCheckpoint::new(tmp_dir.path(), false, "inputs_0");
+        restarted.start().unwrap();
+
+        assert!(restarted.completed_tasks().unwrap().is_empty());
+        assert!(!tmp_dir
+            .path()
+            .join(CHECKPOINT_DIR)
+            .join("auth-linux.json")
+            .exists());
+    }
+
+    #[test]
+    fn test_start_should_remove_checkpoints_of_run_with_different_inputs() {
+        let tmp_dir = TempDir::new("checkpoint").unwrap();
+        let checkpoint = GenerationCheckpoint::new(tmp_dir.path(), true, "inputs_0");
+        checkpoint.start().unwrap();
+        checkpoint
+            .record("auth-linux", &build_generated_task("auth"))
+            .unwrap();
+
+        let resumed = GenerationCheckpoint::new(tmp_dir.path(), true, "inputs_1");
+        resumed.start().unwrap();
+
+        assert!(resumed.completed_tasks().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_input_fingerprint_should_change_with_inputs() {
+        let tmp_dir = TempDir::new("checkpoint").unwrap();
+        let input_file = tmp_dir.path().join("expansions.yml");
+        std::fs::write(&input_file, "build_variant: linux").unwrap();
+        let fingerprint = input_fingerprint(&[&input_file], "--resume").unwrap();
+
+        assert_eq!(
+            input_fingerprint(&[&input_file], "--resume").unwrap(),
+            fingerprint
+        );
+        assert_ne!(
+            input_fingerprint(&[&input_file], "--clean-target").unwrap(),
+            fingerprint
+        );
+        std::fs::write(&input_file, "build_variant: windows").unwrap();
+        assert_ne!(
+            input_fingerprint(&[&input_file], "--resume").unwrap(),
+            fingerprint
+        );
+    }
+
+    #[test]
+    fn test_completed_tasks_should_ignore_unreadable_checkpoints() {
+        let tmp_dir = TempDir::new("checkpoint").unwrap();
+        let checkpoint = GenerationCheckpoint::new(tmp_dir.path(), true, "inputs_0");
+        checkpoint.start().unwrap();
+        std::fs::write(
+            tmp_dir.path().join(CHECKPOINT_DIR).join("auth-linux.json"),
+            "{\"task_name\": ",
+        )
+        .unwrap();
+
+        assert!(checkpoint.completed_tasks().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_finish_should_remove_checkpoint_directory() {
+        let tmp_dir = TempDir::new("checkpoint").unwrap();
+        let checkpoint = GenerationCheckpoint::new(tmp_dir.path(), false, "inputs_0");
+        checkpoint.start().unwrap();
+
+        assert!(checkpoint.is_enabled());
+        checkpoint.finish().unwrap();
+
+        assert!(!checkpoint.is_enabled());
+        assert!(!tmp_dir.path().join(CHECKPOINT_DIR).exists());
+    }
+}
@@ -0,0 +1,120 @@
+//! Metadata describing how a generated configuration was produced.
+//!
+//! The metadata is written to a sidecar file next to `evergreen_config.json` so that anyone
+//! looking at a generated configuration can tell which release of the generator produced it and
+//! from which inputs.
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::{manifest::file_sha256, ProjectInfo};
+
+/// Name of file to write generation metadata to.
+pub const GENERATION_METADATA_FILE: &str = "generation_metadata.json";
+/// Version of the layout of the generated configuration files.
+///
+/// This should be incremented whenever the layout of the generated files changes in a way that
+/// consumers need to be aware of.
+pub const GENERATED_SCHEMA_VERSION: u32 = 1;
+
+/// Metadata describing how a generated configuration was produced.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenerationMetadata {
+    /// Version of the layout of the generated configuration files.
+    pub schema_version: u32,
+    /// Version of mongo-task-generator that produced the configuration.
+    pub generator_version: String,
+    /// Evergreen project the configuration was generated for.
+    pub evg_project: String,
+    /// SHA-256 of the evergreen project configuration generated from.
+    pub project_config_sha256: String,
+    /// SHA-256 of the sub-tasks configuration generated with, if one was used.
+    pub sub_tasks_config_sha256: Option<String>,
+    /// Time the configuration was generated at, in RFC 3339 format.
+    pub generated_at: String,
+}
+
+impl GenerationMetadata {
+    /// Create the metadata for generating the given project.
+    ///
+    /// # Arguments
+    ///
+    /// * `project_info` - Information about the project being generated.
+    ///
+    /// # Returns
+    ///
+    /// Metadata describing the generation.
+    pub fn new(project_info: &ProjectInfo) -> Result<Self> {
+        Ok(Self {
+            schema_version: GENERATED_SCHEMA_VERSION,
+            generator_version: env!("CARGO_PKG_VERSION").to_string(),
+            evg_project: project_info.evg_project.clone(),
+            project_config_sha256: file_sha256(&project_info.evg_project_location)?,
+            sub_tasks_config_sha256: project_info
+                .gen_sub_tasks_config_file
+                .as_deref()
+                .map(file_sha256)
+                .transpose()?,
+            generated_at: Utc::now().to_rfc3339(),
+        })
+    }
+
+    /// Read the metadata from a generated configuration directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `target_directory` - Directory containing the generated configuration.
+    ///
+    /// # Returns
+    ///
+    /// Metadata describing the generation.
+    pub fn from_directory(target_directory: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(target_directory.join(GENERATION_METADATA_FILE))?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Write the metadata to a generated configuration directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `target_directory` - Directory containing the generated configuration.
+    pub fn write(&self, target_directory: &Path) -> Result<()> {
+        std::fs::write(
+            target_directory.join(GENERATION_METADATA_FILE),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_generation_metadata_should_round_trip() {
+        let tmp_dir = TempDir::new("generation_metadata").unwrap();
+        let target_directory = tmp_dir.path();
+        let project_file = target_directory.join("evergreen.yml");
+        std::fs::write(&project_file, "").unwrap();
+        let project_info = ProjectInfo::new(&project_file, "my-project", None, None);
+
+        let metadata = GenerationMetadata::new(&project_info).unwrap();
+        metadata.write(target_directory).unwrap();
+        let read_metadata = GenerationMetadata::from_directory(target_directory).unwrap();
+
+        assert_eq!(metadata, read_metadata);
+        assert_eq!(metadata.schema_version, GENERATED_SCHEMA_VERSION);
+        assert_eq!(metadata.generator_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(metadata.evg_project, "my-project");
+        assert_eq!(
+            metadata.project_config_sha256,
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(metadata.sub_tasks_config_sha256, None);
+    }
+}
@@ -0,0 +1,176 @@
+//! Markdown summary of what was generated.
+//!
+//! The summary is written to the target directory so that the evergreen project can attach it
+//! to the description of a patch or to a GitHub pull request, showing at a glance which tasks
+//! were generated and which tests burn_in picked up.
+use std::{collections::BTreeMap, fmt::Write, path::Path};
+
+use anyhow::Result;
+
+use crate::{machine_time::MachineTimeReport, resmoke::burn_in_proxy::BuildVariantDiscovery};
+
+/// Name of file to write the summary to.
+pub const GENERATION_SUMMARY_FILE: &str = "generation_summary.md";
+
+/// Summary of a generation.
+#[derive(Debug, Clone, Default)]
+pub struct GenerationSummary {
+    /// Number of generated tasks.
+    pub generated_tasks: usize,
+    /// Number of generated sub-tasks.
+    pub generated_sub_tasks: usize,
+    /// Expected machine time of the generated configuration.
+    pub machine_time: MachineTimeReport,
+    /// What burn_in discovered, keyed by build variant name.
+    pub burn_in_discoveries: BTreeMap<String, BuildVariantDiscovery>,
+    /// Notable problems encountered during generation.
+    pub warnings: Vec<String>,
+}
+
+impl GenerationSummary {
+    /// Render the summary as Markdown.
+    ///
+    /// # Returns
+    ///
+    /// Markdown describing the generation.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::from("## Task generation summary\n\n");
+        writeln!(
+            markdown,
+            "Generated **{}** tasks with **{}** sub-tasks on **{}** build variants.",
+            self.generated_tasks,
+            self.generated_sub_tasks,
+            self.machine_time.build_variants.len(),
+        )
+        .unwrap();
+        writeln!(
+            markdown,
+            "\nExpected machine time: **{:.1}** hours.",
+            self.machine_time.total_expected_hours
+        )
+        .unwrap();
+
+        if !self.machine_time.build_variants.is_empty() {
+            markdown.push_str("\n<details>\n<summary>Build variants</summary>\n\n");
+            markdown.push_str("| Build variant | Sub-tasks | Expected hours |\n");
+            markdown.push_str("| --- | ---: | ---: |\n");
+            for (name, variant_time) in &self.machine_time.build_variants {
+                writeln!(
+                    markdown,
+                    "| {} | {} | {:.1} |",
+                    name, variant_time.sub_tasks, variant_time.expected_hours
+                )
+                .unwrap();
+            }
+            markdown.push_str("\n</details>\n");
+        }
+
+        let changed_tests: usize = self
+            .burn_in_discoveries
+            .values()
+            .map(|discovery| discovery.changed_tests.len() + discovery.changed_bazel_targets.len())
+            .sum();
+        if changed_tests > 0 {
+            markdown.push_str("\n### Burn-in\n\n");
+            for (build_variant, discovery) in &self.burn_in_discoveries {
+                if discovery.changed_tests.is_empty() && discovery.changed_bazel_targets.is_empty()
+                {
+                    continue;
+                }
+                writeln!(markdown, "**{}**\n", build_variant).unwrap();
+                for (test, reasons) in &discovery.changed_tests {
+                    let tasks: Vec<&str> = reasons
+                        .iter()
+                        .map(|reason| reason.task_name.as_str())
+                        .collect();
+                    writeln!(markdown, "- `{}` in {}", test, tasks.join(", ")).unwrap();
+                }
+                for (target, tasks) in &discovery.changed_bazel_targets {
+                    writeln!(markdown, "- `{}` in {}", target, tasks.join(", ")).unwrap();
+                }
+                markdown.push('\n');
+            }
+        }
+
+        if !self.warnings.is_empty() {
+            markdown.push_str("\n### Warnings\n\n");
+            for warning in &self.warnings {
+                writeln!(markdown, "- {}", warning).unwrap();
+            }
+        }
+
+        markdown
+    }
+
+    /// Write the summary to a generated configuration directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `target_directory` - Directory containing the generated configuration.
+    pub fn write(&self, target_directory: &Path) -> Result<()> {
+        std::fs::write(
+            target_directory.join(GENERATION_SUMMARY_FILE),
+            self.to_markdown(),
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        machine_time::VariantMachineTime,
+        resmoke::burn_in_proxy::{DiscoveredSuite, DiscoveredTask},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_to_markdown_should_describe_generation() {
+        let summary = GenerationSummary {
+            generated_tasks: 2,
+            generated_sub_tasks: 5,
+            machine_time: MachineTimeReport {
+                total_expected_hours: 2.5,
+                build_variants: BTreeMap::from([(
+                    "bv_0".to_string(),
+                    VariantMachineTime {
+                        expected_hours: 2.5,
+                        sub_tasks: 5,
+                        sub_tasks_without_estimate: 0,
+                    },
+                )]),
+            },
+            burn_in_discoveries: BTreeMap::from([(
+                "bv_0".to_string(),
+                BuildVariantDiscovery::new(vec![DiscoveredTask {
+                    task_name: "jsCore".to_string(),
+                    suites: vec![DiscoveredSuite {
+                        suite_name: "core".to_string(),
+                        test_list: vec!["jstests/core/test_0.js".to_string()],
+                    }],
+                    bazel_targets: vec![],
+                }]),
+            )]),
+            warnings: vec!["2 task history requests failed".to_string()],
+        };
+
+        let markdown = summary.to_markdown();
+
+        assert!(markdown.starts_with("## Task generation summary\n"));
+        assert!(markdown.contains("Generated **2** tasks with **5** sub-tasks on **1** build"));
+        assert!(markdown.contains("Expected machine time: **2.5** hours."));
+        assert!(markdown.contains("| bv_0 | 5 | 2.5 |"));
+        assert!(markdown.contains("- `jstests/core/test_0.js` in jsCore"));
+        assert!(markdown.contains("### Warnings\n\n- 2 task history requests failed"));
+    }
+
+    #[test]
+    fn test_to_markdown_should_skip_empty_sections() {
+        let markdown = GenerationSummary::default().to_markdown();
+
+        assert!(!markdown.contains("<details>"));
+        assert!(!markdown.contains("### Burn-in"));
+        assert!(!markdown.contains("### Warnings"));
+    }
+}
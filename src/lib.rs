@@ -8,62 +8,151 @@
 use core::panic;
 use std::{
     collections::{HashMap, HashSet},
+    fs::File,
+    io::{BufWriter, Write},
     path::{Path, PathBuf},
-    sync::{Arc, Mutex},
+    sync::Arc,
+    time::Duration,
     vec,
 };
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
+use config_schema::unknown_sub_tasks_config_keys;
+use config_upload::upload_generated_config;
+use dashmap::DashMap;
 use evergreen::{
     evg_config::{EvgConfigService, EvgProjectConfig},
     evg_config_utils::{EvgConfigUtils, EvgConfigUtilsImpl},
-    evg_task_history::{build_retryable_client, TaskHistoryServiceImpl},
+    evg_task_history::{
+        build_client, HistoryKeyTemplate, HistoryRetryConfig, TaskHistoryServiceImpl,
+    },
 };
 use evergreen_names::{
-    BURN_IN_TAGS, BURN_IN_TAG_COMPILE_TASK_DEPENDENCY, BURN_IN_TAG_INCLUDE_BUILD_VARIANTS,
-    BURN_IN_TASKS, BURN_IN_TESTS, ENTERPRISE_MODULE, GENERATOR_TASKS, UNIQUE_GEN_SUFFIX_EXPANSION,
+    ACTIVATE_GENERATED_TASKS, BURN_IN_TAGS, BURN_IN_TAG_COMPILE_TASK_DEPENDENCY,
+    BURN_IN_TAG_INCLUDE_BUILD_VARIANTS, BURN_IN_TAG_IN_BASE_VARIANT, BURN_IN_TASKS, BURN_IN_TESTS,
+    GENERATE_RESMOKE_TASKS, GENERATOR_TASKS, MAX_TOTAL_GENERATED_SUBTASKS_EXPANSION,
+    RUN_ON_VARIANT, UNIQUE_GEN_SUFFIX_EXPANSION,
 };
-use generate_sub_tasks_config::GenerateSubTasksConfig;
-use resmoke::{
-    burn_in_proxy::BurnInProxy,
-    resmoke_proxy::{ResmokeProxy, TestDiscovery},
+use generate_sub_tasks_config::{default_required_display_name_prefixes, GenerateSubTasksConfig};
+use generated_tasks_file::{
+    copy_generated_files, read_generated_tasks, record_generated_tasks, GENERATED_TASKS_FILE,
+};
+use generation_checkpoint::GenerationCheckpoint;
+use generation_summary::GenerationSummary;
+use machine_time::MachineTimeReport;
+use manifest::write_manifest;
+use ownership::{OwnershipService, OwnershipServiceImpl, TestOwnershipConfig};
+use rayon::prelude::*;
+use resmoke::{bazel_proxy::BazelProxy, burn_in_proxy::BurnInProxy, resmoke_proxy::ResmokeProxy};
+use serde::Serialize;
+use services::{
+    config_extraction::{
+        ConfigExtractionService, ConfigExtractionServiceImpl, SubTaskDemand, SubTaskLimits,
+    },
+    variant_cloning::{VariantCloningService, VariantCloningServiceImpl},
 };
-use services::config_extraction::{ConfigExtractionService, ConfigExtractionServiceImpl};
 use shrub_rs::models::{
     project::EvgProject,
     task::{EvgTask, TaskRef},
     variant::{BuildVariant, DisplayTask},
 };
 use task_types::{
+    antithesis_tasks::AntithesisTaskKind,
+    bazel_shard_tasks::BazelShardTaskKind,
     burn_in_tests::{BurnInService, BurnInServiceImpl},
-    fuzzer_tasks::{GenFuzzerService, GenFuzzerServiceImpl},
-    generated_suite::GeneratedSuite,
-    multiversion::MultiversionServiceImpl,
-    resmoke_config_writer::{ResmokeConfigActor, ResmokeConfigActorService},
-    resmoke_tasks::{GenResmokeConfig, GenResmokeTaskService, GenResmokeTaskServiceImpl},
+    fuzzer_tasks::{FuzzerTaskKind, GenFuzzerServiceImpl},
+    generated_suite::{GeneratedSubTask, GeneratedSuite},
+    generated_task_kind::GeneratedTaskKindRegistry,
+    history_coverage::suite_history_coverage,
+    multiversion::{write_multiversion_exclude_tags, MultiversionService, MultiversionServiceImpl},
+    resmoke_config_writer::{
+        ResmokeConfigActor, ResmokeConfigActorService, SuiteWriteAcks, SUITE_WRITE_ACKS,
+    },
+    resmoke_tasks::{GenResmokeConfig, GenResmokeTaskServiceImpl, ResmokeTaskKind},
+    task_inventory::build_task_inventory,
 };
 use tokio::{runtime::Handle, task::JoinHandle, time};
 use tracing::{event, Level};
-use utils::fs_service::FsServiceImpl;
-
+use utils::{
+    fs_service::FsServiceImpl,
+    task_name::{lookup_task_name, remove_gen_suffix},
+};
+use worker_watchdog::{WorkerCancelled, WorkerWatchdog};
+
+mod cli_config;
+mod config_diff;
+mod config_graph;
+mod config_schema;
+mod config_upload;
+mod error_report;
 mod evergreen;
 mod evergreen_names;
+mod evergreen_names_config;
 mod generate_sub_tasks_config;
+mod generated_tasks_file;
+mod generation_checkpoint;
+mod generation_metadata;
+mod generation_summary;
+mod machine_time;
+mod manifest;
+mod ownership;
 mod resmoke;
 mod services;
+mod shutdown;
+mod target_directory;
 mod task_types;
+#[cfg(feature = "test-support")]
+pub mod test_support;
 mod utils;
+mod worker_watchdog;
+
+pub use cli_config::args_with_config_file;
+pub use config_diff::{diff_generated_configs, ConfigDiff};
+pub use config_graph::{graph_generated_config, ConfigGraph};
+pub use config_schema::{check_config_files, write_schemas};
+pub use config_upload::UploadLocation;
+pub use error_report::{write_error_report, ErrorCategory};
+pub use evergreen::{
+    evg_project_source::resolve_evg_project_file,
+    evg_task_history::{
+        HistoryFetchStats, HistoryKeyTemplate, HookRuntimeHistory, TaskHistoryService,
+        TaskRuntimeHistory, TestRuntimeHistory, DEFAULT_HISTORY_KEY_TEMPLATE,
+    },
+    extra_test_stats::{ExtraTestStats, ExtraTestStatsHistoryService},
+};
+pub use evergreen_names_config::EvergreenNamesConfig;
+pub use generation_checkpoint::input_fingerprint;
+pub use generation_metadata::GenerationMetadata;
+pub use resmoke::{
+    burn_in_proxy::{BuildVariantDiscovery, BurnInDiscovery, DiscoveredTask},
+    native_discovery::NativeDiscoveryMode,
+    resmoke_proxy::{MultiversionConfig, TestDiscovery},
+    resmoke_suite::ResmokeSuiteConfig,
+};
+pub use shutdown::{wait_for_shutdown_signal, ShutdownSignal};
+pub use target_directory::prepare_target_directory;
+pub use task_types::{
+    history_coverage::{format_coverage_table, SuiteHistoryCoverage},
+    runtime_estimation::{FileSizeEstimator, RuntimeEstimator, SuiteAverageEstimator},
+    task_inventory::{format_task_table, GeneratedTaskInfo},
+};
+pub use utils::fs_service::FsService;
 
 const BURN_IN_TESTS_PREFIX: &str = "burn_in_tests";
 const BURN_IN_TASKS_PREFIX: &str = "burn_in_tasks";
 const BURN_IN_BV_SUFFIX: &str = "generated-by-burn-in-tags";
 const MAX_SUB_TASKS_PER_TASK: usize = 5;
+const BURN_IN_DISCOVERY_FILE: &str = "burn_in_discovery.json";
+const OWNERSHIP_REPORT_FILE: &str = "ownership_report.json";
+const SKIPPED_COVERED_TESTS_FILE: &str = "skipped_covered_tests.json";
+const BAZEL_BINARY: &str = "bazel";
 
-type GenTaskCollection = HashMap<String, Box<dyn GeneratedSuite>>;
+type GenTaskCollection = DashMap<String, Box<dyn GeneratedSuite>>;
 
 pub struct BurnInTagBuildVariantInfo {
     pub compile_task_dependency: String,
+    pub in_base_variant: bool,
 }
 
 /// Information about the Evergreen project being run against.
@@ -76,6 +165,9 @@ pub struct ProjectInfo {
 
     /// Path to the sub-tasks configuration file.
     pub gen_sub_tasks_config_file: Option<PathBuf>,
+
+    /// Path to the file overriding the names in evergreen_names.
+    pub evergreen_names_config_file: Option<PathBuf>,
 }
 
 impl ProjectInfo {
@@ -86,6 +178,7 @@ impl ProjectInfo {
     /// * `evg_project_location` - Path to the evergreen project configuration yaml.
     /// * `evg_project` - Evergreen project being run.
     /// * `gen_sub_tasks_config_file` - Path to the sub-tasks configuration file.
+    /// * `evergreen_names_config_file` - Path to the file overriding the names in evergreen_names.
     ///
     /// # Returns
     ///
@@ -94,25 +187,48 @@ impl ProjectInfo {
         evg_project_location: P,
         evg_project: &str,
         gen_sub_tasks_config_file: Option<P>,
+        evergreen_names_config_file: Option<P>,
     ) -> Self {
         Self {
             evg_project_location: evg_project_location.as_ref().to_path_buf(),
             evg_project: evg_project.to_string(),
             gen_sub_tasks_config_file: gen_sub_tasks_config_file.map(|p| p.as_ref().to_path_buf()),
+            evergreen_names_config_file: evergreen_names_config_file
+                .map(|p| p.as_ref().to_path_buf()),
         }
     }
 
     /// Get the project configuration for this project.
     pub fn get_project_config(&self) -> Result<EvgProjectConfig> {
-        Ok(EvgProjectConfig::new(&self.evg_project_location).expect("Could not find evg project"))
+        let module_configs = self
+            .get_generate_sub_tasks_config()?
+            .map(|config| config.module_evergreen_configs)
+            .unwrap_or_default();
+        EvgProjectConfig::new(
+            &self.evg_project_location,
+            &self.get_evergreen_names_config()?,
+            &module_configs,
+        )
+        .context(ErrorCategory::Config)
+    }
+
+    /// Get the overrides of the names in evergreen_names for this project.
+    pub fn get_evergreen_names_config(&self) -> Result<EvergreenNamesConfig> {
+        if let Some(evergreen_names_config_file) = &self.evergreen_names_config_file {
+            EvergreenNamesConfig::from_yaml_file(evergreen_names_config_file)
+                .context(ErrorCategory::Config)
+        } else {
+            Ok(EvergreenNamesConfig::default())
+        }
     }
 
     /// Get the generate sub-task configuration for this project.
     pub fn get_generate_sub_tasks_config(&self) -> Result<Option<GenerateSubTasksConfig>> {
         if let Some(gen_sub_tasks_config_file) = &self.gen_sub_tasks_config_file {
-            Ok(Some(GenerateSubTasksConfig::from_yaml_file(
-                gen_sub_tasks_config_file,
-            )?))
+            Ok(Some(
+                GenerateSubTasksConfig::from_yaml_file(gen_sub_tasks_config_file)
+                    .context(ErrorCategory::Config)?,
+            ))
         } else {
             Ok(None)
         }
@@ -141,17 +257,226 @@ pub struct ExecutionConfiguration<'a> {
     pub skip_covered_tests: bool,
     /// Command to execute burn_in_tests.
     pub burn_in_tests_command: &'a str,
+    /// Revision burn_in should compare changes against instead of the patch base.
+    pub burn_in_base_revision: Option<&'a str>,
     /// S3 endpoint to get test stats from.
     pub s3_test_stats_endpoint: &'a str,
+    /// Layout of the keys the stats of tasks are stored under.
+    pub history_key_template: &'a HistoryKeyTemplate,
+    /// Evergreen project to query for task history missing in the project being generated.
+    pub history_fallback_project: Option<&'a str>,
+    /// JSON file of test runtimes that take precedence over the runtime history of tests.
+    pub extra_test_stats_file: Option<&'a Path>,
+    /// Project-level expansions to use when a build variant does not define an expansion.
+    pub project_expansions: &'a HashMap<String, String>,
+    /// Fail if the sub-tasks configuration references items not in the project.
+    pub strict: bool,
+    /// Run the unsplit suite for tasks that fail to generate instead of failing generation.
+    pub allow_partial: bool,
+    /// File mapping test paths to the teams owning them.
+    pub test_ownership_file: Option<&'a Path>,
+    /// Fail if tests reported by test discovery do not exist.
+    pub fail_on_missing_tests: bool,
+    /// How to discover the tests of suites that do not need resmoke.
+    pub native_test_discovery: NativeDiscoveryMode,
+    /// Reuse the tasks checkpointed by an earlier, interrupted run.
+    pub resume: bool,
+    /// Fingerprint of the inputs of the run, used to discard checkpoints of other inputs.
+    pub input_fingerprint: &'a str,
+    /// How long a worker may spend generating a task before it is aborted.
+    pub worker_timeout: Option<Duration>,
+    /// Interval to report the progress of generation at.
+    pub monitor_interval: Duration,
+    /// Write the suite files of each task to a sub-directory named after the task.
+    pub nest_suite_files: bool,
+    /// Name sub-tasks with the index formatting used before the naming index options.
+    pub legacy_task_names: bool,
+}
+
+/// Options controlling how the generated configuration is written.
+#[derive(Debug, Clone, Default)]
+pub struct OutputOptions {
+    /// Write generated JSON files without pretty-printing them.
+    pub compact_json: bool,
+    /// S3 location to upload the generated configuration to.
+    pub upload_to: Option<UploadLocation>,
+    /// Metadata describing the generation to write alongside the generated configuration.
+    pub metadata: Option<GenerationMetadata>,
+    /// Names to use in place of the names in evergreen_names in the generated configuration.
+    pub evergreen_names: EvergreenNamesConfig,
+    /// Fail if the generated configuration is expected to use more machine hours than this.
+    pub max_machine_hours: Option<f64>,
+    /// Fail if a dependency on a task that is not generated refers to a missing task or build
+    /// variant.
+    pub strict_dependencies: bool,
+}
+
+/// Create the utilities to work with evergreen project configuration.
+///
+/// # Arguments
+///
+/// * `gen_sub_tasks_config` - Configuration for generating sub-tasks.
+/// * `project_expansions` - Project-level expansions to use when resolving run vars.
+/// * `evergreen_names` - Overrides of the names in evergreen_names for the project.
+///
+/// # Returns
+///
+/// Utilities configured for the project.
+fn build_evg_config_utils(
+    gen_sub_tasks_config: Option<&GenerateSubTasksConfig>,
+    project_expansions: &HashMap<String, String>,
+    evergreen_names: &EvergreenNamesConfig,
+) -> EvgConfigUtilsImpl {
+    let generator_functions = match gen_sub_tasks_config {
+        Some(config) if !config.generator_functions.is_empty() => {
+            config.generator_functions.clone()
+        }
+        _ => vec![GENERATE_RESMOKE_TASKS.to_string()],
+    };
+    EvgConfigUtilsImpl::with_config(
+        generator_functions,
+        evergreen_names.expansions_to_default(project_expansions),
+        gen_sub_tasks_config
+            .map(|c| c.enterprise_detection)
+            .unwrap_or_default(),
+    )
+}
+
+/// List the tasks that would be generated for the given project without generating them.
+///
+/// # Arguments
+///
+/// * `project_info` - Information about the project to list tasks for.
+/// * `project_expansions` - Project-level expansions to use when resolving run vars.
+///
+/// # Returns
+///
+/// Tasks that would be generated, sorted by name.
+pub fn list_generated_tasks(
+    project_info: &ProjectInfo,
+    project_expansions: &HashMap<String, String>,
+) -> Result<Vec<GeneratedTaskInfo>> {
+    let gen_sub_tasks_config = project_info.get_generate_sub_tasks_config()?;
+    let evg_config_service = project_info.get_project_config()?;
+    let evg_config_utils = Arc::new(build_evg_config_utils(
+        gen_sub_tasks_config.as_ref(),
+        project_expansions,
+        &project_info.get_evergreen_names_config()?,
+    ));
+
+    Ok(build_task_inventory(&evg_config_service, evg_config_utils))
+}
+
+/// Report how much of each resmoke suite on a build variant is covered by historic runtimes.
+///
+/// # Arguments
+///
+/// * `project_info` - Information about the project to report on.
+/// * `project_expansions` - Project-level expansions to use when resolving run vars.
+/// * `build_variant` - Build variant to report on.
+/// * `resmoke_command` - Command to invoke resmoke.
+/// * `s3_test_stats_endpoint` - S3 endpoint to get test stats from.
+/// * `history_key_template` - Layout of the keys the stats of tasks are stored under.
+/// * `history_fallback_project` - Evergreen project to query for missing task history.
+///
+/// # Returns
+///
+/// Coverage of each resmoke suite generated on the build variant, sorted by task name.
+pub async fn history_coverage(
+    project_info: &ProjectInfo,
+    project_expansions: &HashMap<String, String>,
+    build_variant: &str,
+    resmoke_command: &str,
+    s3_test_stats_endpoint: &str,
+    history_key_template: &HistoryKeyTemplate,
+    history_fallback_project: Option<&str>,
+) -> Result<Vec<SuiteHistoryCoverage>> {
+    let tasks = list_generated_tasks(project_info, project_expansions)?;
+    let discovery_service =
+        ResmokeProxy::new(resmoke_command, false, NativeDiscoveryMode::default());
+    let task_history_service = TaskHistoryServiceImpl::new(
+        build_client(),
+        s3_test_stats_endpoint.to_string(),
+        project_info.evg_project.clone(),
+        history_key_template.clone(),
+        project_info
+            .get_generate_sub_tasks_config()?
+            .map(|config| config.history_variant_aliases)
+            .unwrap_or_default(),
+        history_fallback_project.map(|project| project.to_string()),
+        HistoryRetryConfig::default(),
+    );
+
+    let mut coverage = vec![];
+    for task in tasks {
+        let suite = match &task.suite {
+            Some(suite) if task.build_variants.iter().any(|bv| bv == build_variant) => suite,
+            _ => continue,
+        };
+        let task_name = remove_gen_suffix(&task.name);
+        let test_list = discovery_service
+            .discover_tests(suite)
+            .context(ErrorCategory::Resmoke)?;
+        let task_history = match task_history_service
+            .get_task_history(task_name, build_variant)
+            .await
+        {
+            Ok(task_history) => Some(task_history),
+            Err(err) => {
+                event!(
+                    Level::WARN,
+                    task_name = task_name,
+                    error = err.to_string().as_str(),
+                    "Could not get task history"
+                );
+                None
+            }
+        };
+
+        coverage.push(suite_history_coverage(
+            task_name,
+            suite,
+            &test_list,
+            task_history.as_ref(),
+        ));
+    }
+    Ok(coverage)
+}
+
+/// Services to use instead of the default implementations when creating dependencies.
+///
+/// This allows the generator to be embedded with custom test discovery, task history or
+/// filesystem access, e.g. for a different test runner or for testing.
+#[derive(Clone, Default)]
+pub struct ServiceOverrides {
+    /// Service to discover the tests and configuration of suites.
+    pub discovery_service: Option<Arc<dyn TestDiscovery>>,
+    /// Service to look up the runtime history of tasks.
+    pub task_history_service: Option<Arc<dyn TaskHistoryService>>,
+    /// Service to discover the tasks and tests burn_in should run.
+    pub burn_in_discovery: Option<Arc<dyn BurnInDiscovery>>,
+    /// Service to work with the filesystem.
+    pub fs_service: Option<Arc<dyn FsService>>,
+    /// Service to estimate the runtime of tests without historic runtime data.
+    pub runtime_estimator: Option<Arc<dyn RuntimeEstimator>>,
 }
 
 /// Collection of services needed to execution.
 #[derive(Clone)]
 pub struct Dependencies {
+    evg_config_service: Arc<dyn EvgConfigService>,
     evg_config_utils: Arc<dyn EvgConfigUtils>,
     gen_task_service: Arc<dyn GenerateTasksService>,
     resmoke_config_actor: Arc<tokio::sync::Mutex<dyn ResmokeConfigActor>>,
     burn_in_service: Arc<dyn BurnInService>,
+    burn_in_discovery: Arc<dyn BurnInDiscovery>,
+    discovery_service: Arc<dyn TestDiscovery>,
+    multiversion_service: Arc<dyn MultiversionService>,
+    variant_cloning_service: Arc<dyn VariantCloningService>,
+    task_history_service: Arc<dyn TaskHistoryService>,
+    ownership_service: Arc<dyn OwnershipService>,
+    generation_checkpoint: Arc<GenerationCheckpoint>,
+    worker_watchdog: Arc<WorkerWatchdog>,
 }
 
 impl Dependencies {
@@ -165,33 +490,114 @@ impl Dependencies {
     ///
     /// A set of dependencies to run against.
     pub fn new(execution_config: ExecutionConfiguration) -> Result<Self> {
-        let fs_service = Arc::new(FsServiceImpl::new());
-        let discovery_service = Arc::new(ResmokeProxy::new(
-            execution_config.resmoke_command,
-            execution_config.skip_covered_tests,
-        ));
+        Self::with_overrides(execution_config, ServiceOverrides::default())
+    }
+
+    /// Create a new set of dependency instances, replacing some of the default services.
+    ///
+    /// # Arguments
+    ///
+    /// * `execution_config` - Information about how generation to take place.
+    /// * `overrides` - Services to use instead of the default implementations.
+    ///
+    /// # Returns
+    ///
+    /// A set of dependencies to run against.
+    pub fn with_overrides(
+        execution_config: ExecutionConfiguration,
+        overrides: ServiceOverrides,
+    ) -> Result<Self> {
+        let fs_service = overrides
+            .fs_service
+            .unwrap_or_else(|| Arc::new(FsServiceImpl::new()));
+        let discovery_service = overrides.discovery_service.unwrap_or_else(|| {
+            Arc::new(ResmokeProxy::new(
+                execution_config.resmoke_command,
+                execution_config.skip_covered_tests,
+                execution_config.native_test_discovery,
+            ))
+        });
+        let gen_sub_tasks_config = execution_config
+            .project_info
+            .get_generate_sub_tasks_config()?
+            .map(|mut config| {
+                if execution_config.legacy_task_names {
+                    config.task_naming = config.task_naming.with_legacy_index();
+                }
+                config
+            });
         let multiversion_service = Arc::new(MultiversionServiceImpl::new(
             discovery_service.get_multiversion_config()?,
+            gen_sub_tasks_config
+                .as_ref()
+                .and_then(|c| c.multiversion_old_versions.clone()),
         )?);
         let evg_config_service = Arc::new(execution_config.project_info.get_project_config()?);
-        let evg_config_utils = Arc::new(EvgConfigUtilsImpl::new());
+        if let Some(config) = &gen_sub_tasks_config {
+            let build_variant_names: HashSet<String> = evg_config_service
+                .get_build_variant_map()
+                .into_keys()
+                .collect();
+            let mut problems = config.validate(&build_variant_names);
+            if let Some(location) = &execution_config.project_info.gen_sub_tasks_config_file {
+                problems.extend(
+                    unknown_sub_tasks_config_keys(location).context(ErrorCategory::Config)?,
+                );
+            }
+            for problem in &problems {
+                event!(
+                    Level::WARN,
+                    problem = problem.as_str(),
+                    "Invalid sub-tasks config"
+                );
+            }
+            if execution_config.strict && !problems.is_empty() {
+                return Err(anyhow!(
+                    "Sub-tasks configuration is invalid: {}",
+                    problems.join("; ")
+                ))
+                .context(ErrorCategory::Validation);
+            }
+        }
+        let evg_config_utils = Arc::new(build_evg_config_utils(
+            gen_sub_tasks_config.as_ref(),
+            execution_config.project_expansions,
+            &execution_config.project_info.get_evergreen_names_config()?,
+        ));
         let gen_fuzzer_service = Arc::new(GenFuzzerServiceImpl::new());
-        let gen_sub_tasks_config = execution_config
-            .project_info
-            .get_generate_sub_tasks_config()?;
         let config_extraction_service = Arc::new(ConfigExtractionServiceImpl::new(
             evg_config_utils.clone(),
             multiversion_service.clone(),
             execution_config.generating_task.to_string(),
             execution_config.config_location.to_string(),
-            gen_sub_tasks_config,
-        ));
-        let client = build_retryable_client();
-        let task_history_service = Arc::new(TaskHistoryServiceImpl::new(
-            client,
-            execution_config.s3_test_stats_endpoint.to_string(),
-            execution_config.project_info.evg_project.clone(),
+            gen_sub_tasks_config.clone(),
+            execution_config.strict,
         ));
+        let task_history_service = overrides.task_history_service.unwrap_or_else(|| {
+            Arc::new(TaskHistoryServiceImpl::new(
+                build_client(),
+                execution_config.s3_test_stats_endpoint.to_string(),
+                execution_config.project_info.evg_project.clone(),
+                execution_config.history_key_template.clone(),
+                gen_sub_tasks_config
+                    .as_ref()
+                    .map(|config| config.history_variant_aliases.clone())
+                    .unwrap_or_default(),
+                execution_config
+                    .history_fallback_project
+                    .map(|project| project.to_string()),
+                HistoryRetryConfig::default(),
+            ))
+        });
+        let task_history_service: Arc<dyn TaskHistoryService> =
+            match execution_config.extra_test_stats_file {
+                Some(extra_test_stats_file) => Arc::new(ExtraTestStatsHistoryService::new(
+                    task_history_service,
+                    ExtraTestStats::from_json_file(extra_test_stats_file)
+                        .context(ErrorCategory::Config)?,
+                )),
+                None => task_history_service,
+            };
         let resmoke_config_actor =
             Arc::new(tokio::sync::Mutex::new(ResmokeConfigActorService::new(
                 discovery_service.clone(),
@@ -202,45 +608,121 @@ impl Dependencies {
                     .expect("Unexpected target directory"),
                 32,
             )));
-        let enterprise_dir = evg_config_service.get_module_dir(ENTERPRISE_MODULE);
+        let test_ownership_config = execution_config
+            .test_ownership_file
+            .map(TestOwnershipConfig::from_yaml_file)
+            .transpose()
+            .context(ErrorCategory::Config)?;
+        let ownership_service = Arc::new(OwnershipServiceImpl::new(test_ownership_config));
         let gen_resmoke_config = GenResmokeConfig::new(
             MAX_SUB_TASKS_PER_TASK,
             execution_config.use_task_split_fallback,
-            enterprise_dir,
+            evg_config_service.get_module_dirs(),
+            gen_sub_tasks_config
+                .as_ref()
+                .map(|c| c.empty_suite_fallback)
+                .unwrap_or_default(),
+            execution_config.fail_on_missing_tests,
+            execution_config.nest_suite_files,
         );
-        let gen_resmoke_task_service = Arc::new(GenResmokeTaskServiceImpl::new(
-            task_history_service,
-            discovery_service,
+        let mut gen_resmoke_task_service = GenResmokeTaskServiceImpl::new(
+            task_history_service.clone(),
+            discovery_service.clone(),
             resmoke_config_actor.clone(),
-            multiversion_service,
+            multiversion_service.clone(),
             fs_service,
+            ownership_service.clone(),
             gen_resmoke_config,
+        );
+        if let Some(runtime_estimator) = overrides.runtime_estimator {
+            gen_resmoke_task_service =
+                gen_resmoke_task_service.with_runtime_estimator(runtime_estimator);
+        }
+        let gen_resmoke_task_service = Arc::new(gen_resmoke_task_service);
+        let mut task_kind_registry = GeneratedTaskKindRegistry::new(
+            evg_config_utils.clone(),
+            Arc::new(ResmokeTaskKind::new(
+                evg_config_utils.clone(),
+                config_extraction_service.clone(),
+                gen_resmoke_task_service.clone(),
+                execution_config.allow_partial,
+                MAX_SUB_TASKS_PER_TASK,
+            )),
+        );
+        task_kind_registry.register_standard_kinds(
+            Arc::new(FuzzerTaskKind::new(
+                evg_config_utils.clone(),
+                config_extraction_service.clone(),
+                gen_fuzzer_service,
+            )),
+            Arc::new(AntithesisTaskKind::new(config_extraction_service.clone())),
+            Arc::new(BazelShardTaskKind::new(
+                evg_config_utils.clone(),
+                config_extraction_service.clone(),
+                Arc::new(BazelProxy::new(BAZEL_BINARY)),
+                task_history_service.clone(),
+                MAX_SUB_TASKS_PER_TASK,
+                execution_config.use_task_split_fallback,
+            )),
+        );
+        config_extraction_service.set_sub_task_demands(estimate_sub_task_demands(
+            &evg_config_service.get_build_variant_map(),
+            &evg_config_service.get_task_def_map(),
+            evg_config_utils.as_ref(),
+            &task_kind_registry,
         ));
         let gen_task_service = Arc::new(GenerateTasksServiceImpl::new(
-            evg_config_service,
+            evg_config_service.clone(),
             evg_config_utils.clone(),
-            gen_fuzzer_service,
-            gen_resmoke_task_service.clone(),
+            Arc::new(task_kind_registry),
             config_extraction_service.clone(),
             execution_config.gen_burn_in,
+            !execution_config.use_task_split_fallback,
+            gen_sub_tasks_config,
         ));
 
-        let burn_in_discovery = Arc::new(BurnInProxy::new(
-            execution_config.burn_in_tests_command,
-            &execution_config.project_info.evg_project_location,
-        ));
+        let burn_in_discovery = overrides.burn_in_discovery.unwrap_or_else(|| {
+            Arc::new(BurnInProxy::new(
+                execution_config.burn_in_tests_command,
+                &execution_config.project_info.evg_project_location,
+                execution_config.burn_in_base_revision,
+            ))
+        });
+        let variant_cloning_service = Arc::new(VariantCloningServiceImpl::new());
         let burn_in_service = Arc::new(BurnInServiceImpl::new(
-            burn_in_discovery,
+            burn_in_discovery.clone(),
             gen_resmoke_task_service,
             config_extraction_service,
             evg_config_utils.clone(),
+            variant_cloning_service.clone(),
+            task_history_service.clone(),
+            execution_config.use_task_split_fallback,
+        ));
+        let generation_checkpoint = Arc::new(GenerationCheckpoint::new(
+            execution_config.target_directory,
+            execution_config.resume,
+            execution_config.input_fingerprint,
+        ));
+        let worker_watchdog = Arc::new(WorkerWatchdog::new(
+            execution_config.worker_timeout,
+            execution_config.allow_partial,
+            execution_config.monitor_interval,
         ));
 
         Ok(Self {
+            evg_config_service,
             evg_config_utils,
             gen_task_service,
             resmoke_config_actor,
             burn_in_service,
+            burn_in_discovery,
+            discovery_service,
+            multiversion_service,
+            variant_cloning_service,
+            task_history_service,
+            ownership_service,
+            generation_checkpoint,
+            worker_watchdog,
         })
     }
 }
@@ -264,6 +746,18 @@ impl GeneratedConfig {
     }
 }
 
+/// Configuration generated for a single build variant.
+struct VariantGenerationResult {
+    /// Build variants to include in the generated configuration.
+    pub build_variants: Vec<BuildVariant>,
+    /// Configuration to attach to other build variants, keyed by build variant name.
+    pub run_on_variant_configs: HashMap<String, GeneratedConfig>,
+    /// Tasks that request a large distro the build variant does not define.
+    pub missing_distros: Vec<String>,
+    /// Whether the build variant runs the burn_in_tags task.
+    pub has_burn_in_tags: bool,
+}
+
 /// Create 'generate.tasks' configuration for all generated tasks in the provided evergreen
 /// project configuration.
 ///
@@ -271,48 +765,608 @@ impl GeneratedConfig {
 ///
 /// * `deps` - Dependencies needed to perform generation.
 /// * `target_directory` - Directory to store generated configuration.
-pub async fn generate_configuration(deps: &Dependencies, target_directory: &Path) -> Result<()> {
+/// * `output_options` - Options controlling how the generated configuration is written.
+pub async fn generate_configuration(
+    deps: &Dependencies,
+    target_directory: &Path,
+    output_options: &OutputOptions,
+) -> Result<()> {
     let generate_tasks_service = deps.gen_task_service.clone();
     std::fs::create_dir_all(target_directory)?;
+    deps.generation_checkpoint.start()?;
+    write_multiversion_exclude_tags(
+        deps.discovery_service.as_ref(),
+        &deps.multiversion_service.old_versions(),
+        target_directory,
+    )?;
 
     // We are going to do 2 passes through the project build variants. In this first pass, we
     // are actually going to create all the generated tasks that we discover.
     let generated_tasks = generate_tasks_service.build_generated_tasks(deps).await?;
+    let fetch_stats = deps.task_history_service.fetch_stats();
+    event!(
+        Level::INFO,
+        requests = fetch_stats.requests,
+        retries = fetch_stats.retries,
+        failures = fetch_stats.failures,
+        "Finished fetching task history"
+    );
 
     // Now that we have generated all the tasks we want to make another pass through all the
     // build variants and add references to the generated tasks that each build variant includes.
     let generated_build_variants =
-        generate_tasks_service.generate_build_variants(deps, generated_tasks.clone())?;
-
-    let task_defs: Vec<EvgTask> = {
-        let generated_tasks = generated_tasks.lock().unwrap();
-        generated_tasks
-            .values()
-            .flat_map(|g| g.sub_tasks())
-            .map(|s| s.evg_task)
-            .collect()
-    };
+        generate_build_variants_blocking(deps, generated_tasks.clone()).await?;
+
+    let duplicates = find_duplicate_sub_task_names(&generated_tasks);
+    if !duplicates.is_empty() {
+        return Err(anyhow!(
+            "Generated sub-task names are not unique:\n{}",
+            duplicates.join("\n")
+        ))
+        .context(ErrorCategory::Validation);
+    }
+    let budget_overruns = sub_task_budget_overruns(
+        &generated_build_variants,
+        &deps.evg_config_service.get_build_variant_map(),
+        deps.evg_config_utils.as_ref(),
+    );
+    if !budget_overruns.is_empty() {
+        return Err(anyhow!(
+            "Generated sub-tasks exceed the budget of their build variant:\n{}",
+            budget_overruns.join("\n")
+        ))
+        .context(ErrorCategory::Validation);
+    }
+    let machine_time = write_generated_config(
+        &generated_tasks,
+        generated_build_variants,
+        deps.evg_config_service.as_ref(),
+        &deps.variant_cloning_service.clone_bases(),
+        target_directory,
+        output_options,
+    )?;
+    let mut generated_tasks_file = target_directory.to_path_buf();
+    generated_tasks_file.push(GENERATED_TASKS_FILE);
+    write_json_file(
+        &generated_tasks_file,
+        &record_generated_tasks(&generated_tasks),
+        output_options.compact_json,
+    )?;
+
+    let burn_in_discoveries = deps.burn_in_discovery.discovery_report();
+    if !burn_in_discoveries.is_empty() {
+        let mut discovery_file = target_directory.to_path_buf();
+        discovery_file.push(BURN_IN_DISCOVERY_FILE);
+        write_json_file(
+            &discovery_file,
+            &burn_in_discoveries,
+            output_options.compact_json,
+        )?;
+    }
+    GenerationSummary {
+        generated_tasks: generated_tasks.len(),
+        generated_sub_tasks: generated_tasks
+            .iter()
+            .map(|entry| entry.value().sub_tasks().len())
+            .sum(),
+        machine_time,
+        burn_in_discoveries,
+        warnings: generation_warnings(deps, fetch_stats.failures),
+    }
+    .write(target_directory)?;
+    let ownership_report = deps.ownership_service.ownership_report();
+    if !ownership_report.is_empty() {
+        let mut ownership_file = target_directory.to_path_buf();
+        ownership_file.push(OWNERSHIP_REPORT_FILE);
+        write_json_file(
+            &ownership_file,
+            &ownership_report,
+            output_options.compact_json,
+        )?;
+    }
+    let skipped_covered_tests = deps.discovery_service.skipped_covered_tests();
+    if !skipped_covered_tests.is_empty() {
+        let mut skipped_file = target_directory.to_path_buf();
+        skipped_file.push(SKIPPED_COVERED_TESTS_FILE);
+        write_json_file(
+            &skipped_file,
+            &skipped_covered_tests,
+            output_options.compact_json,
+        )?;
+    }
+    let mut resmoke_config_actor = deps.resmoke_config_actor.lock().await;
+    let failures = resmoke_config_actor.flush().await?;
+    if !failures.is_empty() {
+        return Err(anyhow!(
+            "Encountered errors writing resmoke configuration files: {:?}",
+            failures
+        ))
+        .context(ErrorCategory::Resmoke);
+    }
+    deps.generation_checkpoint.finish()?;
+    write_manifest(target_directory)?;
 
-    let gen_evg_project = EvgProject {
-        buildvariants: generated_build_variants.to_vec(),
-        tasks: task_defs,
-        ..Default::default()
-    };
+    if let Some(upload_to) = &output_options.upload_to {
+        upload_generated_config(target_directory, upload_to).await?;
+    }
+    Ok(())
+}
 
-    let mut config_file = target_directory.to_path_buf();
-    config_file.push("evergreen_config.json");
-    std::fs::write(config_file, serde_json::to_string_pretty(&gen_evg_project)?)?;
+/// Finish writing the files of a generation that was interrupted.
+///
+/// Resmoke configuration files that have already been queued are written out, so no suite files
+/// are left partially written.
+///
+/// # Arguments
+///
+/// * `deps` - Dependencies the interrupted generation was using.
+pub async fn finish_interrupted_generation(deps: &Dependencies) -> Result<()> {
     let mut resmoke_config_actor = deps.resmoke_config_actor.lock().await;
     let failures = resmoke_config_actor.flush().await?;
     if !failures.is_empty() {
-        bail!(format!(
+        return Err(anyhow!(
             "Encountered errors writing resmoke configuration files: {:?}",
             failures
+        ))
+        .context(ErrorCategory::Resmoke);
+    }
+    Ok(())
+}
+
+/// Regenerate only the build variants of a configuration against previously generated tasks.
+///
+/// The tasks recorded by an earlier generation are reused as they are, so only the membership of
+/// build variants and their display tasks is computed again. This is much faster than generating
+/// the tasks when only which build variants run which tasks has changed. The other files of the
+/// earlier generation, such as resmoke suite files, are copied into the target directory.
+///
+/// # Arguments
+///
+/// * `deps` - Dependencies needed to perform generation.
+/// * `generated_tasks_directory` - Directory containing a previously generated configuration.
+/// * `target_directory` - Directory to store generated configuration.
+/// * `output_options` - Options controlling how the generated configuration is written.
+pub async fn regenerate_build_variants(
+    deps: &Dependencies,
+    generated_tasks_directory: &Path,
+    target_directory: &Path,
+    output_options: &OutputOptions,
+) -> Result<()> {
+    let generated_tasks = Arc::new(
+        read_generated_tasks(generated_tasks_directory)
+            .with_context(|| {
+                format!(
+                    "Could not read generated tasks from '{}'",
+                    generated_tasks_directory.display()
+                )
+            })
+            .context(ErrorCategory::Config)?,
+    );
+    copy_generated_files(generated_tasks_directory, target_directory).with_context(|| {
+        format!(
+            "Could not copy generated files from '{}'",
+            generated_tasks_directory.display()
+        )
+    })?;
+
+    let generated_build_variants =
+        generate_build_variants_blocking(deps, generated_tasks.clone()).await?;
+    event!(
+        Level::INFO,
+        generated_tasks = generated_tasks.len(),
+        "Regenerated build variants against previously generated tasks"
+    );
+
+    write_generated_config(
+        &generated_tasks,
+        generated_build_variants,
+        deps.evg_config_service.as_ref(),
+        &deps.variant_cloning_service.clone_bases(),
+        target_directory,
+        output_options,
+    )?;
+    write_manifest(target_directory)?;
+
+    if let Some(upload_to) = &output_options.upload_to {
+        upload_generated_config(target_directory, upload_to).await?;
+    }
+    Ok(())
+}
+
+/// Create the build variants referencing the given generated tasks.
+///
+/// The build variants are processed on the rayon thread pool, so the work is moved to a blocking
+/// thread instead of stalling the async runtime while it waits. This works on any runtime,
+/// including a current_thread one.
+///
+/// # Arguments
+///
+/// * `deps` - Dependencies needed to perform generation.
+/// * `generated_tasks` - Map of task names and their generated configuration.
+///
+/// # Returns
+///
+/// Build variants with references to the generated tasks.
+async fn generate_build_variants_blocking(
+    deps: &Dependencies,
+    generated_tasks: Arc<GenTaskCollection>,
+) -> Result<Vec<BuildVariant>> {
+    let deps = deps.clone();
+    tokio::task::spawn_blocking(move || {
+        deps.gen_task_service
+            .generate_build_variants(&deps, generated_tasks)
+    })
+    .await?
+}
+
+/// Write the evergreen configuration containing the given tasks and build variants.
+///
+/// A report of the machine time the configuration is expected to use is written alongside it.
+/// The dependencies of the configuration are checked before it is written.
+///
+/// # Arguments
+///
+/// * `generated_tasks` - Map of generated task names to their generated configuration.
+/// * `generated_build_variants` - Build variants referencing the generated tasks.
+/// * `evg_config_service` - Service to query the source evergreen project configuration.
+/// * `clone_bases` - Build variant each cloned build variant was cloned from.
+/// * `target_directory` - Directory to store generated configuration.
+/// * `output_options` - Options controlling how the generated configuration is written.
+///
+/// # Returns
+///
+/// Report of the machine time the configuration is expected to use.
+fn write_generated_config(
+    generated_tasks: &GenTaskCollection,
+    generated_build_variants: Vec<BuildVariant>,
+    evg_config_service: &dyn EvgConfigService,
+    clone_bases: &HashMap<String, String>,
+    target_directory: &Path,
+    output_options: &OutputOptions,
+) -> Result<MachineTimeReport> {
+    let sub_tasks: Vec<GeneratedSubTask> = generated_tasks
+        .iter()
+        .flat_map(|g| g.value().sub_tasks())
+        .collect();
+    let machine_time_report = MachineTimeReport::new(&sub_tasks, &generated_build_variants);
+    let task_defs: Vec<EvgTask> = sub_tasks.into_iter().map(|s| s.evg_task).collect();
+
+    let mut gen_evg_project = EvgProject {
+        buildvariants: generated_build_variants,
+        tasks: task_defs,
+        ..Default::default()
+    };
+    check_generated_dependencies(
+        &gen_evg_project,
+        evg_config_service,
+        clone_bases,
+        output_options.strict_dependencies,
+    )?;
+    output_options
+        .evergreen_names
+        .translate_to_project(&mut gen_evg_project);
+
+    let mut config_file = target_directory.to_path_buf();
+    config_file.push("evergreen_config.json");
+    write_json_file(&config_file, &gen_evg_project, output_options.compact_json)?;
+    if let Some(metadata) = &output_options.metadata {
+        metadata.write(target_directory)?;
+    }
+
+    machine_time_report.write(target_directory)?;
+    event!(
+        Level::INFO,
+        expected_hours = machine_time_report.total_expected_hours,
+        build_variants = machine_time_report.build_variants.len(),
+        sub_tasks_without_estimate = machine_time_report.sub_tasks_without_estimate(),
+        "Expected machine time of generated configuration"
+    );
+    machine_time_report
+        .check_budget(output_options.max_machine_hours)
+        .context(ErrorCategory::Validation)?;
+    Ok(machine_time_report)
+}
+
+/// Check that the dependencies of a generated configuration are valid.
+///
+/// Evergreen rejects the whole generate.tasks call if any dependency refers to a task or build
+/// variant that does not exist or if the dependencies form a cycle, so these are reported up
+/// front instead. Invalid dependencies on tasks that are not generated, like the compile task
+/// burn_in_tags build variants depend on, are only logged unless `strict` is set.
+///
+/// # Arguments
+///
+/// * `gen_evg_project` - Generated configuration to check.
+/// * `evg_config_service` - Service to query the source evergreen project configuration.
+/// * `clone_bases` - Build variant each cloned build variant was cloned from.
+/// * `strict` - Fail on invalid dependencies on tasks that are not generated.
+fn check_generated_dependencies(
+    gen_evg_project: &EvgProject,
+    evg_config_service: &dyn EvgConfigService,
+    clone_bases: &HashMap<String, String>,
+    strict: bool,
+) -> Result<()> {
+    let source_build_variants: HashSet<String> = evg_config_service
+        .get_build_variant_map()
+        .into_keys()
+        .collect();
+    let source_tasks: HashSet<String> = evg_config_service.get_task_def_map().into_keys().collect();
+
+    let graph = ConfigGraph::new(gen_evg_project);
+    if !strict {
+        for problem in
+            graph.missing_dependencies(&source_build_variants, &source_tasks, clone_bases, true)
+        {
+            event!(
+                Level::WARN,
+                problem = problem.as_str(),
+                "Dependency on a task that is not generated is invalid"
+            );
+        }
+    }
+    let problems =
+        graph.dependency_problems(&source_build_variants, &source_tasks, clone_bases, strict);
+    if !problems.is_empty() {
+        return Err(anyhow!(
+            "Generated configuration has invalid dependencies:\n{}",
+            problems.join("\n")
+        ))
+        .context(ErrorCategory::Validation);
+    }
+    Ok(())
+}
+
+/// Collect the notable problems encountered during generation.
+///
+/// # Arguments
+///
+/// * `deps` - Dependencies used to perform generation.
+/// * `history_fetch_failures` - Number of requests for task history that failed.
+///
+/// # Returns
+///
+/// Description of each problem.
+fn generation_warnings(deps: &Dependencies, history_fetch_failures: u64) -> Vec<String> {
+    let mut warnings = vec![];
+    if history_fetch_failures > 0 {
+        warnings.push(format!(
+            "{} task histories could not be fetched, the affected tasks were split without history",
+            history_fetch_failures
+        ));
+    }
+    let hung_workers = deps.worker_watchdog.hung_workers();
+    if !hung_workers.is_empty() {
+        warnings.push(format!(
+            "Generation did not finish in time for {}, the unsplit suites run instead",
+            hung_workers.join(", ")
         ));
     }
+    let skipped_covered_tests: usize = deps
+        .discovery_service
+        .skipped_covered_tests()
+        .values()
+        .map(|tests| tests.len())
+        .sum();
+    if skipped_covered_tests > 0 {
+        warnings.push(format!(
+            "{} tests were skipped because more complex suites cover them",
+            skipped_covered_tests
+        ));
+    }
+    warnings
+}
+
+/// Serialize the given value as JSON directly to a file.
+///
+/// The value is streamed to the file rather than built up as a string in memory first.
+///
+/// # Arguments
+///
+/// * `path` - File to write.
+/// * `value` - Value to serialize.
+/// * `compact` - Write the JSON without pretty-printing it.
+fn write_json_file<T: Serialize>(path: &Path, value: &T, compact: bool) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    if compact {
+        serde_json::to_writer(&mut writer, value)?;
+    } else {
+        serde_json::to_writer_pretty(&mut writer, value)?;
+    }
+    writer.flush()?;
     Ok(())
 }
 
+/// Group "_gen" tasks into display tasks.
+///
+/// # Arguments
+///
+/// * `generating_tasks` - Names of "_gen" tasks and the display task they belong to.
+///
+/// # Returns
+///
+/// Display tasks containing the "_gen" tasks, in the order they were first seen.
+fn group_generator_tasks(
+    generating_tasks: impl Iterator<Item = (String, String)>,
+) -> Vec<DisplayTask> {
+    let mut display_tasks: Vec<DisplayTask> = vec![];
+    for (task_name, display_task_name) in generating_tasks {
+        match display_tasks
+            .iter_mut()
+            .find(|display_task| display_task.name == display_task_name)
+        {
+            Some(display_task) => display_task.execution_tasks.push(task_name),
+            None => display_tasks.push(DisplayTask {
+                name: display_task_name,
+                execution_tasks: vec![task_name],
+            }),
+        }
+    }
+    display_tasks
+}
+
+/// Estimate the sub-tasks the generated tasks on each build variant will be split into.
+///
+/// Each task is estimated by the kind of generated task the `GeneratedTaskKindRegistry` selects
+/// for it, so a task is counted as the same kind here as when it is generated.
+///
+/// # Arguments
+///
+/// * `build_variant_map` - Map of build variant names to their definitions.
+/// * `task_map` - Map of task names to their definitions.
+/// * `evg_config_utils` - Utilities to work with evergreen project configuration.
+/// * `task_kind_registry` - Registry of the kinds of tasks that can be generated.
+///
+/// # Returns
+///
+/// Map of build variant names to the sub-tasks their generated tasks are expected to need.
+fn estimate_sub_task_demands(
+    build_variant_map: &HashMap<String, &BuildVariant>,
+    task_map: &HashMap<String, Arc<EvgTask>>,
+    evg_config_utils: &dyn EvgConfigUtils,
+    task_kind_registry: &GeneratedTaskKindRegistry,
+) -> HashMap<String, SubTaskDemand> {
+    build_variant_map
+        .iter()
+        .map(|(name, build_variant)| {
+            let mut demand = SubTaskDemand::default();
+            let task_defs = build_variant
+                .tasks
+                .iter()
+                .filter_map(|task| task_map.get(&task.name))
+                .filter(|task_def| evg_config_utils.is_task_generated(task_def));
+            for task_def in task_defs {
+                demand += task_kind_registry
+                    .lookup(task_def)
+                    .sub_task_demand(task_def, build_variant);
+            }
+            (name.clone(), demand)
+        })
+        .collect()
+}
+
+/// Find the build variants whose generated sub-tasks exceed their `max_total_generated_subtasks`
+/// budget.
+///
+/// Tasks with a fixed number of sub-tasks and the single sub-task every split task gets can
+/// exceed the budget, in which case it cannot be met.
+///
+/// # Arguments
+///
+/// * `generated_build_variants` - Build variants referencing the generated sub-tasks.
+/// * `build_variant_map` - Map of build variant names to their definitions.
+/// * `evg_config_utils` - Utilities to work with evergreen project configuration.
+///
+/// # Returns
+///
+/// Description of each build variant exceeding its budget.
+fn sub_task_budget_overruns(
+    generated_build_variants: &[BuildVariant],
+    build_variant_map: &HashMap<String, &BuildVariant>,
+    evg_config_utils: &dyn EvgConfigUtils,
+) -> Vec<String> {
+    let mut overruns: Vec<String> = generated_build_variants
+        .iter()
+        .filter_map(|generated_build_variant| {
+            let build_variant = build_variant_map.get(&generated_build_variant.name)?;
+            let max_total = evg_config_utils
+                .lookup_build_variant_expansion(
+                    MAX_TOTAL_GENERATED_SUBTASKS_EXPANSION,
+                    build_variant,
+                )?
+                .trim()
+                .parse::<usize>()
+                .ok()?;
+            let sub_tasks = generated_build_variant.tasks.len();
+            if sub_tasks <= max_total {
+                return None;
+            }
+            Some(format!(
+                "{} sub-tasks were generated for '{}', exceeding its `{}` of {}",
+                sub_tasks,
+                generated_build_variant.name,
+                MAX_TOTAL_GENERATED_SUBTASKS_EXPANSION,
+                max_total
+            ))
+        })
+        .collect();
+    overruns.sort();
+    overruns
+}
+
+/// Add the given generated configuration to the generated build variant with the given name.
+///
+/// If no build variant with the name has been generated, one is added containing only the given
+/// configuration.
+///
+/// # Arguments
+///
+/// * `generated_build_variants` - Build variants generated so far.
+/// * `bv_name` - Name of build variant to add the configuration to.
+/// * `config` - Generated configuration to add.
+fn merge_generated_config(
+    generated_build_variants: &mut Vec<BuildVariant>,
+    bv_name: String,
+    config: GeneratedConfig,
+) {
+    match generated_build_variants
+        .iter_mut()
+        .find(|build_variant| build_variant.name == bv_name)
+    {
+        Some(build_variant) => {
+            build_variant.tasks.extend(config.gen_task_specs);
+            build_variant
+                .display_tasks
+                .get_or_insert_with(Vec::new)
+                .extend(config.display_tasks);
+        }
+        None => generated_build_variants.push(BuildVariant {
+            name: bv_name,
+            tasks: config.gen_task_specs,
+            display_tasks: Some(config.display_tasks),
+            activate: Some(false),
+            ..Default::default()
+        }),
+    }
+}
+
+/// Find generated sub-tasks that share a name.
+///
+/// Evergreen requires task names to be unique, so any collisions would cause the generated
+/// configuration to be rejected.
+///
+/// # Arguments
+///
+/// * `generated_tasks` - Map of generated task names to their generated configuration.
+///
+/// # Returns
+///
+/// Description of each pair of colliding sub-tasks and the generated tasks they came from.
+fn find_duplicate_sub_task_names(generated_tasks: &GenTaskCollection) -> Vec<String> {
+    let mut sub_tasks_by_task: Vec<(String, Vec<GeneratedSubTask>)> = generated_tasks
+        .iter()
+        .map(|entry| (entry.key().clone(), entry.value().sub_tasks()))
+        .collect();
+    sub_tasks_by_task.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut seen_sub_tasks: HashMap<String, &str> = HashMap::new();
+    let mut duplicates = vec![];
+    for (task_name, sub_tasks) in &sub_tasks_by_task {
+        for sub_task in sub_tasks {
+            let sub_task_name = &sub_task.evg_task.name;
+            if let Some(other_task_name) = seen_sub_tasks.get(sub_task_name) {
+                duplicates.push(format!(
+                    "'{}' is generated by both '{}' and '{}'",
+                    sub_task_name, other_task_name, task_name
+                ));
+            } else {
+                seen_sub_tasks.insert(sub_task_name.clone(), task_name);
+            }
+        }
+    }
+
+    duplicates
+}
+
 /// A service for generating tasks.
 #[async_trait]
 trait GenerateTasksService: Sync + Send {
@@ -325,10 +1379,7 @@ trait GenerateTasksService: Sync + Send {
     /// Returns
     ///
     /// Map of task names to generated task definitions.
-    async fn build_generated_tasks(
-        &self,
-        deps: &Dependencies,
-    ) -> Result<Arc<Mutex<GenTaskCollection>>>;
+    async fn build_generated_tasks(&self, deps: &Dependencies) -> Result<Arc<GenTaskCollection>>;
 
     /// Create build variants definitions containing all the generated tasks for each build variant.
     ///
@@ -343,79 +1394,486 @@ trait GenerateTasksService: Sync + Send {
     fn generate_build_variants(
         &self,
         deps: &Dependencies,
-        generated_tasks: Arc<Mutex<GenTaskCollection>>,
+        generated_tasks: Arc<GenTaskCollection>,
     ) -> Result<Vec<BuildVariant>>;
 
-    /// Generate the burn_in build variant information for a build variant.
+    /// Generate the burn_in build variant information for a build variant.
+    ///
+    /// # Arguments
+    ///
+    /// * `burn_in_tag_build_variant_info` - A map of burn_in build variants to config information about them.
+    /// * `build_variant` - The original build variant to generate burn_in information from.
+    /// * `build_variant_map` - A map of build variant names to their definitions.
+    ///
+    /// # Returns
+    ///
+    /// Nothing, modifies the burn_in_tag_build_variant_info with new values.
+    fn generate_burn_in_build_variant_info(
+        &self,
+        burn_in_tag_build_variant_info: &mut HashMap<String, BurnInTagBuildVariantInfo>,
+        build_variant: &BuildVariant,
+        build_variant_map: &HashMap<String, &BuildVariant>,
+    );
+
+    /// Generate a task for the given task definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition to base generated task on.
+    /// * `build_variant` - Build Variant to base generated task on.
+    ///
+    /// # Returns
+    ///
+    /// Configuration for a generated task.
+    async fn generate_task(
+        &self,
+        task_def: &EvgTask,
+        build_variant: &BuildVariant,
+    ) -> Result<Option<Box<dyn GeneratedSuite>>>;
+
+    /// Generate the given task without splitting it.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition to base generated task on.
+    /// * `build_variant` - Build Variant to base generated task on.
+    ///
+    /// # Returns
+    ///
+    /// Configuration for the unsplit task, or None if the task cannot be generated without
+    /// splitting it.
+    fn generate_unsplit_task(
+        &self,
+        task_def: &EvgTask,
+        build_variant: &BuildVariant,
+    ) -> Result<Option<Box<dyn GeneratedSuite>>>;
+}
+
+struct GenerateTasksServiceImpl {
+    evg_config_service: Arc<dyn EvgConfigService>,
+    evg_config_utils: Arc<dyn EvgConfigUtils>,
+    task_kind_registry: Arc<GeneratedTaskKindRegistry>,
+    config_extraction_service: Arc<dyn ConfigExtractionService>,
+    gen_burn_in: bool,
+    prefetch_task_history: bool,
+    gen_sub_tasks_config: Option<GenerateSubTasksConfig>,
+}
+
+impl GenerateTasksServiceImpl {
+    /// Create an instance of GenerateTasksServiceImpl.
+    ///
+    /// # Arguments
+    ///
+    /// * `evg_config_service` - Service to work with evergreen project configuration.
+    /// * `evg_config_utils` - Utilities to work with evergreen project configuration.
+    /// * `task_kind_registry` - Registry of the kinds of tasks that can be generated.
+    /// * `config_extraction_service` - Service to extraction configuration from evergreen config.
+    /// * `gen_burn_in` - Should burn_in tasks be generated.
+    /// * `prefetch_task_history` - Should task history be fetched before generating tasks.
+    /// * `gen_sub_tasks_config` - Configuration for generating sub-tasks.
+    pub fn new(
+        evg_config_service: Arc<dyn EvgConfigService>,
+        evg_config_utils: Arc<dyn EvgConfigUtils>,
+        task_kind_registry: Arc<GeneratedTaskKindRegistry>,
+        config_extraction_service: Arc<dyn ConfigExtractionService>,
+        gen_burn_in: bool,
+        prefetch_task_history: bool,
+        gen_sub_tasks_config: Option<GenerateSubTasksConfig>,
+    ) -> Self {
+        Self {
+            evg_config_service,
+            evg_config_utils,
+            task_kind_registry,
+            config_extraction_service,
+            gen_burn_in,
+            prefetch_task_history,
+            gen_sub_tasks_config,
+        }
+    }
+
+    /// Find build variants that share a generated task but split it with different sub-task
+    /// limits.
+    ///
+    /// A generated task is shared by all build variants with the same platform, enterprise setting
+    /// and `unique_gen_suffix`, and it is only split once. Build variants sharing a task must use
+    /// the same sub-task limits, otherwise the limits of all but one of them would be ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `build_variant_list` - Names of build variants in the order tasks are generated.
+    /// * `build_variant_map` - Map of build variant names to their definitions.
+    /// * `task_map` - Map of task names to their definitions.
+    ///
+    /// # Returns
+    ///
+    /// Description of each generated task shared by build variants with different limits.
+    fn find_sub_task_limit_conflicts(
+        &self,
+        build_variant_list: &[String],
+        build_variant_map: &HashMap<String, &BuildVariant>,
+        task_map: &HashMap<String, Arc<EvgTask>>,
+    ) -> Result<Vec<String>> {
+        let mut task_limits: HashMap<String, (String, SubTaskLimits)> = HashMap::new();
+        let mut conflicts = vec![];
+        for build_variant in build_variant_list
+            .iter()
+            .filter_map(|name| build_variant_map.get(name))
+        {
+            let is_enterprise = self
+                .evg_config_utils
+                .is_enterprise_build_variant(build_variant);
+            let platform = self
+                .evg_config_utils
+                .infer_build_variant_platform(build_variant);
+            let gen_task_suffix = self
+                .evg_config_utils
+                .lookup_build_variant_expansion(UNIQUE_GEN_SUFFIX_EXPANSION, build_variant);
+            for task_def in build_variant
+                .tasks
+                .iter()
+                .filter_map(|task| task_map.get(&task.name))
+                .filter(|task_def| self.evg_config_utils.is_task_generated(task_def))
+                .filter(|task_def| {
+                    self.task_kind_registry
+                        .lookup(task_def)
+                        .uses_sub_task_limits()
+                })
+            {
+                let limits = self
+                    .config_extraction_service
+                    .determine_sub_task_limits(build_variant)?;
+                let task_name = lookup_task_name(
+                    is_enterprise,
+                    &task_def.name,
+                    &platform,
+                    gen_task_suffix.as_deref(),
+                );
+                match task_limits.get(&task_name) {
+                    Some((other_build_variant, other_limits)) if *other_limits != limits => {
+                        conflicts.push(format!(
+                            "'{}' is shared by '{}' ({}) and '{}' ({})",
+                            task_name,
+                            other_build_variant,
+                            other_limits,
+                            build_variant.name,
+                            limits
+                        ));
+                    }
+                    Some(_) => (),
+                    None => {
+                        task_limits.insert(task_name, (build_variant.name.clone(), limits));
+                    }
+                }
+            }
+        }
+        Ok(conflicts)
+    }
+
+    /// Determine if the sub-tasks generated from the given task reference should be activated.
+    ///
+    /// An `activate` setting on the task reference takes precedence over the
+    /// `activate_generated_tasks` variable of the task definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_ref` - Reference to "_gen" task in a build variant.
+    /// * `task_map` - Map of task definitions in evergreen project configuration.
+    ///
+    /// # Returns
+    ///
+    /// true if the generated sub-tasks should be activated.
+    fn determine_activate(
+        &self,
+        task_ref: &TaskRef,
+        task_map: &HashMap<String, Arc<EvgTask>>,
+    ) -> Result<bool> {
+        if let Some(activate) = task_ref.activate {
+            return Ok(activate);
+        }
+
+        match task_map.get(&task_ref.name) {
+            Some(task_def) => self.evg_config_utils.lookup_default_param_bool(
+                task_def,
+                ACTIVATE_GENERATED_TASKS,
+                false,
+            ),
+            None => Ok(false),
+        }
+    }
+
+    /// Determine the build variant the generated sub-tasks of a task should be attached to.
+    ///
+    /// The `run_on_variant` var can be used to attach the generated sub-tasks to a different build
+    /// variant than the one declaring the "_gen" task.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_name` - Name of "_gen" task.
+    /// * `build_variant` - Build variant declaring the "_gen" task.
+    /// * `task_map` - Map of task definitions in evergreen project configuration.
+    /// * `build_variant_map` - Map of build variants in evergreen project configuration.
+    ///
+    /// # Returns
+    ///
+    /// Name of build variant to attach sub-tasks to if it differs from the declaring build variant.
+    fn determine_run_on_variant(
+        &self,
+        task_name: &str,
+        build_variant: &BuildVariant,
+        task_map: &HashMap<String, Arc<EvgTask>>,
+        build_variant_map: &HashMap<String, &BuildVariant>,
+    ) -> Result<Option<String>> {
+        let run_on_variant = task_map
+            .get(task_name)
+            .and_then(|task_def| {
+                self.evg_config_utils
+                    .get_gen_task_var(task_def, RUN_ON_VARIANT)
+            })
+            .and_then(|run_var| {
+                self.evg_config_utils
+                    .translate_run_var(run_var, build_variant)
+            });
+
+        match run_on_variant {
+            Some(variant) if variant != build_variant.name => {
+                if !build_variant_map.contains_key(&variant) {
+                    return Err(anyhow!(
+                        "Task '{}' on build variant '{}' sets '{}' to unknown build variant '{}'",
+                        task_name,
+                        build_variant.name,
+                        RUN_ON_VARIANT,
+                        variant
+                    ))
+                    .context(ErrorCategory::Config);
+                }
+                Ok(Some(variant))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Find the display task a "_gen" task should be grouped under.
     ///
     /// # Arguments
     ///
-    /// * `burn_in_tag_build_variant_info` - A map of burn_in build variants to config information about them.
-    /// * `build_variant` - The original build variant to generate burn_in information from.
-    /// * `build_variant_map` - A map of build variant names to their definitions.
+    /// * `task_name` - Name of "_gen" task.
+    /// * `task_map` - Map of task definitions in evergreen project configuration.
     ///
     /// # Returns
     ///
-    /// Nothing, modifies the burn_in_tag_build_variant_info with new values.
-    fn generate_burn_in_build_variant_info(
+    /// Name of display task to group the "_gen" task under.
+    fn generator_display_task_name(
         &self,
-        burn_in_tag_build_variant_info: &mut HashMap<String, BurnInTagBuildVariantInfo>,
-        build_variant: &BuildVariant,
-        build_variant_map: &HashMap<String, &BuildVariant>,
-    );
+        task_name: &str,
+        task_map: &HashMap<String, Arc<EvgTask>>,
+    ) -> String {
+        let task_tags = task_map
+            .get(task_name)
+            .map(|task_def| self.evg_config_utils.get_task_tags(task_def))
+            .unwrap_or_default();
+        self.gen_sub_tasks_config
+            .as_ref()
+            .and_then(|config| config.generator_display_task_group(task_name, &task_tags))
+            .unwrap_or(GENERATOR_TASKS)
+            .to_string()
+    }
 
-    /// Generate a task for the given task definition.
+    /// Generate the build variant configuration for a single build variant.
+    ///
+    /// Only reads from the project configuration and generated tasks, so it is safe to call
+    /// for several build variants in parallel.
     ///
     /// # Arguments
     ///
-    /// * `task_def` - Task definition to base generated task on.
-    /// * `build_variant` - Build Variant to base generated task on.
+    /// * `deps` - Service dependencies.
+    /// * `bv_name` - Name of build variant to generate configuration for.
+    /// * `build_variant` - Build variant to generate configuration for.
+    /// * `build_variant_map` - Map of build variants in evergreen project configuration.
+    /// * `task_map` - Map of task definitions in evergreen project configuration.
+    /// * `generated_tasks` - Map of task names and their generated configuration.
     ///
     /// # Returns
     ///
-    /// Configuration for a generated task.
-    async fn generate_task(
+    /// Configuration generated for the build variant.
+    fn generate_variant_config(
         &self,
-        task_def: &EvgTask,
+        deps: &Dependencies,
+        bv_name: &str,
         build_variant: &BuildVariant,
-    ) -> Result<Option<Box<dyn GeneratedSuite>>>;
-}
+        build_variant_map: &HashMap<String, &BuildVariant>,
+        task_map: &HashMap<String, Arc<EvgTask>>,
+        generated_tasks: &GenTaskCollection,
+    ) -> Result<VariantGenerationResult> {
+        let is_enterprise = self
+            .evg_config_utils
+            .is_enterprise_build_variant(build_variant);
+        let platform = self
+            .evg_config_utils
+            .infer_build_variant_platform(build_variant);
+        let mut generated_build_variants = vec![];
+        let mut run_on_variant_configs: HashMap<String, GeneratedConfig> = HashMap::new();
+        let mut missing_distros = vec![];
+        let mut has_burn_in_tags = false;
+        let mut gen_config = GeneratedConfig::new();
+        let mut generating_tasks = vec![];
+        for task in &build_variant.tasks {
+            if task.name == BURN_IN_TAGS {
+                has_burn_in_tags = true;
+                generating_tasks.push(BURN_IN_TAGS);
+                continue;
+            }
 
-struct GenerateTasksServiceImpl {
-    evg_config_service: Arc<dyn EvgConfigService>,
-    evg_config_utils: Arc<dyn EvgConfigUtils>,
-    gen_fuzzer_service: Arc<dyn GenFuzzerService>,
-    gen_resmoke_service: Arc<dyn GenResmokeTaskService>,
-    config_extraction_service: Arc<dyn ConfigExtractionService>,
-    gen_burn_in: bool,
-}
+            let task_name = if task.name == BURN_IN_TESTS {
+                format!("{}-{}", BURN_IN_TESTS_PREFIX, bv_name)
+            } else if task.name == BURN_IN_TASKS {
+                format!("{}-{}", BURN_IN_TASKS_PREFIX, bv_name)
+            } else {
+                let gen_task_suffix = self
+                    .evg_config_utils
+                    .lookup_build_variant_expansion(UNIQUE_GEN_SUFFIX_EXPANSION, build_variant);
+                lookup_task_name(
+                    is_enterprise,
+                    &task.name,
+                    &platform,
+                    gen_task_suffix.as_deref(),
+                )
+            };
 
-impl GenerateTasksServiceImpl {
-    /// Create an instance of GenerateTasksServiceImpl.
-    ///
-    /// # Arguments
-    ///
-    /// * `evg_config_service` - Service to work with evergreen project configuration.
-    /// * `evg_config_utils` - Utilities to work with evergreen project configuration.
-    /// * `gen_fuzzer_service` - Service to generate fuzzer tasks.
-    /// * `gen_resmoke_service` - Service for generating resmoke tasks.
-    /// * `config_extraction_service` - Service to extraction configuration from evergreen config.
-    pub fn new(
-        evg_config_service: Arc<dyn EvgConfigService>,
-        evg_config_utils: Arc<dyn EvgConfigUtils>,
-        gen_fuzzer_service: Arc<dyn GenFuzzerService>,
-        gen_resmoke_service: Arc<dyn GenResmokeTaskService>,
-        config_extraction_service: Arc<dyn ConfigExtractionService>,
-        gen_burn_in: bool,
-    ) -> Self {
-        Self {
-            evg_config_service,
-            evg_config_utils,
-            gen_fuzzer_service,
-            gen_resmoke_service,
-            config_extraction_service,
-            gen_burn_in,
+            if let Some(generated_task) = generated_tasks.get(&task_name) {
+                let run_on_variant = self.determine_run_on_variant(
+                    &task.name,
+                    build_variant,
+                    task_map,
+                    build_variant_map,
+                )?;
+                let run_build_variant = run_on_variant
+                    .as_ref()
+                    .and_then(|variant| build_variant_map.get(variant).copied())
+                    .unwrap_or(build_variant);
+                let large_distro = match self
+                    .config_extraction_service
+                    .determine_distro_for_tier(generated_task.value().as_ref(), run_build_variant)
+                {
+                    Ok(distro) => distro,
+                    Err(err) => {
+                        event!(
+                            Level::ERROR,
+                            task = task.name.as_str(),
+                            build_variant = run_build_variant.name.as_str(),
+                            error = err.to_string().as_str(),
+                            "Unable to determine distro for generated task"
+                        );
+                        missing_distros
+                            .push(format!("'{}' on '{}'", task.name, run_build_variant.name));
+                        continue;
+                    }
+                };
+
+                let activate = self.determine_activate(task, task_map)?;
+
+                generating_tasks.push(&task.name);
+                let target_config = match run_on_variant {
+                    Some(variant) => run_on_variant_configs
+                        .entry(variant)
+                        .or_insert_with(GeneratedConfig::new),
+                    None => &mut gen_config,
+                };
+                target_config
+                    .display_tasks
+                    .push(generated_task.build_display_task());
+                target_config
+                    .gen_task_specs
+                    .extend(generated_task.build_task_ref(
+                        large_distro,
+                        task.distros.clone(),
+                        activate,
+                    ));
+            }
+        }
+
+        if !gen_config.gen_task_specs.is_empty() {
+            if let Some(gen_sub_tasks_config) = &self.gen_sub_tasks_config {
+                for clone_config in &gen_sub_tasks_config.build_variant_clones {
+                    if clone_config.matches(bv_name) {
+                        generated_build_variants.push(
+                            deps.variant_cloning_service.clone_build_variant(
+                                build_variant,
+                                &clone_config.variant_name(bv_name),
+                                clone_config,
+                                gen_config.gen_task_specs.clone(),
+                                gen_config.display_tasks.clone(),
+                            ),
+                        );
+                    }
+                }
+
+                for feature_flag_config in &gen_sub_tasks_config.feature_flag_variants {
+                    if !feature_flag_config.matches(bv_name) {
+                        continue;
+                    }
+
+                    let display_tasks: Vec<DisplayTask> = gen_config
+                        .display_tasks
+                        .iter()
+                        .filter(|dt| feature_flag_config.includes_task(&dt.name))
+                        .cloned()
+                        .collect();
+                    if display_tasks.is_empty() {
+                        continue;
+                    }
+                    let execution_tasks: HashSet<&String> = display_tasks
+                        .iter()
+                        .flat_map(|dt| dt.execution_tasks.iter())
+                        .collect();
+                    let task_specs: Vec<TaskRef> = gen_config
+                        .gen_task_specs
+                        .iter()
+                        .filter(|spec| execution_tasks.contains(&spec.name))
+                        .cloned()
+                        .collect();
+
+                    for clone_config in feature_flag_config.clone_configs(build_variant) {
+                        generated_build_variants.push(
+                            deps.variant_cloning_service.clone_build_variant(
+                                build_variant,
+                                &clone_config.variant_name(bv_name),
+                                &clone_config,
+                                task_specs.clone(),
+                                display_tasks.clone(),
+                            ),
+                        );
+                    }
+                }
+            }
         }
+
+        if !generating_tasks.is_empty() {
+            // Put all the "_gen" tasks into display tasks to hide them from view.
+            gen_config.display_tasks.extend(group_generator_tasks(
+                generating_tasks.into_iter().map(|task_name| {
+                    (
+                        task_name.to_string(),
+                        self.generator_display_task_name(task_name, task_map),
+                    )
+                }),
+            ));
+
+            let gen_build_variant = BuildVariant {
+                name: bv_name.to_string(),
+                tasks: gen_config.gen_task_specs.clone(),
+                display_tasks: Some(gen_config.display_tasks.clone()),
+                activate: Some(false),
+                ..Default::default()
+            };
+            generated_build_variants.push(gen_build_variant);
+        }
+
+        Ok(VariantGenerationResult {
+            build_variants: generated_build_variants,
+            run_on_variant_configs,
+            missing_distros,
+            has_burn_in_tags,
+        })
     }
 }
 
@@ -431,22 +1889,49 @@ impl GenerateTasksService for GenerateTasksServiceImpl {
     /// Returns
     ///
     /// Map of task names to generated task definitions.
-    async fn build_generated_tasks(
-        &self,
-        deps: &Dependencies,
-    ) -> Result<Arc<Mutex<GenTaskCollection>>> {
-        let _monitor = RemainingTaskMonitor::new();
+    async fn build_generated_tasks(&self, deps: &Dependencies) -> Result<Arc<GenTaskCollection>> {
+        let _monitor = RemainingTaskMonitor::new(deps.worker_watchdog.clone());
 
-        let build_variant_list = self.evg_config_service.sort_build_variants_by_required();
+        let required_prefixes = match &self.gen_sub_tasks_config {
+            Some(config) => config.required_display_name_prefixes.clone(),
+            None => default_required_display_name_prefixes(),
+        };
+        let build_variant_list = self
+            .evg_config_service
+            .sort_build_variants_by_required(&required_prefixes);
         let build_variant_map = self.evg_config_service.get_build_variant_map();
         let task_map = Arc::new(self.evg_config_service.get_task_def_map());
+        if !self.gen_burn_in {
+            let conflicts = self.find_sub_task_limit_conflicts(
+                &build_variant_list,
+                &build_variant_map,
+                &task_map,
+            )?;
+            if !conflicts.is_empty() {
+                return Err(anyhow!(
+                    "Build variants sharing generated tasks set different sub-task limits, set \
+                     `{}` to give them their own tasks:\n{}",
+                    UNIQUE_GEN_SUFFIX_EXPANSION,
+                    conflicts.join("\n")
+                ))
+                .context(ErrorCategory::Config);
+            }
+        }
+        // Workers share a single copy of each build variant instead of cloning their own.
+        let shared_build_variants: HashMap<String, Arc<BuildVariant>> = build_variant_map
+            .iter()
+            .map(|(name, build_variant)| (name.clone(), Arc::new((*build_variant).clone())))
+            .collect();
 
         let mut thread_handles = vec![];
+        let mut task_workers = vec![];
 
-        let generated_tasks = Arc::new(Mutex::new(HashMap::new()));
+        let generated_tasks = Arc::new(DashMap::new());
+        let completed_tasks = deps.generation_checkpoint.completed_tasks()?;
         let mut seen_tasks = HashSet::new();
-        for build_variant in &build_variant_list {
-            let build_variant = build_variant_map.get(build_variant).unwrap();
+        for build_variant_name in &build_variant_list {
+            let build_variant = build_variant_map.get(build_variant_name).unwrap();
+            let shared_build_variant = shared_build_variants.get(build_variant_name).unwrap();
             let is_enterprise = self
                 .evg_config_utils
                 .is_enterprise_build_variant(build_variant);
@@ -461,7 +1946,7 @@ impl GenerateTasksService for GenerateTasksServiceImpl {
                         thread_handles.push(create_burn_in_worker(
                             deps,
                             task_map.clone(),
-                            build_variant,
+                            shared_build_variant.clone(),
                             build_variant.name.clone(),
                             generated_tasks.clone(),
                         ));
@@ -472,13 +1957,14 @@ impl GenerateTasksService for GenerateTasksServiceImpl {
                             .evg_config_utils
                             .resolve_burn_in_tag_build_variants(build_variant, &build_variant_map)
                         {
-                            let base_build_variant = build_variant_map.get(&base_bv_name).unwrap();
+                            let base_build_variant =
+                                shared_build_variants.get(&base_bv_name).unwrap();
                             let run_build_variant_name =
                                 format!("{}-{}", base_build_variant.name, BURN_IN_BV_SUFFIX);
                             thread_handles.push(create_burn_in_worker(
                                 deps,
                                 task_map.clone(),
-                                base_build_variant,
+                                base_build_variant.clone(),
                                 run_build_variant_name,
                                 generated_tasks.clone(),
                             ));
@@ -489,7 +1975,7 @@ impl GenerateTasksService for GenerateTasksServiceImpl {
                         thread_handles.push(create_burn_in_tasks_worker(
                             deps,
                             task_map.clone(),
-                            build_variant,
+                            shared_build_variant.clone(),
                             generated_tasks.clone(),
                         ));
                     }
@@ -517,24 +2003,68 @@ impl GenerateTasksService for GenerateTasksServiceImpl {
                     continue;
                 }
 
-                seen_tasks.insert(task_name);
+                seen_tasks.insert(task_name.clone());
+                // Tasks completed by an interrupted run are reused instead of generated again.
+                if let Some((task_name, generated_task)) = completed_tasks.remove(&task_name) {
+                    generated_tasks.insert(task_name, generated_task);
+                    continue;
+                }
                 if let Some(task_def) = task_map.get(&task.name) {
                     if self.evg_config_utils.is_task_generated(task_def) {
-                        // Spawn off a tokio task to do the actual generation work.
-                        thread_handles.push(create_task_worker(
-                            deps,
-                            task_def,
-                            build_variant,
-                            generated_tasks.clone(),
-                        ));
+                        task_workers.push((task_def.clone(), shared_build_variant.clone()));
                     }
                 }
             }
         }
 
-        for handle in thread_handles {
-            handle.await.unwrap();
+        // Fetch the history of all tasks being split up-front, so that generation workers
+        // share the fetched history instead of each fetching their own.
+        if self.prefetch_task_history {
+            let task_variants: Vec<(String, String)> = task_workers
+                .iter()
+                .filter(|(task_def, _)| {
+                    self.task_kind_registry.lookup(task_def).uses_task_history()
+                })
+                .map(|(task_def, build_variant)| {
+                    (
+                        remove_gen_suffix(&task_def.name).to_string(),
+                        build_variant.name.clone(),
+                    )
+                })
+                .collect();
+            let task_history_service = deps.task_history_service.clone();
+            thread_handles.push(tokio::spawn(async move {
+                task_history_service
+                    .prefetch_task_histories(task_variants)
+                    .await;
+                Ok(())
+            }));
+        }
+
+        for (task_def, build_variant) in task_workers {
+            // Spawn off a tokio task to do the actual generation work.
+            thread_handles.push(create_task_worker(
+                deps,
+                task_def,
+                build_variant,
+                generated_tasks.clone(),
+            ));
+        }
+
+        // If generation is interrupted, this future is dropped and the workers are aborted.
+        let mut workers = AbortOnDrop(thread_handles);
+        let mut worker_errors = vec![];
+        for handle in workers.0.iter_mut() {
+            match handle.await {
+                Ok(Ok(())) => (),
+                Ok(Err(err)) => worker_errors.push(err),
+                Err(err) => worker_errors.push(anyhow::Error::new(err)),
+            }
         }
+        combine_worker_errors(worker_errors)?;
+        deps.worker_watchdog
+            .check()
+            .context(ErrorCategory::Resmoke)?;
 
         event!(
             Level::INFO,
@@ -559,42 +2089,38 @@ impl GenerateTasksService for GenerateTasksServiceImpl {
         task_def: &EvgTask,
         build_variant: &BuildVariant,
     ) -> Result<Option<Box<dyn GeneratedSuite>>> {
-        let generated_task = if self.evg_config_utils.is_task_fuzzer(task_def) {
-            event!(Level::INFO, "Generating fuzzer: {}", task_def.name);
+        let task_kind = self.task_kind_registry.lookup(task_def);
+        event!(
+            Level::INFO,
+            "Generating {} task: {}",
+            task_kind.name(),
+            task_def.name
+        );
 
-            let params = self
-                .config_extraction_service
-                .task_def_to_fuzzer_params(task_def, build_variant)?;
+        let generated_task = task_kind.generate(task_def, build_variant).await?;
 
-            Some(self.gen_fuzzer_service.generate_fuzzer_task(&params)?)
-        } else {
-            let is_enterprise = self
-                .evg_config_utils
-                .is_enterprise_build_variant(build_variant);
-            let platform = self
-                .evg_config_utils
-                .infer_build_variant_platform(build_variant);
-            event!(
-                Level::INFO,
-                "Generating resmoke task: {}, is_enterprise: {}, platform: {}",
-                task_def.name,
-                is_enterprise,
-                platform
-            );
-            let params = self.config_extraction_service.task_def_to_resmoke_params(
-                task_def,
-                is_enterprise,
-                Some(build_variant),
-                Some(platform),
-            )?;
-            Some(
-                self.gen_resmoke_service
-                    .generate_resmoke_task(&params, &build_variant.name)
-                    .await?,
-            )
-        };
+        Ok(Some(generated_task))
+    }
 
-        Ok(generated_task)
+    /// Generate the given task without splitting it.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition to base generated task on.
+    /// * `build_variant` - Build Variant to base generated task on.
+    ///
+    /// # Returns
+    ///
+    /// Configuration for the unsplit task, or None if the task cannot be generated without
+    /// splitting it.
+    fn generate_unsplit_task(
+        &self,
+        task_def: &EvgTask,
+        build_variant: &BuildVariant,
+    ) -> Result<Option<Box<dyn GeneratedSuite>>> {
+        self.task_kind_registry
+            .lookup(task_def)
+            .generate_unsplit(task_def, build_variant)
     }
 
     /// Generate the burn_in build variant information for a build variant.
@@ -624,12 +2150,17 @@ impl GenerateTasksService for GenerateTasksServiceImpl {
         )
         }
 
+        let in_base_variant = self
+            .evg_config_utils
+            .lookup_build_variant_expansion(BURN_IN_TAG_IN_BASE_VARIANT, build_variant)
+            .map(|value| value == "true")
+            .unwrap_or(false);
         let compile_task_dependency = self
             .evg_config_utils
             .lookup_build_variant_expansion(
                 BURN_IN_TAG_COMPILE_TASK_DEPENDENCY,
                 build_variant,
-            ).unwrap_or_else(|| {
+            ).or_else(|| in_base_variant.then(String::new)).unwrap_or_else(|| {
                 panic!(
                     "`{}` build variant is missing the `{}` expansion to run `{}`. Set the expansion in your project's config to continue.",
                     build_variant.name, BURN_IN_TAG_COMPILE_TASK_DEPENDENCY, BURN_IN_TAGS
@@ -645,6 +2176,7 @@ impl GenerateTasksService for GenerateTasksServiceImpl {
                 .entry(variant.clone())
                 .or_insert(BurnInTagBuildVariantInfo {
                     compile_task_dependency: compile_task_dependency.clone(),
+                    in_base_variant,
                 });
             if bv_info.compile_task_dependency != compile_task_dependency {
                 panic!(
@@ -652,6 +2184,12 @@ impl GenerateTasksService for GenerateTasksServiceImpl {
                 build_variant.name, variant, BURN_IN_TAG_COMPILE_TASK_DEPENDENCY
             )
             }
+            if bv_info.in_base_variant != in_base_variant {
+                panic!(
+                    "`{}` is trying to set a different `{}` value than already exists for `{}`. Check the `{}` expansions in your config.",
+                build_variant.name, BURN_IN_TAG_IN_BASE_VARIANT, variant, BURN_IN_TAG_IN_BASE_VARIANT
+            )
+            }
         }
     }
 
@@ -668,105 +2206,92 @@ impl GenerateTasksService for GenerateTasksServiceImpl {
     fn generate_build_variants(
         &self,
         deps: &Dependencies,
-        generated_tasks: Arc<Mutex<GenTaskCollection>>,
+        generated_tasks: Arc<GenTaskCollection>,
     ) -> Result<Vec<BuildVariant>> {
         let mut generated_build_variants = vec![];
         let mut burn_in_tag_build_variant_info: HashMap<String, BurnInTagBuildVariantInfo> =
             HashMap::new();
 
         let build_variant_map = self.evg_config_service.get_build_variant_map();
-        for (bv_name, build_variant) in &build_variant_map {
-            let is_enterprise = self
-                .evg_config_utils
-                .is_enterprise_build_variant(build_variant);
-            let platform = self
-                .evg_config_utils
-                .infer_build_variant_platform(build_variant);
-            let mut gen_config = GeneratedConfig::new();
-            let mut generating_tasks = vec![];
-            for task in &build_variant.tasks {
-                if task.name == BURN_IN_TAGS {
-                    if self.gen_burn_in {
-                        self.generate_burn_in_build_variant_info(
-                            &mut burn_in_tag_build_variant_info,
-                            build_variant,
-                            &build_variant_map,
-                        );
-                    }
-                    generating_tasks.push(BURN_IN_TAGS);
-                    continue;
-                }
-
-                let generated_tasks = generated_tasks.lock().unwrap();
-
-                let task_name = if task.name == BURN_IN_TESTS {
-                    format!("{}-{}", BURN_IN_TESTS_PREFIX, bv_name)
-                } else if task.name == BURN_IN_TASKS {
-                    format!("{}-{}", BURN_IN_TASKS_PREFIX, bv_name)
-                } else {
-                    let gen_task_suffix = self
-                        .evg_config_utils
-                        .lookup_build_variant_expansion(UNIQUE_GEN_SUFFIX_EXPANSION, build_variant);
-                    lookup_task_name(
-                        is_enterprise,
-                        &task.name,
-                        &platform,
-                        gen_task_suffix.as_deref(),
-                    )
-                };
-
-                if let Some(generated_task) = generated_tasks.get(&task_name) {
-                    let large_distro = self
-                        .config_extraction_service
-                        .determine_large_distro(generated_task.as_ref(), build_variant)?;
-
-                    generating_tasks.push(&task.name);
-                    gen_config
-                        .display_tasks
-                        .push(generated_task.build_display_task());
-                    gen_config
-                        .gen_task_specs
-                        .extend(generated_task.build_task_ref(large_distro));
-                }
+        let task_map = self.evg_config_service.get_task_def_map();
+        let variant_results = build_variant_map
+            .par_iter()
+            .map(|(bv_name, build_variant)| {
+                let variant_result = self.generate_variant_config(
+                    deps,
+                    bv_name,
+                    build_variant,
+                    &build_variant_map,
+                    &task_map,
+                    &generated_tasks,
+                )?;
+                Ok((*build_variant, variant_result))
+            })
+            .collect::<Result<Vec<(&BuildVariant, VariantGenerationResult)>>>()?;
+
+        let mut missing_distros = vec![];
+        let mut run_on_variant_configs: HashMap<String, GeneratedConfig> = HashMap::new();
+        for (build_variant, variant_result) in variant_results {
+            if self.gen_burn_in && variant_result.has_burn_in_tags {
+                self.generate_burn_in_build_variant_info(
+                    &mut burn_in_tag_build_variant_info,
+                    build_variant,
+                    &build_variant_map,
+                );
             }
+            generated_build_variants.extend(variant_result.build_variants);
+            missing_distros.extend(variant_result.missing_distros);
+            for (bv_name, config) in variant_result.run_on_variant_configs {
+                let target_config = run_on_variant_configs
+                    .entry(bv_name)
+                    .or_insert_with(GeneratedConfig::new);
+                target_config.gen_task_specs.extend(config.gen_task_specs);
+                target_config.display_tasks.extend(config.display_tasks);
+            }
+        }
 
-            if !generating_tasks.is_empty() {
-                // Put all the "_gen" tasks into a display task to hide them from view.
-                gen_config.display_tasks.push(DisplayTask {
-                    name: GENERATOR_TASKS.to_string(),
-                    execution_tasks: generating_tasks
-                        .into_iter()
-                        .map(|s| s.to_string())
-                        .collect(),
-                });
+        for (bv_name, config) in run_on_variant_configs {
+            merge_generated_config(&mut generated_build_variants, bv_name, config);
+        }
 
-                let gen_build_variant = BuildVariant {
-                    name: bv_name.clone(),
-                    tasks: gen_config.gen_task_specs.clone(),
-                    display_tasks: Some(gen_config.display_tasks.clone()),
-                    activate: Some(false),
-                    ..Default::default()
-                };
-                generated_build_variants.push(gen_build_variant);
-            }
+        if !missing_distros.is_empty() {
+            missing_distros.sort();
+            return Err(anyhow!(
+                "Tasks request a large distro on build variants that do not define one: {}. Add a \
+                 'large_distro_name' expansion to these build variants or add them to the \
+                 'build_variant_large_distro_exceptions' list in the sub-tasks config.",
+                missing_distros.join(", ")
+            ))
+            .context(ErrorCategory::Validation);
         }
 
         for (base_bv_name, bv_info) in burn_in_tag_build_variant_info {
-            let generated_tasks = generated_tasks.lock().unwrap();
             let base_build_variant = build_variant_map.get(&base_bv_name).unwrap();
             let run_build_variant_name =
                 format!("{}-{}", base_build_variant.name, BURN_IN_BV_SUFFIX);
             let task_name = format!("{}-{}", BURN_IN_TESTS_PREFIX, run_build_variant_name);
 
             if let Some(generated_task) = generated_tasks.get(&task_name) {
-                generated_build_variants.push(
+                let burn_in_build_variant =
                     deps.burn_in_service.generate_burn_in_tags_build_variant(
                         base_build_variant,
                         run_build_variant_name,
-                        generated_task.as_ref(),
+                        generated_task.value().as_ref(),
                         bv_info.compile_task_dependency,
-                    )?,
-                );
+                        bv_info.in_base_variant,
+                    )?;
+                if bv_info.in_base_variant {
+                    merge_generated_config(
+                        &mut generated_build_variants,
+                        burn_in_build_variant.name,
+                        GeneratedConfig {
+                            gen_task_specs: burn_in_build_variant.tasks,
+                            display_tasks: burn_in_build_variant.display_tasks.unwrap_or_default(),
+                        },
+                    );
+                } else {
+                    generated_build_variants.push(burn_in_build_variant);
+                }
             }
         }
 
@@ -779,58 +2304,43 @@ impl GenerateTasksService for GenerateTasksServiceImpl {
     }
 }
 
-/// Determine the task name to use.
-///
-/// We append "enterprise" to tasks run on enterprise module build variants, so they don't
-/// conflict with the normal tasks.
-///
-/// # Arguments
-///
-/// * `is_enterprise` - Whether the task is for an enterprise build variant.
-/// * `task` - Name of task.
-/// * `platform` - Platform that task will run on.
-///
-/// # Returns
-///
-/// Name to use for task.
-fn lookup_task_name(
-    is_enterprise: bool,
-    task_name: &str,
-    platform: &str,
-    unique_gen_suffix: Option<&str>,
-) -> String {
-    if is_enterprise {
-        format!(
-            "{}-{}-{}{}",
-            task_name,
-            platform,
-            ENTERPRISE_MODULE,
-            unique_gen_suffix.unwrap_or("")
-        )
-    } else {
-        format!(
-            "{}-{}{}",
-            task_name,
-            platform,
-            unique_gen_suffix.unwrap_or("")
-        )
+/// Aborts the workers it holds when it is dropped.
+struct AbortOnDrop(Vec<JoinHandle<Result<()>>>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.iter().for_each(|handle| handle.abort());
     }
 }
 
+/// Maximum number of running workers to list when reporting progress.
+const MAX_REPORTED_RUNNING_WORKERS: usize = 10;
+
 /// Runs a task that will periodically report the number of active tasks since the monitor was created.
 struct RemainingTaskMonitor {
     handle: JoinHandle<()>,
 }
 
 impl RemainingTaskMonitor {
-    pub fn new() -> Self {
+    /// Start reporting the progress of generation.
+    ///
+    /// Along with the number of active tasks, the longest running workers and how long they have
+    /// been running are reported, so stuck tasks can be identified while generation is running.
+    ///
+    /// # Arguments
+    ///
+    /// * `worker_watchdog` - Watchdog tracking the running generation workers.
+    pub fn new(worker_watchdog: Arc<WorkerWatchdog>) -> Self {
         let metrics = Handle::current().metrics();
         let offset = metrics.num_alive_tasks() + 1; // + 1 to include the task spawned below.
         let handle = tokio::spawn(async move {
             loop {
-                time::sleep(time::Duration::from_secs(60)).await;
+                time::sleep(worker_watchdog.report_interval()).await;
+                let running_workers = worker_watchdog.running_workers();
                 event!(
                     Level::INFO,
+                    running_workers = running_workers.len(),
+                    longest_running = format_running_workers(&running_workers).as_str(),
                     "Waiting on {} generate tasks to finish...",
                     Handle::current().metrics().num_alive_tasks() - offset
                 );
@@ -846,6 +2356,54 @@ impl Drop for RemainingTaskMonitor {
     }
 }
 
+/// Describe the longest running of the given workers.
+///
+/// # Arguments
+///
+/// * `running_workers` - Running workers and how long they have been running, longest first.
+///
+/// # Returns
+///
+/// Comma separated list of the longest running workers with their elapsed time.
+fn format_running_workers(running_workers: &[(String, Duration)]) -> String {
+    running_workers
+        .iter()
+        .take(MAX_REPORTED_RUNNING_WORKERS)
+        .map(|(name, elapsed)| format!("{} ({}s)", name, elapsed.as_secs()))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Combine the errors of failed generation workers into a single error.
+///
+/// Every error is logged, and the first is returned so that its category is kept in the error
+/// report.
+///
+/// # Arguments
+///
+/// * `worker_errors` - Errors returned by the generation workers.
+///
+/// # Returns
+///
+/// An error if any of the workers failed.
+fn combine_worker_errors(worker_errors: Vec<anyhow::Error>) -> Result<()> {
+    let failed_workers = worker_errors.len();
+    for err in &worker_errors {
+        event!(
+            Level::ERROR,
+            error = format!("{:#}", err).as_str(),
+            "Generation worker failed"
+        );
+    }
+    match worker_errors.into_iter().next() {
+        Some(err) if failed_workers > 1 => {
+            Err(err.context(format!("{} generation workers failed", failed_workers)))
+        }
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
 /// Spawn a tokio task to perform the task generation work.
 ///
 /// # Arguments
@@ -860,21 +2418,47 @@ impl Drop for RemainingTaskMonitor {
 /// Handle to created tokio worker.
 fn create_task_worker(
     deps: &Dependencies,
-    task_def: &EvgTask,
-    build_variant: &BuildVariant,
-    generated_tasks: Arc<Mutex<GenTaskCollection>>,
-) -> tokio::task::JoinHandle<()> {
+    task_def: Arc<EvgTask>,
+    build_variant: Arc<BuildVariant>,
+    generated_tasks: Arc<GenTaskCollection>,
+) -> tokio::task::JoinHandle<Result<()>> {
     let generate_task_service = deps.gen_task_service.clone();
     let evg_config_utils = deps.evg_config_utils.clone();
-    let task_def = task_def.clone();
-    let build_variant = build_variant.clone();
-    let generated_tasks = generated_tasks.clone();
+    let generation_checkpoint = deps.generation_checkpoint.clone();
+    let worker_watchdog = deps.worker_watchdog.clone();
 
     tokio::spawn(async move {
-        let generated_task = generate_task_service
-            .generate_task(&task_def, &build_variant)
+        let suite_write_acks = SuiteWriteAcks::new();
+        let generation = {
+            let generate_task_service = generate_task_service.clone();
+            let task_def = task_def.clone();
+            let build_variant = build_variant.clone();
+            SUITE_WRITE_ACKS.scope(suite_write_acks.clone(), async move {
+                generate_task_service
+                    .generate_task(&task_def, &build_variant)
+                    .await
+            })
+        };
+        // Unsplit tasks run their original suite, so they have no suite files to wait for.
+        let (generated_task, suite_write_acks) = match worker_watchdog
+            .watch(generation, &task_def.name, &build_variant.name)
             .await
-            .unwrap();
+        {
+            Ok(generated_task) => (generated_task, suite_write_acks),
+            Err(WorkerCancelled::TimedOut) if worker_watchdog.allow_partial() => (
+                generate_task_service.generate_unsplit_task(&task_def, &build_variant),
+                SuiteWriteAcks::new(),
+            ),
+            // Timed out workers are reported together once all workers have finished.
+            Err(WorkerCancelled::TimedOut) => (Ok(None), suite_write_acks),
+            Err(cancelled) => (Err(anyhow::Error::new(cancelled)), suite_write_acks),
+        };
+        let generated_task = generated_task.with_context(|| {
+            format!(
+                "Generating task '{}' on build variant '{}'",
+                task_def.name, build_variant.name
+            )
+        })?;
 
         let is_enterprise = evg_config_utils.is_enterprise_build_variant(&build_variant);
         let platform = evg_config_utils.infer_build_variant_platform(&build_variant);
@@ -888,9 +2472,23 @@ fn create_task_worker(
         );
 
         if let Some(generated_task) = generated_task {
-            let mut generated_tasks = generated_tasks.lock().unwrap();
+            // A resumed run does not write the suite files of checkpointed tasks again, so only
+            // checkpoint the task once its own suite files are on disk.
+            if generation_checkpoint.is_enabled() && suite_write_acks.all_written().await {
+                if let Err(err) = generation_checkpoint.record(&task_name, generated_task.as_ref())
+                {
+                    event!(
+                        Level::WARN,
+                        task_name = task_name.as_str(),
+                        error = err.to_string(),
+                        "Could not checkpoint generated task"
+                    );
+                }
+            }
             generated_tasks.insert(task_name, generated_task);
         }
+
+        Ok(())
     })
 }
 
@@ -909,26 +2507,31 @@ fn create_task_worker(
 /// Handle to created tokio worker.
 fn create_burn_in_worker(
     deps: &Dependencies,
-    task_map: Arc<HashMap<String, EvgTask>>,
-    build_variant: &BuildVariant,
+    task_map: Arc<HashMap<String, Arc<EvgTask>>>,
+    build_variant: Arc<BuildVariant>,
     run_build_variant_name: String,
-    generated_tasks: Arc<Mutex<GenTaskCollection>>,
-) -> tokio::task::JoinHandle<()> {
+    generated_tasks: Arc<GenTaskCollection>,
+) -> tokio::task::JoinHandle<Result<()>> {
     let burn_in_service = deps.burn_in_service.clone();
-    let build_variant = build_variant.clone();
-    let generated_tasks = generated_tasks.clone();
 
     tokio::spawn(async move {
         let generated_task = burn_in_service
             .generate_burn_in_suite(&build_variant, &run_build_variant_name, task_map)
-            .unwrap();
+            .await
+            .with_context(|| {
+                format!(
+                    "Generating burn_in_tests for build variant '{}'",
+                    run_build_variant_name
+                )
+            })?;
 
         let task_name = format!("{}-{}", BURN_IN_TESTS_PREFIX, run_build_variant_name);
 
         if !generated_task.sub_tasks().is_empty() {
-            let mut generated_tasks = generated_tasks.lock().unwrap();
             generated_tasks.insert(task_name, generated_task);
         }
+
+        Ok(())
     })
 }
 
@@ -946,41 +2549,60 @@ fn create_burn_in_worker(
 /// Handle to created tokio worker.
 fn create_burn_in_tasks_worker(
     deps: &Dependencies,
-    task_map: Arc<HashMap<String, EvgTask>>,
-    build_variant: &BuildVariant,
-    generated_tasks: Arc<Mutex<GenTaskCollection>>,
-) -> tokio::task::JoinHandle<()> {
+    task_map: Arc<HashMap<String, Arc<EvgTask>>>,
+    build_variant: Arc<BuildVariant>,
+    generated_tasks: Arc<GenTaskCollection>,
+) -> tokio::task::JoinHandle<Result<()>> {
     let burn_in_service = deps.burn_in_service.clone();
-    let build_variant = build_variant.clone();
-    let generated_tasks = generated_tasks.clone();
 
     tokio::spawn(async move {
         let generated_task = burn_in_service
             .generate_burn_in_tasks_suite(&build_variant, task_map)
-            .unwrap();
+            .with_context(|| {
+                format!(
+                    "Generating burn_in_tasks for build variant '{}'",
+                    build_variant.name
+                )
+            })?;
 
         let task_name = format!("{}-{}", BURN_IN_TASKS_PREFIX, build_variant.name);
 
         if !generated_task.sub_tasks().is_empty() {
-            let mut generated_tasks = generated_tasks.lock().unwrap();
             generated_tasks.insert(task_name, generated_task);
         }
+
+        Ok(())
     })
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
+    use maplit::{btreemap, hashmap};
     use rstest::rstest;
+    use shrub_rs::models::{commands::fn_call_with_params, params::ParamValue};
+    use tempdir::TempDir;
 
     use crate::{
-        evergreen::evg_config_utils::MultiversionGenerateTaskConfig,
-        resmoke::burn_in_proxy::{BurnInDiscovery, DiscoveredTask},
+        evergreen::{
+            evg_config_utils::MultiversionGenerateTaskConfig,
+            evg_task_history::{HistoryFetchStats, TaskRuntimeHistory},
+        },
+        resmoke::{
+            burn_in_proxy::{BuildVariantDiscovery, DiscoveredTask},
+            resmoke_proxy::MultiversionConfig,
+            resmoke_suite::ResmokeSuiteConfig,
+        },
         task_types::{
+            antithesis_tasks::AntithesisGenTaskParams,
+            bazel_shard_tasks::BazelShardGenParams,
             fuzzer_tasks::FuzzerGenTaskParams,
             generated_suite::GeneratedSubTask,
-            multiversion::MultiversionService,
+            generated_task_kind::GeneratedTaskKind,
             resmoke_tasks::{
-                GeneratedResmokeSuite, ResmokeGenParams, ResmokeSuiteGenerationInfo, SubSuite,
+                GenResmokeTaskService, GeneratedResmokeSuite, ResmokeGenParams,
+                ResmokeSuiteGenerationInfo, SubSuite,
             },
         },
     };
@@ -993,31 +2615,42 @@ mod tests {
             todo!()
         }
 
-        fn get_task_def_map(&self) -> HashMap<String, EvgTask> {
+        fn get_task_def_map(&self) -> HashMap<String, Arc<EvgTask>> {
             todo!()
         }
 
-        fn sort_build_variants_by_required(&self) -> Vec<String> {
+        fn sort_build_variants_by_required(&self, _required_prefixes: &[String]) -> Vec<String> {
             todo!()
         }
 
-        fn get_module_dir(&self, _module_name: &str) -> Option<String> {
+        fn get_module_dirs(&self) -> HashMap<String, String> {
             todo!()
         }
     }
 
-    struct MockGenFuzzerService {}
-    impl GenFuzzerService for MockGenFuzzerService {
-        fn generate_fuzzer_task(
+    struct MockGeneratedTaskKind {}
+    #[async_trait]
+    impl GeneratedTaskKind for MockGeneratedTaskKind {
+        fn name(&self) -> &str {
+            "mock"
+        }
+
+        fn uses_sub_task_limits(&self) -> bool {
+            true
+        }
+
+        async fn generate(
             &self,
-            _params: &FuzzerGenTaskParams,
+            _task_def: &EvgTask,
+            _build_variant: &BuildVariant,
         ) -> Result<Box<dyn GeneratedSuite>> {
             todo!()
         }
     }
-    struct MockGenResmokeTasksService {}
+
+    struct MockGenResmokeTaskService {}
     #[async_trait]
-    impl GenResmokeTaskService for MockGenResmokeTasksService {
+    impl GenResmokeTaskService for MockGenResmokeTaskService {
         async fn generate_resmoke_task(
             &self,
             _params: &ResmokeGenParams,
@@ -1031,8 +2664,15 @@ mod tests {
             _sub_suite: &SubSuite,
             _total_sub_suites: usize,
             _params: &ResmokeGenParams,
-            _suite_override: Option<String>,
-        ) -> GeneratedSubTask {
+            _suite_override: Option<String>,
+        ) -> GeneratedSubTask {
+            todo!()
+        }
+
+        fn build_unsplit_resmoke_task(
+            &self,
+            _params: &ResmokeGenParams,
+        ) -> Box<dyn GeneratedSuite> {
             todo!()
         }
     }
@@ -1042,35 +2682,555 @@ mod tests {
         GenerateTasksServiceImpl::new(
             Arc::new(MockConfigService {}),
             evg_config_utils.clone(),
-            Arc::new(MockGenFuzzerService {}),
-            Arc::new(MockGenResmokeTasksService {}),
+            Arc::new(GeneratedTaskKindRegistry::new(
+                evg_config_utils.clone(),
+                Arc::new(MockGeneratedTaskKind {}),
+            )),
             Arc::new(ConfigExtractionServiceImpl::new(
                 evg_config_utils,
                 Arc::new(MockMultiversionService {}),
                 "generating_task".to_string(),
                 "config_location".to_string(),
                 None,
+                false,
             )),
             false,
+            false,
+            None,
         )
     }
 
-    // tests for lookup_task_name.
+    // tests for determine_activate.
+    #[rstest]
+    #[case(None, None, false)]
+    #[case(None, Some("true"), true)]
+    #[case(None, Some("false"), false)]
+    #[case(Some(true), None, true)]
+    #[case(Some(false), Some("true"), false)]
+    #[case(Some(true), Some("false"), true)]
+    fn test_determine_activate_should_prefer_task_ref(
+        #[case] task_ref_activate: Option<bool>,
+        #[case] activate_var: Option<&str>,
+        #[case] expected_activate: bool,
+    ) {
+        let gen_task_service = build_mock_generate_tasks_service();
+        let mut vars = HashMap::new();
+        if let Some(activate_var) = activate_var {
+            vars.insert(
+                ACTIVATE_GENERATED_TASKS.to_string(),
+                ParamValue::from(activate_var),
+            );
+        }
+        let task_map = hashmap! {
+            "my_task_gen".to_string() => Arc::new(EvgTask {
+                name: "my_task_gen".to_string(),
+                commands: Some(vec![fn_call_with_params("generate resmoke tasks", vars)]),
+                ..Default::default()
+            }),
+        };
+        let task_ref = task_map["my_task_gen"].get_reference(None, task_ref_activate);
+
+        assert_eq!(
+            gen_task_service
+                .determine_activate(&task_ref, &task_map)
+                .unwrap(),
+            expected_activate
+        );
+    }
+
+    // tests for find_sub_task_limit_conflicts.
+    #[rstest]
+    #[case(vec![], vec![], 0)]
+    #[case(vec![("default_subtasks_per_task", "8")], vec![("default_subtasks_per_task", "8")], 0)]
+    #[case(vec![("default_subtasks_per_task", "8")], vec![], 1)]
+    #[case(vec![("max_subtasks_per_task", "3")], vec![("max_subtasks_per_task", "4")], 1)]
+    #[case(vec![("default_subtasks_per_task", "8")], vec![("unique_gen_suffix", "-b")], 0)]
+    #[case(vec![("max_total_generated_subtasks", "10")], vec![], 1)]
+    #[case(
+        vec![("max_total_generated_subtasks", "10")],
+        vec![("max_total_generated_subtasks", "10")],
+        0
+    )]
+    fn test_find_sub_task_limit_conflicts_should_compare_variants_sharing_tasks(
+        #[case] expansions_a: Vec<(&str, &str)>,
+        #[case] expansions_b: Vec<(&str, &str)>,
+        #[case] expected_conflicts: usize,
+    ) {
+        let gen_task_service = build_mock_generate_tasks_service();
+        let task_map = hashmap! {
+            "my_task_gen".to_string() => Arc::new(EvgTask {
+                name: "my_task_gen".to_string(),
+                commands: Some(vec![fn_call_with_params("generate resmoke tasks", HashMap::new())]),
+                ..Default::default()
+            }),
+        };
+        let build_variant = |name: &str, expansions: Vec<(&str, &str)>| BuildVariant {
+            name: name.to_string(),
+            run_on: Some(vec!["rhel80-small".to_string()]),
+            expansions: Some(
+                expansions
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            ),
+            tasks: vec![task_map["my_task_gen"].get_reference(None, None)],
+            ..Default::default()
+        };
+        let bv_a = build_variant("bv_a", expansions_a);
+        let bv_b = build_variant("bv_b", expansions_b);
+        let build_variant_map = hashmap! {
+            "bv_a".to_string() => &bv_a,
+            "bv_b".to_string() => &bv_b,
+        };
+
+        let conflicts = gen_task_service
+            .find_sub_task_limit_conflicts(
+                &["bv_a".to_string(), "bv_b".to_string()],
+                &build_variant_map,
+                &task_map,
+            )
+            .unwrap();
+
+        assert_eq!(conflicts.len(), expected_conflicts);
+    }
+
+    // tests for determine_run_on_variant.
     #[rstest]
-    #[case(false, "my_task", "my_platform", "my_task-my_platform")]
-    #[case(true, "my_task", "my_platform", "my_task-my_platform-enterprise")]
-    fn test_lookup_task_name_should_use_enterprise_when_specified(
-        #[case] is_enterprise: bool,
-        #[case] task_name: &str,
-        #[case] platform: &str,
-        #[case] expected_task_name: &str,
+    #[case(None, Ok(None))]
+    #[case(Some("declaring_variant"), Ok(None))]
+    #[case(Some("other_variant"), Ok(Some("other_variant")))]
+    #[case(Some("${test_variant|other_variant}"), Ok(Some("other_variant")))]
+    #[case(Some("unknown_variant"), Err(()))]
+    fn test_determine_run_on_variant(
+        #[case] run_on_variant: Option<&str>,
+        #[case] expected: Result<Option<&str>, ()>,
     ) {
+        let gen_task_service = build_mock_generate_tasks_service();
+        let mut vars = HashMap::new();
+        if let Some(run_on_variant) = run_on_variant {
+            vars.insert(RUN_ON_VARIANT.to_string(), ParamValue::from(run_on_variant));
+        }
+        let task_map = hashmap! {
+            "my_task_gen".to_string() => Arc::new(EvgTask {
+                name: "my_task_gen".to_string(),
+                commands: Some(vec![fn_call_with_params("generate resmoke tasks", vars)]),
+                ..Default::default()
+            }),
+        };
+        let declaring_variant = BuildVariant {
+            name: "declaring_variant".to_string(),
+            ..Default::default()
+        };
+        let other_variant = BuildVariant {
+            name: "other_variant".to_string(),
+            ..Default::default()
+        };
+        let build_variant_map = hashmap! {
+            "declaring_variant".to_string() => &declaring_variant,
+            "other_variant".to_string() => &other_variant,
+        };
+
+        let result = gen_task_service.determine_run_on_variant(
+            "my_task_gen",
+            &declaring_variant,
+            &task_map,
+            &build_variant_map,
+        );
+
+        match expected {
+            Ok(expected_variant) => {
+                assert_eq!(result.unwrap(), expected_variant.map(|v| v.to_string()))
+            }
+            Err(_) => assert!(result.is_err()),
+        }
+    }
+
+    // tests for format_running_workers.
+    #[test]
+    fn test_format_running_workers_should_limit_the_reported_workers() {
+        let running_workers: Vec<(String, Duration)> = (0..15)
+            .map(|i| (format!("task_{}", i), Duration::from_secs(100 - i)))
+            .collect();
+
+        let description = format_running_workers(&running_workers);
+
+        assert!(description.starts_with("task_0 (100s), task_1 (99s)"));
+        assert_eq!(
+            description.matches(", ").count(),
+            MAX_REPORTED_RUNNING_WORKERS - 1
+        );
+        assert!(!description.contains("task_10"));
+    }
+
+    // tests for combine_worker_errors.
+    #[test]
+    fn test_combine_worker_errors_should_succeed_without_errors() {
+        assert!(combine_worker_errors(vec![]).is_ok());
+    }
+
+    #[test]
+    fn test_combine_worker_errors_should_keep_the_category_of_the_first_error() {
+        let worker_errors = vec![
+            anyhow!("history unavailable").context(ErrorCategory::HistoryFetch),
+            anyhow!("suite not found").context(ErrorCategory::Resmoke),
+        ];
+
+        let err = combine_worker_errors(worker_errors).unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<ErrorCategory>(),
+            Some(&ErrorCategory::HistoryFetch)
+        );
+        assert!(err.to_string().starts_with("2 generation workers failed"));
+    }
+
+    // tests for group_generator_tasks.
+    #[test]
+    fn test_group_generator_tasks_should_group_by_display_task() {
+        let generating_tasks = vec![
+            ("task_0_gen", "generator_tasks"),
+            ("mv_task_0_gen", "multiversion_generator_tasks"),
+            ("task_1_gen", "generator_tasks"),
+            ("mv_task_1_gen", "multiversion_generator_tasks"),
+        ];
+
+        let display_tasks = group_generator_tasks(
+            generating_tasks
+                .into_iter()
+                .map(|(t, d)| (t.to_string(), d.to_string())),
+        );
+
+        assert_eq!(display_tasks.len(), 2);
+        assert_eq!(display_tasks[0].name, "generator_tasks");
+        assert_eq!(
+            display_tasks[0].execution_tasks,
+            vec!["task_0_gen".to_string(), "task_1_gen".to_string()]
+        );
+        assert_eq!(display_tasks[1].name, "multiversion_generator_tasks");
+        assert_eq!(
+            display_tasks[1].execution_tasks,
+            vec!["mv_task_0_gen".to_string(), "mv_task_1_gen".to_string()]
+        );
+    }
+
+    // tests for estimate_sub_task_demands.
+    #[test]
+    fn test_estimate_sub_task_demands_should_count_sub_tasks_of_each_task_kind() {
+        let task_ref = |name: &str| {
+            EvgTask {
+                name: name.to_string(),
+                ..Default::default()
+            }
+            .get_reference(None, None)
+        };
+        let gen_task = |name: &str, vars: Vec<(&str, &str)>, tags: Vec<&str>| {
+            let vars = vars
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), ParamValue::from(value)))
+                .collect();
+            let mut commands = vec![fn_call_with_params("generate resmoke tasks", vars)];
+            if tags.contains(&"multiversion") {
+                commands.insert(
+                    0,
+                    fn_call_with_params(
+                        "initialize multiversion tasks",
+                        hashmap! {
+                            "suite_last_lts".to_string() => ParamValue::from("last_lts"),
+                            "suite_last_continuous".to_string() => ParamValue::from("last_continuous"),
+                        },
+                    ),
+                );
+            }
+            Arc::new(EvgTask {
+                name: name.to_string(),
+                commands: Some(commands),
+                tags: Some(tags.into_iter().map(|tag| tag.to_string()).collect()),
+                ..Default::default()
+            })
+        };
+        let bv_0 = BuildVariant {
+            name: "bv_0".to_string(),
+            tasks: vec![
+                task_ref("jsCore_gen"),
+                task_ref("compile"),
+                task_ref("fuzzer_gen"),
+                task_ref("mv_fuzzer_gen"),
+                task_ref("mv_gen"),
+                task_ref("bazel_gen"),
+                task_ref("antithesis_gen"),
+                task_ref("antithesis_bazel_gen"),
+            ],
+            ..Default::default()
+        };
+        let bv_1 = BuildVariant {
+            name: "bv_1".to_string(),
+            tasks: vec![task_ref("compile")],
+            ..Default::default()
+        };
+        let build_variant_map = hashmap! {
+            "bv_0".to_string() => &bv_0,
+            "bv_1".to_string() => &bv_1,
+        };
+        let task_map = hashmap! {
+            "jsCore_gen".to_string() => gen_task("jsCore_gen", vec![], vec![]),
+            "fuzzer_gen".to_string() => gen_task(
+                "fuzzer_gen",
+                vec![("is_jstestfuzz", "true"), ("num_tasks", "10")],
+                vec![],
+            ),
+            "mv_fuzzer_gen".to_string() => gen_task(
+                "mv_fuzzer_gen",
+                vec![("is_jstestfuzz", "true"), ("num_tasks", "3")],
+                vec!["multiversion"],
+            ),
+            "mv_gen".to_string() => gen_task("mv_gen", vec![], vec!["multiversion"]),
+            "bazel_gen".to_string() => gen_task(
+                "bazel_gen",
+                vec![("is_bazel_test_shards", "true"), ("num_shards", "4")],
+                vec![],
+            ),
+            "antithesis_gen".to_string() => gen_task(
+                "antithesis_gen",
+                vec![("is_antithesis", "true")],
+                vec![],
+            ),
+            "antithesis_bazel_gen".to_string() => gen_task(
+                "antithesis_bazel_gen",
+                vec![("is_antithesis", "true"), ("is_bazel_test_shards", "true"), ("num_shards", "4")],
+                vec![],
+            ),
+            "compile".to_string() => Arc::new(EvgTask {
+                name: "compile".to_string(),
+                ..Default::default()
+            }),
+        };
+
+        let evg_config_utils = Arc::new(EvgConfigUtilsImpl::new());
+        let config_extraction_service = Arc::new(ConfigExtractionServiceImpl::new(
+            evg_config_utils.clone(),
+            Arc::new(MockMultiversionService {}),
+            "generating_task".to_string(),
+            "config_location".to_string(),
+            None,
+            false,
+        ));
+        let mut task_kind_registry = GeneratedTaskKindRegistry::new(
+            evg_config_utils.clone(),
+            Arc::new(ResmokeTaskKind::new(
+                evg_config_utils.clone(),
+                config_extraction_service.clone(),
+                Arc::new(MockGenResmokeTaskService {}),
+                false,
+                MAX_SUB_TASKS_PER_TASK,
+            )),
+        );
+        task_kind_registry.register_standard_kinds(
+            Arc::new(FuzzerTaskKind::new(
+                evg_config_utils.clone(),
+                config_extraction_service.clone(),
+                Arc::new(GenFuzzerServiceImpl::new()),
+            )),
+            Arc::new(AntithesisTaskKind::new(config_extraction_service.clone())),
+            Arc::new(BazelShardTaskKind::new(
+                evg_config_utils.clone(),
+                config_extraction_service,
+                Arc::new(BazelProxy::new(BAZEL_BINARY)),
+                Arc::new(MockTaskHistoryService {}),
+                MAX_SUB_TASKS_PER_TASK,
+                false,
+            )),
+        );
+
+        let demands = estimate_sub_task_demands(
+            &build_variant_map,
+            &task_map,
+            evg_config_utils.as_ref(),
+            &task_kind_registry,
+        );
+
+        assert_eq!(
+            demands["bv_0"],
+            SubTaskDemand {
+                fixed_sub_tasks: 10 + 3 * 2 + 4 + 1 + 1,
+                split_tasks: 1 + 2,
+                desired_split_sub_tasks: (1 + 2) * MAX_SUB_TASKS_PER_TASK,
+            }
+        );
+        assert_eq!(demands["bv_1"], SubTaskDemand::default());
+    }
+
+    // tests for sub_task_budget_overruns.
+    #[test]
+    fn test_sub_task_budget_overruns_should_report_build_variants_over_budget() {
+        let task_refs = |count: usize| {
+            (0..count)
+                .map(|i| {
+                    EvgTask {
+                        name: format!("sub_task_{}", i),
+                        ..Default::default()
+                    }
+                    .get_reference(None, None)
+                })
+                .collect()
+        };
+        let build_variant = |name: &str, max_total: Option<&str>| BuildVariant {
+            name: name.to_string(),
+            expansions: max_total.map(|max_total| {
+                btreemap! {
+                    "max_total_generated_subtasks".to_string() => max_total.to_string(),
+                }
+            }),
+            ..Default::default()
+        };
+        let over_budget = build_variant("over_budget", Some("10"));
+        let within_budget = build_variant("within_budget", Some("10"));
+        let no_budget = build_variant("no_budget", None);
+        let build_variant_map = hashmap! {
+            "over_budget".to_string() => &over_budget,
+            "within_budget".to_string() => &within_budget,
+            "no_budget".to_string() => &no_budget,
+        };
+        let generated_build_variants = vec![
+            BuildVariant {
+                tasks: task_refs(12),
+                ..build_variant("over_budget", None)
+            },
+            BuildVariant {
+                tasks: task_refs(10),
+                ..build_variant("within_budget", None)
+            },
+            BuildVariant {
+                tasks: task_refs(50),
+                ..build_variant("no_budget", None)
+            },
+        ];
+
+        let overruns = sub_task_budget_overruns(
+            &generated_build_variants,
+            &build_variant_map,
+            &EvgConfigUtilsImpl::new(),
+        );
+
+        assert_eq!(
+            overruns,
+            vec![
+                "12 sub-tasks were generated for 'over_budget', exceeding its `max_total_generated_subtasks` of 10"
+            ]
+        );
+    }
+
+    // tests for merge_generated_config.
+    #[test]
+    fn test_merge_generated_config_should_extend_existing_build_variant() {
+        let task_ref = |name: &str| {
+            EvgTask {
+                name: name.to_string(),
+                ..Default::default()
+            }
+            .get_reference(None, None)
+        };
+        let mut generated_build_variants = vec![BuildVariant {
+            name: "bv_0".to_string(),
+            tasks: vec![task_ref("task_0")],
+            ..Default::default()
+        }];
+
+        merge_generated_config(
+            &mut generated_build_variants,
+            "bv_0".to_string(),
+            GeneratedConfig {
+                gen_task_specs: vec![task_ref("burn_in_0")],
+                display_tasks: vec![],
+            },
+        );
+        merge_generated_config(
+            &mut generated_build_variants,
+            "bv_1".to_string(),
+            GeneratedConfig {
+                gen_task_specs: vec![task_ref("burn_in_1")],
+                display_tasks: vec![],
+            },
+        );
+
+        assert_eq!(generated_build_variants.len(), 2);
+        assert_eq!(generated_build_variants[0].tasks.len(), 2);
+        assert_eq!(generated_build_variants[0].tasks[1].name, "burn_in_0");
+        assert_eq!(generated_build_variants[1].name, "bv_1");
+        assert_eq!(generated_build_variants[1].activate, Some(false));
+    }
+
+    // tests for find_duplicate_sub_task_names.
+    fn build_generated_suite(
+        task_name: &str,
+        sub_task_names: Vec<&str>,
+    ) -> Box<dyn GeneratedSuite> {
+        Box::new(GeneratedResmokeSuite {
+            task_name: task_name.to_string(),
+            sub_suites: sub_task_names
+                .into_iter()
+                .map(|name| GeneratedSubTask {
+                    evg_task: EvgTask {
+                        name: name.to_string(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .collect(),
+        })
+    }
+
+    #[test]
+    fn test_find_duplicate_sub_task_names_should_return_nothing_for_unique_names() {
+        let generated_tasks: GenTaskCollection = DashMap::new();
+        generated_tasks.insert(
+            "task_0-linux".to_string(),
+            build_generated_suite("task_0", vec!["task_0_0-linux", "task_0_1-linux"]),
+        );
+        generated_tasks.insert(
+            "task_0-linux-enterprise".to_string(),
+            build_generated_suite("task_0", vec!["task_0_0-linux-enterprise"]),
+        );
+
+        assert!(find_duplicate_sub_task_names(&generated_tasks).is_empty());
+    }
+
+    #[test]
+    fn test_find_duplicate_sub_task_names_should_report_every_collision() {
+        let generated_tasks: GenTaskCollection = DashMap::new();
+        generated_tasks.insert(
+            "task_0-linux".to_string(),
+            build_generated_suite("task_0", vec!["sub_task_0", "sub_task_1"]),
+        );
+        generated_tasks.insert(
+            "task_0-linux_suffix".to_string(),
+            build_generated_suite("task_0", vec!["sub_task_0", "sub_task_1"]),
+        );
+
+        let duplicates = find_duplicate_sub_task_names(&generated_tasks);
+
         assert_eq!(
-            lookup_task_name(is_enterprise, task_name, platform, None),
-            expected_task_name.to_string()
+            duplicates,
+            vec![
+                "'sub_task_0' is generated by both 'task_0-linux' and 'task_0-linux_suffix'",
+                "'sub_task_1' is generated by both 'task_0-linux' and 'task_0-linux_suffix'",
+            ]
         );
     }
 
+    #[rstest]
+    #[case(false, "{\n  \"name\": \"task_0\"\n}")]
+    #[case(true, "{\"name\":\"task_0\"}")]
+    fn test_write_json_file(#[case] compact: bool, #[case] expected: &str) {
+        let tmp_dir = TempDir::new("write_json_file").unwrap();
+        let path = tmp_dir.path().join("task_0.json");
+        let value = hashmap! {"name" => "task_0"};
+
+        write_json_file(&path, &value, compact).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+
+        assert_eq!(contents, expected);
+    }
+
     struct MockEvgConfigUtils {}
     impl EvgConfigUtils for MockEvgConfigUtils {
         fn get_multiversion_generate_tasks(
@@ -1126,6 +3286,10 @@ mod tests {
             todo!()
         }
 
+        fn lookup_project_expansion(&self, _name: &str) -> Option<String> {
+            todo!()
+        }
+
         fn lookup_and_split_by_whitespace_build_variant_expansion(
             &self,
             _name: &str,
@@ -1210,6 +3374,10 @@ mod tests {
         fn discover_tasks(&self, _build_variant: &str) -> Result<Vec<DiscoveredTask>> {
             todo!()
         }
+
+        fn discovery_report(&self) -> BTreeMap<String, BuildVariantDiscovery> {
+            todo!()
+        }
     }
 
     struct MockConfigExtractionService {}
@@ -1232,13 +3400,107 @@ mod tests {
             todo!()
         }
 
-        fn determine_large_distro(
+        fn task_def_to_antithesis_params(
+            &self,
+            _task_def: &EvgTask,
+            _build_variant: &BuildVariant,
+        ) -> Result<AntithesisGenTaskParams> {
+            todo!()
+        }
+
+        fn task_def_to_bazel_shard_params(
+            &self,
+            _task_def: &EvgTask,
+            _build_variant: &BuildVariant,
+        ) -> Result<BazelShardGenParams> {
+            todo!()
+        }
+
+        fn determine_distro_for_tier(
             &self,
             _generated_suite: &dyn GeneratedSuite,
             _build_variant: &BuildVariant,
         ) -> Result<Option<String>> {
             todo!()
         }
+
+        fn determine_sub_task_limits(
+            &self,
+            _build_variant: &BuildVariant,
+        ) -> Result<SubTaskLimits> {
+            todo!()
+        }
+
+        fn determine_multiversion_generate_tasks(
+            &self,
+            _task_def: &EvgTask,
+            _build_variant: &BuildVariant,
+        ) -> Option<Vec<MultiversionGenerateTaskConfig>> {
+            todo!()
+        }
+    }
+
+    struct MockTaskHistoryService {}
+    #[async_trait]
+    impl TaskHistoryService for MockTaskHistoryService {
+        fn build_url(&self, _task: &str, _variant: &str) -> String {
+            todo!()
+        }
+
+        async fn get_task_history(
+            &self,
+            _task: &str,
+            _variant: &str,
+        ) -> Result<TaskRuntimeHistory> {
+            todo!()
+        }
+
+        async fn get_bazel_target_history(
+            &self,
+            _task: &str,
+            _variant: &str,
+        ) -> Result<HashMap<String, f64>> {
+            todo!()
+        }
+
+        async fn prefetch_task_histories(&self, _task_variants: Vec<(String, String)>) {
+            todo!()
+        }
+
+        fn fetch_stats(&self) -> HistoryFetchStats {
+            todo!()
+        }
+    }
+
+    struct MockTestDiscovery {}
+    impl TestDiscovery for MockTestDiscovery {
+        fn discover_tests(&self, _suite_name: &str) -> Result<Vec<String>> {
+            todo!()
+        }
+
+        fn discover_all_tests(&self, _suite_name: &str) -> Result<Vec<String>> {
+            todo!()
+        }
+
+        fn get_suite_config(&self, _suite_name: &str) -> Result<ResmokeSuiteConfig> {
+            todo!()
+        }
+
+        fn get_multiversion_config(&self) -> Result<MultiversionConfig> {
+            todo!()
+        }
+
+        fn generate_multiversion_exclude_tags(
+            &self,
+            _old_version: &str,
+            _output_file: &Path,
+        ) -> Result<()> {
+            todo!()
+        }
+
+        fn skipped_covered_tests(&self) -> BTreeMap<String, Vec<String>> {
+            todo!()
+        }
     }
 
     struct MockMultiversionService {}
@@ -1250,20 +3512,26 @@ mod tests {
             &self,
             multiversion_generate_tasks: Option<Vec<MultiversionGenerateTaskConfig>>,
             _last_versions_expansion: Option<String>,
+            _excluded_versions_expansion: Option<String>,
         ) -> Option<Vec<MultiversionGenerateTaskConfig>> {
             return multiversion_generate_tasks;
         }
+
+        fn old_versions(&self) -> Vec<String> {
+            todo!()
+        }
     }
 
     struct MockBurnInService {
         sub_suites: Vec<GeneratedSubTask>,
     }
+    #[async_trait]
     impl BurnInService for MockBurnInService {
-        fn generate_burn_in_suite(
+        async fn generate_burn_in_suite(
             &self,
             _build_variant: &BuildVariant,
             _run_build_variant_name: &str,
-            _task_map: Arc<HashMap<String, EvgTask>>,
+            _task_map: Arc<HashMap<String, Arc<EvgTask>>>,
         ) -> Result<Box<dyn GeneratedSuite>> {
             Ok(Box::new(GeneratedResmokeSuite {
                 task_name: "burn_in_tests".to_string(),
@@ -1277,6 +3545,7 @@ mod tests {
             _run_build_variant_name: String,
             _generated_task: &dyn GeneratedSuite,
             _compile_task_dependency: String,
+            _in_base_variant: bool,
         ) -> Result<BuildVariant> {
             todo!()
         }
@@ -1284,7 +3553,7 @@ mod tests {
         fn generate_burn_in_tasks_suite(
             &self,
             _build_variant: &BuildVariant,
-            _task_map: Arc<HashMap<String, EvgTask>>,
+            _task_map: Arc<HashMap<String, Arc<EvgTask>>>,
         ) -> Result<Box<dyn GeneratedSuite>> {
             Ok(Box::new(GeneratedResmokeSuite {
                 task_name: "burn_in_tasks".to_string(),
@@ -1301,15 +3570,100 @@ mod tests {
 
     fn build_mocked_dependencies(burn_in_service: MockBurnInService) -> Dependencies {
         Dependencies {
+            evg_config_service: Arc::new(MockConfigService {}),
             evg_config_utils: Arc::new(MockEvgConfigUtils {}),
             gen_task_service: Arc::new(build_mock_generate_tasks_service()),
             resmoke_config_actor: Arc::new(tokio::sync::Mutex::new(
                 MockResmokeConfigActorService {},
             )),
             burn_in_service: Arc::new(burn_in_service),
+            burn_in_discovery: Arc::new(MockBurnInDiscovery {}),
+            discovery_service: Arc::new(MockTestDiscovery {}),
+            multiversion_service: Arc::new(MockMultiversionService {}),
+            variant_cloning_service: Arc::new(VariantCloningServiceImpl::new()),
+            task_history_service: Arc::new(MockTaskHistoryService {}),
+            ownership_service: Arc::new(OwnershipServiceImpl::new(None)),
+            generation_checkpoint: Arc::new(GenerationCheckpoint::new(Path::new("."), false, "")),
+            worker_watchdog: Arc::new(WorkerWatchdog::new(None, false, Duration::from_secs(60))),
+        }
+    }
+
+    struct MockGenerateTasksService {}
+    #[async_trait]
+    impl GenerateTasksService for MockGenerateTasksService {
+        async fn build_generated_tasks(
+            &self,
+            _deps: &Dependencies,
+        ) -> Result<Arc<GenTaskCollection>> {
+            todo!()
+        }
+
+        fn generate_build_variants(
+            &self,
+            _deps: &Dependencies,
+            generated_tasks: Arc<GenTaskCollection>,
+        ) -> Result<Vec<BuildVariant>> {
+            let task_names: Vec<String> = generated_tasks
+                .iter()
+                .map(|entry| entry.key().clone())
+                .collect();
+            Ok(task_names
+                .par_iter()
+                .map(|name| BuildVariant {
+                    name: name.clone(),
+                    ..Default::default()
+                })
+                .collect())
+        }
+
+        fn generate_burn_in_build_variant_info(
+            &self,
+            _burn_in_tag_build_variant_info: &mut HashMap<String, BurnInTagBuildVariantInfo>,
+            _build_variant: &BuildVariant,
+            _build_variant_map: &HashMap<String, &BuildVariant>,
+        ) {
+            todo!()
+        }
+
+        async fn generate_task(
+            &self,
+            _task_def: &EvgTask,
+            _build_variant: &BuildVariant,
+        ) -> Result<Option<Box<dyn GeneratedSuite>>> {
+            todo!()
+        }
+
+        fn generate_unsplit_task(
+            &self,
+            _task_def: &EvgTask,
+            _build_variant: &BuildVariant,
+        ) -> Result<Option<Box<dyn GeneratedSuite>>> {
+            todo!()
         }
     }
 
+    // tests for generate_build_variants_blocking.
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_generate_build_variants_blocking_should_work_on_a_current_thread_runtime() {
+        let mut mock_deps = build_mocked_dependencies(build_mocked_burn_in_service(vec![]));
+        mock_deps.gen_task_service = Arc::new(MockGenerateTasksService {});
+        let generated_tasks: Arc<GenTaskCollection> = Arc::new(DashMap::new());
+        generated_tasks.insert(
+            "my_task".to_string(),
+            Box::new(GeneratedResmokeSuite {
+                task_name: "my_task".to_string(),
+                sub_suites: vec![],
+            }),
+        );
+
+        let build_variants = generate_build_variants_blocking(&mock_deps, generated_tasks)
+            .await
+            .unwrap();
+
+        assert_eq!(build_variants.len(), 1);
+        assert_eq!(build_variants[0].name, "my_task");
+    }
+
     // tests for create_burn_in_worker.
     #[tokio::test]
     async fn test_create_burn_in_worker_should_add_task_when_burn_in_suites_are_present() {
@@ -1321,24 +3675,21 @@ mod tests {
         }]);
         let mock_deps = build_mocked_dependencies(mock_burn_in_service);
         let task_map = Arc::new(HashMap::new());
-        let generated_tasks = Arc::new(Mutex::new(HashMap::new()));
+        let generated_tasks = Arc::new(DashMap::new());
 
         let thread_handle = create_burn_in_worker(
             &mock_deps,
             task_map.clone(),
-            &BuildVariant {
+            Arc::new(BuildVariant {
                 ..Default::default()
-            },
+            }),
             "run_bv_name".to_string(),
             generated_tasks.clone(),
         );
-        thread_handle.await.unwrap();
+        thread_handle.await.unwrap().unwrap();
 
         assert_eq!(
-            generated_tasks
-                .lock()
-                .unwrap()
-                .contains_key(&format!("{}-{}", BURN_IN_TESTS_PREFIX, "run_bv_name")),
+            generated_tasks.contains_key(&format!("{}-{}", BURN_IN_TESTS_PREFIX, "run_bv_name")),
             true
         );
     }
@@ -1348,24 +3699,21 @@ mod tests {
         let mock_burn_in_service = build_mocked_burn_in_service(vec![]);
         let mock_deps = build_mocked_dependencies(mock_burn_in_service);
         let task_map = Arc::new(HashMap::new());
-        let generated_tasks = Arc::new(Mutex::new(HashMap::new()));
+        let generated_tasks = Arc::new(DashMap::new());
 
         let thread_handle = create_burn_in_worker(
             &mock_deps,
             task_map.clone(),
-            &BuildVariant {
+            Arc::new(BuildVariant {
                 ..Default::default()
-            },
+            }),
             "run_bv_name".to_string(),
             generated_tasks.clone(),
         );
-        thread_handle.await.unwrap();
+        thread_handle.await.unwrap().unwrap();
 
         assert_eq!(
-            generated_tasks
-                .lock()
-                .unwrap()
-                .contains_key(&format!("{}-{}", BURN_IN_TESTS_PREFIX, "run_bv_name")),
+            generated_tasks.contains_key(&format!("{}-{}", BURN_IN_TESTS_PREFIX, "run_bv_name")),
             false
         );
     }
@@ -1381,24 +3729,21 @@ mod tests {
         }]);
         let mock_deps = build_mocked_dependencies(mock_burn_in_service);
         let task_map = Arc::new(HashMap::new());
-        let generated_tasks = Arc::new(Mutex::new(HashMap::new()));
+        let generated_tasks = Arc::new(DashMap::new());
 
         let thread_handle = create_burn_in_tasks_worker(
             &mock_deps,
             task_map.clone(),
-            &BuildVariant {
+            Arc::new(BuildVariant {
                 name: "bv_name".to_string(),
                 ..Default::default()
-            },
+            }),
             generated_tasks.clone(),
         );
-        thread_handle.await.unwrap();
+        thread_handle.await.unwrap().unwrap();
 
         assert_eq!(
-            generated_tasks
-                .lock()
-                .unwrap()
-                .contains_key(&format!("{}-{}", BURN_IN_TASKS_PREFIX, "bv_name")),
+            generated_tasks.contains_key(&format!("{}-{}", BURN_IN_TASKS_PREFIX, "bv_name")),
             true
         );
     }
@@ -1408,24 +3753,21 @@ mod tests {
         let mock_burn_in_service = build_mocked_burn_in_service(vec![]);
         let mock_deps = build_mocked_dependencies(mock_burn_in_service);
         let task_map = Arc::new(HashMap::new());
-        let generated_tasks = Arc::new(Mutex::new(HashMap::new()));
+        let generated_tasks = Arc::new(DashMap::new());
 
         let thread_handle = create_burn_in_tasks_worker(
             &mock_deps,
             task_map.clone(),
-            &BuildVariant {
+            Arc::new(BuildVariant {
                 name: "bv_name".to_string(),
                 ..Default::default()
-            },
+            }),
             generated_tasks.clone(),
         );
-        thread_handle.await.unwrap();
+        thread_handle.await.unwrap().unwrap();
 
         assert_eq!(
-            generated_tasks
-                .lock()
-                .unwrap()
-                .contains_key(&format!("{}-{}", BURN_IN_TASKS_PREFIX, "bv_name")),
+            generated_tasks.contains_key(&format!("{}-{}", BURN_IN_TASKS_PREFIX, "bv_name")),
             false
         );
     }
@@ -0,0 +1,223 @@
+//! Report of the machine time the generated configuration is expected to use.
+//!
+//! The expected runtimes of generated sub-tasks are summed for every build variant that runs
+//! them, so that changes causing a large jump in machine time stand out before they are
+//! scheduled.
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use shrub_rs::models::variant::BuildVariant;
+
+use crate::task_types::generated_suite::GeneratedSubTask;
+
+/// Name of file to write the machine time report to.
+pub const MACHINE_TIME_REPORT_FILE: &str = "machine_time_report.json";
+/// Number of seconds in an hour.
+const SECONDS_PER_HOUR: f64 = 3600.0;
+
+/// Machine time expected for a single build variant.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VariantMachineTime {
+    /// Expected machine time of the generated sub-tasks run by the build variant, in hours.
+    pub expected_hours: f64,
+    /// Number of generated sub-tasks run by the build variant.
+    pub sub_tasks: usize,
+    /// Number of generated sub-tasks run by the build variant without an expected runtime.
+    pub sub_tasks_without_estimate: usize,
+}
+
+/// Machine time expected for the generated configuration.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MachineTimeReport {
+    /// Expected machine time of all generated sub-tasks across all build variants, in hours.
+    pub total_expected_hours: f64,
+    /// Expected machine time of each build variant, keyed by build variant name.
+    pub build_variants: BTreeMap<String, VariantMachineTime>,
+}
+
+impl MachineTimeReport {
+    /// Compute the machine time expected for the given generated configuration.
+    ///
+    /// Tasks referenced by a build variant that are not generated sub-tasks are not counted.
+    ///
+    /// # Arguments
+    ///
+    /// * `sub_tasks` - Generated sub-tasks.
+    /// * `build_variants` - Generated build variants referencing the sub-tasks.
+    ///
+    /// # Returns
+    ///
+    /// Report of the expected machine time.
+    pub fn new(sub_tasks: &[GeneratedSubTask], build_variants: &[BuildVariant]) -> Self {
+        let expected_runtimes: BTreeMap<&str, Option<f64>> = sub_tasks
+            .iter()
+            .map(|sub_task| {
+                (
+                    sub_task.evg_task.name.as_str(),
+                    sub_task.expected_runtime_secs,
+                )
+            })
+            .collect();
+
+        let mut report = Self::default();
+        for build_variant in build_variants {
+            let mut variant_seconds = 0.0;
+            let mut variant_time = VariantMachineTime::default();
+            for task_ref in &build_variant.tasks {
+                match expected_runtimes.get(task_ref.name.as_str()) {
+                    Some(Some(runtime)) => variant_seconds += runtime,
+                    Some(None) => variant_time.sub_tasks_without_estimate += 1,
+                    None => continue,
+                }
+                variant_time.sub_tasks += 1;
+            }
+            if variant_time.sub_tasks == 0 {
+                continue;
+            }
+
+            variant_time.expected_hours = variant_seconds / SECONDS_PER_HOUR;
+            report.total_expected_hours += variant_time.expected_hours;
+            report
+                .build_variants
+                .entry(build_variant.name.clone())
+                .and_modify(|existing| {
+                    existing.expected_hours += variant_time.expected_hours;
+                    existing.sub_tasks += variant_time.sub_tasks;
+                    existing.sub_tasks_without_estimate += variant_time.sub_tasks_without_estimate;
+                })
+                .or_insert(variant_time);
+        }
+
+        report
+    }
+
+    /// Number of generated sub-tasks referenced without an expected runtime.
+    pub fn sub_tasks_without_estimate(&self) -> usize {
+        self.build_variants
+            .values()
+            .map(|variant_time| variant_time.sub_tasks_without_estimate)
+            .sum()
+    }
+
+    /// Check that the expected machine time is within the given budget.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_machine_hours` - Budget of machine hours the configuration may use, if any.
+    pub fn check_budget(&self, max_machine_hours: Option<f64>) -> Result<()> {
+        if let Some(max_machine_hours) = max_machine_hours {
+            if self.total_expected_hours > max_machine_hours {
+                let mut largest: Vec<(&String, &VariantMachineTime)> =
+                    self.build_variants.iter().collect();
+                largest.sort_by(|(_, a), (_, b)| b.expected_hours.total_cmp(&a.expected_hours));
+                let summary: Vec<String> = largest
+                    .iter()
+                    .take(5)
+                    .map(|(name, variant_time)| {
+                        format!("{}: {:.1} hours", name, variant_time.expected_hours)
+                    })
+                    .collect();
+                bail!(
+                    "Generated configuration is expected to use {:.1} machine hours, more than the budget of {:.1} hours. Largest build variants:\n{}",
+                    self.total_expected_hours,
+                    max_machine_hours,
+                    summary.join("\n")
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the report to a generated configuration directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `target_directory` - Directory containing the generated configuration.
+    pub fn write(&self, target_directory: &Path) -> Result<()> {
+        std::fs::write(
+            target_directory.join(MACHINE_TIME_REPORT_FILE),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shrub_rs::models::task::EvgTask;
+
+    use super::*;
+
+    fn build_sub_task(name: &str, expected_runtime_secs: Option<f64>) -> GeneratedSubTask {
+        GeneratedSubTask {
+            evg_task: EvgTask {
+                name: name.to_string(),
+                ..Default::default()
+            },
+            expected_runtime_secs,
+            ..Default::default()
+        }
+    }
+
+    fn build_variant(name: &str, tasks: &[&str]) -> BuildVariant {
+        BuildVariant {
+            name: name.to_string(),
+            tasks: tasks
+                .iter()
+                .map(|task| {
+                    EvgTask {
+                        name: task.to_string(),
+                        ..Default::default()
+                    }
+                    .get_reference(None, None)
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn build_report() -> MachineTimeReport {
+        let sub_tasks = vec![
+            build_sub_task("auth_0", Some(3600.0)),
+            build_sub_task("auth_1", Some(1800.0)),
+            build_sub_task("core_0", None),
+        ];
+        let build_variants = vec![
+            build_variant("bv_0", &["auth_0", "auth_1", "core_0", "compile"]),
+            build_variant("bv_1", &["auth_0"]),
+            build_variant("bv_2", &["compile"]),
+        ];
+
+        MachineTimeReport::new(&sub_tasks, &build_variants)
+    }
+
+    #[test]
+    fn test_new_should_sum_expected_runtimes_per_build_variant() {
+        let report = build_report();
+
+        assert_eq!(report.total_expected_hours, 2.5);
+        assert_eq!(report.build_variants.len(), 2);
+        assert_eq!(
+            report.build_variants["bv_0"],
+            VariantMachineTime {
+                expected_hours: 1.5,
+                sub_tasks: 3,
+                sub_tasks_without_estimate: 1,
+            }
+        );
+        assert_eq!(report.build_variants["bv_1"].expected_hours, 1.0);
+        assert_eq!(report.sub_tasks_without_estimate(), 1);
+    }
+
+    #[test]
+    fn test_check_budget_should_fail_when_budget_is_exceeded() {
+        let report = build_report();
+
+        assert!(report.check_budget(None).is_ok());
+        assert!(report.check_budget(Some(3.0)).is_ok());
+        let error = report.check_budget(Some(2.0)).unwrap_err().to_string();
+        assert!(error.contains("2.5 machine hours"));
+        assert!(error.starts_with("Generated configuration is expected"));
+    }
+}
@@ -1,13 +1,22 @@
 use std::{
+    collections::HashMap,
+    ffi::OsString,
     path::{Path, PathBuf},
     process::exit,
-    time::Instant,
+    time::{Duration, Instant},
 };
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
 use mongo_task_generator::{
-    generate_configuration, Dependencies, ExecutionConfiguration, ProjectInfo,
+    args_with_config_file, check_config_files, diff_generated_configs,
+    finish_interrupted_generation, format_coverage_table, format_task_table,
+    generate_configuration, graph_generated_config, history_coverage, input_fingerprint,
+    list_generated_tasks, prepare_target_directory, regenerate_build_variants,
+    resolve_evg_project_file, wait_for_shutdown_signal, write_error_report, write_schemas,
+    Dependencies, ErrorCategory, ExecutionConfiguration, GenerationMetadata, HistoryKeyTemplate,
+    NativeDiscoveryMode, OutputOptions, ProjectInfo, ShutdownSignal, UploadLocation,
+    DEFAULT_HISTORY_KEY_TEMPLATE,
 };
 use serde::Deserialize;
 use tracing::{error, event, Level};
@@ -19,6 +28,9 @@ const DEFAULT_RESMOKE_COMMAND: &str = "python buildscripts/resmoke.py";
 const DEFAULT_BURN_IN_TESTS_COMMAND: &str = "python buildscripts/burn_in_tests.py run";
 const DEFAULT_TARGET_DIRECTORY: &str = "generated_resmoke_config";
 const DEFAULT_S3_TEST_STATS_ENDPOINT: &str = "https://mongo-test-stats.s3.amazonaws.com";
+const DEFAULT_MONITOR_INTERVAL_SECS: u64 = 60;
+/// Environment variable set by evergreen for commands run in a task.
+const EVG_TASK_ID_ENV: &str = "EVR_TASK_ID";
 
 /// Expansions from evergreen to determine settings for how task should be generated.
 #[derive(Debug, Deserialize)]
@@ -37,6 +49,9 @@ struct EvgExpansions {
     /// True if we should NOT skip tests covered by more complex suites.
     #[serde(default, deserialize_with = "deserialize_bool_string")]
     pub run_covered_tests: bool,
+    /// Revision burn_in should compare changes against instead of the patch base.
+    #[serde(default)]
+    pub burn_in_base_revision: Option<String>,
 }
 
 // The boolean YAML fields `is_patch` and `run_covered_tests` are set to the
@@ -75,6 +90,36 @@ impl EvgExpansions {
         Ok(evg_expansions?)
     }
 
+    /// Read all scalar expansions from the given yaml file.
+    ///
+    /// These are used as project-level expansions when resolving run vars that a build
+    /// variant does not define.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to YAML file to read.
+    ///
+    /// # Returns
+    ///
+    /// Map of expansion names to values.
+    pub fn project_expansions_from_yaml_file(path: &Path) -> Result<HashMap<String, String>> {
+        let contents = std::fs::read_to_string(path)?;
+        let expansions: HashMap<String, serde_yaml::Value> = serde_yaml::from_str(&contents)?;
+
+        Ok(expansions
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let value = match value {
+                    serde_yaml::Value::String(s) => s,
+                    serde_yaml::Value::Bool(b) => b.to_string(),
+                    serde_yaml::Value::Number(n) => n.to_string(),
+                    _ => return None,
+                };
+                Some((name, value))
+            })
+            .collect())
+    }
+
     /// File to store generated configuration under.
     pub fn config_location(&self) -> String {
         format!(
@@ -85,97 +130,761 @@ impl EvgExpansions {
 }
 
 #[derive(Parser, Debug)]
+#[clap(subcommand_negates_reqs = true, args_override_self = true)]
 struct Args {
-    /// File containing evergreen project configuration.
-    #[clap(long, value_parser, default_value = DEFAULT_EVG_PROJECT_FILE)]
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    /// YAML or TOML file containing defaults for command line options.
+    #[clap(long, env = "MTG_CONFIG", value_parser)]
+    config: Option<PathBuf>,
+
+    /// File containing evergreen project configuration, `-` for stdin or an `https://` URL.
+    #[clap(
+        long,
+        env = "MTG_EVG_PROJECT_FILE",
+        value_parser,
+        default_value = DEFAULT_EVG_PROJECT_FILE
+    )]
     evg_project_file: PathBuf,
 
     /// File containing expansions that impact task generation.
-    #[clap(long, value_parser)]
-    expansion_file: PathBuf,
+    #[clap(long, env = "MTG_EXPANSION_FILE", value_parser)]
+    expansion_file: Option<PathBuf>,
 
     /// File with information on how to authenticate against the evergreen API.
-    #[clap(long, value_parser, default_value = DEFAULT_EVG_AUTH_FILE)]
+    #[clap(long, env = "MTG_EVG_AUTH_FILE", value_parser, default_value = DEFAULT_EVG_AUTH_FILE)]
     evg_auth_file: PathBuf,
 
     /// Directory to write generated configuration files.
-    #[clap(long, value_parser, default_value = DEFAULT_TARGET_DIRECTORY)]
+    #[clap(
+        long,
+        env = "MTG_TARGET_DIRECTORY",
+        value_parser,
+        default_value = DEFAULT_TARGET_DIRECTORY
+    )]
     target_directory: PathBuf,
 
     /// Disable evergreen task-history queries and use task splitting fallback.
-    #[clap(long)]
+    #[clap(long, env = "MTG_USE_TASK_SPLIT_FALLBACK")]
     use_task_split_fallback: bool,
 
     /// Command to invoke resmoke.
-    #[clap(long, default_value = DEFAULT_RESMOKE_COMMAND)]
+    #[clap(long, env = "MTG_RESMOKE_COMMAND", default_value = DEFAULT_RESMOKE_COMMAND)]
     resmoke_command: String,
 
     /// File containing configuration for generating sub-tasks.
-    #[clap(long, value_parser)]
+    #[clap(long, env = "MTG_GENERATE_SUB_TASKS_CONFIG", value_parser)]
     generate_sub_tasks_config: Option<PathBuf>,
 
+    /// YAML file mapping names the generator uses by default to the names used by the project.
+    #[clap(long, env = "MTG_EVERGREEN_NAMES_CONFIG", value_parser)]
+    evergreen_names_config: Option<PathBuf>,
+
     /// Generate burn_in related tasks.
-    #[clap(long)]
+    #[clap(long, env = "MTG_BURN_IN")]
     burn_in: bool,
 
     /// Command to invoke burn_in_tests.
-    #[clap(long, default_value = DEFAULT_BURN_IN_TESTS_COMMAND)]
+    #[clap(long, env = "MTG_BURN_IN_TESTS_COMMAND", default_value = DEFAULT_BURN_IN_TESTS_COMMAND)]
     burn_in_tests_command: String,
 
+    /// Revision burn_in compares changes against [default: the patch base, or the
+    /// `burn_in_base_revision` expansion if set].
+    #[clap(long, env = "MTG_BURN_IN_BASE_REVISION")]
+    burn_in_base_revision: Option<String>,
+
     /// S3 endpoint to get test stats from.
-    #[clap(long, default_value = DEFAULT_S3_TEST_STATS_ENDPOINT)]
+    #[clap(
+        long,
+        env = "MTG_S3_TEST_STATS_ENDPOINT",
+        default_value = DEFAULT_S3_TEST_STATS_ENDPOINT
+    )]
     s3_test_stats_endpoint: String,
+
+    /// Layout of the keys of task stats under the S3 endpoint, using `{project}`, `{variant}`
+    /// and `{task}` placeholders.
+    #[clap(
+        long,
+        env = "MTG_HISTORY_KEY_TEMPLATE",
+        value_parser,
+        default_value = DEFAULT_HISTORY_KEY_TEMPLATE
+    )]
+    history_key_template: HistoryKeyTemplate,
+
+    /// Evergreen project to query for task history missing in the project being generated.
+    #[clap(long, env = "MTG_HISTORY_FALLBACK_PROJECT")]
+    history_fallback_project: Option<String>,
+
+    /// JSON file of test runtimes by task that take precedence over the runtime history of tests.
+    #[clap(long, env = "MTG_EXTRA_TEST_STATS", value_parser)]
+    extra_test_stats: Option<PathBuf>,
+
+    /// Fail if the sub-tasks config references build variants not in the project or contains
+    /// unknown keys, tasks request a large distro on build variants without one, or generated
+    /// tasks depend on project tasks or build variants that do not exist.
+    #[clap(long, env = "MTG_STRICT")]
+    strict: bool,
+
+    /// Run the unsplit suite for tasks that fail to split instead of failing generation.
+    #[clap(long, env = "MTG_ALLOW_PARTIAL")]
+    allow_partial: bool,
+
+    /// Fail if resmoke suites include tests that do not exist instead of skipping them.
+    #[clap(long, env = "MTG_FAIL_ON_MISSING_TESTS")]
+    fail_on_missing_tests: bool,
+
+    /// YAML file mapping test paths to the teams owning them, to report the load of each team.
+    #[clap(long, env = "MTG_TEST_OWNERSHIP_FILE", value_parser)]
+    test_ownership_file: Option<PathBuf>,
+
+    /// Discover the tests of suites selected only by `roots` globs without invoking resmoke.
+    #[clap(long, env = "MTG_NATIVE_TEST_DISCOVERY")]
+    native_test_discovery: bool,
+
+    /// Discover tests both natively and with resmoke, and warn about any differences.
+    #[clap(long, env = "MTG_VERIFY_NATIVE_TEST_DISCOVERY")]
+    verify_native_test_discovery: bool,
+
+    /// Directory of a previous generation whose tasks to reuse, only regenerating build variants.
+    #[clap(long, env = "MTG_REGENERATE_BUILD_VARIANTS_FROM", value_parser)]
+    regenerate_build_variants_from: Option<PathBuf>,
+
+    /// Reuse the tasks checkpointed by an interrupted run into the same target directory.
+    #[clap(long, env = "MTG_RESUME")]
+    resume: bool,
+
+    /// Remove files left in the target directory by an earlier run instead of failing.
+    #[clap(long, env = "MTG_CLEAN_TARGET", conflicts_with = "resume")]
+    clean_target: bool,
+
+    /// Seconds a worker may spend generating a task before it is aborted.
+    #[clap(long, env = "MTG_WORKER_TIMEOUT_SECS")]
+    worker_timeout_secs: Option<u64>,
+
+    /// Seconds between reports of the tasks still being generated.
+    #[clap(long, env = "MTG_MONITOR_INTERVAL_SECS", default_value_t = DEFAULT_MONITOR_INTERVAL_SECS)]
+    monitor_interval_secs: u64,
+
+    /// Write the generated suite files of each task to a sub-directory named after the task.
+    #[clap(long, env = "MTG_NEST_SUITE_FILES")]
+    nest_suite_files: bool,
+
+    /// Name sub-tasks with the default index width and delimiter, ignoring `task_naming` options.
+    #[clap(long, env = "MTG_LEGACY_TASK_NAMES")]
+    legacy_task_names: bool,
+
+    /// Write generated JSON configuration without pretty-printing it.
+    #[clap(long, env = "MTG_COMPACT_JSON")]
+    compact_json: bool,
+
+    /// S3 location of the form `s3://<bucket>/<key>` to upload the generated configuration to.
+    #[clap(long, env = "MTG_UPLOAD_TO", value_parser)]
+    upload_to: Option<UploadLocation>,
+
+    /// Fail if the generated configuration is expected to use more machine hours than this.
+    #[clap(long, env = "MTG_MAX_MACHINE_HOURS")]
+    max_machine_hours: Option<f64>,
+
+    /// Format of log output [default: json in evergreen, pretty otherwise].
+    #[clap(long, env = "MTG_LOG_FORMAT", value_enum)]
+    log_format: Option<LogFormat>,
+
+    /// Most verbose level of log output.
+    #[clap(long, env = "MTG_LOG_LEVEL", default_value_t = Level::INFO)]
+    log_level: Level,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List the tasks that would be generated without generating them.
+    ListGeneratedTasks {
+        /// Format to print the generated tasks in.
+        #[clap(long, value_enum, default_value_t = ListFormat::Json)]
+        format: ListFormat,
+    },
+    /// Report how much of each resmoke suite on a build variant has historic runtime data.
+    Stats {
+        /// Build variant to report on.
+        #[clap(long)]
+        build_variant: String,
+        /// Format to print the report in.
+        #[clap(long, value_enum, default_value_t = ListFormat::Table)]
+        format: ListFormat,
+    },
+    /// Compare two generated configurations and print the differences.
+    Diff {
+        /// Directory containing the old generated configuration.
+        old_dir: PathBuf,
+        /// Directory containing the new generated configuration.
+        new_dir: PathBuf,
+    },
+    /// Print a graph of the build variants, tasks and dependencies of a generated configuration.
+    Graph {
+        /// Directory containing the generated configuration.
+        generated_dir: PathBuf,
+        /// Format to print the graph in.
+        #[clap(long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+    },
+    /// Check the configuration files for errors and unknown keys without generating tasks.
+    CheckConfig {
+        /// Directory to write the JSON Schemas of the configuration files to.
+        #[clap(long, value_parser)]
+        schema_dir: Option<PathBuf>,
+    },
+}
+
+/// Formats to list generated tasks in.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ListFormat {
+    Json,
+    Table,
+}
+
+/// Formats to print the graph of a generated configuration in.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+/// Formats to write log output in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum LogFormat {
+    Json,
+    Pretty,
+    Compact,
+}
+
+/// Determine the format to log in.
+///
+/// # Arguments
+///
+/// * `log_format` - Format requested on the command line, if any.
+/// * `evg_task_id` - Value of the evergreen task ID environment variable, if set.
+///
+/// # Returns
+///
+/// The requested format, or json in evergreen and pretty otherwise.
+fn resolve_log_format(log_format: Option<LogFormat>, evg_task_id: Option<OsString>) -> LogFormat {
+    log_format.unwrap_or(if evg_task_id.is_some() {
+        LogFormat::Json
+    } else {
+        LogFormat::Pretty
+    })
 }
 
 /// Configure logging for the command execution.
-fn configure_logging() {
-    let format = format::json();
-    let subscriber = tracing_subscriber::fmt().event_format(format).finish();
+///
+/// # Arguments
+///
+/// * `log_format` - Format to log in, defaults to json in evergreen and pretty otherwise.
+/// * `log_level` - Most verbose level to log.
+fn configure_logging(log_format: Option<LogFormat>, log_level: Level) {
+    let log_format = resolve_log_format(log_format, std::env::var_os(EVG_TASK_ID_ENV));
+    let builder = tracing_subscriber::fmt().with_max_level(log_level);
 
-    tracing::subscriber::set_global_default(subscriber).unwrap();
+    match log_format {
+        LogFormat::Json => {
+            tracing::subscriber::set_global_default(builder.event_format(format::json()).finish())
+        }
+        LogFormat::Pretty => tracing::subscriber::set_global_default(
+            builder
+                .event_format(format::Format::default().pretty())
+                .finish(),
+        ),
+        LogFormat::Compact => tracing::subscriber::set_global_default(
+            builder
+                .event_format(format::Format::default().compact())
+                .finish(),
+        ),
+    }
+    .unwrap();
 }
 
 #[tokio::main]
 async fn main() {
-    let args = Args::parse();
-    configure_logging();
+    let command_line: Vec<String> = std::env::args().collect();
+    let args = match args_with_config_file(command_line.clone()) {
+        Ok(all_args) => Args::parse_from(all_args),
+        Err(err) => exit_with_config_file_error(command_line, err),
+    };
+    configure_logging(args.log_format, args.log_level);
 
-    let gen_sub_tasks_config_file = &args.generate_sub_tasks_config.map(|p| expand_path(&p));
-    let evg_expansions = EvgExpansions::from_yaml_file(&args.expansion_file)
-        .expect("Error reading expansions file.");
+    let target_directory = expand_path(&args.target_directory);
+    let gen_sub_tasks_config_file = args
+        .generate_sub_tasks_config
+        .as_ref()
+        .map(|p| expand_path(p));
+    let evergreen_names_config_file = args.evergreen_names_config.as_ref().map(|p| expand_path(p));
+    if let Some(Command::ListGeneratedTasks { format }) = &args.command {
+        let result = run_list_generated_tasks(
+            &args,
+            gen_sub_tasks_config_file.as_deref(),
+            evergreen_names_config_file.as_deref(),
+            *format,
+        )
+        .await;
+        match result {
+            Ok(output) => println!("{}", output),
+            Err(err) => {
+                eprintln!("Error encountered listing generated tasks: {:?}", err);
+                exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(Command::Stats {
+        build_variant,
+        format,
+    }) = &args.command
+    {
+        let result = run_stats(
+            &args,
+            gen_sub_tasks_config_file.as_deref(),
+            evergreen_names_config_file.as_deref(),
+            build_variant,
+            *format,
+        )
+        .await;
+        match result {
+            Ok(output) => println!("{}", output),
+            Err(err) => {
+                eprintln!("Error encountered reporting history coverage: {:?}", err);
+                exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(Command::Diff { old_dir, new_dir }) = &args.command {
+        match diff_generated_configs(&expand_path(old_dir), &expand_path(new_dir)) {
+            Ok(diff) => print!("{}", diff.format_report()),
+            Err(err) => {
+                eprintln!("Error encountered comparing configurations: {:?}", err);
+                exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(Command::Graph {
+        generated_dir,
+        format,
+    }) = &args.command
+    {
+        match graph_generated_config(&expand_path(generated_dir)) {
+            Ok(graph) => match format {
+                GraphFormat::Dot => print!("{}", graph.to_dot()),
+                GraphFormat::Mermaid => print!("{}", graph.to_mermaid()),
+            },
+            Err(err) => {
+                eprintln!("Error encountered graphing configuration: {:?}", err);
+                exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(Command::CheckConfig { schema_dir }) = &args.command {
+        run_check_config(
+            args.expansion_file.as_deref(),
+            gen_sub_tasks_config_file.as_deref(),
+            evergreen_names_config_file.as_deref(),
+            schema_dir.as_deref(),
+        );
+        return;
+    }
+
+    let start = Instant::now();
+    let result = run_generation(
+        &args,
+        gen_sub_tasks_config_file.as_deref(),
+        evergreen_names_config_file.as_deref(),
+    )
+    .await;
+    event!(
+        Level::INFO,
+        "generation completed: {duration_secs} seconds",
+        duration_secs = start.elapsed().as_secs()
+    );
+    if let Err(err) = result {
+        if let Err(report_err) = write_error_report(&target_directory, &err) {
+            eprintln!("Unable to write error report: {:?}", report_err);
+        }
+        eprintln!("Error encountered during execution: {:?}", err);
+        exit(
+            err.downcast_ref::<ShutdownSignal>()
+                .map(|signal| signal.exit_code())
+                .unwrap_or(1),
+        );
+    }
+}
+
+/// Report an error reading the configuration file given with `--config` and exit.
+///
+/// The options in the configuration file could not be read, so the error report is written to
+/// the target directory given on the command line or in the environment, if any.
+///
+/// # Arguments
+///
+/// * `command_line` - Command line arguments, including the name of the executable.
+/// * `err` - Error encountered reading the configuration file.
+fn exit_with_config_file_error(command_line: Vec<String>, err: anyhow::Error) -> ! {
+    let err = err.context(ErrorCategory::Config);
+    let target_directory = Args::try_parse_from(command_line)
+        .map(|args| expand_path(&args.target_directory))
+        .unwrap_or_else(|_| PathBuf::from(DEFAULT_TARGET_DIRECTORY));
+    if let Err(report_err) = write_error_report(&target_directory, &err) {
+        eprintln!("Unable to write error report: {:?}", report_err);
+    }
+    eprintln!("Error encountered reading config file: {:?}", err);
+    exit(1);
+}
+
+/// Describe the options that affect the tasks a run generates.
+///
+/// Only the options that change the generated tasks are listed, so an interrupted run can be
+/// resumed with options that only control how the run is executed, like logging, changed.
+///
+/// # Arguments
+///
+/// * `args` - Command line arguments.
+fn generation_options(args: &Args) -> String {
+    let options = [
+        (
+            "use_task_split_fallback",
+            args.use_task_split_fallback.to_string(),
+        ),
+        ("resmoke_command", args.resmoke_command.clone()),
+        (
+            "s3_test_stats_endpoint",
+            args.s3_test_stats_endpoint.clone(),
+        ),
+        (
+            "history_key_template",
+            args.history_key_template.to_string(),
+        ),
+        (
+            "history_fallback_project",
+            args.history_fallback_project.clone().unwrap_or_default(),
+        ),
+        ("allow_partial", args.allow_partial.to_string()),
+        (
+            "fail_on_missing_tests",
+            args.fail_on_missing_tests.to_string(),
+        ),
+        (
+            "native_test_discovery",
+            args.native_test_discovery.to_string(),
+        ),
+        (
+            "verify_native_test_discovery",
+            args.verify_native_test_discovery.to_string(),
+        ),
+        ("nest_suite_files", args.nest_suite_files.to_string()),
+        ("legacy_task_names", args.legacy_task_names.to_string()),
+    ];
+    options
+        .iter()
+        .map(|(name, value)| format!("{}={}\n", name, value))
+        .collect()
+}
+
+/// Generate the configuration for all generated tasks in the project.
+///
+/// If a SIGTERM or SIGINT is received, generation is stopped, the resmoke configuration files
+/// already queued are written and an `Interrupted` error is returned.
+///
+/// # Arguments
+///
+/// * `args` - Command line arguments.
+/// * `gen_sub_tasks_config_file` - File containing configuration for generating sub-tasks.
+/// * `evergreen_names_config_file` - File overriding the names used in the project.
+async fn run_generation(
+    args: &Args,
+    gen_sub_tasks_config_file: Option<&Path>,
+    evergreen_names_config_file: Option<&Path>,
+) -> Result<()> {
+    let target_directory = expand_path(&args.target_directory);
+    let evg_auth_file = expand_path(&args.evg_auth_file);
+    let extra_test_stats_file = args.extra_test_stats.as_ref().map(|p| expand_path(p));
+    let evg_project_file = resolve_evg_project_file(&args.evg_project_file, &evg_auth_file)
+        .await
+        .context(ErrorCategory::Config)?;
+    let regenerate_from = args
+        .regenerate_build_variants_from
+        .as_ref()
+        .map(|p| expand_path(p));
+    let expansion_file = args
+        .expansion_file
+        .as_ref()
+        .context("--expansion-file is required to generate tasks")
+        .context(ErrorCategory::Config)?;
+    let evg_expansions =
+        EvgExpansions::from_yaml_file(expansion_file).context(ErrorCategory::Config)?;
+    let project_expansions = EvgExpansions::project_expansions_from_yaml_file(expansion_file)
+        .context(ErrorCategory::Config)?;
     let project_info = ProjectInfo::new(
-        &args.evg_project_file,
+        evg_project_file.path(),
         &evg_expansions.project,
-        gen_sub_tasks_config_file.as_ref(),
+        gen_sub_tasks_config_file,
+        evergreen_names_config_file,
     );
+    let input_files: Vec<&Path> = [
+        Some(evg_project_file.path()),
+        Some(expansion_file.as_path()),
+        gen_sub_tasks_config_file,
+        evergreen_names_config_file,
+        extra_test_stats_file.as_deref(),
+        args.test_ownership_file.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let input_fingerprint = input_fingerprint(&input_files, &generation_options(args))
+        .context(ErrorCategory::Config)?;
+    // A resumed run reuses the files written by the run it resumes.
+    if !args.resume {
+        let mut inputs = input_files.clone();
+        inputs.push(&evg_auth_file);
+        inputs.extend(regenerate_from.as_deref());
+        inputs.extend(args.config.as_deref());
+        prepare_target_directory(&target_directory, args.clean_target, &inputs)
+            .context(ErrorCategory::Config)?;
+    }
     let execution_config = ExecutionConfiguration {
         project_info: &project_info,
-        evg_auth_file: &expand_path(&args.evg_auth_file),
+        evg_auth_file: &evg_auth_file,
         use_task_split_fallback: args.use_task_split_fallback,
         resmoke_command: &args.resmoke_command,
-        target_directory: &expand_path(&args.target_directory),
+        target_directory: &target_directory,
         generating_task: &evg_expansions.task_name,
         config_location: &evg_expansions.config_location(),
         gen_burn_in: args.burn_in,
         skip_covered_tests: evg_expansions.is_patch && !evg_expansions.run_covered_tests,
         burn_in_tests_command: &args.burn_in_tests_command,
+        burn_in_base_revision: args
+            .burn_in_base_revision
+            .as_deref()
+            .or(evg_expansions.burn_in_base_revision.as_deref()),
         s3_test_stats_endpoint: &args.s3_test_stats_endpoint,
+        history_key_template: &args.history_key_template,
+        history_fallback_project: args.history_fallback_project.as_deref(),
+        extra_test_stats_file: extra_test_stats_file.as_deref(),
+        project_expansions: &project_expansions,
+        strict: args.strict,
+        allow_partial: args.allow_partial,
+        test_ownership_file: args.test_ownership_file.as_deref(),
+        fail_on_missing_tests: args.fail_on_missing_tests,
+        native_test_discovery: if args.verify_native_test_discovery {
+            NativeDiscoveryMode::Verify
+        } else if args.native_test_discovery {
+            NativeDiscoveryMode::On
+        } else {
+            NativeDiscoveryMode::Off
+        },
+        resume: args.resume,
+        input_fingerprint: &input_fingerprint,
+        worker_timeout: args.worker_timeout_secs.map(Duration::from_secs),
+        monitor_interval: Duration::from_secs(args.monitor_interval_secs),
+        nest_suite_files: args.nest_suite_files,
+        legacy_task_names: args.legacy_task_names,
     };
-    let deps = Dependencies::new(execution_config).unwrap();
+    let deps = Dependencies::new(execution_config)?;
 
-    let start = Instant::now();
-    let result = generate_configuration(&deps, &args.target_directory).await;
-    event!(
-        Level::INFO,
-        "generation completed: {duration_secs} seconds",
-        duration_secs = start.elapsed().as_secs()
+    let output_options = OutputOptions {
+        compact_json: args.compact_json,
+        upload_to: args.upload_to.clone(),
+        metadata: Some(GenerationMetadata::new(&project_info).context(ErrorCategory::Config)?),
+        evergreen_names: project_info.get_evergreen_names_config()?,
+        max_machine_hours: args.max_machine_hours,
+        strict_dependencies: args.strict,
+    };
+
+    let signal = {
+        let generation = async {
+            match &regenerate_from {
+                Some(generated_tasks_directory) => {
+                    regenerate_build_variants(
+                        &deps,
+                        generated_tasks_directory,
+                        &target_directory,
+                        &output_options,
+                    )
+                    .await
+                }
+                None => generate_configuration(&deps, &target_directory, &output_options).await,
+            }
+        };
+        tokio::select! {
+            result = generation => return result,
+            signal = wait_for_shutdown_signal() => signal,
+        }
+    };
+
+    // The generation was dropped above, aborting its workers. Finish writing any files they
+    // queued before reporting the interruption.
+    if let Err(err) = finish_interrupted_generation(&deps).await {
+        event!(
+            Level::ERROR,
+            error = format!("{:?}", err),
+            "Error finishing interrupted generation"
+        );
+    }
+    Err(anyhow::Error::new(signal)).context(ErrorCategory::Interrupted)
+}
+
+/// List the tasks that would be generated for the project.
+///
+/// # Arguments
+///
+/// * `args` - Command line arguments.
+/// * `gen_sub_tasks_config_file` - File containing configuration for generating sub-tasks.
+/// * `evergreen_names_config_file` - File overriding the names used in the project.
+/// * `format` - Format to list the generated tasks in.
+///
+/// # Returns
+///
+/// The generated tasks formatted for printing.
+async fn run_list_generated_tasks(
+    args: &Args,
+    gen_sub_tasks_config_file: Option<&Path>,
+    evergreen_names_config_file: Option<&Path>,
+    format: ListFormat,
+) -> Result<String> {
+    let evg_project_file =
+        resolve_evg_project_file(&args.evg_project_file, &expand_path(&args.evg_auth_file)).await?;
+    let project_expansions = read_project_expansions(args.expansion_file.as_deref())?;
+    let project = project_expansions
+        .get("project")
+        .cloned()
+        .unwrap_or_default();
+    let project_info = ProjectInfo::new(
+        evg_project_file.path(),
+        &project,
+        gen_sub_tasks_config_file,
+        evergreen_names_config_file,
     );
-    if let Err(err) = result {
-        eprintln!("Error encountered during execution: {:?}", err);
-        exit(1);
+
+    let tasks = list_generated_tasks(&project_info, &project_expansions)?;
+    Ok(match format {
+        ListFormat::Json => serde_json::to_string_pretty(&tasks)?,
+        ListFormat::Table => format_task_table(&tasks),
+    })
+}
+
+/// Read the project expansions from the given expansions file.
+///
+/// # Arguments
+///
+/// * `expansion_file` - File containing evergreen expansions, if one was provided.
+///
+/// # Returns
+///
+/// Project-level expansions, empty if no file was provided.
+fn read_project_expansions(expansion_file: Option<&Path>) -> Result<HashMap<String, String>> {
+    let project_expansions = expansion_file
+        .map(EvgExpansions::project_expansions_from_yaml_file)
+        .transpose()
+        .context("Error reading expansions file")?;
+    Ok(project_expansions.unwrap_or_default())
+}
+
+/// Check the configuration files for errors and unknown keys.
+///
+/// # Arguments
+///
+/// * `expansion_file` - File containing evergreen expansions, if one was provided.
+/// * `gen_sub_tasks_config_file` - File containing configuration for generating sub-tasks.
+/// * `evergreen_names_config_file` - File overriding the names used in the project.
+/// * `schema_dir` - Directory to write the JSON Schemas of the configuration files to, if any.
+fn run_check_config(
+    expansion_file: Option<&Path>,
+    gen_sub_tasks_config_file: Option<&Path>,
+    evergreen_names_config_file: Option<&Path>,
+    schema_dir: Option<&Path>,
+) {
+    let result = schema_dir
+        .map(|dir| write_schemas(&expand_path(dir)))
+        .transpose()
+        .and_then(|_| {
+            if let Some(path) = expansion_file {
+                EvgExpansions::from_yaml_file(path)?;
+            }
+            check_config_files(gen_sub_tasks_config_file, evergreen_names_config_file)
+        });
+    match result {
+        Ok(problems) if problems.is_empty() => println!("Configuration is valid."),
+        Ok(problems) => {
+            for problem in &problems {
+                eprintln!("{}", problem);
+            }
+            exit(1);
+        }
+        Err(err) => {
+            eprintln!("Error encountered checking configuration: {:?}", err);
+            exit(1);
+        }
     }
 }
 
+/// Report how much of each resmoke suite on a build variant has historic runtime data.
+///
+/// # Arguments
+///
+/// * `args` - Command line arguments.
+/// * `gen_sub_tasks_config_file` - File containing configuration for generating sub-tasks.
+/// * `evergreen_names_config_file` - File overriding the names used in the project.
+/// * `build_variant` - Build variant to report on.
+/// * `format` - Format to print the report in.
+///
+/// # Returns
+///
+/// The history coverage report formatted for printing.
+async fn run_stats(
+    args: &Args,
+    gen_sub_tasks_config_file: Option<&Path>,
+    evergreen_names_config_file: Option<&Path>,
+    build_variant: &str,
+    format: ListFormat,
+) -> Result<String> {
+    let evg_project_file =
+        resolve_evg_project_file(&args.evg_project_file, &expand_path(&args.evg_auth_file)).await?;
+    let project_expansions = read_project_expansions(args.expansion_file.as_deref())?;
+    let project = project_expansions
+        .get("project")
+        .cloned()
+        .unwrap_or_default();
+    let project_info = ProjectInfo::new(
+        evg_project_file.path(),
+        &project,
+        gen_sub_tasks_config_file,
+        evergreen_names_config_file,
+    );
+
+    let suites = history_coverage(
+        &project_info,
+        &project_expansions,
+        build_variant,
+        &args.resmoke_command,
+        &args.s3_test_stats_endpoint,
+        &args.history_key_template,
+        args.history_fallback_project.as_deref(),
+    )
+    .await?;
+    for suite in suites.iter().filter(|s| s.uses_fallback) {
+        event!(
+            Level::WARN,
+            task = suite.task.as_str(),
+            suite = suite.suite.as_str(),
+            "Suite has no historic runtime data and will use fallback splitting"
+        );
+    }
+    Ok(match format {
+        ListFormat::Json => serde_json::to_string_pretty(&suites)?,
+        ListFormat::Table => format_coverage_table(&suites),
+    })
+}
+
 /// Expand ~ and any environment variables in the given path.
 ///
 /// # Arguments
@@ -190,3 +899,26 @@ fn expand_path(path: &Path) -> PathBuf {
     let expanded = shellexpand::full(path_str).unwrap();
     PathBuf::from(expanded.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(None, Some("task_id"), LogFormat::Json)]
+    #[case(None, None, LogFormat::Pretty)]
+    #[case(Some(LogFormat::Compact), Some("task_id"), LogFormat::Compact)]
+    #[case(Some(LogFormat::Json), None, LogFormat::Json)]
+    fn test_resolve_log_format(
+        #[case] log_format: Option<LogFormat>,
+        #[case] evg_task_id: Option<&str>,
+        #[case] expected: LogFormat,
+    ) {
+        assert_eq!(
+            resolve_log_format(log_format, evg_task_id.map(OsString::from)),
+            expected
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Checksum manifest of the generated configuration files.
+//!
+//! The manifest lists the SHA-256 of every file in the target directory in the format used by
+//! `sha256sum`, so tasks that download the generated configuration can verify it with
+//! `sha256sum -c` before using it.
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+
+/// Name of file to write the manifest to.
+pub const MANIFEST_FILE: &str = "generated_files.sha256";
+
+/// Write a checksum manifest of every file in the given directory.
+///
+/// # Arguments
+///
+/// * `target_directory` - Directory containing the generated configuration.
+///
+/// # Returns
+///
+/// Path to the written manifest.
+pub fn write_manifest(target_directory: &Path) -> Result<PathBuf> {
+    let manifest_file = target_directory.join(MANIFEST_FILE);
+    let mut files = list_files(target_directory, Path::new(""))?;
+    files.retain(|file| file != Path::new(MANIFEST_FILE));
+    files.sort();
+
+    let mut contents = String::new();
+    for file in files {
+        contents.push_str(&format!(
+            "{}  {}\n",
+            file_sha256(&target_directory.join(&file))?,
+            file.display()
+        ));
+    }
+
+    std::fs::write(&manifest_file, contents)?;
+    Ok(manifest_file)
+}
+
+/// Compute the SHA-256 of the contents of the given file.
+///
+/// # Arguments
+///
+/// * `path` - File to hash.
+///
+/// # Returns
+///
+/// Hex encoded SHA-256 of the file contents.
+pub fn file_sha256(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// List the files under the given directory.
+///
+/// # Arguments
+///
+/// * `root` - Directory to list files in.
+/// * `relative_dir` - Sub-directory of the root directory to list files in.
+///
+/// # Returns
+///
+/// Paths of the files relative to the root directory.
+pub(crate) fn list_files(root: &Path, relative_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for entry in std::fs::read_dir(root.join(relative_dir))? {
+        let entry = entry?;
+        let relative_path = relative_dir.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            files.extend(list_files(root, &relative_path)?);
+        } else {
+            files.push(relative_path);
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_write_manifest_should_list_every_file() {
+        let tmp_dir = TempDir::new("write_manifest").unwrap();
+        let target_directory = tmp_dir.path();
+        std::fs::create_dir_all(target_directory.join("nested")).unwrap();
+        std::fs::write(target_directory.join("evergreen_config.json"), "{}").unwrap();
+        std::fs::write(target_directory.join("nested").join("suite.yml"), "").unwrap();
+
+        write_manifest(target_directory).unwrap();
+        // Writing the manifest again should not include the previous manifest.
+        let manifest_file = write_manifest(target_directory).unwrap();
+        let contents = std::fs::read_to_string(&manifest_file).unwrap();
+
+        assert_eq!(
+            contents,
+            "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a  evergreen_config.json\n\
+             e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  nested/suite.yml\n"
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! Ownership of tests by teams.
+//!
+//! Tests are mapped to the teams owning them with a YAML file listing glob patterns of test
+//! paths for each team. As sub-tasks are generated, the tests they run are attributed to their
+//! owners, so the load each team puts on generated tasks can be reported.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+    sync::Mutex,
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::generate_sub_tasks_config::glob_matches;
+
+/// Team tests without a matching owner are attributed to.
+pub const UNOWNED_TEAM: &str = "unowned";
+
+/// Teams owning tests in the project.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct TestOwnershipConfig {
+    /// Teams and the tests they own, checked in order.
+    pub owners: Vec<TeamOwnership>,
+}
+
+/// Tests owned by a team.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct TeamOwnership {
+    /// Name of the team.
+    pub team: String,
+    /// Test paths or glob patterns owned by the team.
+    pub paths: Vec<String>,
+}
+
+impl TestOwnershipConfig {
+    /// Read the test ownership configuration from the given yaml file.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` - Path to yaml file containing the test ownership configuration.
+    ///
+    /// # Returns
+    ///
+    /// Test ownership configuration read from the file.
+    pub fn from_yaml_file<P: AsRef<Path>>(location: P) -> Result<Self> {
+        let contents = std::fs::read_to_string(&location)?;
+
+        let config: Result<Self, serde_yaml::Error> = serde_yaml::from_str(&contents);
+        if config.is_err() {
+            error!(
+                file = location.as_ref().display().to_string(),
+                contents = &contents,
+                "Failed to parse yaml for TestOwnershipConfig from file",
+            );
+        }
+        Ok(config?)
+    }
+
+    /// Find the team owning the given test.
+    ///
+    /// # Arguments
+    ///
+    /// * `test` - Path of test to query.
+    ///
+    /// # Returns
+    ///
+    /// Name of the first team with a path matching the test, or `unowned`.
+    pub fn owner_of(&self, test: &str) -> &str {
+        self.owners
+            .iter()
+            .find(|owner| owner.paths.iter().any(|path| glob_matches(path, test)))
+            .map(|owner| owner.team.as_str())
+            .unwrap_or(UNOWNED_TEAM)
+    }
+}
+
+/// Load a team puts on generated tasks.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TeamLoad {
+    /// Number of tests run by generated sub-tasks.
+    pub tests: usize,
+    /// Generated tasks running tests owned by the team.
+    pub display_tasks: BTreeSet<String>,
+    /// Generated sub-tasks running tests owned by the team.
+    pub sub_tasks: BTreeSet<String>,
+}
+
+/// A service for attributing generated sub-tasks to the teams owning their tests.
+pub trait OwnershipService: Sync + Send {
+    /// Attribute the tests of a generated sub-task to their owners.
+    ///
+    /// # Arguments
+    ///
+    /// * `display_task` - Name of the generated task the sub-task belongs to.
+    /// * `sub_task` - Name of the generated sub-task.
+    /// * `test_list` - Tests run by the sub-task.
+    fn record_sub_task(&self, display_task: &str, sub_task: &str, test_list: &[String]);
+
+    /// Get the load each team puts on the generated tasks recorded so far.
+    ///
+    /// # Returns
+    ///
+    /// Map of team names to their load, empty if test ownership is not configured.
+    fn ownership_report(&self) -> BTreeMap<String, TeamLoad>;
+}
+
+/// Implementation of the service for attributing generated sub-tasks to teams.
+pub struct OwnershipServiceImpl {
+    /// Configuration of test ownership, if test ownership is tracked.
+    config: Option<TestOwnershipConfig>,
+    /// Load of each team recorded so far.
+    team_loads: Mutex<BTreeMap<String, TeamLoad>>,
+}
+
+impl OwnershipServiceImpl {
+    /// Create a new instance of the ownership service.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Configuration of test ownership, nothing is recorded if not provided.
+    ///
+    /// # Returns
+    ///
+    /// New instance of the ownership service.
+    pub fn new(config: Option<TestOwnershipConfig>) -> Self {
+        Self {
+            config,
+            team_loads: Mutex::new(BTreeMap::new()),
+        }
+    }
+}
+
+impl OwnershipService for OwnershipServiceImpl {
+    /// Attribute the tests of a generated sub-task to their owners.
+    ///
+    /// # Arguments
+    ///
+    /// * `display_task` - Name of the generated task the sub-task belongs to.
+    /// * `sub_task` - Name of the generated sub-task.
+    /// * `test_list` - Tests run by the sub-task.
+    fn record_sub_task(&self, display_task: &str, sub_task: &str, test_list: &[String]) {
+        let config = match &self.config {
+            Some(config) => config,
+            None => return,
+        };
+
+        let mut team_loads = self.team_loads.lock().unwrap();
+        for test in test_list {
+            let team_load = team_loads
+                .entry(config.owner_of(test).to_string())
+                .or_default();
+            team_load.tests += 1;
+            team_load.display_tasks.insert(display_task.to_string());
+            team_load.sub_tasks.insert(sub_task.to_string());
+        }
+    }
+
+    /// Get the load each team puts on the generated tasks recorded so far.
+    ///
+    /// # Returns
+    ///
+    /// Map of team names to their load, empty if test ownership is not configured.
+    fn ownership_report(&self) -> BTreeMap<String, TeamLoad> {
+        self.team_loads.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn build_config() -> TestOwnershipConfig {
+        serde_yaml::from_str(
+            r#"
+            owners:
+              - team: query
+                paths:
+                  - jstests/core/query/*
+                  - jstests/aggregation/*
+              - team: storage
+                paths:
+                  - jstests/core/*
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[rstest]
+    #[case("jstests/core/query/find.js", "query")]
+    #[case("jstests/aggregation/sources/match.js", "query")]
+    #[case("jstests/core/insert.js", "storage")]
+    #[case("jstests/auth/login.js", UNOWNED_TEAM)]
+    fn test_owner_of_should_use_first_matching_team(
+        #[case] test: &str,
+        #[case] expected_team: &str,
+    ) {
+        let config = build_config();
+
+        assert_eq!(config.owner_of(test), expected_team);
+    }
+
+    #[test]
+    fn test_record_sub_task_should_attribute_tests_to_owners() {
+        let ownership_service = OwnershipServiceImpl::new(Some(build_config()));
+
+        ownership_service.record_sub_task(
+            "jsCore",
+            "jsCore_0",
+            &[
+                "jstests/core/query/find.js".to_string(),
+                "jstests/core/insert.js".to_string(),
+            ],
+        );
+        ownership_service.record_sub_task(
+            "jsCore",
+            "jsCore_1",
+            &["jstests/core/query/sort.js".to_string()],
+        );
+
+        let report = ownership_service.ownership_report();
+        assert_eq!(report.len(), 2);
+        assert_eq!(report["query"].tests, 2);
+        assert_eq!(
+            report["query"].sub_tasks,
+            BTreeSet::from(["jsCore_0".to_string(), "jsCore_1".to_string()])
+        );
+        assert_eq!(report["storage"].tests, 1);
+        assert_eq!(
+            report["storage"].display_tasks,
+            BTreeSet::from(["jsCore".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_record_sub_task_should_do_nothing_without_config() {
+        let ownership_service = OwnershipServiceImpl::new(None);
+
+        ownership_service.record_sub_task(
+            "jsCore",
+            "jsCore_0",
+            &["jstests/core/insert.js".to_string()],
+        );
+
+        assert!(ownership_service.ownership_report().is_empty());
+    }
+}
@@ -0,0 +1,89 @@
+use anyhow::Result;
+
+use crate::resmoke::external_cmd::run_command;
+
+/// Interface for discovering bazel test targets.
+pub trait BazelTargetDiscovery: Send + Sync {
+    /// Find the test targets selected by the given query.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Bazel query selecting test targets.
+    ///
+    /// # Returns
+    ///
+    /// A list of bazel test targets.
+    fn query_targets(&self, query: &str) -> Result<Vec<String>>;
+}
+
+/// Implementation of bazel target discovery that runs `bazel query`.
+pub struct BazelProxy {
+    /// Bazel binary to invoke.
+    bazel_binary: String,
+}
+
+impl BazelProxy {
+    /// Create a new `BazelProxy` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `bazel_binary` - Bazel binary to invoke.
+    ///
+    /// # Returns
+    ///
+    /// A new `BazelProxy` instance.
+    pub fn new(bazel_binary: &str) -> Self {
+        Self {
+            bazel_binary: bazel_binary.to_string(),
+        }
+    }
+}
+
+impl BazelTargetDiscovery for BazelProxy {
+    /// Find the test targets selected by the given query.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Bazel query selecting test targets.
+    ///
+    /// # Returns
+    ///
+    /// A list of bazel test targets.
+    fn query_targets(&self, query: &str) -> Result<Vec<String>> {
+        let output = run_command(&[&self.bazel_binary, "query", query])?;
+        Ok(parse_query_output(&output))
+    }
+}
+
+/// Parse the targets out of the output of `bazel query`.
+///
+/// # Arguments
+///
+/// * `output` - Output of `bazel query`.
+///
+/// # Returns
+///
+/// A list of bazel targets.
+fn parse_query_output(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_query_output_should_skip_blank_lines() {
+        let output = "//src/mongo/db:db_test\n\n  //src/mongo/s:s_test  \n";
+
+        assert_eq!(
+            parse_query_output(output),
+            vec!["//src/mongo/db:db_test", "//src/mongo/s:s_test"]
+        );
+    }
+}
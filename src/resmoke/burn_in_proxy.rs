@@ -1,13 +1,13 @@
-use std::{path::Path, time::Instant};
+use std::{collections::BTreeMap, path::Path, sync::Mutex, time::Instant};
 
 use anyhow::Result;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use tracing::{error, event, Level};
 
 use crate::resmoke::external_cmd::run_command;
 
 /// Task that burn_in discovered should be run.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DiscoveredSuite {
     /// Name of suite to run.
     pub suite_name: String,
@@ -16,12 +16,15 @@ pub struct DiscoveredSuite {
 }
 
 /// Task that burn_in discovered should be run.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DiscoveredTask {
     /// Name of task to run.
     pub task_name: String,
     /// List of suites to run as part of task.
     pub suites: Vec<DiscoveredSuite>,
+    /// Bazel test targets mapped from changed C++ files that the task runs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bazel_targets: Vec<String>,
 }
 
 /// List of tasks that should be run as part of burn_in.
@@ -31,6 +34,64 @@ pub struct DiscoveredTaskList {
     pub discovered_tasks: Vec<DiscoveredTask>,
 }
 
+/// Task and suite a changed test was discovered to run in.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct DiscoveryReason {
+    /// Name of task the test will run in.
+    pub task_name: String,
+    /// Name of suite the test will run in.
+    pub suite_name: String,
+}
+
+/// Record of what burn_in discovered for a build variant.
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildVariantDiscovery {
+    /// Tasks that burn_in discovered should be run.
+    pub discovered_tasks: Vec<DiscoveredTask>,
+    /// Changed tests mapped to the tasks and suites they were discovered to run in.
+    pub changed_tests: BTreeMap<String, Vec<DiscoveryReason>>,
+    /// Changed bazel test targets mapped to the tasks they were discovered to run in.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub changed_bazel_targets: BTreeMap<String, Vec<String>>,
+}
+
+impl BuildVariantDiscovery {
+    /// Create a record of the tasks burn_in discovered for a build variant.
+    ///
+    /// # Arguments
+    ///
+    /// * `discovered_tasks` - Tasks that burn_in discovered should be run.
+    pub fn new(discovered_tasks: Vec<DiscoveredTask>) -> Self {
+        let mut changed_tests: BTreeMap<String, Vec<DiscoveryReason>> = BTreeMap::new();
+        let mut changed_bazel_targets: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for task in &discovered_tasks {
+            for target in &task.bazel_targets {
+                changed_bazel_targets
+                    .entry(target.clone())
+                    .or_default()
+                    .push(task.task_name.clone());
+            }
+            for suite in &task.suites {
+                for test in &suite.test_list {
+                    changed_tests
+                        .entry(test.clone())
+                        .or_default()
+                        .push(DiscoveryReason {
+                            task_name: task.task_name.clone(),
+                            suite_name: suite.suite_name.clone(),
+                        });
+                }
+            }
+        }
+
+        Self {
+            discovered_tasks,
+            changed_tests,
+            changed_bazel_targets,
+        }
+    }
+}
+
 /// Interface to query information from burn_in_tests.
 pub trait BurnInDiscovery: Send + Sync {
     /// Discover what tasks/tests should be run as part of burn_in.
@@ -43,6 +104,13 @@ pub trait BurnInDiscovery: Send + Sync {
     ///
     /// A list of tasks/tests that were discovered by burn_in_tests.
     fn discover_tasks(&self, build_variant: &str) -> Result<Vec<DiscoveredTask>>;
+
+    /// Get a record of everything discovered so far.
+    ///
+    /// # Returns
+    ///
+    /// Map of build variant names to what was discovered for them.
+    fn discovery_report(&self) -> BTreeMap<String, BuildVariantDiscovery>;
 }
 
 pub struct BurnInProxy {
@@ -52,6 +120,10 @@ pub struct BurnInProxy {
     burn_in_tests_script: Vec<String>,
     /// File containing evergreen project configuration.
     evg_project_location: String,
+    /// Revision to compare changes against instead of the patch base.
+    base_revision: Option<String>,
+    /// Record of what has been discovered for each build variant.
+    discoveries: Mutex<BTreeMap<String, BuildVariantDiscovery>>,
 }
 
 impl BurnInProxy {
@@ -61,7 +133,12 @@ impl BurnInProxy {
     ///
     /// * `burn_in_tests_cmd` - Command to invoke resmoke.
     /// * `evg_project_location` - File containing evergreen project configuration.
-    pub fn new(burn_in_tests_cmd: &str, evg_project_location: &Path) -> Self {
+    /// * `base_revision` - Revision to compare changes against instead of the patch base.
+    pub fn new(
+        burn_in_tests_cmd: &str,
+        evg_project_location: &Path,
+        base_revision: Option<&str>,
+    ) -> Self {
         let cmd_parts: Vec<_> = burn_in_tests_cmd.split(' ').collect();
         let cmd = cmd_parts[0];
         let script = cmd_parts[1..].iter().map(|s| s.to_string()).collect();
@@ -69,12 +146,14 @@ impl BurnInProxy {
             burn_in_tests_cmd: cmd.to_string(),
             burn_in_tests_script: script,
             evg_project_location: String::from(evg_project_location.to_str().unwrap()),
+            base_revision: base_revision.map(|revision| revision.to_string()),
+            discoveries: Mutex::new(BTreeMap::new()),
         }
     }
 }
 
-impl BurnInDiscovery for BurnInProxy {
-    /// Discover what tasks/tests should be run as part of burn_in.
+impl BurnInProxy {
+    /// Build the command to discover the tasks burn_in should run on the given build variant.
     ///
     /// # Arguments
     ///
@@ -82,23 +161,36 @@ impl BurnInDiscovery for BurnInProxy {
     ///
     /// # Returns
     ///
-    /// A list of tasks/tests that were discovered by burn_in_tests.
-    fn discover_tasks(&self, build_variant: &str) -> Result<Vec<DiscoveredTask>> {
+    /// Command and arguments to run.
+    fn build_discovery_command<'a>(&'a self, build_variant: &'a str) -> Vec<&'a str> {
         let mut cmd = vec![self.burn_in_tests_cmd.as_str()];
-        cmd.append(
-            &mut self
-                .burn_in_tests_script
-                .iter()
-                .map(|s| s.as_str())
-                .collect(),
-        );
-        cmd.append(&mut vec![
+        cmd.extend(self.burn_in_tests_script.iter().map(|s| s.as_str()));
+        cmd.extend([
             "--build-variant",
             build_variant,
             "--yaml",
             "--evg-project-file",
             self.evg_project_location.as_str(),
         ]);
+        if let Some(base_revision) = &self.base_revision {
+            cmd.extend(["--origin-rev", base_revision.as_str()]);
+        }
+        cmd
+    }
+}
+
+impl BurnInDiscovery for BurnInProxy {
+    /// Discover what tasks/tests should be run as part of burn_in.
+    ///
+    /// # Arguments
+    ///
+    /// * `build_variant` - Build variant to query information about.
+    ///
+    /// # Returns
+    ///
+    /// A list of tasks/tests that were discovered by burn_in_tests.
+    fn discover_tasks(&self, build_variant: &str) -> Result<Vec<DiscoveredTask>> {
+        let cmd = self.build_discovery_command(build_variant);
         let start = Instant::now();
 
         let cmd_output = run_command(&cmd)?;
@@ -119,6 +211,133 @@ impl BurnInDiscovery for BurnInProxy {
             );
         }
 
-        Ok(output?.discovered_tasks)
+        let discovered_tasks = output?.discovered_tasks;
+        self.discoveries.lock().unwrap().insert(
+            build_variant.to_string(),
+            BuildVariantDiscovery::new(discovered_tasks.clone()),
+        );
+
+        Ok(discovered_tasks)
+    }
+
+    /// Get a record of everything discovered so far.
+    ///
+    /// # Returns
+    ///
+    /// Map of build variant names to what was discovered for them.
+    fn discovery_report(&self) -> BTreeMap<String, BuildVariantDiscovery> {
+        self.discoveries.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_variant_discovery_should_map_tests_to_tasks_and_suites() {
+        let discovered_tasks = vec![
+            DiscoveredTask {
+                task_name: "jsCore".to_string(),
+                suites: vec![DiscoveredSuite {
+                    suite_name: "core".to_string(),
+                    test_list: vec!["test_0.js".to_string(), "test_1.js".to_string()],
+                }],
+                bazel_targets: vec![],
+            },
+            DiscoveredTask {
+                task_name: "jsCore_txns".to_string(),
+                suites: vec![DiscoveredSuite {
+                    suite_name: "core_txns".to_string(),
+                    test_list: vec!["test_0.js".to_string()],
+                }],
+                bazel_targets: vec![],
+            },
+        ];
+
+        let discovery = BuildVariantDiscovery::new(discovered_tasks);
+
+        assert_eq!(discovery.changed_tests.len(), 2);
+        assert!(discovery.changed_bazel_targets.is_empty());
+        assert_eq!(
+            discovery.changed_tests["test_0.js"],
+            vec![
+                DiscoveryReason {
+                    task_name: "jsCore".to_string(),
+                    suite_name: "core".to_string(),
+                },
+                DiscoveryReason {
+                    task_name: "jsCore_txns".to_string(),
+                    suite_name: "core_txns".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            discovery.changed_tests["test_1.js"],
+            vec![DiscoveryReason {
+                task_name: "jsCore".to_string(),
+                suite_name: "core".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_build_discovery_command_should_pass_base_revision() {
+        let proxy = BurnInProxy::new(
+            "python burn_in_tests.py run",
+            Path::new("etc/evergreen.yml"),
+            Some("abc123"),
+        );
+
+        assert_eq!(
+            proxy.build_discovery_command("bv_0"),
+            vec![
+                "python",
+                "burn_in_tests.py",
+                "run",
+                "--build-variant",
+                "bv_0",
+                "--yaml",
+                "--evg-project-file",
+                "etc/evergreen.yml",
+                "--origin-rev",
+                "abc123",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_discovery_command_should_use_patch_base_by_default() {
+        let proxy = BurnInProxy::new("burn_in_tests", Path::new("evergreen.yml"), None);
+
+        assert!(!proxy
+            .build_discovery_command("bv_0")
+            .contains(&"--origin-rev"));
+    }
+
+    #[test]
+    fn test_build_variant_discovery_should_map_bazel_targets_to_tasks() {
+        let discovered_tasks = vec![DiscoveredTask {
+            task_name: "unittests".to_string(),
+            suites: vec![],
+            bazel_targets: vec!["//src/mongo/db:db_test".to_string()],
+        }];
+
+        let discovery = BuildVariantDiscovery::new(discovered_tasks);
+
+        assert!(discovery.changed_tests.is_empty());
+        assert_eq!(
+            discovery.changed_bazel_targets["//src/mongo/db:db_test"],
+            vec!["unittests".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_discovered_task_list_should_default_bazel_targets() {
+        let output = "discovered_tasks:\n- task_name: jsCore\n  suites: []\n";
+
+        let task_list: DiscoveredTaskList = serde_yaml::from_str(output).unwrap();
+
+        assert!(task_list.discovered_tasks[0].bazel_targets.is_empty());
     }
 }
@@ -1,10 +1,70 @@
-use std::process::{Command, Stdio};
+use std::{
+    io::Read,
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
 
 use anyhow::{bail, Result};
 use tracing::{event, Level};
 
+/// How often to check whether a running command should be killed.
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+tokio::task_local! {
+    /// Cancellation of the commands run by the generation worker on the current task.
+    pub static COMMAND_CANCELLATION: Arc<CommandCancellation>;
+}
+
+/// Cancellation of the external commands run by a generation worker.
+///
+/// Commands are run synchronously, so aborting the worker's tokio task does not stop them. Instead
+/// `run_command` kills its command once the worker is cancelled or its deadline has passed.
+#[derive(Debug)]
+pub struct CommandCancellation {
+    /// When commands of the worker should be killed.
+    deadline: Instant,
+    /// Whether the worker has been aborted.
+    cancelled: AtomicBool,
+}
+
+impl CommandCancellation {
+    /// Create a new cancellation.
+    ///
+    /// # Arguments
+    ///
+    /// * `deadline` - When commands of the worker should be killed.
+    ///
+    /// # Returns
+    ///
+    /// New instance of the cancellation.
+    pub fn new(deadline: Instant) -> Self {
+        Self {
+            deadline,
+            cancelled: AtomicBool::new(false),
+        }
+    }
+
+    /// Kill any command the worker is running.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether commands of the worker should be killed.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst) || Instant::now() >= self.deadline
+    }
+}
+
 /// Run an external command and return the output.
 ///
+/// When called from a generation worker with a deadline, the command is killed once the worker
+/// is cancelled or the deadline passes.
+///
 /// # Arguments
 ///
 /// * `command` - Command with arguments to run.
@@ -15,16 +75,20 @@ use tracing::{event, Level};
 pub fn run_command(command: &[&str]) -> Result<String> {
     let binary = command[0];
     let args = &command[1..];
-    let cmd = Command::new(binary)
+    let mut child = Command::new(binary)
         .args(args)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
-        .spawn()?
-        .wait_with_output()?;
+        .spawn()?;
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+    let status = wait_for_command(&mut child, binary)?;
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
 
-    if !cmd.status.success() {
-        let error_message = String::from_utf8_lossy(&cmd.stderr).to_string();
-        let regular_info = String::from_utf8_lossy(&cmd.stdout).to_string();
+    if !status.success() {
+        let error_message = String::from_utf8_lossy(&stderr).to_string();
+        let regular_info = String::from_utf8_lossy(&stdout).to_string();
 
         event!(
             Level::ERROR,
@@ -37,6 +101,64 @@ pub fn run_command(command: &[&str]) -> Result<String> {
         bail!(error_message)
     }
 
-    let output = String::from_utf8_lossy(&cmd.stdout);
+    let output = String::from_utf8_lossy(&stdout);
     Ok(output.to_string())
 }
+
+/// Read the given output of a command on its own thread, so the command never blocks on a full
+/// pipe while we wait for it.
+///
+/// # Arguments
+///
+/// * `pipe` - Output of the command to read.
+///
+/// # Returns
+///
+/// Handle to the thread returning the contents of the output.
+fn read_in_background<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut output = vec![];
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut output);
+        }
+        output
+    })
+}
+
+/// Wait for the given command to exit, killing it if the generation worker running it is
+/// cancelled.
+///
+/// # Arguments
+///
+/// * `child` - Running command.
+/// * `binary` - Binary the command is running.
+///
+/// # Returns
+///
+/// Exit status of the command.
+fn wait_for_command(child: &mut Child, binary: &str) -> Result<ExitStatus> {
+    let cancellation = match COMMAND_CANCELLATION.try_with(|c| c.clone()) {
+        Ok(cancellation) => cancellation,
+        Err(_) => return Ok(child.wait()?),
+    };
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if cancellation.is_cancelled() {
+            let _ = child.kill();
+            child.wait()?;
+            event!(
+                Level::ERROR,
+                binary = binary,
+                "Killed command of an aborted generation worker"
+            );
+            bail!(
+                "'{}' was killed because its generation worker was aborted",
+                binary
+            )
+        }
+        thread::sleep(COMMAND_POLL_INTERVAL);
+    }
+}
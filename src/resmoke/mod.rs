@@ -1,5 +1,6 @@
+pub mod bazel_proxy;
 pub mod burn_in_proxy;
+pub mod external_cmd;
+pub mod native_discovery;
 pub mod resmoke_proxy;
 pub mod resmoke_suite;
-
-mod external_cmd;
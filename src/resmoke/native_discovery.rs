@@ -0,0 +1,327 @@
+//! Discover the tests of simple resmoke suites without invoking resmoke.
+//!
+//! Most suites select their tests with `roots` globs and `exclude_files`. The tests of those
+//! suites can be found by expanding the globs, which is much faster than starting resmoke.
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use regex::Regex;
+
+use super::resmoke_suite::{ResmokeSuiteConfig, TestRoot};
+
+/// Directory resmoke suite configurations are stored in.
+const RESMOKE_SUITE_DIR: &str = "buildscripts/resmokeconfig/suites";
+
+/// How to discover the tests of suites that can be discovered without resmoke.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NativeDiscoveryMode {
+    /// Always use resmoke to discover tests.
+    #[default]
+    Off,
+    /// Expand the globs of simple suites instead of invoking resmoke.
+    On,
+    /// Discover tests both ways, warn about any differences and use the tests found by resmoke.
+    Verify,
+}
+
+/// Read the configuration of the given suite from its suite file.
+///
+/// # Arguments
+///
+/// * `root` - Directory of the repository containing the suites.
+/// * `suite_name` - Name of suite to read.
+///
+/// # Returns
+///
+/// Configuration of the suite, or None if the suite has no suite file, for example because it is
+/// a matrix suite.
+pub fn read_suite_config(root: &Path, suite_name: &str) -> Option<ResmokeSuiteConfig> {
+    let path = root
+        .join(RESMOKE_SUITE_DIR)
+        .join(format!("{}.yml", suite_name));
+    let contents = std::fs::read_to_string(path).ok()?;
+    ResmokeSuiteConfig::from_str(&contents).ok()
+}
+
+/// Discover the tests of the given suite by expanding its globs.
+///
+/// Only `js_test` suites selecting tests with `roots` and `exclude_files` are supported. Suites
+/// selecting tests by tags or other criteria need resmoke.
+///
+/// # Arguments
+///
+/// * `root` - Directory the globs of the suite are relative to.
+/// * `suite_config` - Configuration of the suite.
+///
+/// # Returns
+///
+/// Tests of the suite, or None if the suite cannot be discovered natively.
+pub fn discover_tests_natively(
+    root: &Path,
+    suite_config: &ResmokeSuiteConfig,
+) -> Option<Vec<String>> {
+    if !is_simple_suite(suite_config) {
+        return None;
+    }
+
+    let roots = match &suite_config.selector.test_root {
+        Some(TestRoot::Roots { roots }) => roots,
+        _ => return None,
+    };
+    let excluded: HashSet<String> = suite_config
+        .selector
+        .exclude_files
+        .iter()
+        .flatten()
+        .flat_map(|pattern| expand_glob(root, pattern))
+        .collect();
+
+    let mut seen = HashSet::new();
+    Some(
+        roots
+            .iter()
+            .flat_map(|pattern| expand_glob(root, pattern))
+            .filter(|test| !excluded.contains(test) && seen.insert(test.clone()))
+            .collect(),
+    )
+}
+
+/// Check if the tests of the given suite only depend on its globs.
+fn is_simple_suite(suite_config: &ResmokeSuiteConfig) -> bool {
+    let selector = &suite_config.selector;
+    suite_config.test_kind == "js_test"
+        && !suite_config.is_matrix_suite()
+        && matches!(selector.test_root, Some(TestRoot::Roots { .. }))
+        && selector.include_files.is_none()
+        && selector.include_tags.is_none()
+        && selector.exclude_tags.is_none()
+        && selector.include_with_any_tags.is_none()
+        && selector.exclude_with_any_tags.is_none()
+        && selector.tag_file.is_none()
+        && selector.group_size.is_none()
+        && selector.test.is_none()
+}
+
+/// Expand the given glob into the files it matches.
+///
+/// `*`, `?` and `[...]` match within a path component and `**` matches any number of
+/// directories.
+///
+/// # Arguments
+///
+/// * `root` - Directory the glob is relative to.
+/// * `pattern` - Glob to expand.
+///
+/// # Returns
+///
+/// Paths of the matching files relative to `root`, sorted within each directory.
+pub fn expand_glob(root: &Path, pattern: &str) -> Vec<String> {
+    let components: Vec<&str> = pattern.split('/').filter(|c| !c.is_empty()).collect();
+    let mut files = vec![];
+    expand_components(root, PathBuf::new(), &components, &mut files);
+    files
+}
+
+/// Expand the remaining glob components under the given directory.
+///
+/// # Arguments
+///
+/// * `root` - Directory the glob is relative to.
+/// * `relative` - Path matched so far, relative to `root`.
+/// * `components` - Glob components left to match.
+/// * `files` - Matching files are added to this list.
+fn expand_components(root: &Path, relative: PathBuf, components: &[&str], files: &mut Vec<String>) {
+    let full_path = root.join(&relative);
+    let (component, rest) = match components.split_first() {
+        Some(split) => split,
+        None => {
+            if full_path.is_file() {
+                files.push(relative.to_string_lossy().to_string());
+            }
+            return;
+        }
+    };
+
+    if *component == "**" {
+        expand_components(root, relative.clone(), rest, files);
+        for entry in sorted_entries(&full_path) {
+            if full_path.join(&entry).is_dir() {
+                expand_components(root, relative.join(&entry), components, files);
+            }
+        }
+    } else if component.contains(|c| matches!(c, '*' | '?' | '[')) {
+        let component_re = glob_component_to_regex(component);
+        for entry in sorted_entries(&full_path) {
+            if component_re.is_match(&entry) {
+                expand_components(root, relative.join(&entry), rest, files);
+            }
+        }
+    } else {
+        expand_components(root, relative.join(component), rest, files);
+    }
+}
+
+/// List the names of the entries of the given directory in sorted order.
+fn sorted_entries(dir: &Path) -> Vec<String> {
+    let mut entries: Vec<String> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    entries.sort();
+    entries
+}
+
+/// Convert a glob path component into a regular expression matching it.
+fn glob_component_to_regex(component: &str) -> Regex {
+    let mut re = String::from("^");
+    let mut in_class = false;
+    for c in component.chars() {
+        match c {
+            '*' if !in_class => re.push_str(".*"),
+            '?' if !in_class => re.push('.'),
+            '[' if !in_class => {
+                in_class = true;
+                re.push('[');
+            }
+            ']' if in_class => {
+                in_class = false;
+                re.push(']');
+            }
+            '!' if in_class && re.ends_with('[') => re.push('^'),
+            _ => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    Regex::new(&re)
+        .unwrap_or_else(|_| Regex::new(&format!("^{}$", regex::escape(component))).unwrap())
+}
+
+/// Describe the differences between tests discovered natively and by resmoke.
+///
+/// # Arguments
+///
+/// * `native_tests` - Tests discovered natively.
+/// * `resmoke_tests` - Tests discovered by resmoke.
+///
+/// # Returns
+///
+/// Tests only discovered by resmoke and tests only discovered natively.
+pub fn compare_discovered_tests(
+    native_tests: &[String],
+    resmoke_tests: &[String],
+) -> (Vec<String>, Vec<String>) {
+    let native: HashSet<&String> = native_tests.iter().collect();
+    let resmoke: HashSet<&String> = resmoke_tests.iter().collect();
+    let missing = resmoke_tests
+        .iter()
+        .filter(|t| !native.contains(t))
+        .cloned()
+        .collect();
+    let extra = native_tests
+        .iter()
+        .filter(|t| !resmoke.contains(t))
+        .cloned()
+        .collect();
+    (missing, extra)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use tempdir::TempDir;
+
+    use super::*;
+
+    fn build_test_tree() -> TempDir {
+        let tmp_dir = TempDir::new("native_discovery").unwrap();
+        for file in [
+            "jstests/core/a.js",
+            "jstests/core/b.js",
+            "jstests/core/notes.txt",
+            "jstests/core/txns/c.js",
+            "jstests/core/txns/deep/d.js",
+            "jstests/auth/e.js",
+        ] {
+            let path = tmp_dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        tmp_dir
+    }
+
+    fn build_suite_config(selector: &str) -> ResmokeSuiteConfig {
+        ResmokeSuiteConfig::from_str(&format!(
+            "test_kind: js_test\nselector:\n{}\nexecutor:\n  config: {{}}\n",
+            selector
+        ))
+        .unwrap()
+    }
+
+    // expand_glob tests.
+    #[rstest]
+    #[case("jstests/core/*.js", vec!["jstests/core/a.js", "jstests/core/b.js"])]
+    #[case("jstests/core/a.js", vec!["jstests/core/a.js"])]
+    #[case("jstests/core/missing.js", vec![])]
+    #[case("jstests/*/e.js", vec!["jstests/auth/e.js"])]
+    #[case("jstests/core/[!a].js", vec!["jstests/core/b.js"])]
+    #[case("jstests/core/**/*.js", vec![
+        "jstests/core/a.js",
+        "jstests/core/b.js",
+        "jstests/core/txns/c.js",
+        "jstests/core/txns/deep/d.js",
+    ])]
+    fn test_expand_glob(#[case] pattern: &str, #[case] expected: Vec<&str>) {
+        let tmp_dir = build_test_tree();
+
+        assert_eq!(expand_glob(tmp_dir.path(), pattern), expected);
+    }
+
+    // discover_tests_natively tests.
+    #[test]
+    fn test_discover_tests_natively_should_remove_excluded_files() {
+        let tmp_dir = build_test_tree();
+        let suite_config = build_suite_config(
+            "  roots:\n    - jstests/core/**/*.js\n    - jstests/core/a.js\n  exclude_files:\n    - jstests/core/txns/**/*.js",
+        );
+
+        let tests = discover_tests_natively(tmp_dir.path(), &suite_config);
+
+        assert_eq!(
+            tests,
+            Some(vec![
+                "jstests/core/a.js".to_string(),
+                "jstests/core/b.js".to_string()
+            ])
+        );
+    }
+
+    #[rstest]
+    #[case("  roots:\n    - jstests/core/*.js\n  exclude_with_any_tags:\n    - slow")]
+    #[case("  roots:\n    - jstests/core/*.js\n  include_files:\n    - jstests/core/a.js")]
+    #[case("  root: test_list.txt")]
+    fn test_discover_tests_natively_should_skip_suites_needing_resmoke(#[case] selector: &str) {
+        let tmp_dir = build_test_tree();
+        let suite_config = build_suite_config(selector);
+
+        assert_eq!(discover_tests_natively(tmp_dir.path(), &suite_config), None);
+    }
+
+    // compare_discovered_tests tests.
+    #[test]
+    fn test_compare_discovered_tests_should_report_differences() {
+        let native_tests = vec!["a.js".to_string(), "b.js".to_string()];
+        let resmoke_tests = vec!["b.js".to_string(), "c.js".to_string()];
+
+        let (missing, extra) = compare_discovered_tests(&native_tests, &resmoke_tests);
+
+        assert_eq!(missing, vec!["c.js".to_string()]);
+        assert_eq!(extra, vec!["a.js".to_string()]);
+    }
+}
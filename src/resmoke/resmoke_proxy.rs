@@ -1,10 +1,24 @@
-use std::{path::Path, str::FromStr, time::Instant};
-
-use anyhow::Result;
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use anyhow::{Context, Result};
 use serde::Deserialize;
-use tracing::{error, event, Level};
+use tracing::{error, event, warn, Level};
+
+use crate::error_report::ErrorCategory;
 
-use super::{external_cmd::run_command, resmoke_suite::ResmokeSuiteConfig};
+use super::{
+    external_cmd::run_command,
+    native_discovery::{
+        compare_discovered_tests, discover_tests_natively, read_suite_config, NativeDiscoveryMode,
+    },
+    resmoke_suite::ResmokeSuiteConfig,
+};
 
 /// Interface for discovering details about test suites.
 pub trait TestDiscovery: Send + Sync {
@@ -19,6 +33,18 @@ pub trait TestDiscovery: Send + Sync {
     /// A list of tests belonging to given suite.
     fn discover_tests(&self, suite_name: &str) -> Result<Vec<String>>;
 
+    /// Get a list of tests that belong to the given suite, including tests covered by more
+    /// complex suites.
+    ///
+    /// # Arguments
+    ///
+    /// * `suite_name` - Name of test suite to query.
+    ///
+    /// # Returns
+    ///
+    /// A list of all tests belonging to given suite.
+    fn discover_all_tests(&self, suite_name: &str) -> Result<Vec<String>>;
+
     /// Get the configuration for the given suite.
     ///
     /// # Arguments
@@ -32,6 +58,25 @@ pub trait TestDiscovery: Send + Sync {
 
     /// Get the multiversion configuration to generate against.
     fn get_multiversion_config(&self) -> Result<MultiversionConfig>;
+
+    /// Generate the multiversion exclude tags file for the given old version.
+    ///
+    /// # Arguments
+    ///
+    /// * `old_version` - Old version tests will be run against.
+    /// * `output_file` - Path to write the exclude tags file to.
+    fn generate_multiversion_exclude_tags(
+        &self,
+        old_version: &str,
+        output_file: &Path,
+    ) -> Result<()>;
+
+    /// Get the tests skipped in each suite because more complex suites run them.
+    ///
+    /// # Returns
+    ///
+    /// Map of suite names to the tests skipped in the suite.
+    fn skipped_covered_tests(&self) -> BTreeMap<String, Vec<String>>;
 }
 
 /// Implementation of `TestDiscovery` that queries details from resmoke.
@@ -43,6 +88,10 @@ pub struct ResmokeProxy {
     resmoke_script: Vec<String>,
     /// True if the generator should skip tests already run in more complex suites.
     skip_covered_tests: bool,
+    /// Map of suite names to the tests skipped in the suite because they are covered.
+    skipped_covered_tests: Arc<Mutex<BTreeMap<String, Vec<String>>>>,
+    /// How to discover the tests of suites that do not need resmoke.
+    native_discovery: NativeDiscoveryMode,
 }
 
 impl ResmokeProxy {
@@ -52,7 +101,12 @@ impl ResmokeProxy {
     ///
     /// * `resmoke_cmd` - Command to invoke resmoke.
     /// * `skip_covered_tests` - Whether the generator should skip tests run in more complex suites.
-    pub fn new(resmoke_cmd: &str, skip_covered_tests: bool) -> Self {
+    /// * `native_discovery` - How to discover the tests of suites that do not need resmoke.
+    pub fn new(
+        resmoke_cmd: &str,
+        skip_covered_tests: bool,
+        native_discovery: NativeDiscoveryMode,
+    ) -> Self {
         let cmd_parts: Vec<_> = resmoke_cmd.split(' ').collect();
         let cmd = cmd_parts[0];
         let script = cmd_parts[1..].iter().map(|s| s.to_string()).collect();
@@ -60,32 +114,79 @@ impl ResmokeProxy {
             resmoke_cmd: cmd.to_string(),
             resmoke_script: script,
             skip_covered_tests,
+            skipped_covered_tests: Arc::new(Mutex::new(BTreeMap::new())),
+            native_discovery,
         }
     }
-}
-
-/// Details about tests comprising a test suite.
-#[derive(Debug, Deserialize)]
-#[allow(dead_code)]
-struct TestDiscoveryOutput {
-    /// Name of suite.
-    pub suite_name: String,
 
-    /// Name of tests comprising suite.
-    pub tests: Vec<String>,
-}
+    /// Discover the tests of the given suite without invoking resmoke if possible.
+    ///
+    /// Resmoke is needed to skip tests covered by more complex suites, so native discovery is
+    /// only used when covered tests are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `suite_name` - Name of test suite to query.
+    /// * `skip_covered_tests` - Exclude tests that are run in more complex suites.
+    ///
+    /// # Returns
+    ///
+    /// A list of tests belonging to given suite.
+    fn discover_suite_tests(
+        &self,
+        suite_name: &str,
+        skip_covered_tests: bool,
+    ) -> Result<Vec<String>> {
+        let native_tests =
+            if self.native_discovery == NativeDiscoveryMode::Off || skip_covered_tests {
+                None
+            } else {
+                let root = Path::new(".");
+                read_suite_config(root, suite_name)
+                    .and_then(|suite_config| discover_tests_natively(root, &suite_config))
+            };
+
+        match (native_tests, self.native_discovery) {
+            (Some(native_tests), NativeDiscoveryMode::On) => {
+                event!(
+                    Level::INFO,
+                    suite_name,
+                    "Discovered tests without invoking resmoke"
+                );
+                Ok(native_tests)
+            }
+            (Some(native_tests), NativeDiscoveryMode::Verify) => {
+                let tests = self.run_test_discovery(suite_name, skip_covered_tests)?;
+                let (missing, extra) = compare_discovered_tests(&native_tests, &tests);
+                if !missing.is_empty() || !extra.is_empty() {
+                    warn!(
+                        suite_name,
+                        missing = missing.join(", "),
+                        extra = extra.join(", "),
+                        "Native test discovery differs from resmoke",
+                    );
+                }
+                Ok(tests)
+            }
+            _ => self.run_test_discovery(suite_name, skip_covered_tests),
+        }
+    }
 
-impl TestDiscovery for ResmokeProxy {
-    /// Get a list of tests that belong to the given suite.
+    /// Run resmoke test discovery for the given suite.
     ///
     /// # Arguments
     ///
     /// * `suite_name` - Name of test suite to query.
+    /// * `skip_covered_tests` - Exclude tests that are run in more complex suites.
     ///
     /// # Returns
     ///
     /// A list of tests belonging to given suite.
-    fn discover_tests(&self, suite_name: &str) -> Result<Vec<String>> {
+    fn run_test_discovery(
+        &self,
+        suite_name: &str,
+        skip_covered_tests: bool,
+    ) -> Result<Vec<String>> {
         let mut cmd = vec![&*self.resmoke_cmd];
         cmd.append(&mut self.resmoke_script.iter().map(|s| s.as_str()).collect());
         cmd.append(&mut vec!["test-discovery", "--suite", suite_name]);
@@ -93,7 +194,7 @@ impl TestDiscovery for ResmokeProxy {
         // When running in a patch build, we use the --skipTestsCoveredByMoreComplexSuites
         // flag to tell Resmoke to exclude any tests in the given suite that will
         // also be run on a more complex suite.
-        if self.skip_covered_tests {
+        if skip_covered_tests {
             cmd.append(&mut vec!["--skipTestsCoveredByMoreComplexSuites"]);
         }
 
@@ -117,11 +218,69 @@ impl TestDiscovery for ResmokeProxy {
             );
         }
 
-        Ok(output?
+        let tests = output?
             .tests
             .into_iter()
             .filter(|f| Path::new(f).exists())
-            .collect())
+            .collect();
+        Ok(dedupe_tests(suite_name, tests, |test| {
+            std::fs::canonicalize(test).unwrap_or_else(|_| PathBuf::from(test))
+        }))
+    }
+}
+
+/// Details about tests comprising a test suite.
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct TestDiscoveryOutput {
+    /// Name of suite.
+    pub suite_name: String,
+
+    /// Name of tests comprising suite.
+    pub tests: Vec<String>,
+}
+
+impl TestDiscovery for ResmokeProxy {
+    /// Get a list of tests that belong to the given suite.
+    ///
+    /// # Arguments
+    ///
+    /// * `suite_name` - Name of test suite to query.
+    ///
+    /// # Returns
+    ///
+    /// A list of tests belonging to given suite.
+    fn discover_tests(&self, suite_name: &str) -> Result<Vec<String>> {
+        let tests = self.discover_suite_tests(suite_name, self.skip_covered_tests)?;
+
+        // Tests skipped because they are covered are recorded so the generation report can
+        // show where they went.
+        if self.skip_covered_tests {
+            let all_tests = self.run_test_discovery(suite_name, false)?;
+            let skipped_tests = find_skipped_tests(&all_tests, &tests);
+            if !skipped_tests.is_empty() {
+                self.skipped_covered_tests
+                    .lock()
+                    .unwrap()
+                    .insert(suite_name.to_string(), skipped_tests);
+            }
+        }
+
+        Ok(tests)
+    }
+
+    /// Get a list of tests that belong to the given suite, including tests covered by more
+    /// complex suites.
+    ///
+    /// # Arguments
+    ///
+    /// * `suite_name` - Name of test suite to query.
+    ///
+    /// # Returns
+    ///
+    /// A list of all tests belonging to given suite.
+    fn discover_all_tests(&self, suite_name: &str) -> Result<Vec<String>> {
+        self.discover_suite_tests(suite_name, false)
     }
 
     /// Get the configuration for the given suite.
@@ -139,12 +298,52 @@ impl TestDiscovery for ResmokeProxy {
         cmd.append(&mut vec!["suiteconfig", "--suite", suite_name]);
         let cmd_output = run_command(&cmd).unwrap();
 
-        Ok(ResmokeSuiteConfig::from_str(&cmd_output)?)
+        // Resmoke resolves matrix suites to their effective configuration, so they can be
+        // split like any other suite.
+        let config = ResmokeSuiteConfig::from_str(&cmd_output)?;
+        if config.is_matrix_suite() {
+            event!(
+                Level::DEBUG,
+                suite_name,
+                "Resolved matrix suite configuration"
+            );
+        }
+        Ok(config)
     }
 
     /// Get the multiversion configuration to generate against.
     fn get_multiversion_config(&self) -> Result<MultiversionConfig> {
         MultiversionConfig::from_resmoke(&self.resmoke_cmd, &self.resmoke_script)
+            .context(ErrorCategory::Resmoke)
+    }
+
+    /// Generate the multiversion exclude tags file for the given old version.
+    ///
+    /// # Arguments
+    ///
+    /// * `old_version` - Old version tests will be run against.
+    /// * `output_file` - Path to write the exclude tags file to.
+    fn generate_multiversion_exclude_tags(
+        &self,
+        old_version: &str,
+        output_file: &Path,
+    ) -> Result<()> {
+        let old_version_arg = format!("--oldBinVersion={}", old_version);
+        let output_file_arg = format!("--excludeTagsFilePath={}", output_file.display());
+        let mut cmd = vec![&*self.resmoke_cmd];
+        cmd.append(&mut self.resmoke_script.iter().map(|s| s.as_str()).collect());
+        cmd.append(&mut vec![
+            "generate-multiversion-exclude-tags",
+            &old_version_arg,
+            &output_file_arg,
+        ]);
+        run_command(&cmd).context(ErrorCategory::Resmoke)?;
+        Ok(())
+    }
+
+    /// Get the tests skipped in each suite because more complex suites run them.
+    fn skipped_covered_tests(&self) -> BTreeMap<String, Vec<String>> {
+        self.skipped_covered_tests.lock().unwrap().clone()
     }
 }
 
@@ -207,10 +406,110 @@ impl MultiversionConfig {
     }
 }
 
+/// Remove duplicate tests from the given list, keeping the first occurrence of each test.
+///
+/// Test discovery can report a test more than once, e.g. when it is reached through a symlink or
+/// matched by multiple selectors of the suite.
+///
+/// # Arguments
+///
+/// * `suite_name` - Name of suite the tests belong to.
+/// * `tests` - Tests reported by test discovery.
+/// * `test_key` - Function to get a key identifying the file of a test.
+///
+/// # Returns
+///
+/// Tests with duplicates removed, in the order they were reported.
+fn dedupe_tests<F>(suite_name: &str, tests: Vec<String>, test_key: F) -> Vec<String>
+where
+    F: Fn(&str) -> PathBuf,
+{
+    let mut seen = HashSet::new();
+    let mut duplicates = vec![];
+    let deduped_tests = tests
+        .into_iter()
+        .filter(|test| {
+            let is_new = seen.insert(test_key(test));
+            if !is_new {
+                duplicates.push(test.clone());
+            }
+            is_new
+        })
+        .collect();
+
+    if !duplicates.is_empty() {
+        warn!(
+            suite_name,
+            duplicates = duplicates.join(", ").as_str(),
+            "Test discovery reported duplicate tests, running each test once",
+        );
+    }
+
+    deduped_tests
+}
+
+/// Find the tests of a suite that were not kept by test discovery.
+///
+/// # Arguments
+///
+/// * `all_tests` - All tests of the suite.
+/// * `kept_tests` - Tests of the suite kept by test discovery.
+///
+/// # Returns
+///
+/// Tests in `all_tests` that are not in `kept_tests`, in the order they were reported.
+fn find_skipped_tests(all_tests: &[String], kept_tests: &[String]) -> Vec<String> {
+    let kept_tests: HashSet<&String> = kept_tests.iter().collect();
+    all_tests
+        .iter()
+        .filter(|test| !kept_tests.contains(test))
+        .cloned()
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    // tests for find_skipped_tests.
+    #[test]
+    fn test_find_skipped_tests_should_return_tests_not_kept() {
+        let all_tests: Vec<String> = vec!["a.js", "b.js", "c.js"]
+            .into_iter()
+            .map(|t| t.to_string())
+            .collect();
+        let kept_tests = vec!["b.js".to_string()];
+
+        let skipped_tests = find_skipped_tests(&all_tests, &kept_tests);
+
+        assert_eq!(skipped_tests, vec!["a.js".to_string(), "c.js".to_string()]);
+    }
+
+    // tests for dedupe_tests.
+    #[test]
+    fn test_dedupe_tests_should_keep_first_occurrence_of_each_test() {
+        let tests = vec![
+            "jstests/core/b.js".to_string(),
+            "jstests/core/a.js".to_string(),
+            "./jstests/core/b.js".to_string(),
+            "jstests/core/c.js".to_string(),
+            "jstests/core/a.js".to_string(),
+        ];
+
+        let deduped_tests = dedupe_tests("core", tests, |test| {
+            PathBuf::from(test.strip_prefix("./").unwrap_or(test))
+        });
+
+        assert_eq!(
+            deduped_tests,
+            vec![
+                "jstests/core/b.js".to_string(),
+                "jstests/core/a.js".to_string(),
+                "jstests/core/c.js".to_string(),
+            ]
+        );
+    }
+
     // tests for get_fcv_tags_for_lts.
     #[test]
     fn test_get_fcv_tags_for_lts_should_use_lts_if_provided() {
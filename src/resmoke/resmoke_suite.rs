@@ -66,6 +66,7 @@ pub struct ResmokeExecutor {
 /// Configuration of a resmoke test suite.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ResmokeSuiteConfig {
+    /// Set when the configuration is the effective configuration of a matrix suite.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub matrix_suite: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -96,8 +97,16 @@ impl ToString for ResmokeSuiteConfig {
 }
 
 impl ResmokeSuiteConfig {
+    /// Check if this is the effective configuration of a matrix suite.
+    pub fn is_matrix_suite(&self) -> bool {
+        self.matrix_suite.unwrap_or(false)
+    }
+
     /// Create a new resmoke suite configuration based on this one but running certain tests.
     ///
+    /// The new configuration is a standalone suite, so it is not marked as a matrix suite even if
+    /// this configuration is. Resmoke only accepts matrix suites from its matrix suite directory.
+    ///
     /// # Arguments
     ///
     /// * `run_tests` - When provided, the new configuration should only run these tests.
@@ -112,6 +121,7 @@ impl ResmokeSuiteConfig {
         exclude_tests: Option<&[String]>,
     ) -> Self {
         let mut config = self.clone();
+        config.matrix_suite = None;
         let mut updated_selector = self.selector.clone();
         if let Some(exclude_tests) = exclude_tests {
             let mut files_to_exclude = vec![];
@@ -135,10 +145,41 @@ impl ResmokeSuiteConfig {
         config.selector = updated_selector;
         config
     }
+
+    /// Estimate the number of processes each resmoke job of this suite starts.
+    ///
+    /// Replica set fixtures start a process per node and sharded cluster fixtures start a process
+    /// per shard node, per mongos and one for the config server. Other fixtures are counted as a
+    /// single process.
+    ///
+    /// # Returns
+    ///
+    /// Number of processes started by each resmoke job.
+    pub fn processes_per_job(&self) -> u64 {
+        let fixture = match &self.executor.fixture {
+            Some(fixture) => fixture,
+            None => return 1,
+        };
+        let lookup_u64 =
+            |key: &str, default: u64| fixture.get(key).and_then(|v| v.as_u64()).unwrap_or(default);
+
+        match fixture.get("class").and_then(|c| c.as_str()) {
+            Some("ReplicaSetFixture") => lookup_u64("num_nodes", 2),
+            Some("ShardedClusterFixture") => {
+                lookup_u64("num_shards", 1) * lookup_u64("num_rs_nodes_per_shard", 1)
+                    + lookup_u64("num_mongos", 1)
+                    + 1
+            }
+            _ => 1,
+        }
+        .max(1)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use rstest::rstest;
+
     use super::*;
 
     // with_new_tests tests
@@ -175,6 +216,57 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_with_new_tests_should_not_mark_matrix_suites() {
+        let config_yaml = "
+            matrix_suite: true
+            test_kind: js_test
+
+            selector:
+              roots:
+                - jstests/auth/*.js
+
+            executor:
+              config:
+                value
+        ";
+        let new_test_list = vec!["test0.js".to_string()];
+
+        let resmoke_suite = ResmokeSuiteConfig::from_str(config_yaml).unwrap();
+        let new_config = resmoke_suite.with_new_tests(Some(&new_test_list), None);
+
+        assert!(resmoke_suite.is_matrix_suite());
+        assert!(!new_config.is_matrix_suite());
+        assert!(!new_config.to_string().contains("matrix_suite"));
+    }
+
+    // processes_per_job tests
+    #[rstest]
+    #[case("config: {}", 1)]
+    #[case("fixture:\n  class: MongoDFixture", 1)]
+    #[case("fixture:\n  class: ReplicaSetFixture", 2)]
+    #[case("fixture:\n  class: ReplicaSetFixture\n  num_nodes: 3", 3)]
+    #[case(
+        "fixture:\n  class: ShardedClusterFixture\n  num_shards: 2\n  num_rs_nodes_per_shard: 3",
+        8
+    )]
+    #[case(
+        "fixture:\n  class: ShardedClusterFixture\n  num_shards: 2\n  num_mongos: 2",
+        5
+    )]
+    fn test_processes_per_job_should_count_fixture_processes(
+        #[case] executor_yaml: &str,
+        #[case] expected_processes: u64,
+    ) {
+        let config_yaml = format!(
+            "test_kind: js_test\nselector:\n  roots:\n    - jstests/core/*.js\nexecutor:\n  {}\n",
+            executor_yaml.replace('\n', "\n  ")
+        );
+        let resmoke_suite = ResmokeSuiteConfig::from_str(&config_yaml).unwrap();
+
+        assert_eq!(resmoke_suite.processes_per_job(), expected_processes);
+    }
+
     #[test]
     fn test_with_new_tests_can_add_tests_to_test_root() {
         let config_yaml = "
@@ -1,25 +1,48 @@
-use std::sync::Arc;
+use std::{
+    cmp::{max, min},
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    ops::AddAssign,
+    sync::{Arc, OnceLock},
+};
 
 use anyhow::{bail, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
 use shrub_rs::models::{task::EvgTask, variant::BuildVariant};
+use tracing::{event, Level};
 
 use crate::{
-    evergreen::evg_config_utils::EvgConfigUtils,
+    evergreen::evg_config_utils::{EvgConfigUtils, MultiversionGenerateTaskConfig},
     evergreen_names::{
-        CONTINUE_ON_FAILURE, FUZZER_PARAMETERS, IDLE_TIMEOUT, LARGE_DISTRO_EXPANSION,
-        LAST_VERSIONS_EXPANSION, MULTIVERSION, NO_MULTIVERSION_GENERATE_TASKS, NPM_COMMAND,
-        NUM_FUZZER_FILES, NUM_FUZZER_TASKS, REPEAT_SUITES, RESMOKE_ARGS, RESMOKE_JOBS_MAX,
-        SHOULD_SHUFFLE_TESTS, UNIQUE_GEN_SUFFIX_EXPANSION, USE_LARGE_DISTRO, USE_XLARGE_DISTRO,
-        XLARGE_DISTRO_EXPANSION,
+        ANTITHESIS_SUITES, BAZEL_ARGS, BAZEL_TARGETS_QUERY, CONSISTENT_SPLIT, CONTINUE_ON_FAILURE,
+        DEFAULT_SUBTASKS_PER_TASK_EXPANSION, DISABLE_STEPBACK, DYNAMIC_RESMOKE_JOBS,
+        EXCLUDED_OLD_VERSIONS_EXPANSION, EXEC_TIMEOUT, FUZZER_PARAMETERS, IDLE_TIMEOUT,
+        IS_DIFFERENTIAL_FUZZER, LARGE_DISTRO_EXPANSION, LARGE_DISTRO_TIER, LAST_VERSIONS_EXPANSION,
+        MAX_SUBTASKS_PER_TASK_EXPANSION, MAX_TASK_RUNTIME_EXPANSION,
+        MAX_TOTAL_GENERATED_SUBTASKS_EXPANSION, MULTIVERSION, NO_MULTIVERSION_GENERATE_TASKS,
+        NPM_COMMAND, NUM_BAZEL_SHARDS, NUM_FUZZER_FILES, NUM_FUZZER_TASKS, REPEAT_SUITES,
+        RESMOKE_ARGS, RESMOKE_JOBS_MAX, RUN_COVERED_TESTS, SCALE_EXEC_TIMEOUT,
+        SHOULD_SHUFFLE_TESTS, TASK_PRIORITY, TEST_RUNNER, TIMEOUT_FACTOR_EXPANSION,
+        UNIQUE_GEN_SUFFIX_EXPANSION, USE_FALLBACK_SPLIT, USE_LARGE_DISTRO, USE_XLARGE_DISTRO,
+        XLARGE_DISTRO_EXPANSION, XLARGE_DISTRO_TIER,
     },
-    generate_sub_tasks_config::GenerateSubTasksConfig,
+    generate_sub_tasks_config::{CommandHooks, FunctionNames, GenerateSubTasksConfig},
     task_types::{
+        antithesis_tasks::AntithesisGenTaskParams, bazel_shard_tasks::BazelShardGenParams,
         fuzzer_tasks::FuzzerGenTaskParams, generated_suite::GeneratedSuite,
         multiversion::MultiversionService, resmoke_tasks::ResmokeGenParams,
+        test_runner_backend::lookup_test_runner_backend,
     },
-    utils::task_name::remove_gen_suffix,
+    utils::task_name::{remove_gen_suffix, TaskNamingPolicy},
 };
 
+lazy_static! {
+    /// Regular expression for finding vars requesting a distro tier.
+    ///   `use_<tier>_distro`
+    static ref DISTRO_TIER_VAR_RE: Regex = Regex::new(r"^use_(?P<tier>[a-z0-9_]+)_distro$").unwrap();
+}
+
 /// Interface for performing extractions of evergreen project configuration.
 pub trait ConfigExtractionService: Sync + Send {
     /// Build the configuration for generated a fuzzer based on the evergreen task definition.
@@ -57,12 +80,46 @@ pub trait ConfigExtractionService: Sync + Send {
         platform: Option<String>,
     ) -> Result<ResmokeGenParams>;
 
-    /// Determine large distro name if the given sub-tasks should run on it.
+    /// Build the configuration for generating an antithesis task based on the evergreen task definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition of antithesis task to generate.
+    /// * `build_variant` - Build variant task is being generated based off.
+    ///
+    /// # Returns
+    ///
+    /// Parameters to configure how antithesis task should be generated.
+    fn task_def_to_antithesis_params(
+        &self,
+        task_def: &EvgTask,
+        build_variant: &BuildVariant,
+    ) -> Result<AntithesisGenTaskParams>;
+
+    /// Build the configuration for generating a bazel test shard task based on the evergreen task
+    /// definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition of bazel test shard task to generate.
+    /// * `build_variant` - Build variant task is being generated based off.
+    ///
+    /// # Returns
+    ///
+    /// Parameters to configure how bazel test shard task should be generated.
+    fn task_def_to_bazel_shard_params(
+        &self,
+        task_def: &EvgTask,
+        build_variant: &BuildVariant,
+    ) -> Result<BazelShardGenParams>;
+
+    /// Determine the distro name for the distro tier the given sub-tasks should run on.
     ///
     /// By default, we won't specify a distro and they will just use the default for the build
     /// variant. If they specify `use_large_distro` then we should instead use the large distro
     /// configured for the build variant. If that is not defined, then throw an error unless
-    /// the build variant is configured to be ignored.
+    /// the build variant is configured to be ignored. Custom distro tiers fallback to the large
+    /// distro if the build variant does not configure a distro for them.
     ///
     /// # Arguments
     ///
@@ -71,12 +128,40 @@ pub trait ConfigExtractionService: Sync + Send {
     ///
     /// # Returns
     ///
-    /// Large distro name if needed.
-    fn determine_large_distro(
+    /// Distro name if needed.
+    fn determine_distro_for_tier(
         &self,
         generated_task: &dyn GeneratedSuite,
         build_variant: &BuildVariant,
     ) -> Result<Option<String>>;
+
+    /// Determine the limits on the sub-tasks tasks on the given build variant are split into.
+    ///
+    /// # Arguments
+    ///
+    /// * `build_variant` - Build variant to query.
+    ///
+    /// # Returns
+    ///
+    /// Sub-task limits set by the expansions of the build variant.
+    fn determine_sub_task_limits(&self, build_variant: &BuildVariant) -> Result<SubTaskLimits>;
+
+    /// Determine the multiversion configurations the given task is generated for on the given
+    /// build variant.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition to query.
+    /// * `build_variant` - Build variant the task is generated for.
+    ///
+    /// # Returns
+    ///
+    /// Multiversion configurations to generate the task for, if it has any.
+    fn determine_multiversion_generate_tasks(
+        &self,
+        task_def: &EvgTask,
+        build_variant: &BuildVariant,
+    ) -> Option<Vec<MultiversionGenerateTaskConfig>>;
 }
 
 /// Implementation for performing extractions of evergreen project configuration.
@@ -86,6 +171,141 @@ pub struct ConfigExtractionServiceImpl {
     generating_task: String,
     config_location: String,
     gen_sub_tasks_config: Option<GenerateSubTasksConfig>,
+    strict: bool,
+    sub_task_demands: OnceLock<HashMap<String, SubTaskDemand>>,
+}
+
+/// Sub-tasks the generated tasks of a build variant are expected to be split into.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SubTaskDemand {
+    /// Sub-tasks of tasks whose number of sub-tasks is set by their task definition, like fuzzers
+    /// and bazel test shards.
+    pub fixed_sub_tasks: usize,
+    /// Number of times a task is split by the sub-task limits of the build variant. Tasks
+    /// generating sub-tasks for each multiversion configuration are split once per configuration.
+    pub split_tasks: usize,
+    /// Sub-tasks the splits would be split into if the build variant had no sub-task budget.
+    pub desired_split_sub_tasks: usize,
+}
+
+impl AddAssign for SubTaskDemand {
+    fn add_assign(&mut self, other: Self) {
+        self.fixed_sub_tasks += other.fixed_sub_tasks;
+        self.split_tasks += other.split_tasks;
+        self.desired_split_sub_tasks += other.desired_split_sub_tasks;
+    }
+}
+
+/// Part of the `max_total_generated_subtasks` budget of a build variant left for the splits of its
+/// tasks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubTaskBudget {
+    /// Sub-tasks left for the splits after the sub-tasks of tasks with a fixed number of sub-tasks.
+    pub available_sub_tasks: usize,
+    /// Sub-tasks the splits would be split into without the budget.
+    pub desired_sub_tasks: usize,
+    /// Number of splits sharing the budget.
+    pub split_tasks: usize,
+}
+
+impl SubTaskBudget {
+    /// Scale the number of sub-tasks a split wants down to its share of the budget.
+    ///
+    /// When the splits want more sub-tasks than are available, each split's count is scaled by
+    /// the same factor, so splits keep their ratio to each other. Counts are rounded up, which
+    /// gives a split less than one sub-task more than its exact share, so one sub-task fewer than
+    /// the number of splits is held back from the budget to keep the total within it. Every split
+    /// gets at least one sub-task, even if that exceeds the budget.
+    ///
+    /// # Arguments
+    ///
+    /// * `desired_sub_tasks` - Number of sub-tasks the split would be split into without the
+    ///   budget.
+    ///
+    /// # Returns
+    ///
+    /// Number of sub-tasks to divide the split into.
+    pub fn scale(&self, desired_sub_tasks: usize) -> usize {
+        if self.desired_sub_tasks <= self.available_sub_tasks {
+            return desired_sub_tasks;
+        }
+        let shared_sub_tasks = self
+            .available_sub_tasks
+            .saturating_sub(self.split_tasks.saturating_sub(1));
+        max(
+            1,
+            (desired_sub_tasks * shared_sub_tasks).div_ceil(self.desired_sub_tasks),
+        )
+    }
+}
+
+impl Display for SubTaskBudget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of {} wanted sub-tasks",
+            self.available_sub_tasks, self.desired_sub_tasks
+        )
+    }
+}
+
+/// Limits on the number of sub-tasks the tasks of a build variant are split into.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SubTaskLimits {
+    /// Number of sub-tasks each task is split into, if the build variant overrides it.
+    pub sub_tasks_per_task: Option<usize>,
+    /// Maximum number of sub-tasks each task can be split into, if the build variant sets one.
+    pub max_sub_tasks: Option<usize>,
+    /// Budget the tasks of the build variant share, if it sets one.
+    pub budget: Option<SubTaskBudget>,
+}
+
+impl SubTaskLimits {
+    /// Determine the number of sub-tasks a task wants to be split into, ignoring the budget.
+    ///
+    /// # Arguments
+    ///
+    /// * `default_sub_tasks` - Number of sub-tasks to split into if the build variant does not
+    ///   override it.
+    ///
+    /// # Returns
+    ///
+    /// Number of sub-tasks the task wants to be split into.
+    pub fn desired_sub_tasks(&self, default_sub_tasks: usize) -> usize {
+        let sub_tasks = self.sub_tasks_per_task.unwrap_or(default_sub_tasks);
+        self.max_sub_tasks
+            .map_or(sub_tasks, |max_sub_tasks| min(max_sub_tasks, sub_tasks))
+    }
+
+    /// Determine the number of sub-tasks to split a task into.
+    ///
+    /// # Arguments
+    ///
+    /// * `default_sub_tasks` - Number of sub-tasks to split into if the build variant does not
+    ///   override it.
+    ///
+    /// # Returns
+    ///
+    /// Number of sub-tasks to split the task into, scaled down to fit the budget.
+    pub fn sub_tasks(&self, default_sub_tasks: usize) -> usize {
+        let desired_sub_tasks = self.desired_sub_tasks(default_sub_tasks);
+        self.budget
+            .map_or(desired_sub_tasks, |budget| budget.scale(desired_sub_tasks))
+    }
+}
+
+impl Display for SubTaskLimits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let describe = |limit: Option<usize>| limit.map_or("unset".to_string(), |l| l.to_string());
+        write!(
+            f,
+            "{} sub-tasks per task, at most {}, budget {}",
+            describe(self.sub_tasks_per_task),
+            describe(self.max_sub_tasks),
+            self.budget
+                .map_or("unset".to_string(), |budget| budget.to_string())
+        )
+    }
 }
 
 impl ConfigExtractionServiceImpl {
@@ -97,6 +317,7 @@ impl ConfigExtractionServiceImpl {
     /// * `generating_task` - Name of task running task generation.
     /// * `config_location` - Location where generated configuration will be stored.
     /// * `gen_sub_tasks_config` - Configuration for generating sub-tasks.
+    /// * `strict` - Fail instead of warning when a large distro exception is used.
     ///
     pub fn new(
         evg_config_utils: Arc<dyn EvgConfigUtils>,
@@ -104,16 +325,65 @@ impl ConfigExtractionServiceImpl {
         generating_task: String,
         config_location: String,
         gen_sub_tasks_config: Option<GenerateSubTasksConfig>,
+        strict: bool,
     ) -> Self {
         Self {
             evg_config_utils,
             multiversion_service,
             generating_task,
             config_location,
+            strict,
             gen_sub_tasks_config,
+            sub_task_demands: OnceLock::new(),
         }
     }
 
+    /// Set the sub-tasks the generated tasks of each build variant are expected to be split into.
+    ///
+    /// The demands are estimated by the kinds of generated tasks, which are created with this
+    /// service, so they can only be set once the kinds exist. Until they are set, budgets do not
+    /// lower the sub-tasks tasks are split into.
+    ///
+    /// # Arguments
+    ///
+    /// * `sub_task_demands` - Sub-tasks the generated tasks of each build variant are expected to
+    ///   be split into.
+    pub fn set_sub_task_demands(&self, sub_task_demands: HashMap<String, SubTaskDemand>) {
+        if self.sub_task_demands.set(sub_task_demands).is_err() {
+            event!(Level::WARN, "Sub-task demands were already set, ignoring");
+        }
+    }
+
+    /// Determine the multiversion configurations a task is generated for.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition to query.
+    /// * `build_variant` - Build variant the task is generated for, if any.
+    ///
+    /// # Returns
+    ///
+    /// Multiversion configurations to generate the task for, if it has any.
+    fn find_multiversion_generate_tasks(
+        &self,
+        task_def: &EvgTask,
+        build_variant: Option<&BuildVariant>,
+    ) -> Option<Vec<MultiversionGenerateTaskConfig>> {
+        let lookup_expansion = |expansion| {
+            build_variant.and_then(|variant| {
+                self.evg_config_utils
+                    .lookup_build_variant_expansion(expansion, variant)
+            })
+        };
+        self.multiversion_service
+            .filter_multiversion_generate_tasks(
+                self.evg_config_utils
+                    .get_multiversion_generate_tasks(task_def),
+                lookup_expansion(LAST_VERSIONS_EXPANSION),
+                lookup_expansion(EXCLUDED_OLD_VERSIONS_EXPANSION),
+            )
+    }
+
     /// Determine the dependencies to add to tasks generated from the given task definition.
     ///
     /// A generated tasks should depend on all tasks listed in its "_gen" tasks depends_on
@@ -134,6 +404,340 @@ impl ConfigExtractionServiceImpl {
             .filter(|t| t != &self.generating_task)
             .collect()
     }
+
+    /// Determine the priority to give sub-tasks generated from the given task definition.
+    ///
+    /// The `priority_override` var takes precedence over the priority of the task definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Definition of task being generated from.
+    ///
+    /// # Returns
+    ///
+    /// Priority to give generated sub-tasks.
+    fn determine_priority(&self, task_def: &EvgTask) -> Result<Option<u64>> {
+        Ok(self
+            .evg_config_utils
+            .lookup_optional_param_u64(task_def, TASK_PRIORITY)?
+            .or(task_def.priority))
+    }
+
+    /// Determine the test runner the given task definition should run its tests with.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Definition of task being generated from.
+    ///
+    /// # Returns
+    ///
+    /// Name of requested test runner, if any.
+    fn determine_test_runner(&self, task_def: &EvgTask) -> Result<Option<String>> {
+        let test_runner = self
+            .evg_config_utils
+            .lookup_default_param_str(task_def, TEST_RUNNER, "");
+        if test_runner.is_empty() {
+            return Ok(None);
+        }
+
+        if lookup_test_runner_backend(Some(&test_runner)).is_none() {
+            bail!(
+                "Unknown test runner '{}' requested by task '{}'",
+                test_runner,
+                task_def.name
+            );
+        }
+        Ok(Some(test_runner))
+    }
+
+    /// Determine the custom distro tier requested by the given task definition.
+    ///
+    /// Tasks request a distro tier by setting a `use_<tier>_distro` var to true. The large and
+    /// xlarge distro tiers are handled separately by their own vars.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Definition of task being generated from.
+    ///
+    /// # Returns
+    ///
+    /// Name of requested distro tier, if any.
+    fn determine_distro_tier(&self, task_def: &EvgTask) -> Result<Option<String>> {
+        let mut var_names: Vec<String> = self
+            .evg_config_utils
+            .get_gen_task_vars(task_def)
+            .map(|vars| vars.into_keys().collect())
+            .unwrap_or_default();
+        var_names.sort();
+
+        for var_name in var_names {
+            if let Some(captures) = DISTRO_TIER_VAR_RE.captures(&var_name) {
+                let tier = &captures["tier"];
+                if tier == LARGE_DISTRO_TIER || tier == XLARGE_DISTRO_TIER {
+                    continue;
+                }
+
+                if self
+                    .evg_config_utils
+                    .lookup_default_param_bool(task_def, &var_name, false)?
+                {
+                    return Ok(Some(tier.to_string()));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Lookup the distro for the given distro tier on the given build variant.
+    ///
+    /// A `<tier>_distro_name` expansion on the build variant takes precedence over the
+    /// `build_variant_distro_tiers` in the sub-tasks config.
+    ///
+    /// # Arguments
+    ///
+    /// * `tier` - Name of distro tier to lookup.
+    /// * `build_variant` - Build variant to lookup distro tier on.
+    ///
+    /// # Returns
+    ///
+    /// Name of distro for the distro tier, if configured.
+    fn lookup_tier_distro(&self, tier: &str, build_variant: &BuildVariant) -> Option<String> {
+        let expansion = match tier {
+            LARGE_DISTRO_TIER => LARGE_DISTRO_EXPANSION.to_string(),
+            XLARGE_DISTRO_TIER => XLARGE_DISTRO_EXPANSION.to_string(),
+            _ => format!("{}_distro_name", tier),
+        };
+
+        self.evg_config_utils
+            .lookup_build_variant_expansion(&expansion, build_variant)
+            .or_else(|| {
+                self.gen_sub_tasks_config.as_ref().and_then(|config| {
+                    config
+                        .distro_for_tier(&build_variant.name, tier)
+                        .map(|distro| distro.to_string())
+                })
+            })
+    }
+
+    /// Lookup the number of CPUs on the distro sub-tasks will run on.
+    ///
+    /// Sub-tasks run on the distro of the requested distro tier, falling back to the default
+    /// distro of the build variant. The number of CPUs comes from the `distro_capabilities` in
+    /// the sub-tasks config.
+    ///
+    /// # Arguments
+    ///
+    /// * `tier` - Name of distro tier sub-tasks requested, if any.
+    /// * `build_variant` - Build variant sub-tasks will run on.
+    ///
+    /// # Returns
+    ///
+    /// Number of CPUs on the distro, if known.
+    fn lookup_distro_cpu_count(
+        &self,
+        tier: Option<&str>,
+        build_variant: &BuildVariant,
+    ) -> Option<u64> {
+        let gen_sub_tasks_config = self.gen_sub_tasks_config.as_ref()?;
+        let distro = tier
+            .and_then(|tier| self.lookup_tier_distro(tier, build_variant))
+            .or_else(|| build_variant.run_on.as_ref()?.first().cloned())?;
+
+        gen_sub_tasks_config.cpu_count_for_distro(&distro)
+    }
+
+    /// Determine the factor timeouts should be scaled by on the given build variant.
+    ///
+    /// Slower build variants (e.g. debug or sanitizer builds) can set the `timeout_factor`
+    /// expansion to scale the timeouts of generated tasks. If the expansion is not set, timeouts
+    /// are left unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `build_variant` - Build variant to query.
+    ///
+    /// # Returns
+    ///
+    /// Factor to scale timeouts by.
+    fn determine_timeout_factor(&self, build_variant: &BuildVariant) -> Result<f64> {
+        let timeout_factor = self
+            .evg_config_utils
+            .lookup_build_variant_expansion(TIMEOUT_FACTOR_EXPANSION, build_variant);
+
+        match timeout_factor {
+            Some(value) => match value.trim().parse::<f64>() {
+                Ok(factor) if factor.is_finite() && factor > 0.0 => Ok(factor),
+                _ => bail!(
+                    "Invalid `{}` expansion on build variant '{}': expected a positive number, got '{}'",
+                    TIMEOUT_FACTOR_EXPANSION,
+                    build_variant.name,
+                    value
+                ),
+            },
+            None => Ok(1.0),
+        }
+    }
+
+    /// Determine the maximum runtime generated sub-tasks should finish in on the given build variant.
+    ///
+    /// Build variants with an execution window (e.g. required build variants) can set the
+    /// `max_task_runtime_secs` expansion to be warned about sub-tasks expected to exceed it.
+    ///
+    /// # Arguments
+    ///
+    /// * `build_variant` - Build variant to query.
+    ///
+    /// # Returns
+    ///
+    /// Maximum runtime of sub-tasks in seconds, if the build variant sets one.
+    fn determine_max_task_runtime(&self, build_variant: &BuildVariant) -> Result<Option<u64>> {
+        let max_task_runtime = self
+            .evg_config_utils
+            .lookup_build_variant_expansion(MAX_TASK_RUNTIME_EXPANSION, build_variant);
+
+        match max_task_runtime {
+            Some(value) => match value.trim().parse::<u64>() {
+                Ok(runtime) if runtime > 0 => Ok(Some(runtime)),
+                _ => bail!(
+                    "Invalid `{}` expansion on build variant '{}': expected a positive integer, got '{}'",
+                    MAX_TASK_RUNTIME_EXPANSION,
+                    build_variant.name,
+                    value
+                ),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Determine the functions to call around the commands of sub-tasks generated for a task.
+    ///
+    /// # Arguments
+    ///
+    /// * `suite_name` - Name of suite the task runs.
+    /// * `task_tags` - Tags of the task.
+    ///
+    /// # Returns
+    ///
+    /// Functions configured for the task in the sub-tasks configuration.
+    fn determine_command_hooks(
+        &self,
+        suite_name: &str,
+        task_tags: &HashSet<String>,
+    ) -> CommandHooks {
+        self.gen_sub_tasks_config
+            .as_ref()
+            .map(|config| config.command_hooks_for(suite_name, task_tags))
+            .unwrap_or_default()
+    }
+
+    /// Determine the names of the functions generated sub-tasks call to run their tests.
+    ///
+    /// # Returns
+    ///
+    /// Function names configured in the sub-tasks configuration, or the default names.
+    fn determine_function_names(&self) -> FunctionNames {
+        self.gen_sub_tasks_config
+            .as_ref()
+            .map(|config| config.function_names.clone())
+            .unwrap_or_default()
+    }
+
+    /// Determine the conventions for naming generated sub-tasks.
+    ///
+    /// # Returns
+    ///
+    /// Naming policy configured in the sub-tasks configuration, or the default policy.
+    fn determine_task_naming(&self) -> TaskNamingPolicy {
+        self.gen_sub_tasks_config
+            .as_ref()
+            .map(|config| config.task_naming.clone())
+            .unwrap_or_default()
+    }
+
+    /// Lookup a sub-task count set by the given expansion on the given build variant.
+    ///
+    /// # Arguments
+    ///
+    /// * `expansion` - Name of expansion to lookup.
+    /// * `build_variant` - Build variant to query.
+    ///
+    /// # Returns
+    ///
+    /// Number of sub-tasks set by the expansion, if the build variant sets it.
+    fn lookup_sub_task_expansion(
+        &self,
+        expansion: &str,
+        build_variant: &BuildVariant,
+    ) -> Result<Option<usize>> {
+        let sub_tasks = self
+            .evg_config_utils
+            .lookup_build_variant_expansion(expansion, build_variant);
+
+        match sub_tasks {
+            Some(value) => match value.trim().parse::<usize>() {
+                Ok(count) if count > 0 => Ok(Some(count)),
+                _ => bail!(
+                    "Invalid `{}` expansion on build variant '{}': expected a positive integer, got '{}'",
+                    expansion,
+                    build_variant.name,
+                    value
+                ),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Determine the budget the tasks on the given build variant share.
+    ///
+    /// Build variants with limited capacity can set the `max_total_generated_subtasks` expansion.
+    /// What is left of it after the sub-tasks of tasks with a fixed number of sub-tasks is shared
+    /// by the splits of the other tasks, in proportion to the number of sub-tasks each split
+    /// wants. Generation fails before any task is split if a build variant shares tasks with
+    /// build variants whose limits differ, so the budget of one build variant never lowers the
+    /// sub-tasks of another build variant's tasks.
+    ///
+    /// # Arguments
+    ///
+    /// * `build_variant` - Build variant to query.
+    ///
+    /// # Returns
+    ///
+    /// Budget of the build variant, if it sets one.
+    fn determine_sub_task_budget(
+        &self,
+        build_variant: &BuildVariant,
+    ) -> Result<Option<SubTaskBudget>> {
+        Ok(self
+            .lookup_sub_task_expansion(MAX_TOTAL_GENERATED_SUBTASKS_EXPANSION, build_variant)?
+            .map(|max_total| {
+                let demand = self
+                    .sub_task_demands
+                    .get()
+                    .and_then(|demands| demands.get(&build_variant.name))
+                    .copied()
+                    .unwrap_or_default();
+                SubTaskBudget {
+                    available_sub_tasks: max_total.saturating_sub(demand.fixed_sub_tasks),
+                    desired_sub_tasks: demand.desired_split_sub_tasks,
+                    split_tasks: demand.split_tasks,
+                }
+            }))
+    }
+}
+
+/// Scale the given timeout by the given factor.
+///
+/// # Arguments
+///
+/// * `timeout_secs` - Timeout to scale.
+/// * `timeout_factor` - Factor to scale the timeout by.
+///
+/// # Returns
+///
+/// Scaled timeout, rounded up to the nearest second.
+fn scale_timeout(timeout_secs: u64, timeout_factor: f64) -> u64 {
+    (timeout_secs as f64 * timeout_factor).ceil() as u64
 }
 
 impl ConfigExtractionService for ConfigExtractionServiceImpl {
@@ -168,18 +772,15 @@ impl ConfigExtractionService for ConfigExtractionServiceImpl {
                 build_variant,
             )
             .unwrap();
-        let last_versions_expansion = self
-            .evg_config_utils
-            .lookup_build_variant_expansion(LAST_VERSIONS_EXPANSION, build_variant);
         let gen_task_suffix = self
             .evg_config_utils
             .lookup_build_variant_expansion(UNIQUE_GEN_SUFFIX_EXPANSION, build_variant);
+        let timeout_factor = self.determine_timeout_factor(build_variant)?;
 
         let suite = evg_config_utils.find_suite_name(task_def).to_string();
         Ok(FuzzerGenTaskParams {
             task_name,
             variant: build_variant.name.to_string(),
-            suite,
             num_files,
             num_tasks: evg_config_utils.lookup_required_param_u64(task_def, NUM_FUZZER_TASKS)?,
             resmoke_args: evg_config_utils.lookup_required_param_str(task_def, RESMOKE_ARGS)?,
@@ -197,21 +798,48 @@ impl ConfigExtractionService for ConfigExtractionServiceImpl {
                 .lookup_required_param_u64(task_def, RESMOKE_JOBS_MAX)?,
             should_shuffle: evg_config_utils
                 .lookup_required_param_bool(task_def, SHOULD_SHUFFLE_TESTS)?,
-            timeout_secs: evg_config_utils.lookup_required_param_u64(task_def, IDLE_TIMEOUT)?,
+            timeout_secs: scale_timeout(
+                evg_config_utils.lookup_required_param_u64(task_def, IDLE_TIMEOUT)?,
+                timeout_factor,
+            ),
+            exec_timeout_secs: evg_config_utils
+                .lookup_optional_param_u64(task_def, EXEC_TIMEOUT)?
+                .map(|t| scale_timeout(t, timeout_factor)),
             require_multiversion_setup: evg_config_utils
                 .get_task_tags(task_def)
                 .contains(MULTIVERSION),
+            is_differential: evg_config_utils.lookup_default_param_bool(
+                task_def,
+                IS_DIFFERENTIAL_FUZZER,
+                false,
+            )?,
             multiversion_generate_tasks: self
-                .multiversion_service
-                .filter_multiversion_generate_tasks(
-                    evg_config_utils.get_multiversion_generate_tasks(task_def),
-                    last_versions_expansion,
-                ),
+                .find_multiversion_generate_tasks(task_def, Some(build_variant)),
             config_location: self.config_location.clone(),
             dependencies: self.determine_task_dependencies(task_def),
             is_enterprise,
             platform: Some(evg_config_utils.infer_build_variant_platform(build_variant)),
             gen_task_suffix,
+            priority: self.determine_priority(task_def)?,
+            patchable: task_def.patchable,
+            disable: task_def.disable,
+            allowed_requesters: task_def.allowed_requesters.clone(),
+            task_exec_timeout_secs: task_def.exec_timeout_secs,
+            scale_exec_timeout: evg_config_utils.lookup_default_param_bool(
+                task_def,
+                SCALE_EXEC_TIMEOUT,
+                false,
+            )?,
+            disable_stepback: evg_config_utils.lookup_default_param_bool(
+                task_def,
+                DISABLE_STEPBACK,
+                false,
+            )?,
+            command_hooks: self
+                .determine_command_hooks(&suite, &evg_config_utils.get_task_tags(task_def)),
+            function_names: self.determine_function_names(),
+            task_naming: self.determine_task_naming(),
+            suite,
         })
     }
 
@@ -238,30 +866,55 @@ impl ConfigExtractionService for ConfigExtractionServiceImpl {
         let task_tags = self.evg_config_utils.get_task_tags(task_def);
         let require_multiversion_setup = task_tags.contains(MULTIVERSION);
         let no_multiversion_generate_tasks = task_tags.contains(NO_MULTIVERSION_GENERATE_TASKS);
-        let mut last_versions_expansion = None;
         let mut gen_task_suffix = None;
+        let mut modules = None;
         if let Some(variant) = build_variant {
-            last_versions_expansion = self
-                .evg_config_utils
-                .lookup_build_variant_expansion(LAST_VERSIONS_EXPANSION, variant);
+            modules = Some(variant.modules.clone().unwrap_or_default());
             gen_task_suffix = self
                 .evg_config_utils
                 .lookup_build_variant_expansion(UNIQUE_GEN_SUFFIX_EXPANSION, variant);
         }
 
+        let use_large_distro =
+            self.evg_config_utils
+                .lookup_default_param_bool(task_def, USE_LARGE_DISTRO, false)?;
+        let use_xlarge_distro =
+            self.evg_config_utils
+                .lookup_default_param_bool(task_def, USE_XLARGE_DISTRO, false)?;
+        let distro_tier = self.determine_distro_tier(task_def)?;
+        let distro_cpu_count = match build_variant {
+            Some(variant)
+                if self.evg_config_utils.lookup_default_param_bool(
+                    task_def,
+                    DYNAMIC_RESMOKE_JOBS,
+                    false,
+                )? =>
+            {
+                let tier = distro_tier.as_deref().or(if use_xlarge_distro {
+                    Some(XLARGE_DISTRO_TIER)
+                } else if use_large_distro {
+                    Some(LARGE_DISTRO_TIER)
+                } else {
+                    None
+                });
+                self.lookup_distro_cpu_count(tier, variant)
+            }
+            _ => None,
+        };
+        let (max_task_runtime_secs, sub_task_limits) = match build_variant {
+            Some(variant) => (
+                self.determine_max_task_runtime(variant)?,
+                self.determine_sub_task_limits(variant)?,
+            ),
+            None => (None, SubTaskLimits::default()),
+        };
+
         Ok(ResmokeGenParams {
             task_name,
             suite_name: suite,
-            use_large_distro: self.evg_config_utils.lookup_default_param_bool(
-                task_def,
-                USE_LARGE_DISTRO,
-                false,
-            )?,
-            use_xlarge_distro: self.evg_config_utils.lookup_default_param_bool(
-                task_def,
-                USE_XLARGE_DISTRO,
-                false,
-            )?,
+            use_large_distro,
+            use_xlarge_distro,
+            distro_tier,
             require_multiversion_setup,
             require_multiversion_generate_tasks: require_multiversion_setup
                 && !no_multiversion_generate_tasks,
@@ -277,27 +930,138 @@ impl ConfigExtractionService for ConfigExtractionServiceImpl {
                 .evg_config_utils
                 .lookup_optional_param_u64(task_def, RESMOKE_JOBS_MAX)?,
             multiversion_generate_tasks: self
-                .multiversion_service
-                .filter_multiversion_generate_tasks(
-                    self.evg_config_utils
-                        .get_multiversion_generate_tasks(task_def),
-                    last_versions_expansion,
-                ),
+                .find_multiversion_generate_tasks(task_def, build_variant),
             config_location: self.config_location.clone(),
             dependencies: self.determine_task_dependencies(task_def),
             is_enterprise,
+            modules,
             pass_through_vars: self.evg_config_utils.get_gen_task_vars(task_def),
             platform,
             gen_task_suffix,
+            priority: self.determine_priority(task_def)?,
+            test_runner: self.determine_test_runner(task_def)?,
+            patchable: task_def.patchable,
+            disable: task_def.disable,
+            allowed_requesters: task_def.allowed_requesters.clone(),
+            task_exec_timeout_secs: task_def.exec_timeout_secs,
+            scale_exec_timeout: self.evg_config_utils.lookup_default_param_bool(
+                task_def,
+                SCALE_EXEC_TIMEOUT,
+                false,
+            )?,
+            disable_stepback: self.evg_config_utils.lookup_default_param_bool(
+                task_def,
+                DISABLE_STEPBACK,
+                false,
+            )?,
+            consistent_split: self.evg_config_utils.lookup_default_param_bool(
+                task_def,
+                CONSISTENT_SPLIT,
+                false,
+            )?,
+            use_fallback_split: self.evg_config_utils.lookup_default_param_bool(
+                task_def,
+                USE_FALLBACK_SPLIT,
+                false,
+            )?,
+            run_covered_tests: self.evg_config_utils.lookup_default_param_bool(
+                task_def,
+                RUN_COVERED_TESTS,
+                false,
+            )?,
+            command_hooks: self.determine_command_hooks(&suite, &task_tags),
+            function_names: self.determine_function_names(),
+            task_naming: self.determine_task_naming(),
+            distro_cpu_count,
+            max_task_runtime_secs,
+            sub_task_limits,
         })
     }
 
-    /// Determine large distro name if the given sub-tasks should run on it.
+    /// Build the configuration for generating an antithesis task based on the evergreen task definition.
+    ///
+    /// The suites to generate for are taken from the `antithesis_suites` var. If that var is not
+    /// set, the suite the task definition runs will be used.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition of antithesis task to generate.
+    /// * `build_variant` - Build variant task is being generated based off.
+    ///
+    /// # Returns
+    ///
+    /// Parameters to configure how antithesis task should be generated.
+    fn task_def_to_antithesis_params(
+        &self,
+        task_def: &EvgTask,
+        build_variant: &BuildVariant,
+    ) -> Result<AntithesisGenTaskParams> {
+        let evg_config_utils = self.evg_config_utils.clone();
+        let mut suites: Vec<String> = evg_config_utils
+            .lookup_default_param_str(task_def, ANTITHESIS_SUITES, "")
+            .split_whitespace()
+            .map(|s| s.to_string())
+            .collect();
+        if suites.is_empty() {
+            suites.push(evg_config_utils.find_suite_name(task_def).to_string());
+        }
+
+        Ok(AntithesisGenTaskParams {
+            task_name: remove_gen_suffix(&task_def.name).to_string(),
+            suites,
+            resmoke_args: evg_config_utils.lookup_default_param_str(task_def, RESMOKE_ARGS, ""),
+            dependencies: self.determine_task_dependencies(task_def),
+            is_enterprise: evg_config_utils.is_enterprise_build_variant(build_variant),
+            platform: Some(evg_config_utils.infer_build_variant_platform(build_variant)),
+            gen_task_suffix: evg_config_utils
+                .lookup_build_variant_expansion(UNIQUE_GEN_SUFFIX_EXPANSION, build_variant),
+            task_naming: self.determine_task_naming(),
+        })
+    }
+
+    /// Build the configuration for generating a bazel test shard task based on the evergreen task
+    /// definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition of bazel test shard task to generate.
+    /// * `build_variant` - Build variant task is being generated based off.
+    ///
+    /// # Returns
+    ///
+    /// Parameters to configure how bazel test shard task should be generated.
+    fn task_def_to_bazel_shard_params(
+        &self,
+        task_def: &EvgTask,
+        build_variant: &BuildVariant,
+    ) -> Result<BazelShardGenParams> {
+        let evg_config_utils = self.evg_config_utils.clone();
+
+        Ok(BazelShardGenParams {
+            task_name: remove_gen_suffix(&task_def.name).to_string(),
+            targets_query: evg_config_utils
+                .lookup_required_param_str(task_def, BAZEL_TARGETS_QUERY)?,
+            num_shards: evg_config_utils.lookup_optional_param_u64(task_def, NUM_BAZEL_SHARDS)?,
+            bazel_args: evg_config_utils.lookup_default_param_str(task_def, BAZEL_ARGS, ""),
+            config_location: self.config_location.clone(),
+            dependencies: self.determine_task_dependencies(task_def),
+            is_enterprise: evg_config_utils.is_enterprise_build_variant(build_variant),
+            platform: Some(evg_config_utils.infer_build_variant_platform(build_variant)),
+            gen_task_suffix: evg_config_utils
+                .lookup_build_variant_expansion(UNIQUE_GEN_SUFFIX_EXPANSION, build_variant),
+            priority: self.determine_priority(task_def)?,
+            task_naming: self.determine_task_naming(),
+            function_names: self.determine_function_names(),
+        })
+    }
+
+    /// Determine the distro name for the distro tier the given sub-tasks should run on.
     ///
     /// By default, we won't specify a distro and they will just use the default for the build
     /// variant. If they specify `use_large_distro` then we should instead use the large distro
     /// configured for the build variant. If that is not defined, then throw an error unless
-    /// the build variant is configured to be ignored.
+    /// the build variant is configured to be ignored. Custom distro tiers fallback to the large
+    /// distro if the build variant does not configure a distro for them.
     ///
     /// # Arguments
     ///
@@ -306,31 +1070,52 @@ impl ConfigExtractionService for ConfigExtractionServiceImpl {
     ///
     /// # Returns
     ///
-    /// Large distro name if needed.
-    fn determine_large_distro(
+    /// Distro name if needed.
+    fn determine_distro_for_tier(
         &self,
         generated_task: &dyn GeneratedSuite,
         build_variant: &BuildVariant,
     ) -> Result<Option<String>> {
-        let large_distro_name = self
-            .evg_config_utils
-            .lookup_build_variant_expansion(LARGE_DISTRO_EXPANSION, build_variant);
-        let xlarge_distro_name = self
-            .evg_config_utils
-            .lookup_build_variant_expansion(XLARGE_DISTRO_EXPANSION, build_variant);
+        let distro_tier = generated_task.distro_tier();
+        if let Some(tier) = &distro_tier {
+            let tier_distro_name = self.lookup_tier_distro(tier, build_variant);
+            if tier_distro_name.is_some() {
+                return Ok(tier_distro_name);
+            }
+        }
+
+        let large_distro_name = self.lookup_tier_distro(LARGE_DISTRO_TIER, build_variant);
+        let xlarge_distro_name = self.lookup_tier_distro(XLARGE_DISTRO_TIER, build_variant);
         let build_variant_name = build_variant.name.as_str();
 
         if generated_task.use_xlarge_distro() && xlarge_distro_name.is_some() {
             return Ok(xlarge_distro_name);
         }
 
-        if generated_task.use_large_distro() || generated_task.use_xlarge_distro() {
+        if generated_task.use_large_distro()
+            || generated_task.use_xlarge_distro()
+            || distro_tier.is_some()
+        {
             if large_distro_name.is_some() {
                 return Ok(large_distro_name);
             }
 
             if let Some(gen_task_config) = &self.gen_sub_tasks_config {
                 if gen_task_config.ignore_missing_large_distro(build_variant_name) {
+                    let task_name = generated_task.display_name();
+                    if self.strict {
+                        bail!(
+                            "Task '{}' requests a large distro but build variant '{}' does not define one",
+                            task_name,
+                            build_variant_name
+                        );
+                    }
+                    event!(
+                        Level::WARN,
+                        task = task_name.as_str(),
+                        build_variant = build_variant_name,
+                        "Task requests a large distro but build variant does not define one, using default distro"
+                    );
                     return Ok(None);
                 }
             }
@@ -355,6 +1140,48 @@ list in the 'etc/generate_subtasks_config.yml' file.
 
         Ok(None)
     }
+
+    /// Determine the limits on the sub-tasks tasks on the given build variant are split into.
+    ///
+    /// Build variants can override the number of sub-tasks each task is split into with the
+    /// `default_subtasks_per_task` expansion and limit it with the `max_subtasks_per_task` and
+    /// `max_total_generated_subtasks` expansions.
+    ///
+    /// # Arguments
+    ///
+    /// * `build_variant` - Build variant to query.
+    ///
+    /// # Returns
+    ///
+    /// Sub-task limits set by the expansions of the build variant.
+    fn determine_sub_task_limits(&self, build_variant: &BuildVariant) -> Result<SubTaskLimits> {
+        Ok(SubTaskLimits {
+            sub_tasks_per_task: self
+                .lookup_sub_task_expansion(DEFAULT_SUBTASKS_PER_TASK_EXPANSION, build_variant)?,
+            max_sub_tasks: self
+                .lookup_sub_task_expansion(MAX_SUBTASKS_PER_TASK_EXPANSION, build_variant)?,
+            budget: self.determine_sub_task_budget(build_variant)?,
+        })
+    }
+
+    /// Determine the multiversion configurations the given task is generated for on the given
+    /// build variant.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition to query.
+    /// * `build_variant` - Build variant the task is generated for.
+    ///
+    /// # Returns
+    ///
+    /// Multiversion configurations to generate the task for, if it has any.
+    fn determine_multiversion_generate_tasks(
+        &self,
+        task_def: &EvgTask,
+        build_variant: &BuildVariant,
+    ) -> Option<Vec<MultiversionGenerateTaskConfig>> {
+        self.find_multiversion_generate_tasks(task_def, Some(build_variant))
+    }
 }
 
 #[cfg(test)]
@@ -362,11 +1189,17 @@ mod tests {
     use super::*;
     use crate::{
         evergreen::evg_config_utils::{EvgConfigUtilsImpl, MultiversionGenerateTaskConfig},
+        generate_sub_tasks_config::{
+            DistroCapabilityConfig, DistroTierConfig, EnterpriseDetection,
+        },
         task_types::{generated_suite::GeneratedSubTask, resmoke_tasks::GeneratedResmokeSuite},
     };
-    use maplit::{btreemap, hashset};
+    use maplit::{btreemap, hashmap, hashset};
     use rstest::rstest;
-    use shrub_rs::models::task::TaskDependency;
+    use shrub_rs::models::{
+        commands::fn_call_with_params, params::ParamValue, task::TaskDependency,
+    };
+    use std::collections::HashMap;
 
     struct MockMultiversionService {}
     impl MultiversionService for MockMultiversionService {
@@ -377,9 +1210,14 @@ mod tests {
             &self,
             multiversion_generate_tasks: Option<Vec<MultiversionGenerateTaskConfig>>,
             _last_versions_expansion: Option<String>,
+            _excluded_versions_expansion: Option<String>,
         ) -> Option<Vec<MultiversionGenerateTaskConfig>> {
             return multiversion_generate_tasks;
         }
+
+        fn old_versions(&self) -> Vec<String> {
+            todo!()
+        }
     }
     fn build_mocked_config_extraction_service() -> ConfigExtractionServiceImpl {
         ConfigExtractionServiceImpl::new(
@@ -388,6 +1226,7 @@ mod tests {
             "generating_task".to_string(),
             "config_location".to_string(),
             None,
+            false,
         )
     }
 
@@ -427,14 +1266,14 @@ mod tests {
         );
     }
 
-    // Tests for determine_large_distro.
+    // Tests for determine_distro_for_tier.
     #[rstest]
     #[case(vec![false, false], None, None)]
     #[case(vec![false, false], Some("large_distro".to_string()), None)]
     #[case(vec![true, false], Some("large_distro".to_string()), Some("large_distro".to_string()))]
     #[case(vec![false, true], Some("large_distro".to_string()), Some("large_distro".to_string()))]
     #[case(vec![true, true], Some("large_distro".to_string()), Some("large_distro".to_string()))]
-    fn test_determine_large_distro_should_return_large_distro_name(
+    fn test_determine_distro_for_tier_should_return_large_distro_name(
         #[case] use_large_distro: Vec<bool>,
         #[case] large_distro_name: Option<String>,
         #[case] expected_distro: Option<String>,
@@ -452,6 +1291,8 @@ mod tests {
                     },
                     use_large_distro: *value,
                     use_xlarge_distro: false,
+                    distro_tier: None,
+                    expected_runtime_secs: None,
                 })
                 .collect(),
         };
@@ -465,14 +1306,14 @@ mod tests {
         };
 
         let large_distro = config_extraction_service
-            .determine_large_distro(generated_task, &build_variant)
+            .determine_distro_for_tier(generated_task, &build_variant)
             .unwrap();
 
         assert_eq!(large_distro, expected_distro);
     }
 
     #[test]
-    fn test_determine_large_distro_should_fail_if_no_large_distro() {
+    fn test_determine_distro_for_tier_should_fail_if_no_large_distro() {
         let config_extraction_service = build_mocked_config_extraction_service();
         let generated_task: &dyn GeneratedSuite = &GeneratedResmokeSuite {
             task_name: "display_task_name".to_string(),
@@ -483,6 +1324,8 @@ mod tests {
                 },
                 use_large_distro: true,
                 use_xlarge_distro: false,
+                distro_tier: None,
+                expected_runtime_secs: None,
             }],
         };
         let build_variant = BuildVariant {
@@ -490,13 +1333,13 @@ mod tests {
         };
 
         let large_distro =
-            config_extraction_service.determine_large_distro(generated_task, &build_variant);
+            config_extraction_service.determine_distro_for_tier(generated_task, &build_variant);
 
         assert!(large_distro.is_err());
     }
 
     #[test]
-    fn test_determine_large_distro_respects_ignore_missing_large_distro() {
+    fn test_determine_distro_for_tier_respects_ignore_missing_large_distro() {
         let mut config_extraction_service = build_mocked_config_extraction_service();
         config_extraction_service.gen_sub_tasks_config = Some(GenerateSubTasksConfig {
             build_variant_large_distro_exceptions: hashset! {
@@ -504,6 +1347,21 @@ mod tests {
                 "my_build_variant".to_string(),
                 "build_variant_1".to_string(),
             },
+            multiversion_old_versions: None,
+            generator_display_task_groups: vec![],
+            generator_functions: vec![],
+            enterprise_detection: EnterpriseDetection::default(),
+            build_variant_distro_tiers: vec![],
+            build_variant_clones: vec![],
+            feature_flag_variants: vec![],
+            empty_suite_fallback: false,
+            distro_capabilities: vec![],
+            required_display_name_prefixes: vec![],
+            command_hooks: vec![],
+            function_names: FunctionNames::default(),
+            module_evergreen_configs: vec![],
+            history_variant_aliases: HashMap::new(),
+            task_naming: TaskNamingPolicy::default(),
         });
         let generated_task: &dyn GeneratedSuite = &GeneratedResmokeSuite {
             task_name: "display_task_name".to_string(),
@@ -514,6 +1372,8 @@ mod tests {
                 },
                 use_large_distro: true,
                 use_xlarge_distro: false,
+                distro_tier: None,
+                expected_runtime_secs: None,
             }],
         };
         let build_variant = BuildVariant {
@@ -522,8 +1382,637 @@ mod tests {
         };
 
         let large_distro =
-            config_extraction_service.determine_large_distro(generated_task, &build_variant);
+            config_extraction_service.determine_distro_for_tier(generated_task, &build_variant);
 
         assert!(large_distro.is_ok());
     }
+
+    #[test]
+    fn test_determine_distro_for_tier_fails_on_large_distro_exception_when_strict() {
+        let mut config_extraction_service = build_mocked_config_extraction_service();
+        config_extraction_service.strict = true;
+        config_extraction_service.gen_sub_tasks_config = Some(GenerateSubTasksConfig {
+            build_variant_large_distro_exceptions: hashset! {
+                "my_build_variant".to_string(),
+            },
+            multiversion_old_versions: None,
+            generator_display_task_groups: vec![],
+            generator_functions: vec![],
+            enterprise_detection: EnterpriseDetection::default(),
+            build_variant_distro_tiers: vec![],
+            build_variant_clones: vec![],
+            feature_flag_variants: vec![],
+            empty_suite_fallback: false,
+            distro_capabilities: vec![],
+            required_display_name_prefixes: vec![],
+            command_hooks: vec![],
+            function_names: FunctionNames::default(),
+            module_evergreen_configs: vec![],
+            history_variant_aliases: HashMap::new(),
+            task_naming: TaskNamingPolicy::default(),
+        });
+        let generated_task: &dyn GeneratedSuite = &GeneratedResmokeSuite {
+            task_name: "display_task_name".to_string(),
+            sub_suites: vec![GeneratedSubTask {
+                evg_task: EvgTask {
+                    name: "sub_suite_name".to_string(),
+                    ..Default::default()
+                },
+                use_large_distro: true,
+                use_xlarge_distro: false,
+                distro_tier: None,
+                expected_runtime_secs: None,
+            }],
+        };
+        let build_variant = BuildVariant {
+            name: "my_build_variant".to_string(),
+            ..Default::default()
+        };
+
+        let large_distro =
+            config_extraction_service.determine_distro_for_tier(generated_task, &build_variant);
+
+        assert!(large_distro.is_err());
+    }
+
+    #[rstest]
+    #[case(false, false, None, Some("large_distro"))]
+    #[case(false, true, None, Some("xlarge_distro"))]
+    #[case(false, false, Some("memory_intensive"), Some("memory_distro"))]
+    #[case(false, false, Some("gpu"), Some("gpu_expansion_distro"))]
+    #[case(false, false, Some("unknown"), Some("large_distro"))]
+    #[case(true, false, Some("memory_intensive"), Some("memory_distro"))]
+    fn test_determine_distro_for_tier_should_use_distro_tiers(
+        #[case] use_large_distro: bool,
+        #[case] use_xlarge_distro: bool,
+        #[case] distro_tier: Option<&str>,
+        #[case] expected_distro: Option<&str>,
+    ) {
+        let mut config_extraction_service = build_mocked_config_extraction_service();
+        config_extraction_service.gen_sub_tasks_config = Some(GenerateSubTasksConfig {
+            build_variant_large_distro_exceptions: hashset! {},
+            multiversion_old_versions: None,
+            generator_display_task_groups: vec![],
+            generator_functions: vec![],
+            enterprise_detection: EnterpriseDetection::default(),
+            build_variant_distro_tiers: vec![DistroTierConfig {
+                build_variants: vec!["my_*".to_string()],
+                tiers: hashmap! {
+                    "large".to_string() => "large_distro".to_string(),
+                    "xlarge".to_string() => "xlarge_distro".to_string(),
+                    "memory_intensive".to_string() => "memory_distro".to_string(),
+                    "gpu".to_string() => "gpu_distro".to_string(),
+                },
+            }],
+            build_variant_clones: vec![],
+            feature_flag_variants: vec![],
+            empty_suite_fallback: false,
+            distro_capabilities: vec![],
+            required_display_name_prefixes: vec![],
+            command_hooks: vec![],
+            function_names: FunctionNames::default(),
+            module_evergreen_configs: vec![],
+            history_variant_aliases: HashMap::new(),
+            task_naming: TaskNamingPolicy::default(),
+        });
+        let generated_task: &dyn GeneratedSuite = &GeneratedResmokeSuite {
+            task_name: "display_task_name".to_string(),
+            sub_suites: vec![GeneratedSubTask {
+                evg_task: EvgTask {
+                    name: "sub_suite_name".to_string(),
+                    ..Default::default()
+                },
+                use_large_distro,
+                use_xlarge_distro,
+                distro_tier: distro_tier.map(|t| t.to_string()),
+                expected_runtime_secs: None,
+            }],
+        };
+        let build_variant = BuildVariant {
+            name: "my_build_variant".to_string(),
+            expansions: Some(btreemap! {
+                "gpu_distro_name".to_string() => "gpu_expansion_distro".to_string(),
+            }),
+            ..Default::default()
+        };
+
+        let distro = config_extraction_service
+            .determine_distro_for_tier(generated_task, &build_variant)
+            .unwrap();
+
+        assert_eq!(distro, expected_distro.map(|d| d.to_string()));
+    }
+
+    // Tests for determine_distro_tier.
+    #[rstest]
+    #[case(hashmap! {}, None)]
+    #[case(hashmap! {"use_large_distro" => "true"}, None)]
+    #[case(hashmap! {"use_memory_intensive_distro" => "false"}, None)]
+    #[case(hashmap! {"use_memory_intensive_distro" => "true"}, Some("memory_intensive"))]
+    #[case(hashmap! {"use_large_distro" => "true", "use_gpu_distro" => "true"}, Some("gpu"))]
+    fn test_determine_distro_tier_should_use_tier_vars(
+        #[case] vars: HashMap<&str, &str>,
+        #[case] expected_tier: Option<&str>,
+    ) {
+        let config_extraction_service = build_mocked_config_extraction_service();
+        let task_def = EvgTask {
+            commands: Some(vec![fn_call_with_params(
+                "generate resmoke tasks",
+                vars.into_iter()
+                    .map(|(k, v)| (k.to_string(), ParamValue::from(v)))
+                    .collect(),
+            )]),
+            ..Default::default()
+        };
+
+        let tier = config_extraction_service
+            .determine_distro_tier(&task_def)
+            .unwrap();
+
+        assert_eq!(tier, expected_tier.map(|t| t.to_string()));
+    }
+
+    // Tests for lookup_distro_cpu_count.
+    #[rstest]
+    #[case(None, Some(2))]
+    #[case(Some("large"), Some(16))]
+    #[case(Some("memory_intensive"), None)]
+    #[case(Some("unknown"), Some(2))]
+    fn test_lookup_distro_cpu_count_should_use_distro_of_tier(
+        #[case] tier: Option<&str>,
+        #[case] expected_cpu_count: Option<u64>,
+    ) {
+        let mut config_extraction_service = build_mocked_config_extraction_service();
+        config_extraction_service.gen_sub_tasks_config = Some(GenerateSubTasksConfig {
+            build_variant_large_distro_exceptions: hashset! {},
+            multiversion_old_versions: None,
+            generator_display_task_groups: vec![],
+            generator_functions: vec![],
+            enterprise_detection: EnterpriseDetection::default(),
+            build_variant_distro_tiers: vec![DistroTierConfig {
+                build_variants: vec!["my_build_variant".to_string()],
+                tiers: hashmap! {
+                    "large".to_string() => "rhel80-large".to_string(),
+                    "memory_intensive".to_string() => "rhel80-memory".to_string(),
+                },
+            }],
+            build_variant_clones: vec![],
+            feature_flag_variants: vec![],
+            empty_suite_fallback: false,
+            distro_capabilities: vec![
+                DistroCapabilityConfig {
+                    distros: vec!["rhel80-large".to_string()],
+                    cpu_count: 16,
+                },
+                DistroCapabilityConfig {
+                    distros: vec!["rhel80-small".to_string()],
+                    cpu_count: 2,
+                },
+            ],
+            required_display_name_prefixes: vec![],
+            command_hooks: vec![],
+            function_names: FunctionNames::default(),
+            module_evergreen_configs: vec![],
+            history_variant_aliases: HashMap::new(),
+            task_naming: TaskNamingPolicy::default(),
+        });
+        let build_variant = BuildVariant {
+            name: "my_build_variant".to_string(),
+            run_on: Some(vec!["rhel80-small".to_string()]),
+            ..Default::default()
+        };
+
+        let cpu_count = config_extraction_service.lookup_distro_cpu_count(tier, &build_variant);
+
+        assert_eq!(cpu_count, expected_cpu_count);
+    }
+
+    // Tests for determine_test_runner.
+    #[rstest]
+    #[case(hashmap! {}, Some(None))]
+    #[case(hashmap! {"test_runner" => "resmoke"}, Some(Some("resmoke")))]
+    #[case(hashmap! {"test_runner" => "bazel"}, Some(Some("bazel")))]
+    #[case(hashmap! {"test_runner" => "pytest"}, None)]
+    fn test_determine_test_runner_should_reject_unknown_runners(
+        #[case] vars: HashMap<&str, &str>,
+        #[case] expected_runner: Option<Option<&str>>,
+    ) {
+        let config_extraction_service = build_mocked_config_extraction_service();
+        let task_def = EvgTask {
+            commands: Some(vec![fn_call_with_params(
+                "generate resmoke tasks",
+                vars.into_iter()
+                    .map(|(k, v)| (k.to_string(), ParamValue::from(v)))
+                    .collect(),
+            )]),
+            ..Default::default()
+        };
+
+        let test_runner = config_extraction_service.determine_test_runner(&task_def);
+
+        assert_eq!(
+            test_runner.ok(),
+            expected_runner.map(|r| r.map(|r| r.to_string()))
+        );
+    }
+
+    // Tests for determine_priority.
+    #[rstest]
+    #[case(None, None, None)]
+    #[case(Some(50), None, Some(50))]
+    #[case(None, Some("75"), Some(75))]
+    #[case(Some(50), Some("75"), Some(75))]
+    fn test_determine_priority_should_prefer_override(
+        #[case] task_priority: Option<u64>,
+        #[case] priority_override: Option<&str>,
+        #[case] expected_priority: Option<u64>,
+    ) {
+        let config_extraction_service = build_mocked_config_extraction_service();
+        let mut vars = hashmap! {};
+        if let Some(priority_override) = priority_override {
+            vars.insert(
+                "priority_override".to_string(),
+                ParamValue::from(priority_override),
+            );
+        }
+        let task_def = EvgTask {
+            commands: Some(vec![fn_call_with_params("generate resmoke tasks", vars)]),
+            priority: task_priority,
+            ..Default::default()
+        };
+
+        let priority = config_extraction_service
+            .determine_priority(&task_def)
+            .unwrap();
+
+        assert_eq!(priority, expected_priority);
+    }
+
+    // Tests for determine_timeout_factor.
+    #[rstest]
+    #[case(None, 1.0)]
+    #[case(Some("2"), 2.0)]
+    #[case(Some("1.5"), 1.5)]
+    #[case(Some(" 3 "), 3.0)]
+    fn test_determine_timeout_factor(
+        #[case] timeout_factor: Option<&str>,
+        #[case] expected_factor: f64,
+    ) {
+        let config_extraction_service = build_mocked_config_extraction_service();
+        let mut build_variant = BuildVariant {
+            ..Default::default()
+        };
+        if let Some(timeout_factor) = timeout_factor {
+            build_variant.expansions = Some(btreemap! {
+                "timeout_factor".to_string() => timeout_factor.to_string(),
+            });
+        }
+
+        let factor = config_extraction_service
+            .determine_timeout_factor(&build_variant)
+            .unwrap();
+
+        assert_eq!(factor, expected_factor);
+    }
+
+    #[rstest]
+    #[case("not_a_number")]
+    #[case("0")]
+    #[case("-2")]
+    fn test_determine_timeout_factor_should_fail_on_invalid_factor(#[case] timeout_factor: &str) {
+        let config_extraction_service = build_mocked_config_extraction_service();
+        let build_variant = BuildVariant {
+            expansions: Some(btreemap! {
+                "timeout_factor".to_string() => timeout_factor.to_string(),
+            }),
+            ..Default::default()
+        };
+
+        let factor = config_extraction_service.determine_timeout_factor(&build_variant);
+
+        assert!(factor.is_err());
+    }
+
+    // Tests for determine_max_task_runtime.
+    #[rstest]
+    #[case(None, Some(None))]
+    #[case(Some("9000"), Some(Some(9000)))]
+    #[case(Some(" 7200 "), Some(Some(7200)))]
+    #[case(Some("0"), None)]
+    #[case(Some("2.5h"), None)]
+    fn test_determine_max_task_runtime(
+        #[case] max_task_runtime: Option<&str>,
+        #[case] expected_runtime: Option<Option<u64>>,
+    ) {
+        let config_extraction_service = build_mocked_config_extraction_service();
+        let mut build_variant = BuildVariant {
+            name: "my_build_variant".to_string(),
+            ..Default::default()
+        };
+        if let Some(max_task_runtime) = max_task_runtime {
+            build_variant.expansions = Some(btreemap! {
+                "max_task_runtime_secs".to_string() => max_task_runtime.to_string(),
+            });
+        }
+
+        let runtime = config_extraction_service.determine_max_task_runtime(&build_variant);
+
+        assert_eq!(runtime.ok(), expected_runtime);
+    }
+
+    // Tests for determine_sub_task_budget.
+    #[rstest]
+    #[case(None, Some(None))]
+    #[case(Some("40"), Some(Some(40)))]
+    #[case(Some("70"), Some(Some(70)))]
+    #[case(Some("0"), None)]
+    #[case(Some("lots"), None)]
+    fn test_determine_sub_task_budget(
+        #[case] max_total_sub_tasks: Option<&str>,
+        #[case] expected_available_sub_tasks: Option<Option<usize>>,
+    ) {
+        let config_extraction_service = build_mocked_config_extraction_service();
+        config_extraction_service.set_sub_task_demands(hashmap! {
+            "my_build_variant".to_string() => SubTaskDemand {
+                fixed_sub_tasks: 0,
+                split_tasks: 10,
+                desired_split_sub_tasks: 50,
+            },
+        });
+        let mut build_variant = BuildVariant {
+            name: "my_build_variant".to_string(),
+            ..Default::default()
+        };
+        if let Some(max_total_sub_tasks) = max_total_sub_tasks {
+            build_variant.expansions = Some(btreemap! {
+                "max_total_generated_subtasks".to_string() => max_total_sub_tasks.to_string(),
+            });
+        }
+
+        let budget = config_extraction_service.determine_sub_task_budget(&build_variant);
+
+        let expected_budget = expected_available_sub_tasks.map(|available| {
+            available.map(|available_sub_tasks| SubTaskBudget {
+                available_sub_tasks,
+                desired_sub_tasks: 50,
+                split_tasks: 10,
+            })
+        });
+        assert_eq!(budget.ok(), expected_budget);
+    }
+
+    #[rstest]
+    #[case(Some("3"), None, 3)]
+    #[case(Some("3"), Some("100"), 3)]
+    #[case(Some("20"), Some("100"), 5)]
+    #[case(None, Some("30"), 3)]
+    #[case(None, None, 5)]
+    fn test_determine_sub_task_limits_should_apply_every_limit(
+        #[case] max_per_task: Option<&str>,
+        #[case] max_total_sub_tasks: Option<&str>,
+        #[case] expected_sub_tasks: usize,
+    ) {
+        let config_extraction_service = build_mocked_config_extraction_service();
+        config_extraction_service.set_sub_task_demands(hashmap! {
+            "my_build_variant".to_string() => SubTaskDemand {
+                fixed_sub_tasks: 0,
+                split_tasks: 10,
+                desired_split_sub_tasks: 50,
+            },
+        });
+        let mut expansions = btreemap! {};
+        if let Some(max_per_task) = max_per_task {
+            expansions.insert(
+                "max_subtasks_per_task".to_string(),
+                max_per_task.to_string(),
+            );
+        }
+        if let Some(max_total_sub_tasks) = max_total_sub_tasks {
+            expansions.insert(
+                "max_total_generated_subtasks".to_string(),
+                max_total_sub_tasks.to_string(),
+            );
+        }
+        let build_variant = BuildVariant {
+            name: "my_build_variant".to_string(),
+            expansions: Some(expansions),
+            ..Default::default()
+        };
+
+        let limits = config_extraction_service
+            .determine_sub_task_limits(&build_variant)
+            .unwrap();
+
+        assert_eq!(limits.sub_tasks(5), expected_sub_tasks);
+    }
+
+    // Tests for lookup_sub_task_expansion.
+    #[rstest]
+    #[case(None, Some(None))]
+    #[case(Some("8"), Some(Some(8)))]
+    #[case(Some("0"), None)]
+    #[case(Some("-1"), None)]
+    fn test_lookup_sub_task_expansion(
+        #[case] default_sub_tasks: Option<&str>,
+        #[case] expected_sub_tasks: Option<Option<usize>>,
+    ) {
+        let config_extraction_service = build_mocked_config_extraction_service();
+        let mut build_variant = BuildVariant {
+            name: "my_build_variant".to_string(),
+            ..Default::default()
+        };
+        if let Some(default_sub_tasks) = default_sub_tasks {
+            build_variant.expansions = Some(btreemap! {
+                "default_subtasks_per_task".to_string() => default_sub_tasks.to_string(),
+            });
+        }
+
+        let sub_tasks = config_extraction_service
+            .lookup_sub_task_expansion(DEFAULT_SUBTASKS_PER_TASK_EXPANSION, &build_variant);
+
+        assert_eq!(sub_tasks.ok(), expected_sub_tasks);
+    }
+
+    // Tests for SubTaskBudget.
+    #[rstest]
+    #[case(100, 50, 10, 5, 5)]
+    #[case(30, 50, 10, 5, 3)]
+    #[case(60, 100, 20, 5, 3)]
+    #[case(10, 150, 30, 5, 1)]
+    #[case(0, 50, 10, 5, 1)]
+    fn test_sub_task_budget_scale(
+        #[case] available_sub_tasks: usize,
+        #[case] desired_sub_tasks: usize,
+        #[case] split_tasks: usize,
+        #[case] desired_split_sub_tasks: usize,
+        #[case] expected: usize,
+    ) {
+        let budget = SubTaskBudget {
+            available_sub_tasks,
+            desired_sub_tasks,
+            split_tasks,
+        };
+
+        assert_eq!(budget.scale(desired_split_sub_tasks), expected);
+    }
+
+    #[test]
+    fn test_sub_task_budget_scale_should_keep_ratio_of_splits() {
+        let budget = SubTaskBudget {
+            available_sub_tasks: 7,
+            desired_sub_tasks: 10 + 5,
+            split_tasks: 2,
+        };
+
+        let large_suite = budget.scale(10);
+        let small_suite = budget.scale(5);
+
+        assert_eq!(large_suite, 4);
+        assert_eq!(small_suite, 2);
+        assert!(large_suite + small_suite <= 7);
+    }
+
+    // Tests for scale_timeout.
+    #[rstest]
+    #[case(1800, 1.0, 1800)]
+    #[case(1800, 2.0, 3600)]
+    #[case(1800, 1.5, 2700)]
+    #[case(1001, 1.5, 1502)]
+    fn test_scale_timeout(
+        #[case] timeout_secs: u64,
+        #[case] timeout_factor: f64,
+        #[case] expected_timeout: u64,
+    ) {
+        assert_eq!(
+            scale_timeout(timeout_secs, timeout_factor),
+            expected_timeout
+        );
+    }
+
+    // Tests for task_def_to_fuzzer_params.
+    fn build_fuzzer_task_def(extra_vars: HashMap<String, ParamValue>) -> EvgTask {
+        let mut vars = hashmap! {
+            "is_jstestfuzz".to_string() => ParamValue::from("true"),
+            "num_files".to_string() => ParamValue::from("10"),
+            "num_tasks".to_string() => ParamValue::from("5"),
+            "resmoke_args".to_string() => ParamValue::from("--args"),
+            "continue_on_failure".to_string() => ParamValue::from("false"),
+            "resmoke_jobs_max".to_string() => ParamValue::from("1"),
+            "should_shuffle".to_string() => ParamValue::from("false"),
+            "timeout_secs".to_string() => ParamValue::from("1800"),
+        };
+        vars.extend(extra_vars);
+
+        EvgTask {
+            name: "my_fuzzer_gen".to_string(),
+            commands: Some(vec![fn_call_with_params("generate resmoke tasks", vars)]),
+            ..Default::default()
+        }
+    }
+
+    #[rstest]
+    #[case(None, None, 1800, None)]
+    #[case(None, Some("3600"), 1800, Some(3600))]
+    #[case(Some("2"), None, 3600, None)]
+    #[case(Some("2"), Some("3600"), 3600, Some(7200))]
+    fn test_task_def_to_fuzzer_params_should_scale_timeouts(
+        #[case] timeout_factor: Option<&str>,
+        #[case] exec_timeout_secs: Option<&str>,
+        #[case] expected_timeout_secs: u64,
+        #[case] expected_exec_timeout_secs: Option<u64>,
+    ) {
+        let config_extraction_service = build_mocked_config_extraction_service();
+        let mut extra_vars = HashMap::new();
+        if let Some(exec_timeout_secs) = exec_timeout_secs {
+            extra_vars.insert(
+                "exec_timeout_secs".to_string(),
+                ParamValue::from(exec_timeout_secs),
+            );
+        }
+        let task_def = build_fuzzer_task_def(extra_vars);
+        let mut build_variant = BuildVariant {
+            name: "my_build_variant".to_string(),
+            ..Default::default()
+        };
+        if let Some(timeout_factor) = timeout_factor {
+            build_variant.expansions = Some(btreemap! {
+                "timeout_factor".to_string() => timeout_factor.to_string(),
+            });
+        }
+
+        let params = config_extraction_service
+            .task_def_to_fuzzer_params(&task_def, &build_variant)
+            .unwrap();
+
+        assert_eq!(params.timeout_secs, expected_timeout_secs);
+        assert_eq!(params.exec_timeout_secs, expected_exec_timeout_secs);
+    }
+
+    #[rstest]
+    #[case(None, false)]
+    #[case(Some("false"), false)]
+    #[case(Some("true"), true)]
+    fn test_task_def_to_fuzzer_params_should_detect_differential_fuzzers(
+        #[case] is_differential_fuzzer: Option<&str>,
+        #[case] expected: bool,
+    ) {
+        let config_extraction_service = build_mocked_config_extraction_service();
+        let mut extra_vars = HashMap::new();
+        if let Some(is_differential_fuzzer) = is_differential_fuzzer {
+            extra_vars.insert(
+                "is_differential_fuzzer".to_string(),
+                ParamValue::from(is_differential_fuzzer),
+            );
+        }
+        let task_def = build_fuzzer_task_def(extra_vars);
+        let build_variant = BuildVariant {
+            name: "my_build_variant".to_string(),
+            ..Default::default()
+        };
+
+        let params = config_extraction_service
+            .task_def_to_fuzzer_params(&task_def, &build_variant)
+            .unwrap();
+
+        assert_eq!(params.is_differential, expected);
+    }
+
+    // Tests for task_def_to_antithesis_params.
+    #[rstest]
+    #[case(None, vec!["my_suite"])]
+    #[case(Some(""), vec!["my_suite"])]
+    #[case(Some("suite_0"), vec!["suite_0"])]
+    #[case(Some("suite_0  suite_1\nsuite_2"), vec!["suite_0", "suite_1", "suite_2"])]
+    fn test_task_def_to_antithesis_params_should_find_suites(
+        #[case] antithesis_suites: Option<&str>,
+        #[case] expected_suites: Vec<&str>,
+    ) {
+        let config_extraction_service = build_mocked_config_extraction_service();
+        let mut vars = hashmap! {
+            "is_antithesis".to_string() => ParamValue::from("true"),
+            "suite".to_string() => ParamValue::from("my_suite"),
+        };
+        if let Some(antithesis_suites) = antithesis_suites {
+            vars.insert(
+                "antithesis_suites".to_string(),
+                ParamValue::from(antithesis_suites),
+            );
+        }
+        let task_def = EvgTask {
+            name: "my_antithesis_task_gen".to_string(),
+            commands: Some(vec![fn_call_with_params("generate resmoke tasks", vars)]),
+            ..Default::default()
+        };
+        let build_variant = BuildVariant {
+            name: "my_build_variant".to_string(),
+            ..Default::default()
+        };
+
+        let params = config_extraction_service
+            .task_def_to_antithesis_params(&task_def, &build_variant)
+            .unwrap();
+
+        assert_eq!(params.task_name, "my_antithesis_task");
+        assert_eq!(params.suites, expected_suites);
+    }
 }
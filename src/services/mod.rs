@@ -1 +1,2 @@
 pub mod config_extraction;
+pub mod variant_cloning;
@@ -0,0 +1,275 @@
+//! Service for creating build variants derived from existing build variants.
+//!
+//! Derived build variants run on the same distros and with the same modules as the build
+//! variant they are based on. They can add expansions and dependencies on top of those copied
+//! from the base build variant.
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+};
+
+use serde::Deserialize;
+use shrub_rs::models::{
+    task::{TaskDependency, TaskRef},
+    variant::{BuildVariant, DisplayTask},
+};
+
+use crate::generate_sub_tasks_config::glob_matches;
+
+/// Description of how to derive a build variant from a base build variant.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct VariantCloneConfig {
+    /// Build variant names or glob patterns to create derived build variants for.
+    #[serde(default)]
+    pub build_variants: Vec<String>,
+    /// Suffix to add to the name of the base build variant.
+    pub name_suffix: String,
+    /// Prefix to add to the display name of the base build variant.
+    #[serde(default)]
+    pub display_name_prefix: Option<String>,
+    /// Expansions to add to the expansions copied from the base build variant.
+    #[serde(default)]
+    pub expansions: BTreeMap<String, String>,
+    /// Tasks the derived build variant should depend on.
+    #[serde(default)]
+    pub depends_on: Vec<TaskDependency>,
+}
+
+impl VariantCloneConfig {
+    /// Check if a derived build variant should be created for the given build variant.
+    ///
+    /// # Arguments
+    ///
+    /// * `build_variant_name` - Name of build variant to check.
+    ///
+    /// # Returns
+    ///
+    /// true if the given build variant should be cloned.
+    pub fn matches(&self, build_variant_name: &str) -> bool {
+        self.build_variants
+            .iter()
+            .any(|pattern| glob_matches(pattern, build_variant_name))
+    }
+
+    /// Get the name of the build variant derived from the given build variant.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_build_variant_name` - Name of build variant being cloned.
+    ///
+    /// # Returns
+    ///
+    /// Name of the derived build variant.
+    pub fn variant_name(&self, base_build_variant_name: &str) -> String {
+        format!("{}-{}", base_build_variant_name, self.name_suffix)
+    }
+}
+
+/// A service for creating derived build variants.
+pub trait VariantCloningService: Sync + Send {
+    /// Create a build variant derived from the given base build variant.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_build_variant` - Build variant to base the derived build variant on.
+    /// * `build_variant_name` - Name of the derived build variant.
+    /// * `clone_config` - Description of how to derive the build variant.
+    /// * `tasks` - Tasks to run on the derived build variant.
+    /// * `display_tasks` - Display tasks to create on the derived build variant.
+    ///
+    /// # Returns
+    ///
+    /// A build variant derived from the base build variant.
+    fn clone_build_variant(
+        &self,
+        base_build_variant: &BuildVariant,
+        build_variant_name: &str,
+        clone_config: &VariantCloneConfig,
+        tasks: Vec<TaskRef>,
+        display_tasks: Vec<DisplayTask>,
+    ) -> BuildVariant;
+
+    /// Get the base build variant of each build variant derived so far.
+    ///
+    /// # Returns
+    ///
+    /// Map of derived build variant names to the name of the build variant they are based on.
+    fn clone_bases(&self) -> HashMap<String, String>;
+}
+
+/// Implementation of the variant cloning service.
+pub struct VariantCloningServiceImpl {
+    /// Base build variant of each build variant derived so far, by derived build variant name.
+    clone_bases: Mutex<HashMap<String, String>>,
+}
+
+impl VariantCloningServiceImpl {
+    /// Create a new instance of the variant cloning service.
+    pub fn new() -> Self {
+        Self {
+            clone_bases: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl VariantCloningService for VariantCloningServiceImpl {
+    /// Create a build variant derived from the given base build variant.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_build_variant` - Build variant to base the derived build variant on.
+    /// * `build_variant_name` - Name of the derived build variant.
+    /// * `clone_config` - Description of how to derive the build variant.
+    /// * `tasks` - Tasks to run on the derived build variant.
+    /// * `display_tasks` - Display tasks to create on the derived build variant.
+    ///
+    /// # Returns
+    ///
+    /// A build variant derived from the base build variant.
+    fn clone_build_variant(
+        &self,
+        base_build_variant: &BuildVariant,
+        build_variant_name: &str,
+        clone_config: &VariantCloneConfig,
+        tasks: Vec<TaskRef>,
+        display_tasks: Vec<DisplayTask>,
+    ) -> BuildVariant {
+        let display_name = base_build_variant
+            .display_name
+            .as_ref()
+            .map(|display_name| match &clone_config.display_name_prefix {
+                Some(prefix) => format!("{} {}", prefix, display_name),
+                None => display_name.clone(),
+            });
+
+        let mut expansions = base_build_variant.expansions.clone().unwrap_or_default();
+        expansions.extend(clone_config.expansions.clone());
+
+        let depends_on = if clone_config.depends_on.is_empty() {
+            None
+        } else {
+            Some(clone_config.depends_on.clone())
+        };
+
+        self.clone_bases.lock().unwrap().insert(
+            build_variant_name.to_string(),
+            base_build_variant.name.clone(),
+        );
+
+        BuildVariant {
+            name: build_variant_name.to_string(),
+            tasks,
+            display_name,
+            run_on: base_build_variant.run_on.clone(),
+            display_tasks: Some(display_tasks),
+            modules: base_build_variant.modules.clone(),
+            expansions: Some(expansions),
+            depends_on,
+            activate: Some(false),
+            ..Default::default()
+        }
+    }
+
+    /// Get the base build variant of each build variant derived so far.
+    ///
+    /// # Returns
+    ///
+    /// Map of derived build variant names to the name of the build variant they are based on.
+    fn clone_bases(&self) -> HashMap<String, String> {
+        self.clone_bases.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::btreemap;
+    use rstest::rstest;
+    use shrub_rs::models::task::EvgTask;
+
+    use super::*;
+
+    // matches tests.
+    #[rstest]
+    #[case(vec![], "rhel80", false)]
+    #[case(vec!["rhel80"], "rhel80", true)]
+    #[case(vec!["rhel80"], "rhel80-debug", false)]
+    #[case(vec!["macos", "rhel80*"], "rhel80-debug", true)]
+    fn test_matches(
+        #[case] build_variants: Vec<&str>,
+        #[case] build_variant_name: &str,
+        #[case] expected: bool,
+    ) {
+        let clone_config = VariantCloneConfig {
+            build_variants: build_variants.iter().map(|bv| bv.to_string()).collect(),
+            ..Default::default()
+        };
+
+        assert_eq!(clone_config.matches(build_variant_name), expected);
+    }
+
+    // clone_build_variant tests.
+    #[test]
+    fn test_clone_build_variant_should_copy_base_build_variant() {
+        let base_build_variant = BuildVariant {
+            name: "base-build-variant".to_string(),
+            display_name: Some("Base Build Variant".to_string()),
+            run_on: Some(vec!["base_distro".to_string()]),
+            modules: Some(vec!["base_module".to_string()]),
+            expansions: Some(btreemap! {
+                "base_expansion".to_string() => "base value".to_string(),
+                "overridden_expansion".to_string() => "base value".to_string(),
+            }),
+            ..Default::default()
+        };
+        let clone_config = VariantCloneConfig {
+            build_variants: vec!["base-build-variant".to_string()],
+            name_suffix: "derived".to_string(),
+            display_name_prefix: Some("[derived]".to_string()),
+            expansions: btreemap! {
+                "overridden_expansion".to_string() => "derived value".to_string(),
+            },
+            depends_on: vec![TaskDependency {
+                name: "compile".to_string(),
+                variant: Some("base-build-variant".to_string()),
+            }],
+        };
+        let task = EvgTask {
+            name: "sub_task_0".to_string(),
+            ..Default::default()
+        };
+        let variant_cloning_service = VariantCloningServiceImpl::new();
+
+        let build_variant = variant_cloning_service.clone_build_variant(
+            &base_build_variant,
+            &clone_config.variant_name(&base_build_variant.name),
+            &clone_config,
+            vec![task.get_reference(None, Some(false))],
+            vec![],
+        );
+
+        assert_eq!(build_variant.name, "base-build-variant-derived");
+        assert_eq!(
+            build_variant.display_name,
+            Some("[derived] Base Build Variant".to_string())
+        );
+        assert_eq!(build_variant.run_on, base_build_variant.run_on);
+        assert_eq!(build_variant.modules, base_build_variant.modules);
+        assert_eq!(
+            build_variant.expansions,
+            Some(btreemap! {
+                "base_expansion".to_string() => "base value".to_string(),
+                "overridden_expansion".to_string() => "derived value".to_string(),
+            })
+        );
+        assert_eq!(build_variant.depends_on.unwrap()[0].name, "compile");
+        assert_eq!(build_variant.tasks[0].name, "sub_task_0");
+        assert_eq!(build_variant.activate, Some(false));
+        assert_eq!(
+            variant_cloning_service.clone_bases(),
+            HashMap::from([(
+                "base-build-variant-derived".to_string(),
+                "base-build-variant".to_string()
+            )])
+        );
+    }
+}
@@ -0,0 +1,93 @@
+//! Handling of the signals sent when the generator task is aborted.
+//!
+//! Evergreen stops an aborted task by signalling it. Rather than being killed part way through
+//! writing files, generation is stopped at its next await point so that files being written are
+//! finished and an error report can be written before exiting.
+use std::fmt::Display;
+
+use tracing::{event, Level};
+
+/// Signal requesting generation to shut down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownSignal {
+    /// SIGINT, sent on Ctrl-C.
+    Interrupt,
+    /// SIGTERM, sent when the task is aborted.
+    Terminate,
+}
+
+impl ShutdownSignal {
+    /// Status to exit with after shutting down because of this signal.
+    ///
+    /// Following shell conventions, this is 128 plus the number of the signal.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ShutdownSignal::Interrupt => 130,
+            ShutdownSignal::Terminate => 143,
+        }
+    }
+}
+
+impl Display for ShutdownSignal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ShutdownSignal::Interrupt => "SIGINT",
+            ShutdownSignal::Terminate => "SIGTERM",
+        };
+        write!(f, "Generation was interrupted by {}", name)
+    }
+}
+
+impl std::error::Error for ShutdownSignal {}
+
+/// Wait until a signal requesting shutdown is received.
+///
+/// # Returns
+///
+/// The signal that was received.
+pub async fn wait_for_shutdown_signal() -> ShutdownSignal {
+    let signal = receive_shutdown_signal().await;
+    event!(Level::WARN, signal = signal.to_string(), "Shutting down");
+    signal
+}
+
+#[cfg(unix)]
+async fn receive_shutdown_signal() -> ShutdownSignal {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate =
+        signal(SignalKind::terminate()).expect("Unable to listen for SIGTERM signals");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => ShutdownSignal::Interrupt,
+        _ = terminate.recv() => ShutdownSignal::Terminate,
+    }
+}
+
+#[cfg(not(unix))]
+async fn receive_shutdown_signal() -> ShutdownSignal {
+    let _ = tokio::signal::ctrl_c().await;
+    ShutdownSignal::Interrupt
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(ShutdownSignal::Interrupt, 130, "Generation was interrupted by SIGINT")]
+    #[case(
+        ShutdownSignal::Terminate,
+        143,
+        "Generation was interrupted by SIGTERM"
+    )]
+    fn test_shutdown_signal_should_describe_signal(
+        #[case] signal: ShutdownSignal,
+        #[case] expected_exit_code: i32,
+        #[case] expected_message: &str,
+    ) {
+        assert_eq!(signal.exit_code(), expected_exit_code);
+        assert_eq!(signal.to_string(), expected_message);
+    }
+}
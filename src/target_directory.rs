@@ -0,0 +1,184 @@
+//! Checks that the target directory does not contain files left behind by an earlier run.
+//!
+//! Everything in the target directory is archived and shipped to the tasks that run the generated
+//! configuration, so stale files from an earlier run would be shipped along with it.
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+
+use crate::error_report::ERROR_REPORT_FILE;
+
+/// Files the generator writes that may be in the target directory before generation starts.
+const EXPECTED_FILES: &[&str] = &[ERROR_REPORT_FILE];
+
+/// Make sure the target directory is ready for generation.
+///
+/// The error report of an earlier, failed run is removed since it does not describe this run.
+/// Inputs of the run are never treated as stale, and if the target directory is itself an input,
+/// like the configuration build variants are regenerated from, it is left as it is.
+///
+/// # Arguments
+///
+/// * `target_directory` - Directory generated configuration will be written to.
+/// * `clean` - Remove files left behind by an earlier run instead of failing.
+/// * `inputs` - Files and directories the run reads its configuration from.
+///
+/// # Returns
+///
+/// An error listing the stale files if the directory contains files from an earlier run and
+/// `clean` is not set.
+pub fn prepare_target_directory(
+    target_directory: &Path,
+    clean: bool,
+    inputs: &[&Path],
+) -> Result<()> {
+    if !target_directory.exists() {
+        return Ok(());
+    }
+    let canonical_directory = target_directory.canonicalize()?;
+    let inputs: Vec<PathBuf> = inputs
+        .iter()
+        .filter_map(|input| input.canonicalize().ok())
+        .collect();
+    if inputs.contains(&canonical_directory) {
+        return Ok(());
+    }
+
+    let error_report = target_directory.join(ERROR_REPORT_FILE);
+    if error_report.is_file() {
+        std::fs::remove_file(error_report)?;
+    }
+
+    let stale_entries = find_stale_entries(&canonical_directory, &inputs)?;
+    if stale_entries.is_empty() {
+        return Ok(());
+    }
+
+    if !clean {
+        bail!(
+            "Target directory '{}' contains files from an earlier run, use --clean-target to remove them: {}",
+            target_directory.display(),
+            stale_entries
+                .iter()
+                .map(|entry| entry.display().to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+    }
+
+    for entry in stale_entries {
+        let path = target_directory.join(entry);
+        if path.is_dir() {
+            std::fs::remove_dir_all(path)?;
+        } else {
+            std::fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Find the entries of the target directory that were not expected to be there.
+///
+/// # Arguments
+///
+/// * `target_directory` - Canonical path of the directory to check.
+/// * `inputs` - Canonical paths of the inputs of the run.
+///
+/// # Returns
+///
+/// Names of the unexpected entries, sorted by name.
+fn find_stale_entries(target_directory: &Path, inputs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut stale_entries = vec![];
+    for entry in std::fs::read_dir(target_directory)? {
+        let file_name = entry?.file_name();
+        let path = target_directory.join(&file_name);
+        let is_expected = EXPECTED_FILES.iter().any(|expected| file_name == *expected)
+            || inputs.iter().any(|input| input.starts_with(&path));
+        if !is_expected {
+            stale_entries.push(PathBuf::from(file_name));
+        }
+    }
+    stale_entries.sort();
+    Ok(stale_entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempdir::TempDir;
+
+    use super::*;
+
+    fn build_target_directory() -> TempDir {
+        let tmp_dir = TempDir::new("target_directory").unwrap();
+        std::fs::write(tmp_dir.path().join("evergreen_config.json"), "").unwrap();
+        std::fs::create_dir(tmp_dir.path().join("generation_checkpoints")).unwrap();
+        tmp_dir
+    }
+
+    #[test]
+    fn test_prepare_target_directory_should_fail_on_stale_files() {
+        let tmp_dir = build_target_directory();
+
+        let result = prepare_target_directory(tmp_dir.path(), false, &[]);
+
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("evergreen_config.json, generation_checkpoints"));
+        assert!(tmp_dir.path().join("evergreen_config.json").exists());
+    }
+
+    #[test]
+    fn test_prepare_target_directory_should_remove_stale_files_when_cleaning() {
+        let tmp_dir = build_target_directory();
+
+        prepare_target_directory(tmp_dir.path(), true, &[]).unwrap();
+
+        let remaining: Vec<PathBuf> = std::fs::read_dir(tmp_dir.path())
+            .unwrap()
+            .map(|entry| PathBuf::from(entry.unwrap().file_name()))
+            .collect();
+        assert!(remaining.is_empty());
+    }
+
+    #[test]
+    fn test_prepare_target_directory_should_accept_missing_directory() {
+        let tmp_dir = TempDir::new("target_directory").unwrap();
+
+        assert!(prepare_target_directory(&tmp_dir.path().join("missing"), false, &[]).is_ok());
+    }
+
+    #[test]
+    fn test_prepare_target_directory_should_remove_error_report_of_earlier_run() {
+        let tmp_dir = TempDir::new("target_directory").unwrap();
+        std::fs::write(tmp_dir.path().join(ERROR_REPORT_FILE), "{}").unwrap();
+
+        prepare_target_directory(tmp_dir.path(), false, &[]).unwrap();
+
+        assert!(!tmp_dir.path().join(ERROR_REPORT_FILE).exists());
+    }
+
+    #[test]
+    fn test_prepare_target_directory_should_keep_inputs() {
+        let tmp_dir = TempDir::new("target_directory").unwrap();
+        std::fs::write(tmp_dir.path().join("expansions.yml"), "").unwrap();
+        std::fs::create_dir(tmp_dir.path().join("inputs")).unwrap();
+        std::fs::write(tmp_dir.path().join("inputs").join("ownership.yml"), "").unwrap();
+        let inputs = [
+            tmp_dir.path().join("expansions.yml"),
+            tmp_dir.path().join("inputs").join("ownership.yml"),
+        ];
+
+        prepare_target_directory(tmp_dir.path(), true, &[&inputs[0], &inputs[1]]).unwrap();
+
+        assert!(inputs.iter().all(|input| input.exists()));
+    }
+
+    #[test]
+    fn test_prepare_target_directory_should_keep_directory_that_is_an_input() {
+        let tmp_dir = build_target_directory();
+
+        prepare_target_directory(tmp_dir.path(), true, &[tmp_dir.path()]).unwrap();
+
+        assert!(tmp_dir.path().join("evergreen_config.json").exists());
+        assert!(tmp_dir.path().join("generation_checkpoints").exists());
+    }
+}
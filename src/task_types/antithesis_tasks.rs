@@ -0,0 +1,314 @@
+//! Service for generating antithesis tasks.
+//!
+//! Antithesis tasks build the docker-compose configuration for a resmoke suite and push the
+//! resulting images to antithesis. A sub-task is generated for each suite tagged for antithesis.
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use maplit::hashmap;
+use shrub_rs::models::{
+    commands::{fn_call, fn_call_with_params},
+    params::ParamValue,
+    task::{EvgTask, TaskDependency},
+    variant::BuildVariant,
+};
+use tracing::{event, Level};
+
+use crate::{
+    evergreen_names::{
+        ANTITHESIS_BUILD_DOCKER_COMPOSE, ANTITHESIS_IMAGE_PUSH, CONFIGURE_EVG_API_CREDS, DO_SETUP,
+        RESMOKE_ARGS, SUITE_NAME, TASK_NAME,
+    },
+    services::config_extraction::ConfigExtractionService,
+    utils::task_name::TaskNamingPolicy,
+};
+
+use super::{
+    generated_suite::{build_origin_tags, GeneratedSubTask, GeneratedSuite},
+    generated_task_kind::GeneratedTaskKind,
+};
+
+/// Parameters for how an antithesis task should be generated.
+#[derive(Default, Debug, Clone)]
+pub struct AntithesisGenTaskParams {
+    /// Name of task being generated.
+    pub task_name: String,
+    /// Resmoke suites to build antithesis images for.
+    pub suites: Vec<String>,
+    /// Arguments to pass to resmoke when building the docker-compose configuration.
+    pub resmoke_args: String,
+    /// List of tasks generated sub-tasks should depend on.
+    pub dependencies: Vec<String>,
+    /// Is this task for enterprise builds.
+    pub is_enterprise: bool,
+    /// Name of platform the task will run on.
+    pub platform: Option<String>,
+    /// Name of variant specific suffix to add to tasks
+    pub gen_task_suffix: Option<String>,
+    /// Conventions for naming generated sub-tasks.
+    pub task_naming: TaskNamingPolicy,
+}
+
+impl AntithesisGenTaskParams {
+    /// Build the vars to pass to the antithesis functions.
+    ///
+    /// # Arguments
+    ///
+    /// * `suite` - Suite to build antithesis images for.
+    ///
+    /// # Returns
+    ///
+    /// Map of arguments to pass to the antithesis functions.
+    fn build_antithesis_vars(&self, suite: &str) -> HashMap<String, ParamValue> {
+        hashmap! {
+            SUITE_NAME.to_string() => ParamValue::from(suite),
+            RESMOKE_ARGS.to_string() => ParamValue::from(self.resmoke_args.as_str()),
+            TASK_NAME.to_string() => ParamValue::from(self.task_name.as_str()),
+        }
+    }
+
+    /// Build the dependency structure to use the the generated sub-tasks.
+    ///
+    /// # Returns
+    ///
+    /// List of `TaskDependency`s for generated tasks.
+    fn get_dependencies(&self) -> Option<Vec<TaskDependency>> {
+        if self.dependencies.is_empty() {
+            None
+        } else {
+            Some(
+                self.dependencies
+                    .iter()
+                    .map(|d| TaskDependency {
+                        name: d.to_string(),
+                        variant: None,
+                    })
+                    .collect(),
+            )
+        }
+    }
+}
+
+/// A generated antithesis task.
+#[derive(Debug)]
+pub struct AntithesisTask {
+    /// Name for generated task.
+    pub task_name: String,
+    /// Sub-tasks comprising generated task.
+    pub sub_tasks: Vec<EvgTask>,
+}
+
+impl GeneratedSuite for AntithesisTask {
+    /// Get the display name to use for the generated task.
+    fn display_name(&self) -> String {
+        self.task_name.to_string()
+    }
+
+    /// Get the list of sub-tasks that comprise the generated task.
+    fn sub_tasks(&self) -> Vec<GeneratedSubTask> {
+        self.sub_tasks
+            .clone()
+            .into_iter()
+            .map(|sub_task| GeneratedSubTask {
+                evg_task: sub_task,
+                use_large_distro: false,
+                use_xlarge_distro: false,
+                distro_tier: None,
+                expected_runtime_secs: None,
+            })
+            .collect()
+    }
+}
+
+/// Generate an antithesis task based on the given parameters.
+///
+/// # Arguments
+///
+/// * `params` - Parameters describing how to generate the antithesis task.
+///
+/// # Returns
+///
+/// GeneratedSuite with details of how shrub task for the antithesis task is built.
+pub fn generate_antithesis_task(params: &AntithesisGenTaskParams) -> Box<dyn GeneratedSuite> {
+    let sub_tasks = params
+        .suites
+        .iter()
+        .enumerate()
+        .map(|(i, suite)| build_antithesis_sub_task(i, suite, params))
+        .collect();
+
+    Box::new(AntithesisTask {
+        task_name: params.task_name.to_string(),
+        sub_tasks,
+    })
+}
+
+/// Build a sub-task for an antithesis task.
+///
+/// # Arguments
+///
+/// * `sub_task_index` - Index of sub-task to build.
+/// * `suite` - Suite the sub-task should build antithesis images for.
+/// * `params` - Parameters for how task should be generated.
+///
+/// # Returns
+///
+/// A shrub task to generate the sub-task.
+fn build_antithesis_sub_task(
+    sub_task_index: usize,
+    suite: &str,
+    params: &AntithesisGenTaskParams,
+) -> EvgTask {
+    let sub_task_name = params.task_naming.sub_task_name(
+        &params.task_name,
+        sub_task_index,
+        params.suites.len(),
+        params.is_enterprise,
+        params.platform.as_deref(),
+    );
+
+    let vars = params.build_antithesis_vars(suite);
+    let commands = vec![
+        fn_call(DO_SETUP),
+        fn_call(CONFIGURE_EVG_API_CREDS),
+        fn_call_with_params(ANTITHESIS_BUILD_DOCKER_COMPOSE, vars.clone()),
+        fn_call_with_params(ANTITHESIS_IMAGE_PUSH, vars),
+    ];
+
+    EvgTask {
+        name: format!(
+            "{}{}",
+            sub_task_name,
+            params.gen_task_suffix.as_deref().unwrap_or("")
+        ),
+        commands: Some(commands),
+        depends_on: params.get_dependencies(),
+        tags: Some(build_origin_tags(&params.task_name, Some(suite), None)),
+        ..Default::default()
+    }
+}
+
+/// Generated task kind for antithesis tasks.
+pub struct AntithesisTaskKind {
+    /// Service to extraction configuration from evergreen config.
+    config_extraction_service: Arc<dyn ConfigExtractionService>,
+}
+
+impl AntithesisTaskKind {
+    /// Name of this kind of generated task.
+    pub const NAME: &'static str = "antithesis";
+
+    /// Create a new instance of the antithesis task kind.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_extraction_service` - Service to extraction configuration from evergreen config.
+    pub fn new(config_extraction_service: Arc<dyn ConfigExtractionService>) -> Self {
+        Self {
+            config_extraction_service,
+        }
+    }
+}
+
+#[async_trait]
+impl GeneratedTaskKind for AntithesisTaskKind {
+    /// Name of this kind of generated task.
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    /// Generate an antithesis task for the given task definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition to base generated task on.
+    /// * `build_variant` - Build Variant to base generated task on.
+    ///
+    /// # Returns
+    ///
+    /// Configuration for a generated antithesis task.
+    async fn generate(
+        &self,
+        task_def: &EvgTask,
+        build_variant: &BuildVariant,
+    ) -> Result<Box<dyn GeneratedSuite>> {
+        let params = self
+            .config_extraction_service
+            .task_def_to_antithesis_params(task_def, build_variant)?;
+        event!(
+            Level::INFO,
+            task_name = params.task_name.as_str(),
+            suites = params.suites.join(" "),
+            "Generating antithesis task"
+        );
+
+        Ok(generate_antithesis_task(&params))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use shrub_rs::models::commands::EvgCommand;
+
+    fn get_evg_fn_name(evg_command: &EvgCommand) -> Option<&str> {
+        if let EvgCommand::Function(func) = evg_command {
+            Some(&func.func)
+        } else {
+            None
+        }
+    }
+
+    // generate_antithesis_task tests.
+    #[rstest]
+    #[case(vec![], 0)]
+    #[case(vec!["suite_0"], 1)]
+    #[case(vec!["suite_0", "suite_1", "suite_2"], 3)]
+    fn test_generate_antithesis_task_should_create_sub_task_per_suite(
+        #[case] suites: Vec<&str>,
+        #[case] expected_sub_tasks: usize,
+    ) {
+        let params = AntithesisGenTaskParams {
+            task_name: "antithesis_task".to_string(),
+            suites: suites.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+
+        let generated_task = generate_antithesis_task(&params);
+
+        assert_eq!(generated_task.display_name(), "antithesis_task");
+        assert_eq!(generated_task.sub_tasks().len(), expected_sub_tasks);
+    }
+
+    // build_antithesis_sub_task tests.
+    #[test]
+    fn test_build_antithesis_sub_task() {
+        let params = AntithesisGenTaskParams {
+            task_name: "antithesis_task".to_string(),
+            suites: vec!["suite_0".to_string(), "suite_1".to_string()],
+            resmoke_args: "--args".to_string(),
+            dependencies: vec!["archive_dist_test".to_string()],
+            gen_task_suffix: Some("-suffix".to_string()),
+            ..Default::default()
+        };
+
+        let sub_task = build_antithesis_sub_task(1, "suite_1", &params);
+
+        assert_eq!(sub_task.name, "antithesis_task_1-suffix");
+        let commands = sub_task.commands.unwrap();
+        assert_eq!(get_evg_fn_name(&commands[0]), Some("do setup"));
+        assert_eq!(
+            get_evg_fn_name(&commands[2]),
+            Some("antithesis build docker compose")
+        );
+        assert_eq!(get_evg_fn_name(&commands[3]), Some("antithesis image push"));
+        if let EvgCommand::Function(func) = &commands[2] {
+            let vars = func.vars.as_ref().unwrap();
+            assert_eq!(vars.get("suite"), Some(&ParamValue::from("suite_1")));
+            assert_eq!(vars.get("resmoke_args"), Some(&ParamValue::from("--args")));
+        }
+        assert_eq!(sub_task.depends_on.unwrap()[0].name, "archive_dist_test");
+    }
+}
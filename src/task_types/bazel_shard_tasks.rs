@@ -0,0 +1,521 @@
+//! Service for generating tasks that run shards of bazel test targets.
+//!
+//! The test targets selected by a bazel query are split into shards that are balanced by the
+//! historic runtime of each target. A sub-task is generated for each shard that runs its targets
+//! with `bazel test`, without involving resmoke.
+use std::{cmp::min, collections::HashMap, sync::Arc};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use maplit::hashmap;
+use shrub_rs::models::{
+    commands::{fn_call, fn_call_with_params},
+    params::ParamValue,
+    task::{EvgTask, TaskDependency},
+    variant::BuildVariant,
+};
+use tracing::{event, warn, Level};
+
+use crate::{
+    evergreen::{evg_config_utils::EvgConfigUtils, evg_task_history::TaskHistoryService},
+    evergreen_names::{
+        BAZEL_ARGS, BAZEL_TARGETS, CONFIGURE_EVG_API_CREDS, DO_SETUP, GEN_TASK_CONFIG_LOCATION,
+        NUM_BAZEL_SHARDS,
+    },
+    generate_sub_tasks_config::FunctionNames,
+    resmoke::bazel_proxy::BazelTargetDiscovery,
+    services::config_extraction::{ConfigExtractionService, SubTaskDemand},
+    utils::task_name::TaskNamingPolicy,
+};
+
+use super::{
+    generated_suite::{build_origin_tags, GeneratedSubTask, GeneratedSuite},
+    generated_task_kind::GeneratedTaskKind,
+    resmoke_tasks::split_by_runtime,
+    test_runner_backend::{BazelTestBackend, TestRunnerBackend},
+};
+
+/// Parameters for how a bazel test shard task should be generated.
+#[derive(Default, Debug, Clone)]
+pub struct BazelShardGenParams {
+    /// Name of task being generated.
+    pub task_name: String,
+    /// Bazel query selecting the test targets to run.
+    pub targets_query: String,
+    /// Number of shards to split the targets into.
+    pub num_shards: Option<u64>,
+    /// Arguments to pass to `bazel test`.
+    pub bazel_args: String,
+    /// Location where generated task configuration will be stored in S3.
+    pub config_location: String,
+    /// List of tasks generated sub-tasks should depend on.
+    pub dependencies: Vec<String>,
+    /// Is this task for enterprise builds.
+    pub is_enterprise: bool,
+    /// Name of platform the task will run on.
+    pub platform: Option<String>,
+    /// Name of variant specific suffix to add to tasks
+    pub gen_task_suffix: Option<String>,
+    /// Priority to give generated sub-tasks.
+    pub priority: Option<u64>,
+    /// Conventions for naming generated sub-tasks.
+    pub task_naming: TaskNamingPolicy,
+    /// Names of the functions used by generated sub-tasks.
+    pub function_names: FunctionNames,
+}
+
+impl BazelShardGenParams {
+    /// Build the vars to pass to the function running a shard.
+    ///
+    /// # Arguments
+    ///
+    /// * `targets` - Bazel test targets in the shard.
+    ///
+    /// # Returns
+    ///
+    /// Map of arguments to pass to the function running a shard.
+    fn build_shard_vars(&self, targets: &[String]) -> HashMap<String, ParamValue> {
+        hashmap! {
+            BAZEL_TARGETS.to_string() => ParamValue::from(targets.join(" ").as_str()),
+            BAZEL_ARGS.to_string() => ParamValue::from(self.bazel_args.as_str()),
+            GEN_TASK_CONFIG_LOCATION.to_string() => ParamValue::from(self.config_location.as_str()),
+        }
+    }
+
+    /// Build the dependency structure to use the the generated sub-tasks.
+    ///
+    /// # Returns
+    ///
+    /// List of `TaskDependency`s for generated tasks.
+    fn get_dependencies(&self) -> Option<Vec<TaskDependency>> {
+        if self.dependencies.is_empty() {
+            None
+        } else {
+            Some(
+                self.dependencies
+                    .iter()
+                    .map(|d| TaskDependency {
+                        name: d.to_string(),
+                        variant: None,
+                    })
+                    .collect(),
+            )
+        }
+    }
+}
+
+/// A generated bazel test shard task.
+#[derive(Debug)]
+pub struct BazelShardTask {
+    /// Name for generated task.
+    pub task_name: String,
+    /// Sub-tasks comprising generated task.
+    pub sub_tasks: Vec<EvgTask>,
+}
+
+impl GeneratedSuite for BazelShardTask {
+    /// Get the display name to use for the generated task.
+    fn display_name(&self) -> String {
+        self.task_name.to_string()
+    }
+
+    /// Get the list of sub-tasks that comprise the generated task.
+    fn sub_tasks(&self) -> Vec<GeneratedSubTask> {
+        self.sub_tasks
+            .clone()
+            .into_iter()
+            .map(|sub_task| GeneratedSubTask {
+                evg_task: sub_task,
+                use_large_distro: false,
+                use_xlarge_distro: false,
+                distro_tier: None,
+                expected_runtime_secs: None,
+            })
+            .collect()
+    }
+}
+
+/// Split bazel test targets into shards with similar total runtimes.
+///
+/// Targets are placed longest first into the shard with the least runtime so far, the same way
+/// resmoke tests are split. Targets without any runtime history are assumed to take the average
+/// runtime of the targets that have history.
+///
+/// # Arguments
+///
+/// * `targets` - Bazel test targets to split.
+/// * `target_runtimes` - Historic runtimes of bazel test targets.
+/// * `num_shards` - Max number of shards to split the targets into.
+///
+/// # Returns
+///
+/// List of shards, each containing a list of targets.
+pub fn split_targets(
+    targets: &[String],
+    target_runtimes: &HashMap<String, f64>,
+    num_shards: usize,
+) -> Vec<Vec<String>> {
+    let num_shards = min(num_shards, targets.len());
+    if num_shards == 0 {
+        return vec![];
+    }
+
+    let known_runtimes: Vec<f64> = targets
+        .iter()
+        .filter_map(|target| target_runtimes.get(target).copied())
+        .collect();
+    let default_runtime = if known_runtimes.is_empty() {
+        1.0
+    } else {
+        known_runtimes.iter().sum::<f64>() / known_runtimes.len() as f64
+    };
+
+    let mut sorted_targets: Vec<(String, f64)> = targets
+        .iter()
+        .map(|target| {
+            (
+                target.clone(),
+                *target_runtimes.get(target).unwrap_or(&default_runtime),
+            )
+        })
+        .collect();
+    sorted_targets.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let (shards, _) = split_by_runtime(sorted_targets, num_shards);
+    shards
+}
+
+/// Generate a bazel test shard task based on the given parameters.
+///
+/// # Arguments
+///
+/// * `params` - Parameters describing how to generate the task.
+/// * `shards` - Bazel test targets in each shard.
+///
+/// # Returns
+///
+/// GeneratedSuite with details of how shrub task for the shards are built.
+pub fn generate_bazel_shard_task(
+    params: &BazelShardGenParams,
+    shards: &[Vec<String>],
+) -> Box<dyn GeneratedSuite> {
+    let sub_tasks = shards
+        .iter()
+        .enumerate()
+        .map(|(i, targets)| build_bazel_shard_sub_task(i, shards.len(), targets, params))
+        .collect();
+
+    Box::new(BazelShardTask {
+        task_name: params.task_name.to_string(),
+        sub_tasks,
+    })
+}
+
+/// Build a sub-task running a shard of bazel test targets.
+///
+/// # Arguments
+///
+/// * `sub_task_index` - Index of sub-task to build.
+/// * `total_sub_tasks` - Total number of sub-tasks being built.
+/// * `targets` - Bazel test targets the sub-task should run.
+/// * `params` - Parameters for how task should be generated.
+///
+/// # Returns
+///
+/// A shrub task to run the shard.
+fn build_bazel_shard_sub_task(
+    sub_task_index: usize,
+    total_sub_tasks: usize,
+    targets: &[String],
+    params: &BazelShardGenParams,
+) -> EvgTask {
+    let sub_task_name = params.task_naming.sub_task_name(
+        &params.task_name,
+        sub_task_index,
+        total_sub_tasks,
+        params.is_enterprise,
+        params.platform.as_deref(),
+    );
+
+    build_bazel_targets_sub_task(&sub_task_name, targets, params)
+}
+
+/// Build a sub-task with the given name that runs the given bazel test targets.
+///
+/// # Arguments
+///
+/// * `sub_task_name` - Name of the sub-task, before any variant specific suffix is added.
+/// * `targets` - Bazel test targets the sub-task should run.
+/// * `params` - Parameters for how task should be generated.
+///
+/// # Returns
+///
+/// A shrub task to run the targets.
+pub fn build_bazel_targets_sub_task(
+    sub_task_name: &str,
+    targets: &[String],
+    params: &BazelShardGenParams,
+) -> EvgTask {
+    let commands = vec![
+        fn_call(DO_SETUP),
+        fn_call(CONFIGURE_EVG_API_CREDS),
+        fn_call_with_params(
+            BazelTestBackend {}.run_tests_fn_name(&params.function_names),
+            params.build_shard_vars(targets),
+        ),
+    ];
+
+    EvgTask {
+        name: format!(
+            "{}{}",
+            sub_task_name,
+            params.gen_task_suffix.as_deref().unwrap_or("")
+        ),
+        commands: Some(commands),
+        depends_on: params.get_dependencies(),
+        tags: Some(build_origin_tags(&params.task_name, None, None)),
+        priority: params.priority,
+        ..Default::default()
+    }
+}
+
+/// Generated task kind for bazel test shard tasks.
+pub struct BazelShardTaskKind {
+    /// Utilities to work with evergreen project configuration.
+    evg_config_utils: Arc<dyn EvgConfigUtils>,
+    /// Service to extraction configuration from evergreen config.
+    config_extraction_service: Arc<dyn ConfigExtractionService>,
+    /// Service to query the bazel test targets to run.
+    bazel_target_discovery: Arc<dyn BazelTargetDiscovery>,
+    /// Service to query task runtime history.
+    task_history_service: Arc<dyn TaskHistoryService>,
+    /// Number of shards to split targets into when a task does not specify one.
+    default_num_shards: usize,
+    /// Split targets without querying their runtime history.
+    use_task_split_fallback: bool,
+}
+
+impl BazelShardTaskKind {
+    /// Name of this kind of generated task.
+    pub const NAME: &'static str = "bazel_test_shards";
+
+    /// Create a new instance of the bazel test shard task kind.
+    ///
+    /// # Arguments
+    ///
+    /// * `evg_config_utils` - Utilities to work with evergreen project configuration.
+    /// * `config_extraction_service` - Service to extraction configuration from evergreen config.
+    /// * `bazel_target_discovery` - Service to query the bazel test targets to run.
+    /// * `task_history_service` - Service to query task runtime history.
+    /// * `default_num_shards` - Number of shards to split targets into when a task does not
+    ///    specify one.
+    /// * `use_task_split_fallback` - Split targets without querying their runtime history.
+    pub fn new(
+        evg_config_utils: Arc<dyn EvgConfigUtils>,
+        config_extraction_service: Arc<dyn ConfigExtractionService>,
+        bazel_target_discovery: Arc<dyn BazelTargetDiscovery>,
+        task_history_service: Arc<dyn TaskHistoryService>,
+        default_num_shards: usize,
+        use_task_split_fallback: bool,
+    ) -> Self {
+        Self {
+            evg_config_utils,
+            config_extraction_service,
+            bazel_target_discovery,
+            task_history_service,
+            default_num_shards,
+            use_task_split_fallback,
+        }
+    }
+
+    /// Get the historic runtimes of the targets run by the given task.
+    ///
+    /// If the history cannot be fetched, targets are split as if they all take the same time.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_name` - Name of task to query.
+    /// * `build_variant` - Name of build variant to query.
+    ///
+    /// # Returns
+    ///
+    /// Map of bazel test targets to their average runtime.
+    async fn get_target_runtimes(
+        &self,
+        task_name: &str,
+        build_variant: &str,
+    ) -> HashMap<String, f64> {
+        if self.use_task_split_fallback {
+            return HashMap::new();
+        }
+
+        match self
+            .task_history_service
+            .get_bazel_target_history(task_name, build_variant)
+            .await
+        {
+            Ok(target_runtimes) => target_runtimes,
+            Err(err) => {
+                warn!(
+                    task_name = task_name,
+                    build_variant = build_variant,
+                    error = format!("{:?}", err).as_str(),
+                    "Could not get bazel target history, splitting targets evenly",
+                );
+                HashMap::new()
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl GeneratedTaskKind for BazelShardTaskKind {
+    /// Name of this kind of generated task.
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    /// Bazel test shard tasks generate a sub-task for each shard set by their task definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition to estimate.
+    /// * `_build_variant` - Build variant the task is generated for.
+    ///
+    /// # Returns
+    ///
+    /// Sub-tasks the generated task is expected to need.
+    fn sub_task_demand(&self, task_def: &EvgTask, _build_variant: &BuildVariant) -> SubTaskDemand {
+        SubTaskDemand {
+            fixed_sub_tasks: self
+                .evg_config_utils
+                .lookup_optional_param_u64(task_def, NUM_BAZEL_SHARDS)
+                .ok()
+                .flatten()
+                .map_or(self.default_num_shards, |shards| shards as usize),
+            split_tasks: 0,
+            desired_split_sub_tasks: 0,
+        }
+    }
+
+    /// Generate a bazel test shard task for the given task definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition to base generated task on.
+    /// * `build_variant` - Build Variant to base generated task on.
+    ///
+    /// # Returns
+    ///
+    /// Configuration for a generated bazel test shard task.
+    async fn generate(
+        &self,
+        task_def: &EvgTask,
+        build_variant: &BuildVariant,
+    ) -> Result<Box<dyn GeneratedSuite>> {
+        let params = self
+            .config_extraction_service
+            .task_def_to_bazel_shard_params(task_def, build_variant)?;
+        let targets = self
+            .bazel_target_discovery
+            .query_targets(&params.targets_query)?;
+        if targets.is_empty() {
+            bail!(
+                "Bazel query '{}' for task '{}' did not match any targets",
+                params.targets_query,
+                params.task_name
+            );
+        }
+
+        let target_runtimes = self
+            .get_target_runtimes(&params.task_name, &build_variant.name)
+            .await;
+        let num_shards = params
+            .num_shards
+            .map(|n| n as usize)
+            .unwrap_or(self.default_num_shards);
+        let shards = split_targets(&targets, &target_runtimes, num_shards);
+        event!(
+            Level::INFO,
+            task_name = params.task_name.as_str(),
+            targets = targets.len(),
+            shards = shards.len(),
+            "Generating bazel test shard task"
+        );
+
+        Ok(generate_bazel_shard_task(&params, &shards))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use shrub_rs::models::commands::EvgCommand;
+
+    use crate::evergreen_names::RUN_BAZEL_TESTS;
+
+    use super::*;
+
+    fn to_targets(targets: &[&str]) -> Vec<String> {
+        targets.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[rstest]
+    #[case(vec!["a", "b", "c", "d"], hashmap! {"a" => 10.0, "b" => 6.0, "c" => 4.0, "d" => 1.0}, 2, vec![vec!["a", "d"], vec!["b", "c"]])]
+    #[case(vec!["a", "b", "c"], hashmap! {}, 5, vec![vec!["a"], vec!["b"], vec!["c"]])]
+    #[case(vec!["a", "b", "c"], hashmap! {"a" => 10.0, "b" => 2.0}, 2, vec![vec!["a"], vec!["c", "b"]])]
+    #[case(vec![], hashmap! {}, 2, vec![])]
+    fn test_split_targets_should_balance_runtimes(
+        #[case] targets: Vec<&str>,
+        #[case] runtimes: HashMap<&str, f64>,
+        #[case] num_shards: usize,
+        #[case] expected_shards: Vec<Vec<&str>>,
+    ) {
+        let target_runtimes = runtimes
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect();
+
+        let shards = split_targets(&to_targets(&targets), &target_runtimes, num_shards);
+
+        assert_eq!(
+            shards,
+            expected_shards
+                .iter()
+                .map(|s| to_targets(s))
+                .collect::<Vec<Vec<String>>>()
+        );
+    }
+
+    #[test]
+    fn test_generate_bazel_shard_task_should_run_shard_targets() {
+        let params = BazelShardGenParams {
+            task_name: "unittests".to_string(),
+            bazel_args: "--config=dbg".to_string(),
+            gen_task_suffix: Some("-suffix".to_string()),
+            priority: Some(50),
+            ..Default::default()
+        };
+        let shards = vec![
+            to_targets(&["//a:test", "//b:test"]),
+            to_targets(&["//c:test"]),
+        ];
+
+        let generated_task = generate_bazel_shard_task(&params, &shards);
+        let sub_tasks = generated_task.sub_tasks();
+
+        assert_eq!(generated_task.display_name(), "unittests");
+        assert_eq!(sub_tasks.len(), 2);
+        assert_eq!(sub_tasks[0].evg_task.name, "unittests_0-suffix");
+        assert_eq!(sub_tasks[0].evg_task.priority, Some(50));
+        let commands = sub_tasks[0].evg_task.commands.as_ref().unwrap();
+        if let EvgCommand::Function(func) = &commands[2] {
+            assert_eq!(func.func, RUN_BAZEL_TESTS);
+            let vars = func.vars.as_ref().unwrap();
+            assert_eq!(vars[BAZEL_TARGETS], ParamValue::from("//a:test //b:test"));
+            assert_eq!(vars[BAZEL_ARGS], ParamValue::from("--config=dbg"));
+        } else {
+            panic!("Expected function call to run the shard");
+        }
+    }
+}
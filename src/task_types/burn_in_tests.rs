@@ -1,26 +1,32 @@
 use anyhow::Result;
+use async_trait::async_trait;
 use shrub_rs::models::task::TaskDependency;
-use shrub_rs::models::{
-    task::{EvgTask, TaskRef},
-    variant::{BuildVariant, DisplayTask},
-};
+use shrub_rs::models::{task::EvgTask, variant::BuildVariant};
 use std::{
     collections::{BTreeMap, HashMap},
     sync::Arc,
 };
+use tracing::warn;
 
 use crate::evergreen::evg_config_utils::EvgConfigUtils;
+use crate::evergreen::evg_task_history::{get_test_name, TaskHistoryService, TaskRuntimeHistory};
 use crate::evergreen_names::{
-    BURN_IN_TASKS, BURN_IN_TASK_NAME, COMPILE_VARIANT, VERSION_BURN_IN_GEN_TASK,
-    VERSION_GEN_VARIANT,
+    BURN_IN_TAG_ADDITIONAL_DEPENDENCIES, BURN_IN_TAG_GEN_TASK_DEPENDENCY,
+    BURN_IN_TAG_GEN_VARIANT_DEPENDENCY, BURN_IN_TASKS, BURN_IN_TASK_NAME, COMPILE_VARIANT,
+    IS_BAZEL_TEST_SHARDS, VERSION_BURN_IN_GEN_TASK, VERSION_GEN_VARIANT,
 };
 use crate::{
     evergreen_names::BURN_IN_BYPASS,
     resmoke::burn_in_proxy::{BurnInDiscovery, DiscoveredTask},
-    services::config_extraction::ConfigExtractionService,
+    services::{
+        config_extraction::ConfigExtractionService,
+        variant_cloning::{VariantCloneConfig, VariantCloningService},
+    },
     task_types::resmoke_tasks::{GeneratedResmokeSuite, SubSuite},
+    utils::task_name::remove_gen_suffix,
 };
 
+use super::bazel_shard_tasks::build_bazel_targets_sub_task;
 use super::generated_suite::GeneratedSubTask;
 use super::{
     generated_suite::GeneratedSuite,
@@ -30,6 +36,12 @@ use super::{
 /// Options to pass to resmoke to enable burn_in repetition.
 const BURN_IN_REPEAT_CONFIG: &str =
     "--repeatTestsSecs=600 --repeatTestsMin=2 --repeatTestsMax=1000";
+/// Target runtime of a burn_in sub_task when the runtime of its test is known.
+const BURN_IN_TARGET_RUNTIME_SECS: f64 = 1200.0;
+/// Maximum number of times to repeat a test during burn_in.
+const BURN_IN_MAX_REPEATS: f64 = 1000.0;
+/// Number of times to run a bazel test target during burn_in when its runtime is not known.
+const BURN_IN_DEFAULT_BAZEL_RUNS: u64 = 20;
 /// How to label burn_in generated sub_tasks.
 const BURN_IN_LABEL: &str = "burn_in";
 /// How to label burn_in generated sub_tasks.
@@ -40,6 +52,7 @@ const BURN_IN_REPEAT_TASK_NUM: usize = 10;
 const BURN_IN_DISPLAY_NAME_PREFIX: &str = "[jstests_affected]";
 
 /// A service for generating burn_in tasks.
+#[async_trait]
 pub trait BurnInService: Sync + Send {
     /// Generate a burn_in_tests task for the given build variant.
     ///
@@ -52,11 +65,11 @@ pub trait BurnInService: Sync + Send {
     /// # Returns
     ///
     /// A generated task for burn_in_tests on the given build variant.
-    fn generate_burn_in_suite(
+    async fn generate_burn_in_suite(
         &self,
         build_variant: &BuildVariant,
         run_build_variant_name: &str,
-        task_map: Arc<HashMap<String, EvgTask>>,
+        task_map: Arc<HashMap<String, Arc<EvgTask>>>,
     ) -> Result<Box<dyn GeneratedSuite>>;
 
     /// Generate a burn_in_tags build variant for the given base build variant.
@@ -67,16 +80,20 @@ pub trait BurnInService: Sync + Send {
     /// * `run_build_variant_name` - Build variant name to run burn_in_tests task on.
     /// * `generated_task` - Generated burn_in_tests task.
     /// * `compile_task_dependency` - Compile task name generated build variant should depend on.
+    /// * `in_base_variant` - Add the generated task to the base build variant instead of a clone.
     ///
     /// # Returns
     ///
-    /// A generated burn_in_tags build variant based on another build variant.
+    /// A generated burn_in_tags build variant based on another build variant. When adding to the
+    /// base build variant, only the task references and display tasks to merge into the
+    /// generated configuration of the base build variant are included.
     fn generate_burn_in_tags_build_variant(
         &self,
         base_build_variant: &BuildVariant,
         run_build_variant_name: String,
         generated_task: &dyn GeneratedSuite,
         compile_task_dependency: String,
+        in_base_variant: bool,
     ) -> Result<BuildVariant>;
 
     /// Generate a burn_in_tasks task for the given build variant.
@@ -92,7 +109,7 @@ pub trait BurnInService: Sync + Send {
     fn generate_burn_in_tasks_suite(
         &self,
         build_variant: &BuildVariant,
-        task_map: Arc<HashMap<String, EvgTask>>,
+        task_map: Arc<HashMap<String, Arc<EvgTask>>>,
     ) -> Result<Box<dyn GeneratedSuite>>;
 }
 
@@ -108,6 +125,15 @@ pub struct BurnInServiceImpl {
 
     /// Utilities to work with evergreen project configuration.
     evg_config_utils: Arc<dyn EvgConfigUtils>,
+
+    /// Service to create derived build variants.
+    variant_cloning_service: Arc<dyn VariantCloningService>,
+
+    /// Service to query the runtime history of tasks.
+    task_history_service: Arc<dyn TaskHistoryService>,
+
+    /// Repeat tests without querying their runtime history.
+    use_task_split_fallback: bool,
 }
 
 /// Information about a suite being generated in burn_in.
@@ -167,17 +193,92 @@ impl BurnInServiceImpl {
     /// * `gen_resmoke_task_service` - Service to generate resmoke tasks.
     /// * `config_extraction_service` - Service to extraction configuration from evergreen project data.
     /// * `evg_config_utils` - Utilities to work with evergreen project configuration.
+    /// * `variant_cloning_service` - Service to clone build variants.
+    /// * `task_history_service` - Service to query the runtime history of tasks.
+    /// * `use_task_split_fallback` - Repeat tests without querying their runtime history.
     pub fn new(
         burn_in_discovery: Arc<dyn BurnInDiscovery>,
         gen_resmoke_task_service: Arc<dyn GenResmokeTaskService>,
         config_extraction_service: Arc<dyn ConfigExtractionService>,
         evg_config_utils: Arc<dyn EvgConfigUtils>,
+        variant_cloning_service: Arc<dyn VariantCloningService>,
+        task_history_service: Arc<dyn TaskHistoryService>,
+        use_task_split_fallback: bool,
     ) -> Self {
         BurnInServiceImpl {
             burn_in_discovery,
             gen_resmoke_task_service,
             config_extraction_service,
             evg_config_utils,
+            variant_cloning_service,
+            task_history_service,
+            use_task_split_fallback,
+        }
+    }
+
+    /// Determine the version burn-in generation task burn_in_tags build variants should depend on.
+    ///
+    /// The task and variant can be set with project expansions, defaulting to the names used in
+    /// the mongodb-mongo project. Setting the task expansion to an empty value removes the
+    /// dependency.
+    ///
+    /// # Returns
+    ///
+    /// Dependency on the version burn-in generation task if one should be added.
+    fn version_burn_in_gen_dependency(&self) -> Option<TaskDependency> {
+        let gen_task = self
+            .evg_config_utils
+            .lookup_project_expansion(BURN_IN_TAG_GEN_TASK_DEPENDENCY)
+            .unwrap_or_else(|| VERSION_BURN_IN_GEN_TASK.to_string());
+        if gen_task.is_empty() {
+            return None;
+        }
+
+        let gen_variant = self
+            .evg_config_utils
+            .lookup_project_expansion(BURN_IN_TAG_GEN_VARIANT_DEPENDENCY)
+            .unwrap_or_else(|| VERSION_GEN_VARIANT.to_string());
+
+        Some(TaskDependency {
+            name: gen_task,
+            variant: Some(gen_variant),
+        })
+    }
+
+    /// Look up the runtime history of the given task to base burn_in repetitions on.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_name` - Name of task to look up.
+    /// * `build_variant` - Build variant the task runs on.
+    ///
+    /// # Returns
+    ///
+    /// Runtime history of the task, or None if it should not be used or is not available.
+    async fn lookup_task_history(
+        &self,
+        task_name: &str,
+        build_variant: &BuildVariant,
+    ) -> Option<TaskRuntimeHistory> {
+        if self.use_task_split_fallback {
+            return None;
+        }
+
+        match self
+            .task_history_service
+            .get_task_history(task_name, &build_variant.name)
+            .await
+        {
+            Ok(task_history) => Some(task_history),
+            Err(err) => {
+                warn!(
+                    build_variant = build_variant.name.as_str(),
+                    task_name = task_name,
+                    error = err.to_string().as_str(),
+                    "Could not get task history for burn_in, using default repeat config",
+                );
+                None
+            }
         }
     }
 
@@ -188,6 +289,7 @@ impl BurnInServiceImpl {
     /// * `discovered_task` - Task discovered to pull into resmoke.
     /// * `task_def` - Evergreen project definition of task.
     /// * `run_build_variant` - Name of build variant to run burn_in_tests task on.
+    /// * `task_history` - Runtime history of the task, if available.
     ///
     /// # Returns
     ///
@@ -197,6 +299,7 @@ impl BurnInServiceImpl {
         discovered_task: &DiscoveredTask,
         task_def: &EvgTask,
         run_build_variant: &str,
+        task_history: Option<&TaskRuntimeHistory>,
     ) -> Result<Vec<GeneratedSubTask>> {
         let mut sub_suites = vec![];
         for suite in discovered_task.suites.iter() {
@@ -204,7 +307,10 @@ impl BurnInServiceImpl {
                 let mut params = self
                     .config_extraction_service
                     .task_def_to_resmoke_params(task_def, false, None, None)?;
-                update_resmoke_params_for_burn_in(&mut params, test);
+                let average_runtime = task_history
+                    .and_then(|history| history.test_map.get(&get_test_name(test)))
+                    .map(|test_history| test_history.average_runtime);
+                update_resmoke_params_for_burn_in(&mut params, test, average_runtime);
 
                 if params.require_multiversion_generate_tasks {
                     for multiversion_task in params.multiversion_generate_tasks.as_ref().unwrap() {
@@ -237,6 +343,110 @@ impl BurnInServiceImpl {
         Ok(sub_suites)
     }
 
+    /// Look up the runtime history of the bazel test targets of the given task.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_name` - Name of task to look up.
+    /// * `build_variant` - Build variant the task runs on.
+    ///
+    /// # Returns
+    ///
+    /// Map of bazel test targets to their average runtime, empty if not available.
+    async fn lookup_bazel_target_history(
+        &self,
+        task_name: &str,
+        build_variant: &BuildVariant,
+    ) -> HashMap<String, f64> {
+        if self.use_task_split_fallback {
+            return HashMap::new();
+        }
+
+        match self
+            .task_history_service
+            .get_bazel_target_history(task_name, &build_variant.name)
+            .await
+        {
+            Ok(target_runtimes) => target_runtimes,
+            Err(err) => {
+                warn!(
+                    build_variant = build_variant.name.as_str(),
+                    task_name = task_name,
+                    error = err.to_string().as_str(),
+                    "Could not get bazel target history for burn_in, using default run count",
+                );
+                HashMap::new()
+            }
+        }
+    }
+
+    /// Build the burn_in sub-tasks repeating the changed bazel test targets of the given task.
+    ///
+    /// # Arguments
+    ///
+    /// * `discovered_task` - Task discovered to run changed bazel test targets.
+    /// * `task_def` - Evergreen project definition of task.
+    /// * `build_variant` - Build variant the task is defined on.
+    /// * `run_build_variant` - Name of build variant to run burn_in_tests task on.
+    /// * `target_runtimes` - Historic runtimes of the bazel test targets of the task.
+    ///
+    /// # Returns
+    ///
+    /// List of sub_tasks to include as part of burn_in_tests.
+    fn build_bazel_targets_for_task(
+        &self,
+        discovered_task: &DiscoveredTask,
+        task_def: &EvgTask,
+        build_variant: &BuildVariant,
+        run_build_variant: &str,
+        target_runtimes: &HashMap<String, f64>,
+    ) -> Result<Vec<GeneratedSubTask>> {
+        let params = self
+            .config_extraction_service
+            .task_def_to_bazel_shard_params(task_def, build_variant)?;
+        let burn_in_suite_info = BurnInSuiteInfo {
+            build_variant: run_build_variant,
+            total_tests: discovered_task.bazel_targets.len(),
+            task_name: &params.task_name,
+            burn_in_label: BURN_IN_LABEL,
+            multiversion_name: None,
+            multiversion_tags: None,
+        };
+        let display_name = burn_in_suite_info.build_display_name();
+
+        Ok(discovered_task
+            .bazel_targets
+            .iter()
+            .enumerate()
+            .map(|(index, target)| {
+                let average_runtime = target_runtimes.get(target).copied();
+                let runs = burn_in_bazel_runs(average_runtime);
+                let mut target_params = params.clone();
+                target_params.bazel_args =
+                    format!("{} --runs_per_test={}", params.bazel_args, runs)
+                        .trim()
+                        .to_string();
+                let sub_task_name = params.task_naming.sub_task_name(
+                    &display_name,
+                    index,
+                    burn_in_suite_info.total_tests,
+                    params.is_enterprise,
+                    params.platform.as_deref(),
+                );
+
+                GeneratedSubTask {
+                    evg_task: build_bazel_targets_sub_task(
+                        &sub_task_name,
+                        &[target.clone()],
+                        &target_params,
+                    ),
+                    expected_runtime_secs: average_runtime.map(|runtime| runtime * runs as f64),
+                    ..Default::default()
+                }
+            })
+            .collect())
+    }
+
     /// Build the burn_in_tasks for the given task.
     ///
     /// # Arguments
@@ -315,6 +525,7 @@ impl BurnInServiceImpl {
             mv_exclude_tags: suite_info.multiversion_tags.clone(),
             is_enterprise: false,
             platform: None,
+            expected_runtime: None,
         };
 
         self.gen_resmoke_task_service.build_resmoke_sub_task(
@@ -326,34 +537,7 @@ impl BurnInServiceImpl {
     }
 }
 
-/// A container for configuration generated for a burn_in_tags build variant.
-#[derive(Debug, Clone)]
-struct BurnInTagsGeneratedConfig {
-    /// Name of burn_in_tags build variant.
-    pub build_variant_name: String,
-    /// References to generated tasks that should be included.
-    pub gen_task_specs: Vec<TaskRef>,
-    /// Display name of burn_in_tags build variant.
-    pub build_variant_display_name: Option<String>,
-    /// Display tasks that should be created.
-    pub display_tasks: Vec<DisplayTask>,
-    /// Expansions that should be added to build variant.
-    pub expansions: BTreeMap<String, String>,
-}
-
-impl BurnInTagsGeneratedConfig {
-    /// Create an empty instance of generated configuration.
-    pub fn new() -> Self {
-        Self {
-            build_variant_name: String::new(),
-            gen_task_specs: vec![],
-            build_variant_display_name: Some(String::new()),
-            display_tasks: vec![],
-            expansions: BTreeMap::new(),
-        }
-    }
-}
-
+#[async_trait]
 impl BurnInService for BurnInServiceImpl {
     /// Generate the burn_in_tests task for the given build_variant.
     ///
@@ -366,22 +550,55 @@ impl BurnInService for BurnInServiceImpl {
     /// # Returns
     ///
     /// A generated suite to use for generating burn_in_tests.
-    fn generate_burn_in_suite(
+    async fn generate_burn_in_suite(
         &self,
         build_variant: &BuildVariant,
         run_build_variant_name: &str,
-        task_map: Arc<HashMap<String, EvgTask>>,
+        task_map: Arc<HashMap<String, Arc<EvgTask>>>,
     ) -> Result<Box<dyn GeneratedSuite>> {
         let mut sub_suites = vec![];
         let discovered_tasks = self.burn_in_discovery.discover_tasks(&build_variant.name)?;
         for discovered_task in discovered_tasks {
             let task_name = &discovered_task.task_name;
             if let Some(task_def) = task_map.get(task_name) {
-                sub_suites.extend(self.build_tests_for_task(
-                    &discovered_task,
-                    task_def,
-                    run_build_variant_name,
-                )?);
+                if !discovered_task.suites.is_empty() {
+                    let task_history = self
+                        .lookup_task_history(remove_gen_suffix(&task_def.name), build_variant)
+                        .await;
+                    sub_suites.extend(self.build_tests_for_task(
+                        &discovered_task,
+                        task_def,
+                        run_build_variant_name,
+                        task_history.as_ref(),
+                    )?);
+                }
+
+                if !discovered_task.bazel_targets.is_empty() {
+                    if self
+                        .evg_config_utils
+                        .get_gen_task_var(task_def, IS_BAZEL_TEST_SHARDS)
+                        != Some("true")
+                    {
+                        warn!(
+                            task_name = task_name.as_str(),
+                            "Skipping changed bazel test targets of task that does not run bazel test shards",
+                        );
+                        continue;
+                    }
+                    let target_runtimes = self
+                        .lookup_bazel_target_history(
+                            remove_gen_suffix(&task_def.name),
+                            build_variant,
+                        )
+                        .await;
+                    sub_suites.extend(self.build_bazel_targets_for_task(
+                        &discovered_task,
+                        task_def,
+                        build_variant,
+                        run_build_variant_name,
+                        &target_runtimes,
+                    )?);
+                }
             }
         }
 
@@ -399,6 +616,7 @@ impl BurnInService for BurnInServiceImpl {
     /// * `run_build_variant_name` - Build variant name to run burn_in_tests task on.
     /// * `generated_task` - Generated burn_in_tests task.
     /// * `compile_task_dependency` - Compile task name generated build variant should depend on.
+    /// * `in_base_variant` - Add the generated task to the base build variant instead of a clone.
     ///
     /// # Returns
     ///
@@ -409,60 +627,60 @@ impl BurnInService for BurnInServiceImpl {
         run_build_variant_name: String,
         generated_task: &dyn GeneratedSuite,
         compile_task_dependency: String,
+        in_base_variant: bool,
     ) -> Result<BuildVariant> {
-        let mut gen_config = BurnInTagsGeneratedConfig::new();
-
-        gen_config.build_variant_name = run_build_variant_name;
-        gen_config.build_variant_display_name = base_build_variant
-            .display_name
-            .as_ref()
-            .map(|s| format!("{} {}", BURN_IN_DISPLAY_NAME_PREFIX, s));
-
-        gen_config.expansions = base_build_variant.expansions.clone().unwrap_or_default();
-        gen_config.expansions.insert(
-            BURN_IN_BYPASS.to_string(),
-            base_build_variant.name.to_string(),
-        );
-
         let large_distro = self
             .config_extraction_service
-            .determine_large_distro(generated_task, base_build_variant)?;
+            .determine_distro_for_tier(generated_task, base_build_variant)?;
 
-        gen_config
-            .gen_task_specs
-            .extend(generated_task.build_task_ref(large_distro));
-        gen_config
-            .display_tasks
-            .push(generated_task.build_display_task());
+        if in_base_variant {
+            return Ok(BuildVariant {
+                name: base_build_variant.name.clone(),
+                tasks: generated_task.build_task_ref(large_distro, None, false),
+                display_tasks: Some(vec![generated_task.build_display_task()]),
+                ..Default::default()
+            });
+        }
 
         let compile_variant = self
             .evg_config_utils
             .lookup_build_variant_expansion(COMPILE_VARIANT, base_build_variant)
             .unwrap_or_else(|| base_build_variant.name.clone());
 
-        let variant_task_dependencies = vec![
-            TaskDependency {
-                name: compile_task_dependency,
-                variant: Some(compile_variant),
-            },
-            TaskDependency {
-                name: VERSION_BURN_IN_GEN_TASK.to_string(),
-                variant: Some(VERSION_GEN_VARIANT.to_string()),
-            },
-        ];
-
-        Ok(BuildVariant {
-            name: gen_config.build_variant_name.clone(),
-            tasks: gen_config.gen_task_specs.clone(),
-            display_name: gen_config.build_variant_display_name.clone(),
-            run_on: base_build_variant.run_on.clone(),
-            display_tasks: Some(gen_config.display_tasks.clone()),
-            modules: base_build_variant.modules.clone(),
-            expansions: Some(gen_config.expansions.clone()),
-            depends_on: Some(variant_task_dependencies.to_vec()),
-            activate: Some(false),
+        let mut depends_on = vec![TaskDependency {
+            name: compile_task_dependency,
+            variant: Some(compile_variant),
+        }];
+        if let Some(gen_task_dependency) = self.version_burn_in_gen_dependency() {
+            depends_on.push(gen_task_dependency);
+        }
+        depends_on.extend(
+            self.evg_config_utils
+                .lookup_and_split_by_whitespace_build_variant_expansion(
+                    BURN_IN_TAG_ADDITIONAL_DEPENDENCIES,
+                    base_build_variant,
+                )
+                .iter()
+                .map(|dependency| parse_task_dependency(dependency, &compile_variant)),
+        );
+
+        let clone_config = VariantCloneConfig {
+            display_name_prefix: Some(BURN_IN_DISPLAY_NAME_PREFIX.to_string()),
+            expansions: BTreeMap::from([(
+                BURN_IN_BYPASS.to_string(),
+                base_build_variant.name.to_string(),
+            )]),
+            depends_on,
             ..Default::default()
-        })
+        };
+
+        Ok(self.variant_cloning_service.clone_build_variant(
+            base_build_variant,
+            &run_build_variant_name,
+            &clone_config,
+            generated_task.build_task_ref(large_distro, None, false),
+            vec![generated_task.build_display_task()],
+        ))
     }
 
     /// Generate a burn_in_tasks task for the given build variant.
@@ -478,7 +696,7 @@ impl BurnInService for BurnInServiceImpl {
     fn generate_burn_in_tasks_suite(
         &self,
         build_variant: &BuildVariant,
-        task_map: Arc<HashMap<String, EvgTask>>,
+        task_map: Arc<HashMap<String, Arc<EvgTask>>>,
     ) -> Result<Box<dyn GeneratedSuite>> {
         let mut sub_suites = vec![];
 
@@ -503,36 +721,119 @@ impl BurnInService for BurnInServiceImpl {
     }
 }
 
+/// Parse a `task@variant` dependency.
+///
+/// # Arguments
+///
+/// * `dependency` - Dependency to parse.
+/// * `default_variant` - Variant to use if the dependency does not specify one.
+///
+/// # Returns
+///
+/// Task dependency described by the given string.
+fn parse_task_dependency(dependency: &str, default_variant: &str) -> TaskDependency {
+    let (name, variant) = match dependency.split_once('@') {
+        Some((name, variant)) => (name, variant),
+        None => (dependency, default_variant),
+    };
+    TaskDependency {
+        name: name.to_string(),
+        variant: Some(variant.to_string()),
+    }
+}
+
 /// Update the given resmoke parameters to include burn_in configuration for the given test.
 ///
 /// # Arguments
 ///
 /// * `params` - resmoke parameters to update.
 /// * `test_name` - Name of test to run.
-fn update_resmoke_params_for_burn_in(params: &mut ResmokeGenParams, test_name: &str) {
+/// * `average_runtime` - Historic average runtime of the test in seconds, if known.
+fn update_resmoke_params_for_burn_in(
+    params: &mut ResmokeGenParams,
+    test_name: &str,
+    average_runtime: Option<f64>,
+) {
     params.resmoke_args = format!(
         "{} {} {}",
-        params.resmoke_args, BURN_IN_REPEAT_CONFIG, test_name
+        params.resmoke_args,
+        burn_in_repeat_config(average_runtime),
+        test_name
     );
 }
 
+/// Determine how many times to run a bazel test target during burn_in.
+///
+/// When the runtime of the target is known, the target is run as many times as fit in the
+/// target runtime of a sub_task. Otherwise, it is run a fixed number of times.
+///
+/// # Arguments
+///
+/// * `average_runtime` - Historic average runtime of the target in seconds, if known.
+///
+/// # Returns
+///
+/// Number of times to run the target.
+fn burn_in_bazel_runs(average_runtime: Option<f64>) -> u64 {
+    match average_runtime {
+        Some(runtime) if runtime > 0.0 => (BURN_IN_TARGET_RUNTIME_SECS / runtime)
+            .floor()
+            .clamp(1.0, BURN_IN_MAX_REPEATS) as u64,
+        _ => BURN_IN_DEFAULT_BAZEL_RUNS,
+    }
+}
+
+/// Determine the resmoke options to repeat a test during burn_in.
+///
+/// When the runtime of the test is known, the test is repeated as many times as fit in the
+/// target runtime of a sub_task. Otherwise, resmoke repeats the test for a fixed amount of time.
+///
+/// # Arguments
+///
+/// * `average_runtime` - Historic average runtime of the test in seconds, if known.
+///
+/// # Returns
+///
+/// Options to pass to resmoke to repeat the test.
+fn burn_in_repeat_config(average_runtime: Option<f64>) -> String {
+    match average_runtime {
+        Some(runtime) if runtime > 0.0 => {
+            let repeats = (BURN_IN_TARGET_RUNTIME_SECS / runtime)
+                .floor()
+                .clamp(1.0, BURN_IN_MAX_REPEATS);
+            format!("--repeatTests={}", repeats as u64)
+        }
+        _ => BURN_IN_REPEAT_CONFIG.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use async_trait::async_trait;
+    use std::sync::Mutex;
+
     use maplit::{btreemap, hashmap};
     use rstest::rstest;
     use shrub_rs::models::{
-        commands::{fn_call, fn_call_with_params},
+        commands::{fn_call, fn_call_with_params, EvgCommand},
         params::ParamValue,
         variant::BuildVariant,
     };
 
     use crate::{
-        evergreen::evg_config_utils::{EvgConfigUtilsImpl, MultiversionGenerateTaskConfig},
+        evergreen::{
+            evg_config_utils::{EvgConfigUtilsImpl, MultiversionGenerateTaskConfig},
+            evg_task_history::HistoryFetchStats,
+        },
         evergreen_names::{GENERATE_RESMOKE_TASKS, INITIALIZE_MULTIVERSION_TASKS},
-        resmoke::burn_in_proxy::DiscoveredSuite,
-        services::config_extraction::ConfigExtractionServiceImpl,
-        task_types::{fuzzer_tasks::FuzzerGenTaskParams, multiversion::MultiversionService},
+        resmoke::burn_in_proxy::{BuildVariantDiscovery, DiscoveredSuite},
+        services::{
+            config_extraction::{ConfigExtractionServiceImpl, SubTaskLimits},
+            variant_cloning::VariantCloningServiceImpl,
+        },
+        task_types::{
+            antithesis_tasks::AntithesisGenTaskParams, bazel_shard_tasks::BazelShardGenParams,
+            fuzzer_tasks::FuzzerGenTaskParams, multiversion::MultiversionService,
+        },
     };
 
     use super::*;
@@ -613,15 +914,99 @@ mod tests {
             "generating_task".to_string(),
             "config_location".to_string(),
             None,
+            false,
         )
     }
 
     // Mocks
+    struct MockTaskHistoryService {}
+    #[async_trait]
+    impl TaskHistoryService for MockTaskHistoryService {
+        fn build_url(&self, _task: &str, _variant: &str) -> String {
+            todo!()
+        }
+
+        async fn get_task_history(
+            &self,
+            _task: &str,
+            _variant: &str,
+        ) -> Result<TaskRuntimeHistory> {
+            todo!()
+        }
+
+        async fn get_bazel_target_history(
+            &self,
+            _task: &str,
+            _variant: &str,
+        ) -> Result<HashMap<String, f64>> {
+            todo!()
+        }
+
+        async fn prefetch_task_histories(&self, _task_variants: Vec<(String, String)>) {
+            todo!()
+        }
+
+        fn fetch_stats(&self) -> HistoryFetchStats {
+            todo!()
+        }
+    }
+
+    struct RecordingTaskHistoryService {
+        pub queried_tasks: Mutex<Vec<String>>,
+    }
+    #[async_trait]
+    impl TaskHistoryService for RecordingTaskHistoryService {
+        fn build_url(&self, _task: &str, _variant: &str) -> String {
+            todo!()
+        }
+
+        async fn get_task_history(&self, task: &str, _variant: &str) -> Result<TaskRuntimeHistory> {
+            self.queried_tasks.lock().unwrap().push(task.to_string());
+            Ok(TaskRuntimeHistory {
+                task_name: task.to_string(),
+                test_map: HashMap::new(),
+            })
+        }
+
+        async fn get_bazel_target_history(
+            &self,
+            _task: &str,
+            _variant: &str,
+        ) -> Result<HashMap<String, f64>> {
+            todo!()
+        }
+
+        async fn prefetch_task_histories(&self, _task_variants: Vec<(String, String)>) {
+            todo!()
+        }
+
+        fn fetch_stats(&self) -> HistoryFetchStats {
+            todo!()
+        }
+    }
+
     struct MockBurnInDiscovery {}
     impl BurnInDiscovery for MockBurnInDiscovery {
         fn discover_tasks(&self, _build_variant: &str) -> Result<Vec<DiscoveredTask>> {
             todo!()
         }
+
+        fn discovery_report(&self) -> BTreeMap<String, BuildVariantDiscovery> {
+            todo!()
+        }
+    }
+
+    struct StaticBurnInDiscovery {
+        pub discovered_tasks: Vec<DiscoveredTask>,
+    }
+    impl BurnInDiscovery for StaticBurnInDiscovery {
+        fn discover_tasks(&self, _build_variant: &str) -> Result<Vec<DiscoveredTask>> {
+            Ok(self.discovered_tasks.clone())
+        }
+
+        fn discovery_report(&self) -> BTreeMap<String, BuildVariantDiscovery> {
+            todo!()
+        }
     }
 
     struct MockGenResmokeTasksService {}
@@ -649,6 +1034,13 @@ mod tests {
                 ..Default::default()
             }
         }
+
+        fn build_unsplit_resmoke_task(
+            &self,
+            _params: &ResmokeGenParams,
+        ) -> Box<dyn GeneratedSuite> {
+            todo!()
+        }
     }
 
     struct MockConfigExtractionService {
@@ -676,13 +1068,48 @@ mod tests {
             })
         }
 
-        fn determine_large_distro(
+        fn task_def_to_antithesis_params(
+            &self,
+            _task_def: &EvgTask,
+            _build_variant: &BuildVariant,
+        ) -> Result<AntithesisGenTaskParams> {
+            todo!()
+        }
+
+        fn task_def_to_bazel_shard_params(
+            &self,
+            task_def: &EvgTask,
+            _build_variant: &BuildVariant,
+        ) -> Result<BazelShardGenParams> {
+            Ok(BazelShardGenParams {
+                task_name: task_def.name.clone(),
+                bazel_args: "--config=dbg".to_string(),
+                ..Default::default()
+            })
+        }
+
+        fn determine_distro_for_tier(
             &self,
             _generated_task: &dyn GeneratedSuite,
             _build_variant: &BuildVariant,
         ) -> Result<Option<String>> {
             Ok(None)
         }
+
+        fn determine_sub_task_limits(
+            &self,
+            _build_variant: &BuildVariant,
+        ) -> Result<SubTaskLimits> {
+            todo!()
+        }
+
+        fn determine_multiversion_generate_tasks(
+            &self,
+            _task_def: &EvgTask,
+            _build_variant: &BuildVariant,
+        ) -> Option<Vec<MultiversionGenerateTaskConfig>> {
+            todo!()
+        }
     }
 
     struct MockMultiversionService {}
@@ -694,13 +1121,19 @@ mod tests {
             &self,
             multiversion_generate_tasks: Option<Vec<MultiversionGenerateTaskConfig>>,
             _last_versions_expansion: Option<String>,
+            _excluded_versions_expansion: Option<String>,
         ) -> Option<Vec<MultiversionGenerateTaskConfig>> {
             return multiversion_generate_tasks;
         }
+
+        fn old_versions(&self) -> Vec<String> {
+            todo!()
+        }
     }
 
     struct MockEvgConfigUtils {
         burn_in_task_name: Option<String>,
+        project_expansions: HashMap<String, String>,
     }
     impl EvgConfigUtils for MockEvgConfigUtils {
         fn get_multiversion_generate_tasks(
@@ -757,12 +1190,21 @@ mod tests {
             self.burn_in_task_name.clone()
         }
 
+        fn lookup_project_expansion(&self, name: &str) -> Option<String> {
+            self.project_expansions.get(name).cloned()
+        }
+
         fn lookup_and_split_by_whitespace_build_variant_expansion(
             &self,
-            _name: &str,
-            _build_variant: &BuildVariant,
+            name: &str,
+            build_variant: &BuildVariant,
         ) -> Vec<String> {
-            todo!()
+            build_variant
+                .expansions
+                .as_ref()
+                .and_then(|expansions| expansions.get(name))
+                .map(|value| value.split_whitespace().map(|s| s.to_string()).collect())
+                .unwrap_or_default()
         }
 
         fn resolve_burn_in_tag_build_variants(
@@ -821,13 +1263,26 @@ mod tests {
     }
 
     fn build_mocked_service(burn_in_task_name: Option<String>) -> BurnInServiceImpl {
+        build_mocked_service_with_project_expansions(burn_in_task_name, HashMap::new())
+    }
+
+    fn build_mocked_service_with_project_expansions(
+        burn_in_task_name: Option<String>,
+        project_expansions: HashMap<String, String>,
+    ) -> BurnInServiceImpl {
         BurnInServiceImpl::new(
             Arc::new(MockBurnInDiscovery {}),
             Arc::new(MockGenResmokeTasksService {}),
             Arc::new(MockConfigExtractionService {
                 is_multiversion: false,
             }),
-            Arc::new(MockEvgConfigUtils { burn_in_task_name }),
+            Arc::new(MockEvgConfigUtils {
+                burn_in_task_name,
+                project_expansions,
+            }),
+            Arc::new(VariantCloningServiceImpl::new()),
+            Arc::new(MockTaskHistoryService {}),
+            false,
         )
     }
 
@@ -836,7 +1291,13 @@ mod tests {
             Arc::new(MockBurnInDiscovery {}),
             Arc::new(MockGenResmokeTasksService {}),
             Arc::new(build_mocked_config_extraction_service()),
-            Arc::new(MockEvgConfigUtils { burn_in_task_name }),
+            Arc::new(MockEvgConfigUtils {
+                burn_in_task_name,
+                project_expansions: HashMap::new(),
+            }),
+            Arc::new(VariantCloningServiceImpl::new()),
+            Arc::new(MockTaskHistoryService {}),
+            false,
         )
     }
 
@@ -849,6 +1310,7 @@ mod tests {
                 suite_name: "my suite".to_string(),
                 test_list: vec!["test_0.js".to_string(), "test_1.js".to_string()],
             }],
+            bazel_targets: vec![],
         };
         let task_def = EvgTask {
             ..Default::default()
@@ -857,7 +1319,7 @@ mod tests {
         let burn_in_service = build_mocked_service(None);
 
         let tasks = burn_in_service
-            .build_tests_for_task(&discovered_task, &task_def, run_build_variant)
+            .build_tests_for_task(&discovered_task, &task_def, run_build_variant, None)
             .unwrap();
 
         assert_eq!(tasks.len(), discovered_task.suites[0].test_list.len());
@@ -885,6 +1347,7 @@ mod tests {
                     test_list: vec!["test_0.js".to_string(), "test_1.js".to_string()],
                 },
             ],
+            bazel_targets: vec![],
         };
         let vars = hashmap! {
             "mv_suite1_last_continuous_new_old_new".to_string() => ParamValue::from("last-continuous"),
@@ -906,7 +1369,7 @@ mod tests {
         let burn_in_service = build_mv_mocked_service(None);
 
         let tasks = burn_in_service
-            .build_tests_for_task(&discovered_task, &task_def, run_build_variant)
+            .build_tests_for_task(&discovered_task, &task_def, run_build_variant, None)
             .unwrap();
 
         assert_eq!(tasks.len(), 8);
@@ -926,6 +1389,7 @@ mod tests {
                     test_list: vec!["test_0.js".to_string(), "test_1.js".to_string()],
                 },
             ],
+            bazel_targets: vec![],
         };
         let vars = hashmap! {
             "mv_suite1_last_continuous_new_old_new".to_string() => ParamValue::from("last-continuous"),
@@ -947,12 +1411,88 @@ mod tests {
         let burn_in_service = build_mv_mocked_service(None);
 
         let tasks = burn_in_service
-            .build_tests_for_task(&discovered_task, &task_def, run_build_variant)
+            .build_tests_for_task(&discovered_task, &task_def, run_build_variant, None)
             .unwrap();
 
         assert_eq!(tasks.len(), 4);
     }
 
+    // burn_in_repeat_config tests.
+    #[rstest]
+    #[case(None, BURN_IN_REPEAT_CONFIG)]
+    #[case(Some(0.0), BURN_IN_REPEAT_CONFIG)]
+    #[case(Some(60.0), "--repeatTests=20")]
+    #[case(Some(500.0), "--repeatTests=2")]
+    #[case(Some(3600.0), "--repeatTests=1")]
+    #[case(Some(0.1), "--repeatTests=1000")]
+    fn test_burn_in_repeat_config(#[case] average_runtime: Option<f64>, #[case] expected: &str) {
+        assert_eq!(burn_in_repeat_config(average_runtime), expected);
+    }
+
+    // burn_in_bazel_runs tests.
+    #[rstest]
+    #[case(None, BURN_IN_DEFAULT_BAZEL_RUNS)]
+    #[case(Some(0.0), BURN_IN_DEFAULT_BAZEL_RUNS)]
+    #[case(Some(60.0), 20)]
+    #[case(Some(3600.0), 1)]
+    #[case(Some(0.1), 1000)]
+    fn test_burn_in_bazel_runs(#[case] average_runtime: Option<f64>, #[case] expected: u64) {
+        assert_eq!(burn_in_bazel_runs(average_runtime), expected);
+    }
+
+    // build_bazel_targets_for_task tests.
+    #[test]
+    fn test_build_bazel_targets_for_task_creates_task_for_each_target() {
+        let discovered_task = DiscoveredTask {
+            task_name: "unittests".to_string(),
+            suites: vec![],
+            bazel_targets: vec![
+                "//src/mongo/db:db_test".to_string(),
+                "//src/mongo/s:s_test".to_string(),
+            ],
+        };
+        let task_def = EvgTask {
+            name: "unittests".to_string(),
+            ..Default::default()
+        };
+        let build_variant = BuildVariant {
+            ..Default::default()
+        };
+        let target_runtimes = hashmap! {
+            "//src/mongo/db:db_test".to_string() => 60.0,
+        };
+        let burn_in_service = build_mocked_service(None);
+
+        let tasks = burn_in_service
+            .build_bazel_targets_for_task(
+                &discovered_task,
+                &task_def,
+                &build_variant,
+                "my_build_variant",
+                &target_runtimes,
+            )
+            .unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(
+            tasks[0].evg_task.name,
+            "burn_in:unittests-my_build_variant_0"
+        );
+        assert_eq!(tasks[0].expected_runtime_secs, Some(1200.0));
+        assert_eq!(tasks[1].expected_runtime_secs, None);
+        let commands = tasks[1].evg_task.commands.as_ref().unwrap();
+        if let EvgCommand::Function(func) = &commands[2] {
+            let vars = func.vars.as_ref().unwrap();
+            assert_eq!(vars["targets"], ParamValue::from("//src/mongo/s:s_test"));
+            assert_eq!(
+                vars["bazel_args"],
+                ParamValue::from("--config=dbg --runs_per_test=20")
+            );
+        } else {
+            panic!("Expected function call to run the targets");
+        }
+    }
+
     // build_burn_in_tasks_for_task tests.
     #[test]
     fn test_build_burn_in_tasks_for_task_creates_tasks() {
@@ -1035,6 +1575,7 @@ mod tests {
                 run_build_variant_name,
                 generated_task,
                 compile_task_dependency,
+                false,
             )
             .unwrap();
 
@@ -1070,6 +1611,137 @@ mod tests {
         assert_eq!(burn_in_tags_build_variant.tasks[0].name, "sub_suite_name");
     }
 
+    #[test]
+    fn test_generate_burn_in_tags_build_variant_should_add_additional_dependencies() {
+        let base_build_variant = BuildVariant {
+            name: "base-build-variant-name".to_string(),
+            expansions: Some(btreemap! {
+                BURN_IN_TAG_ADDITIONAL_DEPENDENCIES.to_string() => "archive_dist_test_debug symbolize@other-variant".to_string(),
+            }),
+            ..Default::default()
+        };
+        let generated_task: &dyn GeneratedSuite = &GeneratedResmokeSuite {
+            task_name: "display_task_name".to_string(),
+            sub_suites: vec![],
+        };
+        let burn_in_service = build_mocked_service(None);
+
+        let burn_in_tags_build_variant = burn_in_service
+            .generate_burn_in_tags_build_variant(
+                &base_build_variant,
+                "run-build-variant-name".to_string(),
+                generated_task,
+                "compile".to_string(),
+                false,
+            )
+            .unwrap();
+
+        let dependencies: Vec<(String, Option<String>)> = burn_in_tags_build_variant
+            .depends_on
+            .unwrap_or_default()
+            .into_iter()
+            .map(|d| (d.name, d.variant))
+            .collect();
+        assert!(dependencies.contains(&(
+            "archive_dist_test_debug".to_string(),
+            Some("base-build-variant-name".to_string())
+        )));
+        assert!(
+            dependencies.contains(&("symbolize".to_string(), Some("other-variant".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_generate_burn_in_tags_build_variant_in_base_variant() {
+        let base_build_variant = BuildVariant {
+            name: "base-build-variant-name".to_string(),
+            display_name: Some("base build variant display name".to_string()),
+            run_on: Some(vec!["base_distro_name".to_string()]),
+            ..Default::default()
+        };
+        let generated_task: &dyn GeneratedSuite = &GeneratedResmokeSuite {
+            task_name: "display_task_name".to_string(),
+            sub_suites: vec![GeneratedSubTask {
+                evg_task: EvgTask {
+                    name: "sub_suite_name".to_string(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }],
+        };
+        let burn_in_service = build_mocked_service(None);
+
+        let burn_in_tags_build_variant = burn_in_service
+            .generate_burn_in_tags_build_variant(
+                &base_build_variant,
+                "run-build-variant-name".to_string(),
+                generated_task,
+                "compile".to_string(),
+                true,
+            )
+            .unwrap();
+
+        assert_eq!(burn_in_tags_build_variant.name, "base-build-variant-name");
+        assert_eq!(burn_in_tags_build_variant.display_name, None);
+        assert_eq!(burn_in_tags_build_variant.run_on, None);
+        assert_eq!(burn_in_tags_build_variant.depends_on, None);
+        assert_eq!(burn_in_tags_build_variant.tasks[0].name, "sub_suite_name");
+        assert_eq!(
+            burn_in_tags_build_variant.display_tasks.unwrap_or_default()[0].name,
+            "display_task_name"
+        );
+    }
+
+    // parse_task_dependency tests.
+    #[rstest]
+    #[case("archive", "archive", "default-variant")]
+    #[case("archive@bv", "archive", "bv")]
+    fn test_parse_task_dependency(
+        #[case] dependency: &str,
+        #[case] expected_name: &str,
+        #[case] expected_variant: &str,
+    ) {
+        let task_dependency = parse_task_dependency(dependency, "default-variant");
+
+        assert_eq!(task_dependency.name, expected_name);
+        assert_eq!(task_dependency.variant, Some(expected_variant.to_string()));
+    }
+
+    // version_burn_in_gen_dependency tests.
+    #[rstest]
+    #[case(None, None, Some((VERSION_BURN_IN_GEN_TASK, VERSION_GEN_VARIANT)))]
+    #[case(Some("burn_in_gen"), None, Some(("burn_in_gen", VERSION_GEN_VARIANT)))]
+    #[case(Some("burn_in_gen"), Some("gen-variant"), Some(("burn_in_gen", "gen-variant")))]
+    #[case(Some(""), Some("gen-variant"), None)]
+    fn test_version_burn_in_gen_dependency(
+        #[case] gen_task: Option<&str>,
+        #[case] gen_variant: Option<&str>,
+        #[case] expected: Option<(&str, &str)>,
+    ) {
+        let mut project_expansions = HashMap::new();
+        if let Some(gen_task) = gen_task {
+            project_expansions.insert(
+                BURN_IN_TAG_GEN_TASK_DEPENDENCY.to_string(),
+                gen_task.to_string(),
+            );
+        }
+        if let Some(gen_variant) = gen_variant {
+            project_expansions.insert(
+                BURN_IN_TAG_GEN_VARIANT_DEPENDENCY.to_string(),
+                gen_variant.to_string(),
+            );
+        }
+        let burn_in_service =
+            build_mocked_service_with_project_expansions(None, project_expansions);
+
+        let dependency = burn_in_service.version_burn_in_gen_dependency();
+
+        assert_eq!(
+            dependency.map(|d| (d.name, d.variant.unwrap())),
+            expected.map(|(name, variant)| (name.to_string(), variant.to_string()))
+        );
+    }
+
     // generate_burn_in_tasks_suite tests.
     #[rstest]
     #[case(Some("task_1".to_string()), BURN_IN_REPEAT_TASK_NUM)]
@@ -1086,12 +1758,12 @@ mod tests {
             ..Default::default()
         };
         let task_map = Arc::new(hashmap! {
-            "task_1".to_string() => EvgTask {
+            "task_1".to_string() => Arc::new(EvgTask {
                 ..Default::default()
-            },
-            "task_2".to_string() => EvgTask {
+            }),
+            "task_2".to_string() => Arc::new(EvgTask {
                 ..Default::default()
-            },
+            }),
         });
         let burn_in_service = build_mocked_service(burn_in_task_name);
 
@@ -1101,4 +1773,56 @@ mod tests {
 
         assert_eq!(suite.sub_tasks().len(), expected_num_tasks);
     }
+
+    // generate_burn_in_suite tests.
+    #[tokio::test]
+    async fn test_generate_burn_in_suite_should_look_up_history_without_gen_suffix() {
+        let build_variant = BuildVariant {
+            name: "bv_name".to_string(),
+            ..Default::default()
+        };
+        let task_map = Arc::new(hashmap! {
+            "my_task_gen".to_string() => Arc::new(EvgTask {
+                name: "my_task_gen".to_string(),
+                ..Default::default()
+            }),
+        });
+        let task_history_service = Arc::new(RecordingTaskHistoryService {
+            queried_tasks: Mutex::new(vec![]),
+        });
+        let burn_in_service = BurnInServiceImpl::new(
+            Arc::new(StaticBurnInDiscovery {
+                discovered_tasks: vec![DiscoveredTask {
+                    task_name: "my_task_gen".to_string(),
+                    suites: vec![DiscoveredSuite {
+                        suite_name: "my_suite".to_string(),
+                        test_list: vec!["test_0.js".to_string()],
+                    }],
+                    bazel_targets: vec![],
+                }],
+            }),
+            Arc::new(MockGenResmokeTasksService {}),
+            Arc::new(MockConfigExtractionService {
+                is_multiversion: false,
+            }),
+            Arc::new(MockEvgConfigUtils {
+                burn_in_task_name: None,
+                project_expansions: HashMap::new(),
+            }),
+            Arc::new(VariantCloningServiceImpl::new()),
+            task_history_service.clone(),
+            false,
+        );
+
+        let suite = burn_in_service
+            .generate_burn_in_suite(&build_variant, "bv_name", task_map)
+            .await
+            .unwrap();
+
+        assert_eq!(suite.sub_tasks().len(), 1);
+        assert_eq!(
+            *task_history_service.queried_tasks.lock().unwrap(),
+            vec!["my_task".to_string()]
+        );
+    }
 }
@@ -1,27 +1,34 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use anyhow::Result;
+use async_trait::async_trait;
 use maplit::hashmap;
 use shrub_rs::models::{
-    commands::{fn_call, fn_call_with_params},
+    commands::{fn_call, fn_call_with_params, EvgCommand},
     params::ParamValue,
     task::{EvgTask, TaskDependency},
+    variant::BuildVariant,
 };
 use tracing::{event, Level};
 
 use crate::{
-    evergreen::evg_config_utils::MultiversionGenerateTaskConfig,
+    evergreen::evg_config_utils::{EvgConfigUtils, MultiversionGenerateTaskConfig},
     evergreen_names::{
-        ADD_GIT_TAG, CONFIGURE_EVG_API_CREDS, CONTINUE_ON_FAILURE, DO_MULTIVERSION_SETUP, DO_SETUP,
-        FUZZER_PARAMETERS, GEN_TASK_CONFIG_LOCATION, GET_PROJECT_WITH_NO_MODULES, IDLE_TIMEOUT,
-        MULTIVERSION_EXCLUDE_TAGS, NPM_COMMAND, REQUIRE_MULTIVERSION_SETUP, RESMOKE_ARGS,
-        RESMOKE_JOBS_MAX, RUN_FUZZER, RUN_GENERATED_TESTS, SETUP_JSTESTFUZZ, SHOULD_SHUFFLE_TESTS,
-        SUITE_NAME, TASK_NAME,
+        ADD_GIT_TAG, CONFIGURE_EVG_API_CREDS, CONTINUE_ON_FAILURE, DIFFERENTIAL_FUZZER_OLD_VERSION,
+        DO_MULTIVERSION_SETUP, DO_SETUP, EXEC_TIMEOUT, FUZZER_PARAMETERS, GEN_TASK_CONFIG_LOCATION,
+        GET_PROJECT_WITH_NO_MODULES, IDLE_TIMEOUT, MULTIVERSION, MULTIVERSION_EXCLUDE_TAGS,
+        MULTIVERSION_LAST_LTS, NPM_COMMAND, NUM_FUZZER_TASKS, REQUIRE_MULTIVERSION_SETUP,
+        RESMOKE_ARGS, RESMOKE_JOBS_MAX, SHOULD_SHUFFLE_TESTS, SUITE_NAME, TASK_NAME,
     },
-    utils::task_name::name_generated_task,
+    generate_sub_tasks_config::{CommandHooks, FunctionNames},
+    services::config_extraction::{ConfigExtractionService, SubTaskDemand},
+    utils::task_name::TaskNamingPolicy,
 };
 
-use super::generated_suite::{GeneratedSubTask, GeneratedSuite};
+use super::{
+    generated_suite::{build_origin_tags, sub_task_exec_timeout, GeneratedSubTask, GeneratedSuite},
+    generated_task_kind::GeneratedTaskKind,
+};
 
 /// Parameters for how a fuzzer task should be generated.
 #[derive(Default, Debug, Clone)]
@@ -52,8 +59,12 @@ pub struct FuzzerGenTaskParams {
     pub should_shuffle: bool,
     /// Timeout before test execution is considered hung.
     pub timeout_secs: u64,
+    /// Timeout before test execution is considered to have run too long.
+    pub exec_timeout_secs: Option<u64>,
     /// Requires downloading multiversion binaries.
     pub require_multiversion_setup: bool,
+    /// Compare results of the current version against the last-lts version.
+    pub is_differential: bool,
     /// Location of generated task configuration.
     pub config_location: String,
     /// List of tasks generated sub-tasks should depend on.
@@ -64,6 +75,26 @@ pub struct FuzzerGenTaskParams {
     pub platform: Option<String>,
     /// Name of variant specific suffix to add to tasks
     pub gen_task_suffix: Option<String>,
+    /// Priority to give generated sub-tasks.
+    pub priority: Option<u64>,
+    /// Should generated sub-tasks be scheduled in patch builds.
+    pub patchable: Option<bool>,
+    /// Should generated sub-tasks be disabled.
+    pub disable: Option<bool>,
+    /// Requesters that are allowed to schedule generated sub-tasks.
+    pub allowed_requesters: Option<Vec<String>>,
+    /// Exec timeout of the task definition, given to generated sub-tasks.
+    pub task_exec_timeout_secs: Option<u64>,
+    /// Divide the exec timeout of the task definition between the generated sub-tasks.
+    pub scale_exec_timeout: bool,
+    /// Should generated sub-tasks opt out of stepback.
+    pub disable_stepback: bool,
+    /// Functions to call around the commands of generated sub-tasks.
+    pub command_hooks: CommandHooks,
+    /// Names of the functions sub-tasks call to run their tests.
+    pub function_names: FunctionNames,
+    /// Conventions for naming generated sub-tasks.
+    pub task_naming: TaskNamingPolicy,
 }
 
 impl FuzzerGenTaskParams {
@@ -80,6 +111,14 @@ impl FuzzerGenTaskParams {
         self.require_multiversion_setup
     }
 
+    /// Determine if the generated sub-tasks need multiversion binaries to be setup.
+    ///
+    /// Differential fuzzers need the previous version's binaries to compare against, even though
+    /// they do not generate multiversion sub-tasks.
+    fn requires_multiversion_binaries(&self) -> bool {
+        self.is_multiversion() || self.is_differential
+    }
+
     /// Build the vars to send to tasks in the 'run tests' function.
     ///
     /// # Arguments
@@ -98,7 +137,7 @@ impl FuzzerGenTaskParams {
         let mut vars = hashmap! {
             CONTINUE_ON_FAILURE.to_string() => ParamValue::from(self.continue_on_failure),
             GEN_TASK_CONFIG_LOCATION.to_string() => ParamValue::from(self.config_location.as_str()),
-            REQUIRE_MULTIVERSION_SETUP.to_string() => ParamValue::from(self.is_multiversion()),
+            REQUIRE_MULTIVERSION_SETUP.to_string() => ParamValue::from(self.requires_multiversion_binaries()),
             RESMOKE_ARGS.to_string() => ParamValue::from(self.resmoke_args.as_str()),
             RESMOKE_JOBS_MAX.to_string() => ParamValue::from(self.resmoke_jobs_max),
             SHOULD_SHUFFLE_TESTS.to_string() => ParamValue::from(self.should_shuffle),
@@ -120,6 +159,25 @@ impl FuzzerGenTaskParams {
                 MULTIVERSION_EXCLUDE_TAGS.to_string(),
                 ParamValue::from(old_version),
             );
+        } else if self.is_differential {
+            vars.insert(
+                MULTIVERSION_EXCLUDE_TAGS.to_string(),
+                ParamValue::from(MULTIVERSION_LAST_LTS),
+            );
+        }
+
+        if self.is_differential {
+            vars.insert(
+                DIFFERENTIAL_FUZZER_OLD_VERSION.to_string(),
+                ParamValue::from(MULTIVERSION_LAST_LTS),
+            );
+        }
+
+        if let Some(exec_timeout_secs) = self.exec_timeout_secs {
+            vars.insert(
+                EXEC_TIMEOUT.to_string(),
+                ParamValue::from(exec_timeout_secs),
+            );
         }
 
         vars
@@ -171,6 +229,8 @@ impl GeneratedSuite for FuzzerTask {
                 evg_task: sub_task,
                 use_large_distro: false,
                 use_xlarge_distro: false,
+                distro_tier: None,
+                expected_runtime_secs: None,
             })
             .collect()
     }
@@ -209,6 +269,13 @@ impl GenFuzzerService for GenFuzzerServiceImpl {
     ) -> Result<Box<dyn GeneratedSuite>> {
         let task_name = &params.task_name;
         let mut sub_tasks: Vec<EvgTask> = vec![];
+        if params.is_differential {
+            event!(
+                Level::INFO,
+                task_name = task_name.as_str(),
+                "Generating differential fuzzer"
+            );
+        }
         if params.is_multiversion() {
             event!(
                 Level::INFO,
@@ -243,6 +310,107 @@ impl GenFuzzerService for GenFuzzerServiceImpl {
     }
 }
 
+/// Generated task kind for fuzzer tasks.
+pub struct FuzzerTaskKind {
+    /// Utilities to work with evergreen project configuration.
+    evg_config_utils: Arc<dyn EvgConfigUtils>,
+    /// Service to extraction configuration from evergreen config.
+    config_extraction_service: Arc<dyn ConfigExtractionService>,
+    /// Service to generate fuzzer tasks.
+    gen_fuzzer_service: Arc<dyn GenFuzzerService>,
+}
+
+impl FuzzerTaskKind {
+    /// Name of this kind of generated task.
+    pub const NAME: &'static str = "fuzzer";
+
+    /// Create a new instance of the fuzzer task kind.
+    ///
+    /// # Arguments
+    ///
+    /// * `evg_config_utils` - Utilities to work with evergreen project configuration.
+    /// * `config_extraction_service` - Service to extraction configuration from evergreen config.
+    /// * `gen_fuzzer_service` - Service to generate fuzzer tasks.
+    pub fn new(
+        evg_config_utils: Arc<dyn EvgConfigUtils>,
+        config_extraction_service: Arc<dyn ConfigExtractionService>,
+        gen_fuzzer_service: Arc<dyn GenFuzzerService>,
+    ) -> Self {
+        Self {
+            evg_config_utils,
+            config_extraction_service,
+            gen_fuzzer_service,
+        }
+    }
+}
+
+#[async_trait]
+impl GeneratedTaskKind for FuzzerTaskKind {
+    /// Name of this kind of generated task.
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    /// Fuzzers generate the number of sub-tasks set by their task definition, once for each
+    /// multiversion configuration of multiversion fuzzers.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition to estimate.
+    /// * `build_variant` - Build variant the task is generated for.
+    ///
+    /// # Returns
+    ///
+    /// Sub-tasks the generated fuzzer is expected to need.
+    fn sub_task_demand(&self, task_def: &EvgTask, build_variant: &BuildVariant) -> SubTaskDemand {
+        let num_tasks = self
+            .evg_config_utils
+            .lookup_optional_param_u64(task_def, NUM_FUZZER_TASKS)
+            .ok()
+            .flatten()
+            .unwrap_or(1) as usize;
+        let num_configs = if self
+            .evg_config_utils
+            .get_task_tags(task_def)
+            .contains(MULTIVERSION)
+        {
+            self.config_extraction_service
+                .determine_multiversion_generate_tasks(task_def, build_variant)
+                .map_or(1, |configs| configs.len())
+        } else {
+            1
+        };
+
+        SubTaskDemand {
+            fixed_sub_tasks: num_tasks * num_configs,
+            split_tasks: 0,
+            desired_split_sub_tasks: 0,
+        }
+    }
+
+    /// Generate a fuzzer task for the given task definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition to base generated task on.
+    /// * `build_variant` - Build Variant to base generated task on.
+    ///
+    /// # Returns
+    ///
+    /// Configuration for a generated fuzzer task.
+    async fn generate(
+        &self,
+        task_def: &EvgTask,
+        build_variant: &BuildVariant,
+    ) -> Result<Box<dyn GeneratedSuite>> {
+        let params = self
+            .config_extraction_service
+            .task_def_to_fuzzer_params(task_def, build_variant)?;
+
+        self.gen_fuzzer_service.generate_fuzzer_task(&params)
+    }
+}
+
 /// Build a sub-task for a fuzzer.
 ///
 /// # Arguments
@@ -263,7 +431,7 @@ fn build_fuzzer_sub_task(
     generated_suite_name: Option<&str>,
     old_version: Option<&str>,
 ) -> EvgTask {
-    let sub_task_name = name_generated_task(
+    let sub_task_name = params.task_naming.sub_task_name(
         display_name,
         sub_task_index,
         params.num_tasks as usize,
@@ -271,8 +439,13 @@ fn build_fuzzer_sub_task(
         params.platform.as_deref(),
     );
 
-    let mut commands = vec![];
-    if params.is_multiversion() {
+    let mut commands: Vec<EvgCommand> = params
+        .command_hooks
+        .prepend
+        .iter()
+        .map(|func| fn_call(func))
+        .collect();
+    if params.requires_multiversion_binaries() {
         commands.extend(vec![
             fn_call(GET_PROJECT_WITH_NO_MODULES),
             fn_call(ADD_GIT_TAG),
@@ -280,18 +453,22 @@ fn build_fuzzer_sub_task(
     }
     commands.extend(vec![fn_call(DO_SETUP), fn_call(CONFIGURE_EVG_API_CREDS)]);
 
-    if params.is_multiversion() {
+    if params.requires_multiversion_binaries() {
         commands.push(fn_call(DO_MULTIVERSION_SETUP));
     }
 
     commands.extend(vec![
-        fn_call(SETUP_JSTESTFUZZ),
-        fn_call_with_params(RUN_FUZZER, params.build_fuzzer_parameters()),
+        fn_call(&params.function_names.setup_jstestfuzz),
         fn_call_with_params(
-            RUN_GENERATED_TESTS,
+            &params.function_names.run_fuzzer,
+            params.build_fuzzer_parameters(),
+        ),
+        fn_call_with_params(
+            &params.function_names.run_generated_tests,
             params.build_run_tests_vars(generated_suite_name, old_version),
         ),
     ]);
+    commands.extend(params.command_hooks.append.iter().map(|func| fn_call(func)));
 
     let formatted_name = format!(
         "{}{}",
@@ -303,6 +480,25 @@ fn build_fuzzer_sub_task(
         name: formatted_name,
         commands: Some(commands),
         depends_on: params.get_dependencies(),
+        tags: Some(build_origin_tags(
+            &params.task_name,
+            Some(generated_suite_name.unwrap_or(&params.suite)),
+            old_version,
+        )),
+        priority: params.priority,
+        patchable: params.patchable,
+        disable: params.disable,
+        allowed_requesters: params.allowed_requesters.clone(),
+        exec_timeout_secs: sub_task_exec_timeout(
+            params.task_exec_timeout_secs,
+            params.scale_exec_timeout,
+            params.num_tasks as usize,
+        ),
+        stepback: if params.disable_stepback {
+            Some(false)
+        } else {
+            None
+        },
         ..Default::default()
     }
 }
@@ -311,7 +507,6 @@ fn build_fuzzer_sub_task(
 mod tests {
     use super::*;
     use rstest::*;
-    use shrub_rs::models::commands::EvgCommand;
 
     // FuzzerGenTasParams tests
     #[rstest]
@@ -396,6 +591,72 @@ mod tests {
         );
     }
 
+    #[rstest]
+    #[case(false, false, false)]
+    #[case(true, false, true)]
+    #[case(false, true, true)]
+    #[case(true, true, true)]
+    fn test_requires_multiversion_binaries(
+        #[case] require_multiversion_setup: bool,
+        #[case] is_differential: bool,
+        #[case] expected: bool,
+    ) {
+        let gen_params = FuzzerGenTaskParams {
+            require_multiversion_setup,
+            is_differential,
+            ..Default::default()
+        };
+
+        assert_eq!(gen_params.requires_multiversion_binaries(), expected);
+    }
+
+    #[test]
+    fn test_build_run_tests_vars_for_differential_fuzzer() {
+        let gen_params = FuzzerGenTaskParams {
+            task_name: "my task".to_string(),
+            is_differential: true,
+            ..Default::default()
+        };
+
+        let run_tests_vars = gen_params.build_run_tests_vars(None, None);
+
+        assert_eq!(
+            run_tests_vars.get("require_multiversion_setup"),
+            Some(&ParamValue::from(true))
+        );
+        assert_eq!(
+            run_tests_vars.get("differential_fuzzer_old_version"),
+            Some(&ParamValue::from("last_lts"))
+        );
+        assert_eq!(
+            run_tests_vars.get("multiversion_exclude_tags_version"),
+            Some(&ParamValue::from("last_lts"))
+        );
+    }
+
+    #[rstest]
+    #[case(None)]
+    #[case(Some(3600))]
+    fn test_build_run_tests_vars_with_exec_timeout(#[case] exec_timeout_secs: Option<u64>) {
+        let gen_params = FuzzerGenTaskParams {
+            task_name: "my task".to_string(),
+            timeout_secs: 1800,
+            exec_timeout_secs,
+            ..Default::default()
+        };
+
+        let run_tests_vars = gen_params.build_run_tests_vars(None, None);
+
+        assert_eq!(
+            run_tests_vars.get("timeout_secs"),
+            Some(&ParamValue::from(1800_u64))
+        );
+        assert_eq!(
+            run_tests_vars.get("exec_timeout_secs"),
+            exec_timeout_secs.map(ParamValue::from).as_ref()
+        );
+    }
+
     // FuzzerTask tests
     #[test]
     fn test_display_name() {
@@ -438,7 +699,7 @@ mod tests {
             ],
         };
 
-        let task_refs = fuzzer_task.build_task_ref(Some("distro".to_string()));
+        let task_refs = fuzzer_task.build_task_ref(Some("distro".to_string()), None, false);
 
         for task in task_refs {
             assert_eq!(task.distros.as_ref(), None);
@@ -479,6 +740,130 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_build_fuzzer_sub_task_should_tag_origin() {
+        let params = FuzzerGenTaskParams {
+            task_name: "some task".to_string(),
+            suite: "some_suite".to_string(),
+            ..Default::default()
+        };
+
+        let sub_task =
+            build_fuzzer_sub_task("my_task", 0, &params, Some("mv_suite"), Some("last-lts"));
+
+        assert_eq!(
+            sub_task.tags,
+            Some(vec![
+                "generated".to_string(),
+                "origin:some task".to_string(),
+                "suite:mv_suite".to_string(),
+                "mv:last-lts".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_build_fuzzer_sub_task_should_include_command_hooks() {
+        let params = FuzzerGenTaskParams {
+            task_name: "some task".to_string(),
+            command_hooks: CommandHooks {
+                prepend: vec!["set up kerberos".to_string()],
+                append: vec!["collect core dumps".to_string()],
+            },
+            ..Default::default()
+        };
+
+        let sub_task = build_fuzzer_sub_task("my_task", 0, &params, None, None);
+
+        let commands = sub_task.commands.unwrap();
+        assert_eq!(commands.len(), 7);
+        assert_eq!(get_evg_fn_name(&commands[0]), Some("set up kerberos"));
+        assert_eq!(get_evg_fn_name(&commands[1]), Some("do setup"));
+        assert_eq!(get_evg_fn_name(&commands[6]), Some("collect core dumps"));
+    }
+
+    #[test]
+    fn test_build_fuzzer_sub_task_should_use_configured_function_names() {
+        let params = FuzzerGenTaskParams {
+            task_name: "some task".to_string(),
+            function_names: FunctionNames {
+                run_generated_tests: "execute tests".to_string(),
+                run_fuzzer: "generate fuzz tests".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let sub_task = build_fuzzer_sub_task("my_task", 0, &params, None, None);
+
+        let commands = sub_task.commands.unwrap();
+        assert_eq!(get_evg_fn_name(&commands[2]), Some("setup jstestfuzz"));
+        assert_eq!(get_evg_fn_name(&commands[3]), Some("generate fuzz tests"));
+        assert_eq!(get_evg_fn_name(&commands[4]), Some("execute tests"));
+    }
+
+    #[rstest]
+    #[case(Some(false), None, None)]
+    #[case(None, Some(true), None)]
+    #[case(None, None, Some(vec!["patch_request".to_string()]))]
+    fn test_build_fuzzer_sub_task_should_keep_scheduling_fields(
+        #[case] patchable: Option<bool>,
+        #[case] disable: Option<bool>,
+        #[case] allowed_requesters: Option<Vec<String>>,
+    ) {
+        let params = FuzzerGenTaskParams {
+            task_name: "some task".to_string(),
+            patchable,
+            disable,
+            allowed_requesters: allowed_requesters.clone(),
+            ..Default::default()
+        };
+
+        let sub_task = build_fuzzer_sub_task("my_task", 0, &params, None, None);
+
+        assert_eq!(sub_task.patchable, patchable);
+        assert_eq!(sub_task.disable, disable);
+        assert_eq!(sub_task.allowed_requesters, allowed_requesters);
+    }
+
+    #[rstest]
+    #[case(false, Some(3600))]
+    #[case(true, Some(900))]
+    fn test_build_fuzzer_sub_task_should_keep_exec_timeout(
+        #[case] scale_exec_timeout: bool,
+        #[case] expected_timeout: Option<u64>,
+    ) {
+        let params = FuzzerGenTaskParams {
+            task_name: "some task".to_string(),
+            num_tasks: 4,
+            task_exec_timeout_secs: Some(3600),
+            scale_exec_timeout,
+            ..Default::default()
+        };
+
+        let sub_task = build_fuzzer_sub_task("my_task", 0, &params, None, None);
+
+        assert_eq!(sub_task.exec_timeout_secs, expected_timeout);
+    }
+
+    #[rstest]
+    #[case(false, None)]
+    #[case(true, Some(false))]
+    fn test_build_fuzzer_sub_task_should_disable_stepback(
+        #[case] disable_stepback: bool,
+        #[case] expected_stepback: Option<bool>,
+    ) {
+        let params = FuzzerGenTaskParams {
+            task_name: "some task".to_string(),
+            disable_stepback,
+            ..Default::default()
+        };
+
+        let sub_task = build_fuzzer_sub_task("my_task", 0, &params, None, None);
+
+        assert_eq!(sub_task.stepback, expected_stepback);
+    }
+
     #[test]
     fn test_build_multiversion_fuzzer_sub_task() {
         let display_name = "my_task";
@@ -508,4 +893,42 @@ mod tests {
             "archive_dist_test_debug"
         )
     }
+
+    #[test]
+    fn test_build_differential_fuzzer_sub_task() {
+        let display_name = "my_task";
+        let sub_task_index = 42;
+        let params = FuzzerGenTaskParams {
+            task_name: "some task".to_string(),
+            is_differential: true,
+            ..Default::default()
+        };
+
+        let sub_task = build_fuzzer_sub_task(display_name, sub_task_index, &params, None, None);
+
+        assert_eq!(sub_task.name, "my_task_42");
+        let commands = sub_task.commands.unwrap();
+        assert_eq!(
+            get_evg_fn_name(&commands[0]),
+            Some("git get project no modules")
+        );
+        assert_eq!(get_evg_fn_name(&commands[4]), Some("do multiversion setup"));
+        assert_eq!(get_evg_fn_name(&commands[6]), Some("run jstestfuzz"));
+        assert_eq!(get_evg_fn_name(&commands[7]), Some("run generated tests"));
+    }
+
+    #[test]
+    fn test_generate_differential_fuzzer_task_does_not_generate_multiversion_sub_tasks() {
+        let params = FuzzerGenTaskParams {
+            task_name: "my_fuzzer".to_string(),
+            num_tasks: 3,
+            is_differential: true,
+            ..Default::default()
+        };
+        let gen_fuzzer_service = GenFuzzerServiceImpl::new();
+
+        let fuzzer_task = gen_fuzzer_service.generate_fuzzer_task(&params).unwrap();
+
+        assert_eq!(fuzzer_task.sub_tasks().len(), 3);
+    }
 }
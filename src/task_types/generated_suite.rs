@@ -1,10 +1,14 @@
+use serde::{Deserialize, Serialize};
 use shrub_rs::models::{
     task::{EvgTask, TaskRef},
     variant::DisplayTask,
 };
 
+/// Tag added to every generated sub-task.
+pub const GENERATED_TAG: &str = "generated";
+
 /// Definition of a generated sub task.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct GeneratedSubTask {
     /// Definition of an Evergreen task.
     pub evg_task: EvgTask,
@@ -12,6 +16,11 @@ pub struct GeneratedSubTask {
     pub use_large_distro: bool,
     /// Whether to run generated task on a xlarge distro.
     pub use_xlarge_distro: bool,
+    /// Custom distro tier to run generated task on.
+    pub distro_tier: Option<String>,
+    /// Expected runtime of the generated task in seconds, if it could be determined.
+    #[serde(default)]
+    pub expected_runtime_secs: Option<f64>,
 }
 
 /// Interface for representing a generated task.
@@ -36,6 +45,13 @@ pub trait GeneratedSuite: Sync + Send {
             .any(|sub_task| sub_task.use_xlarge_distro)
     }
 
+    /// Get the custom distro tier requested by any sub task.
+    fn distro_tier(&self) -> Option<String> {
+        self.sub_tasks()
+            .iter()
+            .find_map(|sub_task| sub_task.distro_tier.clone())
+    }
+
     /// Build a shrub display task for this generated task.
     fn build_display_task(&self) -> DisplayTask {
         DisplayTask {
@@ -49,18 +65,122 @@ pub trait GeneratedSuite: Sync + Send {
     }
 
     /// Build a shrub task reference for this generated task.
-    fn build_task_ref(&self, distro: Option<String>) -> Vec<TaskRef> {
+    ///
+    /// # Arguments
+    ///
+    /// * `distro` - Distro to run sub-tasks requiring a large distro or distro tier on.
+    /// * `distros` - Distros to run sub-tasks on when a large distro is not used.
+    /// * `activate` - Should the sub-tasks be activated when the build variant is created.
+    fn build_task_ref(
+        &self,
+        distro: Option<String>,
+        distros: Option<Vec<String>>,
+        activate: bool,
+    ) -> Vec<TaskRef> {
         self.sub_tasks()
             .iter()
             .map(|sub_task| {
                 let mut large_distro = None;
-                if sub_task.use_large_distro || sub_task.use_xlarge_distro {
+                if sub_task.use_large_distro
+                    || sub_task.use_xlarge_distro
+                    || sub_task.distro_tier.is_some()
+                {
                     large_distro = distro.clone();
                 }
-                sub_task
-                    .evg_task
-                    .get_reference(large_distro.map(|d| vec![d]), Some(false))
+                sub_task.evg_task.get_reference(
+                    large_distro.map(|d| vec![d]).or_else(|| distros.clone()),
+                    Some(activate),
+                )
             })
             .collect()
     }
 }
+
+/// Determine the exec timeout to give a generated sub-task.
+///
+/// # Arguments
+///
+/// * `exec_timeout_secs` - Exec timeout of the task definition being generated from.
+/// * `scale` - Should the timeout be divided between the sub-tasks.
+/// * `total_sub_tasks` - Number of sub-tasks being generated.
+///
+/// # Returns
+///
+/// Exec timeout to give the sub-task, if any.
+pub fn sub_task_exec_timeout(
+    exec_timeout_secs: Option<u64>,
+    scale: bool,
+    total_sub_tasks: usize,
+) -> Option<u64> {
+    exec_timeout_secs.map(|timeout| {
+        if scale && total_sub_tasks > 1 {
+            let total_sub_tasks = total_sub_tasks as u64;
+            (timeout + total_sub_tasks - 1) / total_sub_tasks
+        } else {
+            timeout
+        }
+    })
+}
+
+/// Build the tags describing where a generated sub-task came from.
+///
+/// # Arguments
+///
+/// * `origin_task` - Name of task the sub-task was generated from.
+/// * `suite` - Name of suite the sub-task runs, if any.
+/// * `old_version` - Previous version of mongo the sub-task tests against, if any.
+///
+/// # Returns
+///
+/// Tags to add to the generated sub-task.
+pub fn build_origin_tags(
+    origin_task: &str,
+    suite: Option<&str>,
+    old_version: Option<&str>,
+) -> Vec<String> {
+    let mut tags = vec![GENERATED_TAG.to_string(), format!("origin:{}", origin_task)];
+    if let Some(suite) = suite {
+        tags.push(format!("suite:{}", suite));
+    }
+    if let Some(old_version) = old_version {
+        tags.push(format!("mv:{}", old_version));
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(None, true, 4, None)]
+    #[case(Some(3600), false, 4, Some(3600))]
+    #[case(Some(3600), true, 4, Some(900))]
+    #[case(Some(3601), true, 4, Some(901))]
+    #[case(Some(3600), true, 0, Some(3600))]
+    fn test_sub_task_exec_timeout(
+        #[case] exec_timeout_secs: Option<u64>,
+        #[case] scale: bool,
+        #[case] total_sub_tasks: usize,
+        #[case] expected_timeout: Option<u64>,
+    ) {
+        assert_eq!(
+            sub_task_exec_timeout(exec_timeout_secs, scale, total_sub_tasks),
+            expected_timeout
+        );
+    }
+
+    #[rstest]
+    #[case(None, None, vec!["generated", "origin:auth"])]
+    #[case(Some("auth_audit"), None, vec!["generated", "origin:auth", "suite:auth_audit"])]
+    #[case(Some("auth_audit"), Some("last-lts"), vec!["generated", "origin:auth", "suite:auth_audit", "mv:last-lts"])]
+    fn test_build_origin_tags(
+        #[case] suite: Option<&str>,
+        #[case] old_version: Option<&str>,
+        #[case] expected_tags: Vec<&str>,
+    ) {
+        assert_eq!(build_origin_tags("auth", suite, old_version), expected_tags);
+    }
+}
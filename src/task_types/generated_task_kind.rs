@@ -0,0 +1,293 @@
+//! Registry of the kinds of tasks that can be generated.
+//!
+//! Each kind of generated task (fuzzers, resmoke suites, etc) is handled by an implementation of
+//! `GeneratedTaskKind`. Kinds are registered against a var in the "generate resmoke tasks"
+//! function of a task definition. When that var is set to `"true"`, the registered kind will be
+//! used to generate the task. Task definitions that do not match any registered kind will be
+//! generated by the default kind.
+//!
+//! New kinds of generated tasks can be supported by implementing `GeneratedTaskKind` and
+//! registering the implementation when creating the registry.
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use shrub_rs::models::{task::EvgTask, variant::BuildVariant};
+
+use crate::{
+    evergreen::evg_config_utils::EvgConfigUtils,
+    evergreen_names::{FUZZER_TASK_VARS, IS_ANTITHESIS, IS_BAZEL_TEST_SHARDS},
+    services::config_extraction::SubTaskDemand,
+};
+
+use super::generated_suite::GeneratedSuite;
+
+/// A kind of task that can be generated.
+#[async_trait]
+pub trait GeneratedTaskKind: Sync + Send {
+    /// Name of this kind of generated task.
+    fn name(&self) -> &str;
+
+    /// Whether generating this kind of task uses the runtime history of the task.
+    fn uses_task_history(&self) -> bool {
+        false
+    }
+
+    /// Whether this kind of task is split by the sub-task limits of the build variant.
+    fn uses_sub_task_limits(&self) -> bool {
+        false
+    }
+
+    /// Estimate the sub-tasks generating the given task on the given build variant will need.
+    ///
+    /// By default, kinds split by the sub-task limits of the build variant are counted as a single
+    /// split wanting a single sub-task and other kinds as a single sub-task.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition to estimate.
+    /// * `build_variant` - Build variant the task is generated for.
+    ///
+    /// # Returns
+    ///
+    /// Sub-tasks the generated task is expected to need.
+    fn sub_task_demand(&self, _task_def: &EvgTask, _build_variant: &BuildVariant) -> SubTaskDemand {
+        if self.uses_sub_task_limits() {
+            SubTaskDemand {
+                fixed_sub_tasks: 0,
+                split_tasks: 1,
+                desired_split_sub_tasks: 1,
+            }
+        } else {
+            SubTaskDemand {
+                fixed_sub_tasks: 1,
+                split_tasks: 0,
+                desired_split_sub_tasks: 0,
+            }
+        }
+    }
+
+    /// Generate a task for the given task definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition to base generated task on.
+    /// * `build_variant` - Build Variant to base generated task on.
+    ///
+    /// # Returns
+    ///
+    /// Configuration for a generated task.
+    async fn generate(
+        &self,
+        task_def: &EvgTask,
+        build_variant: &BuildVariant,
+    ) -> Result<Box<dyn GeneratedSuite>>;
+
+    /// Generate the given task without splitting it.
+    ///
+    /// This is used when splitting the task did not finish in time.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition to base generated task on.
+    /// * `build_variant` - Build Variant to base generated task on.
+    ///
+    /// # Returns
+    ///
+    /// Configuration for the unsplit task, or None if this kind of task cannot be generated
+    /// without splitting it.
+    fn generate_unsplit(
+        &self,
+        _task_def: &EvgTask,
+        _build_variant: &BuildVariant,
+    ) -> Result<Option<Box<dyn GeneratedSuite>>> {
+        Ok(None)
+    }
+}
+
+/// A registry of the kinds of tasks that can be generated.
+pub struct GeneratedTaskKindRegistry {
+    /// Utilities to work with evergreen project configuration.
+    evg_config_utils: Arc<dyn EvgConfigUtils>,
+    /// Registered task vars and the kind of task they indicate, in registration order.
+    kinds: Vec<(String, Arc<dyn GeneratedTaskKind>)>,
+    /// Kind to use when a task definition does not match any registered kind.
+    default_kind: Arc<dyn GeneratedTaskKind>,
+}
+
+impl GeneratedTaskKindRegistry {
+    /// Create a new registry of generated task kinds.
+    ///
+    /// # Arguments
+    ///
+    /// * `evg_config_utils` - Utilities to work with evergreen project configuration.
+    /// * `default_kind` - Kind to use when a task definition does not match any registered kind.
+    ///
+    /// # Returns
+    ///
+    /// New registry with no kinds registered.
+    pub fn new(
+        evg_config_utils: Arc<dyn EvgConfigUtils>,
+        default_kind: Arc<dyn GeneratedTaskKind>,
+    ) -> Self {
+        Self {
+            evg_config_utils,
+            kinds: vec![],
+            default_kind,
+        }
+    }
+
+    /// Register a kind of generated task.
+    ///
+    /// If a task definition matches several registered kinds, the kind registered first is used.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_var` - Var in the "generate resmoke tasks" function that indicates this kind.
+    /// * `kind` - Kind of task to generate when `task_var` is set to `"true"`.
+    pub fn register(&mut self, task_var: &str, kind: Arc<dyn GeneratedTaskKind>) {
+        self.kinds.push((task_var.to_string(), kind));
+    }
+
+    /// Register the fuzzer, antithesis and bazel test shard kinds against the task vars that
+    /// select them.
+    ///
+    /// Fuzzers are selected by the same vars as `EvgConfigUtils::is_task_fuzzer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `fuzzer_kind` - Kind of task to generate for fuzzer tasks.
+    /// * `antithesis_kind` - Kind of task to generate for antithesis tasks.
+    /// * `bazel_shard_kind` - Kind of task to generate for bazel test shard tasks.
+    pub fn register_standard_kinds(
+        &mut self,
+        fuzzer_kind: Arc<dyn GeneratedTaskKind>,
+        antithesis_kind: Arc<dyn GeneratedTaskKind>,
+        bazel_shard_kind: Arc<dyn GeneratedTaskKind>,
+    ) {
+        for task_var in FUZZER_TASK_VARS {
+            self.register(task_var, fuzzer_kind.clone());
+        }
+        self.register(IS_ANTITHESIS, antithesis_kind);
+        self.register(IS_BAZEL_TEST_SHARDS, bazel_shard_kind);
+    }
+
+    /// Find the kind of task that should be used to generate the given task definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition to query.
+    ///
+    /// # Returns
+    ///
+    /// Kind of task to generate for the given task definition.
+    pub fn lookup(&self, task_def: &EvgTask) -> Arc<dyn GeneratedTaskKind> {
+        self.kinds
+            .iter()
+            .find(|(task_var, _)| {
+                self.evg_config_utils.get_gen_task_var(task_def, task_var) == Some("true")
+            })
+            .map(|(_, kind)| kind.clone())
+            .unwrap_or_else(|| self.default_kind.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::hashmap;
+    use rstest::rstest;
+    use shrub_rs::models::{
+        commands::{fn_call, fn_call_with_params},
+        params::ParamValue,
+    };
+
+    use crate::evergreen::evg_config_utils::EvgConfigUtilsImpl;
+
+    use super::*;
+
+    struct MockTaskKind {
+        name: String,
+    }
+    #[async_trait]
+    impl GeneratedTaskKind for MockTaskKind {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        async fn generate(
+            &self,
+            _task_def: &EvgTask,
+            _build_variant: &BuildVariant,
+        ) -> Result<Box<dyn GeneratedSuite>> {
+            todo!()
+        }
+    }
+
+    fn build_mocked_registry() -> GeneratedTaskKindRegistry {
+        let mut registry = GeneratedTaskKindRegistry::new(
+            Arc::new(EvgConfigUtilsImpl::new()),
+            Arc::new(MockTaskKind {
+                name: "default".to_string(),
+            }),
+        );
+        registry.register(
+            "is_kind_0",
+            Arc::new(MockTaskKind {
+                name: "kind_0".to_string(),
+            }),
+        );
+        registry.register(
+            "is_kind_1",
+            Arc::new(MockTaskKind {
+                name: "kind_1".to_string(),
+            }),
+        );
+        registry
+    }
+
+    // lookup tests.
+    #[rstest]
+    #[case(vec![], "default")]
+    #[case(vec![("some_var", "true")], "default")]
+    #[case(vec![("is_kind_0", "false")], "default")]
+    #[case(vec![("is_kind_0", "true")], "kind_0")]
+    #[case(vec![("is_kind_1", "true")], "kind_1")]
+    #[case(vec![("is_kind_0", "false"), ("is_kind_1", "true")], "kind_1")]
+    #[case(vec![("is_kind_1", "true"), ("is_kind_0", "true")], "kind_0")]
+    fn test_lookup_should_use_registered_kinds(
+        #[case] vars: Vec<(&str, &str)>,
+        #[case] expected_kind: &str,
+    ) {
+        let registry = build_mocked_registry();
+        let evg_task = EvgTask {
+            commands: Some(vec![
+                fn_call("hello world"),
+                fn_call_with_params(
+                    "generate resmoke tasks",
+                    vars.into_iter()
+                        .map(|(k, v)| (k.to_string(), ParamValue::from(v)))
+                        .collect(),
+                ),
+            ]),
+            ..Default::default()
+        };
+
+        assert_eq!(registry.lookup(&evg_task).name(), expected_kind);
+    }
+
+    #[test]
+    fn test_lookup_should_use_default_kind_if_task_is_not_generated() {
+        let registry = build_mocked_registry();
+        let evg_task = EvgTask {
+            commands: Some(vec![fn_call_with_params(
+                "run tests",
+                hashmap! {
+                    "is_kind_0".to_string() => ParamValue::from("true"),
+                },
+            )]),
+            ..Default::default()
+        };
+
+        assert_eq!(registry.lookup(&evg_task).name(), "default");
+    }
+}
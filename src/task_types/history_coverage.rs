@@ -0,0 +1,216 @@
+//! Coverage of the tests in resmoke suites by historic runtime data.
+//!
+//! Suites are split into sub-suites based on the historic runtimes of their tests. Tests without
+//! history are distributed evenly, so suites with little history are split no better than the
+//! fallback splitting. This reports how much of each suite is covered by history.
+use serde::Serialize;
+
+use crate::evergreen::evg_task_history::{get_test_name, TaskRuntimeHistory};
+
+/// Coverage of the tests of a suite by historic runtime data.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SuiteHistoryCoverage {
+    /// Name of the task running the suite.
+    pub task: String,
+    /// Name of the suite.
+    pub suite: String,
+    /// Number of tests in the suite.
+    pub tests: usize,
+    /// Number of tests in the suite with historic runtime data.
+    pub tests_with_history: usize,
+    /// Expected runtime of the suite from the historic runtime data, in seconds.
+    pub expected_runtime: f64,
+    /// Will the suite be split without historic runtime data.
+    pub uses_fallback: bool,
+}
+
+impl SuiteHistoryCoverage {
+    /// Fraction of the tests in the suite with historic runtime data.
+    pub fn coverage(&self) -> f64 {
+        if self.tests == 0 {
+            0.0
+        } else {
+            self.tests_with_history as f64 / self.tests as f64
+        }
+    }
+}
+
+/// Determine how much of the given suite is covered by historic runtime data.
+///
+/// # Arguments
+///
+/// * `task` - Name of the task running the suite.
+/// * `suite` - Name of the suite.
+/// * `test_list` - Tests in the suite.
+/// * `task_history` - Historic runtime data of the task, if it could be fetched.
+///
+/// # Returns
+///
+/// Coverage of the suite by historic runtime data.
+pub fn suite_history_coverage(
+    task: &str,
+    suite: &str,
+    test_list: &[String],
+    task_history: Option<&TaskRuntimeHistory>,
+) -> SuiteHistoryCoverage {
+    let runtimes: Vec<f64> = task_history
+        .map(|history| {
+            test_list
+                .iter()
+                .filter_map(|test| history.test_map.get(&get_test_name(test)))
+                .map(|test_stats| test_stats.average_runtime)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    SuiteHistoryCoverage {
+        task: task.to_string(),
+        suite: suite.to_string(),
+        tests: test_list.len(),
+        tests_with_history: runtimes.len(),
+        expected_runtime: runtimes.iter().sum(),
+        uses_fallback: runtimes.is_empty(),
+    }
+}
+
+/// Format the given suite coverage as a table.
+///
+/// Suites that will be split without historic runtime data are marked in the `FALLBACK` column.
+///
+/// # Arguments
+///
+/// * `suites` - Coverage of suites to format.
+///
+/// # Returns
+///
+/// Table with a row for each suite.
+pub fn format_coverage_table(suites: &[SuiteHistoryCoverage]) -> String {
+    let rows: Vec<[String; 6]> = suites
+        .iter()
+        .map(|suite| {
+            [
+                suite.task.clone(),
+                suite.suite.clone(),
+                format!("{}/{}", suite.tests_with_history, suite.tests),
+                format!("{:.0}%", suite.coverage() * 100.0),
+                format!("{:.0}s", suite.expected_runtime),
+                if suite.uses_fallback { "yes" } else { "" }.to_string(),
+            ]
+        })
+        .collect();
+    let header = [
+        "TASK".to_string(),
+        "SUITE".to_string(),
+        "TESTS WITH HISTORY".to_string(),
+        "COVERAGE".to_string(),
+        "EXPECTED RUNTIME".to_string(),
+        "FALLBACK".to_string(),
+    ];
+
+    let mut widths = header.clone().map(|h| h.len());
+    for row in &rows {
+        for (width, value) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(value.len());
+        }
+    }
+
+    std::iter::once(&header)
+        .chain(rows.iter())
+        .map(|row| {
+            row.iter()
+                .zip(widths.iter())
+                .map(|(value, width)| format!("{:width$}", value, width = width))
+                .collect::<Vec<String>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::hashmap;
+    use rstest::rstest;
+
+    use crate::evergreen::evg_task_history::TestRuntimeHistory;
+
+    use super::*;
+
+    fn build_history() -> TaskRuntimeHistory {
+        TaskRuntimeHistory {
+            task_name: "jsCore".to_string(),
+            test_map: hashmap! {
+                "test_0".to_string() => TestRuntimeHistory {
+                    test_name: "test_0".to_string(),
+                    average_runtime: 30.0,
+                    hooks: vec![],
+                },
+                "test_1".to_string() => TestRuntimeHistory {
+                    test_name: "test_1".to_string(),
+                    average_runtime: 12.0,
+                    hooks: vec![],
+                },
+            },
+        }
+    }
+
+    #[rstest]
+    #[case(Some(build_history()), 2, 42.0, false)]
+    #[case(None, 0, 0.0, true)]
+    fn test_suite_history_coverage(
+        #[case] task_history: Option<TaskRuntimeHistory>,
+        #[case] expected_with_history: usize,
+        #[case] expected_runtime: f64,
+        #[case] expected_fallback: bool,
+    ) {
+        let test_list = vec![
+            "jstests/core/test_0.js".to_string(),
+            "jstests/core/test_1.js".to_string(),
+            "jstests/core/test_2.js".to_string(),
+            "jstests/core/test_3.js".to_string(),
+        ];
+
+        let coverage = suite_history_coverage("jsCore", "core", &test_list, task_history.as_ref());
+
+        assert_eq!(coverage.tests, 4);
+        assert_eq!(coverage.tests_with_history, expected_with_history);
+        assert_eq!(coverage.expected_runtime, expected_runtime);
+        assert_eq!(coverage.uses_fallback, expected_fallback);
+        assert_eq!(coverage.coverage(), expected_with_history as f64 / 4.0);
+    }
+
+    #[test]
+    fn test_format_coverage_table_should_mark_fallback_suites() {
+        let suites = vec![
+            SuiteHistoryCoverage {
+                task: "jsCore".to_string(),
+                suite: "core".to_string(),
+                tests: 4,
+                tests_with_history: 3,
+                expected_runtime: 120.0,
+                uses_fallback: false,
+            },
+            SuiteHistoryCoverage {
+                task: "noPassthrough".to_string(),
+                suite: "no_passthrough".to_string(),
+                tests: 2,
+                tests_with_history: 0,
+                expected_runtime: 0.0,
+                uses_fallback: true,
+            },
+        ];
+
+        let table = format_coverage_table(&suites);
+
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("TASK"));
+        assert!(lines[1].starts_with("jsCore"));
+        assert!(lines[1].contains("3/4"));
+        assert!(lines[1].contains("75%"));
+        assert!(!lines[1].ends_with("yes"));
+        assert!(lines[2].ends_with("yes"));
+    }
+}
@@ -1,6 +1,13 @@
+pub mod antithesis_tasks;
+pub mod bazel_shard_tasks;
 pub mod burn_in_tests;
 pub mod fuzzer_tasks;
 pub mod generated_suite;
+pub mod generated_task_kind;
+pub mod history_coverage;
 pub mod multiversion;
 pub mod resmoke_config_writer;
 pub mod resmoke_tasks;
+pub mod runtime_estimation;
+pub mod task_inventory;
+pub mod test_runner_backend;
@@ -16,16 +16,27 @@
 //!   refer what version each node in the replica set should be. The version value will be either
 //!   `new` or `old`. `new` refers to the version of MongoDB being tested. `old` refers to the
 //!   previous `old_version` being tests (`lts` or `continuous`).
+//!
+//! Projects that need to test against specific releases (e.g. extended EOL testing) can provide
+//! an explicit list of old versions in the generate sub-tasks config. When provided, this list
+//! replaces the `last versions` reported by resmoke and sub-tasks are generated for each listed
+//! version found in the `"initialize multiversion tasks"` function.
+//!
+//! The exclude tags files referenced by generated multiversion sub-tasks are created by the
+//! generator in the target directory, one file per old version.
+
+use std::path::Path;
 
 use anyhow::Result;
+use tracing::{event, Level};
 
 use crate::{
     evergreen::evg_config_utils::MultiversionGenerateTaskConfig,
     evergreen_names::{
-        BACKPORT_REQUIRED_TAG, MULTIVERSION_INCOMPATIBLE, MULTIVERSION_LAST_CONTINUOUS,
-        MULTIVERSION_LAST_LTS,
+        BACKPORT_REQUIRED_TAG, MULTIVERSION_EXCLUDE_TAGS_FILE, MULTIVERSION_INCOMPATIBLE,
+        MULTIVERSION_LAST_CONTINUOUS, MULTIVERSION_LAST_LTS,
     },
-    resmoke::resmoke_proxy::MultiversionConfig,
+    resmoke::resmoke_proxy::{MultiversionConfig, TestDiscovery},
 };
 
 /// A service for helping generating multiversion tasks.
@@ -47,15 +58,21 @@ pub trait MultiversionService: Sync + Send {
     /// # Arguments
     ///
     /// * `multiversion_generate_tasks` - Array of desired tasks to generate.
+    /// * `last_versions_expansion` - Comma-separated override of the config's `last versions`.
+    /// * `excluded_versions_expansion` - Comma-separated old versions that should not be generated.
     ///
     /// # Returns
     ///
-    /// Multiversion generate tasks that match the config's `last versions`.
+    /// Multiversion generate tasks that match the config's `last versions` and are not excluded.
     fn filter_multiversion_generate_tasks(
         &self,
         multiversion_generate_tasks: Option<Vec<MultiversionGenerateTaskConfig>>,
         last_versions_expansion: Option<String>,
+        excluded_versions_expansion: Option<String>,
     ) -> Option<Vec<MultiversionGenerateTaskConfig>>;
+
+    /// Get the old versions that multiversion tasks can be generated against.
+    fn old_versions(&self) -> Vec<String>;
 }
 
 /// Implementation of Multiversion service.
@@ -71,7 +88,14 @@ impl MultiversionServiceImpl {
     /// # Arguments
     ///
     /// * `multiversion_config` - Instance of multiversion config.
-    pub fn new(multiversion_config: MultiversionConfig) -> Result<Self> {
+    /// * `old_versions` - Explicit list of old versions to use instead of the config's `last versions`.
+    pub fn new(
+        mut multiversion_config: MultiversionConfig,
+        old_versions: Option<Vec<String>>,
+    ) -> Result<Self> {
+        if let Some(old_versions) = old_versions {
+            multiversion_config.last_versions = old_versions;
+        }
         Ok(Self {
             multiversion_config,
         })
@@ -84,6 +108,8 @@ impl MultiversionService for MultiversionServiceImpl {
     /// # Arguments
     ///
     /// * `task_name` - Name of task to query.
+    /// * `mv_mode` - Type of multiversion task being generated (last_lts, continuous or an
+    ///   explicit old version).
     ///
     /// # Returns
     ///
@@ -96,7 +122,7 @@ impl MultiversionService for MultiversionServiceImpl {
                 MULTIVERSION_LAST_CONTINUOUS => {
                     self.multiversion_config.get_fcv_tags_for_continuous()
                 }
-                _ => panic!("Unknown multiversion mode: {}", &mode),
+                _ => self.multiversion_config.requires_fcv_tag.clone(),
             }
         } else {
             self.multiversion_config.requires_fcv_tag.clone()
@@ -116,37 +142,164 @@ impl MultiversionService for MultiversionServiceImpl {
     /// # Arguments
     ///
     /// * `multiversion_generate_tasks` - Array of desired tasks to generate.
+    /// * `last_versions_expansion` - Comma-separated override of the config's `last versions`.
+    /// * `excluded_versions_expansion` - Comma-separated old versions that should not be generated.
     ///
     /// # Returns
     ///
-    /// Multiversion generate tasks that match the config's `last versions`.
+    /// Multiversion generate tasks that match the config's `last versions` and are not excluded.
     fn filter_multiversion_generate_tasks(
         &self,
         multiversion_generate_tasks: Option<Vec<MultiversionGenerateTaskConfig>>,
         last_versions_expansion: Option<String>,
+        excluded_versions_expansion: Option<String>,
     ) -> Option<Vec<MultiversionGenerateTaskConfig>> {
         let last_versions: Vec<String> = last_versions_expansion
             .unwrap_or_else(|| self.multiversion_config.last_versions.join(","))
             .split(',')
             .map(|s| s.to_string())
             .collect();
+        let excluded_versions: Vec<String> = excluded_versions_expansion
+            .map(|versions| {
+                versions
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
         Some(
             multiversion_generate_tasks?
                 .into_iter()
-                .filter(|task_config| last_versions.contains(&task_config.old_version))
+                .filter(|task_config| {
+                    last_versions.contains(&task_config.old_version)
+                        && !excluded_versions.contains(&task_config.old_version)
+                })
                 .collect(),
         )
     }
+
+    /// Get the old versions that multiversion tasks can be generated against.
+    fn old_versions(&self) -> Vec<String> {
+        self.multiversion_config.last_versions.clone()
+    }
+}
+
+/// Get the name of the exclude tags file to use for the given old version.
+///
+/// # Arguments
+///
+/// * `old_version` - Old version being tested against, `last_lts` is used if not specified.
+///
+/// # Returns
+///
+/// Name of exclude tags file for the old version.
+pub fn multiversion_exclude_tags_file(old_version: Option<&str>) -> String {
+    format!(
+        "{}_{}.yml",
+        MULTIVERSION_EXCLUDE_TAGS_FILE,
+        old_version.unwrap_or(MULTIVERSION_LAST_LTS)
+    )
+}
+
+/// Write the exclude tags files for all the given old versions to the target directory.
+///
+/// A file for `last_lts` is always written since it is used by tasks that perform multiversion
+/// setup without generating version specific sub-tasks.
+///
+/// # Arguments
+///
+/// * `discovery_service` - Service to generate the exclude tags with.
+/// * `old_versions` - Old versions to write exclude tags files for.
+/// * `target_directory` - Directory to write exclude tags files to.
+pub fn write_multiversion_exclude_tags(
+    discovery_service: &dyn TestDiscovery,
+    old_versions: &[String],
+    target_directory: &Path,
+) -> Result<()> {
+    let mut versions: Vec<&str> = vec![MULTIVERSION_LAST_LTS];
+    for old_version in old_versions {
+        if !versions.contains(&old_version.as_str()) {
+            versions.push(old_version);
+        }
+    }
+
+    for old_version in versions {
+        let output_file = target_directory.join(multiversion_exclude_tags_file(Some(old_version)));
+        event!(
+            Level::INFO,
+            old_version,
+            output_file = output_file.display().to_string(),
+            "Writing multiversion exclude tags"
+        );
+        discovery_service.generate_multiversion_exclude_tags(old_version, &output_file)?;
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
+    use std::{path::PathBuf, sync::Mutex};
+
+    use anyhow::Result;
+    use rstest::rstest;
+
     use crate::{
         evergreen::evg_config_utils::MultiversionGenerateTaskConfig,
-        resmoke::resmoke_proxy::MultiversionConfig,
+        resmoke::{
+            resmoke_proxy::{MultiversionConfig, TestDiscovery},
+            resmoke_suite::ResmokeSuiteConfig,
+        },
     };
 
-    use super::{MultiversionService, MultiversionServiceImpl};
+    use super::*;
+
+    struct MockTestDiscovery {
+        written_files: Mutex<Vec<(String, PathBuf)>>,
+    }
+    impl TestDiscovery for MockTestDiscovery {
+        fn discover_tests(&self, _suite_name: &str) -> Result<Vec<String>> {
+            todo!()
+        }
+
+        fn discover_all_tests(&self, _suite_name: &str) -> Result<Vec<String>> {
+            todo!()
+        }
+
+        fn get_suite_config(&self, _suite_name: &str) -> Result<ResmokeSuiteConfig> {
+            todo!()
+        }
+
+        fn get_multiversion_config(&self) -> Result<MultiversionConfig> {
+            todo!()
+        }
+
+        fn generate_multiversion_exclude_tags(
+            &self,
+            old_version: &str,
+            output_file: &Path,
+        ) -> Result<()> {
+            self.written_files
+                .lock()
+                .unwrap()
+                .push((old_version.to_string(), output_file.to_path_buf()));
+            Ok(())
+        }
+
+        fn skipped_covered_tests(&self) -> std::collections::BTreeMap<String, Vec<String>> {
+            todo!()
+        }
+    }
+
+    fn build_multiversion_config(last_versions: Vec<&str>) -> MultiversionConfig {
+        MultiversionConfig {
+            last_versions: last_versions.iter().map(|v| v.to_string()).collect(),
+            requires_fcv_tag: "requires_fcv_71".to_string(),
+            requires_fcv_tag_lts: Some("requires_fcv_70".to_string()),
+            requires_fcv_tag_continuous: Some("requires_fcv_71".to_string()),
+        }
+    }
 
     #[test]
     fn test_multiversion_generate_tasks_exist() {
@@ -170,7 +323,11 @@ mod tests {
         };
         assert_eq!(
             multiversion_service
-                .filter_multiversion_generate_tasks(Some(multiversion_generate_tasks.clone()), None)
+                .filter_multiversion_generate_tasks(
+                    Some(multiversion_generate_tasks.clone()),
+                    None,
+                    None
+                )
                 .unwrap(),
             multiversion_generate_tasks
         );
@@ -196,7 +353,11 @@ mod tests {
             },
         };
         let filtered_multiversion_generate_tasks = multiversion_service
-            .filter_multiversion_generate_tasks(Some(multiversion_generate_tasks.clone()), None)
+            .filter_multiversion_generate_tasks(
+                Some(multiversion_generate_tasks.clone()),
+                None,
+                None,
+            )
             .unwrap();
         assert_eq!(filtered_multiversion_generate_tasks.len(), 1);
         assert_eq!(
@@ -216,9 +377,174 @@ mod tests {
         };
         assert_eq!(
             multiversion_service
-                .filter_multiversion_generate_tasks(None, None)
+                .filter_multiversion_generate_tasks(None, None, None)
                 .is_none(),
             true
         );
     }
+
+    #[test]
+    fn test_explicit_old_versions_should_replace_last_versions() {
+        let multiversion_generate_tasks = vec![
+            MultiversionGenerateTaskConfig {
+                suite_name: "suite_last_lts".to_string(),
+                old_version: "last_lts".to_string(),
+            },
+            MultiversionGenerateTaskConfig {
+                suite_name: "suite_v5_0".to_string(),
+                old_version: "v5.0".to_string(),
+            },
+            MultiversionGenerateTaskConfig {
+                suite_name: "suite_v6_0".to_string(),
+                old_version: "v6.0".to_string(),
+            },
+        ];
+        let multiversion_service = MultiversionServiceImpl::new(
+            build_multiversion_config(vec!["last_lts", "last_continuous"]),
+            Some(vec!["v5.0".to_string(), "v6.0".to_string()]),
+        )
+        .unwrap();
+
+        let filtered_multiversion_generate_tasks = multiversion_service
+            .filter_multiversion_generate_tasks(
+                Some(multiversion_generate_tasks.clone()),
+                None,
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(
+            filtered_multiversion_generate_tasks,
+            multiversion_generate_tasks[1..].to_vec()
+        );
+    }
+
+    #[test]
+    fn test_no_explicit_old_versions_should_use_last_versions() {
+        let multiversion_service = MultiversionServiceImpl::new(
+            build_multiversion_config(vec!["last_lts", "last_continuous"]),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            multiversion_service.multiversion_config.last_versions,
+            vec!["last_lts".to_string(), "last_continuous".to_string()]
+        );
+    }
+
+    // exclude_tags_for_task tests.
+    #[test]
+    fn test_exclude_tags_for_task_should_use_mode_specific_fcv_tags() {
+        let multiversion_service =
+            MultiversionServiceImpl::new(build_multiversion_config(vec!["last_lts"]), None)
+                .unwrap();
+
+        let exclude_tags =
+            multiversion_service.exclude_tags_for_task("my_task", Some("last_lts".to_string()));
+
+        assert_eq!(
+            exclude_tags,
+            "multiversion_incompatible,backport_required_multiversion,my_task_backport_required_multiversion,requires_fcv_70"
+        );
+    }
+
+    #[test]
+    fn test_exclude_tags_for_task_with_explicit_old_version_should_use_fcv_tag() {
+        let multiversion_service = MultiversionServiceImpl::new(
+            build_multiversion_config(vec!["last_lts"]),
+            Some(vec!["v5.0".to_string()]),
+        )
+        .unwrap();
+
+        let exclude_tags =
+            multiversion_service.exclude_tags_for_task("my_task", Some("v5.0".to_string()));
+
+        assert!(exclude_tags.ends_with(",requires_fcv_71"));
+    }
+
+    // multiversion_exclude_tags_file tests.
+    #[test]
+    fn test_multiversion_exclude_tags_file() {
+        assert_eq!(
+            multiversion_exclude_tags_file(Some("last_continuous")),
+            "multiversion_exclude_tags_last_continuous.yml"
+        );
+        assert_eq!(
+            multiversion_exclude_tags_file(None),
+            "multiversion_exclude_tags_last_lts.yml"
+        );
+    }
+
+    // write_multiversion_exclude_tags tests.
+    #[test]
+    fn test_write_multiversion_exclude_tags_should_write_file_per_old_version() {
+        let discovery_service = MockTestDiscovery {
+            written_files: Mutex::new(vec![]),
+        };
+
+        write_multiversion_exclude_tags(
+            &discovery_service,
+            &["last_continuous".to_string(), "last_lts".to_string()],
+            Path::new("target_dir"),
+        )
+        .unwrap();
+
+        let written_files = discovery_service.written_files.lock().unwrap();
+        assert_eq!(
+            *written_files,
+            vec![
+                (
+                    "last_lts".to_string(),
+                    PathBuf::from("target_dir/multiversion_exclude_tags_last_lts.yml")
+                ),
+                (
+                    "last_continuous".to_string(),
+                    PathBuf::from("target_dir/multiversion_exclude_tags_last_continuous.yml")
+                ),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case(None, vec!["last_lts", "last_continuous"])]
+    #[case(Some(""), vec!["last_lts", "last_continuous"])]
+    #[case(Some("last_lts"), vec!["last_continuous"])]
+    #[case(Some("last_lts, last_continuous"), vec![])]
+    fn test_filter_multiversion_generate_tasks_should_remove_excluded_versions(
+        #[case] excluded_versions: Option<&str>,
+        #[case] expected_versions: Vec<&str>,
+    ) {
+        let multiversion_generate_tasks = vec![
+            MultiversionGenerateTaskConfig {
+                suite_name: "suite1".to_string(),
+                old_version: "last_lts".to_string(),
+            },
+            MultiversionGenerateTaskConfig {
+                suite_name: "suite2".to_string(),
+                old_version: "last_continuous".to_string(),
+            },
+        ];
+        let multiversion_service = MultiversionServiceImpl::new(
+            build_multiversion_config(vec!["last_lts", "last_continuous"]),
+            None,
+        )
+        .unwrap();
+
+        let filtered_multiversion_generate_tasks = multiversion_service
+            .filter_multiversion_generate_tasks(
+                Some(multiversion_generate_tasks),
+                None,
+                excluded_versions.map(|v| v.to_string()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            filtered_multiversion_generate_tasks
+                .iter()
+                .map(|t| t.old_version.as_str())
+                .collect::<Vec<_>>(),
+            expected_versions
+        );
+    }
 }
@@ -7,7 +7,15 @@
 //! When using this actor, to ensure that all in-flight requests have been completed, you
 //! will want to a `flush` message. This message will wait for all actor instance to complete
 //! any work they have queued up before returning.
-use std::{collections::HashMap, path::PathBuf, sync::Arc};
+//!
+//! To wait for the suite files of a single task instead, generate the task within the scope of
+//! `SUITE_WRITE_ACKS`. Each write requested within the scope is acknowledged once its files have
+//! been written, without waiting on the writes of other tasks.
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 
 use anyhow::Result;
 use async_trait::async_trait;
@@ -15,7 +23,7 @@ use tokio::sync::{mpsc, oneshot};
 
 use crate::{
     resmoke::{resmoke_proxy::TestDiscovery, resmoke_suite::ResmokeSuiteConfig},
-    utils::{fs_service::FsService, task_name::name_generated_task},
+    utils::{fs_service::FsService, task_name::TaskNamingPolicy},
 };
 
 use super::resmoke_tasks::{ResmokeSuiteGenerationInfo, SubSuite};
@@ -23,13 +31,59 @@ use super::resmoke_tasks::{ResmokeSuiteGenerationInfo, SubSuite};
 #[derive(Debug)]
 /// Messages that can be sent to the `ResmokeConfigWriter` actor.
 enum ResmokeConfigMessage {
-    /// Generate and write resmoke configuration files for the given list of sub-suites.
-    SuiteFiles(ResmokeSuiteGenerationInfo),
+    /// Generate and write resmoke configuration files for the given list of sub-suites, reporting
+    /// whether they were written to the given sender.
+    SuiteFiles(ResmokeSuiteGenerationInfo, Option<oneshot::Sender<bool>>),
 
     /// Wait for all in-flight config files to be written to disk.
     Flush(oneshot::Sender<Vec<String>>),
 }
 
+tokio::task_local! {
+    /// Acknowledgements of the suite files written for the task generated on the current task.
+    pub static SUITE_WRITE_ACKS: SuiteWriteAcks;
+}
+
+/// Acknowledgements of the suite file writes requested while generating a task.
+#[derive(Debug, Clone, Default)]
+pub struct SuiteWriteAcks {
+    /// Receivers of the acknowledgements of each requested write.
+    receivers: Arc<Mutex<Vec<oneshot::Receiver<bool>>>>,
+}
+
+impl SuiteWriteAcks {
+    /// Create a new, empty set of acknowledgements.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a write to be acknowledged.
+    ///
+    /// # Returns
+    ///
+    /// Sender to acknowledge the write with.
+    fn register(&self) -> oneshot::Sender<bool> {
+        let (sender, receiver) = oneshot::channel();
+        self.receivers.lock().unwrap().push(receiver);
+        sender
+    }
+
+    /// Wait for every registered write to complete.
+    ///
+    /// # Returns
+    ///
+    /// true if all the suite files were written.
+    pub async fn all_written(&self) -> bool {
+        let receivers: Vec<oneshot::Receiver<bool>> =
+            self.receivers.lock().unwrap().drain(..).collect();
+        let mut all_written = true;
+        for receiver in receivers {
+            all_written &= receiver.await.unwrap_or(false);
+        }
+        all_written
+    }
+}
+
 /// The actor implementation that performs actions based on received messages.
 struct WriteConfigActorImpl {
     /// Test discovery service.
@@ -90,7 +144,13 @@ impl WriteConfigActorImpl {
     /// * `msg` - Message to act on.
     fn handle_message(&mut self, msg: ResmokeConfigMessage) {
         match msg {
-            ResmokeConfigMessage::SuiteFiles(suite_info) => self.write_suite_files(suite_info),
+            ResmokeConfigMessage::SuiteFiles(suite_info, ack) => {
+                let written = self.write_suite_files(suite_info);
+                if let Some(ack) = ack {
+                    // The task may have been aborted and stopped waiting for the acknowledgement.
+                    let _ = ack.send(written);
+                }
+            }
             ResmokeConfigMessage::Flush(sender) => sender.send(self.errors.clone()).unwrap(),
         }
     }
@@ -100,14 +160,20 @@ impl WriteConfigActorImpl {
     /// # Arguments
     ///
     /// * `suite_info` - Details about the suite that was generated.
-    fn write_suite_files(&mut self, suite_info: ResmokeSuiteGenerationInfo) {
+    ///
+    /// # Returns
+    ///
+    /// true if the suite files were written.
+    fn write_suite_files(&mut self, suite_info: ResmokeSuiteGenerationInfo) -> bool {
         let result = self.write_standard_suite(&suite_info);
 
         // If we encountered an error, save it off so we can report it on flush.
         if let Err(error) = result {
             self.errors
                 .push(format!("ERROR: {}: {}", &suite_info.task_name, error));
+            return false;
         }
+        true
     }
 
     /// Write resmoke configurations for a standard generated resmoke task.
@@ -119,7 +185,12 @@ impl WriteConfigActorImpl {
         let mut resmoke_config_cache = ResmokeConfigCache::new(self.test_discovery.clone());
 
         // Create suite files for all the sub-suites.
-        self.write_sub_suites(&suite_info.sub_suites, &mut resmoke_config_cache)?;
+        self.write_sub_suites(
+            &suite_info.sub_suites,
+            suite_info.suite_dir.as_deref(),
+            &suite_info.task_naming,
+            &mut resmoke_config_cache,
+        )?;
 
         Ok(())
     }
@@ -129,10 +200,14 @@ impl WriteConfigActorImpl {
     /// # Arguments
     ///
     /// * `sub_suites` - List of sub-suites to write configuration for.
+    /// * `suite_dir` - Directory relative to the target directory to write configuration to.
+    /// * `task_naming` - Conventions for naming the suite files.
     /// * `resmoke_config_cache` - Cache to get resmoke suite configurations.
     fn write_sub_suites(
         &self,
         sub_suites: &[SubSuite],
+        suite_dir: Option<&str>,
+        task_naming: &TaskNamingPolicy,
         resmoke_config_cache: &mut ResmokeConfigCache,
     ) -> Result<()> {
         let total_tasks = sub_suites.len();
@@ -145,7 +220,7 @@ impl WriteConfigActorImpl {
 
                 let filename = format!(
                     "{}.yml",
-                    name_generated_task(
+                    task_naming.sub_task_name(
                         &s.name,
                         s.index,
                         total_tasks,
@@ -154,9 +229,13 @@ impl WriteConfigActorImpl {
                     )
                 );
                 let mut path = PathBuf::from(&self.target_dir);
+                if let Some(suite_dir) = suite_dir {
+                    path.push(suite_dir);
+                }
                 path.push(filename);
 
-                self.fs_service.write_file(&path, &config.to_string())?;
+                let contents = format!("{}{}", sub_suite_header(s), config.to_string());
+                self.fs_service.write_file(&path, &contents)?;
                 Ok(())
             })
             .collect();
@@ -165,6 +244,31 @@ impl WriteConfigActorImpl {
     }
 }
 
+/// Build a comment describing what the generator expects of the given sub-suite.
+///
+/// The comment is written at the top of the sub-suite's configuration file, so the expected
+/// runtime can be compared with how long the sub-task actually took.
+///
+/// # Arguments
+///
+/// * `sub_suite` - Sub-suite to describe.
+///
+/// # Returns
+///
+/// YAML comment to place at the top of the configuration file.
+fn sub_suite_header(sub_suite: &SubSuite) -> String {
+    let expected_runtime = sub_suite
+        .expected_runtime
+        .map_or("unknown".to_string(), |runtime| format!("{:.0}s", runtime));
+
+    format!(
+        "# Generated from suite: {}\n# Tests: {}\n# Expected runtime: {}\n",
+        sub_suite.origin_suite,
+        sub_suite.test_list.len(),
+        expected_runtime
+    )
+}
+
 #[async_trait]
 pub trait ResmokeConfigActor: Sync + Send {
     /// Send a message to write a configuration file to disk.
@@ -233,8 +337,11 @@ impl ResmokeConfigActorService {
 #[async_trait]
 impl ResmokeConfigActor for ResmokeConfigActorService {
     /// Send a message to write a configuration file to disk.
+    ///
+    /// Within the scope of `SUITE_WRITE_ACKS`, the write is acknowledged once it completes.
     async fn write_sub_suite(&mut self, gen_suite: &ResmokeSuiteGenerationInfo) {
-        let msg = ResmokeConfigMessage::SuiteFiles(gen_suite.clone());
+        let ack = SUITE_WRITE_ACKS.try_with(|acks| acks.register()).ok();
+        let msg = ResmokeConfigMessage::SuiteFiles(gen_suite.clone(), ack);
         self.round_robbin(msg).await;
     }
 
@@ -299,6 +406,7 @@ mod tests {
     use std::{cell::RefCell, collections::HashMap, ops::AddAssign, str::FromStr, sync::Mutex};
 
     use anyhow::bail;
+    use rstest::rstest;
 
     use crate::{resmoke::resmoke_suite::ResmokeSuiteConfig, task_types::resmoke_tasks::SubSuite};
 
@@ -310,6 +418,10 @@ mod tests {
             todo!()
         }
 
+        fn discover_all_tests(&self, _suite_name: &str) -> anyhow::Result<Vec<String>> {
+            todo!()
+        }
+
         fn get_suite_config(
             &self,
             _suite_name: &str,
@@ -343,6 +455,18 @@ mod tests {
         ) -> anyhow::Result<crate::resmoke::resmoke_proxy::MultiversionConfig> {
             todo!()
         }
+
+        fn generate_multiversion_exclude_tags(
+            &self,
+            _old_version: &str,
+            _output_file: &std::path::Path,
+        ) -> anyhow::Result<()> {
+            todo!()
+        }
+
+        fn skipped_covered_tests(&self) -> std::collections::BTreeMap<String, Vec<String>> {
+            todo!()
+        }
     }
 
     struct MockFsService {
@@ -375,6 +499,10 @@ mod tests {
             todo!()
         }
 
+        fn file_size(&self, _path: &str) -> Option<u64> {
+            todo!()
+        }
+
         fn write_file(&self, path: &std::path::Path, _contents: &str) -> anyhow::Result<()> {
             if self.raise_errors {
                 bail!("Error injected for {:?}", path);
@@ -397,14 +525,18 @@ mod tests {
         WriteConfigActorImpl::new(test_discovery, fs_service, rx, "target".to_string())
     }
 
-    #[test]
-    fn test_write_suite_files() {
+    #[rstest]
+    #[case(None, "target")]
+    #[case(Some("my_task"), "target/my_task")]
+    fn test_write_suite_files(#[case] suite_dir: Option<&str>, #[case] expected_dir: &str) {
         let fs_service = Arc::new(MockFsService::new());
         let mut resmoke_config_actor = build_mock_service(fs_service.clone());
         let suite_info = ResmokeSuiteGenerationInfo {
             task_name: "my_task".to_string(),
             origin_suite: "original_suite".to_string(),
             require_multiversion_generate_tasks: false,
+            suite_dir: suite_dir.map(|d| d.to_string()),
+            task_naming: TaskNamingPolicy::default(),
             sub_suites: vec![
                 SubSuite {
                     index: 0,
@@ -425,8 +557,34 @@ mod tests {
 
         resmoke_config_actor.write_suite_files(suite_info);
 
-        assert_eq!(fs_service.get_call_counts("target/suite_name_0.yml"), 1);
-        assert_eq!(fs_service.get_call_counts("target/suite_name_1.yml"), 1);
+        assert_eq!(
+            fs_service.get_call_counts(&format!("{}/suite_name_0.yml", expected_dir)),
+            1
+        );
+        assert_eq!(
+            fs_service.get_call_counts(&format!("{}/suite_name_1.yml", expected_dir)),
+            1
+        );
+    }
+
+    #[rstest]
+    #[case(Some(125.4), "# Expected runtime: 125s\n")]
+    #[case(None, "# Expected runtime: unknown\n")]
+    fn test_sub_suite_header_should_describe_sub_suite(
+        #[case] expected_runtime: Option<f64>,
+        #[case] expected_runtime_line: &str,
+    ) {
+        let sub_suite = SubSuite {
+            origin_suite: "suite".to_string(),
+            test_list: vec!["test_0.js".to_string(), "test_1.js".to_string()],
+            expected_runtime,
+            ..Default::default()
+        };
+
+        let header = sub_suite_header(&sub_suite);
+
+        assert!(header.starts_with("# Generated from suite: suite\n# Tests: 2\n"));
+        assert!(header.ends_with(expected_runtime_line));
     }
 
     #[tokio::test]
@@ -439,6 +597,8 @@ mod tests {
             task_name: "my_task".to_string(),
             origin_suite: "original_suite".to_string(),
             require_multiversion_generate_tasks: false,
+            suite_dir: None,
+            task_naming: TaskNamingPolicy::default(),
             sub_suites: vec![
                 SubSuite {
                     index: 0,
@@ -465,4 +625,40 @@ mod tests {
 
         assert_eq!(errors.len(), n_operations);
     }
+
+    #[tokio::test]
+    async fn test_write_acks_should_report_each_tasks_own_writes() {
+        let test_discovery = Arc::new(MockTestDiscovery {});
+        let mut failing_actor = ResmokeConfigActorService::new(
+            test_discovery.clone(),
+            Arc::new(MockFsService::new_failure_mode()),
+            "target_dir",
+            3,
+        );
+        let suite_info = ResmokeSuiteGenerationInfo {
+            task_name: "my_task".to_string(),
+            origin_suite: "original_suite".to_string(),
+            require_multiversion_generate_tasks: false,
+            suite_dir: None,
+            task_naming: TaskNamingPolicy::default(),
+            sub_suites: vec![SubSuite {
+                index: 0,
+                name: "suite".to_string(),
+                origin_suite: "suite".to_string(),
+                test_list: vec!["test_0.js".to_string()],
+                ..Default::default()
+            }],
+        };
+
+        let failed_acks = SuiteWriteAcks::new();
+        SUITE_WRITE_ACKS
+            .scope(failed_acks.clone(), async {
+                failing_actor.write_sub_suite(&suite_info).await;
+            })
+            .await;
+        let empty_acks = SuiteWriteAcks::new();
+
+        assert!(!failed_acks.all_written().await);
+        assert!(empty_acks.all_written().await);
+    }
 }
@@ -4,43 +4,57 @@
 //! use that information to divide the tests into sub-suites that can be run in parallel.
 //!
 //! Each task will contain the generated sub-suites.
-use std::{cmp::min, collections::HashMap, sync::Arc};
+use std::{
+    cmp::{min, Reverse},
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use async_trait::async_trait;
 use maplit::hashmap;
 use rand::{prelude::SliceRandom, thread_rng};
+use sha2::{Digest, Sha256};
 use shrub_rs::models::{
     commands::{fn_call, fn_call_with_params, EvgCommand},
     params::ParamValue,
     task::{EvgTask, TaskDependency},
+    variant::BuildVariant,
 };
 use tokio::sync::Mutex;
 use tracing::{event, warn, Level};
 
 use crate::{
     evergreen::{
-        evg_config_utils::MultiversionGenerateTaskConfig,
-        evg_task_history::{
-            get_test_name, TaskHistoryService, TaskRuntimeHistory, TestRuntimeHistory,
-        },
+        evg_config_utils::{EvgConfigUtils, MultiversionGenerateTaskConfig},
+        evg_task_history::{get_test_name, TaskHistoryService, TaskRuntimeHistory},
     },
     evergreen_names::{
-        ADD_GIT_TAG, CONFIGURE_EVG_API_CREDS, DO_MULTIVERSION_SETUP, DO_SETUP,
-        GEN_TASK_CONFIG_LOCATION, GET_PROJECT_WITH_NO_MODULES, MULTIVERSION_EXCLUDE_TAG,
-        MULTIVERSION_EXCLUDE_TAGS_FILE, REQUIRE_MULTIVERSION_SETUP, RESMOKE_ARGS, RESMOKE_JOBS_MAX,
-        RUN_GENERATED_TESTS, SUITE_NAME,
+        ADD_GIT_TAG, CONFIGURE_EVG_API_CREDS, DO_MULTIVERSION_SETUP, DO_SETUP, ENTERPRISE_MODULE,
+        GEN_TASK_CONFIG_LOCATION, GET_PROJECT_WITH_NO_MODULES, MULTIVERSION,
+        MULTIVERSION_EXCLUDE_TAG, NO_MULTIVERSION_GENERATE_TASKS, REQUIRE_MULTIVERSION_SETUP,
+        RESMOKE_ARGS, RESMOKE_JOBS_MAX, SUB_TASK_INDEX, SUITE_NAME, TOTAL_SUB_TASKS,
     },
+    generate_sub_tasks_config::{CommandHooks, FunctionNames},
+    ownership::OwnershipService,
     resmoke::resmoke_proxy::TestDiscovery,
-    utils::{fs_service::FsService, task_name::name_generated_task},
+    services::config_extraction::{ConfigExtractionService, SubTaskDemand, SubTaskLimits},
+    utils::{fs_service::FsService, task_name::TaskNamingPolicy},
 };
 
 use super::{
-    generated_suite::{GeneratedSubTask, GeneratedSuite},
-    multiversion::MultiversionService,
+    generated_suite::{build_origin_tags, sub_task_exec_timeout, GeneratedSubTask, GeneratedSuite},
+    generated_task_kind::GeneratedTaskKind,
+    multiversion::{multiversion_exclude_tags_file, MultiversionService},
     resmoke_config_writer::ResmokeConfigActor,
+    runtime_estimation::{FileSizeEstimator, RuntimeEstimator},
+    test_runner_backend::lookup_test_runner_backend,
 };
 
+/// Fraction a sub-suite may exceed an even share of the runtime when splitting consistently.
+const CONSISTENT_SPLIT_SLACK: f64 = 0.1;
+
 /// Parameters describing how a specific resmoke suite should be generated.
 #[derive(Clone, Debug, Default)]
 pub struct ResmokeGenParams {
@@ -54,6 +68,8 @@ pub struct ResmokeGenParams {
     pub use_large_distro: bool,
     /// Should the generated tasks run on a 'xlarge' distro.
     pub use_xlarge_distro: bool,
+    /// Custom distro tier the generated tasks should run on.
+    pub distro_tier: Option<String>,
     /// Does this task require multiversion setup.
     pub require_multiversion_setup: bool,
     /// Should multiversion generate tasks exist for this.
@@ -70,28 +86,91 @@ pub struct ResmokeGenParams {
     pub dependencies: Vec<String>,
     /// Is this task for enterprise builds.
     pub is_enterprise: bool,
+    /// Modules of the build variant the task is for, None if they are not known.
+    pub modules: Option<Vec<String>>,
     /// Arguments to pass to 'run tests' function.
     pub pass_through_vars: Option<HashMap<String, ParamValue>>,
     /// Name of platform the task will run on.
     pub platform: Option<String>,
     /// Name of variant specific suffix to add to tasks
     pub gen_task_suffix: Option<String>,
+    /// Priority to give generated sub-tasks.
+    pub priority: Option<u64>,
+    /// Should generated sub-tasks be scheduled in patch builds.
+    pub patchable: Option<bool>,
+    /// Should generated sub-tasks be disabled.
+    pub disable: Option<bool>,
+    /// Requesters that are allowed to schedule generated sub-tasks.
+    pub allowed_requesters: Option<Vec<String>>,
+    /// Test runner to run the tests with, resmoke if not set.
+    pub test_runner: Option<String>,
+    /// Exec timeout of the task definition, given to generated sub-tasks.
+    pub task_exec_timeout_secs: Option<u64>,
+    /// Divide the exec timeout of the task definition between the generated sub-tasks.
+    pub scale_exec_timeout: bool,
+    /// Should generated sub-tasks opt out of stepback.
+    pub disable_stepback: bool,
+    /// Assign tests to sub-suites by consistent hashing so membership is stable across commits.
+    pub consistent_split: bool,
+    /// Split tests evenly between sub-suites without using their historic runtimes.
+    pub use_fallback_split: bool,
+    /// Run tests covered by more complex suites even when they are skipped for other tasks.
+    pub run_covered_tests: bool,
+    /// Functions to call around the commands of generated sub-tasks.
+    pub command_hooks: CommandHooks,
+    /// Names of the functions sub-tasks call to run their tests.
+    pub function_names: FunctionNames,
+    /// Conventions for naming generated sub-tasks.
+    pub task_naming: TaskNamingPolicy,
+    /// Number of CPUs on the distro sub-tasks run on, set to choose resmoke jobs dynamically.
+    pub distro_cpu_count: Option<u64>,
+    /// Maximum runtime sub-tasks should finish in on the build variant, in seconds.
+    pub max_task_runtime_secs: Option<u64>,
+    /// Limits on the number of sub-tasks to split the task into.
+    pub sub_task_limits: SubTaskLimits,
 }
 
 impl ResmokeGenParams {
+    /// Check if tests from the given module should be run by the task.
+    ///
+    /// Enterprise tests are run on enterprise build variants. Tests from other modules are run if
+    /// the build variant lists the module, or if the modules of the build variant are not known.
+    ///
+    /// # Arguments
+    ///
+    /// * `module_name` - Name of module to check.
+    ///
+    /// # Returns
+    ///
+    /// true if tests from the module should be run.
+    pub fn includes_module(&self, module_name: &str) -> bool {
+        if module_name == ENTERPRISE_MODULE {
+            return self.is_enterprise;
+        }
+        self.modules
+            .as_ref()
+            .map_or(true, |modules| modules.iter().any(|m| m == module_name))
+    }
+
     /// Build the vars to send to the tasks in the 'run tests' function.
     ///
     /// # Arguments
     ///
-    /// * `suite_file` - Name of suite file to run.
+    /// * `suite_file` - Path of suite file to run relative to the target directory, without the
+    ///    extension.
+    /// * `sub_suite` - Sub-suite the sub-task runs.
+    /// * `total_sub_suites` - Number of sub-suites the task was split into.
+    /// * `exclude_tags` - Tags of tests to exclude.
+    /// * `suite_override` - Suite to run instead of the generated suite file.
     ///
     /// # Returns
     ///
     /// Map of arguments to pass to 'run tests' function.
-    fn build_run_test_vars(
+    pub(crate) fn build_run_test_vars(
         &self,
         suite_file: &str,
         sub_suite: &SubSuite,
+        total_sub_suites: usize,
         exclude_tags: &str,
         suite_override: Option<String>,
     ) -> HashMap<String, ParamValue> {
@@ -100,7 +179,11 @@ impl ResmokeGenParams {
             run_test_vars.extend(pass_through_vars.clone());
         }
 
-        let resmoke_args = self.build_resmoke_args(exclude_tags, &sub_suite.origin_suite);
+        let resmoke_args = self.build_resmoke_args(
+            exclude_tags,
+            &sub_suite.origin_suite,
+            sub_suite.mv_exclude_tags.as_deref(),
+        );
         let suite = if let Some(suite_override) = suite_override {
             suite_override
         } else {
@@ -113,6 +196,7 @@ impl ResmokeGenParams {
             SUITE_NAME.to_string() => ParamValue::from(suite.as_str()),
             GEN_TASK_CONFIG_LOCATION.to_string() => ParamValue::from(self.config_location.as_str()),
         });
+        run_test_vars.extend(sub_task_index_vars(sub_suite.index, total_sub_suites));
 
         if let Some(mv_exclude_tags) = &sub_suite.mv_exclude_tags {
             run_test_vars.insert(
@@ -137,15 +221,22 @@ impl ResmokeGenParams {
     ///
     /// * `exclude_tags` - Resmoke tags to exclude.
     /// * `origin_suite` - Suite the generated suite is based on.
+    /// * `old_version` - Old version being tested against for multiversion sub-suites.
     ///
     /// # Returns
     ///
     /// String of arguments to pass to resmoke.
-    fn build_resmoke_args(&self, exclude_tags: &str, origin_suite: &str) -> String {
+    fn build_resmoke_args(
+        &self,
+        exclude_tags: &str,
+        origin_suite: &str,
+        old_version: Option<&str>,
+    ) -> String {
         let suffix = if self.require_multiversion_setup {
             format!(
                 "--tagFile=generated_resmoke_config/{} --excludeWithAnyTags={}",
-                MULTIVERSION_EXCLUDE_TAGS_FILE, exclude_tags
+                multiversion_exclude_tags_file(old_version),
+                exclude_tags
             )
         } else {
             "".to_string()
@@ -211,6 +302,9 @@ pub struct SubSuite {
 
     /// Platform of build_variant the sub-suite is for.
     pub platform: Option<String>,
+
+    /// Expected runtime of the sub-suite based on historic test runtimes, in seconds.
+    pub expected_runtime: Option<f64>,
 }
 
 /// Information needed to generate resmoke configuration files for the generated task.
@@ -227,6 +321,11 @@ pub struct ResmokeSuiteGenerationInfo {
 
     /// If true, sub-tasks should be generated for the multiversion generate tasks.
     pub require_multiversion_generate_tasks: bool,
+
+    /// Directory relative to the target directory to write the suite files to.
+    pub suite_dir: Option<String>,
+    /// Conventions for naming the suite files of sub-suites.
+    pub task_naming: TaskNamingPolicy,
 }
 
 /// Representation of a generated resmoke suite.
@@ -287,6 +386,17 @@ pub trait GenResmokeTaskService: Sync + Send {
         params: &ResmokeGenParams,
         suite_override: Option<String>,
     ) -> GeneratedSubTask;
+
+    /// Build a task that runs the original suite in a single sub-task without splitting it.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - Parameters for how task should be generated.
+    ///
+    /// # Returns
+    ///
+    /// A generated suite with a single sub-task running the entire suite.
+    fn build_unsplit_resmoke_task(&self, params: &ResmokeGenParams) -> Box<dyn GeneratedSuite>;
 }
 
 #[derive(Debug, Clone)]
@@ -297,8 +407,17 @@ pub struct GenResmokeConfig {
     /// Disable evergreen task-history queries and use task splitting fallback.
     use_task_split_fallback: bool,
 
-    /// Enterprise directory.
-    enterprise_dir: Option<String>,
+    /// Map of module names to the directories the modules are checked out to.
+    module_dirs: HashMap<String, String>,
+
+    /// Run the original suite in a single sub-task when no tests are found to split.
+    empty_suite_fallback: bool,
+
+    /// Fail instead of warning when tests reported by test discovery do not exist.
+    fail_on_missing_tests: bool,
+
+    /// Write the suite files of each task to a sub-directory named after the task.
+    nest_suite_files: bool,
 }
 
 impl GenResmokeConfig {
@@ -309,7 +428,13 @@ impl GenResmokeConfig {
     /// * `n_suite` - Number of sub-suites to split tasks into.
     /// * `use_task_split_fallback` - Disable evergreen task-history queries and use task
     ///    splitting fallback.
-    /// * `enterprise_dir` - Directory enterprise files are stored in.
+    /// * `module_dirs` - Map of module names to the directories their files are stored in.
+    /// * `empty_suite_fallback` - Run the original suite in a single sub-task when no tests are
+    ///    found to split.
+    /// * `fail_on_missing_tests` - Fail instead of warning when tests reported by test discovery
+    ///    do not exist.
+    /// * `nest_suite_files` - Write the suite files of each task to a sub-directory named after
+    ///    the task.
     ///
     /// # Returns
     ///
@@ -317,16 +442,60 @@ impl GenResmokeConfig {
     pub fn new(
         n_suites: usize,
         use_task_split_fallback: bool,
-        enterprise_dir: Option<String>,
+        module_dirs: HashMap<String, String>,
+        empty_suite_fallback: bool,
+        fail_on_missing_tests: bool,
+        nest_suite_files: bool,
     ) -> Self {
         Self {
             n_suites,
             use_task_split_fallback,
-            enterprise_dir,
+            module_dirs,
+            empty_suite_fallback,
+            fail_on_missing_tests,
+            nest_suite_files,
+        }
+    }
+
+    /// Get the directory to write the suite files of the given task to.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_name` - Name of task suite files are being written for.
+    ///
+    /// # Returns
+    ///
+    /// Directory relative to the target directory, or None to write them to the target directory.
+    fn suite_dir(&self, task_name: &str) -> Option<String> {
+        if self.nest_suite_files {
+            Some(task_name.to_string())
+        } else {
+            None
         }
     }
 }
 
+/// Inputs that determine how a suite is split into sub-suites.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SplitCacheKey {
+    /// Name of suite being split.
+    suite_name: String,
+    /// Platform of build variant the suite is split for.
+    platform: Option<String>,
+    /// Is the suite split for an enterprise build variant.
+    is_enterprise: bool,
+    /// Modules of the build variant the suite is split for.
+    modules: Option<Vec<String>>,
+    /// Were tests covered by more complex suites included in the split.
+    run_covered_tests: bool,
+    /// Hash of the runtime history used to split the suite, if history was available.
+    history_hash: Option<u64>,
+    /// Was the suite split by consistent hashing.
+    consistent_split: bool,
+    /// Number of sub-suites the suite was split into at most.
+    n_suites: usize,
+}
+
 /// Implementation of service to generate resmoke tasks.
 #[derive(Clone)]
 pub struct GenResmokeTaskServiceImpl {
@@ -345,8 +514,17 @@ pub struct GenResmokeTaskServiceImpl {
     /// Service to interact with file system.
     fs_service: Arc<dyn FsService>,
 
+    /// Service to attribute generated sub-tasks to the teams owning their tests.
+    ownership_service: Arc<dyn OwnershipService>,
+
     /// Configuration for generating resmoke tasks.
     config: GenResmokeConfig,
+
+    /// Test lists of previously split suites, shared by tasks split from the same inputs.
+    split_cache: Arc<std::sync::Mutex<HashMap<SplitCacheKey, Vec<Vec<String>>>>>,
+
+    /// Service to estimate the runtime of tests without historic runtime data.
+    runtime_estimator: Arc<dyn RuntimeEstimator>,
 }
 
 impl GenResmokeTaskServiceImpl {
@@ -357,8 +535,11 @@ impl GenResmokeTaskServiceImpl {
     /// * `task_history_service` - An instance of the service to query task history.
     /// * `test_discovery` - An instance of the service to query tests belonging to a task.
     /// * `fs_service` - An instance of the service too work with the file system.
+    /// * `ownership_service` - An instance of the service to attribute sub-tasks to teams.
     /// * `gen_resmoke_config` - Configuration for how resmoke tasks should be generated.
     ///
+    /// Tests without historic runtime data are estimated from their file size.
+    ///
     /// # Returns
     ///
     /// New instance of GenResmokeTaskService.
@@ -368,6 +549,7 @@ impl GenResmokeTaskServiceImpl {
         resmoke_config_actor: Arc<Mutex<dyn ResmokeConfigActor>>,
         multiversion_service: Arc<dyn MultiversionService>,
         fs_service: Arc<dyn FsService>,
+        ownership_service: Arc<dyn OwnershipService>,
         config: GenResmokeConfig,
     ) -> Self {
         Self {
@@ -375,13 +557,43 @@ impl GenResmokeTaskServiceImpl {
             test_discovery,
             resmoke_config_actor,
             multiversion_service,
+            runtime_estimator: Arc::new(FileSizeEstimator::new(fs_service.clone())),
             fs_service,
+            ownership_service,
             config,
+            split_cache: Arc::new(std::sync::Mutex::new(HashMap::new())),
         }
     }
+
+    /// Use the given service to estimate the runtime of tests without historic runtime data.
+    ///
+    /// # Arguments
+    ///
+    /// * `runtime_estimator` - Service to estimate the runtime of tests with.
+    ///
+    /// # Returns
+    ///
+    /// The service using the given estimator.
+    pub fn with_runtime_estimator(mut self, runtime_estimator: Arc<dyn RuntimeEstimator>) -> Self {
+        self.runtime_estimator = runtime_estimator;
+        self
+    }
 }
 
 impl GenResmokeTaskServiceImpl {
+    /// Determine the number of sub-tasks to split the given task into.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - Parameters for how tasks should be generated.
+    ///
+    /// # Returns
+    ///
+    /// Maximum number of sub-tasks to split the task into.
+    fn n_suites(&self, params: &ResmokeGenParams) -> usize {
+        params.sub_task_limits.sub_tasks(self.config.n_suites)
+    }
+
     /// Split the given task into a number of sub-tasks for parallel execution.
     ///
     /// # Arguments
@@ -403,12 +615,16 @@ impl GenResmokeTaskServiceImpl {
     ) -> Result<Vec<SubSuite>> {
         let origin_suite = multiversion_name.unwrap_or(&params.suite_name);
         let test_list = self.get_test_list(params, multiversion_name)?;
+        let estimated_runtimes = self
+            .runtime_estimator
+            .estimate_runtimes(&test_list, task_stats);
         let total_runtime = task_stats
             .test_map
             .iter()
-            .fold(0.0, |init, (_, item)| init + item.average_runtime);
+            .fold(0.0, |init, (_, item)| init + item.average_runtime)
+            + estimated_runtimes.values().sum::<f64>();
 
-        let max_tasks = min(self.config.n_suites, test_list.len());
+        let max_tasks = min(self.n_suites(params), test_list.len());
         let runtime_per_subtask = total_runtime / max_tasks as f64;
         event!(
             Level::INFO,
@@ -418,22 +634,25 @@ impl GenResmokeTaskServiceImpl {
             test_list.len()
         );
 
-        let sorted_test_list = sort_tests_by_runtime(test_list, task_stats);
-        let mut running_tests = vec![vec![]; max_tasks];
-        let mut running_runtimes = vec![0.0; max_tasks];
+        let sorted_test_list = sort_tests_by_runtime(test_list, task_stats, &estimated_runtimes);
+        let mut timed_tests = vec![];
         let mut left_tests = vec![];
 
         for test in sorted_test_list {
-            let min_idx = get_min_index(&running_runtimes);
             let test_name = get_test_name(&test);
-            if let Some(test_stats) = task_stats.test_map.get(&test_name) {
-                running_runtimes[min_idx] += test_stats.average_runtime;
-                running_tests[min_idx].push(test.clone());
+            let runtime = task_stats
+                .test_map
+                .get(&test_name)
+                .map(|test_stats| test_stats.average_runtime)
+                .or_else(|| estimated_runtimes.get(&test).copied());
+            if let Some(runtime) = runtime {
+                timed_tests.push((test, runtime));
             } else {
-                left_tests.push(test.clone());
+                left_tests.push(test);
             }
         }
 
+        let (mut running_tests, running_runtimes) = split_by_runtime(timed_tests, max_tasks);
         let min_idx = get_min_index(&running_runtimes);
         for (i, test) in left_tests.iter().enumerate() {
             running_tests[(min_idx + i) % max_tasks].push(test.clone());
@@ -450,6 +669,7 @@ impl GenResmokeTaskServiceImpl {
                 mv_exclude_tags: multiversion_tags.clone(),
                 is_enterprise: params.is_enterprise,
                 platform: params.platform.clone(),
+                expected_runtime: None,
             });
         }
 
@@ -471,16 +691,37 @@ impl GenResmokeTaskServiceImpl {
         multiversion_name: Option<&str>,
     ) -> Result<Vec<String>> {
         let suite_name = multiversion_name.unwrap_or(&params.suite_name);
-        let mut test_list: Vec<String> = self
-            .test_discovery
-            .discover_tests(suite_name)?
+        let discovered_tests = if params.run_covered_tests {
+            self.test_discovery.discover_all_tests(suite_name)?
+        } else {
+            self.test_discovery.discover_tests(suite_name)?
+        };
+        let (mut test_list, missing_tests): (Vec<String>, Vec<String>) = discovered_tests
             .into_iter()
-            .filter(|s| self.fs_service.file_exists(s))
-            .collect();
+            .partition(|s| self.fs_service.file_exists(s));
+
+        if !missing_tests.is_empty() {
+            if self.config.fail_on_missing_tests {
+                bail!(
+                    "Suite '{}' includes {} tests that do not exist: {}",
+                    suite_name,
+                    missing_tests.len(),
+                    missing_tests.join(", ")
+                );
+            }
+            warn!(
+                task_name = params.task_name.as_str(),
+                suite = suite_name,
+                missing_count = missing_tests.len(),
+                found_count = test_list.len(),
+                missing_tests = missing_tests.join(", ").as_str(),
+                "Suite includes tests that do not exist, they will not be run",
+            );
+        }
 
-        if !params.is_enterprise {
-            if let Some(enterprise_dir) = &self.config.enterprise_dir {
-                test_list.retain(|s| !s.starts_with(enterprise_dir));
+        for (module_name, module_dir) in &self.config.module_dirs {
+            if !params.includes_module(module_name) {
+                test_list.retain(|s| !s.starts_with(module_dir));
             }
         }
 
@@ -516,7 +757,7 @@ impl GenResmokeTaskServiceImpl {
         if test_list.is_empty() {
             return Ok(sub_suites);
         }
-        let n_suites = min(test_list.len(), self.config.n_suites);
+        let n_suites = min(test_list.len(), self.n_suites(params));
         let tasks_per_suite = test_list.len() / n_suites;
 
         let mut current_tests = vec![];
@@ -533,6 +774,7 @@ impl GenResmokeTaskServiceImpl {
                     mv_exclude_tags: multiversion_tags.clone(),
                     is_enterprise: params.is_enterprise,
                     platform: params.platform.clone(),
+                    expected_runtime: None,
                 });
                 current_tests = vec![];
                 i += 1;
@@ -549,12 +791,66 @@ impl GenResmokeTaskServiceImpl {
                 mv_exclude_tags: multiversion_tags,
                 is_enterprise: params.is_enterprise,
                 platform: params.platform.clone(),
+                expected_runtime: None,
             });
         }
 
         Ok(sub_suites)
     }
 
+    /// Split the given task so each test runs in the same sub-suite across commits.
+    ///
+    /// Tests are assigned to sub-suites by consistent hashing of their path, so adding or
+    /// removing tests only moves a few other tests. Historic runtimes are used to keep the
+    /// sub-suites balanced, if available.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - Parameters for how tasks should be generated.
+    /// * `task_stats` - Statistics on the historic runtimes of tests in the task, if available.
+    /// * `multiversion_name` - Name of task if performing multiversion generation.
+    /// * `multiversion_tags` - Tag to include when performing multiversion generation.
+    ///
+    /// # Returns
+    ///
+    /// A list of sub-suites to run the tests is the given task.
+    fn split_task_consistently(
+        &self,
+        params: &ResmokeGenParams,
+        task_stats: Option<&TaskRuntimeHistory>,
+        multiversion_name: Option<&str>,
+        multiversion_tags: Option<String>,
+    ) -> Result<Vec<SubSuite>> {
+        let origin_suite = multiversion_name.unwrap_or(&params.suite_name);
+        let test_list = self.get_test_list(params, multiversion_name)?;
+        if test_list.is_empty() {
+            return Ok(vec![]);
+        }
+        let n_suites = min(test_list.len(), self.n_suites(params));
+        event!(
+            Level::INFO,
+            "Consistently splitting task: {}, tests: {}",
+            &params.suite_name,
+            test_list.len()
+        );
+
+        Ok(split_tests_consistently(test_list, task_stats, n_suites)
+            .into_iter()
+            .enumerate()
+            .map(|(index, test_list)| SubSuite {
+                index,
+                name: multiversion_name.unwrap_or(&params.task_name).to_string(),
+                test_list,
+                origin_suite: origin_suite.to_string(),
+                exclude_test_list: None,
+                mv_exclude_tags: multiversion_tags.clone(),
+                is_enterprise: params.is_enterprise,
+                platform: params.platform.clone(),
+                expected_runtime: None,
+            })
+            .collect())
+    }
+
     /// Create version of the generated sub-tasks for all the multiversion combinations.
     ///
     /// # Arguments
@@ -605,21 +901,15 @@ impl GenResmokeTaskServiceImpl {
         multiversion_name: Option<&str>,
         multiversion_tags: Option<String>,
     ) -> Result<Vec<SubSuite>> {
-        let sub_suites = if self.config.use_task_split_fallback {
-            self.split_task_fallback(params, multiversion_name, multiversion_tags.clone())?
+        let task_history = if self.config.use_task_split_fallback || params.use_fallback_split {
+            None
         } else {
-            let task_history = self
+            match self
                 .task_history_service
                 .get_task_history(&params.task_name, build_variant)
-                .await;
-
-            match task_history {
-                Ok(task_history) => self.split_task(
-                    params,
-                    &task_history,
-                    multiversion_name,
-                    multiversion_tags.clone(),
-                )?,
+                .await
+            {
+                Ok(task_history) => Some(task_history),
                 Err(err) => {
                     warn!(
                         build_variant = build_variant,
@@ -627,25 +917,294 @@ impl GenResmokeTaskServiceImpl {
                         error = err.to_string().as_str(),
                         "Could not get task history from S3",
                     );
-                    // If we couldn't get the task history, then fallback to splitting the tests evenly
-                    // among the desired number of sub-suites.
-                    self.split_task_fallback(params, multiversion_name, multiversion_tags.clone())?
+                    None
                 }
             }
         };
 
+        let cache_key = SplitCacheKey {
+            suite_name: multiversion_name.unwrap_or(&params.suite_name).to_string(),
+            platform: params.platform.clone(),
+            is_enterprise: params.is_enterprise,
+            modules: params.modules.clone(),
+            run_covered_tests: params.run_covered_tests,
+            history_hash: task_history.as_ref().map(hash_task_history),
+            consistent_split: params.consistent_split,
+            n_suites: self.n_suites(params),
+        };
+        let cached_test_lists = self.split_cache.lock().unwrap().get(&cache_key).cloned();
+        if let Some(test_lists) = cached_test_lists {
+            event!(
+                Level::INFO,
+                "Reusing split of suite: {} for task: {}",
+                cache_key.suite_name,
+                params.task_name
+            );
+            return Ok(test_lists
+                .into_iter()
+                .enumerate()
+                .map(|(index, test_list)| SubSuite {
+                    index,
+                    name: multiversion_name.unwrap_or(&params.task_name).to_string(),
+                    expected_runtime: expected_runtime(&test_list, task_history.as_ref()),
+                    test_list,
+                    origin_suite: cache_key.suite_name.clone(),
+                    exclude_test_list: None,
+                    mv_exclude_tags: multiversion_tags.clone(),
+                    is_enterprise: params.is_enterprise,
+                    platform: params.platform.clone(),
+                })
+                .collect());
+        }
+
+        // If we couldn't get the task history, then fallback to splitting the tests evenly
+        // among the desired number of sub-suites.
+        let mut sub_suites = match &task_history {
+            _ if params.consistent_split => self.split_task_consistently(
+                params,
+                task_history.as_ref(),
+                multiversion_name,
+                multiversion_tags,
+            )?,
+            Some(task_history) => {
+                self.split_task(params, task_history, multiversion_name, multiversion_tags)?
+            }
+            None => self.split_task_fallback(params, multiversion_name, multiversion_tags)?,
+        };
+
+        for sub_suite in sub_suites.iter_mut() {
+            sub_suite.expected_runtime =
+                expected_runtime(&sub_suite.test_list, task_history.as_ref());
+        }
+
+        self.split_cache.lock().unwrap().insert(
+            cache_key,
+            sub_suites.iter().map(|s| s.test_list.clone()).collect(),
+        );
         Ok(sub_suites)
     }
+
+    /// Choose the maximum resmoke jobs of the given task from the CPUs of its distro.
+    ///
+    /// If the number of CPUs on the distro is not known, or the suite configuration cannot be
+    /// read, the parameters are left unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - Parameters for how task should be generated.
+    ///
+    /// # Returns
+    ///
+    /// Parameters with the maximum resmoke jobs to run sub-tasks with.
+    fn with_dynamic_resmoke_jobs(&self, params: &ResmokeGenParams) -> ResmokeGenParams {
+        let mut params = params.clone();
+        if let Some(cpu_count) = params.distro_cpu_count {
+            match self.test_discovery.get_suite_config(&params.suite_name) {
+                Ok(suite_config) => {
+                    params.resmoke_jobs_max = Some(dynamic_resmoke_jobs(
+                        cpu_count,
+                        suite_config.processes_per_job(),
+                        params.resmoke_jobs_max,
+                    ));
+                }
+                Err(err) => warn!(
+                    task_name = params.task_name.as_str(),
+                    suite = params.suite_name.as_str(),
+                    error = err.to_string().as_str(),
+                    "Could not read suite configuration, using static resmoke jobs",
+                ),
+            }
+        }
+        params
+    }
+}
+
+/// Determine the maximum resmoke jobs to run on a host with the given number of CPUs.
+///
+/// Each job is given a CPU for every process its fixture starts. The result is never more than
+/// the `resmoke_jobs_max` configured on the task.
+///
+/// # Arguments
+///
+/// * `cpu_count` - Number of CPUs on the host.
+/// * `processes_per_job` - Number of processes each resmoke job starts.
+/// * `static_jobs_max` - Maximum resmoke jobs configured on the task.
+///
+/// # Returns
+///
+/// Maximum resmoke jobs to run.
+fn dynamic_resmoke_jobs(
+    cpu_count: u64,
+    processes_per_job: u64,
+    static_jobs_max: Option<u64>,
+) -> u64 {
+    let jobs = (cpu_count / processes_per_job.max(1)).max(1);
+    static_jobs_max.map_or(jobs, |jobs_max| jobs.min(jobs_max))
+}
+
+/// Hash the given task history so splits based on the same history can be identified.
+///
+/// # Arguments
+///
+/// * `task_history` - Task history to hash.
+///
+/// # Returns
+///
+/// Hash of the test runtimes in the task history.
+fn hash_task_history(task_history: &TaskRuntimeHistory) -> u64 {
+    let mut test_runtimes: Vec<(&String, u64)> = task_history
+        .test_map
+        .iter()
+        .map(|(test_name, test_history)| (test_name, test_history.average_runtime.to_bits()))
+        .collect();
+    test_runtimes.sort();
+
+    let mut hasher = DefaultHasher::new();
+    test_runtimes.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Calculate the expected runtime of the given tests from their historic runtimes.
+///
+/// Tests without historic runtimes are not counted.
+///
+/// # Arguments
+///
+/// * `test_list` - Tests to calculate the expected runtime of.
+/// * `task_stats` - Statistics on the historic runtimes of tests, if available.
+///
+/// # Returns
+///
+/// Expected runtime of the tests in seconds, if historic runtimes are available.
+fn expected_runtime(test_list: &[String], task_stats: Option<&TaskRuntimeHistory>) -> Option<f64> {
+    task_stats.map(|stats| {
+        test_list
+            .iter()
+            .filter_map(|test| stats.test_map.get(&get_test_name(test)))
+            .map(|test_stats| test_stats.average_runtime)
+            .sum()
+    })
+}
+
+/// Find the sub-suites expected to run longer than the given maximum runtime.
+///
+/// Sub-suites without an expected runtime are not reported.
+///
+/// # Arguments
+///
+/// * `sub_suites` - Sub-suites to check.
+/// * `max_runtime_secs` - Maximum runtime sub-suites should finish in, in seconds.
+///
+/// # Returns
+///
+/// Sub-suites whose expected runtime exceeds the maximum runtime.
+fn sub_suites_over_runtime(sub_suites: &[SubSuite], max_runtime_secs: u64) -> Vec<&SubSuite> {
+    sub_suites
+        .iter()
+        .filter(|sub_suite| {
+            sub_suite
+                .expected_runtime
+                .map_or(false, |runtime| runtime > max_runtime_secs as f64)
+        })
+        .collect()
+}
+
+/// Split the given tests into sub-suites by consistent hashing of their paths.
+///
+/// Each test ranks the sub-suites by a hash of its path and the sub-suite index and is placed in
+/// the highest ranked sub-suite that has room for it. A sub-suite has room while its runtime
+/// stays within `CONSISTENT_SPLIT_SLACK` of an even share of the total runtime. Tests without
+/// historic runtimes are given the average runtime of the tests with one.
+///
+/// # Arguments
+///
+/// * `test_list` - Tests to split.
+/// * `task_stats` - Statistics on the historic runtimes of tests, if available.
+/// * `n_suites` - Number of sub-suites to split the tests into.
+///
+/// # Returns
+///
+/// Sorted list of tests for each sub-suite.
+fn split_tests_consistently(
+    test_list: Vec<String>,
+    task_stats: Option<&TaskRuntimeHistory>,
+    n_suites: usize,
+) -> Vec<Vec<String>> {
+    let known_runtimes: Vec<Option<f64>> = test_list
+        .iter()
+        .map(|test| {
+            task_stats
+                .and_then(|stats| stats.test_map.get(&get_test_name(test)))
+                .map(|test_stats| test_stats.average_runtime)
+        })
+        .collect();
+    let known: Vec<f64> = known_runtimes.iter().flatten().copied().collect();
+    let default_runtime = if known.is_empty() {
+        1.0
+    } else {
+        known.iter().sum::<f64>() / known.len() as f64
+    };
+
+    let mut weighted_tests: Vec<(String, f64)> = test_list
+        .into_iter()
+        .zip(known_runtimes)
+        .map(|(test, runtime)| (test, runtime.unwrap_or(default_runtime)))
+        .collect();
+    weighted_tests.sort_by(|(test_a, runtime_a), (test_b, runtime_b)| {
+        runtime_b
+            .partial_cmp(runtime_a)
+            .unwrap()
+            .then_with(|| test_a.cmp(test_b))
+    });
+
+    let total_runtime: f64 = weighted_tests.iter().map(|(_, runtime)| runtime).sum();
+    let capacity = total_runtime / n_suites as f64 * (1.0 + CONSISTENT_SPLIT_SLACK);
+    let mut running_tests = vec![vec![]; n_suites];
+    let mut running_runtimes = vec![0.0; n_suites];
+    for (test, runtime) in weighted_tests {
+        let mut ranked_suites: Vec<usize> = (0..n_suites).collect();
+        ranked_suites.sort_by_key(|index| Reverse(rendezvous_score(&test, *index)));
+        let index = ranked_suites
+            .into_iter()
+            .find(|index| running_runtimes[*index] + runtime <= capacity)
+            .unwrap_or_else(|| get_min_index(&running_runtimes));
+        running_runtimes[index] += runtime;
+        running_tests[index].push(test);
+    }
+
+    for tests in running_tests.iter_mut() {
+        tests.sort();
+    }
+    running_tests
+}
+
+/// Score a sub-suite for a test when splitting by consistent hashing.
+///
+/// # Arguments
+///
+/// * `test` - Path of test being placed.
+/// * `index` - Index of sub-suite being scored.
+///
+/// # Returns
+///
+/// Score of the sub-suite, the highest scoring sub-suite is preferred.
+fn rendezvous_score(test: &str, index: usize) -> u64 {
+    let digest = Sha256::digest(format!("{}:{}", test, index).as_bytes());
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(bytes)
 }
 
 /// Sort tests by historic runtime descending.
 ///
-/// Tests without historic runtime data will be placed at the end of the list.
+/// Tests without historic runtime data are sorted by their estimated runtime. Tests without
+/// either will be placed at the end of the list.
 ///
 /// # Arguments
 ///
 /// * `test_list` - List of tests.
 /// * `task_stats` - Historic task stats.
+/// * `estimated_runtimes` - Estimated runtimes of tests without historic runtime data.
 ///
 /// # Returns
 ///
@@ -653,32 +1212,52 @@ impl GenResmokeTaskServiceImpl {
 fn sort_tests_by_runtime(
     test_list: Vec<String>,
     task_stats: &TaskRuntimeHistory,
+    estimated_runtimes: &HashMap<String, f64>,
 ) -> Vec<std::string::String> {
+    let runtime = |test_file: &String| {
+        task_stats
+            .test_map
+            .get(&get_test_name(test_file))
+            .map(|test_stats| test_stats.average_runtime)
+            .or_else(|| estimated_runtimes.get(test_file).copied())
+            .unwrap_or(0.0)
+    };
     let mut sorted_test_list = test_list;
     sorted_test_list.sort_by(|test_file_a, test_file_b| {
-        let test_name_a = get_test_name(test_file_a);
-        let test_name_b = get_test_name(test_file_b);
-        let default_runtime = TestRuntimeHistory {
-            test_name: "default".to_string(),
-            average_runtime: 0.0,
-            hooks: vec![],
-        };
-        let runtime_history_a = task_stats
-            .test_map
-            .get(&test_name_a)
-            .unwrap_or(&default_runtime);
-        let runtime_history_b = task_stats
-            .test_map
-            .get(&test_name_b)
-            .unwrap_or(&default_runtime);
-        runtime_history_b
-            .average_runtime
-            .partial_cmp(&runtime_history_a.average_runtime)
+        runtime(test_file_b)
+            .partial_cmp(&runtime(test_file_a))
             .unwrap()
     });
     sorted_test_list
 }
 
+/// Split tests into the given number of sub-suites with similar total runtimes.
+///
+/// Each test is placed into the sub-suite with the least total runtime so far, so tests should be
+/// given longest first.
+///
+/// # Arguments
+///
+/// * `tests` - Tests to split with their runtimes, sorted by runtime descending.
+/// * `n_suites` - Number of sub-suites to split the tests into.
+///
+/// # Returns
+///
+/// Tests in each sub-suite and the total runtime of each sub-suite.
+pub(crate) fn split_by_runtime(
+    tests: Vec<(String, f64)>,
+    n_suites: usize,
+) -> (Vec<Vec<String>>, Vec<f64>) {
+    let mut running_tests = vec![vec![]; n_suites];
+    let mut running_runtimes = vec![0.0; n_suites];
+    for (test, runtime) in tests {
+        let min_idx = get_min_index(&running_runtimes);
+        running_runtimes[min_idx] += runtime;
+        running_tests[min_idx].push(test);
+    }
+    (running_tests, running_runtimes)
+}
+
 /// Get the index of sub suite with the least total runtime of tests.
 ///
 /// # Arguments
@@ -715,6 +1294,7 @@ impl GenResmokeTaskService for GenResmokeTaskServiceImpl {
         params: &ResmokeGenParams,
         build_variant: &str,
     ) -> Result<Box<dyn GeneratedSuite>> {
+        let params = &self.with_dynamic_resmoke_jobs(params);
         let sub_suites = if params.require_multiversion_generate_tasks {
             self.create_multiversion_tasks(params, build_variant)
                 .await?
@@ -722,21 +1302,67 @@ impl GenResmokeTaskService for GenResmokeTaskServiceImpl {
             self.create_tasks(params, build_variant, None, None).await?
         };
 
+        if let Some(max_runtime) = params.max_task_runtime_secs {
+            for sub_suite in sub_suites_over_runtime(&sub_suites, max_runtime) {
+                warn!(
+                    build_variant = build_variant,
+                    task_name = params.task_name.as_str(),
+                    sub_task = sub_suite.index,
+                    sub_task_count = sub_suites.len(),
+                    expected_runtime = sub_suite.expected_runtime.unwrap_or_default(),
+                    max_runtime = max_runtime,
+                    "Generated sub-task is expected to exceed the maximum task runtime of the build variant",
+                );
+            }
+        }
+
+        if sub_suites.is_empty() {
+            if self.config.empty_suite_fallback {
+                warn!(
+                    build_variant = build_variant,
+                    task_name = params.task_name.as_str(),
+                    suite = params.suite_name.as_str(),
+                    "No tests found to split, running the original suite",
+                );
+                return Ok(self.build_unsplit_resmoke_task(params));
+            }
+            warn!(
+                build_variant = build_variant,
+                task_name = params.task_name.as_str(),
+                suite = params.suite_name.as_str(),
+                "No tests found to split, the task will not be generated",
+            );
+        }
+
         let sub_task_total = sub_suites.len();
         let suite_info = ResmokeSuiteGenerationInfo {
             task_name: params.task_name.to_string(),
             origin_suite: params.suite_name.to_string(),
             sub_suites: sub_suites.clone(),
             require_multiversion_generate_tasks: params.require_multiversion_generate_tasks,
+            suite_dir: self.config.suite_dir(&params.task_name),
+            task_naming: params.task_naming.clone(),
         };
-        let mut resmoke_config_actor = self.resmoke_config_actor.lock().await;
-        resmoke_config_actor.write_sub_suite(&suite_info).await;
+        let writes_suite_files = lookup_test_runner_backend(params.test_runner.as_deref())
+            .map_or(true, |backend| backend.writes_suite_files());
+        if writes_suite_files {
+            let mut resmoke_config_actor = self.resmoke_config_actor.lock().await;
+            resmoke_config_actor.write_sub_suite(&suite_info).await;
+        }
 
         Ok(Box::new(GeneratedResmokeSuite {
             task_name: params.task_name.clone(),
             sub_suites: sub_suites
                 .into_iter()
-                .map(|s| self.build_resmoke_sub_task(&s, sub_task_total, params, None))
+                .map(|s| {
+                    let sub_task = self.build_resmoke_sub_task(&s, sub_task_total, params, None);
+                    self.ownership_service.record_sub_task(
+                        &params.task_name,
+                        &sub_task.evg_task.name,
+                        &s.test_list,
+                    );
+                    sub_task
+                })
                 .collect(),
         }))
     }
@@ -761,7 +1387,7 @@ impl GenResmokeTaskService for GenResmokeTaskServiceImpl {
         let exclude_tags = self
             .multiversion_service
             .exclude_tags_for_task(&params.task_name, sub_suite.mv_exclude_tags.clone());
-        let suite_file = name_generated_task(
+        let suite_file = params.task_naming.sub_task_name(
             &sub_suite.name,
             sub_suite.index,
             total_sub_suites,
@@ -769,8 +1395,21 @@ impl GenResmokeTaskService for GenResmokeTaskServiceImpl {
             params.platform.as_deref(),
         );
 
-        let run_test_vars =
-            params.build_run_test_vars(&suite_file, sub_suite, &exclude_tags, suite_override);
+        let suite_path = match self.config.suite_dir(&params.task_name) {
+            Some(suite_dir) => format!("{}/{}", suite_dir, suite_file),
+            None => suite_file.clone(),
+        };
+
+        let backend = lookup_test_runner_backend(params.test_runner.as_deref())
+            .expect("Test runner should be validated when the task is read");
+        let run_test_vars = backend.build_run_test_vars(
+            params,
+            sub_suite,
+            total_sub_suites,
+            &suite_path,
+            &exclude_tags,
+            suite_override,
+        );
 
         let formatted_name = format!(
             "{}{}",
@@ -781,26 +1420,293 @@ impl GenResmokeTaskService for GenResmokeTaskServiceImpl {
             evg_task: EvgTask {
                 name: formatted_name,
                 commands: Some(resmoke_commands(
-                    RUN_GENERATED_TESTS,
+                    backend.run_tests_fn_name(&params.function_names),
                     run_test_vars,
                     params.require_multiversion_setup,
+                    &params.command_hooks,
                 )),
                 depends_on: params.get_dependencies(),
+                tags: Some(build_origin_tags(
+                    &params.task_name,
+                    Some(&sub_suite.origin_suite),
+                    sub_suite.mv_exclude_tags.as_deref(),
+                )),
+                priority: params.priority,
+                patchable: params.patchable,
+                disable: params.disable,
+                allowed_requesters: params.allowed_requesters.clone(),
+                exec_timeout_secs: sub_task_exec_timeout(
+                    params.task_exec_timeout_secs,
+                    params.scale_exec_timeout,
+                    total_sub_suites,
+                ),
+                stepback: if params.disable_stepback {
+                    Some(false)
+                } else {
+                    None
+                },
                 ..Default::default()
             },
             use_large_distro: params.use_large_distro,
             use_xlarge_distro: params.use_xlarge_distro,
+            distro_tier: params.distro_tier.clone(),
+            expected_runtime_secs: sub_suite.expected_runtime,
         }
     }
+
+    /// Build a task that runs the original suite in a single sub-task without splitting it.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - Parameters for how task should be generated.
+    ///
+    /// # Returns
+    ///
+    /// A generated suite with a single sub-task running the entire suite.
+    fn build_unsplit_resmoke_task(&self, params: &ResmokeGenParams) -> Box<dyn GeneratedSuite> {
+        let sub_suite = SubSuite {
+            index: 0,
+            name: params.task_name.to_string(),
+            origin_suite: params.suite_name.to_string(),
+            is_enterprise: params.is_enterprise,
+            platform: params.platform.clone(),
+            ..Default::default()
+        };
+
+        Box::new(GeneratedResmokeSuite {
+            task_name: params.task_name.clone(),
+            sub_suites: vec![self.build_resmoke_sub_task(
+                &sub_suite,
+                1,
+                params,
+                Some(params.suite_name.to_string()),
+            )],
+        })
+    }
 }
 
-/// Create a list of commands to run a resmoke task in evergreen.
-///
-/// # Arguments
-///
+/// Generated task kind for resmoke tasks.
+pub struct ResmokeTaskKind {
+    /// Utilities to work with evergreen project configuration.
+    evg_config_utils: Arc<dyn EvgConfigUtils>,
+    /// Service to extraction configuration from evergreen config.
+    config_extraction_service: Arc<dyn ConfigExtractionService>,
+    /// Service for generating resmoke tasks.
+    gen_resmoke_service: Arc<dyn GenResmokeTaskService>,
+    /// Run the unsplit suite instead of failing when a task cannot be split.
+    allow_partial: bool,
+    /// Number of sub-tasks to split tasks into if the build variant does not override it.
+    default_sub_tasks: usize,
+}
+
+impl ResmokeTaskKind {
+    /// Name of this kind of generated task.
+    pub const NAME: &'static str = "resmoke";
+
+    /// Create a new instance of the resmoke task kind.
+    ///
+    /// # Arguments
+    ///
+    /// * `evg_config_utils` - Utilities to work with evergreen project configuration.
+    /// * `config_extraction_service` - Service to extraction configuration from evergreen config.
+    /// * `gen_resmoke_service` - Service for generating resmoke tasks.
+    /// * `allow_partial` - Run the unsplit suite instead of failing when a task cannot be split.
+    /// * `default_sub_tasks` - Number of sub-tasks to split tasks into if the build variant does
+    ///   not override it.
+    pub fn new(
+        evg_config_utils: Arc<dyn EvgConfigUtils>,
+        config_extraction_service: Arc<dyn ConfigExtractionService>,
+        gen_resmoke_service: Arc<dyn GenResmokeTaskService>,
+        allow_partial: bool,
+        default_sub_tasks: usize,
+    ) -> Self {
+        Self {
+            evg_config_utils,
+            config_extraction_service,
+            gen_resmoke_service,
+            allow_partial,
+            default_sub_tasks,
+        }
+    }
+
+    /// Build the parameters to generate a resmoke task for the given task definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition to base generated task on.
+    /// * `build_variant` - Build Variant to base generated task on.
+    ///
+    /// # Returns
+    ///
+    /// Parameters to generate the resmoke task with.
+    fn build_params(
+        &self,
+        task_def: &EvgTask,
+        build_variant: &BuildVariant,
+    ) -> Result<ResmokeGenParams> {
+        let is_enterprise = self
+            .evg_config_utils
+            .is_enterprise_build_variant(build_variant);
+        let platform = self
+            .evg_config_utils
+            .infer_build_variant_platform(build_variant);
+        event!(
+            Level::INFO,
+            "Generating resmoke task: {}, is_enterprise: {}, platform: {}",
+            task_def.name,
+            is_enterprise,
+            platform
+        );
+        self.config_extraction_service.task_def_to_resmoke_params(
+            task_def,
+            is_enterprise,
+            Some(build_variant),
+            Some(platform),
+        )
+    }
+}
+
+#[async_trait]
+impl GeneratedTaskKind for ResmokeTaskKind {
+    /// Name of this kind of generated task.
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    /// Resmoke tasks are split based on the runtime history of their tests.
+    fn uses_task_history(&self) -> bool {
+        true
+    }
+
+    /// Resmoke tasks are split by the sub-task limits of the build variant.
+    fn uses_sub_task_limits(&self) -> bool {
+        true
+    }
+
+    /// Resmoke tasks are split once, or once for each multiversion configuration of tasks that
+    /// generate multiversion sub-tasks. Each split wants the number of sub-tasks set by the
+    /// limits of the build variant.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition to estimate.
+    /// * `build_variant` - Build variant the task is generated for.
+    ///
+    /// # Returns
+    ///
+    /// Sub-tasks the generated task is expected to need.
+    fn sub_task_demand(&self, task_def: &EvgTask, build_variant: &BuildVariant) -> SubTaskDemand {
+        let tags = self.evg_config_utils.get_task_tags(task_def);
+        let split_tasks =
+            if tags.contains(MULTIVERSION) && !tags.contains(NO_MULTIVERSION_GENERATE_TASKS) {
+                self.config_extraction_service
+                    .determine_multiversion_generate_tasks(task_def, build_variant)
+                    .map_or(1, |configs| configs.len())
+            } else {
+                1
+            };
+
+        // Invalid limits fail generation when the task is split, so they are ignored here.
+        let desired_sub_tasks = self
+            .config_extraction_service
+            .determine_sub_task_limits(build_variant)
+            .map_or(self.default_sub_tasks, |limits| {
+                limits.desired_sub_tasks(self.default_sub_tasks)
+            });
+
+        SubTaskDemand {
+            fixed_sub_tasks: 0,
+            split_tasks,
+            desired_split_sub_tasks: split_tasks * desired_sub_tasks,
+        }
+    }
+
+    /// Generate a resmoke task for the given task definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition to base generated task on.
+    /// * `build_variant` - Build Variant to base generated task on.
+    ///
+    /// # Returns
+    ///
+    /// Configuration for a generated resmoke task.
+    async fn generate(
+        &self,
+        task_def: &EvgTask,
+        build_variant: &BuildVariant,
+    ) -> Result<Box<dyn GeneratedSuite>> {
+        let params = self.build_params(task_def, build_variant)?;
+
+        match self
+            .gen_resmoke_service
+            .generate_resmoke_task(&params, &build_variant.name)
+            .await
+        {
+            Err(err) if self.allow_partial => {
+                event!(
+                    Level::WARN,
+                    task = task_def.name.as_str(),
+                    build_variant = build_variant.name.as_str(),
+                    error = format!("{:?}", err).as_str(),
+                    "Failed to split task, running the unsplit suite instead"
+                );
+                Ok(self.gen_resmoke_service.build_unsplit_resmoke_task(&params))
+            }
+            result => result,
+        }
+    }
+
+    /// Generate a resmoke task running the unsplit suite of the given task definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_def` - Task definition to base generated task on.
+    /// * `build_variant` - Build Variant to base generated task on.
+    ///
+    /// # Returns
+    ///
+    /// Configuration for a resmoke task running the whole suite.
+    fn generate_unsplit(
+        &self,
+        task_def: &EvgTask,
+        build_variant: &BuildVariant,
+    ) -> Result<Option<Box<dyn GeneratedSuite>>> {
+        let params = self.build_params(task_def, build_variant)?;
+        Ok(Some(
+            self.gen_resmoke_service.build_unsplit_resmoke_task(&params),
+        ))
+    }
+}
+
+/// Build the vars telling a sub-task which of the sub-tasks of its task it is.
+///
+/// # Arguments
+///
+/// * `sub_task_index` - Index of the sub-task.
+/// * `total_sub_tasks` - Number of sub-tasks the task was split into.
+///
+/// # Returns
+///
+/// Map of vars to pass to the run tests function.
+pub(crate) fn sub_task_index_vars(
+    sub_task_index: usize,
+    total_sub_tasks: usize,
+) -> HashMap<String, ParamValue> {
+    hashmap! {
+        SUB_TASK_INDEX.to_string() => ParamValue::from(sub_task_index as u64),
+        TOTAL_SUB_TASKS.to_string() => ParamValue::from(total_sub_tasks as u64),
+    }
+}
+
+/// Create a list of commands to run a resmoke task in evergreen.
+///
+/// # Arguments
+///
 /// * `run_test_fn_name` - Name of function to run tests.
 /// * `run_test_vars` - Variable to pass to the run tests function.
 /// * `requires_multiversion` - Does this task require multiversion setup.
+/// * `command_hooks` - Functions to call around the commands of the task.
 ///
 /// # Returns
 ///
@@ -809,8 +1715,13 @@ fn resmoke_commands(
     run_test_fn_name: &str,
     run_test_vars: HashMap<String, ParamValue>,
     requires_multiversion_setup: bool,
+    command_hooks: &CommandHooks,
 ) -> Vec<EvgCommand> {
-    let mut commands = vec![];
+    let mut commands: Vec<EvgCommand> = command_hooks
+        .prepend
+        .iter()
+        .map(|func| fn_call(func))
+        .collect();
 
     if requires_multiversion_setup {
         commands.push(fn_call(GET_PROJECT_WITH_NO_MODULES));
@@ -825,6 +1736,7 @@ fn resmoke_commands(
     }
 
     commands.push(fn_call_with_params(run_test_fn_name, run_test_vars));
+    commands.extend(command_hooks.append.iter().map(|func| fn_call(func)));
     commands
 }
 
@@ -833,13 +1745,17 @@ mod tests {
     use rstest::rstest;
 
     use crate::{
-        evergreen::evg_task_history::TestRuntimeHistory,
+        evergreen::evg_task_history::{HistoryFetchStats, TestRuntimeHistory},
+        ownership::{OwnershipServiceImpl, TestOwnershipConfig},
         resmoke::{resmoke_proxy::MultiversionConfig, resmoke_suite::ResmokeSuiteConfig},
     };
 
     use super::*;
 
     const MOCK_ENTERPRISE_DIR: &str = "src/enterprise";
+    const MOCK_MODULE: &str = "streams";
+    const MOCK_COVERED_TEST: &str = "jstests/covered.js";
+    const MOCK_MODULE_DIR: &str = "src/modules/streams";
 
     // ResmokeGenParams tests.
     #[test]
@@ -857,10 +1773,12 @@ mod tests {
             ..Default::default()
         };
 
-        let test_vars = params.build_run_test_vars("my_suite_0", &sub_suite, "", None);
+        let test_vars = params.build_run_test_vars("my_suite_0", &sub_suite, 1, "", None);
 
-        assert_eq!(test_vars.len(), 4);
+        assert_eq!(test_vars.len(), 6);
         assert!(!test_vars.contains_key("resmoke_jobs_max"));
+        assert_eq!(test_vars[SUB_TASK_INDEX], ParamValue::from(0));
+        assert_eq!(test_vars[TOTAL_SUB_TASKS], ParamValue::from(1));
         assert_eq!(
             test_vars.get("suite").unwrap(),
             &ParamValue::from("generated_resmoke_config/my_suite_0.yml")
@@ -884,9 +1802,9 @@ mod tests {
             ..Default::default()
         };
 
-        let test_vars = params.build_run_test_vars("my_suite_0", &sub_suite, "", None);
+        let test_vars = params.build_run_test_vars("my_suite_0", &sub_suite, 1, "", None);
 
-        assert_eq!(test_vars.len(), 5);
+        assert_eq!(test_vars.len(), 7);
         assert_eq!(
             test_vars.get("resmoke_jobs_max").unwrap(),
             &ParamValue::from(5)
@@ -916,16 +1834,16 @@ mod tests {
         };
 
         let test_vars =
-            params.build_run_test_vars("my_suite_0", &sub_suite, "tag_0,tag_1,tag_2", None);
+            params.build_run_test_vars("my_suite_0", &sub_suite, 1, "tag_0,tag_1,tag_2", None);
 
-        assert_eq!(test_vars.len(), 5);
+        assert_eq!(test_vars.len(), 7);
         assert_eq!(
             test_vars.get("multiversion_exclude_tags_version").unwrap(),
             &ParamValue::from("last_lts")
         );
         assert_eq!(
             test_vars.get("resmoke_args").unwrap(),
-            &ParamValue::from("--originSuite=my_origin_suite  --tagFile=generated_resmoke_config/multiversion_exclude_tags.yml --excludeWithAnyTags=tag_0,tag_1,tag_2 resmoke args")
+            &ParamValue::from("--originSuite=my_origin_suite  --tagFile=generated_resmoke_config/multiversion_exclude_tags_last_lts.yml --excludeWithAnyTags=tag_0,tag_1,tag_2 resmoke args")
         );
     }
 
@@ -945,9 +1863,9 @@ mod tests {
             ..Default::default()
         };
 
-        let test_vars = params.build_run_test_vars("my_suite_0", &sub_suite, "", None);
+        let test_vars = params.build_run_test_vars("my_suite_0", &sub_suite, 1, "", None);
 
-        assert_eq!(test_vars.len(), 5);
+        assert_eq!(test_vars.len(), 7);
         assert_eq!(
             test_vars.get("multiversion_exclude_tags_version").unwrap(),
             &ParamValue::from("last_lts")
@@ -976,9 +1894,9 @@ mod tests {
             ..Default::default()
         };
 
-        let test_vars = params.build_run_test_vars("my_suite_0", &sub_suite, "", None);
+        let test_vars = params.build_run_test_vars("my_suite_0", &sub_suite, 1, "", None);
 
-        assert_eq!(test_vars.len(), 5);
+        assert_eq!(test_vars.len(), 7);
         assert_eq!(
             test_vars.get("multiversion_exclude_tags_version").unwrap(),
             &ParamValue::from("last_lts")
@@ -998,13 +1916,38 @@ mod tests {
             ..Default::default()
         };
 
-        let resmoke_args = params.build_resmoke_args("", "my_origin_suite");
+        let resmoke_args = params.build_resmoke_args("", "my_origin_suite", None);
 
         assert!(resmoke_args.contains("--originSuite=my_origin_suite"));
         assert!(resmoke_args.contains("--args to --pass to resmoke"));
         assert!(resmoke_args.contains("--repeatSuites=3"));
     }
 
+    #[rstest]
+    #[case(None, "multiversion_exclude_tags_last_lts.yml")]
+    #[case(Some("last_lts"), "multiversion_exclude_tags_last_lts.yml")]
+    #[case(
+        Some("last_continuous"),
+        "multiversion_exclude_tags_last_continuous.yml"
+    )]
+    fn test_build_resmoke_args_should_use_tag_file_for_old_version(
+        #[case] old_version: Option<&str>,
+        #[case] expected_tag_file: &str,
+    ) {
+        let params = ResmokeGenParams {
+            suite_name: "my_suite".to_string(),
+            require_multiversion_setup: true,
+            ..Default::default()
+        };
+
+        let resmoke_args = params.build_resmoke_args("tag_0", "my_origin_suite", old_version);
+
+        assert!(resmoke_args.contains(&format!(
+            "--tagFile=generated_resmoke_config/{} --excludeWithAnyTags=tag_0",
+            expected_tag_file
+        )));
+    }
+
     // GeneratedResmokeSuite tests
     #[rstest]
     #[case(vec![false, false, false])]
@@ -1029,11 +1972,13 @@ mod tests {
                     },
                     use_large_distro: *value,
                     use_xlarge_distro: false,
+                    distro_tier: None,
+                    expected_runtime_secs: None,
                 })
                 .collect(),
         };
 
-        let task_refs = gen_suite.build_task_ref(Some(distro.clone()));
+        let task_refs = gen_suite.build_task_ref(Some(distro.clone()), None, false);
 
         for (i, task) in task_refs.iter().enumerate() {
             assert_eq!(task.name, format!("sub_suite_name_{}", i));
@@ -1046,6 +1991,54 @@ mod tests {
         }
     }
 
+    #[rstest]
+    #[case(false, Some(vec!["task_ref_distro".to_string()]))]
+    #[case(true, Some(vec!["large_distro".to_string()]))]
+    fn test_build_task_ref_should_use_task_ref_distros_when_no_large_distro(
+        #[case] use_large_distro: bool,
+        #[case] expected_distros: Option<Vec<String>>,
+    ) {
+        let gen_suite = GeneratedResmokeSuite {
+            task_name: "my_task".to_string(),
+            sub_suites: vec![GeneratedSubTask {
+                evg_task: EvgTask {
+                    name: "sub_suite_name_0".to_string(),
+                    ..Default::default()
+                },
+                use_large_distro,
+                ..Default::default()
+            }],
+        };
+
+        let task_refs = gen_suite.build_task_ref(
+            Some("large_distro".to_string()),
+            Some(vec!["task_ref_distro".to_string()]),
+            false,
+        );
+
+        assert_eq!(task_refs[0].distros, expected_distros);
+    }
+
+    #[rstest]
+    #[case(true)]
+    #[case(false)]
+    fn test_build_task_ref_should_set_activate(#[case] activate: bool) {
+        let gen_suite = GeneratedResmokeSuite {
+            task_name: "my_task".to_string(),
+            sub_suites: vec![GeneratedSubTask {
+                evg_task: EvgTask {
+                    name: "sub_suite_name_0".to_string(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }],
+        };
+
+        let task_refs = gen_suite.build_task_ref(None, None, activate);
+
+        assert_eq!(task_refs[0].activate, Some(activate));
+    }
+
     // split_task tests
     struct MockTaskHistoryService {
         task_history: TaskRuntimeHistory,
@@ -1064,6 +2057,20 @@ mod tests {
         ) -> Result<TaskRuntimeHistory> {
             Ok(self.task_history.clone())
         }
+
+        async fn get_bazel_target_history(
+            &self,
+            _task: &str,
+            _variant: &str,
+        ) -> Result<HashMap<String, f64>> {
+            todo!()
+        }
+
+        async fn prefetch_task_histories(&self, _task_variants: Vec<(String, String)>) {}
+
+        fn fetch_stats(&self) -> HistoryFetchStats {
+            HistoryFetchStats::default()
+        }
     }
 
     struct MockTestDiscovery {
@@ -1075,6 +2082,12 @@ mod tests {
             Ok(self.test_list.clone())
         }
 
+        fn discover_all_tests(&self, _suite_name: &str) -> Result<Vec<String>> {
+            let mut test_list = self.test_list.clone();
+            test_list.push(MOCK_COVERED_TEST.to_string());
+            Ok(test_list)
+        }
+
         fn get_suite_config(&self, _suite_name: &str) -> Result<ResmokeSuiteConfig> {
             todo!()
         }
@@ -1082,6 +2095,18 @@ mod tests {
         fn get_multiversion_config(&self) -> Result<MultiversionConfig> {
             todo!()
         }
+
+        fn generate_multiversion_exclude_tags(
+            &self,
+            _old_version: &str,
+            _output_file: &std::path::Path,
+        ) -> Result<()> {
+            todo!()
+        }
+
+        fn skipped_covered_tests(&self) -> std::collections::BTreeMap<String, Vec<String>> {
+            todo!()
+        }
     }
 
     struct MockFsService {}
@@ -1090,6 +2115,27 @@ mod tests {
             true
         }
 
+        fn file_size(&self, _path: &str) -> Option<u64> {
+            None
+        }
+
+        fn write_file(&self, _path: &std::path::Path, _contents: &str) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    struct MockMissingFilesFsService {
+        missing_files: Vec<String>,
+    }
+    impl FsService for MockMissingFilesFsService {
+        fn file_exists(&self, path: &str) -> bool {
+            !self.missing_files.contains(&path.to_string())
+        }
+
+        fn file_size(&self, _path: &str) -> Option<u64> {
+            None
+        }
+
         fn write_file(&self, _path: &std::path::Path, _contents: &str) -> Result<()> {
             Ok(())
         }
@@ -1114,9 +2160,14 @@ mod tests {
             &self,
             multiversion_generate_tasks: Option<Vec<MultiversionGenerateTaskConfig>>,
             _last_versions_expansion: Option<String>,
+            _excluded_versions_expansion: Option<String>,
         ) -> Option<Vec<MultiversionGenerateTaskConfig>> {
             return multiversion_generate_tasks;
         }
+
+        fn old_versions(&self) -> Vec<String> {
+            todo!()
+        }
     }
 
     fn build_mocked_service(
@@ -1132,7 +2183,17 @@ mod tests {
         let fs_service = MockFsService {};
         let resmoke_config_actor = MockResmokeConfigActor {};
 
-        let config = GenResmokeConfig::new(n_suites, false, Some(MOCK_ENTERPRISE_DIR.to_string()));
+        let config = GenResmokeConfig::new(
+            n_suites,
+            false,
+            hashmap! {
+                ENTERPRISE_MODULE.to_string() => MOCK_ENTERPRISE_DIR.to_string(),
+                MOCK_MODULE.to_string() => MOCK_MODULE_DIR.to_string(),
+            },
+            false,
+            false,
+            false,
+        );
 
         GenResmokeTaskServiceImpl::new(
             Arc::new(task_history_service),
@@ -1140,6 +2201,7 @@ mod tests {
             Arc::new(Mutex::new(resmoke_config_actor)),
             Arc::new(multiversion_service),
             Arc::new(fs_service),
+            Arc::new(OwnershipServiceImpl::new(None)),
             config,
         )
     }
@@ -1230,6 +2292,49 @@ mod tests {
         assert_eq!(suite_2.test_list.len(), 4);
     }
 
+    struct MockRuntimeEstimator {
+        estimated_runtimes: HashMap<String, f64>,
+    }
+    impl RuntimeEstimator for MockRuntimeEstimator {
+        fn estimate_runtimes(
+            &self,
+            _test_list: &[String],
+            _task_stats: &TaskRuntimeHistory,
+        ) -> HashMap<String, f64> {
+            self.estimated_runtimes.clone()
+        }
+    }
+
+    #[test]
+    fn test_split_task_should_place_tests_without_history_by_estimated_runtime() {
+        let n_suites = 2;
+        let test_list: Vec<String> = (0..4).map(|i| format!("test_{}.js", i)).collect();
+        let task_history = TaskRuntimeHistory {
+            task_name: "my task".to_string(),
+            test_map: hashmap! {
+                "test_0".to_string() => build_mock_test_runtime("test_0.js", 100.0),
+                "test_1".to_string() => build_mock_test_runtime("test_1.js", 10.0),
+                "test_2".to_string() => build_mock_test_runtime("test_2.js", 10.0),
+            },
+        };
+        let gen_resmoke_service = build_mocked_service(test_list, task_history.clone(), n_suites)
+            .with_runtime_estimator(Arc::new(MockRuntimeEstimator {
+                estimated_runtimes: hashmap! {
+                    "test_3.js".to_string() => 90.0,
+                },
+            }));
+
+        let sub_suites = gen_resmoke_service
+            .split_task(&ResmokeGenParams::default(), &task_history, None, None)
+            .unwrap();
+
+        assert_eq!(sub_suites.len(), n_suites);
+        assert!(sub_suites[0].test_list.contains(&"test_0.js".to_string()));
+        assert!(!sub_suites[0].test_list.contains(&"test_3.js".to_string()));
+        assert_eq!(sub_suites[0].test_list.len(), 2);
+        assert_eq!(sub_suites[1].test_list.len(), 2);
+    }
+
     #[test]
     fn test_split_tasks_should_include_multiversion_information() {
         let n_suites = 3;
@@ -1310,6 +2415,42 @@ mod tests {
         }
     }
 
+    #[rstest]
+    #[case(None, None, 3)]
+    #[case(None, Some(2), 2)]
+    #[case(None, Some(5), 3)]
+    #[case(Some(4), None, 4)]
+    #[case(Some(6), None, 6)]
+    #[case(Some(6), Some(2), 2)]
+    fn test_split_task_fallback_should_respect_sub_task_limits(
+        #[case] sub_tasks_per_task: Option<usize>,
+        #[case] max_sub_tasks: Option<usize>,
+        #[case] expected_sub_suites: usize,
+    ) {
+        let test_list: Vec<String> = (0..12)
+            .map(|i| format!("jstests/core/test_{}.js", i))
+            .collect();
+        let task_history = TaskRuntimeHistory {
+            task_name: "my task".to_string(),
+            test_map: hashmap! {},
+        };
+        let gen_resmoke_service = build_mocked_service(test_list, task_history, 3);
+        let params = ResmokeGenParams {
+            sub_task_limits: SubTaskLimits {
+                sub_tasks_per_task,
+                max_sub_tasks,
+                budget: None,
+            },
+            ..Default::default()
+        };
+
+        let sub_suites = gen_resmoke_service
+            .split_task_fallback(&params, None, None)
+            .unwrap();
+
+        assert_eq!(sub_suites.len(), expected_sub_suites);
+    }
+
     #[test]
     fn test_split_task_fallback_empty_suite() {
         let n_suites = 1;
@@ -1372,6 +2513,86 @@ mod tests {
         assert_eq!(expected_tests, all_tests.len());
     }
 
+    #[rstest]
+    #[case(None, 12)]
+    #[case(Some(vec![]), 6)]
+    #[case(Some(vec![MOCK_MODULE]), 12)]
+    fn test_get_test_list_should_filter_tests_of_modules_not_on_build_variant(
+        #[case] modules: Option<Vec<&str>>,
+        #[case] expected_tests: usize,
+    ) {
+        let mut test_list: Vec<String> = (0..6).map(|i| format!("test_{}.js", i)).collect();
+        test_list.extend((6..12).map(|i| format!("{}/test_{}.js", MOCK_MODULE_DIR, i)));
+        let task_history = TaskRuntimeHistory {
+            task_name: "my task".to_string(),
+            test_map: hashmap! {},
+        };
+        let gen_resmoke_service = build_mocked_service(test_list, task_history, 3);
+        let params = ResmokeGenParams {
+            is_enterprise: true,
+            modules: modules.map(|m| m.iter().map(|s| s.to_string()).collect()),
+            ..Default::default()
+        };
+
+        let test_list = gen_resmoke_service.get_test_list(&params, None).unwrap();
+
+        assert_eq!(test_list.len(), expected_tests);
+    }
+
+    #[rstest]
+    #[case(false, 6)]
+    #[case(true, 7)]
+    fn test_get_test_list_should_include_covered_tests_if_requested(
+        #[case] run_covered_tests: bool,
+        #[case] expected_tests: usize,
+    ) {
+        let test_list: Vec<String> = (0..6).map(|i| format!("test_{}.js", i)).collect();
+        let task_history = TaskRuntimeHistory {
+            task_name: "my task".to_string(),
+            test_map: hashmap! {},
+        };
+        let gen_resmoke_service = build_mocked_service(test_list, task_history, 3);
+        let params = ResmokeGenParams {
+            run_covered_tests,
+            ..Default::default()
+        };
+
+        let test_list = gen_resmoke_service.get_test_list(&params, None).unwrap();
+
+        assert_eq!(test_list.len(), expected_tests);
+        assert_eq!(
+            test_list.contains(&MOCK_COVERED_TEST.to_string()),
+            run_covered_tests
+        );
+    }
+
+    #[rstest]
+    #[case(false, Some(4))]
+    #[case(true, None)]
+    fn test_get_test_list_should_handle_missing_tests(
+        #[case] fail_on_missing_tests: bool,
+        #[case] expected_tests: Option<usize>,
+    ) {
+        let test_list: Vec<String> = (0..6).map(|i| format!("test_{}.js", i)).collect();
+        let task_history = TaskRuntimeHistory {
+            task_name: "my task".to_string(),
+            test_map: hashmap! {},
+        };
+        let mut gen_resmoke_service = build_mocked_service(test_list, task_history, 3);
+        gen_resmoke_service.fs_service = Arc::new(MockMissingFilesFsService {
+            missing_files: vec!["test_1.js".to_string(), "test_4.js".to_string()],
+        });
+        gen_resmoke_service.config.fail_on_missing_tests = fail_on_missing_tests;
+        let params = ResmokeGenParams {
+            suite_name: "my_suite".to_string(),
+            ..Default::default()
+        };
+
+        let test_list = gen_resmoke_service.get_test_list(&params, None);
+
+        assert_eq!(test_list.ok().map(|tests| tests.len()), expected_tests);
+    }
+
     #[rstest]
     #[case(true, 12)]
     #[case(false, 12)]
@@ -1396,7 +2617,7 @@ mod tests {
         };
         let mut gen_resmoke_service =
             build_mocked_service(test_list, task_history.clone(), n_suites);
-        gen_resmoke_service.config.enterprise_dir = None;
+        gen_resmoke_service.config.module_dirs = HashMap::new();
 
         let params = ResmokeGenParams {
             is_enterprise,
@@ -1463,6 +2684,97 @@ mod tests {
             .all(|test| test_list.contains(test)));
     }
 
+    // create_tasks tests.
+    #[tokio::test]
+    async fn test_create_tasks_should_reuse_split_for_same_inputs() {
+        let test_list: Vec<String> = (0..12).map(|i| format!("test_{}.js", i)).collect();
+        let task_history = TaskRuntimeHistory {
+            task_name: "my_task".to_string(),
+            test_map: hashmap! {},
+        };
+        let gen_resmoke_service = build_mocked_service(test_list, task_history, 3);
+        let params_0 = ResmokeGenParams {
+            task_name: "task_0".to_string(),
+            suite_name: "my_suite".to_string(),
+            ..Default::default()
+        };
+        let params_1 = ResmokeGenParams {
+            task_name: "task_1".to_string(),
+            ..params_0.clone()
+        };
+
+        let sub_suites_0 = gen_resmoke_service
+            .create_tasks(&params_0, "bv_0", None, None)
+            .await
+            .unwrap();
+        let sub_suites_1 = gen_resmoke_service
+            .create_tasks(&params_1, "bv_1", None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(sub_suites_0.len(), sub_suites_1.len());
+        for (sub_suite_0, sub_suite_1) in sub_suites_0.iter().zip(sub_suites_1.iter()) {
+            assert_eq!(sub_suite_0.test_list, sub_suite_1.test_list);
+            assert_eq!(sub_suite_1.name, "task_1");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_tasks_should_ignore_history_when_using_fallback_split() {
+        let test_list: Vec<String> = (0..12).map(|i| format!("test_{}.js", i)).collect();
+        let task_history = TaskRuntimeHistory {
+            task_name: "my_task".to_string(),
+            test_map: hashmap! {
+                "test_0".to_string() => build_mock_test_runtime("test_0.js", 1000.0),
+                "test_1".to_string() => build_mock_test_runtime("test_1.js", 10.0),
+            },
+        };
+        let gen_resmoke_service = build_mocked_service(test_list, task_history, 3);
+        let params = ResmokeGenParams {
+            task_name: "my_task".to_string(),
+            suite_name: "my_suite".to_string(),
+            use_fallback_split: true,
+            ..Default::default()
+        };
+
+        let sub_suites = gen_resmoke_service
+            .create_tasks(&params, "bv_0", None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(sub_suites.len(), 3);
+        for sub_suite in &sub_suites {
+            assert_eq!(sub_suite.test_list.len(), 4);
+            assert_eq!(sub_suite.expected_runtime, None);
+        }
+    }
+
+    // hash_task_history tests.
+    #[test]
+    fn test_hash_task_history_should_depend_on_runtimes() {
+        let task_history_0 = TaskRuntimeHistory {
+            task_name: "my_task".to_string(),
+            test_map: hashmap! {
+                "test_0".to_string() => build_mock_test_runtime("test_0.js", 100.0),
+                "test_1".to_string() => build_mock_test_runtime("test_1.js", 50.0),
+            },
+        };
+        let mut task_history_1 = task_history_0.clone();
+        task_history_1.test_map.insert(
+            "test_1".to_string(),
+            build_mock_test_runtime("test_1.js", 51.0),
+        );
+
+        assert_eq!(
+            hash_task_history(&task_history_0),
+            hash_task_history(&task_history_0.clone())
+        );
+        assert_ne!(
+            hash_task_history(&task_history_0),
+            hash_task_history(&task_history_1)
+        );
+    }
+
     // generate_resmoke_task tests.
     #[tokio::test]
     async fn test_generate_resmoke_tasks_standard() {
@@ -1502,6 +2814,235 @@ mod tests {
         assert_eq!(suite.sub_tasks().len(), n_suites);
     }
 
+    #[tokio::test]
+    async fn test_generate_resmoke_tasks_should_record_test_ownership() {
+        let test_list = vec![
+            "jstests/core/query/find.js".to_string(),
+            "jstests/core/insert.js".to_string(),
+        ];
+        let task_history = TaskRuntimeHistory {
+            task_name: "my_task".to_string(),
+            test_map: hashmap! {},
+        };
+        let mut gen_resmoke_service = build_mocked_service(test_list, task_history, 2);
+        let ownership_service = Arc::new(OwnershipServiceImpl::new(Some(
+            serde_yaml::from_str::<TestOwnershipConfig>(
+                "owners:\n  - team: query\n    paths:\n      - jstests/core/query/*\n",
+            )
+            .unwrap(),
+        )));
+        gen_resmoke_service.ownership_service = ownership_service.clone();
+        let params = ResmokeGenParams {
+            task_name: "my_task".to_string(),
+            suite_name: "my_suite".to_string(),
+            ..Default::default()
+        };
+
+        gen_resmoke_service
+            .generate_resmoke_task(&params, "build-variant")
+            .await
+            .unwrap();
+
+        let report = ownership_service.ownership_report();
+        assert_eq!(report["query"].tests, 1);
+        assert_eq!(report["unowned"].tests, 1);
+        assert_eq!(report["query"].display_tasks.len(), 1);
+        assert!(report["query"].display_tasks.contains("my_task"));
+    }
+
+    #[rstest]
+    #[case(false, 0)]
+    #[case(true, 1)]
+    #[tokio::test]
+    async fn test_generate_resmoke_tasks_with_no_tests(
+        #[case] empty_suite_fallback: bool,
+        #[case] expected_sub_tasks: usize,
+    ) {
+        let task_history = TaskRuntimeHistory {
+            task_name: "my_task".to_string(),
+            test_map: hashmap! {},
+        };
+        let mut gen_resmoke_service = build_mocked_service(vec![], task_history, 3);
+        gen_resmoke_service.config.empty_suite_fallback = empty_suite_fallback;
+        let params = ResmokeGenParams {
+            task_name: "my_task".to_string(),
+            suite_name: "my_suite".to_string(),
+            ..Default::default()
+        };
+
+        let suite = gen_resmoke_service
+            .generate_resmoke_task(&params, "build-variant")
+            .await
+            .unwrap();
+
+        assert_eq!(suite.sub_tasks().len(), expected_sub_tasks);
+    }
+
+    // build_unsplit_resmoke_task tests.
+    #[test]
+    fn test_build_unsplit_resmoke_task_should_run_original_suite() {
+        let task_history = TaskRuntimeHistory {
+            task_name: "my_task".to_string(),
+            test_map: hashmap! {},
+        };
+        let gen_resmoke_service = build_mocked_service(vec![], task_history, 3);
+        let params = ResmokeGenParams {
+            task_name: "my_task".to_string(),
+            suite_name: "my_suite".to_string(),
+            platform: Some("linux".to_string()),
+            ..Default::default()
+        };
+
+        let suite = gen_resmoke_service.build_unsplit_resmoke_task(&params);
+
+        let sub_tasks = suite.sub_tasks();
+        assert_eq!(suite.display_name(), "my_task");
+        assert_eq!(sub_tasks.len(), 1);
+        assert_eq!(sub_tasks[0].evg_task.name, "my_task_0-linux");
+        let commands = sub_tasks[0].evg_task.commands.as_ref().unwrap();
+        let run_tests = commands.last().unwrap();
+        if let EvgCommand::Function(function) = run_tests {
+            assert_eq!(
+                function.vars.as_ref().unwrap().get(SUITE_NAME),
+                Some(&ParamValue::from("my_suite"))
+            );
+        } else {
+            panic!("Expected a function call");
+        }
+    }
+
+    #[rstest]
+    #[case(Some(false), None, None)]
+    #[case(None, Some(true), None)]
+    #[case(None, None, Some(vec!["patch_request".to_string()]))]
+    fn test_build_resmoke_sub_task_should_keep_scheduling_fields(
+        #[case] patchable: Option<bool>,
+        #[case] disable: Option<bool>,
+        #[case] allowed_requesters: Option<Vec<String>>,
+    ) {
+        let task_history = TaskRuntimeHistory {
+            task_name: "my_task".to_string(),
+            test_map: hashmap! {},
+        };
+        let gen_resmoke_service = build_mocked_service(vec![], task_history, 3);
+        let params = ResmokeGenParams {
+            task_name: "my_task".to_string(),
+            suite_name: "my_suite".to_string(),
+            patchable,
+            disable,
+            allowed_requesters: allowed_requesters.clone(),
+            ..Default::default()
+        };
+        let sub_suite = SubSuite {
+            name: "my_task".to_string(),
+            origin_suite: "my_suite".to_string(),
+            ..Default::default()
+        };
+
+        let sub_task = gen_resmoke_service.build_resmoke_sub_task(&sub_suite, 1, &params, None);
+
+        assert_eq!(sub_task.evg_task.patchable, patchable);
+        assert_eq!(sub_task.evg_task.disable, disable);
+        assert_eq!(sub_task.evg_task.allowed_requesters, allowed_requesters);
+    }
+
+    #[rstest]
+    #[case(false, Some(3600))]
+    #[case(true, Some(1200))]
+    fn test_build_resmoke_sub_task_should_keep_exec_timeout(
+        #[case] scale_exec_timeout: bool,
+        #[case] expected_timeout: Option<u64>,
+    ) {
+        let task_history = TaskRuntimeHistory {
+            task_name: "my_task".to_string(),
+            test_map: hashmap! {},
+        };
+        let gen_resmoke_service = build_mocked_service(vec![], task_history, 3);
+        let params = ResmokeGenParams {
+            task_name: "my_task".to_string(),
+            suite_name: "my_suite".to_string(),
+            task_exec_timeout_secs: Some(3600),
+            scale_exec_timeout,
+            ..Default::default()
+        };
+        let sub_suite = SubSuite {
+            name: "my_task".to_string(),
+            origin_suite: "my_suite".to_string(),
+            ..Default::default()
+        };
+
+        let sub_task = gen_resmoke_service.build_resmoke_sub_task(&sub_suite, 3, &params, None);
+
+        assert_eq!(sub_task.evg_task.exec_timeout_secs, expected_timeout);
+    }
+
+    #[rstest]
+    #[case(false, None)]
+    #[case(true, Some(false))]
+    fn test_build_resmoke_sub_task_should_disable_stepback(
+        #[case] disable_stepback: bool,
+        #[case] expected_stepback: Option<bool>,
+    ) {
+        let task_history = TaskRuntimeHistory {
+            task_name: "my_task".to_string(),
+            test_map: hashmap! {},
+        };
+        let gen_resmoke_service = build_mocked_service(vec![], task_history, 3);
+        let params = ResmokeGenParams {
+            task_name: "my_task".to_string(),
+            suite_name: "my_suite".to_string(),
+            disable_stepback,
+            ..Default::default()
+        };
+        let sub_suite = SubSuite {
+            name: "my_task".to_string(),
+            origin_suite: "my_suite".to_string(),
+            ..Default::default()
+        };
+
+        let sub_task = gen_resmoke_service.build_resmoke_sub_task(&sub_suite, 1, &params, None);
+
+        assert_eq!(sub_task.evg_task.stepback, expected_stepback);
+    }
+
+    #[rstest]
+    #[case(false, "generated_resmoke_config/my_task_0.yml")]
+    #[case(true, "generated_resmoke_config/my_task/my_task_0.yml")]
+    fn test_build_resmoke_sub_task_should_point_to_suite_file(
+        #[case] nest_suite_files: bool,
+        #[case] expected_suite: &str,
+    ) {
+        let task_history = TaskRuntimeHistory {
+            task_name: "my_task".to_string(),
+            test_map: hashmap! {},
+        };
+        let mut gen_resmoke_service = build_mocked_service(vec![], task_history, 3);
+        gen_resmoke_service.config.nest_suite_files = nest_suite_files;
+        let params = ResmokeGenParams {
+            task_name: "my_task".to_string(),
+            suite_name: "my_suite".to_string(),
+            ..Default::default()
+        };
+        let sub_suite = SubSuite {
+            name: "my_task".to_string(),
+            origin_suite: "my_suite".to_string(),
+            ..Default::default()
+        };
+
+        let sub_task = gen_resmoke_service.build_resmoke_sub_task(&sub_suite, 2, &params, None);
+
+        assert_eq!(sub_task.evg_task.name, "my_task_0");
+        let commands = sub_task.evg_task.commands.as_ref().unwrap();
+        if let EvgCommand::Function(function) = commands.last().unwrap() {
+            assert_eq!(
+                function.vars.as_ref().unwrap().get(SUITE_NAME),
+                Some(&ParamValue::from(expected_suite))
+            );
+        } else {
+            panic!("Expected a function call");
+        }
+    }
+
     #[tokio::test]
     async fn test_generate_resmoke_tasks_multiversion_success() {
         let n_suites = 3;
@@ -1583,7 +3124,7 @@ mod tests {
 
     #[test]
     fn test_resmoke_commands() {
-        let commands = resmoke_commands("run test", hashmap! {}, false);
+        let commands = resmoke_commands("run test", hashmap! {}, false, &CommandHooks::default());
 
         assert_eq!(commands.len(), 3);
         assert_eq!(get_evg_fn_name(&commands[0]), Some("do setup"));
@@ -1592,7 +3133,7 @@ mod tests {
 
     #[test]
     fn test_resmoke_commands_should_include_multiversion() {
-        let commands = resmoke_commands("run test", hashmap! {}, true);
+        let commands = resmoke_commands("run test", hashmap! {}, true, &CommandHooks::default());
 
         assert_eq!(commands.len(), 6);
         assert_eq!(get_evg_fn_name(&commands[2]), Some("do setup"));
@@ -1600,6 +3141,22 @@ mod tests {
         assert_eq!(get_evg_fn_name(&commands[5]), Some("run test"));
     }
 
+    #[test]
+    fn test_resmoke_commands_should_include_command_hooks() {
+        let command_hooks = CommandHooks {
+            prepend: vec!["set up kerberos".to_string()],
+            append: vec!["collect core dumps".to_string()],
+        };
+
+        let commands = resmoke_commands("run test", hashmap! {}, false, &command_hooks);
+
+        assert_eq!(commands.len(), 5);
+        assert_eq!(get_evg_fn_name(&commands[0]), Some("set up kerberos"));
+        assert_eq!(get_evg_fn_name(&commands[1]), Some("do setup"));
+        assert_eq!(get_evg_fn_name(&commands[3]), Some("run test"));
+        assert_eq!(get_evg_fn_name(&commands[4]), Some("collect core dumps"));
+    }
+
     // sort_tests_by_runtime tests.
     #[rstest]
     #[case(vec![100.0, 50.0, 30.0, 25.0, 20.0, 15.0], vec![0, 1, 2, 3, 4, 5])]
@@ -1637,11 +3194,143 @@ mod tests {
             .map(|i| format!("test_{}.js", sorted_indexes[i]))
             .collect();
 
-        let result = sort_tests_by_runtime(test_list, &task_stats);
+        let result = sort_tests_by_runtime(test_list, &task_stats, &HashMap::new());
 
         assert_eq!(result, expected_result);
     }
 
+    // expected_runtime tests.
+    #[test]
+    fn test_expected_runtime_should_sum_known_runtimes() {
+        let test_list = vec![
+            "jstests/core/test_0.js".to_string(),
+            "jstests/core/test_1.js".to_string(),
+            "jstests/core/new_test.js".to_string(),
+        ];
+        let task_history = TaskRuntimeHistory {
+            task_name: "my_task".to_string(),
+            test_map: hashmap! {
+                "test_0".to_string() => build_mock_test_runtime("test_0", 12.5),
+                "test_1".to_string() => build_mock_test_runtime("test_1", 30.0),
+            },
+        };
+
+        assert_eq!(
+            expected_runtime(&test_list, Some(&task_history)),
+            Some(42.5)
+        );
+        assert_eq!(expected_runtime(&test_list, None), None);
+    }
+
+    // sub_suites_over_runtime tests.
+    #[test]
+    fn test_sub_suites_over_runtime_should_only_report_slow_sub_suites() {
+        let sub_suites: Vec<SubSuite> = vec![Some(9500.0), Some(9000.0), None, Some(12000.0)]
+            .into_iter()
+            .enumerate()
+            .map(|(index, expected_runtime)| SubSuite {
+                index,
+                name: "my_suite".to_string(),
+                expected_runtime,
+                ..Default::default()
+            })
+            .collect();
+
+        let slow_sub_suites = sub_suites_over_runtime(&sub_suites, 9000);
+
+        let slow_indexes: Vec<usize> = slow_sub_suites.iter().map(|s| s.index).collect();
+        assert_eq!(slow_indexes, vec![0, 3]);
+    }
+
+    // split_tests_consistently tests.
+    #[test]
+    fn test_split_tests_consistently_should_keep_membership_when_tests_are_added() {
+        let test_list: Vec<String> = (0..40)
+            .map(|i| format!("jstests/core/test_{}.js", i))
+            .collect();
+        let mut new_test_list = test_list.clone();
+        new_test_list.push("jstests/core/new_test.js".to_string());
+
+        let split = split_tests_consistently(test_list.clone(), None, 4);
+        let new_split = split_tests_consistently(new_test_list, None, 4);
+
+        let find_index = |split: &Vec<Vec<String>>, test: &String| {
+            split.iter().position(|tests| tests.contains(test)).unwrap()
+        };
+        let moved_tests = test_list
+            .iter()
+            .filter(|test| find_index(&split, test) != find_index(&new_split, test))
+            .count();
+        assert_eq!(split.iter().map(|tests| tests.len()).sum::<usize>(), 40);
+        assert_eq!(new_split.iter().map(|tests| tests.len()).sum::<usize>(), 41);
+        assert!(moved_tests <= 2);
+    }
+
+    #[test]
+    fn test_split_tests_consistently_should_not_depend_on_test_order() {
+        let test_list: Vec<String> = (0..20).map(|i| format!("test_{}.js", i)).collect();
+        let mut reversed_test_list = test_list.clone();
+        reversed_test_list.reverse();
+
+        assert_eq!(
+            split_tests_consistently(test_list, None, 3),
+            split_tests_consistently(reversed_test_list, None, 3)
+        );
+    }
+
+    #[test]
+    fn test_split_tests_consistently_should_balance_by_runtime() {
+        let test_list: Vec<String> = (0..12).map(|i| format!("test_{}.js", i)).collect();
+        let task_history = TaskRuntimeHistory {
+            task_name: "my_task".to_string(),
+            test_map: (0..10)
+                .map(|i| {
+                    let test_name = format!("test_{}", i);
+                    let runtime = build_mock_test_runtime(&test_name, (i + 1) as f64);
+                    (test_name, runtime)
+                })
+                .collect(),
+        };
+
+        let split = split_tests_consistently(test_list, Some(&task_history), 3);
+
+        // Tests without history count as the average runtime of 5.5, for a total of 66.
+        let capacity = 66.0 / 3.0 * (1.0 + CONSISTENT_SPLIT_SLACK);
+        for tests in &split {
+            let runtime: f64 = tests
+                .iter()
+                .map(|test| {
+                    task_history
+                        .test_map
+                        .get(&get_test_name(test))
+                        .map_or(5.5, |test_stats| test_stats.average_runtime)
+                })
+                .sum();
+            assert!(runtime <= capacity);
+        }
+        assert_eq!(split.iter().map(|tests| tests.len()).sum::<usize>(), 12);
+    }
+
+    // dynamic_resmoke_jobs tests.
+    #[rstest]
+    #[case(16, 1, None, 16)]
+    #[case(16, 3, None, 5)]
+    #[case(16, 3, Some(4), 4)]
+    #[case(16, 1, Some(32), 16)]
+    #[case(2, 8, None, 1)]
+    #[case(8, 0, None, 8)]
+    fn test_dynamic_resmoke_jobs(
+        #[case] cpu_count: u64,
+        #[case] processes_per_job: u64,
+        #[case] static_jobs_max: Option<u64>,
+        #[case] expected_jobs: u64,
+    ) {
+        assert_eq!(
+            dynamic_resmoke_jobs(cpu_count, processes_per_job, static_jobs_max),
+            expected_jobs
+        );
+    }
+
     // get_min_index tests.
     #[rstest]
     #[case(vec![100.0, 50.0, 30.0, 25.0, 20.0, 15.0], 5)]
@@ -0,0 +1,289 @@
+//! Estimate the runtime of tests without historic runtime data.
+//!
+//! Tests that were recently added have no runtime history yet. Rather than spreading them over
+//! sub-suites by count, an estimate of their runtime is used to place them like any other test.
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    evergreen::evg_task_history::{get_test_name, TaskRuntimeHistory},
+    utils::fs_service::FsService,
+};
+
+/// A service to estimate the runtime of tests without historic runtime data.
+pub trait RuntimeEstimator: Send + Sync {
+    /// Estimate the runtime of the tests in the given list that have no historic runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `test_list` - Tests of the suite being split.
+    /// * `task_stats` - Statistics on the historic runtimes of tests in the task.
+    ///
+    /// # Returns
+    ///
+    /// Map of tests without historic runtime to their estimated runtime in seconds. Tests that
+    /// cannot be estimated are left out.
+    fn estimate_runtimes(
+        &self,
+        test_list: &[String],
+        task_stats: &TaskRuntimeHistory,
+    ) -> HashMap<String, f64>;
+}
+
+/// Historic runtimes of the tests in the given list that have one.
+///
+/// # Arguments
+///
+/// * `test_list` - Tests to look up.
+/// * `task_stats` - Statistics on the historic runtimes of tests in the task.
+///
+/// # Returns
+///
+/// Pairs of tests and their historic runtime.
+fn known_runtimes<'a>(
+    test_list: &'a [String],
+    task_stats: &TaskRuntimeHistory,
+) -> Vec<(&'a String, f64)> {
+    test_list
+        .iter()
+        .filter_map(|test| {
+            task_stats
+                .test_map
+                .get(&get_test_name(test))
+                .map(|test_stats| (test, test_stats.average_runtime))
+        })
+        .collect()
+}
+
+/// Average runtime of the tests in the suite with a historic runtime.
+///
+/// # Arguments
+///
+/// * `test_list` - Tests of the suite being split.
+/// * `task_stats` - Statistics on the historic runtimes of tests in the task.
+///
+/// # Returns
+///
+/// Average runtime in seconds, if any test has a historic runtime.
+fn suite_average_runtime(test_list: &[String], task_stats: &TaskRuntimeHistory) -> Option<f64> {
+    let known = known_runtimes(test_list, task_stats);
+    if known.is_empty() {
+        None
+    } else {
+        Some(known.iter().map(|(_, runtime)| runtime).sum::<f64>() / known.len() as f64)
+    }
+}
+
+/// Estimate tests without history at the average runtime of the other tests in the suite.
+#[derive(Debug, Clone, Default)]
+pub struct SuiteAverageEstimator {}
+
+impl RuntimeEstimator for SuiteAverageEstimator {
+    /// Estimate the runtime of the tests in the given list that have no historic runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `test_list` - Tests of the suite being split.
+    /// * `task_stats` - Statistics on the historic runtimes of tests in the task.
+    ///
+    /// # Returns
+    ///
+    /// Map of tests without historic runtime to the average runtime of the suite.
+    fn estimate_runtimes(
+        &self,
+        test_list: &[String],
+        task_stats: &TaskRuntimeHistory,
+    ) -> HashMap<String, f64> {
+        let average_runtime = match suite_average_runtime(test_list, task_stats) {
+            Some(average_runtime) => average_runtime,
+            None => return HashMap::new(),
+        };
+
+        test_list
+            .iter()
+            .filter(|test| !task_stats.test_map.contains_key(&get_test_name(test)))
+            .map(|test| (test.clone(), average_runtime))
+            .collect()
+    }
+}
+
+/// Estimate tests without history from their file size.
+///
+/// The runtime per byte of the tests in the suite with history is applied to the size of tests
+/// without history. Tests whose size cannot be read use the average runtime of the suite.
+pub struct FileSizeEstimator {
+    /// Service to interact with file system.
+    fs_service: Arc<dyn FsService>,
+}
+
+impl FileSizeEstimator {
+    /// Create a new instance of the estimator.
+    ///
+    /// # Arguments
+    ///
+    /// * `fs_service` - Service to read the size of test files with.
+    ///
+    /// # Returns
+    ///
+    /// New instance of the estimator.
+    pub fn new(fs_service: Arc<dyn FsService>) -> Self {
+        Self { fs_service }
+    }
+}
+
+impl RuntimeEstimator for FileSizeEstimator {
+    /// Estimate the runtime of the tests in the given list that have no historic runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `test_list` - Tests of the suite being split.
+    /// * `task_stats` - Statistics on the historic runtimes of tests in the task.
+    ///
+    /// # Returns
+    ///
+    /// Map of tests without historic runtime to their estimated runtime in seconds.
+    fn estimate_runtimes(
+        &self,
+        test_list: &[String],
+        task_stats: &TaskRuntimeHistory,
+    ) -> HashMap<String, f64> {
+        let average_runtime = match suite_average_runtime(test_list, task_stats) {
+            Some(average_runtime) => average_runtime,
+            None => return HashMap::new(),
+        };
+        let (known_bytes, known_runtime) = known_runtimes(test_list, task_stats)
+            .into_iter()
+            .filter_map(|(test, runtime)| {
+                self.fs_service
+                    .file_size(test)
+                    .map(|size| (size as f64, runtime))
+            })
+            .fold((0.0, 0.0), |(bytes, total), (size, runtime)| {
+                (bytes + size, total + runtime)
+            });
+        let runtime_per_byte = if known_bytes > 0.0 {
+            Some(known_runtime / known_bytes)
+        } else {
+            None
+        };
+
+        test_list
+            .iter()
+            .filter(|test| !task_stats.test_map.contains_key(&get_test_name(test)))
+            .map(|test| {
+                let estimate = runtime_per_byte
+                    .zip(self.fs_service.file_size(test))
+                    .map(|(rate, size)| rate * size as f64)
+                    .unwrap_or(average_runtime);
+                (test.clone(), estimate)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use anyhow::Result;
+
+    use crate::evergreen::evg_task_history::TestRuntimeHistory;
+
+    use super::*;
+
+    struct MockFsService {
+        file_sizes: HashMap<String, u64>,
+    }
+
+    impl FsService for MockFsService {
+        fn file_exists(&self, path: &str) -> bool {
+            self.file_sizes.contains_key(path)
+        }
+
+        fn file_size(&self, path: &str) -> Option<u64> {
+            self.file_sizes.get(path).copied()
+        }
+
+        fn write_file(&self, _path: &Path, _contents: &str) -> Result<()> {
+            todo!()
+        }
+    }
+
+    fn build_task_stats(runtimes: &[(&str, f64)]) -> TaskRuntimeHistory {
+        TaskRuntimeHistory {
+            task_name: "my_task".to_string(),
+            test_map: runtimes
+                .iter()
+                .map(|(test_name, runtime)| {
+                    (
+                        test_name.to_string(),
+                        TestRuntimeHistory {
+                            test_name: format!("jstests/{}.js", test_name),
+                            average_runtime: *runtime,
+                            hooks: vec![],
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn build_test_list(test_names: &[&str]) -> Vec<String> {
+        test_names
+            .iter()
+            .map(|test_name| format!("jstests/{}.js", test_name))
+            .collect()
+    }
+
+    #[test]
+    fn test_suite_average_estimator_should_use_average_of_known_tests() {
+        let test_list = build_test_list(&["test_0", "test_1", "test_new"]);
+        let task_stats = build_task_stats(&[("test_0", 10.0), ("test_1", 30.0)]);
+
+        let estimates = SuiteAverageEstimator::default().estimate_runtimes(&test_list, &task_stats);
+
+        assert_eq!(
+            estimates,
+            HashMap::from([("jstests/test_new.js".to_string(), 20.0)])
+        );
+    }
+
+    #[test]
+    fn test_estimators_should_not_estimate_suites_without_history() {
+        let test_list = build_test_list(&["test_new"]);
+        let task_stats = build_task_stats(&[]);
+        let fs_service = MockFsService {
+            file_sizes: HashMap::from([("jstests/test_new.js".to_string(), 100)]),
+        };
+
+        assert!(SuiteAverageEstimator::default()
+            .estimate_runtimes(&test_list, &task_stats)
+            .is_empty());
+        assert!(FileSizeEstimator::new(Arc::new(fs_service))
+            .estimate_runtimes(&test_list, &task_stats)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_file_size_estimator_should_scale_runtime_by_file_size() {
+        let test_list = build_test_list(&["test_0", "test_1", "test_new", "test_unreadable"]);
+        let task_stats = build_task_stats(&[("test_0", 10.0), ("test_1", 30.0)]);
+        let fs_service = MockFsService {
+            file_sizes: HashMap::from([
+                ("jstests/test_0.js".to_string(), 100),
+                ("jstests/test_1.js".to_string(), 300),
+                ("jstests/test_new.js".to_string(), 1000),
+            ]),
+        };
+
+        let estimates =
+            FileSizeEstimator::new(Arc::new(fs_service)).estimate_runtimes(&test_list, &task_stats);
+
+        assert_eq!(
+            estimates,
+            HashMap::from([
+                ("jstests/test_new.js".to_string(), 100.0),
+                ("jstests/test_unreadable.js".to_string(), 20.0),
+            ])
+        );
+    }
+}
@@ -0,0 +1,392 @@
+//! Inventory of the tasks that would be generated for a project.
+//!
+//! The inventory is built from the project configuration alone, so it can be created without
+//! querying task history or running resmoke.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use serde::Serialize;
+use shrub_rs::models::{task::EvgTask, variant::BuildVariant};
+
+use crate::{
+    evergreen::{evg_config::EvgConfigService, evg_config_utils::EvgConfigUtils},
+    evergreen_names::{BURN_IN_TAGS, BURN_IN_TASKS, BURN_IN_TESTS},
+};
+
+use super::{
+    antithesis_tasks::AntithesisTaskKind,
+    bazel_shard_tasks::BazelShardTaskKind,
+    fuzzer_tasks::FuzzerTaskKind,
+    generated_suite::GeneratedSuite,
+    generated_task_kind::{GeneratedTaskKind, GeneratedTaskKindRegistry},
+    resmoke_tasks::ResmokeTaskKind,
+};
+
+/// Type of the burn-in tasks, which are not generated by a `GeneratedTaskKind`.
+const BURN_IN_TASK_TYPE: &str = "burn_in";
+
+/// Kind of generated task that only records the name of the kind it stands in for.
+///
+/// The inventory classifies tasks with a registry of these kinds, so the same task vars select a
+/// kind as select the kind used to generate the task.
+struct InventoryTaskKind {
+    /// Name of the kind this kind stands in for.
+    name: &'static str,
+}
+
+#[async_trait]
+impl GeneratedTaskKind for InventoryTaskKind {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    async fn generate(
+        &self,
+        task_def: &EvgTask,
+        _build_variant: &BuildVariant,
+    ) -> Result<Box<dyn GeneratedSuite>> {
+        bail!(
+            "Task '{}' cannot be generated while building a task inventory",
+            task_def.name
+        )
+    }
+}
+
+/// Description of a task that would be generated.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GeneratedTaskInfo {
+    /// Name of the task definition generating the task.
+    pub name: String,
+    /// Name of the kind of task being generated.
+    pub task_type: String,
+    /// Build variants that include the task.
+    pub build_variants: Vec<String>,
+    /// Resmoke suite the task runs, if it runs one.
+    pub suite: Option<String>,
+}
+
+/// Build an inventory of the tasks that would be generated for a project.
+///
+/// # Arguments
+///
+/// * `evg_config_service` - Service to work with evergreen project configuration.
+/// * `evg_config_utils` - Utilities to work with evergreen project configuration.
+///
+/// # Returns
+///
+/// Generated tasks sorted by name.
+pub fn build_task_inventory(
+    evg_config_service: &dyn EvgConfigService,
+    evg_config_utils: Arc<dyn EvgConfigUtils>,
+) -> Vec<GeneratedTaskInfo> {
+    let task_kind_registry = build_inventory_registry(evg_config_utils.clone());
+    let task_map = evg_config_service.get_task_def_map();
+    let mut build_variants_by_task: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for (bv_name, build_variant) in evg_config_service.get_build_variant_map() {
+        for task in &build_variant.tasks {
+            build_variants_by_task
+                .entry(task.name.clone())
+                .or_default()
+                .insert(bv_name.clone());
+        }
+    }
+
+    build_variants_by_task
+        .into_iter()
+        .filter_map(|(task_name, build_variants)| {
+            let (task_type, suite) = if is_burn_in_task(&task_name) {
+                (BURN_IN_TASK_TYPE.to_string(), None)
+            } else {
+                let task_def = task_map.get(&task_name)?;
+                if !evg_config_utils.is_task_generated(task_def) {
+                    return None;
+                }
+                let task_type = task_kind_registry.lookup(task_def).name();
+                let suite = if task_type == ResmokeTaskKind::NAME {
+                    Some(evg_config_utils.find_suite_name(task_def).to_string())
+                } else {
+                    None
+                };
+                (task_type.to_string(), suite)
+            };
+
+            Some(GeneratedTaskInfo {
+                name: task_name,
+                task_type,
+                build_variants: build_variants.into_iter().collect(),
+                suite,
+            })
+        })
+        .collect()
+}
+
+/// Format the given generated tasks as a table.
+///
+/// # Arguments
+///
+/// * `tasks` - Generated tasks to format.
+///
+/// # Returns
+///
+/// Table with a row for each generated task.
+pub fn format_task_table(tasks: &[GeneratedTaskInfo]) -> String {
+    let rows: Vec<[String; 4]> = tasks
+        .iter()
+        .map(|task| {
+            [
+                task.name.clone(),
+                task.task_type.clone(),
+                task.suite.clone().unwrap_or_default(),
+                task.build_variants.join(","),
+            ]
+        })
+        .collect();
+    let header = [
+        "NAME".to_string(),
+        "TYPE".to_string(),
+        "SUITE".to_string(),
+        "BUILD VARIANTS".to_string(),
+    ];
+
+    let mut widths = header.clone().map(|h| h.len());
+    for row in &rows {
+        for (width, value) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(value.len());
+        }
+    }
+
+    std::iter::once(&header)
+        .chain(rows.iter())
+        .map(|row| {
+            format!(
+                "{:w0$}  {:w1$}  {:w2$}  {}",
+                row[0],
+                row[1],
+                row[2],
+                row[3],
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2],
+            )
+            .trim_end()
+            .to_string()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Check if the given task is one of the burn-in tasks.
+fn is_burn_in_task(task_name: &str) -> bool {
+    task_name == BURN_IN_TESTS || task_name == BURN_IN_TAGS || task_name == BURN_IN_TASKS
+}
+
+/// Build a registry classifying task definitions by the kind of task they generate.
+///
+/// # Arguments
+///
+/// * `evg_config_utils` - Utilities to work with evergreen project configuration.
+///
+/// # Returns
+///
+/// Registry of the kinds of generated tasks, each standing in for the kind of the same name.
+fn build_inventory_registry(
+    evg_config_utils: Arc<dyn EvgConfigUtils>,
+) -> GeneratedTaskKindRegistry {
+    let inventory_kind = |name| Arc::new(InventoryTaskKind { name });
+    let mut task_kind_registry =
+        GeneratedTaskKindRegistry::new(evg_config_utils, inventory_kind(ResmokeTaskKind::NAME));
+    task_kind_registry.register_standard_kinds(
+        inventory_kind(FuzzerTaskKind::NAME),
+        inventory_kind(AntithesisTaskKind::NAME),
+        inventory_kind(BazelShardTaskKind::NAME),
+    );
+    task_kind_registry
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use maplit::hashmap;
+    use shrub_rs::models::{
+        commands::{fn_call, fn_call_with_params},
+        params::ParamValue,
+        variant::BuildVariant,
+    };
+
+    use rstest::rstest;
+
+    use crate::{
+        evergreen::evg_config_utils::EvgConfigUtilsImpl,
+        evergreen_names::{IS_ANTITHESIS, IS_BAZEL_TEST_SHARDS, IS_DIFFERENTIAL_FUZZER, IS_FUZZER},
+    };
+
+    use super::*;
+
+    struct MockConfigService {
+        build_variants: Vec<BuildVariant>,
+        tasks: Vec<EvgTask>,
+    }
+    impl EvgConfigService for MockConfigService {
+        fn get_build_variant_map(&self) -> HashMap<String, &BuildVariant> {
+            self.build_variants
+                .iter()
+                .map(|bv| (bv.name.clone(), bv))
+                .collect()
+        }
+
+        fn get_task_def_map(&self) -> HashMap<String, Arc<EvgTask>> {
+            self.tasks
+                .iter()
+                .map(|t| (t.name.clone(), Arc::new(t.clone())))
+                .collect()
+        }
+
+        fn sort_build_variants_by_required(&self, _required_prefixes: &[String]) -> Vec<String> {
+            todo!()
+        }
+
+        fn get_module_dirs(&self) -> HashMap<String, String> {
+            todo!()
+        }
+    }
+
+    fn build_variant(name: &str, tasks: &[&str]) -> BuildVariant {
+        BuildVariant {
+            name: name.to_string(),
+            tasks: tasks
+                .iter()
+                .map(|t| {
+                    EvgTask {
+                        name: t.to_string(),
+                        ..Default::default()
+                    }
+                    .get_reference(None, None)
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn gen_task(name: &str, vars: HashMap<String, ParamValue>) -> EvgTask {
+        EvgTask {
+            name: name.to_string(),
+            commands: Some(vec![fn_call_with_params("generate resmoke tasks", vars)]),
+            ..Default::default()
+        }
+    }
+
+    // build_task_inventory tests.
+    #[test]
+    fn test_build_task_inventory_should_list_generated_tasks() {
+        let config_service = MockConfigService {
+            build_variants: vec![
+                build_variant(
+                    "bv_0",
+                    &["jsCore_gen", "not_generated", "burn_in_tests_gen"],
+                ),
+                build_variant("bv_1", &["jsCore_gen", "fuzzer_gen"]),
+            ],
+            tasks: vec![
+                gen_task(
+                    "jsCore_gen",
+                    hashmap! {"suite".to_string() => ParamValue::from("core")},
+                ),
+                gen_task(
+                    "fuzzer_gen",
+                    hashmap! {IS_FUZZER.to_string() => ParamValue::from("true")},
+                ),
+                EvgTask {
+                    name: "not_generated".to_string(),
+                    commands: Some(vec![fn_call("run tests")]),
+                    ..Default::default()
+                },
+            ],
+        };
+
+        let inventory = build_task_inventory(&config_service, Arc::new(EvgConfigUtilsImpl::new()));
+
+        assert_eq!(
+            inventory,
+            vec![
+                GeneratedTaskInfo {
+                    name: "burn_in_tests_gen".to_string(),
+                    task_type: "burn_in".to_string(),
+                    build_variants: vec!["bv_0".to_string()],
+                    suite: None,
+                },
+                GeneratedTaskInfo {
+                    name: "fuzzer_gen".to_string(),
+                    task_type: "fuzzer".to_string(),
+                    build_variants: vec!["bv_1".to_string()],
+                    suite: None,
+                },
+                GeneratedTaskInfo {
+                    name: "jsCore_gen".to_string(),
+                    task_type: "resmoke".to_string(),
+                    build_variants: vec!["bv_0".to_string(), "bv_1".to_string()],
+                    suite: Some("core".to_string()),
+                },
+            ]
+        );
+    }
+
+    // build_inventory_registry tests.
+    #[rstest]
+    #[case(vec![], "resmoke")]
+    #[case(vec![IS_FUZZER], "fuzzer")]
+    #[case(vec![IS_DIFFERENTIAL_FUZZER], "fuzzer")]
+    #[case(vec![IS_ANTITHESIS], "antithesis")]
+    #[case(vec![IS_BAZEL_TEST_SHARDS], "bazel_test_shards")]
+    #[case(vec![IS_BAZEL_TEST_SHARDS, IS_FUZZER], "fuzzer")]
+    fn test_build_inventory_registry_should_match_generated_task_kind(
+        #[case] task_vars: Vec<&str>,
+        #[case] expected_kind: &str,
+    ) {
+        let task_kind_registry = build_inventory_registry(Arc::new(EvgConfigUtilsImpl::new()));
+        let task_def = gen_task(
+            "my_task_gen",
+            task_vars
+                .into_iter()
+                .map(|var| (var.to_string(), ParamValue::from("true")))
+                .collect(),
+        );
+
+        assert_eq!(task_kind_registry.lookup(&task_def).name(), expected_kind);
+    }
+
+    // format_task_table tests.
+    #[test]
+    fn test_format_task_table_should_align_columns() {
+        let tasks = vec![
+            GeneratedTaskInfo {
+                name: "jsCore_gen".to_string(),
+                task_type: "resmoke".to_string(),
+                build_variants: vec!["bv_0".to_string(), "bv_1".to_string()],
+                suite: Some("core".to_string()),
+            },
+            GeneratedTaskInfo {
+                name: "fuzzer_gen".to_string(),
+                task_type: "fuzzer".to_string(),
+                build_variants: vec!["bv_1".to_string()],
+                suite: None,
+            },
+        ];
+
+        let table = format_task_table(&tasks);
+
+        assert_eq!(
+            table,
+            [
+                "NAME        TYPE     SUITE  BUILD VARIANTS",
+                "jsCore_gen  resmoke  core   bv_0,bv_1",
+                "fuzzer_gen  fuzzer          bv_1",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -0,0 +1,237 @@
+//! Backends for the test runners that generated sub-tasks run their tests with.
+//!
+//! How a task is split into sub-tasks does not depend on how the tests are run. A backend
+//! supplies the pieces that are specific to a test runner: the function a sub-task calls to run
+//! its tests, the vars passed to that function and whether each sub-task needs a suite file.
+use std::{collections::HashMap, sync::Arc};
+
+use maplit::hashmap;
+use shrub_rs::models::params::ParamValue;
+
+use crate::{
+    evergreen_names::{BAZEL_ARGS, BAZEL_TARGETS, GEN_TASK_CONFIG_LOCATION},
+    generate_sub_tasks_config::FunctionNames,
+};
+
+use super::resmoke_tasks::{sub_task_index_vars, ResmokeGenParams, SubSuite};
+
+/// Name of the backend that runs tests with resmoke.
+pub const RESMOKE_TEST_RUNNER: &str = "resmoke";
+/// Name of the backend that runs tests as bazel test targets.
+pub const BAZEL_TEST_RUNNER: &str = "bazel";
+
+/// A test runner that generated sub-tasks can run their tests with.
+pub trait TestRunnerBackend: Sync + Send {
+    /// Name of the test runner, as used in the `test_runner` var of a task.
+    fn name(&self) -> &str;
+
+    /// Name of the evergreen function sub-tasks call to run their tests.
+    ///
+    /// # Arguments
+    ///
+    /// * `function_names` - Names of the functions configured for the project.
+    fn run_tests_fn_name<'a>(&self, function_names: &'a FunctionNames) -> &'a str;
+
+    /// Does the test runner need a suite file written for each sub-task.
+    fn writes_suite_files(&self) -> bool;
+
+    /// Build the vars to pass to the run tests function of a sub-task.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - Parameters for how the task is being generated.
+    /// * `sub_suite` - Sub-suite the sub-task runs.
+    /// * `total_sub_suites` - Number of sub-suites the task was split into.
+    /// * `suite_file` - Name of the suite file generated for the sub-task.
+    /// * `exclude_tags` - Tags of tests to exclude.
+    /// * `suite_override` - Suite to run instead of the generated suite file.
+    ///
+    /// # Returns
+    ///
+    /// Map of arguments to pass to the run tests function.
+    fn build_run_test_vars(
+        &self,
+        params: &ResmokeGenParams,
+        sub_suite: &SubSuite,
+        total_sub_suites: usize,
+        suite_file: &str,
+        exclude_tags: &str,
+        suite_override: Option<String>,
+    ) -> HashMap<String, ParamValue>;
+}
+
+/// Backend that runs the tests of each sub-task with resmoke using a generated suite file.
+#[derive(Debug, Clone, Default)]
+pub struct ResmokeBackend {}
+
+impl TestRunnerBackend for ResmokeBackend {
+    fn name(&self) -> &str {
+        RESMOKE_TEST_RUNNER
+    }
+
+    fn run_tests_fn_name<'a>(&self, function_names: &'a FunctionNames) -> &'a str {
+        &function_names.run_generated_tests
+    }
+
+    fn writes_suite_files(&self) -> bool {
+        true
+    }
+
+    fn build_run_test_vars(
+        &self,
+        params: &ResmokeGenParams,
+        sub_suite: &SubSuite,
+        total_sub_suites: usize,
+        suite_file: &str,
+        exclude_tags: &str,
+        suite_override: Option<String>,
+    ) -> HashMap<String, ParamValue> {
+        params.build_run_test_vars(
+            suite_file,
+            sub_suite,
+            total_sub_suites,
+            exclude_tags,
+            suite_override,
+        )
+    }
+}
+
+/// Backend that runs the tests of each sub-task as bazel test targets.
+///
+/// The tests discovered for the suite are treated as bazel targets and each sub-task runs its
+/// share of them with `bazel test`, so no suite files are written. A sub-task running the
+/// unsplit suite runs the suite name as a target pattern instead.
+#[derive(Debug, Clone, Default)]
+pub struct BazelTestBackend {}
+
+impl TestRunnerBackend for BazelTestBackend {
+    fn name(&self) -> &str {
+        BAZEL_TEST_RUNNER
+    }
+
+    fn run_tests_fn_name<'a>(&self, function_names: &'a FunctionNames) -> &'a str {
+        &function_names.run_bazel_tests
+    }
+
+    fn writes_suite_files(&self) -> bool {
+        false
+    }
+
+    fn build_run_test_vars(
+        &self,
+        params: &ResmokeGenParams,
+        sub_suite: &SubSuite,
+        total_sub_suites: usize,
+        _suite_file: &str,
+        _exclude_tags: &str,
+        suite_override: Option<String>,
+    ) -> HashMap<String, ParamValue> {
+        let mut run_test_vars: HashMap<String, ParamValue> = hashmap! {};
+        if let Some(pass_through_vars) = &params.pass_through_vars {
+            run_test_vars.extend(pass_through_vars.clone());
+        }
+
+        let targets = match suite_override {
+            Some(suite) => suite,
+            None if sub_suite.test_list.is_empty() => sub_suite.origin_suite.clone(),
+            None => sub_suite.test_list.join(" "),
+        };
+
+        run_test_vars.extend(hashmap! {
+            BAZEL_TARGETS.to_string() => ParamValue::from(targets.as_str()),
+            BAZEL_ARGS.to_string() => ParamValue::from(params.resmoke_args.as_str()),
+            GEN_TASK_CONFIG_LOCATION.to_string() => ParamValue::from(params.config_location.as_str()),
+        });
+        run_test_vars.extend(sub_task_index_vars(sub_suite.index, total_sub_suites));
+
+        run_test_vars
+    }
+}
+
+/// Look up the backend for the given test runner.
+///
+/// # Arguments
+///
+/// * `test_runner` - Name of the test runner, tasks without one run with resmoke.
+///
+/// # Returns
+///
+/// The backend for the test runner, or None if the test runner is not known.
+pub fn lookup_test_runner_backend(test_runner: Option<&str>) -> Option<Arc<dyn TestRunnerBackend>> {
+    match test_runner.unwrap_or(RESMOKE_TEST_RUNNER) {
+        RESMOKE_TEST_RUNNER => Some(Arc::new(ResmokeBackend {})),
+        BAZEL_TEST_RUNNER => Some(Arc::new(BazelTestBackend {})),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::evergreen_names::{RUN_BAZEL_TESTS, TOTAL_SUB_TASKS};
+
+    use super::*;
+
+    #[rstest]
+    #[case(None, Some(RESMOKE_TEST_RUNNER))]
+    #[case(Some("resmoke"), Some(RESMOKE_TEST_RUNNER))]
+    #[case(Some("bazel"), Some(BAZEL_TEST_RUNNER))]
+    #[case(Some("pytest"), None)]
+    fn test_lookup_test_runner_backend(
+        #[case] test_runner: Option<&str>,
+        #[case] expected_backend: Option<&str>,
+    ) {
+        let backend = lookup_test_runner_backend(test_runner);
+
+        assert_eq!(backend.as_ref().map(|b| b.name()), expected_backend);
+    }
+
+    #[rstest]
+    #[case(vec!["//src/a:test", "//src/b:test"], None, "//src/a:test //src/b:test")]
+    #[case(vec![], None, "//src/...")]
+    #[case(vec![], Some("//jstests/..."), "//jstests/...")]
+    fn test_bazel_backend_should_run_sub_suite_targets(
+        #[case] test_list: Vec<&str>,
+        #[case] suite_override: Option<&str>,
+        #[case] expected_targets: &str,
+    ) {
+        let params = ResmokeGenParams {
+            resmoke_args: "--config=dbg".to_string(),
+            config_location: "bucket/path".to_string(),
+            pass_through_vars: Some(hashmap! {
+                "extra".to_string() => ParamValue::from("value"),
+            }),
+            ..Default::default()
+        };
+        let sub_suite = SubSuite {
+            test_list: test_list.into_iter().map(|t| t.to_string()).collect(),
+            origin_suite: "//src/...".to_string(),
+            ..Default::default()
+        };
+        let backend = BazelTestBackend {};
+
+        let vars = backend.build_run_test_vars(
+            &params,
+            &sub_suite,
+            2,
+            "suite_0",
+            "",
+            suite_override.map(|s| s.to_string()),
+        );
+
+        assert_eq!(
+            backend.run_tests_fn_name(&FunctionNames::default()),
+            RUN_BAZEL_TESTS
+        );
+        assert!(!backend.writes_suite_files());
+        assert_eq!(vars[BAZEL_TARGETS], ParamValue::from(expected_targets));
+        assert_eq!(vars[BAZEL_ARGS], ParamValue::from("--config=dbg"));
+        assert_eq!(
+            vars[GEN_TASK_CONFIG_LOCATION],
+            ParamValue::from("bucket/path")
+        );
+        assert_eq!(vars["extra"], ParamValue::from("value"));
+        assert_eq!(vars[TOTAL_SUB_TASKS], ParamValue::from(2));
+    }
+}
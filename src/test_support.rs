@@ -0,0 +1,247 @@
+//! Support for testing projects that embed the generator.
+//!
+//! Enabled with the `test-support` feature. Provides in-memory implementations of the services
+//! that query resmoke, the task history store and the filesystem, along with helpers to load
+//! their data from fixture files, so projects can write snapshot tests of their generated
+//! configuration without running resmoke or reaching S3.
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Mutex,
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+
+pub use crate::{
+    evergreen::evg_task_history::{
+        HistoryFetchStats, S3TestStats, TaskHistoryService, TaskRuntimeHistory,
+    },
+    resmoke::{
+        resmoke_proxy::{MultiversionConfig, TestDiscovery},
+        resmoke_suite::ResmokeSuiteConfig,
+    },
+    utils::fs_service::FsService,
+};
+
+use crate::evergreen::evg_task_history::task_history_from_stats;
+
+/// Test discovery that serves suites from memory instead of querying resmoke.
+#[derive(Debug, Default)]
+pub struct MockTestDiscovery {
+    /// Map of suite names to the tests in the suite.
+    pub suite_tests: HashMap<String, Vec<String>>,
+    /// Map of suite names to the configuration of the suite.
+    pub suite_configs: HashMap<String, ResmokeSuiteConfig>,
+    /// Multiversion configuration to generate against.
+    pub multiversion_config: Option<MultiversionConfig>,
+}
+
+impl TestDiscovery for MockTestDiscovery {
+    /// Get a list of tests that belong to the given suite.
+    fn discover_tests(&self, suite_name: &str) -> Result<Vec<String>> {
+        self.suite_tests
+            .get(suite_name)
+            .cloned()
+            .ok_or_else(|| anyhow!("No tests defined for suite '{}'", suite_name))
+    }
+
+    /// Get a list of all tests that belong to the given suite.
+    fn discover_all_tests(&self, suite_name: &str) -> Result<Vec<String>> {
+        self.discover_tests(suite_name)
+    }
+
+    /// Get the configuration for the given suite.
+    fn get_suite_config(&self, suite_name: &str) -> Result<ResmokeSuiteConfig> {
+        self.suite_configs
+            .get(suite_name)
+            .cloned()
+            .ok_or_else(|| anyhow!("No configuration defined for suite '{}'", suite_name))
+    }
+
+    /// Get the multiversion configuration to generate against.
+    fn get_multiversion_config(&self) -> Result<MultiversionConfig> {
+        self.multiversion_config
+            .clone()
+            .ok_or_else(|| anyhow!("No multiversion configuration defined"))
+    }
+
+    /// Write an empty multiversion exclude tags file.
+    fn generate_multiversion_exclude_tags(
+        &self,
+        _old_version: &str,
+        output_file: &Path,
+    ) -> Result<()> {
+        std::fs::write(output_file, "")?;
+        Ok(())
+    }
+
+    /// No tests are skipped by the mock.
+    fn skipped_covered_tests(&self) -> BTreeMap<String, Vec<String>> {
+        BTreeMap::new()
+    }
+}
+
+/// Task history service that serves task histories from memory instead of S3.
+#[derive(Debug, Default)]
+pub struct MockTaskHistoryService {
+    /// Map of task and build variant names to the history of the task.
+    pub task_histories: HashMap<(String, String), TaskRuntimeHistory>,
+    /// Map of task and build variant names to the runtimes of the task's bazel test targets.
+    pub bazel_target_histories: HashMap<(String, String), HashMap<String, f64>>,
+}
+
+#[async_trait]
+impl TaskHistoryService for MockTaskHistoryService {
+    /// Build URL to send request to.
+    fn build_url(&self, task: &str, variant: &str) -> String {
+        format!("mock://{}/{}", variant, task)
+    }
+
+    /// Get the test runtime history of the given task.
+    async fn get_task_history(&self, task: &str, variant: &str) -> Result<TaskRuntimeHistory> {
+        self.task_histories
+            .get(&(task.to_string(), variant.to_string()))
+            .cloned()
+            .ok_or_else(|| anyhow!("No history defined for '{}' on '{}'", task, variant))
+    }
+
+    /// Get the runtime history of the bazel test targets run by the given task.
+    async fn get_bazel_target_history(
+        &self,
+        task: &str,
+        variant: &str,
+    ) -> Result<HashMap<String, f64>> {
+        self.bazel_target_histories
+            .get(&(task.to_string(), variant.to_string()))
+            .cloned()
+            .ok_or_else(|| {
+                anyhow!(
+                    "No bazel target history defined for '{}' on '{}'",
+                    task,
+                    variant
+                )
+            })
+    }
+
+    /// Task histories are already in memory, so there is nothing to prefetch.
+    async fn prefetch_task_histories(&self, _task_variants: Vec<(String, String)>) {}
+
+    /// Get counts of the requests made to fetch task history.
+    fn fetch_stats(&self) -> HistoryFetchStats {
+        HistoryFetchStats::default()
+    }
+}
+
+/// Filesystem service that records writes in memory instead of writing to disk.
+#[derive(Debug, Default)]
+pub struct MockFsService {
+    /// Paths that should be reported as existing.
+    pub existing_files: HashSet<String>,
+    /// Files that have been written and their contents.
+    written_files: Mutex<HashMap<PathBuf, String>>,
+}
+
+impl MockFsService {
+    /// Get the files that have been written and their contents.
+    pub fn written_files(&self) -> HashMap<PathBuf, String> {
+        self.written_files.lock().unwrap().clone()
+    }
+}
+
+impl FsService for MockFsService {
+    /// Determine whether the given file path points to a file.
+    fn file_exists(&self, path: &str) -> bool {
+        self.existing_files.contains(path)
+    }
+
+    /// Report no size for any file.
+    fn file_size(&self, _path: &str) -> Option<u64> {
+        None
+    }
+
+    /// Record the given contents as written to the given location.
+    fn write_file(&self, path: &Path, contents: &str) -> Result<()> {
+        self.written_files
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), contents.to_string());
+        Ok(())
+    }
+}
+
+/// Load the history of a task from a fixture file.
+///
+/// The fixture uses the same JSON format as the test stats stored in S3.
+///
+/// # Arguments
+///
+/// * `task` - Name of task the history belongs to.
+/// * `path` - Path to the fixture file.
+///
+/// # Returns
+///
+/// Runtime history of the task.
+pub fn load_task_history_fixture(task: &str, path: &Path) -> Result<TaskRuntimeHistory> {
+    let contents = std::fs::read_to_string(path)?;
+    let stats: Vec<S3TestStats> = serde_json::from_str(&contents)?;
+    Ok(task_history_from_stats(task, &stats))
+}
+
+/// Load the configuration of a resmoke suite from a fixture file.
+///
+/// # Arguments
+///
+/// * `path` - Path to a resmoke suite YAML file.
+///
+/// # Returns
+///
+/// Configuration of the suite.
+pub fn load_suite_config_fixture(path: &Path) -> Result<ResmokeSuiteConfig> {
+    let contents = std::fs::read_to_string(path)?;
+    Ok(ResmokeSuiteConfig::from_str(&contents)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_load_task_history_fixture_should_attach_hooks_to_tests() {
+        let tmp_dir = TempDir::new("task_history_fixture").unwrap();
+        let fixture = tmp_dir.path().join("my_task.json");
+        std::fs::write(
+            &fixture,
+            r#"[
+                {"test_name": "jstests/core/test_0.js", "num_pass": 1, "num_fail": 0, "avg_duration_pass": 10.0},
+                {"test_name": "test_0:CheckReplDBHash", "num_pass": 1, "num_fail": 0, "avg_duration_pass": 2.0}
+            ]"#,
+        )
+        .unwrap();
+
+        let task_history = load_task_history_fixture("my_task", &fixture).unwrap();
+
+        assert_eq!(task_history.task_name, "my_task");
+        let test_history = &task_history.test_map["test_0"];
+        assert_eq!(test_history.average_runtime, 10.0);
+        assert_eq!(test_history.hooks.len(), 1);
+    }
+
+    #[test]
+    fn test_mock_fs_service_should_record_writes() {
+        let fs_service = MockFsService::default();
+
+        fs_service
+            .write_file(Path::new("suite.yml"), "contents")
+            .unwrap();
+
+        assert_eq!(
+            fs_service.written_files()[Path::new("suite.yml")],
+            "contents"
+        );
+        assert!(!fs_service.file_exists("suite.yml"));
+    }
+}
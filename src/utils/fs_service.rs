@@ -15,6 +15,17 @@ pub trait FsService: Sync + Send {
     /// true if there is a file at the given path.
     fn file_exists(&self, path: &str) -> bool;
 
+    /// Get the size of the file at the given path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Filesystem path to check.
+    ///
+    /// # Returns
+    ///
+    /// Size of the file in bytes, if it could be read.
+    fn file_size(&self, path: &str) -> Option<u64>;
+
     /// Write the given contents to disk at the given location.
     ///
     /// # Arguments
@@ -52,6 +63,19 @@ impl FsService for FsServiceImpl {
         Path::new(path).exists()
     }
 
+    /// Get the size of the file at the given path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Filesystem path to check.
+    ///
+    /// # Returns
+    ///
+    /// Size of the file in bytes, if it could be read.
+    fn file_size(&self, path: &str) -> Option<u64> {
+        std::fs::metadata(path).ok().map(|metadata| metadata.len())
+    }
+
     /// Write the given contents to disk at the given location.
     ///
     /// # Arguments
@@ -63,6 +87,9 @@ impl FsService for FsServiceImpl {
     ///
     /// Returns the unit value after contents have been written successfully.
     fn write_file(&self, path: &Path, contents: &str) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
         Ok(std::fs::write(path, contents)?)
     }
 }
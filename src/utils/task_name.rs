@@ -1,42 +1,193 @@
 //! Utilities for working with task names.
 
+use anyhow::{bail, Result};
+use serde::Deserialize;
+
 use crate::evergreen_names::ENTERPRISE_MODULE;
 const GEN_SUFFIX: &str = "_gen";
 
-/// Generate a name for a generated task.
+/// Placeholder for the name of the task being generated.
+const TASK_PLACEHOLDER: &str = "{task}";
+/// Placeholder for the index of the sub-task, zero-padded to the index width.
+const INDEX_PLACEHOLDER: &str = "{index}";
+/// Placeholder for the delimiter placed before the index.
+const DELIMITER_PLACEHOLDER: &str = "{delimiter}";
+/// Placeholder for `-<platform>`, empty if the platform is not known.
+const PLATFORM_PLACEHOLDER: &str = "{platform}";
+/// Placeholder for `-enterprise` on enterprise build variants, empty otherwise.
+const ENTERPRISE_PLACEHOLDER: &str = "{enterprise}";
+/// Default template of the names of generated sub-tasks.
+pub const DEFAULT_SUB_TASK_NAME_TEMPLATE: &str = "{task}{delimiter}{index}{platform}{enterprise}";
+/// Default delimiter placed before the index of sub-tasks.
+pub const DEFAULT_INDEX_DELIMITER: &str = "_";
+
+/// Conventions for naming generated sub-tasks.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct TaskNamingPolicy {
+    /// Template of the names of generated sub-tasks, using `{task}`, `{delimiter}`, `{index}`,
+    /// `{platform}` and `{enterprise}` placeholders.
+    pub sub_task_template: String,
+    /// Delimiter the `{delimiter}` placeholder is replaced with.
+    pub index_delimiter: String,
+    /// Number of digits to zero-pad indexes to, the width of the largest index if not set.
+    pub index_width: Option<usize>,
+}
+
+impl Default for TaskNamingPolicy {
+    fn default() -> Self {
+        Self {
+            sub_task_template: DEFAULT_SUB_TASK_NAME_TEMPLATE.to_string(),
+            index_delimiter: DEFAULT_INDEX_DELIMITER.to_string(),
+            index_width: None,
+        }
+    }
+}
+
+impl TaskNamingPolicy {
+    /// Check that the policy gives every sub-task of a task a distinct name.
+    ///
+    /// # Returns
+    ///
+    /// An error if the template is missing the task name or index, or uses an unknown
+    /// placeholder.
+    pub fn validate(&self) -> Result<()> {
+        let template = &self.sub_task_template;
+        for required in [TASK_PLACEHOLDER, INDEX_PLACEHOLDER] {
+            if !template.contains(required) {
+                bail!(
+                    "Sub-task name template '{}' must contain '{}' for sub-task names to be unique",
+                    template,
+                    required
+                );
+            }
+        }
+
+        let mut rest = template.as_str();
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end + 1)
+                .unwrap_or(rest.len());
+            let placeholder = &rest[start..end];
+            if ![
+                TASK_PLACEHOLDER,
+                DELIMITER_PLACEHOLDER,
+                INDEX_PLACEHOLDER,
+                PLATFORM_PLACEHOLDER,
+                ENTERPRISE_PLACEHOLDER,
+            ]
+            .contains(&placeholder)
+            {
+                bail!(
+                    "Unknown placeholder '{}' in sub-task name template '{}'",
+                    placeholder,
+                    template
+                );
+            }
+            rest = &rest[end..];
+        }
+
+        Ok(())
+    }
+
+    /// Get the policy with the index formatting sub-tasks were named with before the index
+    /// options were added.
+    ///
+    /// Names that dashboards key on can be kept while the index options are rolled out.
+    ///
+    /// # Returns
+    ///
+    /// Policy using the default index delimiter and padding indexes to the largest index.
+    pub fn with_legacy_index(&self) -> Self {
+        Self {
+            sub_task_template: self.sub_task_template.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Generate a name for a generated sub-task.
+    ///
+    /// # Arguments
+    ///
+    /// * `display_name` - Name of parent task being generated.
+    /// * `sub_task_index` - Index of sub-task being named.
+    /// * `total_tasks` - Total number of sub-tasks generated for this parent task.
+    /// * `is_enterprise` - Whether the task is for an enterprise build variant.
+    /// * `platform` - Platform that task will run on.
+    ///
+    /// # Returns
+    ///
+    /// Name of the sub-task.
+    pub fn sub_task_name(
+        &self,
+        display_name: &str,
+        sub_task_index: usize,
+        total_tasks: usize,
+        is_enterprise: bool,
+        platform: Option<&str>,
+    ) -> String {
+        let alignment = self
+            .index_width
+            .unwrap_or_else(|| (total_tasks as f64).log10().ceil() as usize);
+        let index = format!("{:0fill$}", sub_task_index, fill = alignment);
+        let platform = platform
+            .map(|platform| format!("-{}", platform))
+            .unwrap_or_default();
+        let enterprise = if is_enterprise {
+            format!("-{}", ENTERPRISE_MODULE)
+        } else {
+            "".to_string()
+        };
+
+        // The task name is substituted last so placeholders in it are left alone.
+        self.sub_task_template
+            .replace(DELIMITER_PLACEHOLDER, &self.index_delimiter)
+            .replace(INDEX_PLACEHOLDER, &index)
+            .replace(PLATFORM_PLACEHOLDER, &platform)
+            .replace(ENTERPRISE_PLACEHOLDER, &enterprise)
+            .replace(TASK_PLACEHOLDER, display_name)
+    }
+}
+
+/// Determine the name generated tasks are tracked under while generating.
+///
+/// We append "enterprise" to tasks run on enterprise module build variants, so they don't
+/// conflict with the normal tasks. This name does not appear in the generated configuration,
+/// so it does not follow the naming policy.
 ///
 /// # Arguments
 ///
-/// * `display_name` - Name of parent task being generated.
-/// * `sub_task_index` - Index of sub-task being named.
-/// * `total_tasks` - Total number of sub-tasks generated for this parent task.
 /// * `is_enterprise` - Whether the task is for an enterprise build variant.
+/// * `task_name` - Name of task.
 /// * `platform` - Platform that task will run on.
-pub fn name_generated_task(
-    display_name: &str,
-    sub_task_index: usize,
-    total_tasks: usize,
+/// * `unique_gen_suffix` - Build variant specific suffix to add to the name.
+///
+/// # Returns
+///
+/// Name to use for task.
+pub fn lookup_task_name(
     is_enterprise: bool,
-    platform: Option<&str>,
+    task_name: &str,
+    platform: &str,
+    unique_gen_suffix: Option<&str>,
 ) -> String {
-    let mut suffix = if is_enterprise {
-        format!("-{}", ENTERPRISE_MODULE)
+    if is_enterprise {
+        format!(
+            "{}-{}-{}{}",
+            task_name,
+            platform,
+            ENTERPRISE_MODULE,
+            unique_gen_suffix.unwrap_or("")
+        )
     } else {
-        "".to_string()
-    };
-
-    if let Some(platform) = platform {
-        suffix = format!("-{}{}", platform, suffix)
+        format!(
+            "{}-{}{}",
+            task_name,
+            platform,
+            unique_gen_suffix.unwrap_or("")
+        )
     }
-
-    let alignment = (total_tasks as f64).log10().ceil() as usize;
-    format!(
-        "{}_{:0fill$}{}",
-        display_name,
-        sub_task_index,
-        suffix,
-        fill = alignment
-    )
 }
 
 /// Remove the '_gen' from end of the given task name if it exists.
@@ -71,7 +222,7 @@ mod tests {
     #[case("task", 0, 10, true, Some("linux"), "task_0-linux-enterprise")]
     #[case("task", 42, 1001, true, None, "task_0042-enterprise")]
     #[case("task", 42, 1001, true, Some("linux"), "task_0042-linux-enterprise")]
-    fn test_name_generated_task_should_not_include_suffix(
+    fn test_sub_task_name_should_not_include_suffix(
         #[case] name: &str,
         #[case] index: usize,
         #[case] total: usize,
@@ -79,11 +230,99 @@ mod tests {
         #[case] platform: Option<&str>,
         #[case] expected: &str,
     ) {
-        let task_name = name_generated_task(name, index, total, is_enterprise, platform);
+        let task_name =
+            TaskNamingPolicy::default().sub_task_name(name, index, total, is_enterprise, platform);
 
         assert_eq!(task_name, expected);
     }
 
+    #[test]
+    fn test_sub_task_name_should_follow_template() {
+        let naming = TaskNamingPolicy {
+            sub_task_template: "{task}{platform}{enterprise}-part{index}".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            naming.sub_task_name("task", 3, 20, true, Some("linux")),
+            "task-linux-enterprise-part03"
+        );
+    }
+
+    #[rstest]
+    #[case(2, 10, None, "_", "task_2-linux")]
+    #[case(2, 11, None, "_", "task_02-linux")]
+    #[case(2, 10, Some(3), "_", "task_002-linux")]
+    #[case(2, 11, Some(3), "-", "task-002-linux")]
+    #[case(1234, 2000, Some(3), "_", "task_1234-linux")]
+    #[case(2, 11, Some(1), "_", "task_2-linux")]
+    fn test_sub_task_name_should_use_index_options(
+        #[case] index: usize,
+        #[case] total: usize,
+        #[case] index_width: Option<usize>,
+        #[case] index_delimiter: &str,
+        #[case] expected: &str,
+    ) {
+        let naming = TaskNamingPolicy {
+            index_width,
+            index_delimiter: index_delimiter.to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            naming.sub_task_name("task", index, total, false, Some("linux")),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_with_legacy_index_should_keep_template_only() {
+        let naming = TaskNamingPolicy {
+            sub_task_template: "{task}{delimiter}{index}{enterprise}".to_string(),
+            index_delimiter: "-".to_string(),
+            index_width: Some(3),
+        };
+
+        assert_eq!(
+            naming
+                .with_legacy_index()
+                .sub_task_name("task", 2, 11, true, None),
+            "task_02-enterprise"
+        );
+    }
+
+    #[rstest]
+    #[case("{task}_{index}{platform}{enterprise}", true)]
+    #[case("{task}-{index}", true)]
+    #[case("{task}{delimiter}{index}", true)]
+    #[case("{task}{platform}", false)]
+    #[case("sub_task_{index}", false)]
+    #[case("{task}_{index}{variant}", false)]
+    #[case("{task}_{index}{", false)]
+    fn test_validate_should_require_unique_names(#[case] template: &str, #[case] valid: bool) {
+        let naming = TaskNamingPolicy {
+            sub_task_template: template.to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(naming.validate().is_ok(), valid);
+    }
+
+    #[rstest]
+    #[case(false, "my_task", "my_platform", "my_task-my_platform")]
+    #[case(true, "my_task", "my_platform", "my_task-my_platform-enterprise")]
+    fn test_lookup_task_name_should_use_enterprise_when_specified(
+        #[case] is_enterprise: bool,
+        #[case] task_name: &str,
+        #[case] platform: &str,
+        #[case] expected_task_name: &str,
+    ) {
+        assert_eq!(
+            lookup_task_name(is_enterprise, task_name, platform, None),
+            expected_task_name.to_string()
+        );
+    }
+
     #[rstest]
     #[case("task_name", "task_name")]
     #[case("task_name_gen", "task_name")]
@@ -0,0 +1,373 @@
+//! Progress tracking and deadline enforcement for generation workers.
+//!
+//! A worker that never finishes, usually because of a hung resmoke invocation, would otherwise
+//! consume the whole timeout of the generator task without saying which task it was stuck on.
+use std::{
+    fmt::Display,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+use dashmap::DashMap;
+use tokio::time;
+use tracing::{event, Level};
+
+use crate::resmoke::external_cmd::{CommandCancellation, COMMAND_CANCELLATION};
+
+/// Reason a watched generation worker did not produce a result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkerCancelled {
+    /// The worker did not finish before its deadline and was aborted.
+    TimedOut,
+    /// The worker panicked or was cancelled before it finished.
+    Failed(String),
+}
+
+impl Display for WorkerCancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorkerCancelled::TimedOut => write!(f, "Generation worker did not finish in time"),
+            WorkerCancelled::Failed(reason) => {
+                write!(f, "Generation worker did not finish: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WorkerCancelled {}
+
+/// Watchdog tracking generation workers and aborting those that exceed their deadline.
+#[derive(Debug)]
+pub struct WorkerWatchdog {
+    /// How long a worker may run before it is aborted.
+    timeout: Option<Duration>,
+    /// Continue generation when a worker is aborted instead of failing.
+    allow_partial: bool,
+    /// Interval to report the progress of generation at.
+    report_interval: Duration,
+    /// Workers that are still running and when they started.
+    running_workers: DashMap<String, Instant>,
+    /// Tasks whose workers were aborted, with the build variant they were generated for.
+    hung_workers: Mutex<Vec<String>>,
+}
+
+/// Removes a worker from the running workers when it finishes, even if it panics.
+struct RunningWorker<'a> {
+    running_workers: &'a DashMap<String, Instant>,
+    name: String,
+}
+
+impl Drop for RunningWorker<'_> {
+    fn drop(&mut self) {
+        self.running_workers.remove(&self.name);
+    }
+}
+
+impl WorkerWatchdog {
+    /// Create a new watchdog.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long a worker may run before it is aborted, or None for no deadline.
+    /// * `allow_partial` - Continue generation when a worker is aborted instead of failing.
+    /// * `report_interval` - Interval to report the progress of generation at.
+    ///
+    /// # Returns
+    ///
+    /// New instance of the watchdog.
+    pub fn new(timeout: Option<Duration>, allow_partial: bool, report_interval: Duration) -> Self {
+        Self {
+            timeout,
+            allow_partial,
+            report_interval,
+            running_workers: DashMap::new(),
+            hung_workers: Mutex::new(vec![]),
+        }
+    }
+
+    /// Whether generation continues when a worker is aborted.
+    pub fn allow_partial(&self) -> bool {
+        self.allow_partial
+    }
+
+    /// Interval to report the progress of generation at.
+    pub fn report_interval(&self) -> Duration {
+        self.report_interval
+    }
+
+    /// Get the workers that are still running, longest running first.
+    ///
+    /// # Returns
+    ///
+    /// Name of the task and build variant of each running worker and how long it has been
+    /// running.
+    pub fn running_workers(&self) -> Vec<(String, Duration)> {
+        let mut running_workers: Vec<(String, Duration)> = self
+            .running_workers
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().elapsed()))
+            .collect();
+        running_workers.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        running_workers
+    }
+
+    /// Run the given generation work, aborting it if it exceeds the deadline.
+    ///
+    /// The work is run in its own tokio task, which is aborted when the deadline passes. Aborting
+    /// the task cannot interrupt work that blocks its thread, so external commands the work runs,
+    /// like resmoke, are killed at the deadline instead. This frees the blocked thread even when
+    /// no other thread is left to notice that the deadline has passed.
+    ///
+    /// # Arguments
+    ///
+    /// * `work` - Generation work to run.
+    /// * `task_name` - Name of the task being generated.
+    /// * `build_variant` - Name of the build variant the task is being generated for.
+    ///
+    /// # Returns
+    ///
+    /// Result of the work, or the reason it did not finish. Callers decide whether a worker that
+    /// timed out fails generation or falls back to the unsplit task.
+    pub async fn watch<F, T>(
+        &self,
+        work: F,
+        task_name: &str,
+        build_variant: &str,
+    ) -> Result<T, WorkerCancelled>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let name = format!("{} on {}", task_name, build_variant);
+        self.running_workers.insert(name.clone(), Instant::now());
+        let _running_worker = RunningWorker {
+            running_workers: &self.running_workers,
+            name: name.clone(),
+        };
+
+        let timeout = match self.timeout {
+            Some(timeout) => timeout,
+            None => return Ok(work.await),
+        };
+
+        let cancellation = Arc::new(CommandCancellation::new(Instant::now() + timeout));
+        let mut handle = tokio::spawn(COMMAND_CANCELLATION.scope(cancellation.clone(), work));
+        let outcome = time::timeout(timeout, &mut handle).await;
+        // Work whose commands were killed at the deadline can finish before the timeout is
+        // noticed, so anything that finishes after the deadline is treated as hung.
+        if !cancellation.is_cancelled() {
+            match outcome {
+                Ok(Ok(result)) => return Ok(result),
+                Ok(Err(err)) => return Err(WorkerCancelled::Failed(err.to_string())),
+                Err(_) => (),
+            }
+        }
+
+        cancellation.cancel();
+        handle.abort();
+        event!(
+            Level::ERROR,
+            task_name,
+            build_variant,
+            timeout_secs = timeout.as_secs(),
+            "Aborted generation worker that did not finish in time"
+        );
+        self.hung_workers.lock().unwrap().push(name);
+        Err(WorkerCancelled::TimedOut)
+    }
+
+    /// Get the tasks whose workers were aborted.
+    pub fn hung_workers(&self) -> Vec<String> {
+        self.hung_workers.lock().unwrap().clone()
+    }
+
+    /// Check whether generation can continue after its workers have finished.
+    ///
+    /// # Returns
+    ///
+    /// An error listing the aborted workers if any were aborted and partial generation is not
+    /// allowed.
+    pub fn check(&self) -> Result<()> {
+        let hung_workers = self.hung_workers();
+        if hung_workers.is_empty() {
+            return Ok(());
+        }
+        if self.allow_partial {
+            event!(
+                Level::WARN,
+                hung_workers = hung_workers.join(", "),
+                "Continuing generation without splitting tasks whose workers were aborted"
+            );
+            return Ok(());
+        }
+        bail!(
+            "Generation did not finish within the worker timeout for: {}",
+            hung_workers.join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::resmoke::external_cmd::run_command;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_watch_should_return_result_of_work_that_finishes_in_time() {
+        let watchdog = WorkerWatchdog::new(
+            Some(Duration::from_secs(60)),
+            false,
+            Duration::from_secs(60),
+        );
+
+        let result = watchdog.watch(async { 42 }, "task", "variant").await;
+
+        assert_eq!(result, Ok(42));
+        assert!(watchdog.hung_workers().is_empty());
+        assert!(watchdog.running_workers().is_empty());
+        assert!(watchdog.check().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_watch_should_return_an_error_for_work_that_panics() {
+        let watchdog = WorkerWatchdog::new(
+            Some(Duration::from_secs(60)),
+            false,
+            Duration::from_secs(60),
+        );
+
+        let result: Result<u32, WorkerCancelled> = watchdog
+            .watch(async { panic!("resmoke exploded") }, "task", "variant")
+            .await;
+
+        assert!(matches!(result, Err(WorkerCancelled::Failed(_))));
+        assert!(watchdog.hung_workers().is_empty());
+        assert!(watchdog.running_workers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_running_workers_should_list_unfinished_workers() {
+        let watchdog =
+            std::sync::Arc::new(WorkerWatchdog::new(None, false, Duration::from_secs(60)));
+        let (sender, receiver) = tokio::sync::oneshot::channel::<()>();
+        let worker_watchdog = watchdog.clone();
+        let worker = tokio::spawn(async move {
+            worker_watchdog
+                .watch(async { receiver.await.unwrap() }, "task", "variant")
+                .await
+        });
+        tokio::task::yield_now().await;
+
+        let running_workers = watchdog.running_workers();
+        sender.send(()).unwrap();
+        worker.await.unwrap();
+
+        assert_eq!(running_workers.len(), 1);
+        assert_eq!(running_workers[0].0, "task on variant");
+        assert!(watchdog.running_workers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_watch_should_wait_for_work_without_a_timeout() {
+        let watchdog = WorkerWatchdog::new(None, false, Duration::from_secs(60));
+
+        let result = watchdog
+            .watch(
+                async {
+                    time::sleep(Duration::from_millis(10)).await;
+                    42
+                },
+                "task",
+                "variant",
+            )
+            .await;
+
+        assert_eq!(result, Ok(42));
+    }
+
+    #[rstest]
+    #[case(false, false)]
+    #[case(true, true)]
+    #[tokio::test]
+    async fn test_watch_should_abort_work_that_exceeds_the_deadline(
+        #[case] allow_partial: bool,
+        #[case] expected_ok: bool,
+    ) {
+        let watchdog = WorkerWatchdog::new(
+            Some(Duration::from_millis(10)),
+            allow_partial,
+            Duration::from_secs(60),
+        );
+
+        let result = watchdog
+            .watch(
+                async {
+                    time::sleep(Duration::from_secs(60)).await;
+                    42
+                },
+                "task",
+                "variant",
+            )
+            .await;
+
+        assert_eq!(result, Err(WorkerCancelled::TimedOut));
+        assert_eq!(watchdog.hung_workers(), vec!["task on variant".to_string()]);
+        assert_eq!(watchdog.check().is_ok(), expected_ok);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_watch_should_abort_work_that_blocks_its_thread() {
+        let watchdog = WorkerWatchdog::new(
+            Some(Duration::from_millis(10)),
+            false,
+            Duration::from_secs(60),
+        );
+        let start = Instant::now();
+
+        let result = watchdog
+            .watch(
+                async {
+                    std::thread::sleep(Duration::from_secs(2));
+                    42
+                },
+                "task",
+                "variant",
+            )
+            .await;
+
+        assert_eq!(result, Err(WorkerCancelled::TimedOut));
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert_eq!(watchdog.hung_workers(), vec!["task on variant".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_watch_should_kill_commands_of_work_that_exceeds_the_deadline() {
+        let watchdog = WorkerWatchdog::new(
+            Some(Duration::from_millis(100)),
+            false,
+            Duration::from_secs(60),
+        );
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let start = Instant::now();
+
+        let result = watchdog
+            .watch(
+                async move {
+                    sender.send(run_command(&["sleep", "60"]).is_err()).unwrap();
+                    42
+                },
+                "task",
+                "variant",
+            )
+            .await;
+
+        assert_eq!(result, Err(WorkerCancelled::TimedOut));
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(30)), Ok(true));
+        assert!(start.elapsed() < Duration::from_secs(30));
+    }
+}
@@ -1,7 +1,10 @@
 use assert_cmd::Command;
 use rstest::rstest;
+use std::collections::BTreeSet;
+use std::ffi::OsString;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use std::path::Path;
 use tempdir::TempDir;
 
 #[test]
@@ -31,7 +34,172 @@ fn test_end2end_execution() {
     assert!(tmp_dir_path.exists());
 
     let files = std::fs::read_dir(tmp_dir_path).unwrap();
-    assert_eq!(686, files.into_iter().collect::<Vec<_>>().len());
+    assert_eq!(693, files.into_iter().collect::<Vec<_>>().len());
+}
+
+#[test]
+fn test_end2end_execution_with_config_file() {
+    let mut cmd = Command::cargo_bin("mongo-task-generator").unwrap();
+    let tmp_dir = TempDir::new("generated_resmoke_config").unwrap();
+
+    cmd.args(&[
+        "--config",
+        "tests/data/sample_cli_config.yml",
+        "--target-directory",
+        tmp_dir.path().to_str().unwrap(),
+    ])
+    .assert()
+    .success();
+
+    let tmp_dir_path = tmp_dir.path();
+    assert!(tmp_dir_path.exists());
+
+    let files = std::fs::read_dir(tmp_dir_path).unwrap();
+    assert_eq!(693, files.into_iter().collect::<Vec<_>>().len());
+}
+
+#[test]
+fn test_end2end_regenerate_build_variants_into_new_directory() {
+    let tmp_dir = TempDir::new("generated_resmoke_config").unwrap();
+    let regenerated_dir = TempDir::new("regenerated_resmoke_config").unwrap();
+    let common_args = [
+        "--expansion-file",
+        "tests/data/sample_expansions.yml",
+        "--evg-project-file",
+        "tests/data/evergreen.yml",
+        "--evg-auth-file",
+        "tests/data/sample_evergreen_auth.yml",
+        "--resmoke-command",
+        "python3 tests/mocks/resmoke.py",
+        "--use-task-split-fallback",
+        "--generate-sub-tasks-config",
+        "tests/data/sample_generate_subtasks_config.yml",
+    ];
+
+    Command::cargo_bin("mongo-task-generator")
+        .unwrap()
+        .args(&common_args)
+        .args(&["--target-directory", tmp_dir.path().to_str().unwrap()])
+        .assert()
+        .success();
+    Command::cargo_bin("mongo-task-generator")
+        .unwrap()
+        .args(&common_args)
+        .args(&[
+            "--target-directory",
+            regenerated_dir.path().to_str().unwrap(),
+            "--regenerate-build-variants-from",
+            tmp_dir.path().to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    // The regenerated directory should hold every file the generated configuration refers to.
+    let file_names = |directory: &Path| -> BTreeSet<OsString> {
+        std::fs::read_dir(directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect()
+    };
+    assert_eq!(
+        file_names(regenerated_dir.path()),
+        file_names(tmp_dir.path())
+    );
+}
+
+#[test]
+fn test_end2end_regenerate_build_variants_in_place() {
+    let tmp_dir = TempDir::new("generated_resmoke_config").unwrap();
+    let common_args = [
+        "--expansion-file",
+        "tests/data/sample_expansions.yml",
+        "--evg-project-file",
+        "tests/data/evergreen.yml",
+        "--evg-auth-file",
+        "tests/data/sample_evergreen_auth.yml",
+        "--resmoke-command",
+        "python3 tests/mocks/resmoke.py",
+        "--use-task-split-fallback",
+        "--generate-sub-tasks-config",
+        "tests/data/sample_generate_subtasks_config.yml",
+        "--target-directory",
+        tmp_dir.path().to_str().unwrap(),
+    ];
+
+    let file_names = |directory: &Path| -> BTreeSet<OsString> {
+        std::fs::read_dir(directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect()
+    };
+
+    Command::cargo_bin("mongo-task-generator")
+        .unwrap()
+        .args(&common_args)
+        .assert()
+        .success();
+    let generated_files = file_names(tmp_dir.path());
+    Command::cargo_bin("mongo-task-generator")
+        .unwrap()
+        .args(&common_args)
+        .args(&[
+            "--clean-target",
+            "--regenerate-build-variants-from",
+            tmp_dir.path().to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    // Regenerating into the directory it reads from should keep everything it generated.
+    assert_eq!(file_names(tmp_dir.path()), generated_files);
+}
+
+#[test]
+fn test_end2end_execution_with_missing_config_file() {
+    let mut cmd = Command::cargo_bin("mongo-task-generator").unwrap();
+    let tmp_dir = TempDir::new("generated_resmoke_config").unwrap();
+
+    cmd.args(&[
+        "--config",
+        "tests/data/missing_cli_config.yml",
+        "--target-directory",
+        tmp_dir.path().to_str().unwrap(),
+    ])
+    .assert()
+    .failure()
+    .code(1);
+
+    let error_report = std::fs::read_to_string(tmp_dir.path().join("errors.json")).unwrap();
+    assert!(error_report.contains("\"category\": \"config\""));
+    assert!(error_report.contains("missing_cli_config.yml"));
+}
+
+#[test]
+fn test_end2end_execution_after_failed_run() {
+    let tmp_dir = TempDir::new("generated_resmoke_config").unwrap();
+
+    Command::cargo_bin("mongo-task-generator")
+        .unwrap()
+        .args(&[
+            "--config",
+            "tests/data/missing_cli_config.yml",
+            "--target-directory",
+            tmp_dir.path().to_str().unwrap(),
+        ])
+        .assert()
+        .failure();
+    Command::cargo_bin("mongo-task-generator")
+        .unwrap()
+        .args(&[
+            "--config",
+            "tests/data/sample_cli_config.yml",
+            "--target-directory",
+            tmp_dir.path().to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    assert!(!tmp_dir.path().join("errors.json").exists());
 }
 
 #[test]
@@ -64,9 +232,42 @@ fn test_end2end_burn_in_execution() {
     assert!(tmp_dir_path.exists());
 
     let files = std::fs::read_dir(tmp_dir_path).unwrap();
-    // Only one file `evergreen_config.json` should be generated.
-    // That means non-burn-in tasks are NOT generated.
-    assert_eq!(1, files.into_iter().collect::<Vec<_>>().len());
+    // Only `evergreen_config.json`, `burn_in_discovery.json`, `generation_metadata.json`,
+    // `generated_tasks.json`, `machine_time_report.json`, `generation_summary.md`,
+    // `generated_files.sha256` and the multiversion exclude tags files should be generated. That means non-burn-in tasks are NOT generated.
+    assert_eq!(9, files.into_iter().collect::<Vec<_>>().len());
+}
+
+#[test]
+fn test_end2end_burn_in_tags_pass_dependency_check() {
+    let mut cmd = Command::cargo_bin("mongo-task-generator").unwrap();
+    let tmp_dir = TempDir::new("generated_resmoke_config").unwrap();
+
+    // With `--strict`, generation fails if any generated dependency is invalid, so a successful
+    // run means the dependencies of the burn_in_tags build variants passed the check.
+    cmd.args(&[
+        "--target-directory",
+        tmp_dir.path().to_str().unwrap(),
+        "--expansion-file",
+        "tests/data/sample_expansions.yml",
+        "--evg-project-file",
+        "tests/data/burn_in/evergreen_with_burn_in_tags_dependencies.yml",
+        "--evg-auth-file",
+        "tests/data/sample_evergreen_auth.yml",
+        "--resmoke-command",
+        "python3 tests/mocks/resmoke.py",
+        "--use-task-split-fallback",
+        "--strict",
+        "--burn-in",
+        "--burn-in-tests-command",
+        "python3 tests/mocks/burn_in_tests.py run",
+    ])
+    .assert()
+    .success();
+
+    let config = std::fs::read_to_string(tmp_dir.path().join("evergreen_config.json")).unwrap();
+    assert!(config.contains("\"enterprise-rhel-80-64-bit-inmem-generated-by-burn-in-tags\""));
+    assert!(config.contains("\"archive_dist_test\""));
 }
 
 #[rstest]
@@ -110,11 +311,11 @@ fn test_end2end_burn_in_with_no_distro(#[case] config_location: String) {
 #[case("tests/data/burn_in/evergreen_burn_in_tasks_with_no_tasks.yml", 4)]
 #[case(
     "tests/data/burn_in/evergreen_burn_in_tasks_with_large_distro_task.yml",
-    315
+    385
 )]
 #[case(
     "tests/data/burn_in/evergreen_burn_in_tasks_with_non_large_distro_task.yml",
-    275
+    345
 )]
 fn test_end2end_burn_in_tasks(#[case] config_location: String, #[case] expected_num_lines: usize) {
     let mut cmd = Command::cargo_bin("mongo-task-generator").unwrap();
@@ -52,6 +52,13 @@ tests:
     """)
 
 
+def generate_multiversion_exclude_tags():
+    file_arg = [arg for arg in sys.argv if arg.startswith("--excludeTagsFilePath=")][0]
+    file_name = file_arg.split("=", 1)[1]
+    with open(file_name, "w") as file:
+        file.write("selector: {}\n")
+
+
 def main():
     subcommand = sys.argv[1]
     if subcommand == "multiversion-config":
@@ -60,6 +67,8 @@ def main():
         suiteconfig()
     elif subcommand == "test-discovery":
         test_discovery()
+    elif subcommand == "generate-multiversion-exclude-tags":
+        generate_multiversion_exclude_tags()
     else:
         raise ValueError(f"Unknown subcommand: {subcommand}")
 